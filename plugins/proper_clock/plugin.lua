@@ -0,0 +1,31 @@
+local clocks = {}
+
+plugin.command("clock", "spawns a clock in front of you", function(args)
+    local user = world.frame(world.user())
+    local distance = tonumber(args[1]) or 5
+    local id = world.spawn_prefab("pocket_clock", { position = { user.x, user.y, user.z - distance } })
+    if id then
+        table.insert(clocks, id)
+        gui.toast("§7Spawned clock §f" .. #clocks)
+    end
+end)
+
+plugin.command("clocks", "forgets every clock", function()
+    clocks = {}
+end)
+
+plugin.panel("Proper time", function()
+    local user = world.frame(world.user())
+    if user == nil then
+        return nil
+    end
+
+    local text = string.format("§7you §f%.2fs §8| §7coordinate §f%.2fs", user.proper_time, world.time())
+    for index, id in ipairs(clocks) do
+        local clock = world.frame(id)
+        if clock then
+            text = text .. string.format("\n§7clock %d §f%.2fs", index, clock.proper_time)
+        end
+    end
+    return text
+end)
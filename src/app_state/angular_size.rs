@@ -0,0 +1,98 @@
+use crate::special::{
+    inertial_frame::InertialFrame,
+    transform::{lorentz_boost, lorentz_factor},
+};
+use cgmath::{vec3, InnerSpace, Vector3};
+
+/// How many times [`simultaneous_frame()`] refines its guess.
+pub const SIMULTANEITY_ITERATIONS: usize = 16;
+
+/// How big an entity's bounding sphere looks, against how big it actually is right now. Both are angular
+/// diameters, in radians.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AngularSize {
+    /// From the light reaching the user now, which left the entity at the retarded event, and gets aberrated by how
+    /// it's moving relative to them.
+    pub apparent: f64,
+    /// Where the entity is in the user's simultaneity slice, with no light delay or aberration.
+    pub instantaneous: f64,
+}
+
+impl AngularSize {
+    /// How much the headlight effect and aberration magnify it. Less than 1 means it looks smaller than it is.
+    pub fn magnification(&self) -> f64 {
+        self.apparent / self.instantaneous
+    }
+}
+
+/// The angular diameter of a sphere `radius` across at `distance` away, all of the sky if it's around the viewer.
+pub fn sphere_angular_size(radius: f64, distance: f64) -> f64 {
+    2.0 * (radius / distance).min(1.0).asin()
+}
+
+/// The angular diameter of a sphere `radius` across (in its own rest frame), seen from `seen_offset` away (where its
+/// center was when the light left it, relative to the viewer in their rest frame), while it moves at `velocity`
+/// relative to the viewer. Velocities and offsets have to be in units where light goes 1 per unit of time.
+///
+/// A moving sphere still looks like a circle, so this works out how big it looks in the sphere's rest frame, then
+/// aberrates the two sides of it that are in line with the velocity back into the viewer's.
+pub fn apparent_angular_size(
+    radius: f64,
+    seen_offset: Vector3<f64>,
+    velocity: Vector3<f64>,
+) -> f64 {
+    let distance = seen_offset.magnitude();
+    if distance <= 0.0 {
+        return std::f64::consts::PI;
+    }
+
+    // from where the light left to where it got to, which takes as long as it's far in every frame
+    let light_path = (-seen_offset).extend(distance);
+    let rest_path = lorentz_boost(velocity) * light_path;
+    let rest_distance = rest_path.w;
+    let rest_direction = -rest_path.truncate() / rest_distance;
+    let half_size = (radius / rest_distance).min(1.0).asin();
+
+    // the side direction, in the plane of the velocity and the line of sight
+    let side = match velocity - rest_direction * velocity.dot(rest_direction) {
+        side if side.magnitude2() > 0.0 => side.normalize(),
+        _ => rest_direction
+            .cross(any_perpendicular(rest_direction))
+            .normalize(),
+    };
+    let to_viewer = lorentz_boost(-velocity);
+    let [near_edge, far_edge] = [half_size, -half_size].map(|angle| {
+        let look = rest_direction * angle.cos() + side * angle.sin();
+        let photon = to_viewer * (-look).extend(1.0);
+        -photon.truncate() / photon.w
+    });
+    near_edge.angle(far_edge).0
+}
+
+fn any_perpendicular(direction: Vector3<f64>) -> Vector3<f64> {
+    if direction.x.abs() < 0.9 {
+        vec3(1.0, 0.0, 0.0)
+    } else {
+        vec3(0.0, 1.0, 0.0)
+    }
+}
+
+/// The frame something following `frame_at` (an inertial frame for each coordinate time) has at the same time as
+/// `user_frame`, in the user's rest frame.
+pub fn simultaneous_frame(
+    user_frame: InertialFrame,
+    frame_at: impl Fn(f64) -> InertialFrame,
+) -> InertialFrame {
+    let gamma = lorentz_factor(user_frame.velocity);
+    let mut frame = frame_at(user_frame.position.w);
+    for _ in 0..SIMULTANEITY_ITERATIONS {
+        // the user's time for it, which goes up with its coordinate time at this rate
+        let offset = frame.relative_to(user_frame).position.w;
+        let rate = gamma * (1.0 - user_frame.velocity.dot(frame.velocity));
+        if offset.abs() < 1e-9 || rate <= 0.0 {
+            break;
+        }
+        frame = frame_at(frame.position.w - offset / rate);
+    }
+    frame
+}
@@ -0,0 +1,113 @@
+use crate::{
+    graphics::{camera::Camera, graphics_controller::AuxiliarySurface},
+    shared::localization::tr,
+};
+use cgmath::{vec3, Deg, InnerSpace, Quaternion, Rotation3, Vector3};
+
+/// Where the overview camera starts out, in light-seconds above the user.
+pub const DEFAULT_OVERVIEW_HEIGHT: f32 = 60.0;
+pub const MIN_OVERVIEW_HEIGHT: f32 = 5.0;
+pub const MAX_OVERVIEW_HEIGHT: f32 = 5000.0;
+/// How much one notch of the scroll wheel moves the overview camera, as a factor.
+pub const OVERVIEW_ZOOM_PER_NOTCH: f32 = 1.15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AuxiliaryViewMode {
+    /// Looking the other way from the main view.
+    #[default]
+    RearView,
+    /// Looking straight down at the user from above.
+    Overview,
+    /// Zoomed in on the selected entity.
+    Tracking,
+}
+
+impl AuxiliaryViewMode {
+    pub fn next(self) -> Self {
+        match self {
+            Self::RearView => Self::Overview,
+            Self::Overview => Self::Tracking,
+            Self::Tracking => Self::RearView,
+        }
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            Self::RearView => tr!("auxiliary.rear_view"),
+            Self::Overview => tr!("auxiliary.overview"),
+            Self::Tracking => tr!("auxiliary.tracking"),
+        }
+    }
+}
+
+/// A second window showing the same universe from another camera. Everything is seen from the user's frame, the
+/// same as in the main window, so it's only the camera that's different.
+#[derive(Debug)]
+pub struct AuxiliaryView {
+    pub surface: AuxiliarySurface,
+    pub mode: AuxiliaryViewMode,
+    pub overview_height: f32,
+}
+
+impl AuxiliaryView {
+    pub fn new(surface: AuxiliarySurface) -> Self {
+        let view = Self {
+            surface,
+            mode: AuxiliaryViewMode::default(),
+            overview_height: DEFAULT_OVERVIEW_HEIGHT,
+        };
+        view.update_title();
+        view
+    }
+
+    pub fn cycle_mode(&mut self) {
+        self.mode = self.mode.next();
+        self.update_title();
+    }
+
+    fn update_title(&self) {
+        self.surface
+            .window
+            .set_title(&tr!("auxiliary.title", mode = self.mode.name()));
+    }
+
+    /// Scrolling zooms the overview in and out.
+    pub fn scroll(&mut self, notches: f32) {
+        self.overview_height = (self.overview_height / OVERVIEW_ZOOM_PER_NOTCH.powf(notches))
+            .clamp(MIN_OVERVIEW_HEIGHT, MAX_OVERVIEW_HEIGHT);
+    }
+
+    pub fn aspect_ratio(&self) -> f32 {
+        let size = self.surface.size();
+        size.width as f32 / size.height as f32
+    }
+
+    /// `tracked` is where the selected entity looks like it is, if there is one.
+    pub fn camera(&self, main_camera: &Camera, tracked: Option<Vector3<f32>>) -> Camera {
+        match self.mode {
+            AuxiliaryViewMode::RearView => Camera {
+                rotation: main_camera.rotation * Quaternion::from_angle_y(Deg(180.0)),
+                ..*main_camera
+            },
+            AuxiliaryViewMode::Overview => Camera {
+                position: vec3(0.0, self.overview_height, 0.0),
+                rotation: Quaternion::from_angle_x(Deg(-90.0)),
+                vertical_fov: Deg(60.0),
+                ..*main_camera
+            },
+            AuxiliaryViewMode::Tracking => {
+                let rotation = match tracked {
+                    Some(position) if position.magnitude2() > 0.0 => {
+                        Quaternion::from_arc(vec3(0.0, 0.0, -1.0), position.normalize(), None)
+                    }
+                    _ => main_camera.rotation,
+                };
+                Camera {
+                    rotation,
+                    vertical_fov: Deg(15.0),
+                    ..*main_camera
+                }
+            }
+        }
+    }
+}
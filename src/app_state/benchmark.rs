@@ -0,0 +1,224 @@
+use crate::shared::{
+    performance_counter::{PerformanceCounter, RenderStats},
+    version::APP_VERSION,
+};
+use anyhow::Result;
+use cgmath::{Deg, Quaternion, Rotation3};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// The stress scene `--benchmark` runs, unless it's given some other scenario.
+pub const BENCHMARK_SCENARIO: &str = include_str!("../scenarios/benchmark.ron");
+/// How long `--benchmark` runs for, not counting loading.
+pub const BENCHMARK_DURATION: Duration = Duration::from_secs(30);
+/// How many full turns the camera makes over the course of the benchmark.
+pub const CAMERA_TURNS: f64 = 2.0;
+
+/// A single frame's timings, for the CSV.
+#[derive(Debug, Clone)]
+struct FrameSample {
+    elapsed: Duration,
+    frame_time: Duration,
+    sections: Vec<(&'static str, Duration)>,
+    render_stats: RenderStats,
+}
+
+/// The summary that gets written out as JSON. All times are in milliseconds.
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchmarkReport {
+    pub app_version: String,
+    pub adapter: String,
+    pub backend: String,
+    pub scenario: String,
+    pub entity_count: usize,
+    pub depth_prepass: bool,
+    pub frame_count: usize,
+    pub duration: f64,
+    pub mean_fps: f64,
+    pub mean: f64,
+    pub fastest: f64,
+    pub slowest: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+    /// Mean time per frame spent in each section.
+    pub sections: BTreeMap<String, f64>,
+    /// Added up over every frame. Divide by `frame_count` for how much goes into a typical one.
+    pub render_stats: RenderStats,
+}
+
+/// Details about the run that go into the report, besides the timings.
+#[derive(Debug, Clone, Default)]
+pub struct BenchmarkInfo {
+    pub adapter: String,
+    pub backend: String,
+    pub scenario: String,
+    pub entity_count: usize,
+    pub depth_prepass: bool,
+}
+
+fn ms(time: Duration) -> f64 {
+    time.as_secs_f64() * 1000.0
+}
+
+/// Times every frame of a `--benchmark` run, flies the camera along a fixed path, and writes out a report at the
+/// end so runs on different commits can be compared.
+#[derive(Debug)]
+pub struct Benchmark {
+    pub output_dir: PathBuf,
+    start: Option<Instant>,
+    counter: PerformanceCounter,
+    samples: Vec<FrameSample>,
+}
+
+impl Benchmark {
+    pub fn new(output_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            output_dir: output_dir.into(),
+            start: None,
+            counter: PerformanceCounter::new(),
+            samples: Vec::new(),
+        }
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.start.map(|start| start.elapsed()).unwrap_or_default()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed() >= BENCHMARK_DURATION
+    }
+
+    /// Copies the frame that `frame_counter` just ticked, along with what it drew. The clock starts on the first frame
+    /// after loading.
+    pub fn record_frame(&mut self, frame_counter: &PerformanceCounter, render_stats: RenderStats) {
+        let Some(start) = self.start else {
+            // the first frame's time includes loading
+            self.start = Some(Instant::now());
+            return;
+        };
+
+        let (frame_time, sections) = frame_counter.last_frame();
+        self.counter.push_frame(frame_time, sections);
+        self.samples.push(FrameSample {
+            elapsed: start.elapsed(),
+            frame_time,
+            sections: sections.to_vec(),
+            render_stats,
+        });
+    }
+
+    /// Where the camera should be looking: a slow turn around the vertical axis, nodding up and down as it goes.
+    pub fn camera_rotation(&self) -> Quaternion<f64> {
+        let progress = self.elapsed().as_secs_f64() / BENCHMARK_DURATION.as_secs_f64();
+        let yaw = Deg(360.0 * CAMERA_TURNS * progress);
+        let pitch = Deg(30.0 * (progress * std::f64::consts::TAU * 3.0).sin());
+        Quaternion::from_angle_y(yaw) * Quaternion::from_angle_x(pitch)
+    }
+
+    pub fn report(&self, info: BenchmarkInfo) -> Option<BenchmarkReport> {
+        let report = self.counter.report()?;
+        let duration = self.elapsed().as_secs_f64();
+
+        Some(BenchmarkReport {
+            app_version: APP_VERSION.to_owned(),
+            adapter: info.adapter,
+            backend: info.backend,
+            scenario: info.scenario,
+            entity_count: info.entity_count,
+            depth_prepass: info.depth_prepass,
+            frame_count: self.samples.len(),
+            duration,
+            mean_fps: self.samples.len() as f64 / duration.max(f64::EPSILON),
+            mean: ms(report.mean),
+            fastest: ms(report.fastest),
+            slowest: ms(report.slowest),
+            p50: ms(report.p50),
+            p95: ms(report.p95),
+            p99: ms(report.p99),
+            sections: report
+                .sections
+                .iter()
+                .map(|&(name, time)| (name.to_owned(), ms(time)))
+                .collect(),
+            render_stats: self
+                .samples
+                .iter()
+                .fold(RenderStats::default(), |mut total, sample| {
+                    total += sample.render_stats;
+                    total
+                }),
+        })
+    }
+
+    /// One row per frame, with a column for every section that showed up.
+    fn frames_csv(&self) -> String {
+        let mut section_names: Vec<&'static str> = Vec::new();
+        for sample in self.samples.iter() {
+            for &(name, _) in sample.sections.iter() {
+                if !section_names.contains(&name) {
+                    section_names.push(name);
+                }
+            }
+        }
+
+        let mut csv = String::from(
+            "frame,elapsed_ms,frame_time_ms,draw_calls,instances,vertices,buffer_bytes_written,render_targets_recreated",
+        );
+        for name in section_names.iter() {
+            let _ = write!(csv, ",{name}_ms");
+        }
+        csv.push('\n');
+
+        for (index, sample) in self.samples.iter().enumerate() {
+            let stats = sample.render_stats;
+            let _ = write!(
+                csv,
+                "{},{:.3},{:.3},{},{},{},{},{}",
+                index,
+                ms(sample.elapsed),
+                ms(sample.frame_time),
+                stats.draw_calls,
+                stats.instances,
+                stats.vertices,
+                stats.buffer_bytes_written,
+                stats.render_targets_recreated
+            );
+            for name in section_names.iter() {
+                let time = sample
+                    .sections
+                    .iter()
+                    .find(|(section_name, _)| section_name == name)
+                    .map(|&(_, time)| ms(time))
+                    .unwrap_or_default();
+                let _ = write!(csv, ",{time:.3}");
+            }
+            csv.push('\n');
+        }
+
+        csv
+    }
+
+    /// Writes `report.json` and `frames.csv` to [`Benchmark::output_dir`], returning the report.
+    pub fn save(&self, info: BenchmarkInfo) -> Result<BenchmarkReport> {
+        let Some(report) = self.report(info) else {
+            anyhow::bail!("no frames were recorded");
+        };
+
+        let output_dir: &Path = &self.output_dir;
+        fs::create_dir_all(output_dir)?;
+        fs::write(
+            output_dir.join("report.json"),
+            serde_json::to_string_pretty(&report)?,
+        )?;
+        fs::write(output_dir.join("frames.csv"), self.frames_csv())?;
+
+        Ok(report)
+    }
+}
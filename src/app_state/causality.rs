@@ -0,0 +1,134 @@
+use super::event_ordering::LIGHTLIKE_TOLERANCE;
+use crate::special::{metric::MetricTensor, universe::EntityId};
+use cgmath::{InnerSpace, Matrix4, Vector4};
+
+/// Any more and the graph gets too cramped to read.
+pub const MAX_CAUSAL_EVENTS: usize = 8;
+
+/// An event added to the causal graph, somewhere along an entity's worldline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CausalEvent {
+    /// What it's called in the panel and in commands. Never reused until the graph's cleared, so an event keeps its
+    /// label when others are removed.
+    pub label: usize,
+    pub entity_id: EntityId,
+    /// In the universe's rest frame.
+    pub position: Vector4<f64>,
+}
+
+/// A handful of events, and which of them can affect which, for checking that a scripted sequence could actually
+/// happen in that order.
+#[derive(Debug, Clone, Default)]
+pub struct CausalGraph {
+    /// In the order they were added.
+    pub events: Vec<CausalEvent>,
+    next_label: usize,
+}
+
+impl CausalGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+        self.next_label = 0;
+    }
+
+    /// Adds an event, returning its label, unless there's already [`MAX_CAUSAL_EVENTS`].
+    pub fn add(&mut self, entity_id: EntityId, position: Vector4<f64>) -> Option<usize> {
+        if self.events.len() >= MAX_CAUSAL_EVENTS {
+            return None;
+        }
+        self.next_label += 1;
+        self.events.push(CausalEvent {
+            label: self.next_label,
+            entity_id,
+            position,
+        });
+        Some(self.next_label)
+    }
+
+    /// Returns whether there was an event with that label.
+    pub fn remove(&mut self, label: usize) -> bool {
+        let count = self.events.len();
+        self.events.retain(|event| event.label != label);
+        self.events.len() < count
+    }
+
+    /// Moves the events along with the universe when it's rebased by `offset`.
+    pub fn rebase(&mut self, offset: Vector4<f64>) {
+        for event in self.events.iter_mut() {
+            event.position -= offset;
+        }
+    }
+
+    /// Whether the event at index `cause` is in the past light cone of the one at index `effect`, so it could've
+    /// affected it. Lightlike counts, since a signal at the speed of light can just make it.
+    pub fn can_affect(&self, cause: usize, effect: usize) -> bool {
+        let separation = self.events[effect].position - self.events[cause].position;
+        if separation.w <= 0.0 {
+            return false;
+        }
+        let interval2 = Matrix4::<f64>::minkowski().length2(separation);
+        // relative, the same way as for event ordering
+        let scale = separation.w * separation.w + separation.truncate().magnitude2();
+        interval2 >= -LIGHTLIKE_TOLERANCE * scale.max(1.0)
+    }
+
+    /// Every pair of indices where the first can affect the second, leaving out the ones that only follow from
+    /// others (if 1 can affect 2 and 2 can affect 3, then 1 can affect 3 as well). These are the graph's edges.
+    pub fn edges(&self) -> Vec<(usize, usize)> {
+        let count = self.events.len();
+        let mut edges = Vec::new();
+        for cause in 0..count {
+            for effect in 0..count {
+                if !self.can_affect(cause, effect) {
+                    continue;
+                }
+                let implied = (0..count).any(|between| {
+                    self.can_affect(cause, between) && self.can_affect(between, effect)
+                });
+                if !implied {
+                    edges.push((cause, effect));
+                }
+            }
+        }
+        edges
+    }
+
+    /// Every pair of indices where neither can affect the other, so different observers can disagree on which
+    /// happens first. A script that needs one to lead to the other can't work.
+    pub fn unrelated_pairs(&self) -> Vec<(usize, usize)> {
+        let count = self.events.len();
+        (0..count)
+            .flat_map(|a| (a + 1..count).map(move |b| (a, b)))
+            .filter(|&(a, b)| !self.can_affect(a, b) && !self.can_affect(b, a))
+            .collect()
+    }
+
+    /// How many steps each event is from the start of the longest chain of causes leading up to it, for laying the
+    /// graph out in rows.
+    pub fn depths(&self) -> Vec<usize> {
+        let count = self.events.len();
+        // causes always come first in coordinate time, so going in that order handles them before their effects
+        let mut order = (0..count).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| {
+            self.events[a]
+                .position
+                .w
+                .total_cmp(&self.events[b].position.w)
+        });
+
+        let mut depths = vec![0; count];
+        for (position, &effect) in order.iter().enumerate() {
+            depths[effect] = order[..position]
+                .iter()
+                .filter(|&&cause| self.can_affect(cause, effect))
+                .map(|&cause| depths[cause] + 1)
+                .max()
+                .unwrap_or(0);
+        }
+        depths
+    }
+}
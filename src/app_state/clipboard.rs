@@ -0,0 +1,194 @@
+use super::selection::{is_part, part_roots};
+use crate::special::{
+    inertial_frame::InertialFrame,
+    prefab::PrefabRegistry,
+    transform::{lorentz_boost, lorentz_factor, transform_3_velocity},
+    universe::{Entity, EntityId, Universe},
+    worldline::{Worldline, WorldlineEvent},
+};
+use anyhow::{bail, Result};
+use cgmath::{InnerSpace, Vector3, Zero};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Copied entities, ready to be pasted any number of times. Goes through the OS clipboard as JSON too, so it can be
+/// pasted into another session.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EntityClipboard {
+    /// By the IDs they had when they were copied, so attachments between them still line up. The ones that aren't
+    /// attached to another copy get a worldline that's just their frame when they were copied: relative to the user's
+    /// rest frame, and centered on the middle of the copies.
+    pub entities: BTreeMap<EntityId, Entity>,
+}
+
+impl EntityClipboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copies `entity_ids` as they are at the universe's current time. Parts are copied along with whatever they're
+    /// part of (all of it, even if only the part was selected), and the user never is. Anything attached to something
+    /// that isn't copied comes along as an ordinary inertial entity.
+    pub fn copy(universe: &Universe, prefabs: &PrefabRegistry, entity_ids: &[EntityId]) -> Self {
+        let mut pending: Vec<EntityId> = part_roots(universe, prefabs, entity_ids.iter().copied())
+            .into_iter()
+            .filter(|&entity_id| entity_id != universe.user_entity_id)
+            .collect();
+        let mut entities = BTreeMap::new();
+        while let Some(entity_id) = pending.pop() {
+            let Some(entity) = universe.entities.get(&entity_id) else {
+                continue;
+            };
+            if entities.insert(entity_id, entity.clone()).is_some() {
+                continue;
+            }
+            pending.extend(
+                universe
+                    .children(entity_id)
+                    .filter(|child_id| is_part(prefabs, &universe.entities[child_id])),
+            );
+        }
+
+        let user_frame = universe.user_event_now().frame;
+        let mut frames = BTreeMap::new();
+        for (&entity_id, entity) in entities.iter() {
+            if entity
+                .attachment
+                .is_some_and(|attachment| entities.contains_key(&attachment.parent))
+            {
+                continue;
+            }
+            let frame = universe.entity_event_at_time(entity, universe.time).frame;
+            frames.insert(
+                entity_id,
+                InertialFrame {
+                    position: rest_offset(
+                        user_frame.velocity,
+                        frame.position.truncate() - user_frame.position.truncate(),
+                    )
+                    .extend(0.0),
+                    velocity: transform_3_velocity(
+                        lorentz_boost(user_frame.velocity),
+                        frame.velocity,
+                    ),
+                },
+            );
+        }
+
+        let center = frames.values().fold(Vector3::zero(), |sum, frame| {
+            sum + frame.position.truncate()
+        }) / frames.len().max(1) as f64;
+        for (entity_id, entity) in entities.iter_mut() {
+            entity.worldline = match frames.get(entity_id) {
+                Some(frame) => {
+                    entity.attachment = None;
+                    Worldline::new(InertialFrame {
+                        position: frame.position - center.extend(0.0),
+                        ..*frame
+                    })
+                }
+                // it goes wherever its parent does
+                None => Worldline::default(),
+            };
+        }
+
+        Self { entities }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Fails if it isn't JSON for a clipboard, or if it is but something in it is impossible (see
+    /// [`Self::validate()`]).
+    pub fn from_json(json: &str) -> Result<Self> {
+        let clipboard: Self = serde_json::from_str(json)?;
+        clipboard.validate()?;
+        Ok(clipboard)
+    }
+
+    /// Makes sure everything's somewhere real and slower than light, since a clipboard from outside could have
+    /// anything in it.
+    pub fn validate(&self) -> Result<()> {
+        for entity in self.entities.values() {
+            if !entity
+                .worldline
+                .events()
+                .iter()
+                .all(WorldlineEvent::is_valid)
+            {
+                bail!("a copied worldline is faster than light or isn't finite");
+            }
+            if let Some(attachment) = entity.attachment {
+                let offsets = [
+                    attachment.offset,
+                    attachment.bounce.unwrap_or_else(Vector3::zero),
+                ];
+                if !offsets.iter().all(|offset| {
+                    offset.x.is_finite() && offset.y.is_finite() && offset.z.is_finite()
+                }) {
+                    bail!("a copied attachment isn't finite");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Fresh copies of everything, with new IDs, centered on `anchor` and moving relative to it the same way they
+    /// were moving relative to the user when they were copied. Anything attached comes after what it's attached to.
+    /// Fails without pasting anything if [`Self::validate()`] does.
+    pub fn paste(&self, anchor: WorldlineEvent) -> Result<Vec<(EntityId, Entity)>> {
+        self.validate()?;
+        let new_ids: BTreeMap<EntityId, EntityId> = self
+            .entities
+            .keys()
+            .map(|&entity_id| (entity_id, EntityId::generate()))
+            .collect();
+        let from_rest_frame = lorentz_boost(-anchor.frame.velocity);
+
+        let mut pasted: Vec<(EntityId, Entity)> = self
+            .entities
+            .iter()
+            .map(|(entity_id, entity)| {
+                let mut entity = entity.clone();
+                let new_parent = entity
+                    .attachment
+                    .and_then(|attachment| new_ids.get(&attachment.parent));
+                match (entity.attachment.as_mut(), new_parent) {
+                    (Some(attachment), Some(&parent)) => attachment.parent = parent,
+                    // only a hand-edited clipboard would have it attached to something that isn't there
+                    _ => {
+                        entity.attachment = None;
+                        let relative = entity.worldline.get_event_at_time(0.0).frame;
+                        entity.worldline = Worldline::new(InertialFrame {
+                            position: anchor.comoving(relative.position.truncate()).frame.position,
+                            velocity: transform_3_velocity(from_rest_frame, relative.velocity),
+                        });
+                    }
+                }
+                (new_ids[entity_id], entity)
+            })
+            .collect();
+        pasted.sort_by_key(|(_, entity)| entity.attachment.is_some());
+        Ok(pasted)
+    }
+}
+
+/// How far `offset` (at one coordinate time) is in the rest frame of something moving at `velocity`, undoing the
+/// length contraction [`WorldlineEvent::comoving()`] does.
+fn rest_offset(velocity: Vector3<f64>, offset: Vector3<f64>) -> Vector3<f64> {
+    let speed2 = velocity.magnitude2();
+    if speed2 <= 0.0 {
+        return offset;
+    }
+    let parallel = velocity * (offset.dot(velocity) / speed2);
+    offset - parallel + parallel * lorentz_factor(velocity)
+}
@@ -0,0 +1,59 @@
+use crate::shared::input::Input;
+
+/// Which page of the debug overlay is up. F3 goes through them in order, then hides the overlay, then starts over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DebugPage {
+    /// Where the user is and how fast they're going.
+    #[default]
+    Kinematics,
+    /// Draw calls, instances, and buffer sizes.
+    Renderer,
+    /// How many worldline events there are, and what they take up.
+    Memory,
+    /// What's held, where the cursor is, and which actions that adds up to.
+    Input,
+    Hidden,
+}
+
+impl DebugPage {
+    /// The pages that actually show something, in order.
+    pub const PAGES: [Self; 4] = [Self::Kinematics, Self::Renderer, Self::Memory, Self::Input];
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Kinematics => Self::Renderer,
+            Self::Renderer => Self::Memory,
+            Self::Memory => Self::Input,
+            Self::Input => Self::Hidden,
+            Self::Hidden => Self::Kinematics,
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Kinematics => "Kinematics",
+            Self::Renderer => "Renderer",
+            Self::Memory => "Worldlines & memory",
+            Self::Input => "Input",
+            Self::Hidden => "Hidden",
+        }
+    }
+
+    /// From 1, or `None` if it's [`DebugPage::Hidden`].
+    pub fn number(self) -> Option<usize> {
+        Self::PAGES
+            .iter()
+            .position(|&page| page == self)
+            .map(|index| index + 1)
+    }
+}
+
+/// How an input shows up on the input page.
+pub fn input_name(input: &Input) -> String {
+    match input {
+        Input::CharacterKey(key) => key.to_uppercase(),
+        Input::NamedKey(key) => format!("{key:?}"),
+        Input::MouseButton(button) => format!("Mouse {button:?}"),
+        Input::GamepadButton(button) => format!("Gamepad {button:?}"),
+    }
+}
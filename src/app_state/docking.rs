@@ -0,0 +1,92 @@
+use super::scenario::DockingTarget;
+use crate::special::universe::Universe;
+use cgmath::{InnerSpace, Vector3};
+
+/// Where the docking target is relative to the user, in the user's rest frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DockingStatus {
+    /// From the user to the target, in light-seconds.
+    pub offset: Vector3<f64>,
+    /// The user's velocity relative to the target, as a portion of c.
+    pub relative_velocity: Vector3<f64>,
+    /// How fast the gap is shrinking, or negative if it's growing.
+    pub closing_speed: f64,
+}
+
+impl DockingStatus {
+    pub fn distance(&self) -> f64 {
+        self.offset.magnitude()
+    }
+}
+
+/// A scenario's docking challenge, if it has one: get close enough to the target, going slowly enough relative to it.
+#[derive(Debug, Clone, Default)]
+pub struct DockingChallenge {
+    pub target: Option<DockingTarget>,
+    /// The user's proper time when the challenge started.
+    started_at: Option<f64>,
+    /// How much of the user's proper time docking took, once it's done.
+    pub docked_after: Option<f64>,
+}
+
+impl DockingChallenge {
+    pub fn new(target: Option<DockingTarget>) -> Self {
+        Self {
+            target,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_docked(&self) -> bool {
+        self.docked_after.is_some()
+    }
+
+    /// Where the target is right now, or `None` if there's no challenge (or the target's gone).
+    pub fn status(&self, universe: &Universe) -> Option<DockingStatus> {
+        let target = self.target?;
+        let user_frame = universe.user_event_now().frame;
+        let target_frame = universe
+            .get_event_at_time(target.entity_id, universe.time)?
+            .frame;
+
+        // the target's event now in the universe's frame isn't now in the user's, so move it along to when it is
+        let relative_frame = target_frame.relative_to(user_frame);
+        let relative_frame = relative_frame.predict(-relative_frame.position.w);
+        let offset = relative_frame.position.truncate();
+        let relative_velocity = -relative_frame.velocity;
+        let closing_speed = if offset.magnitude() > 0.0 {
+            relative_velocity.dot(offset.normalize())
+        } else {
+            0.0
+        };
+        Some(DockingStatus {
+            offset,
+            relative_velocity,
+            closing_speed,
+        })
+    }
+
+    /// Checks whether the user's within tolerances. Returns `true` the moment they first are.
+    pub fn update(&mut self, universe: &Universe) -> bool {
+        let Some(target) = self.target else {
+            return false;
+        };
+        if self.is_docked() {
+            return false;
+        }
+        let proper_time = universe.user_event_now().proper_time;
+        let started_at = *self.started_at.get_or_insert(proper_time);
+        let Some(status) = self.status(universe) else {
+            return false;
+        };
+
+        if status.distance() <= target.distance
+            && status.relative_velocity.magnitude() <= target.speed
+        {
+            self.docked_after = Some(proper_time - started_at);
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use crate::special::{metric::MetricTensor, transform::lorentz_boost, universe::EntityId};
+use cgmath::{vec2, vec3, InnerSpace, Matrix4, Vector2, Vector4};
+
+/// How close to light speed the observer slider goes, either way.
+pub const MAX_OBSERVER_SPEED: f64 = 0.95;
+/// Intervals closer to zero than this count as lightlike.
+pub const LIGHTLIKE_TOLERANCE: f64 = 1e-6;
+
+/// An event marked for comparing, somewhere along an entity's worldline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarkedEvent {
+    pub entity_id: EntityId,
+    /// In the universe's rest frame.
+    pub position: Vector4<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntervalKind {
+    /// One can cause the other, so every observer agrees on which came first.
+    Timelike,
+    Lightlike,
+    /// Neither can cause the other, so which came first depends on who's asking.
+    Spacelike,
+}
+
+/// Two marked events, and an observer sweeping through velocities to see how they'd disagree on the order.
+#[derive(Debug, Clone, Default)]
+pub struct EventOrdering {
+    /// Oldest first, at most two.
+    pub events: Vec<MarkedEvent>,
+    /// Along the line between the events, as a portion of the speed of light.
+    pub observer_speed: f64,
+}
+
+impl EventOrdering {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks an event, forgetting the older one if there's already two. Returns which one it is (0 or 1).
+    pub fn mark(&mut self, event: MarkedEvent) -> usize {
+        if self.events.len() >= 2 {
+            self.events.remove(0);
+        }
+        self.events.push(event);
+        self.events.len() - 1
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+        self.observer_speed = 0.0;
+    }
+
+    /// Moves the marked events along with the universe when it's rebased by `offset`.
+    pub fn rebase(&mut self, offset: Vector4<f64>) {
+        for event in self.events.iter_mut() {
+            event.position -= offset;
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.events.len() == 2
+    }
+
+    /// From the first event to the second, in the universe's rest frame.
+    pub fn separation(&self) -> Option<Vector4<f64>> {
+        let [first, second] = self.events.as_slice() else {
+            return None;
+        };
+        Some(second.position - first.position)
+    }
+
+    /// The invariant interval squared (positive for timelike), which every observer agrees on.
+    pub fn interval2(&self) -> Option<f64> {
+        Some(Matrix4::<f64>::minkowski().length2(self.separation()?))
+    }
+
+    pub fn interval_kind(&self) -> Option<IntervalKind> {
+        let interval2 = self.interval2()?;
+        let separation = self.separation()?;
+        // relative, so far-apart events don't count as lightlike from rounding alone
+        let scale = separation.w * separation.w + separation.truncate().magnitude2();
+        Some(if interval2.abs() <= LIGHTLIKE_TOLERANCE * scale.max(1.0) {
+            IntervalKind::Lightlike
+        } else if interval2 > 0.0 {
+            IntervalKind::Timelike
+        } else {
+            IntervalKind::Spacelike
+        })
+    }
+
+    /// The separation as seen by an observer moving at `speed` along the line from the first event to the second, as
+    /// (distance along that line, time). Nothing's left over sideways, so that's all of it.
+    pub fn separation_in_frame(&self, speed: f64) -> Option<Vector2<f64>> {
+        let separation = self.separation()?;
+        let distance = separation.truncate().magnitude();
+        let direction = if distance > 0.0 {
+            separation.truncate() / distance
+        } else {
+            vec3(1.0, 0.0, 0.0)
+        };
+        let boosted = lorentz_boost(direction * speed) * separation;
+        Some(vec2(boosted.truncate().dot(direction), boosted.w))
+    }
+
+    /// How fast an observer would have to go for the events to happen at the same time, if any can.
+    pub fn simultaneity_speed(&self) -> Option<f64> {
+        if self.interval_kind()? != IntervalKind::Spacelike {
+            return None;
+        }
+        let separation = self.separation()?;
+        Some(separation.w / separation.truncate().magnitude())
+    }
+}
@@ -0,0 +1,179 @@
+use crate::special::worldline::PHYS_TIME_STEP;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Any more ticks than this in one frame and we'd rather fall behind than spiral out of control.
+pub const DEFAULT_MAX_CATCH_UP_TICKS: u32 = 20;
+/// How often falling behind gets logged, so a slow machine doesn't flood the log.
+const DROPPED_TIME_REPORT_INTERVAL: Duration = Duration::from_secs(5);
+/// How long before a frame's due [`FrameLimiter`] stops sleeping and starts spinning, since sleeps tend to overshoot by
+/// about this much.
+const SPIN_MARGIN: Duration = Duration::from_micros(1500);
+
+/// A cap on how many frames get drawn per second, on top of (or instead of) vsync.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum FrameLimit {
+    /// The monitor's refresh rate, but only when vsync isn't already holding things back.
+    #[default]
+    Auto,
+    Off,
+    Fps(f64),
+}
+
+impl FrameLimit {
+    /// How many frames per second to hold to, if any.
+    pub fn target_fps(self, refresh_rate: Option<f64>, vsync: bool) -> Option<f64> {
+        match self {
+            Self::Auto if !vsync => refresh_rate,
+            Self::Auto | Self::Off => None,
+            Self::Fps(fps) => Some(fps),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FramePacingSettings {
+    /// The most physics ticks one frame can ask for. Whatever time is owed past that gets dropped, so a slow frame
+    /// can't make the next one slower still.
+    pub max_catch_up_ticks: u32,
+    /// Makes every frame count as exactly `1 / render_cadence` seconds, however long it really took, e.g. for
+    /// recording video at a steady rate. `None` goes by real time.
+    pub render_cadence: Option<f64>,
+    pub frame_limit: FrameLimit,
+}
+
+impl Default for FramePacingSettings {
+    fn default() -> Self {
+        Self {
+            max_catch_up_ticks: DEFAULT_MAX_CATCH_UP_TICKS,
+            render_cadence: None,
+            frame_limit: FrameLimit::default(),
+        }
+    }
+}
+
+impl FramePacingSettings {
+    /// How long a frame counts as, given how long it really took.
+    pub fn frame_time(&self, real_frame_time: f64) -> f64 {
+        match self.render_cadence {
+            Some(cadence) => 1.0 / cadence,
+            None => real_frame_time,
+        }
+    }
+}
+
+/// Turns frame times into physics ticks, keeping track of partial ticks between frames and of any time that had to be
+/// dropped to keep up.
+#[derive(Debug)]
+pub struct FramePacer {
+    ticks_owed: f64,
+    /// Simulated time dropped over the whole session, in seconds.
+    dropped_time: f64,
+    /// Dropped time that hasn't been logged yet.
+    unreported_dropped_time: f64,
+    last_report: Instant,
+}
+
+impl Default for FramePacer {
+    fn default() -> Self {
+        Self {
+            ticks_owed: 0.0,
+            dropped_time: 0.0,
+            unreported_dropped_time: 0.0,
+            last_report: Instant::now(),
+        }
+    }
+}
+
+impl FramePacer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds up `frame_time`, returning how many ticks are due. Nothing's owed while paused.
+    pub fn owe_time(
+        &mut self,
+        frame_time: f64,
+        paused: bool,
+        settings: &FramePacingSettings,
+    ) -> u32 {
+        if paused {
+            self.ticks_owed = 0.0;
+            return 0;
+        }
+
+        self.ticks_owed += frame_time / PHYS_TIME_STEP;
+        let owed = self.ticks_owed as u32;
+        let ticks = owed.min(settings.max_catch_up_ticks);
+        self.ticks_owed = self.ticks_owed.rem_euclid(1.0);
+
+        if ticks < owed {
+            let dropped = (owed - ticks) as f64 * PHYS_TIME_STEP;
+            self.dropped_time += dropped;
+            self.unreported_dropped_time += dropped;
+        }
+        if self.unreported_dropped_time > 0.0
+            && self.last_report.elapsed() > DROPPED_TIME_REPORT_INTERVAL
+        {
+            warn!(
+                "Physics fell behind, dropped {:.3}s of simulated time (at most {} ticks per frame)",
+                self.unreported_dropped_time, settings.max_catch_up_ticks
+            );
+            self.unreported_dropped_time = 0.0;
+            self.last_report = Instant::now();
+        }
+
+        ticks
+    }
+
+    /// How much simulated time has been dropped so far, in seconds.
+    pub fn dropped_time(&self) -> f64 {
+        self.dropped_time
+    }
+}
+
+/// Holds rendering to a steady frame rate on the CPU side, independently of vsync. Useful on laptops, and when vsync
+/// is off.
+#[derive(Debug, Default)]
+pub struct FrameLimiter {
+    next_frame: Option<Instant>,
+}
+
+impl FrameLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Waits until it's time for the next frame at `fps`, or returns right away if there's no limit. Sleeps for most
+    /// of the wait, then spins for the last bit so the deadline isn't overshot.
+    pub fn wait(&mut self, fps: Option<f64>) {
+        let Some(fps) = fps.filter(|fps| fps.is_finite() && *fps > 0.0) else {
+            self.next_frame = None;
+            return;
+        };
+        let period = Duration::from_secs_f64(1.0 / fps);
+        let deadline = self.next_frame.unwrap_or_else(Instant::now);
+
+        if let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            if remaining > SPIN_MARGIN {
+                thread::sleep(remaining - SPIN_MARGIN);
+            }
+            while Instant::now() < deadline {
+                std::hint::spin_loop();
+            }
+        }
+
+        // a frame that ran way late starts the schedule over, instead of the next few rushing to catch up
+        let now = Instant::now();
+        self.next_frame = Some(if deadline + period > now {
+            deadline + period
+        } else {
+            now + period
+        });
+    }
+}
@@ -0,0 +1,117 @@
+use crate::special::{
+    inertial_frame::InertialFrame,
+    transform::{velocity_3_to_proper, velocity_proper_to_3},
+    universe::EntityId,
+};
+use cgmath::{InnerSpace, Vector3, Vector4, Zero};
+
+/// How long the view takes to settle into a new frame of reference, in real seconds.
+pub const FRAME_TRANSITION_DURATION: f64 = 0.5;
+/// How much the user's proper velocity can change in one frame before it counts as a jump instead of thrust, as a
+/// portion of `1 + |u|` so that thrusting at high speeds (where each frame covers more coordinate time) doesn't
+/// count.
+pub const FRAME_JUMP_THRESHOLD: f64 = 0.1;
+
+/// Eases the frame everything gets rendered from when the user's frame jumps (a hard boost, or watching from another
+/// entity), so things on screen glide over instead of teleporting. It's only for show, the physics never see it.
+#[derive(Debug, Clone)]
+pub struct FrameTransition {
+    /// The user's entity, real frame and shown frame as of the last update.
+    last: Option<(EntityId, InertialFrame, InertialFrame)>,
+    /// How far the shown frame was from the real one when the jump happened.
+    position_offset: Vector3<f64>,
+    proper_velocity_offset: Vector3<f64>,
+    /// Real time left until the shown frame catches up.
+    remaining: f64,
+}
+
+impl Default for FrameTransition {
+    fn default() -> Self {
+        Self {
+            last: None,
+            position_offset: Vector3::zero(),
+            proper_velocity_offset: Vector3::zero(),
+            remaining: 0.0,
+        }
+    }
+}
+
+impl FrameTransition {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.remaining > 0.0
+    }
+
+    /// Catches up the next `delta` seconds, and starts a new transition if the user's frame just jumped. With
+    /// `instant`, jumps happen right away like they really do.
+    pub fn update(
+        &mut self,
+        user_entity_id: EntityId,
+        user_frame: InertialFrame,
+        delta: f64,
+        instant: bool,
+    ) {
+        self.remaining = (self.remaining - delta).max(0.0);
+        if instant {
+            self.remaining = 0.0;
+        }
+
+        if let Some((last_entity_id, last_frame, last_shown_frame)) = self.last {
+            let last_proper_velocity = velocity_3_to_proper(last_frame.velocity);
+            let velocity_change =
+                (velocity_3_to_proper(user_frame.velocity) - last_proper_velocity).magnitude();
+            let jumped = last_entity_id != user_entity_id
+                || velocity_change
+                    > FRAME_JUMP_THRESHOLD * (1.0 + last_proper_velocity.magnitude());
+
+            if jumped && !instant {
+                // starting from whatever was shown last, so jumps in the middle of a transition stay smooth too
+                self.position_offset = if last_entity_id == user_entity_id {
+                    // the same worldline just bent, so only what's left of the last transition carries over
+                    (last_shown_frame.position - last_frame.position).truncate()
+                } else {
+                    (last_shown_frame.position - user_frame.position).truncate()
+                };
+                self.proper_velocity_offset = velocity_3_to_proper(last_shown_frame.velocity)
+                    - velocity_3_to_proper(user_frame.velocity);
+                self.remaining = FRAME_TRANSITION_DURATION;
+            }
+        }
+
+        self.last = Some((user_entity_id, user_frame, self.apply(user_frame)));
+    }
+
+    /// Moves the last frames seen along with the universe when it's rebased by `offset`, so it doesn't look like a
+    /// jump.
+    pub fn rebase(&mut self, offset: Vector4<f64>) {
+        if let Some((_, last_frame, last_shown_frame)) = &mut self.last {
+            last_frame.position -= offset;
+            last_shown_frame.position -= offset;
+        }
+    }
+
+    /// The frame to render from instead of the user's real one.
+    pub fn apply(&self, user_frame: InertialFrame) -> InertialFrame {
+        if !self.is_active() {
+            return user_frame;
+        }
+
+        // smoothstep, so it eases in and out
+        let progress = self.remaining / FRAME_TRANSITION_DURATION;
+        let weight = progress * progress * (3.0 - 2.0 * progress);
+
+        let proper_velocity =
+            velocity_3_to_proper(user_frame.velocity) + self.proper_velocity_offset * weight;
+        InertialFrame {
+            position: user_frame.position + (self.position_offset * weight).extend(0.0),
+            velocity: if proper_velocity.is_zero() {
+                Vector3::zero()
+            } else {
+                velocity_proper_to_3(proper_velocity)
+            },
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use crate::{
+    graphics::{
+        graphics_controller::{DepthMode, PipelineVariant},
+        vertex::FlareInstance,
+    },
+    special::transform::lorentz_factor,
+};
+use cgmath::{InnerSpace, Vector3};
+
+/// Entities tagged with this shine on their own. Anything else only gets a flare once it's beamed bright enough.
+pub const LIGHT_SOURCE_TAG: &str = "star";
+/// How bright something that doesn't shine on its own is, compared to a star, before any beaming.
+pub const REFLECTED_BRIGHTNESS: f64 = 0.02;
+/// How bright a source has to look to get a flare at all.
+pub const MIN_FLARE_BRIGHTNESS: f64 = 0.5;
+/// Flares stop getting any bigger past this, or something coming head-on near the speed of light would cover the
+/// whole screen.
+pub const MAX_FLARE_BRIGHTNESS: f64 = 16.0;
+/// Only the brightest sources get flares.
+pub const MAX_FLARES: usize = 32;
+/// How big the glare around a source with a brightness of 1 is, as a portion of the screen's height.
+const GLARE_SIZE: f32 = 0.3;
+/// The ghosts each source leaves along the line through the middle of the screen: how far along it they are (see
+/// [`FlareInstance::ghost`]), how big they are compared to the glare, and how bright.
+const GHOSTS: [(f32, f32, f32); 4] = [
+    (0.5, 0.12, 0.25),
+    (1.3, 0.2, 0.15),
+    (1.7, 0.08, 0.3),
+    (2.4, 0.35, 0.08),
+];
+
+/// The variant of the lens flare pipeline that reads a depth buffer drawn with (or without) logarithmic depth.
+pub fn flare_pipeline_variant(log_depth: bool) -> PipelineVariant {
+    PipelineVariant {
+        shader_defines: if log_depth {
+            vec!["LOG_DEPTH"]
+        } else {
+            Vec::new()
+        },
+        wireframe: false,
+        depth_mode: DepthMode::Normal,
+    }
+}
+
+/// How much something at `offset` from the camera moving at `velocity` (as a portion of the shown speed of light) has
+/// its light shifted: over 1 coming closer, under 1 going away.
+pub fn doppler_factor(velocity: Vector3<f64>, offset: Vector3<f64>) -> f64 {
+    let distance = offset.magnitude();
+    if distance == 0.0 {
+        return 1.0;
+    }
+    // how fast it's coming towards the camera
+    let approach = -velocity.dot(offset / distance);
+    1.0 / (lorentz_factor(velocity) * (1.0 - approach))
+}
+
+/// How bright a source looks compared to a star at rest. Light from something moving gets bunched up in the direction
+/// it's going, so it's as many times brighter as the fourth power of its [Doppler factor](doppler_factor()).
+pub fn apparent_brightness(
+    is_light_source: bool,
+    velocity: Vector3<f64>,
+    offset: Vector3<f64>,
+) -> f64 {
+    let brightness = if is_light_source {
+        1.0
+    } else {
+        REFLECTED_BRIGHTNESS
+    };
+    brightness * doppler_factor(velocity, offset).powi(4)
+}
+
+/// Something bright enough to get a lens flare.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlareSource {
+    /// Relative to the instance origin.
+    pub position: Vector3<f32>,
+    /// How far out its surface goes from `position`.
+    pub radius: f32,
+    pub color: [f32; 3],
+    /// See [`apparent_brightness()`].
+    pub brightness: f64,
+}
+
+impl FlareSource {
+    /// Its glare, then its ghosts.
+    pub fn sprites(self) -> impl Iterator<Item = FlareInstance> {
+        let brightness = self.brightness.min(MAX_FLARE_BRIGHTNESS) as f32;
+        let glare_size = GLARE_SIZE * brightness.sqrt();
+        let sprite = move |ghost: f32, size: f32, intensity: f32| FlareInstance {
+            position: self.position.into(),
+            radius: self.radius,
+            color: self.color,
+            intensity,
+            ghost,
+            size,
+        };
+
+        std::iter::once(sprite(0.0, glare_size, brightness)).chain(GHOSTS.into_iter().map(
+            move |(ghost, size, intensity)| {
+                sprite(ghost, glare_size * size, brightness * intensity)
+            },
+        ))
+    }
+}
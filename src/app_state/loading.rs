@@ -0,0 +1,193 @@
+use super::scenario::{load_scenario_or, LoadedScenario};
+use crate::{
+    graphics::{
+        mesh::FALLBACK_NORMAL,
+        model::{ModelData, MODEL_DATA},
+        texture::OrientedSection,
+        vertex::Vertex3D,
+    },
+    shared::{indexed_container::IndexedContainer, interning::ModelId, tasks::TaskPool},
+};
+use log::{debug, warn};
+use obj::{IndexTuple, SimplePolygon};
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, TryRecvError},
+};
+
+/// Something produced by the [`StartupLoader`]'s background task.
+#[derive(Debug)]
+pub enum LoadedAsset {
+    /// CPU-side model vertices that still need to be uploaded to the GPU.
+    Model(ModelId, IndexedContainer<Vertex3D>),
+    /// The starting scenario. This is always the last thing to be loaded.
+    Scenario(Box<LoadedScenario>),
+}
+
+#[derive(Debug)]
+enum LoadingMessage {
+    Stage(&'static str),
+    Progress(f32),
+    Asset(LoadedAsset),
+}
+
+/// Loads everything that doesn't need the GPU in a background task, so the window can keep drawing a
+/// loading screen instead of freezing.
+///
+/// Textures aren't loaded here since the loading screen itself needs the texture atlas to draw anything.
+#[derive(Debug)]
+pub struct StartupLoader {
+    receiver: Receiver<LoadingMessage>,
+    stage: &'static str,
+    progress: f32,
+    finished: bool,
+}
+
+impl StartupLoader {
+    /// `model_sections` maps every model's name to its texture section in the already-packed texture atlas.
+    /// Without a `scenario_path` (or if it's broken), `fallback_scenario` gets loaded instead, which is the source
+    /// of one of the built-in scenarios.
+    pub fn start<C: 'static>(
+        tasks: &TaskPool<C>,
+        model_sections: BTreeMap<String, OrientedSection>,
+        scenario_path: Option<PathBuf>,
+        fallback_scenario: &'static str,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        tasks.spawn_detached(move || {
+            let send = |message| {
+                // the receiver only gets dropped if the app is closing, in which case nobody cares
+                let _ = sender.send(message);
+            };
+
+            send(LoadingMessage::Stage("Processing models"));
+            let model_count = MODEL_DATA.len().max(1);
+            for (index, (name, data)) in MODEL_DATA.iter().enumerate() {
+                let Some(&section) = model_sections.get(name) else {
+                    warn!("No texture section was provided for model '{}'", name);
+                    continue;
+                };
+                send(LoadingMessage::Asset(LoadedAsset::Model(
+                    ModelId::intern(name),
+                    build_model_vertices(data, section),
+                )));
+                send(LoadingMessage::Progress(
+                    (index + 1) as f32 / model_count as f32,
+                ));
+            }
+
+            send(LoadingMessage::Stage("Loading scenario"));
+            send(LoadingMessage::Progress(0.0));
+            let scenario = load_scenario_or(scenario_path.as_deref(), fallback_scenario).build();
+            send(LoadingMessage::Progress(1.0));
+            send(LoadingMessage::Asset(LoadedAsset::Scenario(Box::new(
+                scenario,
+            ))));
+        });
+
+        Self {
+            receiver,
+            stage: "Starting",
+            progress: 0.0,
+            finished: false,
+        }
+    }
+
+    /// The name of the current loading stage.
+    pub fn stage(&self) -> &'static str {
+        self.stage
+    }
+
+    /// Progress through the current stage, from `0.0` to `1.0`.
+    pub fn progress(&self) -> f32 {
+        self.progress
+    }
+
+    /// Whether the scenario has been received, meaning everything is done.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Collects everything the background task has finished since the last poll.
+    pub fn poll(&mut self) -> Vec<LoadedAsset> {
+        let mut assets = Vec::new();
+        loop {
+            match self.receiver.try_recv() {
+                Ok(LoadingMessage::Stage(stage)) => {
+                    debug!("Startup stage: {}", stage);
+                    self.stage = stage;
+                }
+                Ok(LoadingMessage::Progress(progress)) => self.progress = progress,
+                Ok(LoadingMessage::Asset(asset)) => {
+                    if let LoadedAsset::Scenario(_) = asset {
+                        self.finished = true;
+                    }
+                    assets.push(asset);
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    if !self.finished {
+                        warn!("The startup loader task stopped before finishing");
+                        self.finished = true;
+                    }
+                    break;
+                }
+            }
+        }
+        assets
+    }
+}
+
+pub fn build_model_vertices(
+    model_data: &ModelData,
+    texture_section: OrientedSection,
+) -> IndexedContainer<Vertex3D> {
+    let data = &model_data.obj;
+    let mut missing_normals = false;
+    let mut vertices = IndexedContainer::with_capacity(data.position.len(), data.position.len());
+
+    for object in data.objects.iter() {
+        for group in object.groups.iter() {
+            for SimplePolygon(tuples) in group.polys.iter() {
+                if tuples.len() == 3 {
+                    for &IndexTuple(position_index, uv_index, normal_index) in tuples.iter() {
+                        let position = data.position[position_index];
+                        let uv = data
+                            .texture
+                            .get(uv_index.unwrap_or_default())
+                            .copied()
+                            .unwrap_or([0.0, 0.0]);
+                        let normal = normal_index
+                            .and_then(|normal_index| data.normal.get(normal_index))
+                            .copied()
+                            .unwrap_or_else(|| {
+                                missing_normals = true;
+                                FALLBACK_NORMAL
+                            });
+
+                        // this kinda sucks because we don't take advantage of vertex indexing
+                        // but i don't feel like writing an algorithm to convert the seperately indexed positions,
+                        // texture coords, and surface normals into a shared-index container
+                        vertices.items.push(Vertex3D {
+                            pos: position,
+                            uv: texture_section.section.local_point(uv.into()).into(),
+                            tex_index: texture_section.section.layer_index,
+                            normal,
+                            tangent: [0.0; 4],
+                        });
+                        vertices.indices.push(vertices.indices.len() as u32);
+                    }
+                }
+            }
+        }
+    }
+
+    if missing_normals {
+        vertices.compute_normals(model_data.normal_mode);
+    }
+    vertices.compute_tangents();
+
+    vertices
+}
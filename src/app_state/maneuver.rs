@@ -0,0 +1,231 @@
+use super::player::plan_burn;
+use crate::{
+    gui::color::GuiColor,
+    special::{
+        inertial_frame::InertialFrame,
+        universe::Universe,
+        worldline::{Worldline, WorldlineBranch, WorldlineEvent, WorldlineEventKind},
+    },
+};
+use cgmath::Vector3;
+
+/// How far past the end of the burn the preview keeps going, in seconds of coordinate time.
+pub const MANEUVER_PREVIEW_COAST: f64 = 60.0;
+pub const MANEUVER_PREVIEW_SAMPLES: usize = 120;
+/// How often the preview gets redone, in real seconds. The user's worldline can change underneath it at any time.
+pub const MANEUVER_PREVIEW_UPDATE_INTERVAL: f64 = 0.25;
+pub const MANEUVER_TRAIL_COLOR: GuiColor = GuiColor::YELLOW;
+
+/// A burn planned for later on along the user's worldline, like a maneuver node in KSP.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ManeuverNode {
+    /// When the burn starts.
+    pub coord_time: f64,
+    /// The proper acceleration to burn with, in the universe's axes.
+    pub acceleration: Vector3<f64>,
+    /// How long to burn for, in seconds of the user's proper time.
+    pub proper_time: f64,
+}
+
+impl ManeuverNode {
+    /// A node that doesn't burn at all yet.
+    pub fn new(coord_time: f64) -> Self {
+        Self {
+            coord_time,
+            acceleration: Vector3::new(0.0, 0.0, 0.0),
+            proper_time: 0.0,
+        }
+    }
+
+    /// The burn as it'd go on `worldline`.
+    pub fn burn(&self, worldline: &Worldline) -> SolvedBurn {
+        let start = worldline.get_event_at_time(self.coord_time);
+        let (end, duration) = plan_burn(
+            &start,
+            self.acceleration,
+            self.proper_time,
+            worldline.time_resolution,
+        );
+        SolvedBurn {
+            node: *self,
+            start,
+            end,
+            duration,
+        }
+    }
+
+    /// A branch of `worldline` with the burn put in, taking `duration` seconds of coordinate time (see
+    /// [`Self::burn()`]), so the real one stays untouched until it's committed.
+    pub fn preview(&self, worldline: &Worldline, duration: f64) -> WorldlineBranch {
+        let mut preview = worldline.branch();
+        preview.insert_event(
+            self.coord_time,
+            WorldlineEventKind::Acceleration(self.acceleration),
+        );
+        preview.insert_event(self.coord_time + duration, WorldlineEventKind::Inertial);
+        preview
+    }
+}
+
+/// A node's burn, worked out from where it starts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SolvedBurn {
+    pub node: ManeuverNode,
+    pub start: WorldlineEvent,
+    pub end: WorldlineEvent,
+    /// In seconds of coordinate time.
+    pub duration: f64,
+}
+
+impl SolvedBurn {
+    /// Whether this is still the burn for `node` on `worldline`, which only takes finding where it starts.
+    pub fn is_current(&self, node: ManeuverNode, worldline: &Worldline) -> bool {
+        self.node == node && self.start == worldline.get_event_at_time(node.coord_time)
+    }
+}
+
+/// Everything needed to redo a [`ManeuverPlanner`]'s preview, so it can be done away from the main thread.
+#[derive(Debug, Clone)]
+pub struct ManeuverPreviewJob {
+    generation: u64,
+    node: ManeuverNode,
+    /// The user's, as it was when the job was made.
+    worldline: Worldline,
+    now: f64,
+    /// The last burn worked out, which gets reused if it's still current.
+    solved: Option<SolvedBurn>,
+}
+
+impl ManeuverPreviewJob {
+    pub fn run(self) -> ManeuverPreview {
+        let burn = match self.solved {
+            Some(solved) if solved.is_current(self.node, &self.worldline) => solved,
+            _ => self.node.burn(&self.worldline),
+        };
+        let preview = self.node.preview(&self.worldline, burn.duration);
+        let end_time = preview
+            .worldline()
+            .events()
+            .back()
+            .map_or(self.node.coord_time, |event| event.frame.position.w)
+            + MANEUVER_PREVIEW_COAST;
+        ManeuverPreview {
+            generation: self.generation,
+            burn,
+            events: preview.sample(self.now, end_time, MANEUVER_PREVIEW_SAMPLES),
+        }
+    }
+}
+
+/// What a [`ManeuverPreviewJob`] comes back with, for [`ManeuverPlanner::finish()`].
+#[derive(Debug, Clone)]
+pub struct ManeuverPreview {
+    generation: u64,
+    burn: SolvedBurn,
+    events: Vec<WorldlineEvent>,
+}
+
+/// The user's maneuver node, if they've placed one, along with a preview of where it leads.
+#[derive(Debug, Clone)]
+pub struct ManeuverPlanner {
+    pub node: Option<ManeuverNode>,
+    /// From now until [`MANEUVER_PREVIEW_COAST`] after the burn, oldest first. This is the actual path, not what the
+    /// user would see of it after the light delay.
+    preview: Vec<WorldlineEvent>,
+    /// The node's burn as of the last preview, so it's only worked out again once something it depends on changes.
+    solved: Option<SolvedBurn>,
+    since_update: f64,
+    /// Goes up every time the node changes, so previews of an old one can be told apart.
+    generation: u64,
+    /// The generation of the preview that's being worked out, if there is one. Only one goes at a time.
+    in_flight: Option<u64>,
+}
+
+impl Default for ManeuverPlanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ManeuverPlanner {
+    pub fn new() -> Self {
+        Self {
+            node: None,
+            preview: Vec::new(),
+            solved: None,
+            since_update: MANEUVER_PREVIEW_UPDATE_INTERVAL,
+            generation: 0,
+            in_flight: None,
+        }
+    }
+
+    /// Replaces the node, or takes it away if `node` is `None`.
+    pub fn set(&mut self, node: Option<ManeuverNode>) {
+        self.node = node;
+        self.preview.clear();
+        self.generation += 1;
+        // so the next update doesn't have to wait
+        self.since_update = MANEUVER_PREVIEW_UPDATE_INTERVAL;
+    }
+
+    /// The node's burn on `worldline`, which is only worked out again if the last preview's is out of date.
+    pub fn burn(&self, worldline: &Worldline) -> Option<SolvedBurn> {
+        let node = self.node?;
+        Some(match self.solved {
+            Some(solved) if solved.is_current(node, worldline) => solved,
+            _ => node.burn(worldline),
+        })
+    }
+
+    /// Drops the node once it's been passed by, and every [`MANEUVER_PREVIEW_UPDATE_INTERVAL`] hands back a job to
+    /// redo the preview with, to [`ManeuverPreviewJob::run()`] off the main thread and then give to
+    /// [`Self::finish()`]. There's never more than one out at a time.
+    pub fn update(&mut self, universe: &Universe, delta: f64) -> Option<ManeuverPreviewJob> {
+        let node = self.node?;
+        if node.coord_time < universe.time {
+            self.set(None);
+            return None;
+        }
+        self.since_update += delta;
+        if self.since_update < MANEUVER_PREVIEW_UPDATE_INTERVAL || self.in_flight.is_some() {
+            return None;
+        }
+        self.since_update = 0.0;
+
+        self.in_flight = Some(self.generation);
+        Some(ManeuverPreviewJob {
+            generation: self.generation,
+            node,
+            worldline: universe.get_user_entity().worldline.clone(),
+            now: universe.time,
+            solved: self.solved,
+        })
+    }
+
+    /// Takes in a preview from [`Self::update()`]'s job, unless the node's changed since.
+    pub fn finish(&mut self, preview: ManeuverPreview) {
+        if self.in_flight == Some(preview.generation) {
+            self.in_flight = None;
+        }
+        if preview.generation != self.generation {
+            return;
+        }
+        self.solved = Some(preview.burn);
+        self.preview = preview.events;
+    }
+
+    /// The previewed path, as positions relative to `user_frame` in its rest frame.
+    pub fn trail(&self, user_frame: InertialFrame) -> Vec<Vector3<f32>> {
+        self.preview
+            .iter()
+            .map(|event| {
+                event
+                    .frame
+                    .relative_to(user_frame)
+                    .position
+                    .truncate()
+                    .map(|v| v as f32)
+            })
+            .collect()
+    }
+}
@@ -1,3 +1,68 @@
+#[cfg(feature = "client")]
 mod state;
+#[cfg(feature = "client")]
 pub use state::*;
+#[cfg(feature = "client")]
+pub mod angular_size;
+#[cfg(feature = "client")]
+pub mod auxiliary_view;
+pub mod benchmark;
+#[cfg(feature = "client")]
+pub mod causality;
+#[cfg(feature = "client")]
+pub mod clipboard;
+#[cfg(feature = "client")]
+pub mod debug_overlay;
+#[cfg(feature = "client")]
+pub mod docking;
+#[cfg(feature = "client")]
+pub mod event_ordering;
+#[cfg(feature = "client")]
+pub mod frame_pacing;
+#[cfg(feature = "client")]
+pub mod frame_transition;
+#[cfg(feature = "client")]
+pub mod lens_flare;
+#[cfg(feature = "client")]
+pub mod loading;
+#[cfg(feature = "client")]
+pub mod maneuver;
+#[cfg(feature = "client")]
+pub mod motion_blur;
+#[cfg(feature = "client")]
+pub mod observers;
+#[cfg(feature = "client")]
+pub mod orbit_camera;
+#[cfg(feature = "client")]
+pub mod photo_mode;
+pub mod physics;
+#[cfg(feature = "client")]
 pub mod player;
+#[cfg(feature = "client")]
+pub mod plugins;
+pub mod post_effects;
+#[cfg(feature = "client")]
+pub mod proximity;
+#[cfg(feature = "client")]
+pub mod replay;
+#[cfg(feature = "client")]
+pub mod ruler;
+#[cfg(feature = "client")]
+pub mod save_game;
+pub mod scenario;
+#[cfg(feature = "client")]
+pub mod scripting;
+#[cfg(feature = "client")]
+pub mod selection;
+#[cfg(feature = "client")]
+pub mod settings;
+pub mod signals;
+#[cfg(feature = "client")]
+pub mod stereo;
+#[cfg(feature = "client")]
+pub mod tour;
+#[cfg(feature = "client")]
+pub mod trajectory;
+pub mod undo;
+#[cfg(feature = "client")]
+pub mod waypoints;
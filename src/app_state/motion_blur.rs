@@ -0,0 +1,88 @@
+use crate::special::universe::EntityId;
+use cgmath::{Matrix4, Vector3, Zero};
+use std::collections::BTreeMap;
+
+/// In degrees, like a film camera's: 360 has the shutter open for the whole time between frames, and 180 for half of it.
+pub const DEFAULT_SHUTTER_ANGLE: f32 = 180.0;
+pub const MAX_SHUTTER_ANGLE: f32 = 360.0;
+/// How far each pixel moved on screen, drawn by the 3D pipeline's `velocity_main`. It's a float target, since the
+/// velocities can go either way.
+pub const VELOCITY_TARGET_NAME: &str = "motion_velocity";
+pub const VELOCITY_TARGET_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+/// What the scene gets blurred into, before it's put back.
+pub const BLURRED_TARGET_NAME: &str = "motion_blurred";
+
+/// Where the main camera and everything drawn were last frame, so this frame can work out how far they've moved on
+/// screen.
+#[derive(Debug, Clone)]
+pub struct MotionHistory {
+    /// Where the camera was, and its view-projection matrix relative to there.
+    camera: Option<(Vector3<f32>, Matrix4<f32>)>,
+    /// What entity instances were positioned relative to.
+    origin: Vector3<f32>,
+    /// Each entity that was drawn, by the camera-relative model matrix it was drawn with.
+    model_matrices: BTreeMap<EntityId, Matrix4<f32>>,
+}
+
+impl Default for MotionHistory {
+    fn default() -> Self {
+        Self {
+            camera: None,
+            origin: Vector3::zero(),
+            model_matrices: BTreeMap::new(),
+        }
+    }
+}
+
+impl MotionHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forgets everything, so nothing looks like it's moved the next time it's drawn.
+    pub fn clear(&mut self) {
+        self.camera = None;
+        self.model_matrices.clear();
+    }
+
+    /// Last frame's view-projection matrix for a camera that's at `origin` now, going from positions relative to
+    /// `origin`. Then `view_projection` (relative to `origin` too) becomes the one for next frame.
+    pub fn advance_camera(
+        &mut self,
+        origin: Vector3<f32>,
+        view_projection: Matrix4<f32>,
+    ) -> Matrix4<f32> {
+        let previous = match self.camera {
+            Some((previous_origin, previous_view_projection)) => {
+                previous_view_projection * Matrix4::from_translation(origin - previous_origin)
+            }
+            None => view_projection,
+        };
+        self.camera = Some((origin, view_projection));
+        previous
+    }
+
+    /// What an entity drawn with `model_matrix` (relative to `origin`) was drawn with last frame, relative to `origin`
+    /// too. If it wasn't drawn, it's taken to not have moved.
+    pub fn previous_model_matrix(
+        &self,
+        entity_id: EntityId,
+        origin: Vector3<f32>,
+        model_matrix: Matrix4<f32>,
+    ) -> Matrix4<f32> {
+        match self.model_matrices.get(&entity_id) {
+            Some(previous) => Matrix4::from_translation(self.origin - origin) * previous,
+            None => model_matrix,
+        }
+    }
+
+    /// Remembers what everything drawn this frame was drawn with, relative to `origin`, for next frame.
+    pub fn advance_models(
+        &mut self,
+        origin: Vector3<f32>,
+        model_matrices: BTreeMap<EntityId, Matrix4<f32>>,
+    ) {
+        self.origin = origin;
+        self.model_matrices = model_matrices;
+    }
+}
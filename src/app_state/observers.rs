@@ -0,0 +1,180 @@
+use crate::{
+    gui::color::GuiColor,
+    special::{
+        inertial_frame::InertialFrame,
+        transform::lorentz_factor,
+        universe::{EntityId, Universe},
+    },
+};
+use cgmath::{InnerSpace, Vector3};
+
+pub const MAX_PINNED_OBSERVERS: usize = 4;
+/// How far back trails go, in seconds of coordinate time.
+pub const OBSERVER_TRAIL_DURATION: f64 = 30.0;
+pub const OBSERVER_TRAIL_SAMPLES: usize = 90;
+/// One per pin slot, along with the format code for the same color, so a row in the table matches its trail.
+pub const OBSERVER_COLORS: [(GuiColor, char); MAX_PINNED_OBSERVERS] = [
+    (GuiColor::AQUA, 'b'),
+    (GuiColor::GOLD, '6'),
+    (GuiColor::LIGHT_PURPLE, 'd'),
+    (GuiColor::GREEN, 'a'),
+];
+
+/// How one observer's doing compared to the user, since the ensemble started.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObserverComparison {
+    pub entity_id: EntityId,
+    pub prefab: Option<String>,
+    pub elapsed_proper_time: f64,
+    /// The user's own elapsed proper time over the same stretch of coordinate time, for comparing against.
+    pub user_elapsed_proper_time: f64,
+    pub relative_gamma: f64,
+    /// In the universe's rest frame, at the current coordinate time.
+    pub distance: f64,
+}
+
+impl ObserverComparison {
+    /// How much more (or less, if negative) proper time has passed for the observer than for the user.
+    pub fn proper_time_difference(&self) -> f64 {
+        self.elapsed_proper_time - self.user_elapsed_proper_time
+    }
+}
+
+/// A few entities pinned side by side, so their clocks can be compared against each other and the user's.
+#[derive(Debug, Clone, Default)]
+pub struct ObserverEnsemble {
+    pub entity_ids: Vec<EntityId>,
+    /// The coordinate time that elapsed proper times are measured from, which is when the first one got pinned.
+    pub since: f64,
+}
+
+impl ObserverEnsemble {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entity_ids.is_empty()
+    }
+
+    pub fn is_pinned(&self, entity_id: EntityId) -> bool {
+        self.entity_ids.contains(&entity_id)
+    }
+
+    /// Returns `false` if there's no room for another one.
+    pub fn pin(&mut self, entity_id: EntityId, coord_time: f64) -> bool {
+        if self.is_pinned(entity_id) {
+            return true;
+        }
+        if self.entity_ids.len() >= MAX_PINNED_OBSERVERS {
+            return false;
+        }
+        if self.is_empty() {
+            self.since = coord_time;
+        }
+        self.entity_ids.push(entity_id);
+        true
+    }
+
+    /// Returns whether it was pinned.
+    pub fn unpin(&mut self, entity_id: EntityId) -> bool {
+        let count = self.entity_ids.len();
+        self.entity_ids.retain(|&pinned_id| pinned_id != entity_id);
+        self.entity_ids.len() != count
+    }
+
+    pub fn clear(&mut self) {
+        self.entity_ids.clear();
+    }
+
+    /// Starts measuring elapsed proper times over again from `coord_time`.
+    pub fn reset(&mut self, coord_time: f64) {
+        self.since = coord_time;
+    }
+
+    /// Keeps measuring from the same moment after the universe is rebased by `time_offset` seconds.
+    pub fn rebase(&mut self, time_offset: f64) {
+        self.since -= time_offset;
+    }
+
+    /// Unpins anything that isn't around anymore, and starts over if time went back past the start (e.g. from
+    /// scrubbing the timeline).
+    pub fn update(&mut self, universe: &Universe) {
+        self.entity_ids
+            .retain(|entity_id| universe.entities.contains_key(entity_id));
+        if universe.time < self.since {
+            self.since = universe.time;
+        }
+    }
+
+    /// The color for a pinned entity's row and trail.
+    pub fn color(&self, entity_id: EntityId) -> Option<(GuiColor, char)> {
+        let index = self
+            .entity_ids
+            .iter()
+            .position(|&pinned_id| pinned_id == entity_id)?;
+        Some(OBSERVER_COLORS[index])
+    }
+
+    pub fn compare(&self, universe: &Universe) -> Vec<ObserverComparison> {
+        let user_id = universe.user_entity_id;
+        let elapsed = |entity_id| {
+            let now = universe.get_event_at_time(entity_id, universe.time)?;
+            let then = universe.get_event_at_time(entity_id, self.since)?;
+            Some((now, now.proper_time - then.proper_time))
+        };
+        let Some((user_event, user_elapsed_proper_time)) = elapsed(user_id) else {
+            return Vec::new();
+        };
+
+        self.entity_ids
+            .iter()
+            .filter_map(|&entity_id| {
+                let (event, elapsed_proper_time) = elapsed(entity_id)?;
+                let relative_frame = event.frame.relative_to(user_event.frame);
+                Some(ObserverComparison {
+                    entity_id,
+                    prefab: universe.entities[&entity_id]
+                        .prefab
+                        .map(|prefab| prefab.name().to_owned()),
+                    elapsed_proper_time,
+                    user_elapsed_proper_time,
+                    relative_gamma: lorentz_factor(relative_frame.velocity),
+                    distance: (event.frame.position - user_event.frame.position)
+                        .truncate()
+                        .magnitude(),
+                })
+            })
+            .collect()
+    }
+
+    /// Where a pinned entity has been lately, oldest first, as positions relative to `user_frame` in its rest frame.
+    /// This is the actual path, not what the user would see of it after the light delay.
+    pub fn trail(
+        &self,
+        universe: &Universe,
+        entity_id: EntityId,
+        user_frame: InertialFrame,
+    ) -> Vec<Vector3<f32>> {
+        let Some(entity) = universe.entities.get(&entity_id) else {
+            return Vec::new();
+        };
+        let start_time = self
+            .since
+            .max(universe.time - OBSERVER_TRAIL_DURATION)
+            .min(universe.time);
+        let step = (universe.time - start_time) / (OBSERVER_TRAIL_SAMPLES - 1) as f64;
+
+        (0..OBSERVER_TRAIL_SAMPLES)
+            .map(|i| {
+                let event = universe.entity_event_at_time(entity, start_time + step * i as f64);
+                event
+                    .frame
+                    .relative_to(user_frame)
+                    .position
+                    .truncate()
+                    .map(|v| v as f32)
+            })
+            .collect()
+    }
+}
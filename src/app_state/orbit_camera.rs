@@ -0,0 +1,117 @@
+use super::player::PlayerController;
+use crate::{
+    graphics::camera::Camera,
+    shared::{gamepad::GamepadAxis, input::InputController},
+    special::{
+        universe::{EntityId, Universe},
+        worldline::WorldlineEvent,
+    },
+};
+use cgmath::{vec2, vec3, Deg, Quaternion, Rad, Rotation, Rotation3, Vector3};
+use winit::event::MouseButton;
+
+/// In light-seconds.
+pub const DEFAULT_ORBIT_DISTANCE: f64 = 12.0;
+pub const MIN_ORBIT_DISTANCE: f64 = 1.0;
+pub const MAX_ORBIT_DISTANCE: f64 = 2000.0;
+/// How much one notch of the scroll wheel zooms in or out by.
+pub const ORBIT_ZOOM_PER_NOTCH: f64 = 1.15;
+/// How many notches' worth a second the left stick zooms by, pushed all the way.
+pub const ORBIT_STICK_ZOOM_RATE: f64 = 8.0;
+/// Just shy of straight up or down, so the camera never flips over.
+pub const MAX_ORBIT_PITCH: Deg<f64> = Deg(89.0);
+
+/// A camera circling an entity, watching it from a frame moving along with it. The entity sits still in the middle,
+/// so what's left is how it looks from close by, without the user's own motion mixed in. It's only for rendering, so
+/// the user's ship carries on as it was in the meantime.
+#[derive(Debug, Clone)]
+pub struct OrbitCamera {
+    pub target: EntityId,
+    /// From the target, in its rest frame.
+    pub distance: f64,
+    pub yaw: Rad<f64>,
+    pub pitch: Rad<f64>,
+    /// The player's rotation from before, to go back to afterwards.
+    pub previous_rotation: Quaternion<f64>,
+}
+
+impl OrbitCamera {
+    /// Starts out looking the same way the player is.
+    pub fn new(target: EntityId, player: &PlayerController) -> Self {
+        let forward = player.rotation.rotate_vector(vec3(0.0, 0.0, -1.0));
+        Self {
+            target,
+            distance: DEFAULT_ORBIT_DISTANCE,
+            yaw: Rad((-forward.x).atan2(-forward.z)),
+            pitch: Rad(forward.y.clamp(-1.0, 1.0).asin()),
+            previous_rotation: player.rotation,
+        }
+    }
+
+    /// Which way the camera's turned. It always faces the target.
+    pub fn rotation(&self) -> Quaternion<f64> {
+        Quaternion::from_angle_y(self.yaw) * Quaternion::from_angle_x(self.pitch)
+    }
+
+    /// Where the camera is, relative to the target in its rest frame.
+    pub fn offset(&self) -> Vector3<f64> {
+        self.rotation().rotate_vector(vec3(0.0, 0.0, self.distance))
+    }
+
+    /// Scrolling or the left stick zooms, and dragging (or just moving the mouse, while it's locked) or the right
+    /// stick turns.
+    pub fn update(&mut self, input: &mut InputController, delta: f64) {
+        let mut zoom = input.uncaptured_scroll_delta() as f64;
+        if input.is_movement_suppressed() {
+            self.zoom(zoom);
+            return;
+        }
+        zoom += input.gamepad_axis(GamepadAxis::LeftStickY) as f64 * ORBIT_STICK_ZOOM_RATE * delta;
+        self.zoom(zoom);
+
+        let mut look_delta = if input.is_mouse_locked() || input.dragging(MouseButton::Left) {
+            input.mouse_delta().cast().unwrap()
+        } else {
+            vec2(0.0, 0.0)
+        };
+        // the right stick gets converted into the equivalent amount of mouse movement, the same as for the player
+        let stick_pixels = input.gamepad_settings.look_sensitivity as f64 * delta
+            / PlayerController::ANGLE_PER_PIXEL.0;
+        let stick_pitch = input.gamepad_axis(GamepadAxis::RightStickY) as f64;
+        look_delta.x += input.gamepad_axis(GamepadAxis::RightStickX) as f64 * stick_pixels;
+        look_delta.y -= if input.gamepad_settings.invert_look_y {
+            -stick_pitch
+        } else {
+            stick_pitch
+        } * stick_pixels;
+
+        let max_pitch = Rad::from(MAX_ORBIT_PITCH);
+        self.yaw -= Rad(PlayerController::ANGLE_PER_PIXEL.0 * look_delta.x);
+        self.pitch = Rad(
+            (self.pitch.0 - PlayerController::ANGLE_PER_PIXEL.0 * look_delta.y)
+                .clamp(-max_pitch.0, max_pitch.0),
+        );
+    }
+
+    /// Zooms in by `notches` of the scroll wheel, or out if it's negative.
+    fn zoom(&mut self, notches: f64) {
+        if notches != 0.0 {
+            self.distance = (self.distance / ORBIT_ZOOM_PER_NOTCH.powf(notches))
+                .clamp(MIN_ORBIT_DISTANCE, MAX_ORBIT_DISTANCE);
+        }
+    }
+
+    /// Where the camera is at `coord_time`, moving along with the target. `None` if the target's gone.
+    pub fn observer_event(&self, universe: &Universe, coord_time: f64) -> Option<WorldlineEvent> {
+        let event = universe.get_event_at_time(self.target, coord_time)?;
+        Some(event.comoving(self.offset()))
+    }
+
+    pub fn camera(&self) -> Camera {
+        Camera {
+            rotation: self.rotation().cast().unwrap(),
+            vertical_fov: Deg(90.0),
+            ..Default::default()
+        }
+    }
+}
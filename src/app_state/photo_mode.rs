@@ -0,0 +1,191 @@
+use super::player::PlayerController;
+use crate::{
+    graphics::camera::Camera,
+    shared::{action::Action, input::InputController, localization::tr},
+};
+use anyhow::Result;
+use cgmath::{vec3, Deg, InnerSpace, Quaternion, Rotation3, Vector3, Zero};
+use image::RgbaImage;
+use std::{fs, path::PathBuf};
+use winit::dpi::PhysicalSize;
+
+pub const PHOTOS_DIR: &str = "photos";
+/// How many times the window's size photos come out at.
+pub const PHOTO_SCALE: u32 = 2;
+/// The biggest texture every GPU can do.
+pub const MAX_PHOTO_SIZE: u32 = 8192;
+/// Reading a texture back needs rows that are a multiple of 256 bytes, so photo widths get rounded up to this.
+pub const PHOTO_WIDTH_ALIGNMENT: u32 = 64;
+/// How fast the camera flies, in light-seconds per second.
+pub const PHOTO_CAMERA_SPEED: f32 = 20.0;
+pub const MIN_PHOTO_FOV: Deg<f32> = Deg(10.0);
+pub const MAX_PHOTO_FOV: Deg<f32> = Deg(120.0);
+/// How much one notch of the scroll wheel zooms.
+pub const PHOTO_FOV_PER_NOTCH: Deg<f32> = Deg(5.0);
+
+/// A color grade for photos, shown live while framing the shot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum PhotoFilter {
+    #[default]
+    None,
+    Grayscale,
+    Sepia,
+    HighContrast,
+    Inverted,
+}
+
+impl PhotoFilter {
+    pub const ALL: [Self; 5] = [
+        Self::None,
+        Self::Grayscale,
+        Self::Sepia,
+        Self::HighContrast,
+        Self::Inverted,
+    ];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&filter| filter == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            Self::None => tr!("photo.filter.none"),
+            Self::Grayscale => tr!("photo.filter.grayscale"),
+            Self::Sepia => tr!("photo.filter.sepia"),
+            Self::HighContrast => tr!("photo.filter.high_contrast"),
+            Self::Inverted => tr!("photo.filter.inverted"),
+        }
+    }
+
+    /// Its fragment shader in `photo_filter.wgsl`, or `None` if it leaves the picture alone.
+    pub fn entry_point(self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Grayscale => Some("frag_grayscale"),
+            Self::Sepia => Some("frag_sepia"),
+            Self::HighContrast => Some("frag_high_contrast"),
+            Self::Inverted => Some("frag_inverted"),
+        }
+    }
+}
+
+/// A camera that's come loose from the user's worldline, for taking pictures. It's only for rendering, so flying it
+/// around doesn't make any worldline events, and the universe stays frozen the whole time.
+#[derive(Debug, Clone)]
+pub struct PhotoMode {
+    /// Relative to the user, in their rest frame.
+    pub position: Vector3<f32>,
+    /// Just yaw and pitch, the same as the player's.
+    pub rotation: Quaternion<f64>,
+    pub roll: Deg<f32>,
+    pub fov: Deg<f32>,
+    pub filter: PhotoFilter,
+    /// Whether the universe was already paused, so leaving photo mode can put it back how it was.
+    pub was_paused: bool,
+}
+
+impl PhotoMode {
+    /// Starts from wherever the player is looking.
+    pub fn new(player: &PlayerController, was_paused: bool) -> Self {
+        Self {
+            position: player.camera.position,
+            rotation: player.rotation,
+            roll: Deg(0.0),
+            fov: player.camera.vertical_fov,
+            filter: PhotoFilter::default(),
+            was_paused,
+        }
+    }
+
+    pub fn update(&mut self, input: &mut InputController, delta: f64) {
+        if input.action_pressed(Action::ToggleMouseLock) {
+            input.force_mouse_unlock = !input.force_mouse_unlock;
+        }
+        if input.action_pressed(Action::CyclePhotoFilter) {
+            self.filter = self.filter.next();
+        }
+
+        let zoom = input.uncaptured_scroll_delta();
+        if zoom != 0.0 {
+            self.fov =
+                Deg((self.fov.0 - PHOTO_FOV_PER_NOTCH.0 * zoom)
+                    .clamp(MIN_PHOTO_FOV.0, MAX_PHOTO_FOV.0));
+        }
+
+        if input.is_movement_suppressed() {
+            return;
+        }
+
+        // zoomed in, the mouse turns the camera less so aiming stays precise
+        let mouse_delta = input.mouse_delta();
+        let turn_scale = (self.fov.0 / 90.0) as f64;
+        self.rotation = (self.rotation
+            * Quaternion::from_angle_x(
+                PlayerController::ANGLE_PER_PIXEL * -mouse_delta.y as f64 * turn_scale,
+            )
+            * Quaternion::from_angle_y(
+                PlayerController::ANGLE_PER_PIXEL * -mouse_delta.x as f64 * turn_scale,
+            ))
+        .normalize();
+
+        let mut roll_delta = 0.0;
+        if input.action_held(Action::RollLeft) {
+            roll_delta += 1.0;
+        }
+        if input.action_held(Action::RollRight) {
+            roll_delta -= 1.0;
+        }
+        self.roll += Deg(PlayerController::ROLL_PER_SECOND.0 as f32 * roll_delta * delta as f32);
+
+        let mut movement = vec3(0.0, 0.0, 0.0);
+        for (action, direction) in [
+            (Action::ThrustForward, vec3(0.0, 0.0, -1.0)),
+            (Action::ThrustBackward, vec3(0.0, 0.0, 1.0)),
+            (Action::ThrustLeft, vec3(-1.0, 0.0, 0.0)),
+            (Action::ThrustRight, vec3(1.0, 0.0, 0.0)),
+            (Action::ThrustDown, vec3(0.0, -1.0, 0.0)),
+            (Action::ThrustUp, vec3(0.0, 1.0, 0.0)),
+        ] {
+            if input.action_held(action) {
+                movement += direction;
+            }
+        }
+        if !movement.is_zero() {
+            self.position +=
+                self.camera().rotation * movement.normalize() * PHOTO_CAMERA_SPEED * delta as f32;
+        }
+    }
+
+    pub fn camera(&self) -> Camera {
+        let rotation: Quaternion<f32> = self.rotation.cast().unwrap();
+        Camera {
+            position: self.position,
+            rotation: rotation * Quaternion::from_angle_z(self.roll),
+            vertical_fov: self.fov,
+            ..Default::default()
+        }
+    }
+
+    /// How big a photo taken in a window of `window_size` comes out.
+    pub fn photo_size(window_size: PhysicalSize<u32>) -> PhysicalSize<u32> {
+        let (window_width, window_height) = (
+            window_size.width.max(1) as f64,
+            window_size.height.max(1) as f64,
+        );
+        // huge windows get less than the full scale, to stay under the size limit
+        let scale =
+            (PHOTO_SCALE as f64).min(MAX_PHOTO_SIZE as f64 / window_width.max(window_height));
+        let width = ((window_width * scale) as u32).next_multiple_of(PHOTO_WIDTH_ALIGNMENT);
+        let height = (width as f64 * window_height / window_width).round() as u32;
+        PhysicalSize::new(width.min(MAX_PHOTO_SIZE), height.clamp(1, MAX_PHOTO_SIZE))
+    }
+
+    /// Writes a photo to [`PHOTOS_DIR`] as a PNG, returning where it went.
+    pub fn save_photo(image: &RgbaImage, name: &str) -> Result<PathBuf> {
+        fs::create_dir_all(PHOTOS_DIR)?;
+        let path = PathBuf::from(PHOTOS_DIR).join(format!("{name}.png"));
+        image.save(&path)?;
+        Ok(path)
+    }
+}
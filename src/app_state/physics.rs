@@ -0,0 +1,438 @@
+use crate::special::{
+    transform::lorentz_factor,
+    universe::{Entity, EntityId, Universe},
+    worldline::{WorldlineBranch, WorldlineEvent, WorldlineEventKind, PHYS_TIME_STEP},
+};
+use cgmath::{Vector3, Vector4};
+use std::{
+    collections::VecDeque,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// A change to the universe made from the render thread. It's applied to the render thread's snapshot right away
+/// so it shows up immediately, and to the physics thread's universe in order with the ticks.
+#[derive(Debug, Clone)]
+pub enum UniverseEdit {
+    /// See [`Worldline::insert_event()`](crate::special::worldline::Worldline::insert_event). An attached entity
+    /// gets detached first, since it has no worldline of its own to change.
+    InsertEvent {
+        entity_id: EntityId,
+        coord_time: f64,
+        kind: WorldlineEventKind,
+    },
+    /// See [`Worldline::insert_exact_event()`](crate::special::worldline::Worldline::insert_exact_event). Also
+    /// detaches the entity first.
+    InsertExactEvent {
+        entity_id: EntityId,
+        event: WorldlineEvent,
+    },
+    /// See [`Universe::insert_entity_with_id()`].
+    InsertEntity {
+        entity_id: EntityId,
+        entity: Box<Entity>,
+    },
+    /// See [`Universe::remove_entity()`].
+    RemoveEntity(EntityId),
+    /// See [`Universe::attach()`].
+    Attach {
+        entity_id: EntityId,
+        parent_id: EntityId,
+        offset: Vector3<f64>,
+    },
+    /// See [`Universe::detach()`].
+    Detach(EntityId),
+    /// Throws out the whole universe for a new one.
+    Replace(Box<Universe>),
+    /// Jumps to a coordinate time and watches from another entity's frame, for replays.
+    SetView { time: f64, user_entity_id: EntityId },
+    /// See [`Universe::set_speed_of_light()`].
+    SetSpeedOfLight(f64),
+    /// See [`Universe::rebase()`].
+    Rebase(Vector4<f64>),
+    SetColor {
+        entity_id: EntityId,
+        color: Vector4<f32>,
+    },
+    SetTags {
+        entity_id: EntityId,
+        tags: Vec<String>,
+    },
+    /// Moves an entity's whole worldline over in space, past and future alike.
+    Translate {
+        entity_id: EntityId,
+        offset: Vector3<f64>,
+    },
+}
+
+impl UniverseEdit {
+    /// Moves whatever events and times the edit carries `offset` over (with coordinate time in `w`), so it still
+    /// does the same thing after a [`UniverseEdit::Rebase`]. A whole new universe brings its own origin, so that's
+    /// left alone.
+    pub fn translate(&mut self, offset: Vector4<f64>) {
+        match self {
+            Self::InsertEvent {
+                coord_time, kind, ..
+            } => {
+                *coord_time += offset.w;
+                if let WorldlineEventKind::Circular(orbit) = kind {
+                    orbit.center += offset.truncate();
+                }
+            }
+            Self::InsertExactEvent { event, .. } => *event = event.translated(offset),
+            Self::InsertEntity { entity, .. } => entity.worldline.translate(offset),
+            Self::SetView { time, .. } => *time += offset.w,
+            _ => {}
+        }
+    }
+
+    pub fn apply(&self, universe: &mut Universe) {
+        match self {
+            Self::InsertEvent {
+                entity_id,
+                coord_time,
+                kind,
+            } => {
+                universe.detach(*entity_id);
+                if let Some(entity) = universe.entities.get_mut(entity_id) {
+                    entity.worldline.insert_event(*coord_time, *kind);
+                }
+            }
+            Self::InsertExactEvent { entity_id, event } => {
+                universe.detach(*entity_id);
+                if let Some(entity) = universe.entities.get_mut(entity_id) {
+                    entity.worldline.insert_exact_event(*event);
+                }
+            }
+            Self::InsertEntity { entity_id, entity } => {
+                universe.insert_entity_with_id(*entity_id, (**entity).clone());
+            }
+            Self::RemoveEntity(entity_id) => {
+                universe.remove_entity(*entity_id);
+            }
+            Self::Attach {
+                entity_id,
+                parent_id,
+                offset,
+            } => {
+                universe.attach(*entity_id, *parent_id, *offset);
+            }
+            Self::Detach(entity_id) => universe.detach(*entity_id),
+            Self::Replace(new_universe) => {
+                *universe = (**new_universe).clone();
+            }
+            Self::SetView {
+                time,
+                user_entity_id,
+            } => {
+                universe.time = *time;
+                if universe.entities.contains_key(user_entity_id) {
+                    universe.user_entity_id = *user_entity_id;
+                }
+            }
+            Self::SetSpeedOfLight(speed_of_light) => universe.set_speed_of_light(*speed_of_light),
+            Self::Rebase(offset) => universe.rebase(*offset),
+            Self::SetColor { entity_id, color } => {
+                if let Some(entity) = universe.entities.get_mut(entity_id) {
+                    entity.model_color = *color;
+                }
+            }
+            Self::SetTags { entity_id, tags } => {
+                if let Some(entity) = universe.entities.get_mut(entity_id) {
+                    entity.tags = tags.clone();
+                }
+            }
+            Self::Translate { entity_id, offset } => {
+                if let Some(entity) = universe.entities.get_mut(entity_id) {
+                    entity.worldline.translate(offset.extend(0.0));
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+enum PhysicsCommand {
+    Edit(u64, UniverseEdit),
+    Step(u64, u32),
+}
+
+#[derive(Debug)]
+struct PhysicsSnapshot {
+    universe: Universe,
+    /// The ID of the last command that went into this snapshot.
+    last_command_id: u64,
+    /// How long the physics thread spent on the commands since the last snapshot.
+    step_time: Duration,
+}
+
+/// The back buffer the physics thread publishes snapshots into. An unclaimed snapshot just gets replaced by a
+/// newer one.
+#[derive(Debug, Default)]
+struct SnapshotSlot {
+    snapshot: Mutex<Option<PhysicsSnapshot>>,
+    published: Condvar,
+}
+
+/// Steps the [`Universe`] (and bakes worldlines) on a background thread, so heavy physics can't stall rendering.
+/// The render thread works with the most recent snapshot of the universe, and changes it through
+/// [`UniverseEdit`]s.
+#[derive(Debug)]
+pub struct PhysicsThread {
+    universe: Universe,
+    sender: Sender<PhysicsCommand>,
+    slot: Arc<SnapshotSlot>,
+
+    next_command_id: u64,
+    /// Edits the physics thread might not have gotten to yet, which have to be redone on newer snapshots.
+    pending_edits: VecDeque<(u64, UniverseEdit)>,
+    /// How far ahead of (or behind) the snapshot's coordinate time things should be drawn, so motion stays smooth
+    /// between snapshots.
+    render_time_offset: f64,
+    last_step_time: Option<Duration>,
+    /// Every edit since the last [`PhysicsThread::take_edit_log()`], once [`PhysicsThread::start_edit_log()`] has
+    /// been called.
+    edit_log: Option<Vec<UniverseEdit>>,
+    /// Every entity taken out by a [`UniverseEdit::RemoveEntity`] since the last
+    /// [`PhysicsThread::take_removal_log()`], as it was right before, once [`PhysicsThread::start_removal_log()`] has
+    /// been called.
+    removal_log: Option<Vec<(EntityId, Entity)>>,
+    /// Whether to wait for the physics thread after every step. Slower, but the snapshot is always up to date,
+    /// which deterministic input playback relies on.
+    pub synchronous: bool,
+    /// Stops time from passing. Edits still go through.
+    pub paused: bool,
+}
+
+impl PhysicsThread {
+    pub fn start(universe: Universe) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let slot = Arc::new(SnapshotSlot::default());
+
+        let thread_universe = universe.clone();
+        let thread_slot = Arc::clone(&slot);
+        thread::Builder::new()
+            .name("physics".into())
+            .spawn(move || run_physics_thread(thread_universe, receiver, thread_slot))
+            .expect("failed to spawn the physics thread");
+
+        Self {
+            universe,
+            sender,
+            slot,
+
+            next_command_id: 0,
+            pending_edits: VecDeque::new(),
+            render_time_offset: 0.0,
+            last_step_time: None,
+            edit_log: None,
+            removal_log: None,
+            synchronous: false,
+            paused: false,
+        }
+    }
+
+    /// The latest snapshot, with any edits the physics thread hasn't caught up on applied.
+    pub fn universe(&self) -> &Universe {
+        &self.universe
+    }
+
+    fn send(&mut self, command: impl FnOnce(u64) -> PhysicsCommand) -> u64 {
+        let command_id = self.next_command_id;
+        self.next_command_id += 1;
+        // the physics thread only goes away if it panicked, which already got reported
+        let _ = self.sender.send(command(command_id));
+        command_id
+    }
+
+    /// The coordinate time to draw the universe at, which is extrapolated or interpolated from the snapshot based on
+    /// how much real time has passed since it was taken.
+    pub fn render_time(&self) -> f64 {
+        self.universe.time + self.render_time_offset
+    }
+
+    /// Moves [`PhysicsThread::render_time()`] along by a frame's worth of time, as seen by the user. It can't get more
+    /// than `max_ticks` ahead of or behind the snapshot.
+    pub fn advance_render_time(&mut self, frame_time: f64, max_ticks: u32) {
+        if self.paused {
+            return;
+        }
+        if self.synchronous {
+            // the snapshot is always current
+            self.render_time_offset = 0.0;
+            return;
+        }
+
+        let user_gamma = lorentz_factor(self.universe.user_event_now().frame.velocity);
+        let max_offset = max_ticks as f64 * PHYS_TIME_STEP * user_gamma;
+        self.render_time_offset =
+            (self.render_time_offset + frame_time * user_gamma).clamp(-max_offset, max_offset);
+    }
+
+    pub fn edit(&mut self, edit: UniverseEdit) {
+        if let UniverseEdit::Replace(_) | UniverseEdit::SetView { .. } = edit {
+            self.render_time_offset = 0.0;
+        }
+        if let (Some(removal_log), UniverseEdit::RemoveEntity(entity_id)) =
+            (&mut self.removal_log, &edit)
+        {
+            removal_log.extend(
+                self.universe
+                    .entities
+                    .get(entity_id)
+                    .map(|entity| (*entity_id, entity.clone())),
+            );
+        }
+        edit.apply(&mut self.universe);
+        if let Some(edit_log) = &mut self.edit_log {
+            edit_log.push(edit.clone());
+        }
+        let command_id = self.send(|command_id| PhysicsCommand::Edit(command_id, edit.clone()));
+        self.pending_edits.push_back((command_id, edit));
+    }
+
+    /// Makes the same changes `branch` made to its copy of an entity's worldline to the real one, as ordinary
+    /// [`UniverseEdit::InsertEvent`]s, so they get sent over the network like any others.
+    pub fn commit_branch(&mut self, entity_id: EntityId, branch: &WorldlineBranch) {
+        for &(coord_time, kind) in branch.candidates() {
+            self.edit(UniverseEdit::InsertEvent {
+                entity_id,
+                coord_time,
+                kind,
+            });
+        }
+    }
+
+    /// Starts keeping track of every edit, e.g. so they can be sent over the network.
+    pub fn start_edit_log(&mut self) {
+        self.edit_log.get_or_insert_with(Vec::new);
+    }
+
+    /// Stops keeping track of edits.
+    pub fn stop_edit_log(&mut self) {
+        self.edit_log = None;
+    }
+
+    /// The edits made since the last call, oldest first.
+    pub fn take_edit_log(&mut self) -> Vec<UniverseEdit> {
+        self.edit_log
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Starts keeping a copy of every entity that gets removed, e.g. so a replay can still show it from before.
+    pub fn start_removal_log(&mut self) {
+        self.removal_log.get_or_insert_with(Vec::new);
+    }
+
+    /// Stops keeping track of removed entities.
+    pub fn stop_removal_log(&mut self) {
+        self.removal_log = None;
+    }
+
+    /// The entities removed since the last call, oldest first.
+    pub fn take_removal_log(&mut self) -> Vec<(EntityId, Entity)> {
+        self.removal_log
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Asks the physics thread for some ticks. Unless [`PhysicsThread::synchronous`] is set, the results show up
+    /// in a later [`PhysicsThread::receive()`].
+    pub fn step(&mut self, ticks: u32) {
+        if ticks == 0 {
+            return;
+        }
+
+        let command_id = self.send(|command_id| PhysicsCommand::Step(command_id, ticks));
+        if self.synchronous {
+            self.wait_for(command_id);
+        }
+    }
+
+    fn wait_for(&mut self, command_id: u64) {
+        let mut snapshot = self.slot.snapshot.lock().unwrap();
+        while !snapshot
+            .as_ref()
+            .is_some_and(|snapshot| snapshot.last_command_id >= command_id)
+        {
+            snapshot = self.slot.published.wait(snapshot).unwrap();
+        }
+        drop(snapshot);
+
+        self.receive();
+    }
+
+    /// Picks up the newest snapshot, if there's one. Returns whether there was.
+    pub fn receive(&mut self) -> bool {
+        let Some(snapshot) = self.slot.snapshot.lock().unwrap().take() else {
+            return false;
+        };
+
+        let previous_time = self.universe.time;
+        self.universe = snapshot.universe;
+        self.pending_edits
+            .retain(|&(command_id, _)| command_id > snapshot.last_command_id);
+        for (_, edit) in self.pending_edits.iter() {
+            edit.apply(&mut self.universe);
+        }
+        // whatever time the snapshot caught up on doesn't need to be made up for anymore. This goes by the time with
+        // the pending edits redone, since a rebase the snapshot hasn't caught up on moves it all at once
+        self.render_time_offset -= self.universe.time - previous_time;
+        self.last_step_time = Some(snapshot.step_time);
+
+        true
+    }
+
+    /// How long the physics thread took to produce the last received snapshot. Taken, so it's only reported once.
+    pub fn take_step_time(&mut self) -> Option<Duration> {
+        self.last_step_time.take()
+    }
+}
+
+fn run_physics_thread(
+    mut universe: Universe,
+    receiver: Receiver<PhysicsCommand>,
+    slot: Arc<SnapshotSlot>,
+) {
+    // ends once the render thread drops its sender
+    while let Ok(first_command) = receiver.recv() {
+        let start = Instant::now();
+
+        let mut last_command_id = 0;
+        for command in std::iter::once(first_command).chain(receiver.try_iter()) {
+            match command {
+                PhysicsCommand::Edit(command_id, edit) => {
+                    edit.apply(&mut universe);
+                    last_command_id = command_id;
+                }
+                PhysicsCommand::Step(command_id, ticks) => {
+                    for _ in 0..ticks {
+                        universe.step(PHYS_TIME_STEP);
+                    }
+                    last_command_id = command_id;
+                }
+            }
+        }
+
+        let mut snapshot = slot.snapshot.lock().unwrap();
+        // the render thread never saw the last one, so it still needs to hear about the time spent on it
+        let unclaimed_step_time = snapshot
+            .as_ref()
+            .map(|snapshot| snapshot.step_time)
+            .unwrap_or_default();
+        *snapshot = Some(PhysicsSnapshot {
+            universe: universe.clone(),
+            last_command_id,
+            step_time: start.elapsed() + unclaimed_step_time,
+        });
+        drop(snapshot);
+        slot.published.notify_all();
+    }
+}
@@ -1,16 +1,303 @@
+use super::{
+    maneuver::SolvedBurn,
+    physics::{PhysicsThread, UniverseEdit},
+    save_game::PlayerSave,
+    waypoints::Waypoint,
+};
 use crate::{
     graphics::camera::Camera,
-    shared::input::InputController,
-    special::{universe::Universe, worldline::WorldlineEventKind},
+    shared::{action::Action, gamepad::GamepadAxis, input::InputController, localization::tr},
+    special::{
+        transform::{lorentz_boost, lorentz_factor, transform_3_velocity},
+        universe::{EntityId, Universe},
+        worldline::{WorldlineEvent, WorldlineEventKind},
+    },
+};
+use cgmath::{
+    vec3, Deg, InnerSpace, Matrix3, One, Quaternion, Rotation3, SquareMatrix, Vector3, Vector4,
+    Zero,
 };
-use cgmath::{vec3, Deg, InnerSpace, One, Quaternion, Rotation, Rotation3, Zero};
-use winit::keyboard::NamedKey;
+use std::collections::VecDeque;
+
+/// Relative speed (as a fraction of c) below which velocity matching is considered done.
+pub const VELOCITY_MATCH_TOLERANCE: f64 = 1e-6;
 
+/// How much each line of scrolling multiplies or divides the throttle by.
+pub const THROTTLE_STEP: f64 = 1.25;
+pub const MIN_ACCELERATION: f64 = 0.01;
+pub const MAX_ACCELERATION: f64 = 10.0;
+/// How many times the last step of a planned burn gets narrowed down by half.
+pub const BURN_SEARCH_ITERATIONS: usize = 48;
+/// How many times a planned burn gets corrected to come out matched.
+pub const BURN_SOLVER_ITERATIONS: usize = 12;
+/// How far (in seconds of proper time) a planned burn gets nudged to see how the result changes.
+pub const BURN_SOLVER_STEP: f64 = 1e-6;
+/// How far back (in seconds of the user's proper time) [`GammaHistory`] goes.
+pub const GAMMA_HISTORY_DURATION: f64 = 60.0;
+/// How many samples [`GammaHistory`] keeps over that time.
+pub const GAMMA_HISTORY_SAMPLES: usize = 240;
+
+/// Where a burn with a constant proper acceleration from `start` ends up after `proper_time`, and how much coordinate
+/// time that takes.
+///
+/// The burn's stepped through once, the same way its worldline will be, until the step that goes past
+/// `proper_time`, and only that last step gets narrowed down.
+pub fn plan_burn(
+    start: &WorldlineEvent,
+    acceleration: Vector3<f64>,
+    proper_time: f64,
+    time_resolution: f64,
+) -> (WorldlineEvent, f64) {
+    let mut frame = start.frame;
+    let mut elapsed = 0.0;
+    let mut duration = 0.0;
+    let last_step = loop {
+        let mut next = frame;
+        let step_proper_time = next.step(time_resolution, acceleration, start.integrator);
+        if elapsed + step_proper_time >= proper_time {
+            break (frame, elapsed);
+        }
+        frame = next;
+        elapsed += step_proper_time;
+        duration += time_resolution;
+    };
+
+    let (frame, elapsed) = last_step;
+    let partial_step = |step_size: f64| {
+        let mut frame = frame;
+        let step_proper_time = frame.step(step_size, acceleration, start.integrator);
+        (frame, elapsed + step_proper_time)
+    };
+    let (mut low, mut high) = (0.0, time_resolution);
+    for _ in 0..BURN_SEARCH_ITERATIONS {
+        let middle = (low + high) / 2.0;
+        if partial_step(middle).1 < proper_time {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    let (frame, elapsed) = partial_step(high);
+
+    let end = WorldlineEvent {
+        frame,
+        proper_time: start.proper_time + elapsed,
+        kind: WorldlineEventKind::Acceleration(acceleration),
+        ..*start
+    };
+    (end, duration + high)
+}
+
+/// Everything needed to plan a velocity matching burn, taken from the universe when it was asked for. See
+/// [`PlayerController::plan_velocity_match()`].
+#[derive(Debug, Clone)]
+pub struct VelocityMatchJob {
+    universe: Universe,
+    target_id: EntityId,
+    proper_time: Option<f64>,
+    /// The throttle.
+    max_acceleration: f64,
+}
+
+/// A velocity matching burn that's been worked out, for [`PlayerController::finish_velocity_match()`].
 #[derive(Debug, Clone, Copy)]
+pub struct VelocityMatchPlan {
+    pub target_id: EntityId,
+    /// The user's velocity it was planned from.
+    pub start_velocity: Vector3<f64>,
+    /// The proper acceleration to burn with.
+    pub acceleration: Vector3<f64>,
+    /// How long the burn takes, in seconds of the user's proper time.
+    pub proper_time: f64,
+    /// How long the burn takes, in seconds of coordinate time.
+    pub duration: f64,
+    /// How fast the user will still be going relative to the target afterwards, as a fraction of c.
+    pub leftover_speed: f64,
+    /// Whether it takes longer than was asked, since the throttle caps the acceleration.
+    pub throttle_limited: bool,
+}
+
+impl VelocityMatchJob {
+    /// Works out the burn, which takes a few dozen goes at integrating it, so this is best kept off the main thread.
+    pub fn solve(self) -> VelocityMatchPlan {
+        let universe = &self.universe;
+        let target = &universe.entities[&self.target_id];
+        let user_event = universe.user_event_now();
+        let relative_velocity = |user_velocity: Vector3<f64>, coord_time: f64| {
+            let target_velocity = universe
+                .entity_event_at_time(target, coord_time)
+                .frame
+                .velocity;
+            transform_3_velocity(lorentz_boost(user_velocity), target_velocity)
+        };
+        let start_velocity = relative_velocity(user_event.frame.velocity, universe.time);
+
+        // constant proper acceleration adds up rapidity at a steady rate of proper time
+        let rapidity = start_velocity.magnitude().atanh();
+        let throttle_limited = self
+            .proper_time
+            .is_some_and(|proper_time| rapidity / proper_time > self.max_acceleration);
+        let acceleration = match self.proper_time {
+            Some(proper_time) => (rapidity / proper_time).min(self.max_acceleration),
+            None => self.max_acceleration,
+        };
+
+        // a burn with a fixed direction only goes in a straight line through velocities if it starts from rest,
+        // since the rest frame it's measured in turns as the user speeds up. so the direction and length (together,
+        // as a vector of proper time) get nudged until the user comes out matched
+        let time_resolution = universe.get_user_entity().worldline.time_resolution;
+        let leftover_velocity = |burn_vector: Vector3<f64>| {
+            let (end_event, duration) = plan_burn(
+                &user_event,
+                burn_vector.normalize_to(acceleration),
+                burn_vector.magnitude(),
+                time_resolution,
+            );
+            relative_velocity(end_event.frame.velocity, universe.time + duration)
+        };
+        let mut burn_vector = start_velocity.normalize_to(rapidity / acceleration);
+        for _ in 0..BURN_SOLVER_ITERATIONS {
+            let leftover = leftover_velocity(burn_vector);
+            if leftover.magnitude() < VELOCITY_MATCH_TOLERANCE {
+                break;
+            }
+            let column = |axis: Vector3<f64>| {
+                (leftover_velocity(burn_vector + axis * BURN_SOLVER_STEP) - leftover)
+                    / BURN_SOLVER_STEP
+            };
+            let jacobian = Matrix3::from_cols(
+                column(Vector3::unit_x()),
+                column(Vector3::unit_y()),
+                column(Vector3::unit_z()),
+            );
+            let Some(inverse) = jacobian.invert() else {
+                break;
+            };
+            burn_vector -= inverse * leftover;
+        }
+
+        let proper_time = burn_vector.magnitude();
+        let (end_event, duration) = plan_burn(
+            &user_event,
+            burn_vector.normalize_to(acceleration),
+            proper_time,
+            time_resolution,
+        );
+        VelocityMatchPlan {
+            target_id: self.target_id,
+            start_velocity: user_event.frame.velocity,
+            acceleration: burn_vector.normalize_to(acceleration),
+            proper_time,
+            duration,
+            leftover_speed: relative_velocity(end_event.frame.velocity, universe.time + duration)
+                .magnitude(),
+            throttle_limited,
+        }
+    }
+}
+
+/// Things that can be done from the quick-action radial menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuickAction {
+    CutEngines,
+    FlipOrientation,
+    MatchVelocity,
+    BookmarkFrame,
+}
+
+impl QuickAction {
+    pub const ALL: [Self; 4] = [
+        Self::CutEngines,
+        Self::FlipOrientation,
+        Self::MatchVelocity,
+        Self::BookmarkFrame,
+    ];
+
+    pub fn label(self) -> String {
+        match self {
+            Self::CutEngines => tr!("quick_action.cut_engines"),
+            Self::FlipOrientation => tr!("quick_action.flip_around"),
+            Self::MatchVelocity => tr!("quick_action.match_velocity"),
+            Self::BookmarkFrame => tr!("quick_action.bookmark_frame"),
+        }
+    }
+}
+
+/// The user's Lorentz factor at steady steps of their own proper time, for seeing how maneuvers have played out.
+#[derive(Debug, Clone, Default)]
+pub struct GammaHistory {
+    /// The proper time and Lorentz factor of each sample, oldest first. Once it's full, the oldest gets dropped for
+    /// each new one.
+    samples: VecDeque<(f64, f64)>,
+}
+
+impl GammaHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Takes a sample at `event` if it's been long enough since the last one. Anything after it gets forgotten, since
+    /// the user must've gone back in time.
+    pub fn record(&mut self, event: &WorldlineEvent) {
+        let proper_time = event.proper_time;
+        while self
+            .samples
+            .back()
+            .is_some_and(|&(sample_time, _)| sample_time > proper_time)
+        {
+            self.samples.pop_back();
+        }
+
+        let interval = GAMMA_HISTORY_DURATION / GAMMA_HISTORY_SAMPLES as f64;
+        if self
+            .samples
+            .back()
+            .is_some_and(|&(sample_time, _)| proper_time - sample_time < interval)
+        {
+            return;
+        }
+        if self.samples.len() >= GAMMA_HISTORY_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples
+            .push_back((proper_time, lorentz_factor(event.frame.velocity)));
+    }
+
+    /// Each sample's Lorentz factor, oldest first.
+    pub fn values(&self) -> impl Iterator<Item = f64> + '_ {
+        self.samples.iter().map(|&(_, gamma)| gamma)
+    }
+
+    pub fn latest(&self) -> Option<f64> {
+        self.samples.back().map(|&(_, gamma)| gamma)
+    }
+
+    /// The highest it's been in the whole history.
+    pub fn peak(&self) -> Option<f64> {
+        self.values().reduce(f64::max)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct PlayerController {
     pub camera: Camera,
     pub rotation: Quaternion<f64>,
     pub acceleration: f64,
+    /// While set (and no thrust keys are held), the player accelerates to match this entity's velocity.
+    pub velocity_match_target: Option<EntityId>,
+    /// The coordinate time a burn from [`Self::finish_velocity_match()`] or [`Self::execute_maneuver()`] ends at. Until
+    /// then, the engines are left alone unless there's manual thrust.
+    pub planned_burn_end: Option<f64>,
+    /// The entity a burn's being planned to match velocity with, until it's been worked out. See
+    /// [`Self::plan_velocity_match()`].
+    pub pending_velocity_match: Option<EntityId>,
+    pub bookmarks: Vec<WorldlineEvent>,
+    pub waypoints: Vec<Waypoint>,
+    pub gamma_history: GammaHistory,
 }
 
 impl Default for PlayerController {
@@ -19,6 +306,12 @@ impl Default for PlayerController {
             camera: Default::default(),
             rotation: Quaternion::one(),
             acceleration: 0.25,
+            velocity_match_target: None,
+            planned_burn_end: None,
+            pending_velocity_match: None,
+            bookmarks: Vec::new(),
+            waypoints: Vec::new(),
+            gamma_history: GammaHistory::new(),
         }
     }
 }
@@ -27,81 +320,371 @@ impl PlayerController {
     pub const ANGLE_PER_PIXEL: Deg<f64> = Deg(0.1);
     pub const ROLL_PER_SECOND: Deg<f64> = Deg(45.0);
 
-    pub fn update(&mut self, universe: &mut Universe, input: &mut InputController, delta: f64) {
-        if input.pressed(NamedKey::Tab) {
+    pub fn to_save(&self) -> PlayerSave {
+        PlayerSave {
+            rotation: self.rotation,
+            acceleration: self.acceleration,
+            bookmarks: self.bookmarks.clone(),
+            waypoints: self.waypoints.clone(),
+        }
+    }
+
+    pub fn load_save(&mut self, save: PlayerSave) {
+        self.rotation = save.rotation;
+        self.acceleration = save.acceleration.clamp(MIN_ACCELERATION, MAX_ACCELERATION);
+        self.bookmarks = save.bookmarks;
+        self.waypoints = save.waypoints;
+        // the entity might not exist anymore
+        self.velocity_match_target = None;
+        self.planned_burn_end = None;
+        self.pending_velocity_match = None;
+        self.gamma_history.clear();
+    }
+
+    /// Keeps bookmarks, waypoints and the end of a planned burn where they were after the universe is rebased by
+    /// `offset`.
+    pub fn rebase(&mut self, offset: Vector4<f64>) {
+        if let Some(end_time) = &mut self.planned_burn_end {
+            *end_time -= offset.w;
+        }
+        for bookmark in self.bookmarks.iter_mut() {
+            *bookmark = bookmark.translated(-offset);
+        }
+        for waypoint in self.waypoints.iter_mut() {
+            waypoint.rebase(offset);
+        }
+    }
+
+    pub fn update(&mut self, physics: &mut PhysicsThread, input: &mut InputController, delta: f64) {
+        let universe = physics.universe();
+
+        if input.action_pressed(Action::ToggleMouseLock) {
             input.force_mouse_unlock = !input.force_mouse_unlock;
         }
 
+        // scrolling over the GUI scrolls it instead
+        let throttle_scroll = input.uncaptured_scroll_delta() as f64;
+        if throttle_scroll != 0.0 {
+            self.acceleration = (self.acceleration * THROTTLE_STEP.powf(throttle_scroll))
+                .clamp(MIN_ACCELERATION, MAX_ACCELERATION);
+        }
+
         let acceleration = if input.is_movement_suppressed() {
             vec3(0.0, 0.0, 0.0)
         } else {
             let mut movement_vector = vec3(0.0, 0.0, 0.0);
 
-            if input.held("w") {
+            if input.action_held(Action::ThrustForward) {
                 movement_vector.z -= 1.0;
             }
-            if input.held("a") {
+            if input.action_held(Action::ThrustLeft) {
                 movement_vector.x -= 1.0;
             }
-            if input.held("s") {
+            if input.action_held(Action::ThrustBackward) {
                 movement_vector.z += 1.0;
             }
-            if input.held("d") {
+            if input.action_held(Action::ThrustRight) {
                 movement_vector.x += 1.0;
             }
-            if input.held(NamedKey::Control) {
+            if input.action_held(Action::ThrustDown) {
                 movement_vector.y -= 1.0;
             }
-            if input.held(NamedKey::Shift) {
+            if input.action_held(Action::ThrustUp) {
                 movement_vector.y += 1.0;
             }
 
-            let mouse_delta = input.mouse_delta();
-            let (yaw_delta, pitch_delta) = (-mouse_delta.x as f64, -mouse_delta.y as f64);
-
-            let mut roll_delta = 0.0;
-            if input.held("q") {
-                roll_delta += 1.0;
-            }
-            if input.held("e") {
-                roll_delta -= 1.0;
-            }
-            roll_delta *= delta;
+            // analog input allows for partial thrust
+            movement_vector.x += input.gamepad_axis(GamepadAxis::LeftStickX) as f64;
+            movement_vector.y += input.gamepad_axis(GamepadAxis::LeftStickY) as f64;
+            movement_vector.z += (input.gamepad_axis(GamepadAxis::LeftTrigger)
+                - input.gamepad_axis(GamepadAxis::RightTrigger))
+                as f64;
 
-            self.rotation = (self.rotation
-                * Quaternion::from_angle_x(Self::ANGLE_PER_PIXEL * pitch_delta)
-                * Quaternion::from_angle_y(Self::ANGLE_PER_PIXEL * yaw_delta)
-                * Quaternion::from_angle_z(Self::ROLL_PER_SECOND * roll_delta))
-            .normalize();
+            self.turn(input, delta);
 
             if movement_vector.is_zero() {
                 vec3(0.0, 0.0, 0.0)
             } else {
-                self.rotation * (movement_vector.normalize() * self.acceleration)
+                // manual thrust takes over from velocity matching
+                self.velocity_match_target = None;
+                self.planned_burn_end = None;
+                self.pending_velocity_match = None;
+                if movement_vector.magnitude2() > 1.0 {
+                    movement_vector = movement_vector.normalize();
+                }
+                self.rotation * (movement_vector * self.acceleration)
             }
         };
+        let acceleration = if acceleration.is_zero() {
+            self.velocity_match_acceleration(universe)
+        } else {
+            acceleration
+        };
 
         let user_event = universe.user_event_now();
+        self.gamma_history.record(&user_event);
 
-        let update_acceleration =
-            if let WorldlineEventKind::Acceleration(proper_accel) = user_event.kind {
-                proper_accel != acceleration
-            } else {
-                !acceleration.is_zero()
-            };
+        // the planned burn's events are already in, so there's nothing to do until it's over
+        if acceleration.is_zero()
+            && self
+                .planned_burn_end
+                .is_some_and(|end_time| universe.time < end_time)
+        {
+            self.update_camera();
+            return;
+        }
+        self.planned_burn_end = None;
+
+        // close enough would just get coalesced into what's already there
+        let update_acceleration = if user_event.kind.is_acceleration() {
+            !user_event
+                .kind
+                .nearly_eq(&WorldlineEventKind::Acceleration(acceleration))
+        } else {
+            !acceleration.is_zero()
+        };
 
         if update_acceleration {
-            let time = universe.time;
-            universe
-                .get_user_entity_mut()
-                .worldline
-                .insert_event(time, WorldlineEventKind::Acceleration(acceleration));
+            physics.edit(UniverseEdit::InsertEvent {
+                entity_id: universe.user_entity_id,
+                coord_time: universe.time,
+                kind: WorldlineEventKind::Acceleration(acceleration),
+            });
         }
 
+        self.update_camera();
+    }
+
+    /// Just looking around, without any thrust. Used while watching replays.
+    pub fn update_free_camera(&mut self, input: &mut InputController, delta: f64) {
+        if input.action_pressed(Action::ToggleMouseLock) {
+            input.force_mouse_unlock = !input.force_mouse_unlock;
+        }
+        if !input.is_movement_suppressed() {
+            self.turn(input, delta);
+        }
+        self.update_camera();
+    }
+
+    /// Turns with the mouse, right stick and roll actions.
+    fn turn(&mut self, input: &InputController, delta: f64) {
+        let mouse_delta = input.mouse_delta();
+        let (mut yaw_delta, mut pitch_delta) = (-mouse_delta.x as f64, -mouse_delta.y as f64);
+
+        // the right stick gets converted into the equivalent amount of mouse movement
+        let stick_pixels =
+            input.gamepad_settings.look_sensitivity as f64 * delta / Self::ANGLE_PER_PIXEL.0;
+        let stick_pitch = input.gamepad_axis(GamepadAxis::RightStickY) as f64;
+        yaw_delta -= input.gamepad_axis(GamepadAxis::RightStickX) as f64 * stick_pixels;
+        pitch_delta += if input.gamepad_settings.invert_look_y {
+            -stick_pitch
+        } else {
+            stick_pitch
+        } * stick_pixels;
+
+        let mut roll_delta = 0.0;
+        if input.action_held(Action::RollLeft) {
+            roll_delta += 1.0;
+        }
+        if input.action_held(Action::RollRight) {
+            roll_delta -= 1.0;
+        }
+        roll_delta *= delta;
+
+        self.rotation = (self.rotation
+            * Quaternion::from_angle_x(Self::ANGLE_PER_PIXEL * pitch_delta)
+            * Quaternion::from_angle_y(Self::ANGLE_PER_PIXEL * yaw_delta)
+            * Quaternion::from_angle_z(Self::ROLL_PER_SECOND * roll_delta))
+        .normalize();
+    }
+
+    pub fn update_camera(&mut self) {
         self.camera = Camera {
             rotation: self.rotation.cast().unwrap(),
             vertical_fov: Deg(90.0),
             ..Default::default()
         }
     }
+
+    /// The proper acceleration needed to close in on the velocity match target's velocity, or zero if there's
+    /// nothing to match.
+    fn velocity_match_acceleration(&mut self, universe: &Universe) -> Vector3<f64> {
+        let zero = vec3(0.0, 0.0, 0.0);
+
+        let Some(target_id) = self.velocity_match_target else {
+            return zero;
+        };
+        let Some(target) = universe.entities.get(&target_id) else {
+            self.velocity_match_target = None;
+            return zero;
+        };
+
+        let user_velocity = universe.user_event_now().frame.velocity;
+        let target_velocity = universe
+            .entity_event_at_time(target, universe.time)
+            .frame
+            .velocity;
+        let relative_velocity = transform_3_velocity(lorentz_boost(user_velocity), target_velocity);
+
+        let relative_speed = relative_velocity.magnitude();
+        if relative_speed < VELOCITY_MATCH_TOLERANCE {
+            self.velocity_match_target = None;
+            return zero;
+        }
+
+        // ease off when close so we don't overshoot
+        relative_velocity.normalize_to(self.acceleration.min(relative_speed * 2.0))
+    }
+
+    /// Starts planning a single burn that leaves the user moving along with the target, taking `proper_time` seconds
+    /// of the user's time if the throttle's up for it, or as long as it takes at full throttle otherwise. Working it
+    /// out takes a while, so it's handed back as a job to [`VelocityMatchJob::solve()`] off the main thread, then
+    /// [`Self::finish_velocity_match()`] with the result. Returns a message to show the user instead if there's
+    /// nothing to plan.
+    pub fn plan_velocity_match(
+        &mut self,
+        target_id: EntityId,
+        proper_time: Option<f64>,
+        universe: &Universe,
+    ) -> Result<VelocityMatchJob, String> {
+        if self.pending_velocity_match.is_some() {
+            return Err(tr!("match.planning"));
+        }
+        let Some(target) = universe.entities.get(&target_id) else {
+            return Err(tr!("match.nothing_selected"));
+        };
+        let user_velocity = universe.user_event_now().frame.velocity;
+        let target_velocity = universe
+            .entity_event_at_time(target, universe.time)
+            .frame
+            .velocity;
+        if transform_3_velocity(lorentz_boost(user_velocity), target_velocity).magnitude()
+            < VELOCITY_MATCH_TOLERANCE
+        {
+            return Err(tr!("match.already_matched"));
+        }
+
+        self.pending_velocity_match = Some(target_id);
+        Ok(VelocityMatchJob {
+            universe: universe.clone(),
+            target_id,
+            proper_time,
+            max_acceleration: self.acceleration,
+        })
+    }
+
+    /// Puts a burn from [`VelocityMatchJob::solve()`] into the user's worldline, starting now. Unlike the quick
+    /// action, nothing steers it along the way, so it only comes out exact if the target's worldline doesn't change
+    /// in the meantime. Returns a message to show the user, or `None` if the plan's been called off since (by manual
+    /// thrust, or loading a save).
+    pub fn finish_velocity_match(
+        &mut self,
+        plan: VelocityMatchPlan,
+        physics: &mut PhysicsThread,
+    ) -> Option<String> {
+        if self.pending_velocity_match.take() != Some(plan.target_id) {
+            return None;
+        }
+        // the burn only depends on the velocity it starts from, so it still works from wherever the user is now
+        let universe = physics.universe();
+        let user_velocity = universe.user_event_now().frame.velocity;
+        if (user_velocity - plan.start_velocity).magnitude() >= VELOCITY_MATCH_TOLERANCE {
+            return Some(tr!("match.stale"));
+        }
+
+        let user_entity_id = universe.user_entity_id;
+        let now = universe.time;
+        physics.edit(UniverseEdit::InsertEvent {
+            entity_id: user_entity_id,
+            coord_time: now,
+            kind: WorldlineEventKind::Acceleration(plan.acceleration),
+        });
+        physics.edit(UniverseEdit::InsertEvent {
+            entity_id: user_entity_id,
+            coord_time: now + plan.duration,
+            kind: WorldlineEventKind::Inertial,
+        });
+        self.velocity_match_target = None;
+        self.planned_burn_end = Some(now + plan.duration);
+
+        let message = tr!(
+            "match.planned",
+            acceleration = format!("{:.3}", plan.acceleration.magnitude()),
+            proper_time = format!("{:.2}", plan.proper_time),
+            duration = format!("{:.2}", plan.duration),
+            leftover = format!("{:.1e}", plan.leftover_speed)
+        );
+        Some(if plan.throttle_limited {
+            message + &tr!("match.throttle_limited")
+        } else {
+            message
+        })
+    }
+
+    /// Hands a maneuver node over to the autopilot, which puts its burn into the user's worldline. Like with
+    /// [`Self::finish_velocity_match()`], nothing else happens to the engines until it's over, unless there's manual
+    /// thrust, which calls the whole thing off. Returns a message to show the user.
+    pub fn execute_maneuver(&mut self, burn: SolvedBurn, physics: &mut PhysicsThread) -> String {
+        let universe = physics.universe();
+        let node = burn.node;
+        if node.coord_time < universe.time {
+            return tr!("maneuver.passed");
+        }
+        let branch = node.preview(&universe.get_user_entity().worldline, burn.duration);
+        // the branch's last event is where the burn ends
+        let end_time = branch
+            .candidates()
+            .last()
+            .map_or(node.coord_time, |&(coord_time, _)| coord_time);
+
+        let user_entity_id = universe.user_entity_id;
+        let time_until = node.coord_time - universe.time;
+        physics.commit_branch(user_entity_id, &branch);
+        self.velocity_match_target = None;
+        self.planned_burn_end = Some(end_time);
+
+        tr!(
+            "maneuver.executing",
+            time = format!("{time_until:.2}"),
+            duration = format!("{:.2}", end_time - node.coord_time)
+        )
+    }
+
+    /// Does a quick action, returning a message to show the user.
+    pub fn perform_quick_action(
+        &mut self,
+        action: QuickAction,
+        physics: &mut PhysicsThread,
+    ) -> String {
+        let universe = physics.universe();
+        match action {
+            QuickAction::CutEngines => {
+                self.velocity_match_target = None;
+                self.planned_burn_end = None;
+                if universe.user_event_now().kind.is_acceleration() {
+                    physics.edit(UniverseEdit::InsertEvent {
+                        entity_id: universe.user_entity_id,
+                        coord_time: universe.time,
+                        kind: WorldlineEventKind::Inertial,
+                    });
+                }
+                tr!("quick_action.engines_cut")
+            }
+            QuickAction::FlipOrientation => {
+                self.rotation = (self.rotation * Quaternion::from_angle_y(Deg(180.0))).normalize();
+                tr!("quick_action.flipped_around")
+            }
+            QuickAction::MatchVelocity => match universe.nearest_entity_to_user() {
+                Some(target_id) => {
+                    self.velocity_match_target = Some(target_id);
+                    tr!("quick_action.matching_velocity")
+                }
+                None => tr!("quick_action.nothing_to_match"),
+            },
+            QuickAction::BookmarkFrame => {
+                self.bookmarks.push(universe.user_event_now());
+                tr!("quick_action.bookmarked", number = self.bookmarks.len())
+            }
+        }
+    }
 }
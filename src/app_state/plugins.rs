@@ -0,0 +1,263 @@
+use super::scripting::{ScriptCommand, ScriptEngine};
+use crate::{
+    shared::interning::TextureId,
+    special::{
+        prefab::{PrefabRegistry, PREFABS},
+        universe::Universe,
+    },
+};
+use anyhow::{Context, Result};
+use image::DynamicImage;
+use log::{info, warn};
+use mlua::Table;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub const PLUGINS_PATH: &str = "plugins";
+/// Every folder in [`PLUGINS_PATH`] with one of these is a plugin.
+pub const PLUGIN_MANIFEST_FILE: &str = "plugin.ron";
+/// More prefabs, in the same format as [`PREFABS_PATH`](crate::special::prefab::PREFABS_PATH).
+pub const PLUGIN_PREFABS_FILE: &str = "prefabs.ron";
+/// A Lua script, with the same hooks and `world`/`gui` tables as a scenario's (see [`ScriptEngine`]).
+pub const PLUGIN_SCRIPT_FILE: &str = "plugin.lua";
+/// Shown next to the plugin's panels.
+pub const PLUGIN_ICON_FILE: &str = "icon.png";
+
+/// Sets up the `plugin` table that scripts register things with at their top level.
+const PLUGIN_PRELUDE: &str = r#"
+plugin = { commands = {}, panels = {}, panel_order = {} }
+
+function plugin.command(name, help, run)
+    plugin.commands[name] = { help = help, run = run }
+end
+
+function plugin.panel(title, render)
+    if plugin.panels[title] == nil then
+        table.insert(plugin.panel_order, title)
+    end
+    plugin.panels[title] = render
+end
+"#;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginManifest {
+    /// Defaults to the name of the plugin's folder.
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    /// Disabled plugins don't get loaded at all.
+    pub enabled: bool,
+}
+
+impl Default for PluginManifest {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            version: "0.1.0".into(),
+            description: String::new(),
+            enabled: true,
+        }
+    }
+}
+
+/// Registered with `plugin.command(name, help, function(args) ... end)`, and run by typing `/name` into the chat box.
+/// `args` is a list of the words after the name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConsoleCommand {
+    pub name: String,
+    pub help: String,
+}
+
+#[derive(Debug)]
+pub struct Plugin {
+    pub manifest: PluginManifest,
+    pub directory: PathBuf,
+    pub script: Option<ScriptEngine>,
+    pub commands: Vec<ConsoleCommand>,
+    /// Panel titles, in the order they were registered with `plugin.panel(title, function() ... end)`. The function
+    /// gets called every frame, and returns the panel's text (with the usual `§` formatting codes), or `nil` to hide
+    /// it.
+    pub panels: Vec<String>,
+    /// How many prefabs it added.
+    pub prefab_count: usize,
+    /// Kept around decoded, since the texture made from it can get evicted whenever its panels aren't showing.
+    pub icon: Option<DynamicImage>,
+    /// What its icon's called in the texture atlas.
+    pub icon_texture: TextureId,
+}
+
+impl Plugin {
+    /// Loads the plugin in `directory`, adding its prefabs to [`PREFABS`] once everything else has loaded, so one that
+    /// fails doesn't leave any behind.
+    pub fn load(directory: impl AsRef<Path>) -> Result<Self> {
+        let directory = directory.as_ref();
+
+        let manifest_path = directory.join(PLUGIN_MANIFEST_FILE);
+        let source = fs::read_to_string(&manifest_path)
+            .with_context(|| format!("couldn't read {}", manifest_path.display()))?;
+        let mut manifest: PluginManifest = ron::from_str(&source)
+            .with_context(|| format!("couldn't parse {}", manifest_path.display()))?;
+        if manifest.name.is_empty() {
+            manifest.name = directory
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+        }
+
+        let icon_texture = TextureId::intern(&format!("plugin/{}/icon", manifest.name));
+        let mut plugin = Self {
+            icon_texture,
+            manifest,
+            directory: directory.to_owned(),
+            script: None,
+            commands: Vec::new(),
+            panels: Vec::new(),
+            prefab_count: 0,
+            icon: None,
+        };
+        if !plugin.manifest.enabled {
+            return Ok(plugin);
+        }
+
+        let prefabs_path = directory.join(PLUGIN_PREFABS_FILE);
+        let prefabs = if prefabs_path.exists() {
+            Some(PrefabRegistry::load(&prefabs_path)?)
+        } else {
+            None
+        };
+
+        let icon_path = directory.join(PLUGIN_ICON_FILE);
+        if icon_path.exists() {
+            plugin.icon = Some(
+                image::open(&icon_path)
+                    .with_context(|| format!("couldn't read {}", icon_path.display()))?,
+            );
+        }
+
+        let script_path = directory.join(PLUGIN_SCRIPT_FILE);
+        if script_path.exists() {
+            let source = fs::read_to_string(&script_path)
+                .with_context(|| format!("couldn't read {}", script_path.display()))?;
+            let script = ScriptEngine::with_prelude(&plugin.manifest.name, PLUGIN_PRELUDE, &source)
+                .with_context(|| format!("couldn't run {}", script_path.display()))?;
+            plugin.read_registrations(&script)?;
+            plugin.script = Some(script);
+        }
+
+        if let Some(prefabs) = prefabs {
+            plugin.prefab_count = prefabs.names().count();
+            PREFABS.write().unwrap().extend(prefabs);
+        }
+        Ok(plugin)
+    }
+
+    fn read_registrations(&mut self, script: &ScriptEngine) -> Result<()> {
+        let registry: Table = script.lua().globals().get("plugin")?;
+
+        let commands: Table = registry.get("commands")?;
+        for pair in commands.pairs::<String, Table>() {
+            let (name, command) = pair?;
+            self.commands.push(ConsoleCommand {
+                name,
+                help: command
+                    .get::<_, Option<String>>("help")?
+                    .unwrap_or_default(),
+            });
+        }
+        self.commands.sort_by(|a, b| a.name.cmp(&b.name));
+
+        self.panels = registry.get("panel_order")?;
+        Ok(())
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.script
+            .as_ref()
+            .is_some_and(|script| !script.has_failed())
+    }
+
+    pub fn has_command(&self, name: &str) -> bool {
+        self.commands.iter().any(|command| command.name == name)
+    }
+
+    /// Runs one of its console commands, returning what it asked for.
+    pub fn run_command(
+        &mut self,
+        name: &str,
+        args: Vec<String>,
+        universe: &Universe,
+    ) -> Vec<ScriptCommand> {
+        let Some(script) = &mut self.script else {
+            return Vec::new();
+        };
+        let (_, commands) =
+            script.call::<_, ()>(&["plugin", "commands", name, "run"], args, universe);
+        commands
+    }
+
+    /// The text of each of its panels that's showing right now, with the panel's title, and whatever the panels
+    /// asked for while working it out.
+    pub fn render_panels(
+        &mut self,
+        universe: &Universe,
+    ) -> (Vec<(String, String)>, Vec<ScriptCommand>) {
+        let Some(script) = &mut self.script else {
+            return (Vec::new(), Vec::new());
+        };
+
+        let mut panels = Vec::new();
+        let mut commands = Vec::new();
+        for title in self.panels.iter() {
+            let (text, panel_commands) =
+                script.call::<_, Option<String>>(&["plugin", "panels", title], (), universe);
+            commands.extend(panel_commands);
+            if let Some(text) = text.flatten() {
+                panels.push((title.clone(), text));
+            }
+        }
+        (panels, commands)
+    }
+}
+
+/// Loads every plugin in [`PLUGINS_PATH`], in alphabetical order so later ones win when prefabs clash. Plugins that
+/// fail to load are skipped.
+pub fn load_plugins() -> Vec<Plugin> {
+    let Ok(entries) = fs::read_dir(PLUGINS_PATH) else {
+        return Vec::new();
+    };
+
+    let mut directories: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.join(PLUGIN_MANIFEST_FILE).is_file())
+        .collect();
+    directories.sort();
+
+    let mut plugins = Vec::new();
+    for directory in directories {
+        match Plugin::load(&directory) {
+            Ok(plugin) if plugin.manifest.enabled => {
+                info!(
+                    "Loaded plugin '{}' {} ({} prefabs, {} commands, {} panels)",
+                    plugin.manifest.name,
+                    plugin.manifest.version,
+                    plugin.prefab_count,
+                    plugin.commands.len(),
+                    plugin.panels.len()
+                );
+                plugins.push(plugin);
+            }
+            Ok(plugin) => info!("Skipped plugin '{}', it's disabled", plugin.manifest.name),
+            Err(error) => warn!(
+                "Couldn't load the plugin in {}: {:#}",
+                directory.display(),
+                error
+            ),
+        }
+    }
+    plugins
+}
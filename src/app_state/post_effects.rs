@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+/// How far auto exposure can go by default, either way: up to this many times brighter, or this many times darker.
+pub const DEFAULT_MAX_EXPOSURE: f32 = 4.0;
+
+/// Effects drawn over the 3D view (but not the HUD), for cinematic recordings. Scenarios can set them, and they're
+/// all off otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PostEffects {
+    /// How much the corners get darkened, from 0 (not at all) to 1 (all the way to black).
+    pub vignette: f32,
+    /// How much film grain there is, from 0 to 1.
+    pub grain: f32,
+    /// Brightens or darkens the view towards a middle gray, going by how bright it's been lately, like eyes adjusting
+    /// after looking at something bright.
+    pub auto_exposure: bool,
+    /// In seconds, how long the exposure takes to get most of the way to where it's going.
+    pub adaptation_time: f32,
+    /// See [`DEFAULT_MAX_EXPOSURE`].
+    pub max_exposure: f32,
+}
+
+impl Default for PostEffects {
+    fn default() -> Self {
+        Self {
+            vignette: 0.0,
+            grain: 0.0,
+            auto_exposure: false,
+            adaptation_time: 1.0,
+            max_exposure: DEFAULT_MAX_EXPOSURE,
+        }
+    }
+}
+
+impl PostEffects {
+    /// Whether there's anything to draw at all.
+    pub fn is_active(&self) -> bool {
+        self.vignette > 0.0 || self.grain > 0.0 || self.auto_exposure
+    }
+
+    /// How much of the way the exposure gets to the current brightness after `delta` seconds.
+    pub fn adaptation(&self, delta: f64) -> f32 {
+        if self.adaptation_time <= 0.0 {
+            return 1.0;
+        }
+        1.0 - (-delta as f32 / self.adaptation_time).exp()
+    }
+}
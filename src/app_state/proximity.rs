@@ -0,0 +1,242 @@
+use crate::{
+    shared::bounding_box::BBox3F64,
+    special::{
+        spatial_index::SpatialIndex,
+        universe::{Entity, EntityId, Universe},
+    },
+};
+use cgmath::{InnerSpace, Vector3};
+use std::collections::BTreeMap;
+
+/// How far ahead closest approaches get looked for by default, in seconds of coordinate time.
+pub const DEFAULT_PROXIMITY_LOOKAHEAD: f64 = 30.0;
+pub const MAX_PROXIMITY_LOOKAHEAD: f64 = 600.0;
+/// How many points along each worldline get checked when looking ahead. Closest approaches between them get narrowed
+/// down afterwards, so this only has to be enough to not step over one entirely.
+pub const PROXIMITY_SAMPLES: usize = 60;
+pub const PROXIMITY_REFINE_ITERATIONS: usize = 32;
+/// Closest approaches with less of a gap than this between surfaces (in light-seconds) raise a warning.
+pub const PROXIMITY_WARNING_DISTANCE: f64 = 3.0;
+/// Anything that's not going to get at least this much closer (in light-seconds) isn't really approaching.
+pub const PROXIMITY_MIN_CLOSING: f64 = 0.01;
+/// How often the prediction gets redone, in real seconds.
+pub const PROXIMITY_UPDATE_INTERVAL: f64 = 0.25;
+pub const MAX_PROXIMITY_ALERTS: usize = 4;
+/// Light delays get worked out this many times over, each time closer.
+const LIGHT_DELAY_ITERATIONS: usize = 16;
+
+/// An entity that's about to come close to the user.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProximityAlert {
+    pub entity_id: EntityId,
+    /// Coordinate time from now until the closest approach, or until they first touch if they hit.
+    pub time_to_closest: f64,
+    /// The gap between surfaces at the closest approach, or zero or less if they hit.
+    pub distance: f64,
+    /// Coordinate time from now until light from that moment reaches the user, which is the soonest they could
+    /// actually see it happen.
+    pub time_to_seen: f64,
+}
+
+impl ProximityAlert {
+    pub fn is_collision(&self) -> bool {
+        self.distance <= 0.0
+    }
+}
+
+/// Looks ahead along every worldline for anything that's going to come close to the user.
+#[derive(Debug, Clone)]
+pub struct ProximityMonitor {
+    pub enabled: bool,
+    /// In seconds of coordinate time.
+    pub lookahead: f64,
+    /// Soonest first.
+    pub alerts: Vec<ProximityAlert>,
+    since_update: f64,
+}
+
+impl Default for ProximityMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProximityMonitor {
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            lookahead: DEFAULT_PROXIMITY_LOOKAHEAD,
+            alerts: Vec::new(),
+            since_update: PROXIMITY_UPDATE_INTERVAL,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.alerts.clear();
+        // so the next update doesn't have to wait
+        self.since_update = PROXIMITY_UPDATE_INTERVAL;
+    }
+
+    pub fn alert_for(&self, entity_id: EntityId) -> Option<&ProximityAlert> {
+        self.alerts
+            .iter()
+            .find(|alert| alert.entity_id == entity_id)
+    }
+
+    /// Redoes the prediction every [`PROXIMITY_UPDATE_INTERVAL`].
+    pub fn update(&mut self, universe: &Universe, delta: f64) {
+        if !self.enabled {
+            self.alerts.clear();
+            return;
+        }
+        self.since_update += delta;
+        if self.since_update < PROXIMITY_UPDATE_INTERVAL {
+            return;
+        }
+        self.since_update = 0.0;
+        self.alerts = predict_closest_approaches(universe, self.lookahead);
+    }
+}
+
+/// Roughly how far an entity's surface is from its center, going by how much its model is scaled up. Models are about
+/// 2 across before scaling.
+fn entity_radius(entity: &Entity) -> f64 {
+    let matrix = entity.model_matrix;
+    (0..3)
+        .map(|column| matrix[column].truncate().magnitude())
+        .fold(0.0f32, f32::max) as f64
+}
+
+fn position_at(universe: &Universe, entity: &Entity, coord_time: f64) -> Vector3<f64> {
+    universe
+        .entity_event_at_time(entity, coord_time)
+        .frame
+        .position
+        .truncate()
+}
+
+/// Every entity that gets within [`PROXIMITY_WARNING_DISTANCE`] of the user in the next `lookahead` seconds of
+/// coordinate time, going by where their worldlines are headed right now. Anything that isn't getting any closer is
+/// left out, unless it's touching. Attached entities are left out too, since whatever they're attached to covers them.
+/// Soonest first, at most [`MAX_PROXIMITY_ALERTS`].
+pub fn predict_closest_approaches(universe: &Universe, lookahead: f64) -> Vec<ProximityAlert> {
+    let now = universe.time;
+    let interval = lookahead / PROXIMITY_SAMPLES as f64;
+    let sample_times = (0..=PROXIMITY_SAMPLES)
+        .map(|i| now + i as f64 * interval)
+        .collect::<Vec<_>>();
+    let swept_bounds = |positions: &[Vector3<f64>], padding: f64| {
+        let bounds = BBox3F64::new(
+            positions
+                .iter()
+                .map(|&position| Into::<[f64; 3]>::into(position)),
+        );
+        BBox3F64::new([
+            bounds.min().map(|value| value - padding),
+            bounds.max().map(|value| value + padding),
+        ])
+    };
+
+    let user = universe.get_user_entity();
+    let user_radius = entity_radius(user);
+    let user_positions = sample_times
+        .iter()
+        .map(|&time| position_at(universe, user, time))
+        .collect::<Vec<_>>();
+
+    // broad phase, with everything's bounds stretched over where it'll be
+    let mut index = SpatialIndex::default();
+    let mut sampled_positions = BTreeMap::new();
+    for (&entity_id, entity) in universe.entities.iter() {
+        if entity_id == universe.user_entity_id
+            || entity.model.is_none()
+            || entity.attachment.is_some()
+        {
+            continue;
+        }
+        let positions = sample_times
+            .iter()
+            .map(|&time| position_at(universe, entity, time))
+            .collect::<Vec<_>>();
+        index.update(entity_id, swept_bounds(&positions, entity_radius(entity)));
+        sampled_positions.insert(entity_id, positions);
+    }
+    let candidates = index.query_box(swept_bounds(
+        &user_positions,
+        user_radius + PROXIMITY_WARNING_DISTANCE,
+    ));
+
+    let mut alerts = Vec::new();
+    for entity_id in candidates {
+        let entity = &universe.entities[&entity_id];
+        let radii = user_radius + entity_radius(entity);
+        let gap = |time: f64| {
+            (position_at(universe, entity, time) - position_at(universe, user, time)).magnitude()
+                - radii
+        };
+
+        let positions = &sampled_positions[&entity_id];
+        let closest_sample = (0..=PROXIMITY_SAMPLES)
+            .min_by(|&a, &b| {
+                let distance2 = |i: usize| (positions[i] - user_positions[i]).magnitude2();
+                distance2(a).total_cmp(&distance2(b))
+            })
+            .unwrap();
+        // the real closest approach is somewhere between the samples either side
+        let mut low = sample_times[closest_sample.saturating_sub(1)];
+        let mut high = sample_times[(closest_sample + 1).min(PROXIMITY_SAMPLES)];
+        for _ in 0..PROXIMITY_REFINE_ITERATIONS {
+            let third = (high - low) / 3.0;
+            if gap(low + third) < gap(high - third) {
+                high -= third;
+            } else {
+                low += third;
+            }
+        }
+        let closest_time = (low + high) / 2.0;
+        let distance = gap(closest_time);
+        let approaching = gap(now) - distance >= PROXIMITY_MIN_CLOSING;
+        if distance > PROXIMITY_WARNING_DISTANCE || (!approaching && distance > 0.0) {
+            continue;
+        }
+
+        // if they hit, what matters is when they first touch
+        let approach_time = if distance > 0.0 {
+            closest_time
+        } else if gap(now) <= 0.0 {
+            now
+        } else {
+            let mut low = now;
+            let mut high = closest_time;
+            for _ in 0..PROXIMITY_REFINE_ITERATIONS {
+                let middle = (low + high) / 2.0;
+                if gap(middle) > 0.0 {
+                    low = middle;
+                } else {
+                    high = middle;
+                }
+            }
+            high
+        };
+
+        // when the light from it catches up with the user
+        let approach_position = position_at(universe, entity, approach_time);
+        let mut seen_time = approach_time;
+        for _ in 0..LIGHT_DELAY_ITERATIONS {
+            seen_time = approach_time
+                + (position_at(universe, user, seen_time) - approach_position).magnitude()
+                    / universe.speed_of_light;
+        }
+
+        alerts.push(ProximityAlert {
+            entity_id,
+            time_to_closest: approach_time - now,
+            distance,
+            time_to_seen: seen_time - now,
+        });
+    }
+
+    alerts.sort_by(|a, b| a.time_to_closest.total_cmp(&b.time_to_closest));
+    alerts.truncate(MAX_PROXIMITY_ALERTS);
+    alerts
+}
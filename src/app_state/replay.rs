@@ -0,0 +1,348 @@
+use super::{physics::UniverseEdit, save_game::SaveGame};
+use crate::{
+    shared::version::APP_VERSION,
+    special::universe::{Entity, EntityId, Universe},
+};
+use anyhow::Result;
+use cgmath::{Quaternion, Vector4};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::PathBuf,
+};
+
+/// Bumped whenever a change would make older replays play back wrong.
+pub const REPLAY_VERSION: u32 = 1;
+pub const REPLAYS_DIR: &str = "replays";
+/// How much coordinate time passes between samples of where the camera was looking.
+pub const CAMERA_SAMPLE_INTERVAL: f64 = 0.1;
+pub const MIN_REPLAY_SPEED: f64 = 0.125;
+pub const MAX_REPLAY_SPEED: f64 = 16.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CameraSample {
+    /// Coordinate time.
+    pub time: f64,
+    pub rotation: Quaternion<f64>,
+}
+
+/// An entity taken out of the universe partway through a recording, so it can be hidden from then on while watching.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EntityRemoval {
+    pub entity_id: EntityId,
+    /// Coordinate time.
+    pub removed_at: f64,
+    /// When it was put back (e.g. by undoing), if it was. Coordinate time.
+    pub restored_at: Option<f64>,
+}
+
+impl EntityRemoval {
+    /// Whether the entity was gone at `time`.
+    pub fn covers(&self, time: f64) -> bool {
+        time >= self.removed_at
+            && self
+                .restored_at
+                .is_none_or(|restored_at| time < restored_at)
+    }
+}
+
+/// A recorded stretch of a run, which can be watched again from any entity's point of view.
+///
+/// Rather than a snapshot per tick, this only keeps the universe as it was when recording stopped, along with
+/// whatever was removed on the way. Worldlines never forget their keyframe events, so that's already everything
+/// needed to find where anything was at any time in between.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    /// The version of the app that recorded it, just for reference.
+    pub app_version: String,
+    /// Coordinate time.
+    pub start_time: f64,
+    /// Coordinate time.
+    pub end_time: f64,
+    /// Entities that got removed while recording are in here too, as they were right before.
+    pub universe: Universe,
+    /// In order of time.
+    pub camera: Vec<CameraSample>,
+    /// Oldest first.
+    #[serde(default)]
+    pub removals: Vec<EntityRemoval>,
+}
+
+impl Replay {
+    pub fn path(name: &str) -> PathBuf {
+        PathBuf::from(REPLAYS_DIR).join(format!("{name}.ron"))
+    }
+
+    /// The names of every replay on disk, sorted. Recorded replays are named after when they were recorded, so
+    /// the newest one is last.
+    pub fn names() -> Vec<String> {
+        let Ok(entries) = fs::read_dir(REPLAYS_DIR) else {
+            return Vec::new();
+        };
+
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "ron" {
+                    return None;
+                }
+                Some(path.file_stem()?.to_string_lossy().into_owned())
+            })
+            .collect();
+        names.sort();
+        names
+    }
+
+    pub fn load(name: &str) -> Result<Self> {
+        let replay: Self = ron::from_str(&fs::read_to_string(Self::path(name))?)?;
+        if replay.version != REPLAY_VERSION {
+            anyhow::bail!(
+                "replay is version {} (from {}), expected {}",
+                replay.version,
+                replay.app_version,
+                REPLAY_VERSION
+            );
+        }
+        Ok(replay)
+    }
+
+    pub fn save(&self, name: &str) -> Result<()> {
+        fs::create_dir_all(REPLAYS_DIR)?;
+        fs::write(Self::path(name), ron::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.end_time - self.start_time
+    }
+
+    /// Where the camera was looking at some coordinate time, blended between the nearest samples.
+    pub fn camera_rotation_at(&self, time: f64) -> Option<Quaternion<f64>> {
+        let index_after = self.camera.partition_point(|sample| sample.time <= time);
+        match (
+            index_after.checked_sub(1).map(|index| self.camera[index]),
+            self.camera.get(index_after).copied(),
+        ) {
+            (Some(before), Some(after)) => {
+                let amount = (time - before.time) / (after.time - before.time);
+                Some(before.rotation.nlerp(after.rotation, amount))
+            }
+            (Some(sample), None) | (None, Some(sample)) => Some(sample.rotation),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Keeps track of a recording in progress. The universe itself only gets copied once it's finished.
+#[derive(Debug)]
+pub struct ReplayRecorder {
+    pub name: String,
+    start_time: f64,
+    camera: Vec<CameraSample>,
+    removals: Vec<EntityRemoval>,
+    /// The entities that are still gone, as they were right before they were removed.
+    removed: BTreeMap<EntityId, Entity>,
+}
+
+impl ReplayRecorder {
+    pub fn new(name: impl Into<String>, start_time: f64) -> Self {
+        Self {
+            name: name.into(),
+            start_time,
+            camera: Vec::new(),
+            removals: Vec::new(),
+            removed: BTreeMap::new(),
+        }
+    }
+
+    pub fn record_frame(&mut self, time: f64, rotation: Quaternion<f64>) {
+        if self
+            .camera
+            .last()
+            .is_some_and(|sample| time - sample.time < CAMERA_SAMPLE_INTERVAL)
+        {
+            return;
+        }
+
+        self.camera.push(CameraSample { time, rotation });
+    }
+
+    /// Takes note of the entities in `removed` (see [`PhysicsThread::take_removal_log()`]), and of whichever ones
+    /// removed earlier are back in `universe` now.
+    ///
+    /// [`PhysicsThread::take_removal_log()`]: super::physics::PhysicsThread::take_removal_log
+    pub fn record_removals(&mut self, universe: &Universe, removed: Vec<(EntityId, Entity)>) {
+        let time = universe.time;
+        for removal in self.removals.iter_mut() {
+            if removal.restored_at.is_none() && universe.entities.contains_key(&removal.entity_id) {
+                removal.restored_at = Some(time);
+                self.removed.remove(&removal.entity_id);
+            }
+        }
+        for (entity_id, entity) in removed {
+            self.removals.push(EntityRemoval {
+                entity_id,
+                removed_at: time,
+                restored_at: None,
+            });
+            self.removed.insert(entity_id, entity);
+        }
+    }
+
+    /// Keeps what's been recorded lined up with the universe after it's rebased by `offset`.
+    pub fn rebase(&mut self, offset: Vector4<f64>) {
+        self.start_time -= offset.w;
+        for sample in self.camera.iter_mut() {
+            sample.time -= offset.w;
+        }
+        for removal in self.removals.iter_mut() {
+            removal.removed_at -= offset.w;
+            if let Some(restored_at) = &mut removal.restored_at {
+                *restored_at -= offset.w;
+            }
+        }
+        for entity in self.removed.values_mut() {
+            entity.worldline.translate(-offset);
+        }
+    }
+
+    pub fn finish(self, universe: &Universe) -> Replay {
+        let mut replay_universe = universe.clone();
+        for (entity_id, entity) in self.removed {
+            replay_universe.entities.entry(entity_id).or_insert(entity);
+        }
+        Replay {
+            version: REPLAY_VERSION,
+            app_version: APP_VERSION.to_owned(),
+            start_time: self.start_time,
+            end_time: universe.time,
+            universe: replay_universe,
+            camera: self.camera,
+            removals: self.removals,
+        }
+    }
+}
+
+/// Steps through a [`Replay`]. The live universe is set aside while watching, and put back afterwards.
+#[derive(Debug)]
+pub struct ReplayPlayer {
+    pub name: String,
+    pub replay: Replay,
+    /// Coordinate time.
+    pub time: f64,
+    pub playing: bool,
+    /// How much coordinate time passes per second.
+    pub speed: f64,
+    /// Whether the camera is left to the user rather than following the recording.
+    pub free_camera: bool,
+    /// Whose frame it's being watched from.
+    pub observer: EntityId,
+
+    /// What to go back to once playback stops.
+    pub live: SaveGame,
+    pub live_paused: bool,
+}
+
+impl ReplayPlayer {
+    pub fn new(name: impl Into<String>, replay: Replay, live: SaveGame, live_paused: bool) -> Self {
+        Self {
+            name: name.into(),
+            time: replay.start_time,
+            playing: true,
+            speed: 1.0,
+            free_camera: false,
+            observer: replay.universe.user_entity_id,
+            replay,
+
+            live,
+            live_paused,
+        }
+    }
+
+    pub fn advance(&mut self, delta: f64) {
+        if !self.playing {
+            return;
+        }
+
+        self.time += delta * self.speed;
+        if self.time >= self.replay.end_time {
+            self.time = self.replay.end_time;
+            self.playing = false;
+        }
+    }
+
+    pub fn seek(&mut self, time: f64) {
+        self.time = time.clamp(self.replay.start_time, self.replay.end_time);
+    }
+
+    /// Starts over if it's at the end.
+    pub fn toggle_playing(&mut self) {
+        if !self.playing && self.time >= self.replay.end_time {
+            self.time = self.replay.start_time;
+        }
+        self.playing = !self.playing;
+    }
+
+    /// Scales the speed by some number of doublings.
+    pub fn change_speed(&mut self, doublings: f64) {
+        self.speed = (self.speed * 2f64.powf(doublings)).clamp(MIN_REPLAY_SPEED, MAX_REPLAY_SPEED);
+    }
+
+    /// Switches to watching from the next entity along.
+    pub fn cycle_observer(&mut self) {
+        let entities = &self.replay.universe.entities;
+        self.observer = entities
+            .range(self.observer..)
+            .nth(1)
+            .or_else(|| entities.iter().next())
+            .map(|(&entity_id, _)| entity_id)
+            .unwrap_or(self.observer);
+    }
+
+    /// The edits that take whatever had been removed by now in the recording out of `universe`, the one being
+    /// shown, and put back whatever hadn't been yet (after going back). Watching from an entity that's gone switches
+    /// back to the recording's own point of view.
+    pub fn removal_edits(&mut self, universe: &Universe) -> Vec<UniverseEdit> {
+        let entity_ids: BTreeSet<EntityId> = self
+            .replay
+            .removals
+            .iter()
+            .map(|removal| removal.entity_id)
+            .collect();
+
+        let mut edits = Vec::new();
+        for entity_id in entity_ids {
+            let gone = self
+                .replay
+                .removals
+                .iter()
+                .any(|removal| removal.entity_id == entity_id && removal.covers(self.time));
+            let shown = universe.entities.contains_key(&entity_id);
+            if gone && shown {
+                edits.push(UniverseEdit::RemoveEntity(entity_id));
+            } else if !gone && !shown {
+                if let Some(entity) = self.replay.universe.entities.get(&entity_id) {
+                    edits.push(UniverseEdit::InsertEntity {
+                        entity_id,
+                        entity: Box::new(entity.clone()),
+                    });
+                }
+            }
+            if gone && self.observer == entity_id {
+                self.observer = self.replay.universe.user_entity_id;
+            }
+        }
+        edits
+    }
+
+    /// Where the recording's camera was looking, or `None` if it's not the recording's own point of view being
+    /// watched.
+    pub fn recorded_camera_rotation(&self) -> Option<Quaternion<f64>> {
+        if self.free_camera || self.observer != self.replay.universe.user_entity_id {
+            return None;
+        }
+        self.replay.camera_rotation_at(self.time)
+    }
+}
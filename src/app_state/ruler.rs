@@ -0,0 +1,124 @@
+use super::physics::UniverseEdit;
+use crate::special::{
+    prefab::PREFABS,
+    universe::{EntityId, Universe},
+};
+use cgmath::{vec3, InnerSpace, Matrix4, Quaternion, Vector3};
+
+pub const RULER_PREFAB: &str = "ruler";
+pub const RULER_TICK_PREFAB: &str = "ruler_tick";
+/// In light-seconds.
+pub const RULER_TICK_SPACING: f64 = 1.0;
+pub const MIN_RULER_LENGTH: f64 = RULER_TICK_SPACING;
+/// Past this many ticks, the rest of a ruler goes without.
+pub const MAX_RULER_TICKS: usize = 1000;
+/// Every this many ticks, there's a bigger one.
+pub const RULER_MAJOR_TICK_INTERVAL: usize = 10;
+pub const RULER_THICKNESS: f32 = 0.1;
+pub const RULER_TICK_SIZE: f32 = 0.4;
+
+/// Lays down rulers by dragging.
+#[derive(Debug, Clone, Default)]
+pub struct RulerTool {
+    pub active: bool,
+    /// Where the drag started, relative to the user in their rest frame.
+    pub start: Option<Vector3<f64>>,
+}
+
+impl RulerTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn toggle(&mut self) {
+        self.active = !self.active;
+        self.start = None;
+    }
+}
+
+/// The edits that put a ruler in the universe, and the ones that take it back out.
+#[derive(Debug, Clone)]
+pub struct PlacedRuler {
+    pub entity_id: EntityId,
+    pub length: f64,
+    pub edits: Vec<UniverseEdit>,
+    pub undo: Vec<UniverseEdit>,
+}
+
+/// Where the ticks go along a ruler of some length, measured from its start.
+pub fn ruler_tick_distances(length: f64) -> impl Iterator<Item = f64> {
+    let count = ((length / RULER_TICK_SPACING).floor() as usize + 1).min(MAX_RULER_TICKS);
+    (0..count).map(|i| i as f64 * RULER_TICK_SPACING)
+}
+
+/// A ruler from `start` to `end` (relative to the user, in their rest frame), at rest in a frame moving at `velocity`
+/// (in the universe's rest frame). The dragged-out length is its rest length, so a ruler at rest in some other frame
+/// than the user's looks contracted to them. Returns `None` if it'd be too short to have any ticks on it.
+pub fn place_ruler(
+    universe: &Universe,
+    start: Vector3<f64>,
+    end: Vector3<f64>,
+    velocity: Vector3<f64>,
+) -> Option<PlacedRuler> {
+    let length = (end - start).magnitude();
+    if length < MIN_RULER_LENGTH {
+        return None;
+    }
+    let direction = (end - start) / length;
+
+    let mut frame = universe
+        .user_event_now()
+        .comoving((start + end) / 2.0)
+        .frame;
+    frame.velocity = velocity;
+
+    let prefabs = PREFABS.read().unwrap();
+    let mut ruler = prefabs.instantiate(RULER_PREFAB, frame)?;
+    let rotation = Matrix4::from(Quaternion::from_arc(
+        vec3(1.0, 0.0, 0.0),
+        direction.cast().unwrap(),
+        None,
+    ));
+    // the cube model goes from -1 to 1
+    ruler.model_matrix = rotation
+        * Matrix4::from_nonuniform_scale(length as f32 / 2.0, RULER_THICKNESS, RULER_THICKNESS);
+
+    let entity_id = EntityId::generate();
+    let mut edits = vec![UniverseEdit::InsertEntity {
+        entity_id,
+        entity: Box::new(ruler),
+    }];
+    let mut undo = Vec::new();
+    for (i, distance) in ruler_tick_distances(length).enumerate() {
+        let Some(mut tick) = prefabs.instantiate(RULER_TICK_PREFAB, frame) else {
+            break;
+        };
+        let size = if i % RULER_MAJOR_TICK_INTERVAL == 0 {
+            RULER_TICK_SIZE * 2.0
+        } else {
+            RULER_TICK_SIZE
+        };
+        tick.model_matrix = rotation
+            * Matrix4::from_nonuniform_scale(RULER_THICKNESS / 2.0, size, RULER_THICKNESS * 1.5);
+
+        let tick_id = EntityId::generate();
+        edits.push(UniverseEdit::InsertEntity {
+            entity_id: tick_id,
+            entity: Box::new(tick),
+        });
+        edits.push(UniverseEdit::Attach {
+            entity_id: tick_id,
+            parent_id: entity_id,
+            offset: direction * (distance - length / 2.0),
+        });
+        undo.push(UniverseEdit::RemoveEntity(tick_id));
+    }
+    undo.push(UniverseEdit::RemoveEntity(entity_id));
+
+    Some(PlacedRuler {
+        entity_id,
+        length,
+        edits,
+        undo,
+    })
+}
@@ -0,0 +1,169 @@
+use super::waypoints::Waypoint;
+use crate::{
+    shared::version::APP_VERSION,
+    special::{universe::Universe, worldline::WorldlineEvent},
+};
+use anyhow::Result;
+use cgmath::Quaternion;
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf, time::SystemTime};
+
+/// Bumped whenever a change would make older saves load wrong.
+pub const SAVE_GAME_VERSION: u32 = 1;
+pub const SAVES_DIR: &str = "saves";
+pub const QUICKSAVE_SLOT: &str = "quicksave";
+/// Autosave slots are this followed by when they were made, in seconds since the Unix epoch.
+pub const AUTOSAVE_PREFIX: &str = "autosave-";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutosaveSettings {
+    /// Turning this off only stops new autosaves, old ones can still be restored.
+    pub enabled: bool,
+    /// Seconds of real time between autosaves.
+    pub interval: f64,
+    /// How many autosaves to keep around. Older ones get deleted.
+    pub retention: usize,
+}
+
+impl Default for AutosaveSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: 300.0,
+            retention: 5,
+        }
+    }
+}
+
+/// The parts of the [`PlayerController`](super::player::PlayerController) worth keeping.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerSave {
+    pub rotation: Quaternion<f64>,
+    pub acceleration: f64,
+    #[serde(default)]
+    pub bookmarks: Vec<WorldlineEvent>,
+    #[serde(default)]
+    pub waypoints: Vec<Waypoint>,
+}
+
+/// Everything needed to pick an experiment back up where it was left off.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveGame {
+    pub version: u32,
+    /// The version of the app that wrote the save, just for reference.
+    pub app_version: String,
+    pub universe: Universe,
+    pub player: PlayerSave,
+}
+
+impl SaveGame {
+    pub fn new(universe: Universe, player: PlayerSave) -> Self {
+        Self {
+            version: SAVE_GAME_VERSION,
+            app_version: APP_VERSION.to_owned(),
+            universe,
+            player,
+        }
+    }
+
+    /// Whether `slot` is fine to use as a slot name, which means it can't reach outside of [`SAVES_DIR`].
+    pub fn is_valid_slot(slot: &str) -> bool {
+        !slot.is_empty() && !slot.contains(['/', '\\']) && !slot.contains("..")
+    }
+
+    /// Where a save slot lives on disk. Slots are just file names, so keep them simple (see
+    /// [`Self::is_valid_slot()`]).
+    pub fn slot_path(slot: &str) -> PathBuf {
+        PathBuf::from(SAVES_DIR).join(format!("{slot}.ron"))
+    }
+
+    /// The names of every save slot on disk, sorted.
+    pub fn slots() -> Vec<String> {
+        let Ok(entries) = fs::read_dir(SAVES_DIR) else {
+            return Vec::new();
+        };
+
+        let mut slots: Vec<String> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "ron" {
+                    return None;
+                }
+                Some(path.file_stem()?.to_string_lossy().into_owned())
+            })
+            .collect();
+        slots.sort();
+        slots
+    }
+
+    /// A slot for an autosave made right now.
+    pub fn new_autosave_slot() -> String {
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        format!("{AUTOSAVE_PREFIX}{timestamp}")
+    }
+
+    /// Every autosave slot, oldest first.
+    pub fn autosave_slots() -> Vec<String> {
+        let mut slots: Vec<String> = Self::slots()
+            .into_iter()
+            .filter(|slot| slot.starts_with(AUTOSAVE_PREFIX))
+            .collect();
+        // sorting by the timestamp as a number keeps working if it ever gets another digit
+        slots.sort_by_key(|slot| {
+            slot[AUTOSAVE_PREFIX.len()..]
+                .parse::<u64>()
+                .unwrap_or_default()
+        });
+        slots
+    }
+
+    /// The newest autosave, and when it was written.
+    pub fn latest_autosave() -> Option<(String, SystemTime)> {
+        let slot = Self::autosave_slots().pop()?;
+        let saved_at = fs::metadata(Self::slot_path(&slot))
+            .and_then(|metadata| metadata.modified())
+            .ok()?;
+        Some((slot, saved_at))
+    }
+
+    /// Deletes all but the newest `retention` autosaves.
+    pub fn prune_autosaves(retention: usize) -> Result<()> {
+        let slots = Self::autosave_slots();
+        let excess = slots.len().saturating_sub(retention);
+        for slot in slots.iter().take(excess) {
+            fs::remove_file(Self::slot_path(slot))?;
+        }
+        Ok(())
+    }
+
+    pub fn load(slot: &str) -> Result<Self> {
+        let save_game: Self = ron::from_str(&fs::read_to_string(Self::slot_path(slot))?)?;
+        if save_game.version != SAVE_GAME_VERSION {
+            anyhow::bail!(
+                "save is version {} (from {}), expected {}",
+                save_game.version,
+                save_game.app_version,
+                SAVE_GAME_VERSION
+            );
+        }
+        Ok(save_game)
+    }
+
+    pub fn save(&self, slot: &str) -> Result<()> {
+        fs::create_dir_all(SAVES_DIR)?;
+        fs::write(Self::slot_path(slot), ron::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Saves to a new autosave slot, then deletes the oldest ones past `retention`. Returns the new slot.
+    pub fn autosave(&self, retention: usize) -> Result<String> {
+        let slot = Self::new_autosave_slot();
+        self.save(&slot)?;
+        Self::prune_autosaves(retention.max(1))?;
+        Ok(slot)
+    }
+}
@@ -0,0 +1,574 @@
+use super::post_effects::PostEffects;
+use crate::{
+    shared::{
+        interning::{ModelId, PrefabId},
+        localization::{StringTable, LOCALIZATION},
+        numerical_integration::Integrator,
+    },
+    special::{
+        inertial_frame::InertialFrame,
+        prefab::{Prefab, PrefabRegistry, PREFABS},
+        star_system::{generate_star_systems, StarSystemSettings},
+        universe::{Entity, EntityId, Universe},
+        worldline::{Orbit, Worldline, WorldlineEventKind},
+    },
+};
+use anyhow::{Context, Result};
+use cgmath::{vec3, vec4, Deg, ElementWise, Matrix4, Quaternion, Rotation3};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Used when no scenario is given, or the given one can't be loaded.
+pub const DEFAULT_SCENARIO: &str = include_str!("../scenarios/lattice.ron");
+
+/// Where something starts out. Coordinates are in the universe's rest frame, in light-seconds and seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScenarioFrame {
+    pub position: [f64; 3],
+    pub time: f64,
+    /// As a fraction of c.
+    pub velocity: [f64; 3],
+}
+
+impl Default for ScenarioFrame {
+    fn default() -> Self {
+        Self {
+            position: [0.0; 3],
+            time: 0.0,
+            velocity: [0.0; 3],
+        }
+    }
+}
+
+impl From<ScenarioFrame> for InertialFrame {
+    fn from(frame: ScenarioFrame) -> Self {
+        let [x, y, z] = frame.position;
+        Self {
+            position: vec4(x, y, z, frame.time),
+            velocity: frame.velocity.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ScenarioEventKind {
+    Inertial,
+    /// Proper acceleration, in c/s.
+    Acceleration([f64; 3]),
+    /// Orbiting a point. The angular velocity is in radians per second, around its own direction.
+    Circular {
+        center: [f64; 3],
+        angular_velocity: [f64; 3],
+    },
+}
+
+impl From<ScenarioEventKind> for WorldlineEventKind {
+    fn from(kind: ScenarioEventKind) -> Self {
+        match kind {
+            ScenarioEventKind::Inertial => Self::Inertial,
+            ScenarioEventKind::Acceleration(acceleration) => {
+                Self::Acceleration(acceleration.into())
+            }
+            ScenarioEventKind::Circular {
+                center,
+                angular_velocity,
+            } => Self::Circular(Orbit {
+                center: center.into(),
+                angular_velocity: angular_velocity.into(),
+            }),
+        }
+    }
+}
+
+/// A change in motion that's planned ahead of time.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScenarioEvent {
+    /// Coordinate time.
+    pub time: f64,
+    pub kind: ScenarioEventKind,
+    #[serde(default)]
+    pub integrator: Integrator,
+}
+
+/// Copies an entity across a grid, centered on its frame's position.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScenarioLattice {
+    pub count: [u32; 3],
+    pub spacing: [f64; 3],
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScenarioEntity {
+    /// For annotations to refer to. Entities copied across a lattice all share it.
+    pub name: Option<String>,
+    /// One of the [`PREFABS`] to start from. Its scale and color get multiplied by these ones, and `model`
+    /// replaces its model if it's set.
+    pub prefab: Option<String>,
+    pub model: Option<String>,
+    pub scale: f32,
+    pub color: [f32; 4],
+    pub frame: ScenarioFrame,
+    /// The name of an entity further up the list to ride along with. The frame's position is then an offset in
+    /// the parent's rest frame, and the rest of the frame and the events are ignored.
+    pub parent: Option<String>,
+    /// Has to be in order of time.
+    pub events: Vec<ScenarioEvent>,
+    pub lattice: Option<ScenarioLattice>,
+    /// See [`Entity::tags`].
+    pub tags: Vec<String>,
+}
+
+impl Default for ScenarioEntity {
+    fn default() -> Self {
+        Self {
+            name: None,
+            prefab: None,
+            model: None,
+            scale: 1.0,
+            color: [1.0; 4],
+            frame: ScenarioFrame::default(),
+            parent: None,
+            events: Vec::new(),
+            lattice: None,
+            tags: Vec::new(),
+        }
+    }
+}
+
+/// A text callout that follows an entity around on screen.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScenarioAnnotation {
+    /// The [`ScenarioEntity::name`] to attach to.
+    pub entity: String,
+    /// Starting it with `@` makes it a key into [`Scenario::strings`] (or the built-in string tables).
+    pub text: String,
+}
+
+/// What it takes for a tour step to show up, once the one before it is done.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum TourTrigger {
+    #[default]
+    Immediately,
+    /// Once coordinate time gets this far.
+    Time(f64),
+    /// Once the user is within this distance of the step's entity. Steps without one show up right away.
+    Near(f64),
+    /// Once the user's going at least this fast, as a portion of c.
+    Speed(f64),
+}
+
+/// One stop on a guided tour. Steps show up one at a time, in order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScenarioTourStep {
+    /// The [`ScenarioEntity::name`] to point at. Without one, the text goes in the tour panel instead.
+    pub entity: Option<String>,
+    /// Starting it with `@` makes it a key into [`Scenario::strings`] (or the built-in string tables).
+    pub text: String,
+    pub trigger: TourTrigger,
+    /// Pauses the universe while it's up, until the user moves on.
+    pub pause: bool,
+    /// How long it stays up, in real seconds, unless it pauses. The user can always move on sooner.
+    pub duration: f64,
+}
+
+impl Default for ScenarioTourStep {
+    fn default() -> Self {
+        Self {
+            entity: None,
+            text: String::new(),
+            trigger: TourTrigger::default(),
+            pause: false,
+            duration: 8.0,
+        }
+    }
+}
+
+/// A docking challenge: get close to an entity and match its velocity.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScenarioDocking {
+    /// The [`ScenarioEntity::name`] to dock with.
+    pub target: String,
+    /// How close the user has to get, in light-seconds.
+    pub distance: f64,
+    /// How slow the user has to be going relative to the target, as a portion of c.
+    pub speed: f64,
+}
+
+impl Default for ScenarioDocking {
+    fn default() -> Self {
+        Self {
+            target: String::new(),
+            distance: 4.0,
+            speed: 0.01,
+        }
+    }
+}
+
+/// Which way the camera faces at the start, in degrees.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScenarioCamera {
+    pub yaw: f64,
+    pub pitch: f64,
+}
+
+/// A Lua script to run alongside the scenario. See [`scripting`](super::scripting) for what it can do.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ScenarioScript {
+    Inline(String),
+    /// Relative to the scenario file.
+    File(PathBuf),
+}
+
+/// A user-authored starting setup for the universe, loaded from a RON file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Scenario {
+    pub name: String,
+    pub start_time: f64,
+    pub user: ScenarioEntity,
+    pub camera: ScenarioCamera,
+    pub entities: Vec<ScenarioEntity>,
+    pub annotations: Vec<ScenarioAnnotation>,
+    pub script: Option<ScenarioScript>,
+    /// Fills space with generated star systems, on top of the entities above.
+    pub star_systems: Option<StarSystemSettings>,
+    /// The scenario's own translated strings, by language code, for its annotations and script to use.
+    pub strings: BTreeMap<String, StringTable>,
+    /// See [`Universe::speed_of_light`].
+    pub speed_of_light: f64,
+    /// A guided tour to walk the user through whatever the scenario's showing off.
+    pub tour: Vec<ScenarioTourStep>,
+    pub docking: Option<ScenarioDocking>,
+    pub post_effects: PostEffects,
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Self {
+            name: "Empty".to_owned(),
+            start_time: 1000.0,
+            user: ScenarioEntity::default(),
+            camera: ScenarioCamera::default(),
+            entities: Vec::new(),
+            annotations: Vec::new(),
+            script: None,
+            star_systems: None,
+            strings: BTreeMap::new(),
+            speed_of_light: 1.0,
+            tour: Vec::new(),
+            docking: None,
+            post_effects: PostEffects::default(),
+        }
+    }
+}
+
+/// An annotation whose entity has been found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub entity_id: EntityId,
+    pub text: String,
+}
+
+/// A tour step whose entity has been found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TourStep {
+    pub entity_id: Option<EntityId>,
+    pub text: String,
+    pub trigger: TourTrigger,
+    pub pause: bool,
+    pub duration: f64,
+}
+
+/// A docking challenge whose target has been found.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DockingTarget {
+    pub entity_id: EntityId,
+    pub distance: f64,
+    pub speed: f64,
+}
+
+/// What a [`Scenario`] turns into.
+#[derive(Debug, Clone)]
+pub struct LoadedScenario {
+    pub name: String,
+    pub universe: Universe,
+    pub annotations: Vec<Annotation>,
+    pub tour: Vec<TourStep>,
+    pub docking: Option<DockingTarget>,
+    pub camera_rotation: Quaternion<f64>,
+    /// The script's source, if there is one.
+    pub script: Option<String>,
+    pub post_effects: PostEffects,
+}
+
+impl Scenario {
+    pub fn parse(source: &str) -> Result<Self> {
+        Ok(ron::from_str(source)?)
+    }
+
+    /// Also reads in the script if it's in its own file, so [`Scenario::build`] doesn't need to know where the
+    /// scenario came from.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("couldn't read {}", path.display()))?;
+        let mut scenario =
+            Self::parse(&source).with_context(|| format!("couldn't parse {}", path.display()))?;
+
+        if let Some(ScenarioScript::File(script_path)) = &scenario.script {
+            let script_path = path
+                .parent()
+                .map(|parent| parent.join(script_path))
+                .unwrap_or_else(|| script_path.clone());
+            let script = fs::read_to_string(&script_path)
+                .with_context(|| format!("couldn't read {}", script_path.display()))?;
+            scenario.script = Some(ScenarioScript::Inline(script));
+        }
+
+        Ok(scenario)
+    }
+
+    fn build_entity(
+        entity: &ScenarioEntity,
+        prefab: Option<(PrefabId, &Prefab)>,
+        offset: [f64; 3],
+    ) -> Entity {
+        let mut frame = InertialFrame::from(entity.frame);
+        frame.position += vec4(offset[0], offset[1], offset[2], 0.0);
+
+        let mut built = match prefab {
+            Some((prefab_id, prefab)) => prefab.instantiate(prefab_id, frame),
+            None => Entity {
+                worldline: Worldline::new(frame),
+                ..Default::default()
+            },
+        };
+        for event in entity.events.iter() {
+            built.worldline.insert_event_with_integrator(
+                event.time,
+                event.kind.into(),
+                event.integrator,
+            );
+        }
+
+        if let Some(model) = entity.model.as_deref() {
+            built.model = Some(ModelId::intern(model));
+        }
+        built.model_matrix = built.model_matrix * Matrix4::from_scale(entity.scale);
+        let [r, g, b, a] = entity.color;
+        built.model_color = built.model_color.mul_element_wise(vec4(r, g, b, a));
+        built.tags = entity.tags.clone();
+        built
+    }
+
+    /// Looks up a scenario entity's prefab, if it has one.
+    fn find_prefab<'a>(
+        entity: &ScenarioEntity,
+        prefabs: &'a PrefabRegistry,
+    ) -> Option<(PrefabId, &'a Prefab)> {
+        let name = entity.prefab.as_deref()?;
+        match prefabs.get(name) {
+            Some(prefab) => Some((PrefabId::intern(name), prefab)),
+            None => {
+                warn!(
+                    "Scenario refers to a prefab called '{}', which doesn't exist",
+                    name
+                );
+                None
+            }
+        }
+    }
+
+    fn lattice_offsets(lattice: Option<ScenarioLattice>) -> Vec<[f64; 3]> {
+        let Some(ScenarioLattice { count, spacing }) = lattice else {
+            return vec![[0.0; 3]];
+        };
+
+        let axis_offsets = |axis: usize| {
+            (0..count[axis])
+                .map(move |index| (index as f64 - (count[axis] as f64 - 1.0) / 2.0) * spacing[axis])
+        };
+        let mut offsets = Vec::new();
+        for x in axis_offsets(0) {
+            for y in axis_offsets(1) {
+                for z in axis_offsets(2) {
+                    offsets.push([x, y, z]);
+                }
+            }
+        }
+        offsets
+    }
+
+    pub fn build(&self) -> LoadedScenario {
+        let mut universe = Universe {
+            time: self.start_time,
+            ..Default::default()
+        };
+        universe.set_speed_of_light(self.speed_of_light);
+        let prefabs = PREFABS.read().unwrap();
+        *universe.get_user_entity_mut() = Self::build_entity(
+            &self.user,
+            Self::find_prefab(&self.user, &prefabs),
+            [0.0; 3],
+        );
+
+        let mut named_entities = BTreeMap::<&str, EntityId>::new();
+        for entity in self.entities.iter() {
+            let prefab = Self::find_prefab(entity, &prefabs);
+            let parent_id = entity.parent.as_deref().and_then(|parent| {
+                let parent_id = named_entities.get(parent).copied();
+                if parent_id.is_none() {
+                    warn!(
+                        "Scenario entity's parent is '{}', which doesn't exist (or comes after it)",
+                        parent
+                    );
+                }
+                parent_id
+            });
+
+            for offset in Self::lattice_offsets(entity.lattice) {
+                let built = Self::build_entity(entity, prefab, offset);
+                let frame = built.worldline.get_event_at_time(universe.time).frame;
+                let entity_id = universe.insert_entity(built);
+                if let Some((prefab_id, _)) = prefab {
+                    for (part_id, part) in
+                        prefabs.instantiate_parts(prefab_id.name(), entity_id, frame)
+                    {
+                        universe.insert_entity_with_id(part_id, part);
+                    }
+                }
+                if let Some(parent_id) = parent_id {
+                    let [x, y, z] = entity.frame.position;
+                    universe.attach(
+                        entity_id,
+                        parent_id,
+                        vec3(x + offset[0], y + offset[1], z + offset[2]),
+                    );
+                }
+                if let Some(name) = entity.name.as_deref() {
+                    // annotations go on the first copy
+                    named_entities.entry(name).or_insert(entity_id);
+                }
+            }
+        }
+
+        drop(prefabs);
+        {
+            let mut localization = LOCALIZATION.write().unwrap();
+            for (language, table) in self.strings.iter() {
+                localization.add_strings(language, table.clone());
+            }
+        }
+        if let Some(settings) = &self.star_systems {
+            generate_star_systems(&mut universe, settings);
+        }
+
+        let annotations = self
+            .annotations
+            .iter()
+            .filter_map(
+                |annotation| match named_entities.get(annotation.entity.as_str()) {
+                    Some(&entity_id) => Some(Annotation {
+                        entity_id,
+                        text: annotation.text.clone(),
+                    }),
+                    None => {
+                        warn!(
+                        "Scenario annotation refers to an entity named '{}', which doesn't exist",
+                        annotation.entity
+                    );
+                        None
+                    }
+                },
+            )
+            .collect();
+
+        let tour = self
+            .tour
+            .iter()
+            .map(|step| TourStep {
+                // a missing entity just leaves the text in the tour panel
+                entity_id: step.entity.as_deref().and_then(|name| {
+                    let entity_id = named_entities.get(name).copied();
+                    if entity_id.is_none() {
+                        warn!(
+                            "Scenario tour step refers to an entity named '{}', which doesn't exist",
+                            name
+                        );
+                    }
+                    entity_id
+                }),
+                text: step.text.clone(),
+                trigger: step.trigger,
+                pause: step.pause,
+                duration: step.duration,
+            })
+            .collect();
+
+        let docking = self.docking.as_ref().and_then(|docking| {
+            let Some(&entity_id) = named_entities.get(docking.target.as_str()) else {
+                warn!(
+                    "Scenario docking target is an entity named '{}', which doesn't exist",
+                    docking.target
+                );
+                return None;
+            };
+            Some(DockingTarget {
+                entity_id,
+                distance: docking.distance,
+                speed: docking.speed,
+            })
+        });
+
+        let camera_rotation = Quaternion::from_angle_y(Deg(self.camera.yaw))
+            * Quaternion::from_angle_x(Deg(self.camera.pitch));
+
+        LoadedScenario {
+            name: self.name.clone(),
+            universe,
+            annotations,
+            tour,
+            docking,
+            camera_rotation,
+            script: match &self.script {
+                Some(ScenarioScript::Inline(source)) => Some(source.clone()),
+                Some(ScenarioScript::File(path)) => {
+                    warn!(
+                        "Scenario script {} wasn't loaded, so it won't run",
+                        path.display()
+                    );
+                    None
+                }
+                None => None,
+            },
+            post_effects: self.post_effects,
+        }
+    }
+}
+
+/// Loads the scenario at `path`, falling back to [`DEFAULT_SCENARIO`] if there isn't one or it's broken.
+pub fn load_scenario_or_default(path: Option<&Path>) -> Scenario {
+    load_scenario_or(path, DEFAULT_SCENARIO)
+}
+
+/// Like [`load_scenario_or_default()`], with some other built-in scenario's source to fall back to.
+pub fn load_scenario_or(path: Option<&Path>, fallback: &str) -> Scenario {
+    if let Some(path) = path {
+        match Scenario::load(path) {
+            Ok(scenario) => return scenario,
+            Err(error) => warn!("{:#}, using the built-in scenario", error),
+        }
+    }
+
+    Scenario::parse(fallback).expect("built-in scenarios should be valid")
+}
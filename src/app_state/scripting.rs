@@ -0,0 +1,541 @@
+use super::{scenario::Annotation, signals::SignalDelivery};
+use crate::{
+    shared::{action::Action, interning::ModelId, localization::translate},
+    special::{
+        inertial_frame::InertialFrame,
+        prefab::PREFABS,
+        universe::{Entity, EntityId, Universe},
+        worldline::{Orbit, Worldline, WorldlineEventKind},
+    },
+};
+use anyhow::Result;
+use cgmath::{vec3, vec4, Matrix4, Vector3};
+use log::warn;
+use mlua::{FromLuaMulti, Function, IntoLuaMulti, Lua, LuaOptions, StdLib, Table, Value};
+use std::{cell::RefCell, fmt::Display};
+
+/// Something a script asked for, which gets carried out once the hook returns.
+#[derive(Debug, Clone)]
+pub enum ScriptCommand {
+    Spawn {
+        entity_id: EntityId,
+        entity: Box<Entity>,
+    },
+    InsertEvent {
+        entity_id: EntityId,
+        coord_time: f64,
+        kind: WorldlineEventKind,
+    },
+    Attach {
+        entity_id: EntityId,
+        parent_id: EntityId,
+        offset: Vector3<f64>,
+    },
+    Detach(EntityId),
+    Annotate(Annotation),
+    Signal {
+        sender: EntityId,
+        receiver: Option<EntityId>,
+        channel: String,
+        message: String,
+    },
+    SetPaused(bool),
+    Toast(String),
+}
+
+/// When a script's global functions get called, if it has them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptHook {
+    /// `on_start()`, once the scenario has loaded.
+    Start,
+    /// `on_tick(delta)`, every frame, with the frame time in seconds.
+    Tick(f64),
+    /// `on_action(name)`, whenever an [`Action`] is pressed, e.g. `"QuickSave"`.
+    Action(Action),
+    /// `on_signal(receiver, channel, message, sender, sent_at, arrived_at)`, whenever a signal sent with
+    /// `world.signal()` reaches one of its receivers, with the coordinate times it was sent and got there.
+    Signal(SignalDelivery),
+}
+
+impl ScriptHook {
+    pub fn function_name(&self) -> &'static str {
+        match self {
+            Self::Start => "on_start",
+            Self::Tick(_) => "on_tick",
+            Self::Action(_) => "on_action",
+            Self::Signal(_) => "on_signal",
+        }
+    }
+}
+
+fn parse_entity_id(id: String) -> mlua::Result<EntityId> {
+    EntityId::try_from(id.clone())
+        .map_err(|_| mlua::Error::RuntimeError(format!("'{id}' isn't an entity ID")))
+}
+
+fn vector_field(table: &Table, key: &str, default: Vector3<f64>) -> mlua::Result<Vector3<f64>> {
+    Ok(match table.get::<_, Option<Vec<f64>>>(key)? {
+        Some(components) => {
+            let component = |index: usize| components.get(index).copied().unwrap_or_default();
+            vec3(component(0), component(1), component(2))
+        }
+        None => default,
+    })
+}
+
+fn frame_table<'lua>(
+    lua: &'lua Lua,
+    frame: InertialFrame,
+    proper_time: f64,
+) -> mlua::Result<Table<'lua>> {
+    let table = lua.create_table()?;
+    table.set("x", frame.position.x)?;
+    table.set("y", frame.position.y)?;
+    table.set("z", frame.position.z)?;
+    table.set("t", frame.position.w)?;
+    table.set("vx", frame.velocity.x)?;
+    table.set("vy", frame.velocity.y)?;
+    table.set("vz", frame.velocity.z)?;
+    table.set("proper_time", proper_time)?;
+    Ok(table)
+}
+
+fn find_function<'lua>(lua: &'lua Lua, path: &[&str]) -> mlua::Result<Option<Function<'lua>>> {
+    let Some((name, tables)) = path.split_last() else {
+        return Ok(None);
+    };
+    let mut table = lua.globals();
+    for &key in tables {
+        match table.get::<_, Option<Table>>(key)? {
+            Some(inner) => table = inner,
+            None => return Ok(None),
+        }
+    }
+    table.get(*name)
+}
+
+/// A scenario's script, in Lua. Once a hook fails, the script stops running so the same error doesn't get logged every
+/// frame.
+pub struct ScriptEngine {
+    lua: Lua,
+    name: String,
+    failed: bool,
+}
+
+impl std::fmt::Debug for ScriptEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScriptEngine")
+            .field("name", &self.name)
+            .field("failed", &self.failed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ScriptEngine {
+    /// Runs the top level of the script, which is where it should define its hooks.
+    pub fn new(name: impl Into<String>, source: &str) -> Result<Self> {
+        Self::with_prelude(name, "", source)
+    }
+
+    /// Like [`ScriptEngine::new()`], but runs `prelude` first, so it can set up globals for the script to use.
+    pub fn with_prelude(name: impl Into<String>, prelude: &str, source: &str) -> Result<Self> {
+        let name = name.into();
+        let lua = Self::sandbox()?;
+        lua.load(prelude).set_name("prelude").exec()?;
+        lua.load(source).set_name(name.as_str()).exec()?;
+
+        Ok(Self {
+            lua,
+            name,
+            failed: false,
+        })
+    }
+
+    /// A Lua state without anything that reaches outside of it, since scenarios (and plugins) get passed around and
+    /// shouldn't be able to touch files or run programs. `world` and `gui` cover everything they need.
+    fn sandbox() -> Result<Lua> {
+        let lua = Lua::new_with(
+            StdLib::TABLE | StdLib::STRING | StdLib::MATH | StdLib::UTF8,
+            LuaOptions::default(),
+        )?;
+        // the base library always comes along, and these can read files or run code from outside the script
+        let globals = lua.globals();
+        for name in ["dofile", "loadfile", "load", "require"] {
+            globals.set(name, Value::Nil)?;
+        }
+        drop(globals);
+        Ok(lua)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn has_failed(&self) -> bool {
+        self.failed
+    }
+
+    pub fn lua(&self) -> &Lua {
+        &self.lua
+    }
+
+    /// Calls the script's function for `hook`, if it has one, and returns what it asked for.
+    pub fn run_hook(&mut self, hook: &ScriptHook, universe: &Universe) -> Vec<ScriptCommand> {
+        let path = [hook.function_name()];
+        let (_, commands) = match hook {
+            ScriptHook::Start => self.call::<_, ()>(&path, (), universe),
+            ScriptHook::Tick(delta) => self.call::<_, ()>(&path, *delta, universe),
+            ScriptHook::Action(action) => {
+                self.call::<_, ()>(&path, format!("{action:?}"), universe)
+            }
+            ScriptHook::Signal(delivery) => self.call::<_, ()>(
+                &path,
+                (
+                    String::from(delivery.receiver),
+                    delivery.channel.clone(),
+                    delivery.message.clone(),
+                    String::from(delivery.sender),
+                    delivery.sent_at,
+                    delivery.arrived_at,
+                ),
+                universe,
+            ),
+        };
+        commands
+    }
+
+    /// Calls the function at `path` (e.g. `["plugin", "panels", "clock"]`, going through tables from the globals),
+    /// with `world` and `gui` available just like in a hook. Returns what it returned, unless there's no such
+    /// function or it failed, and what it asked for. Nothing it changes shows up in `world`'s queries until it
+    /// returns.
+    pub fn call<A, R>(
+        &mut self,
+        path: &[&str],
+        args: A,
+        universe: &Universe,
+    ) -> (Option<R>, Vec<ScriptCommand>)
+    where
+        A: for<'lua> IntoLuaMulti<'lua>,
+        R: for<'lua> FromLuaMulti<'lua>,
+    {
+        if self.failed {
+            return (None, Vec::new());
+        }
+
+        let function = match find_function(&self.lua, path) {
+            Ok(Some(function)) => function,
+            Ok(None) => return (None, Vec::new()),
+            Err(error) => {
+                warn!(
+                    "Script {} has a broken {}: {}",
+                    self.name,
+                    path.join("."),
+                    error
+                );
+                self.failed = true;
+                return (None, Vec::new());
+            }
+        };
+
+        let commands = RefCell::new(Vec::new());
+        let push = |command| commands.borrow_mut().push(command);
+        let result = self.lua.scope(|scope| {
+            let lua = &self.lua;
+
+            let world = lua.create_table()?;
+            // the current coordinate time
+            world.set(
+                "time",
+                scope.create_function(|_, ()| Ok(universe.scenario_time(universe.time)))?,
+            )?;
+            // entity IDs are passed around as strings
+            world.set(
+                "user",
+                scope.create_function(|_, ()| Ok(String::from(universe.user_entity_id)))?,
+            )?;
+            world.set(
+                "entities",
+                scope.create_function(|_, ()| {
+                    Ok(universe
+                        .entities
+                        .keys()
+                        .map(|&entity_id| String::from(entity_id))
+                        .collect::<Vec<_>>())
+                })?,
+            )?;
+            // `(id, [time])`: where an entity is at some coordinate time (now by default), as a table with `x`, `y`,
+            // `z`, `t`, `vx`, `vy`, `vz` and `proper_time`, or `nil` if there's no such entity
+            world.set(
+                "frame",
+                scope.create_function(|lua, (id, time): (String, Option<f64>)| {
+                    let entity_id = parse_entity_id(id)?;
+                    let Some(entity) = universe.entities.get(&entity_id) else {
+                        return Ok(None);
+                    };
+                    let coord_time =
+                        time.map_or(universe.time, |time| universe.from_scenario_time(time));
+                    let mut event = universe.entity_event_at_time(entity, coord_time);
+                    // scripts keep working in the scenario's coordinates, wherever the origin's moved to
+                    event.frame.position += universe.scenario_origin();
+                    frame_table(lua, event.frame, event.proper_time).map(Some)
+                })?,
+            )?;
+            // `({ model, position, velocity, scale, color })`, with every field optional and vectors as plain lists
+            // like `{ 0, 0, 10 }`. Returns the new entity's ID
+            world.set(
+                "spawn",
+                scope.create_function(|_, options: Option<Table>| {
+                    let mut entity = Entity::default();
+                    if let Some(options) = options {
+                        let position =
+                            vector_field(&options, "position", Vector3::new(0.0, 0.0, 0.0))?;
+                        let velocity =
+                            vector_field(&options, "velocity", Vector3::new(0.0, 0.0, 0.0))?;
+                        entity.worldline = Worldline::new(InertialFrame {
+                            position: (position - universe.origin).extend(universe.time),
+                            velocity,
+                        });
+                        entity.model = options
+                            .get::<_, Option<String>>("model")?
+                            .map(|model| ModelId::intern(&model));
+                        if let Some(scale) = options.get::<_, Option<f32>>("scale")? {
+                            entity.model_matrix = Matrix4::from_scale(scale);
+                        }
+                        if let Some(color) = options.get::<_, Option<Vec<f32>>>("color")? {
+                            let channel = |index: usize| color.get(index).copied().unwrap_or(1.0);
+                            entity.model_color =
+                                vec4(channel(0), channel(1), channel(2), channel(3));
+                        }
+                    }
+
+                    let entity_id = EntityId::generate();
+                    push(ScriptCommand::Spawn {
+                        entity_id,
+                        entity: Box::new(entity),
+                    });
+                    Ok(String::from(entity_id))
+                })?,
+            )?;
+            // `(name, { position, velocity })`: returns the new entity's ID, or `nil` if there's no prefab by that name
+            world.set(
+                "spawn_prefab",
+                scope.create_function(|_, (name, options): (String, Option<Table>)| {
+                    let (position, velocity) = match options {
+                        Some(options) => (
+                            vector_field(&options, "position", Vector3::new(0.0, 0.0, 0.0))?,
+                            vector_field(&options, "velocity", Vector3::new(0.0, 0.0, 0.0))?,
+                        ),
+                        None => (Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 0.0)),
+                    };
+                    let frame = InertialFrame {
+                        position: (position - universe.origin).extend(universe.time),
+                        velocity,
+                    };
+                    let prefabs = PREFABS.read().unwrap();
+                    let Some(entity) = prefabs.instantiate(&name, frame) else {
+                        return Ok(None);
+                    };
+
+                    let entity_id = EntityId::generate();
+                    push(ScriptCommand::Spawn {
+                        entity_id,
+                        entity: Box::new(entity),
+                    });
+                    for (part_id, part) in prefabs.instantiate_parts(&name, entity_id, frame) {
+                        push(ScriptCommand::Spawn {
+                            entity_id: part_id,
+                            entity: Box::new(part),
+                        });
+                    }
+                    Ok(Some(String::from(entity_id)))
+                })?,
+            )?;
+            // `(id, time, center, angular_velocity)`: starts going around `center`, at `angular_velocity` radians per
+            // second around its own direction, which the entity's velocity should then be perpendicular to
+            world.set(
+                "orbit",
+                scope.create_function(
+                    |_,
+                     (id, coord_time, center, angular_velocity): (
+                        String,
+                        f64,
+                        Vec<f64>,
+                        Vec<f64>,
+                    )| {
+                        let vector = |components: Vec<f64>| {
+                            let component =
+                                |index: usize| components.get(index).copied().unwrap_or_default();
+                            vec3(component(0), component(1), component(2))
+                        };
+                        push(ScriptCommand::InsertEvent {
+                            entity_id: parse_entity_id(id)?,
+                            coord_time: universe.from_scenario_time(coord_time),
+                            kind: WorldlineEventKind::Circular(Orbit {
+                                center: vector(center) - universe.origin,
+                                angular_velocity: vector(angular_velocity),
+                            }),
+                        });
+                        Ok(())
+                    },
+                )?,
+            )?;
+            // `(id, time, [acceleration])`: starts accelerating, or coasting without an acceleration
+            world.set(
+                "insert_event",
+                scope.create_function(
+                    |_, (id, coord_time, acceleration): (String, f64, Option<Vec<f64>>)| {
+                        let kind = match acceleration {
+                            Some(components) => {
+                                let component = |index: usize| {
+                                    components.get(index).copied().unwrap_or_default()
+                                };
+                                WorldlineEventKind::Acceleration(vec3(
+                                    component(0),
+                                    component(1),
+                                    component(2),
+                                ))
+                            }
+                            None => WorldlineEventKind::Inertial,
+                        };
+                        push(ScriptCommand::InsertEvent {
+                            entity_id: parse_entity_id(id)?,
+                            coord_time: universe.from_scenario_time(coord_time),
+                            kind,
+                        });
+                        Ok(())
+                    },
+                )?,
+            )?;
+            // `(id, parent, [offset])`: `offset` is in the parent's rest frame, and defaults to where it is right now
+            world.set(
+                "attach",
+                scope.create_function(
+                    |_, (id, parent, offset): (String, String, Option<Vec<f64>>)| {
+                        let entity_id = parse_entity_id(id)?;
+                        let parent_id = parse_entity_id(parent)?;
+                        let offset = match offset {
+                            Some(components) => {
+                                let component = |index: usize| {
+                                    components.get(index).copied().unwrap_or_default()
+                                };
+                                vec3(component(0), component(1), component(2))
+                            }
+                            None => {
+                                let (Some(event), Some(parent_event)) = (
+                                    universe.get_event_at_time(entity_id, universe.time),
+                                    universe.get_event_at_time(parent_id, universe.time),
+                                ) else {
+                                    return Ok(());
+                                };
+                                event
+                                    .frame
+                                    .relative_to(parent_event.frame)
+                                    .position
+                                    .truncate()
+                            }
+                        };
+                        push(ScriptCommand::Attach {
+                            entity_id,
+                            parent_id,
+                            offset,
+                        });
+                        Ok(())
+                    },
+                )?,
+            )?;
+            world.set(
+                "detach",
+                scope.create_function(|_, id: String| {
+                    push(ScriptCommand::Detach(parse_entity_id(id)?));
+                    Ok(())
+                })?,
+            )?;
+            world.set(
+                "annotate",
+                scope.create_function(|_, (id, text): (String, String)| {
+                    push(ScriptCommand::Annotate(Annotation {
+                        entity_id: parse_entity_id(id)?,
+                        text,
+                    }));
+                    Ok(())
+                })?,
+            )?;
+            // `(sender, channel, message, [receiver])`: sent at the speed of light from wherever `sender` is now, to
+            // `receiver` or else everything else there is right now (see [`signals`](super::signals))
+            world.set(
+                "signal",
+                scope.create_function(
+                    |_,
+                     (sender, channel, message, receiver): (
+                        String,
+                        String,
+                        String,
+                        Option<String>,
+                    )| {
+                        push(ScriptCommand::Signal {
+                            sender: parse_entity_id(sender)?,
+                            receiver: receiver.map(parse_entity_id).transpose()?,
+                            channel,
+                            message,
+                        });
+                        Ok(())
+                    },
+                )?,
+            )?;
+            world.set(
+                "pause",
+                scope.create_function(|_, paused: Option<bool>| {
+                    push(ScriptCommand::SetPaused(paused.unwrap_or(true)));
+                    Ok(())
+                })?,
+            )?;
+
+            let gui = lua.create_table()?;
+            gui.set(
+                "toast",
+                scope.create_function(|_, text: String| {
+                    push(ScriptCommand::Toast(text));
+                    Ok(())
+                })?,
+            )?;
+            // `(key, [args])`: a string in the current language, with each `{name}` in it filled in from `args`
+            gui.set(
+                "tr",
+                scope.create_function(|_, (key, args): (String, Option<Table>)| {
+                    let args = match args {
+                        Some(args) => args
+                            .pairs::<String, String>()
+                            .collect::<mlua::Result<Vec<_>>>()?,
+                        None => Vec::new(),
+                    };
+                    let args: Vec<(&str, &dyn Display)> = args
+                        .iter()
+                        .map(|(name, value)| (name.as_str(), value as &dyn Display))
+                        .collect();
+                    Ok(translate(&key, &args))
+                })?,
+            )?;
+
+            lua.globals().set("world", world)?;
+            lua.globals().set("gui", gui)?;
+
+            function.call::<_, R>(args)
+        });
+
+        let returned = match result {
+            Ok(returned) => Some(returned),
+            Err(error) => {
+                warn!(
+                    "Script {} failed in {}: {}",
+                    self.name,
+                    path.join("."),
+                    error
+                );
+                self.failed = true;
+                None
+            }
+        };
+
+        (returned, commands.into_inner())
+    }
+}
@@ -0,0 +1,259 @@
+use super::{maneuver::ManeuverNode, physics::UniverseEdit, player::plan_burn};
+use crate::{
+    shared::bounding_box::BBox2,
+    special::{
+        prefab::{PrefabRegistry, PART_TAG},
+        universe::{Entity, EntityId, Universe},
+        worldline::WorldlineEventKind,
+    },
+};
+use cgmath::{Vector2, Vector3, Vector4};
+use std::collections::{BTreeSet, HashMap};
+
+/// Past this many, a box select stops picking up more entities.
+pub const MAX_GROUP_SIZE: usize = 4096;
+
+/// Entities selected along with the selected one (by Ctrl+clicking or dragging a box), so they can all be edited at
+/// once. The selected entity itself isn't in here, it's the most recently picked one.
+#[derive(Debug, Clone, Default)]
+pub struct EntityGroup {
+    pub entity_ids: BTreeSet<EntityId>,
+    /// Where a box select started on screen, from 0 to 1, while one's being dragged out.
+    pub box_start: Option<Vector2<f32>>,
+}
+
+impl EntityGroup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.entity_ids.clear();
+        self.box_start = None;
+    }
+
+    /// Adds `entity_id` to the selection, making it the selected one, or takes it out if it was already in. Returns
+    /// what's selected now.
+    pub fn toggle(&mut self, selected: Option<EntityId>, entity_id: EntityId) -> Option<EntityId> {
+        if selected == Some(entity_id) {
+            return self.entity_ids.pop_last();
+        }
+        if self.entity_ids.remove(&entity_id) {
+            return selected;
+        }
+        if let Some(selected) = selected {
+            self.entity_ids.insert(selected);
+        }
+        Some(entity_id)
+    }
+
+    /// Adds everything in `entity_ids` to the selection. Returns what's selected now.
+    pub fn extend(
+        &mut self,
+        selected: Option<EntityId>,
+        entity_ids: impl IntoIterator<Item = EntityId>,
+    ) -> Option<EntityId> {
+        let mut selected = selected;
+        for entity_id in entity_ids {
+            if self.entity_ids.len() >= MAX_GROUP_SIZE {
+                break;
+            }
+            match selected {
+                Some(selected) if selected != entity_id => {
+                    self.entity_ids.insert(entity_id);
+                }
+                Some(_) => {}
+                None => selected = Some(entity_id),
+            }
+        }
+        selected
+    }
+
+    /// Everything selected, including the selected entity, in no particular order.
+    pub fn with_selected(&self, selected: Option<EntityId>) -> Vec<EntityId> {
+        let mut entity_ids: Vec<EntityId> = self.entity_ids.iter().copied().collect();
+        entity_ids.extend(selected.filter(|entity_id| !self.entity_ids.contains(entity_id)));
+        entity_ids
+    }
+
+    /// The box being dragged out so far, on screen from 0 to 1.
+    pub fn selection_box(&self, cursor: Vector2<f32>) -> Option<BBox2> {
+        let start = self.box_start?;
+        Some(BBox2::new([[start.x, start.y], [cursor.x, cursor.y]]))
+    }
+}
+
+/// Whether `entity` came from a [`PART_TAG`]ged prefab, so it only makes sense along with what it's attached to.
+pub fn is_part(prefabs: &PrefabRegistry, entity: &Entity) -> bool {
+    entity
+        .prefab
+        .and_then(|prefab| prefabs.get(prefab.name()))
+        .is_some_and(|prefab| prefab.has_tag(PART_TAG))
+}
+
+/// `entity_ids`, with every part swapped for whatever it's part of.
+pub fn part_roots(
+    universe: &Universe,
+    prefabs: &PrefabRegistry,
+    entity_ids: impl IntoIterator<Item = EntityId>,
+) -> BTreeSet<EntityId> {
+    let mut root_ids = BTreeSet::new();
+    for mut entity_id in entity_ids {
+        let Some(mut entity) = universe.entities.get(&entity_id) else {
+            continue;
+        };
+        while is_part(prefabs, entity) {
+            let Some(parent) = entity
+                .attachment
+                .and_then(|attachment| universe.entities.get_key_value(&attachment.parent))
+            else {
+                break;
+            };
+            (entity_id, entity) = (*parent.0, parent.1);
+        }
+        root_ids.insert(entity_id);
+    }
+    root_ids
+}
+
+/// The edits that make a change to a group of entities, and the ones that take it back.
+#[derive(Debug, Clone, Default)]
+pub struct GroupEdit {
+    /// How many entities it changes, which might be fewer than were selected.
+    pub count: usize,
+    pub edits: Vec<UniverseEdit>,
+    pub undo: Vec<UniverseEdit>,
+}
+
+impl GroupEdit {
+    fn push(&mut self, edit: UniverseEdit, undo: UniverseEdit) {
+        self.count += 1;
+        self.edits.push(edit);
+        self.undo.push(undo);
+    }
+}
+
+/// Gives every entity in `entity_ids` the same color.
+pub fn recolor(universe: &Universe, entity_ids: &[EntityId], color: Vector4<f32>) -> GroupEdit {
+    let mut group_edit = GroupEdit::default();
+    for &entity_id in entity_ids {
+        let Some(entity) = universe.entities.get(&entity_id) else {
+            continue;
+        };
+        group_edit.push(
+            UniverseEdit::SetColor { entity_id, color },
+            UniverseEdit::SetColor {
+                entity_id,
+                color: entity.model_color,
+            },
+        );
+    }
+    group_edit
+}
+
+/// Adds `tag` to every entity in `entity_ids` that doesn't have it yet.
+pub fn add_tag(universe: &Universe, entity_ids: &[EntityId], tag: &str) -> GroupEdit {
+    let mut group_edit = GroupEdit::default();
+    for &entity_id in entity_ids {
+        let Some(entity) = universe.entities.get(&entity_id) else {
+            continue;
+        };
+        if entity.has_tag(tag) {
+            continue;
+        }
+        let mut tags = entity.tags.clone();
+        tags.push(tag.to_owned());
+        group_edit.push(
+            UniverseEdit::SetTags { entity_id, tags },
+            UniverseEdit::SetTags {
+                entity_id,
+                tags: entity.tags.clone(),
+            },
+        );
+    }
+    group_edit
+}
+
+/// Moves every entity in `entity_ids` `offset` over in space, their whole worldlines included. Attached entities
+/// are left out, since they go wherever they're attached to.
+pub fn offset(universe: &Universe, entity_ids: &[EntityId], offset: Vector3<f64>) -> GroupEdit {
+    let mut group_edit = GroupEdit::default();
+    for &entity_id in entity_ids {
+        let Some(entity) = universe.entities.get(&entity_id) else {
+            continue;
+        };
+        if entity.attachment.is_some() {
+            continue;
+        }
+        group_edit.push(
+            UniverseEdit::Translate { entity_id, offset },
+            UniverseEdit::Translate {
+                entity_id,
+                offset: -offset,
+            },
+        );
+    }
+    group_edit
+}
+
+/// Has every entity in `entity_ids` make the same burn as `node`: starting at the same coordinate time, with the same
+/// proper acceleration, for the same amount of its own proper time. Attached entities are left out, the same as
+/// [`offset()`].
+pub fn share_maneuver(
+    universe: &Universe,
+    entity_ids: &[EntityId],
+    node: ManeuverNode,
+) -> GroupEdit {
+    let mut group_edit = GroupEdit::default();
+    // how long a burn takes only depends on the velocity it starts from and how it's stepped through, and a group
+    // that's flying together shares those, so each one's only worked out once
+    let mut durations = HashMap::new();
+    for &entity_id in entity_ids {
+        let Some(entity) = universe.entities.get(&entity_id) else {
+            continue;
+        };
+        if entity.attachment.is_some() || entity_id == universe.user_entity_id {
+            continue;
+        }
+        let start = entity.worldline.get_event_at_time(node.coord_time);
+        let velocity = start.frame.velocity;
+        let key = (
+            [
+                velocity.x,
+                velocity.y,
+                velocity.z,
+                entity.worldline.time_resolution,
+            ]
+            .map(f64::to_bits),
+            start.integrator,
+        );
+        let duration = *durations.entry(key).or_insert_with(|| {
+            plan_burn(
+                &start,
+                node.acceleration,
+                node.proper_time,
+                entity.worldline.time_resolution,
+            )
+            .1
+        });
+        group_edit.count += 1;
+        group_edit.edits.extend([
+            UniverseEdit::InsertEvent {
+                entity_id,
+                coord_time: node.coord_time,
+                kind: WorldlineEventKind::Acceleration(node.acceleration),
+            },
+            UniverseEdit::InsertEvent {
+                entity_id,
+                coord_time: node.coord_time + duration,
+                kind: WorldlineEventKind::Inertial,
+            },
+        ]);
+        // there's no taking an event back out, so the whole entity goes back the way it was
+        group_edit.undo.push(UniverseEdit::InsertEntity {
+            entity_id,
+            entity: Box::new(entity.clone()),
+        });
+    }
+    group_edit
+}
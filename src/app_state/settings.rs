@@ -0,0 +1,200 @@
+use super::{
+    frame_pacing::FramePacingSettings,
+    motion_blur::DEFAULT_SHUTTER_ANGLE,
+    save_game::AutosaveSettings,
+    stereo::{StereoMode, DEFAULT_EYE_SEPARATION},
+};
+use crate::{
+    audio::SoundSettings,
+    graphics::graphics_controller::{DepthMode, PipelineVariant},
+    gui::hud::HudLayout,
+    shared::{action::ActionBindings, gamepad::GamepadSettings, input::KeyRepeatSettings},
+};
+use anyhow::Result;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+
+pub const SETTINGS_PATH: &str = "settings.ron";
+pub const MIN_RENDER_SCALE: f32 = 0.25;
+pub const MAX_RENDER_SCALE: f32 = 2.0;
+
+/// Parts of 3D rendering that can be switched on and off, to see what the scene looks like without them (or how fast
+/// it draws).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShaderFeatures {
+    /// Red and blue shift from things moving towards or away from the camera.
+    pub doppler: bool,
+    /// Lighting that depends on which way surfaces face.
+    pub shading: bool,
+    pub wireframe: bool,
+    /// Draws everything opaque into the depth buffer first, so only what ends up on top gets shaded. Pays off with
+    /// lots of overlapping entities.
+    pub depth_prepass: bool,
+    /// Depth that's just as precise far away as it is close by, so distant things don't flicker through each other.
+    pub log_depth: bool,
+    /// Glare and ghosts around bright sources, like stars or anything coming towards the camera fast enough to be
+    /// beamed bright.
+    pub lens_flare: bool,
+}
+
+impl Default for ShaderFeatures {
+    fn default() -> Self {
+        Self {
+            doppler: true,
+            shading: true,
+            wireframe: false,
+            depth_prepass: false,
+            log_depth: false,
+            lens_flare: true,
+        }
+    }
+}
+
+impl ShaderFeatures {
+    pub const NAMES: [&'static str; 6] = [
+        "doppler",
+        "shading",
+        "wireframe",
+        "prepass",
+        "log_depth",
+        "lens_flare",
+    ];
+
+    /// The flag called `name` in the `/shader` command.
+    pub fn flag_mut(&mut self, name: &str) -> Option<&mut bool> {
+        match name {
+            "doppler" => Some(&mut self.doppler),
+            "shading" => Some(&mut self.shading),
+            "wireframe" => Some(&mut self.wireframe),
+            "prepass" => Some(&mut self.depth_prepass),
+            "log_depth" => Some(&mut self.log_depth),
+            "lens_flare" => Some(&mut self.lens_flare),
+            _ => None,
+        }
+    }
+
+    /// What `main_3d.wgsl` gets preprocessed with.
+    pub fn defines(self) -> Vec<&'static str> {
+        let mut defines = Vec::new();
+        if self.doppler {
+            defines.push("DOPPLER");
+        }
+        if self.shading {
+            defines.push("SHADING");
+        }
+        if self.log_depth {
+            defines.push("LOG_DEPTH");
+        }
+        defines
+    }
+
+    pub fn pipeline_variant(self, depth_mode: DepthMode) -> PipelineVariant {
+        PipelineVariant {
+            shader_defines: self.defines(),
+            wireframe: self.wireframe,
+            depth_mode,
+        }
+    }
+
+    /// Every variant of the 3D pipeline that drawing with these takes.
+    pub fn pipeline_variants(self) -> Vec<PipelineVariant> {
+        let depth_modes: &[DepthMode] = if self.depth_prepass {
+            &[DepthMode::Prepass, DepthMode::Equal, DepthMode::Normal]
+        } else {
+            &[DepthMode::Normal]
+        };
+        depth_modes
+            .iter()
+            .map(|&depth_mode| self.pipeline_variant(depth_mode))
+            .collect()
+    }
+}
+
+/// User preferences that stick around between sessions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Multiplier for HUD margins and other GUI measurements.
+    pub gui_scale: f32,
+    pub hud_layout: HudLayout,
+    pub bindings: ActionBindings,
+    pub gamepad: GamepadSettings,
+    pub key_repeat: KeyRepeatSettings,
+    /// Language code, e.g. `"de"`. Empty means whatever the system is set to.
+    pub language: String,
+    pub autosave: AutosaveSettings,
+    /// Skips easing the view over when the user's frame of reference jumps, for purists.
+    pub instant_frame_changes: bool,
+    pub stereo_mode: StereoMode,
+    /// In light-seconds.
+    pub eye_separation: f64,
+    pub shader_features: ShaderFeatures,
+    pub frame_pacing: FramePacingSettings,
+    /// The 3D view's resolution as a fraction of the window's, to trade sharpness for speed (or the other way around).
+    /// The GUI always draws at the window's own resolution.
+    pub render_scale: f32,
+    /// Smears things out along how fast they're moving across the screen, which only really shows up during close
+    /// flybys. Off in stereo and photo mode.
+    pub motion_blur: bool,
+    /// In degrees. See [`DEFAULT_SHUTTER_ANGLE`].
+    pub shutter_angle: f32,
+    pub sound: SoundSettings,
+
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            gui_scale: 1.0,
+            hud_layout: HudLayout::default(),
+            bindings: ActionBindings::default(),
+            gamepad: GamepadSettings::default(),
+            key_repeat: KeyRepeatSettings::default(),
+            language: String::new(),
+            autosave: AutosaveSettings::default(),
+            instant_frame_changes: false,
+            stereo_mode: StereoMode::default(),
+            eye_separation: DEFAULT_EYE_SEPARATION,
+            shader_features: ShaderFeatures::default(),
+            frame_pacing: FramePacingSettings::default(),
+            render_scale: 1.0,
+            motion_blur: false,
+            shutter_angle: DEFAULT_SHUTTER_ANGLE,
+            sound: SoundSettings::default(),
+
+            path: SETTINGS_PATH.into(),
+        }
+    }
+}
+
+impl Settings {
+    /// Loads the settings file at `path`, falling back to the defaults if it doesn't exist or is broken.
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+
+        let mut settings = match fs::read_to_string(&path) {
+            Ok(contents) => ron::from_str::<Self>(&contents).unwrap_or_else(|error| {
+                warn!(
+                    "Couldn't parse {}, using defaults: {}",
+                    path.display(),
+                    error
+                );
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        };
+        settings.path = path;
+
+        settings
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        fs::write(&self.path, contents)?;
+        Ok(())
+    }
+}
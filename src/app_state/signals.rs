@@ -0,0 +1,140 @@
+use crate::special::universe::{EntityId, Universe};
+use cgmath::{InnerSpace, Vector4};
+
+/// How many times the moment a signal reached someone gets halved down once it's known to have.
+pub const ARRIVAL_ITERATIONS: usize = 48;
+
+/// A message going out from an entity at the speed of light, which only gets to each receiver once its light cone
+/// does. Scripts send these with `world.signal()` (see [`scripting`](super::scripting)).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signal {
+    pub sender: EntityId,
+    /// Something for receivers to tell what kind of message it is by, e.g. `"turn_around"`.
+    pub channel: String,
+    pub message: String,
+    /// Where and when it was sent from, in the scenario's coordinates (so unaffected by
+    /// [`Universe::rebase()`]).
+    pub origin: Vector4<f64>,
+    /// Everyone it hasn't gotten to yet.
+    pub receivers: Vec<EntityId>,
+}
+
+/// A signal getting to one of its receivers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignalDelivery {
+    pub receiver: EntityId,
+    pub sender: EntityId,
+    pub channel: String,
+    pub message: String,
+    /// The coordinate time it was sent at, in the scenario's coordinates like [`Signal::origin`].
+    pub sent_at: f64,
+    /// The coordinate time its light cone reached the receiver (also in the scenario's coordinates), which is usually a little before it's delivered,
+    /// since that only happens once per frame.
+    pub arrived_at: f64,
+}
+
+/// Every signal that's still on its way to someone.
+#[derive(Debug, Clone, Default)]
+pub struct SignalNetwork {
+    pub in_flight: Vec<Signal>,
+}
+
+/// Where an entity is at some coordinate time, in the scenario's coordinates.
+fn scenario_position(
+    universe: &Universe,
+    entity_id: EntityId,
+    coord_time: f64,
+) -> Option<Vector4<f64>> {
+    let entity = universe.entities.get(&entity_id)?;
+    let event = universe.entity_event_at_time(entity, coord_time);
+    Some(event.frame.position + universe.scenario_origin())
+}
+
+impl SignalNetwork {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.in_flight.clear();
+    }
+
+    /// Sends a signal from wherever `sender` is right now, to `receiver`, or to every other entity there is right
+    /// now without one. Does nothing if there's no such sender.
+    pub fn send(
+        &mut self,
+        universe: &Universe,
+        sender: EntityId,
+        receiver: Option<EntityId>,
+        channel: String,
+        message: String,
+    ) {
+        let Some(origin) = scenario_position(universe, sender, universe.time) else {
+            return;
+        };
+        let receivers = match receiver {
+            Some(receiver) => vec![receiver],
+            None => universe
+                .entities
+                .keys()
+                .copied()
+                .filter(|&entity_id| entity_id != sender)
+                .collect(),
+        };
+        self.in_flight.push(Signal {
+            sender,
+            channel,
+            message,
+            origin,
+            receivers,
+        });
+    }
+
+    /// Takes out every delivery whose light cone has reached its receiver by now, in the order they arrived.
+    /// Receivers that have been removed are dropped along the way.
+    pub fn update(&mut self, universe: &Universe) -> Vec<SignalDelivery> {
+        let mut deliveries = Vec::new();
+        for signal in self.in_flight.iter_mut() {
+            // how far inside its light cone a receiver is, which only grows as long as it's slower than light
+            let lead = |receiver: EntityId, coord_time: f64| {
+                scenario_position(universe, receiver, coord_time).map(|position| {
+                    let elapsed = position.w - signal.origin.w;
+                    elapsed - (position - signal.origin).truncate().magnitude()
+                })
+            };
+
+            signal.receivers.retain(|&receiver| {
+                let Some(lead_now) = lead(receiver, universe.time) else {
+                    return false;
+                };
+                if lead_now < 0.0 {
+                    return true;
+                }
+
+                let (mut low, mut high) =
+                    (universe.from_scenario_time(signal.origin.w), universe.time);
+                for _ in 0..ARRIVAL_ITERATIONS {
+                    let middle = (low + high) / 2.0;
+                    if lead(receiver, middle).unwrap_or_default() < 0.0 {
+                        low = middle;
+                    } else {
+                        high = middle;
+                    }
+                }
+                deliveries.push(SignalDelivery {
+                    receiver,
+                    sender: signal.sender,
+                    channel: signal.channel.clone(),
+                    message: signal.message.clone(),
+                    sent_at: signal.origin.w,
+                    arrived_at: universe.scenario_time(high),
+                });
+                false
+            });
+        }
+        self.in_flight.retain(|signal| !signal.receivers.is_empty());
+
+        deliveries.sort_by(|a, b| a.arrived_at.total_cmp(&b.arrived_at));
+        deliveries
+    }
+}
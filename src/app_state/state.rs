@@ -1,58 +1,706 @@
 use crate::{
+    audio::AudioSystem,
+    cli::Cli,
     graphics::{
-        camera::Camera,
+        camera::{camera_relative_model_matrix, Camera},
         graphics_controller::{
-            BindedTexture, GpuHandle, GpuVec, GraphicsController, Pipeline, PipelineBuffers,
-            PipelineDescriptor, RenderTarget,
+            BindedTexture, DepthMode, GpuHandle, GpuVec, GraphicsController, Pipeline,
+            PipelineBuffers, PipelineDescriptor, PipelineVariant, PipelineVariants, RenderTarget,
         },
+        ktx2::Ktx2Image,
         model::{Model, MODEL_DATA},
-        texture::{self, OrientedSection, Texture, TEXTURE_IMAGES},
-        vertex::{EntityInstance, Vertex2D, Vertex3D},
+        texture::{self, OrientedSection, Texture, KTX2_IMAGES, TEXTURE_IMAGES},
+        vertex::{EntityInstance, FlareInstance, Vertex2D, Vertex3D},
     },
     gui::{
+        builder::GuiBuilder,
         color::GuiColor,
-        component::menu::RootComponent,
-        element::GuiContext,
-        text::{StyledText, TextBackgroundType, TextLabel},
+        component::{
+            chat_box::ChatBox,
+            lobby::LobbyPanel,
+            menu::RootComponent,
+            message_log::{MessageLink, MessageLog},
+            radial_menu::RadialMenu,
+            replay_scrubber::ReplayScrubber,
+            session_restore::{SessionRestoreChoice, SessionRestorePrompt},
+            slider::Slider,
+            spawn_palette::SpawnPalette,
+            timeline::TimelineScrubber,
+        },
+        cursor::CursorStyle,
+        element::{GuiContext, WHITE_TEXTURE},
+        graph::Graph,
+        hud::{HudAnchor, HudLayoutEditor, HudPlacement},
+        progress_bar::ProgressBar,
+        sound::{GuiSound, GuiSoundTheme},
+        text::{StyledText, TextBackgroundType, TextLabel, TextOverflow},
+        texture_frame::TextureFrame,
         transform::{GuiTransform, UDim2},
     },
+    net::{
+        client::{NetClient, NetEvent},
+        protocol::{ClientMessage, ServerMessage},
+        server::{spawn_server, Server},
+    },
+    shared::{
+        action::Action,
+        bounding_box::{bbox, BBox3F64},
+        indexed_container::IndexedVertices,
+        input::{InputController, ModifierKey},
+        input_recording::{InputPlayer, InputRecorder, InputRecording},
+    },
     shared::{
-        indexed_container::{IndexedContainer, IndexedVertices},
-        input::InputController,
+        crash::update_crash_context,
+        determinism::FIXED_FRAME_TIME,
+        interning::{ModelId, TextureId},
+        localization::{localize, system_language, tr, FALLBACK_LANGUAGE, LOCALIZATION},
+        tasks::TaskPool,
     },
     special::{
         inertial_frame::InertialFrame,
+        prefab::PREFABS,
+        spatial_index::SpatialIndex,
         transform::{lorentz_boost, lorentz_factor},
-        universe::{Entity, Universe},
-        worldline::{Worldline, PHYS_TIME_STEP},
+        universe::{EntityId, Universe},
+        worldline::{WorldlineEvent, WorldlineEventKind},
     },
 };
 use crate::{
     graphics::{
         camera::CameraUniform,
-        graphics_controller::BindedBuffer,
         packing::{PackResult, PackedSection, Packer},
+        post_effects::{
+            PostEffectsUniform, LUMINANCE_TARGET_FORMAT, LUMINANCE_TARGET_NAME,
+            POST_EFFECTS_TARGET_NAME,
+        },
+        uniforms::{UniformPool, UniformSlot},
+    },
+    shared::{
+        performance_counter::{PerformanceCounter, PerformanceReport, RenderStats},
+        profiler::{profile_scope, Profiler, PROFILE_PATH},
     },
-    shared::performance_counter::{PerformanceCounter, PerformanceReport},
 };
 use anyhow::Result;
-use cgmath::{vec2, vec3, vec4, InnerSpace, Matrix4, Vector4};
+use cgmath::{vec2, vec3, vec4, InnerSpace, Matrix4, Rotation, Vector2, Vector3, Vector4};
 use linear_map::LinearMap;
-use log::{debug, warn};
-use obj::{IndexTuple, SimplePolygon};
-use rand::Rng;
+use log::{debug, info, warn};
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use std::{
-    collections::BTreeMap,
-    sync::Arc,
-    time::{Duration, Instant},
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    mem,
+    net::SocketAddr,
+    path::PathBuf,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
 };
 use winit::{
-    event::{DeviceEvent, WindowEvent},
-    window::Window,
+    dpi::PhysicalSize,
+    event::{DeviceEvent, MouseButton, WindowEvent},
+    window::{Window, WindowId},
+};
+
+use super::{
+    angular_size::{apparent_angular_size, simultaneous_frame, sphere_angular_size, AngularSize},
+    auxiliary_view::AuxiliaryView,
+    benchmark::{Benchmark, BenchmarkInfo, BENCHMARK_SCENARIO},
+    causality::{CausalGraph, MAX_CAUSAL_EVENTS},
+    clipboard::EntityClipboard,
+    debug_overlay::{input_name, DebugPage},
+    docking::DockingChallenge,
+    event_ordering::{EventOrdering, IntervalKind, MarkedEvent, MAX_OBSERVER_SPEED},
+    frame_pacing::{FrameLimit, FramePacer},
+    frame_transition::FrameTransition,
+    lens_flare::{
+        apparent_brightness, flare_pipeline_variant, FlareSource, LIGHT_SOURCE_TAG, MAX_FLARES,
+        MIN_FLARE_BRIGHTNESS,
+    },
+    loading::{LoadedAsset, StartupLoader},
+    maneuver::{ManeuverNode, ManeuverPlanner, MANEUVER_TRAIL_COLOR},
+    motion_blur::{
+        MotionHistory, BLURRED_TARGET_NAME, MAX_SHUTTER_ANGLE, VELOCITY_TARGET_FORMAT,
+        VELOCITY_TARGET_NAME,
+    },
+    observers::{ObserverEnsemble, MAX_PINNED_OBSERVERS},
+    orbit_camera::OrbitCamera,
+    photo_mode::{PhotoFilter, PhotoMode},
+    physics::{PhysicsThread, UniverseEdit},
+    player::{PlayerController, QuickAction, GAMMA_HISTORY_DURATION, MAX_ACCELERATION},
+    plugins::{load_plugins, Plugin, PLUGINS_PATH},
+    post_effects::PostEffects,
+    proximity::{ProximityMonitor, MAX_PROXIMITY_LOOKAHEAD},
+    replay::{Replay, ReplayPlayer, ReplayRecorder},
+    ruler::{place_ruler, ruler_tick_distances, RulerTool},
+    save_game::{SaveGame, QUICKSAVE_SLOT},
+    scenario::{Annotation, LoadedScenario, DEFAULT_SCENARIO},
+    scripting::{ScriptCommand, ScriptEngine, ScriptHook},
+    selection::{self, is_part, part_roots, EntityGroup},
+    settings::{Settings, ShaderFeatures, MAX_RENDER_SCALE, MIN_RENDER_SCALE},
+    signals::SignalNetwork,
+    stereo::{eye_cameras, StereoMode, MAX_EYE_SEPARATION, STEREO_TARGET_NAMES},
+    tour::GuidedTour,
+    trajectory::{ExportFormat, TrajectoryExport, DEFAULT_EXPORT_CADENCE},
+    undo::{UndoEntry, UndoStack},
+    waypoints::{travel_proper_time, Waypoint, WaypointTarget, MAX_WAYPOINTS},
 };
+#[cfg(feature = "gamepad")]
+use crate::shared::gamepad::GamepadPoller;
+
+/// Every HUD widget's name, default placement and size.
+pub fn hud_widgets() -> [(&'static str, HudPlacement, UDim2); 15] {
+    [
+        (
+            "debug",
+            HudPlacement::new(HudAnchor::TopLeft, vec2(0.0, 0.0)),
+            UDim2::new(0.5, (0.0, 176.0)),
+        ),
+        (
+            "messages",
+            HudPlacement::new(HudAnchor::BottomLeft, vec2(8.0, 80.0)),
+            UDim2::new(0.5, 0.5),
+        ),
+        (
+            "timeline",
+            HudPlacement::new(HudAnchor::Bottom, vec2(0.0, 8.0)),
+            UDim2::new((1.0, -16.0), (0.0, 64.0)),
+        ),
+        (
+            "palette",
+            HudPlacement::new(HudAnchor::Right, vec2(8.0, 0.0)),
+            UDim2::new((0.0, 160.0), (0.0, 200.0)),
+        ),
+        (
+            "lobby",
+            HudPlacement::new(HudAnchor::TopRight, vec2(8.0, 8.0)),
+            UDim2::new((0.0, 280.0), (0.0, 160.0)),
+        ),
+        (
+            "plugins",
+            HudPlacement::new(HudAnchor::Left, vec2(8.0, 0.0)),
+            UDim2::new((0.0, 280.0), (0.0, 240.0)),
+        ),
+        (
+            "session",
+            HudPlacement::new(HudAnchor::Top, vec2(0.0, 8.0)),
+            UDim2::new((0.0, 360.0), (0.0, 64.0)),
+        ),
+        (
+            "observers",
+            HudPlacement::new(HudAnchor::BottomRight, vec2(8.0, 8.0)),
+            UDim2::new((0.0, 440.0), (0.0, 96.0)),
+        ),
+        (
+            "tour",
+            HudPlacement::new(HudAnchor::Top, vec2(0.0, 80.0)),
+            UDim2::new((0.0, 560.0), (0.0, 100.0)),
+        ),
+        (
+            "ordering",
+            HudPlacement::new(HudAnchor::TopRight, vec2(8.0, 176.0)),
+            UDim2::new((0.0, 320.0), (0.0, 344.0)),
+        ),
+        (
+            "proximity",
+            HudPlacement::new(HudAnchor::Top, vec2(0.0, 188.0)),
+            UDim2::new((0.0, 440.0), (0.0, 100.0)),
+        ),
+        (
+            "docking",
+            HudPlacement::new(HudAnchor::Bottom, vec2(0.0, 80.0)),
+            UDim2::new((0.0, 480.0), (0.0, 80.0)),
+        ),
+        (
+            "angular",
+            HudPlacement::new(HudAnchor::BottomRight, vec2(8.0, 112.0)),
+            UDim2::new((0.0, 360.0), (0.0, 80.0)),
+        ),
+        (
+            "gamma",
+            HudPlacement::new(HudAnchor::BottomRight, vec2(8.0, 200.0)),
+            UDim2::new((0.0, 280.0), (0.0, 72.0)),
+        ),
+        (
+            "causal",
+            HudPlacement::new(HudAnchor::TopLeft, vec2(8.0, 184.0)),
+            UDim2::new((0.0, 320.0), (0.0, 400.0)),
+        ),
+    ]
+}
+
+/// How far in front of the camera things get spawned, in the user's frame.
+pub const SPAWN_DISTANCE: f64 = 30.0;
+/// How far away entities can be picked by clicking on them.
+pub const MAX_PICK_DISTANCE: f64 = 15000.0;
+
+/// How far the user can get from the origin (in light-seconds, or seconds of coordinate time) before it's moved over
+/// to them.
+pub const ORIGIN_REBASE_DISTANCE: f64 = 65536.0;
+/// The origin only ever moves by whole multiples of this, which take nothing off positions and times but the top
+/// bits.
+pub const ORIGIN_REBASE_GRANULARITY: f64 = 4096.0;
+/// How see-through the preview of what's about to be spawned is.
+pub const SPAWN_PREVIEW_ALPHA: f32 = 0.4;
+
+/// How far below the message log the chat box goes.
+pub const CHAT_BOX_GAP: f32 = 4.0;
+pub const CHAT_BOX_HEIGHT: f32 = 20.0;
+
+/// How far pointers' arrows stay from the edges of the screen when what it's pointing at is off screen, as a portion
+/// of the screen.
+pub const POINTER_ARROW_MARGIN: f32 = 0.05;
+
+/// How big each dot in a pinned observer's trail is, in pixels.
+pub const OBSERVER_TRAIL_DOT_SIZE: f32 = 3.0;
+/// How big the marker over each entity in the selection group is, in pixels.
+pub const GROUP_MARKER_SIZE: f32 = 6.0;
+/// The least the Lorentz factor sparkline goes up to above 1, so tiny wobbles at low speeds don't fill all of it.
+pub const GAMMA_GRAPH_MIN_SPAN: f64 = 0.01;
+/// How big plugins' icons are next to their panels, in pixels.
+pub const PLUGIN_ICON_SIZE: f32 = 32.0;
+
+/// How often textures that nothing's using anymore get thrown out of the atlas, in real seconds. They'd be evicted
+/// anyway once the room's needed, but this keeps the atlas from sitting on them.
+pub const TEXTURE_EVICTION_INTERVAL: f64 = 60.0;
+
+/// Set from the command line, and acted on once the scenario's loaded.
+#[derive(Debug, Clone)]
+pub enum MultiplayerLaunch {
+    /// Host a server, then join it.
+    Host {
+        address: SocketAddr,
+        max_players: Option<usize>,
+    },
+    Connect(String),
+}
+
+/// The quick action menu's options, in the current language.
+fn quick_action_options() -> Vec<(QuickAction, StyledText)> {
+    QuickAction::ALL
+        .into_iter()
+        .map(|action| (action, StyledText::from_format_string(&action.label())))
+        .collect()
+}
+
+/// Switches to `language`, or the system's language if it's empty. Anything without a string table ends up as
+/// [`FALLBACK_LANGUAGE`].
+fn set_language(language: &str) {
+    let language = match language {
+        "" => system_language().unwrap_or_default(),
+        language => language.to_owned(),
+    };
+    let mut localization = LOCALIZATION.write().unwrap();
+    if !localization.set_language(&language) {
+        if !language.is_empty() {
+            info!(
+                "No strings for language '{}', using '{}'",
+                language, FALLBACK_LANGUAGE
+            );
+        }
+        localization.set_language(FALLBACK_LANGUAGE);
+    }
+}
+
+/// Where something following `frame_at` (an inertial frame for each coordinate time) looks like it is from
+/// `user_frame` at `render_time`, after the light delay at the shown speed of light.
+fn light_delayed_frame(
+    universe: &Universe,
+    render_time: f64,
+    user_frame: InertialFrame,
+    frame_at: impl Fn(f64) -> InertialFrame,
+) -> InertialFrame {
+    // use newton's method for finding the event whose delay matches the expected delay given its distance
+    let mut estimated_frame = frame_at(render_time);
+    let mut prev_offset: Option<f64> = None;
+    let mut prev_change: Option<f64> = None;
+    for _ in 0..30 {
+        let relative_frame = universe
+            .shown_frame(estimated_frame)
+            .relative_to(universe.shown_frame(user_frame));
+        let relative_gamma = lorentz_factor(relative_frame.velocity);
+        let travel_time = (estimated_frame.position - user_frame.position)
+            .truncate()
+            .magnitude()
+            / universe.speed_of_light;
+        let timeline_delay = render_time - estimated_frame.position.w;
+        let offset = timeline_delay - travel_time;
+
+        let change = if let (Some(prev_offset), Some(prev_change)) = (prev_offset, prev_change) {
+            let derivative = (prev_offset - offset) / prev_change;
+
+            offset / derivative
+        } else {
+            offset / relative_gamma
+        };
+
+        prev_offset = Some(offset);
+        prev_change = Some(change);
+
+        if offset.abs() < 0.001 {
+            break;
+        }
+
+        estimated_frame = frame_at(estimated_frame.position.w + change);
+    }
+    estimated_frame
+}
+
+/// A text callout over an entity, drawn wherever the entity's bounds are on screen.
+fn render_entity_callout(
+    builder: &mut GuiBuilder,
+    camera: &Camera,
+    aspect_ratio: f32,
+    bounds: BBox3F64,
+    text: &str,
+) {
+    let screen_point = camera.world_to_screen_point(aspect_ratio, bounds.to_f32().center().into());
+    if screen_point.z <= 0.0 {
+        // behind the camera
+        return;
+    }
+
+    let frame = builder.context.frame;
+    builder.element(TextLabel {
+        transform: GuiTransform {
+            anchor_point: vec2(0.5, 1.0),
+            ..GuiTransform::from_absolute(
+                vec2(screen_point.x * frame.x, screen_point.y * frame.y),
+                vec2(256.0, 16.0),
+            )
+        },
+        text: StyledText::from_format_string(text),
+        char_pixel_height: 16.0,
+        text_alignment: TextLabel::ALIGN_BOTTOM_CENTER,
+        background_color: GuiColor::BLACK.with_alpha(0.5),
+        background_type: TextBackgroundType::BoundingBoxPerLine,
+        ..Default::default()
+    });
+}
+
+/// Points at something with an arrow under some text. If it's off screen (or behind the camera), the arrow sits at the
+/// edge of the screen instead, pointing the way to turn.
+fn render_pointer(
+    builder: &mut GuiBuilder,
+    camera: &Camera,
+    aspect_ratio: f32,
+    point: Vector3<f32>,
+    text: &str,
+) {
+    let frame = builder.context.frame;
+    let screen_point = camera.world_to_screen_point(aspect_ratio, point);
+    let on_screen = screen_point.z > 0.0
+        && (0.0..=1.0).contains(&screen_point.x)
+        && (0.0..=1.0).contains(&screen_point.y);
+
+    let (position, text, alignment) = if on_screen {
+        (
+            vec2(screen_point.x, screen_point.y),
+            format!("{text}\n§e▼"),
+            TextLabel::ALIGN_BOTTOM_CENTER,
+        )
+    } else {
+        // behind the camera, the projection comes out mirrored
+        let mut direction = vec2(screen_point.x - 0.5, screen_point.y - 0.5);
+        if screen_point.z <= 0.0 {
+            direction = -direction;
+        }
+        let largest = direction.x.abs().max(direction.y.abs()).max(f32::EPSILON);
+        let position = vec2(0.5, 0.5) + direction * ((0.5 - POINTER_ARROW_MARGIN) / largest);
+
+        let (text, alignment) = if direction.x.abs() >= direction.y.abs() {
+            if direction.x > 0.0 {
+                (format!("{text} §e►"), TextLabel::ALIGN_MIDDLE_RIGHT)
+            } else {
+                (format!("§e◄ §r{text}"), TextLabel::ALIGN_MIDDLE_LEFT)
+            }
+        } else if direction.y > 0.0 {
+            (format!("{text}\n§e▼"), TextLabel::ALIGN_BOTTOM_CENTER)
+        } else {
+            (format!("§e▲\n§r{text}"), TextLabel::ALIGN_TOP_CENTER)
+        };
+        (position, text, alignment)
+    };
+
+    let line_count = text.lines().count() as f32;
+    builder.element(TextLabel {
+        transform: GuiTransform {
+            anchor_point: alignment,
+            ..GuiTransform::from_absolute(
+                vec2(position.x * frame.x, position.y * frame.y),
+                vec2(640.0, 20.0 * line_count),
+            )
+        },
+        text: StyledText::from_format_string(&text),
+        char_pixel_height: 16.0,
+        text_alignment: alignment,
+        background_color: GuiColor::BLACK.with_alpha(0.5),
+        background_type: TextBackgroundType::BoundingBoxPerLine,
+        ..Default::default()
+    });
+}
+
+/// Draws a trail as a line of dots, fading out towards the oldest end.
+fn render_observer_trail(
+    builder: &mut GuiBuilder,
+    camera: &Camera,
+    aspect_ratio: f32,
+    points: &[Vector3<f32>],
+    color: GuiColor,
+) {
+    let frame = builder.context.frame;
+    let white = builder.context.white();
+    for (i, &point) in points.iter().enumerate() {
+        let screen_point = camera.world_to_screen_point(aspect_ratio, point);
+        if screen_point.z <= 0.0
+            || !(0.0..=1.0).contains(&screen_point.x)
+            || !(0.0..=1.0).contains(&screen_point.y)
+        {
+            continue;
+        }
+
+        builder.element(TextureFrame {
+            transform: GuiTransform {
+                anchor_point: vec2(0.5, 0.5),
+                ..GuiTransform::from_absolute(
+                    vec2(screen_point.x * frame.x, screen_point.y * frame.y),
+                    vec2(OBSERVER_TRAIL_DOT_SIZE, OBSERVER_TRAIL_DOT_SIZE),
+                )
+            },
+            color: color.with_alpha((i + 1) as f32 / points.len() as f32),
+            section: white,
+        });
+    }
+}
+
+/// Where the ticks of the ruler being dragged out would go, and how long it is so far.
+fn render_ruler_preview(
+    builder: &mut GuiBuilder,
+    camera: &Camera,
+    aspect_ratio: f32,
+    start: Vector3<f64>,
+    end: Vector3<f64>,
+) {
+    let length = (end - start).magnitude();
+    let direction = (end - start) / length.max(f64::EPSILON);
+    let points: Vec<Vector3<f32>> = ruler_tick_distances(length)
+        .map(|distance| (start + direction * distance).cast().unwrap())
+        .collect();
+    let frame = builder.context.frame;
+    let white = builder.context.white();
+    for point in points {
+        let screen_point = camera.world_to_screen_point(aspect_ratio, point);
+        if screen_point.z <= 0.0 {
+            continue;
+        }
+        builder.element(TextureFrame {
+            transform: GuiTransform {
+                anchor_point: vec2(0.5, 0.5),
+                ..GuiTransform::from_absolute(
+                    vec2(screen_point.x * frame.x, screen_point.y * frame.y),
+                    vec2(OBSERVER_TRAIL_DOT_SIZE, OBSERVER_TRAIL_DOT_SIZE),
+                )
+            },
+            color: GuiColor::YELLOW,
+            section: white,
+        });
+    }
+
+    let screen_point = camera.world_to_screen_point(aspect_ratio, end.cast().unwrap());
+    if screen_point.z <= 0.0 {
+        return;
+    }
+    let text = tr!("ruler.length", length = format!("{length:.1}"));
+    builder.element(TextLabel {
+        transform: GuiTransform {
+            anchor_point: TextLabel::ALIGN_BOTTOM_LEFT,
+            ..GuiTransform::from_absolute(
+                vec2(
+                    screen_point.x * frame.x + 8.0,
+                    screen_point.y * frame.y - 8.0,
+                ),
+                vec2(320.0, 20.0),
+            )
+        },
+        text: StyledText::from_format_string(&text),
+        char_pixel_height: 16.0,
+        text_alignment: TextLabel::ALIGN_BOTTOM_LEFT,
+        background_color: GuiColor::BLACK.with_alpha(0.5),
+        background_type: TextBackgroundType::BoundingBoxPerLine,
+        ..Default::default()
+    });
+}
+
+/// Colors for the first and second marked events, along with their format codes.
+pub const MARKED_EVENT_COLORS: [(GuiColor, char); 2] =
+    [(GuiColor::AQUA, 'b'), (GuiColor::GOLD, '6')];
+pub const MINKOWSKI_DIAGRAM_SAMPLES: usize = 48;
+
+/// A spacetime diagram of the marked events as the observer sees them, with the first one in the middle and space
+/// going across. Along with the first event's light cone, there's the hyperbola the second one slides along as the
+/// observer's speed changes, since that's every point with the same interval.
+fn render_minkowski_diagram(
+    builder: &mut GuiBuilder,
+    position: Vector2<f32>,
+    size: f32,
+    ordering: &EventOrdering,
+) {
+    let (Some(separation), Some(interval2)) = (
+        ordering.separation_in_frame(ordering.observer_speed),
+        ordering.interval2(),
+    ) else {
+        return;
+    };
+    let white = builder.context.white();
+    let extent = (separation.x.abs().max(separation.y.abs()) * 1.25).max(f64::EPSILON);
+    let center = position + vec2(size, size) / 2.0;
+    let to_screen =
+        |x: f64, t: f64| center + vec2((x / extent) as f32, -(t / extent) as f32) * size / 2.0;
+    let dot = |builder: &mut GuiBuilder, point: Vector2<f32>, dot_size: f32, color: GuiColor| {
+        builder.element(TextureFrame {
+            transform: GuiTransform {
+                anchor_point: vec2(0.5, 0.5),
+                ..GuiTransform::from_absolute(point, vec2(dot_size, dot_size))
+            },
+            color,
+            section: white,
+        });
+    };
+
+    builder.element(TextureFrame {
+        transform: GuiTransform::from_absolute(position, vec2(size, size)),
+        color: GuiColor::BLACK.with_alpha(0.75),
+        section: white,
+    });
+    // the observer's worldline and their line of simultaneity
+    builder.element(TextureFrame {
+        transform: GuiTransform::from_absolute(vec2(position.x, center.y - 0.5), vec2(size, 1.0)),
+        color: GuiColor::DARK_GRAY,
+        section: white,
+    });
+    builder.element(TextureFrame {
+        transform: GuiTransform::from_absolute(vec2(center.x - 0.5, position.y), vec2(1.0, size)),
+        color: GuiColor::DARK_GRAY,
+        section: white,
+    });
+
+    for i in 0..MINKOWSKI_DIAGRAM_SAMPLES {
+        let along = (i as f64 / (MINKOWSKI_DIAGRAM_SAMPLES - 1) as f64 * 2.0 - 1.0) * extent;
+        for point in [to_screen(along, along), to_screen(along, -along)] {
+            dot(builder, point, 2.0, GuiColor::YELLOW.with_alpha(0.5));
+        }
+    }
+
+    // lightlike separations sit right on the light cone already
+    let scale = interval2.abs().sqrt();
+    if scale > f64::EPSILON {
+        let max_rapidity = (extent / scale).max(1.0).acosh();
+        for i in 0..MINKOWSKI_DIAGRAM_SAMPLES {
+            let rapidity =
+                (i as f64 / (MINKOWSKI_DIAGRAM_SAMPLES - 1) as f64 * 2.0 - 1.0) * max_rapidity;
+            let (x, t) = if interval2 > 0.0 {
+                (
+                    scale * rapidity.sinh(),
+                    scale * rapidity.cosh() * separation.y.signum(),
+                )
+            } else {
+                (
+                    scale * rapidity.cosh() * separation.x.signum(),
+                    scale * rapidity.sinh(),
+                )
+            };
+            dot(
+                builder,
+                to_screen(x, t),
+                2.0,
+                GuiColor::LIGHT_PURPLE.with_alpha(0.75),
+            );
+        }
+    }
+
+    let [(first_color, _), (second_color, _)] = MARKED_EVENT_COLORS;
+    dot(builder, to_screen(0.0, 0.0), 6.0, first_color);
+    dot(
+        builder,
+        to_screen(separation.x, separation.y),
+        6.0,
+        second_color,
+    );
+}
+
+/// How far apart the dots along each edge of the causal graph are, in pixels.
+pub const CAUSAL_EDGE_SPACING: f32 = 4.0;
+
+/// The causal graph as rows of events, with causes below their effects the way time goes up in a spacetime diagram.
+/// Each event sits one row above the highest of its causes, and the dotted lines join each event to the ones it's a
+/// direct cause of.
+fn render_causal_graph(
+    builder: &mut GuiBuilder,
+    position: Vector2<f32>,
+    size: Vector2<f32>,
+    graph: &CausalGraph,
+) {
+    if graph.events.is_empty() || size.y <= 0.0 {
+        return;
+    }
+    let white = builder.context.white();
+    let dot = |builder: &mut GuiBuilder, point: Vector2<f32>, dot_size: f32, color: GuiColor| {
+        builder.element(TextureFrame {
+            transform: GuiTransform {
+                anchor_point: vec2(0.5, 0.5),
+                ..GuiTransform::from_absolute(point, vec2(dot_size, dot_size))
+            },
+            color,
+            section: white,
+        });
+    };
+
+    builder.element(TextureFrame {
+        transform: GuiTransform::from_absolute(position, size),
+        color: GuiColor::BLACK.with_alpha(0.75),
+        section: white,
+    });
+
+    let depths = graph.depths();
+    let rows = depths.iter().max().map_or(1, |&deepest| deepest + 1);
+    let mut points = vec![vec2(0.0, 0.0); depths.len()];
+    for row in 0..rows {
+        let in_row = (0..depths.len())
+            .filter(|&index| depths[index] == row)
+            .collect::<Vec<_>>();
+        for (column, &index) in in_row.iter().enumerate() {
+            points[index] = position
+                + vec2(
+                    size.x * (column + 1) as f32 / (in_row.len() + 1) as f32,
+                    size.y * (1.0 - (row as f32 + 0.5) / rows as f32),
+                );
+        }
+    }
+
+    for (cause, effect) in graph.edges() {
+        let (from, to) = (points[cause], points[effect]);
+        let steps = ((to - from).magnitude() / CAUSAL_EDGE_SPACING)
+            .ceil()
+            .max(1.0) as usize;
+        for step in 0..=steps {
+            let point = from + (to - from) * (step as f32 / steps as f32);
+            dot(builder, point, 2.0, GuiColor::YELLOW.with_alpha(0.5));
+        }
+    }
 
-use super::player::PlayerController;
+    for (event, &point) in graph.events.iter().zip(points.iter()) {
+        dot(builder, point, 24.0, GuiColor::DARK_GRAY);
+        builder.element(TextLabel {
+            transform: GuiTransform {
+                anchor_point: vec2(0.5, 0.5),
+                ..GuiTransform::from_absolute(point, vec2(32.0, 24.0))
+            },
+            text: StyledText::from_format_string(&format!("§f{}", event.label)),
+            char_pixel_height: 16.0,
+            text_alignment: TextLabel::ALIGN_MIDDLE_CENTER,
+            ..Default::default()
+        });
+    }
+}
 
 #[derive(Debug, Clone, Copy)]
 pub enum WinitEvent<'a> {
@@ -60,18 +708,58 @@ pub enum WinitEvent<'a> {
     Device(&'a DeviceEvent),
 }
 
+/// A texture added to an atlas after startup, kept around so it can be written back in whenever the atlas gets
+/// repacked.
+#[derive(Debug)]
+struct DynamicTexture<T> {
+    content: T,
+    references: usize,
+    /// When the last reference went away, going by [`TextureProvider::release_counter`]. The longest unused get
+    /// evicted first.
+    released_at: u64,
+}
+
+/// The unreferenced texture that's gone unused the longest.
+fn least_recently_used<T>(textures: &LinearMap<String, DynamicTexture<T>>) -> Option<String> {
+    textures
+        .iter()
+        .filter(|(_, texture)| texture.references == 0)
+        .min_by_key(|(_, texture)| texture.released_at)
+        .map(|(name, _)| name.clone())
+}
+
+/// Compressed textures of one format. They can't be copied into the main atlas, so each format gets one of its own,
+/// bound in its place for whatever gets drawn with them.
+#[derive(Debug)]
+struct CompressedAtlas {
+    texture: BindedTexture,
+    textures: LinearMap<String, DynamicTexture<Ktx2Image>>,
+    sections: LinearMap<String, PackedSection>,
+}
+
 #[derive(Debug)]
 pub struct TextureProvider {
     main_texture: BindedTexture,
-    texture_sections: LinearMap<String, PackedSection>,
+    /// Indexed by [`TextureId`].
+    texture_sections: Vec<Option<PackedSection>>,
+    fallback_texture: TextureId,
     reserved_textures: LinearMap<String, wgpu::Texture>,
     packer: Packer,
+    /// How many layers the textures packed at startup take up. Textures added later go in the layers after these,
+    /// so the startup ones (which models have their UVs baked against) never move.
+    static_layers: u32,
+    dynamic_textures: LinearMap<String, DynamicTexture<wgpu::Texture>>,
+    compressed_atlases: LinearMap<wgpu::TextureFormat, CompressedAtlas>,
+    release_counter: u64,
     handle: Arc<GpuHandle>,
 }
 
 impl TextureProvider {
     pub const TEXTURE_SIDE_LENGTH: u32 = 2048;
     pub const PADDING: u32 = 2;
+    /// How many layers textures added after startup get between them. Once they're full, anything that's not
+    /// referenced anymore gets evicted to make room.
+    pub const MAX_DYNAMIC_LAYERS: u32 = 4;
 
     fn texture_descriptor(layers: u32) -> wgpu::TextureDescriptor<'static> {
         wgpu::TextureDescriptor {
@@ -101,16 +789,61 @@ impl TextureProvider {
                 ),
             ),
             texture_sections: Default::default(),
+            fallback_texture: TextureId::intern("fallback"),
             reserved_textures: Default::default(),
             packer: Packer::new(
                 Self::TEXTURE_SIDE_LENGTH,
                 Self::TEXTURE_SIDE_LENGTH,
                 Self::PADDING,
             ),
+            static_layers: 0,
+            dynamic_textures: Default::default(),
+            compressed_atlases: Default::default(),
+            release_counter: 0,
             handle,
         }
     }
 
+    /// A provider with every built-in texture from [`TEXTURE_IMAGES`] already packed in, along with the compressed
+    /// ones from [`KTX2_IMAGES`] the GPU supports.
+    pub fn with_texture_images(handle: Arc<GpuHandle>) -> Self {
+        let mut texture_provider = Self::new(Arc::clone(&handle));
+        for (name, img) in TEXTURE_IMAGES.iter() {
+            let texture = texture_provider.image_texture(img);
+            texture_provider.reserve_texture(name, texture);
+        }
+
+        texture_provider.pack();
+
+        // these all go in now, before any model's UVs get baked against them, and nothing else ever gets added to
+        // their atlases, so they never move. the models that use them hold the references, not this
+        for (name, image) in KTX2_IMAGES.iter() {
+            if texture_provider.add_compressed_texture(name, image.clone()) {
+                texture_provider.release(TextureId::intern(name));
+            } else {
+                info!(
+                    "Using the uncompressed {} instead, since {:?} isn't supported",
+                    name, image.format
+                );
+            }
+        }
+        texture_provider
+    }
+
+    /// An image as a texture that can go in the atlas.
+    fn image_texture(&self, img: &image::DynamicImage) -> wgpu::Texture {
+        Texture::from_image(
+            &self.handle,
+            img,
+            &wgpu::TextureDescriptor {
+                usage: wgpu::TextureUsages::COPY_SRC | texture::TEXTURE_IMAGE.usage,
+                ..*texture::TEXTURE_IMAGE
+            },
+            &texture::SAMPLER_PIXELATED,
+        )
+        .inner_texture
+    }
+
     pub fn bind_group(&self) -> &wgpu::BindGroup {
         &self.main_texture.bind_group
     }
@@ -171,219 +904,1001 @@ impl TextureProvider {
         } = packer.pack();
 
         self.reset_main_texture(total_layers);
-        self.texture_sections = sections;
+        self.texture_sections.clear();
+        for (name, section) in sections {
+            let texture_id = TextureId::intern(&name);
+            if self.texture_sections.len() <= texture_id.index() {
+                self.texture_sections.resize(texture_id.index() + 1, None);
+            }
+            self.texture_sections[texture_id.index()] = Some(section);
+        }
 
         for (name, texture) in std::mem::take(&mut self.reserved_textures) {
             self.write_texture(name, &texture);
         }
+
+        self.static_layers = total_layers;
+        if !self.dynamic_textures.is_empty() {
+            self.repack_dynamic();
+        }
     }
 
-    pub fn write_texture(&self, name: impl Into<String>, texture: &wgpu::Texture) -> bool {
+    /// Adds a texture at runtime (e.g. one that came with a loaded model), with one reference held by the caller. It
+    /// needs `COPY_SRC` usage. Unreferenced textures get evicted if there's no room otherwise. Returns `false` if it
+    /// still doesn't fit, or if the name's already taken by a texture from startup.
+    ///
+    /// Textures added this way can move whenever another one gets added, so their sections should be looked up
+    /// again each time rather than kept around.
+    pub fn add_texture(&mut self, name: impl Into<String>, texture: wgpu::Texture) -> bool {
         let name = name.into();
-        if let Some(&section) = self.texture_sections.get(&name) {
-            if section.layer_index < self.layer_count() {
-                let mut encoder = self
-                    .handle
-                    .device
-                    .create_command_encoder(&Default::default());
-
-                encoder.copy_texture_to_texture(
-                    texture.as_image_copy(),
-                    wgpu::ImageCopyTexture {
-                        texture: &self.main_texture.texture.inner_texture,
-                        mip_level: 0,
-                        origin: wgpu::Origin3d {
-                            x: (section.uv.min()[0] * Self::TEXTURE_SIDE_LENGTH as f32) as u32,
-                            y: (section.uv.min()[1] * Self::TEXTURE_SIDE_LENGTH as f32) as u32,
-                            z: section.layer_index,
-                        },
-                        aspect: wgpu::TextureAspect::All,
-                    },
-                    texture.size(),
-                );
-
-                self.handle.queue.submit(std::iter::once(encoder.finish()));
+        if texture.width() > Self::TEXTURE_SIDE_LENGTH
+            || texture.height() > Self::TEXTURE_SIDE_LENGTH
+            || (!self.dynamic_textures.contains_key(&name)
+                && self.packed_section(TextureId::intern(&name)).is_some())
+        {
+            return false;
+        }
+        let references = self
+            .dynamic_textures
+            .get(&name)
+            .map_or(0, |existing| existing.references);
+        self.dynamic_textures.insert(
+            name.clone(),
+            DynamicTexture {
+                content: texture,
+                references: references + 1,
+                released_at: 0,
+            },
+        );
 
-                true
-            } else {
-                false
+        while !self.repack_dynamic() {
+            if !self.evict_least_recently_used() {
+                self.forget_dynamic(&name);
+                self.repack_dynamic();
+                warn!("no room left in the texture atlas for {name}");
+                return false;
             }
-        } else {
-            false
         }
+        true
     }
 
-    pub fn get_packed_section(&self, name: &str) -> PackedSection {
-        *self
-            .texture_sections
-            .get(name)
-            .unwrap_or_else(|| self.texture_sections.get("fallback").unwrap())
+    /// [`TextureProvider::add_texture()`] for an image.
+    pub fn add_image(&mut self, name: impl Into<String>, img: &image::DynamicImage) -> bool {
+        let texture = self.image_texture(img);
+        self.add_texture(name, texture)
     }
 
-    pub fn get_section(&self, name: &str) -> OrientedSection {
-        self.get_packed_section(name).unoriented()
+    /// Whether there's a texture by that name in the main atlas, which a runtime one won't be anymore once it's been
+    /// evicted.
+    pub fn has_texture(&self, texture_id: TextureId) -> bool {
+        self.packed_section(texture_id).is_some()
     }
-}
 
-#[derive(Debug)]
-struct AppStateGraphics {
-    pub texture_provider: TextureProvider,
-    pub models: BTreeMap<String, Model>,
+    /// Whether the GPU can sample textures of a (compressed) format, so it's worth loading them.
+    pub fn supports_format(&self, format: wgpu::TextureFormat) -> bool {
+        self.handle
+            .device
+            .features()
+            .contains(format.required_features())
+    }
 
-    pub generic_quad_indices: GpuVec<u32>,
-    pub generic_vertices_2d: GpuVec<Vertex2D>,
+    /// Like [`TextureProvider::add_texture()`], but for a compressed texture, which goes in the atlas for its format.
+    /// Returns `false` if the GPU doesn't support the format (so an uncompressed version should be used instead), or
+    /// if there's no room.
+    pub fn add_compressed_texture(&mut self, name: impl Into<String>, image: Ktx2Image) -> bool {
+        let name = name.into();
+        let format = image.format;
+        let (side_x, side_y) = Self::compressed_side_lengths(format);
+        if !self.supports_format(format) || image.width > side_x || image.height > side_y {
+            return false;
+        }
 
-    pub pipeline_3d: Pipeline<Vertex3D, EntityInstance>,
-    pub instance_buffer: GpuVec<EntityInstance>,
-    pub entity_model_instances: BTreeMap<String, Vec<EntityInstance>>,
-    pub camera_uniform: BindedBuffer<CameraUniform>,
+        let atlas = match self.compressed_atlases.get_mut(&format) {
+            Some(atlas) => atlas,
+            None => {
+                let texture = self.compressed_atlas_texture(format, 1);
+                self.compressed_atlases.insert(
+                    format,
+                    CompressedAtlas {
+                        texture,
+                        textures: Default::default(),
+                        sections: Default::default(),
+                    },
+                );
+                self.compressed_atlases.get_mut(&format).unwrap()
+            }
+        };
+        let references = atlas
+            .textures
+            .get(&name)
+            .map_or(0, |existing| existing.references);
+        atlas.textures.insert(
+            name.clone(),
+            DynamicTexture {
+                content: image,
+                references: references + 1,
+                released_at: 0,
+            },
+        );
 
-    pub pipeline_2d: Pipeline<Vertex2D>,
-    pub gui_vertices: IndexedVertices<Vertex2D>,
-}
+        while !self.repack_compressed(format) {
+            let atlas = self.compressed_atlases.get_mut(&format).unwrap();
+            let Some(evicted) = least_recently_used(&atlas.textures) else {
+                atlas.textures.remove(&name);
+                atlas.sections.remove(&name);
+                self.repack_compressed(format);
+                warn!("no room left in the {format:?} texture atlas for {name}");
+                return false;
+            };
+            debug!("evicting {evicted} from the {format:?} texture atlas");
+            atlas.textures.remove(&evicted);
+            atlas.sections.remove(&evicted);
+        }
+        true
+    }
 
-#[derive(Debug)]
-pub struct AppState {
-    pub graphics_controller: GraphicsController,
-    pub input_controller: InputController,
-    pub gui: RootComponent,
-    pub universe: Universe,
-    pub player_controller: PlayerController,
+    /// Where a compressed texture is, and which format's atlas it's in. Draw it with that atlas's
+    /// [`TextureProvider::compressed_bind_group()`] in place of the usual one.
+    pub fn get_compressed_section(
+        &self,
+        name: &str,
+    ) -> Option<(wgpu::TextureFormat, PackedSection)> {
+        self.compressed_atlases
+            .iter()
+            .find_map(|(&format, atlas)| Some((format, *atlas.sections.get(name)?)))
+    }
 
-    frame_counter: PerformanceCounter,
-    last_performance_report: (Instant, Option<PerformanceReport>),
+    pub fn compressed_bind_group(&self, format: wgpu::TextureFormat) -> Option<&wgpu::BindGroup> {
+        self.compressed_atlases
+            .get(&format)
+            .map(|atlas| &atlas.texture.bind_group)
+    }
 
-    graphics: AppStateGraphics,
-}
+    /// Where a model's texture is, and which compressed format's atlas it's in, if it's not in the main one. The
+    /// compressed version gets used if there's one, and the uncompressed one otherwise.
+    pub fn get_model_section(&self, name: &str) -> (Option<wgpu::TextureFormat>, OrientedSection) {
+        match self.get_compressed_section(name) {
+            Some((format, section)) => (Some(format), section.unoriented()),
+            None => (None, self.get_section(name)),
+        }
+    }
 
-impl AppState {
-    pub fn new(window: Arc<Window>) -> Result<Self> {
-        let graphics_controller = GraphicsController::new(window)?;
-        let input_controller = InputController::new();
-        let gui = RootComponent::default();
+    /// The bind group for a [`Model::texture_format`]: its compressed atlas, or the main one.
+    pub fn model_bind_group(
+        &self,
+        texture_format: Option<wgpu::TextureFormat>,
+    ) -> &wgpu::BindGroup {
+        texture_format
+            .and_then(|format| self.compressed_bind_group(format))
+            .unwrap_or_else(|| self.bind_group())
+    }
 
-        let generic_quad_indices = graphics_controller.index_vec(vec![0, 1, 2, 2, 3, 0]);
-        let generic_vertices_2d = graphics_controller.vertex_vec(vec![]);
+    /// Adds a reference to a texture added with [`TextureProvider::add_texture()`] (or
+    /// [`TextureProvider::add_compressed_texture()`]), so it won't get evicted. Does nothing for textures that were
+    /// there from startup, since they never are.
+    pub fn retain(&mut self, texture_id: TextureId) {
+        if let Some((references, _)) = self.references_mut(texture_id.name()) {
+            *references += 1;
+        }
+    }
 
-        let mut texture_provider = TextureProvider::new(graphics_controller.handle_arc());
-        for (name, img) in TEXTURE_IMAGES.iter() {
-            let texture = Texture::from_image(
-                graphics_controller.handle(),
-                img,
-                &wgpu::TextureDescriptor {
-                    usage: wgpu::TextureUsages::COPY_SRC | texture::TEXTURE_IMAGE.usage,
-                    ..*texture::TEXTURE_IMAGE
-                },
-                &texture::SAMPLER_PIXELATED,
-            );
+    /// Takes back a reference from [`TextureProvider::retain()`] or [`TextureProvider::add_texture()`]. Once none
+    /// are left, the texture stays in until the room's needed for something else.
+    pub fn release(&mut self, texture_id: TextureId) {
+        let release_counter = self.release_counter + 1;
+        if let Some((references, released_at)) = self.references_mut(texture_id.name()) {
+            if *references == 0 {
+                return;
+            }
+            *references -= 1;
+            if *references == 0 {
+                *released_at = release_counter;
+                self.release_counter = release_counter;
+            }
+        }
+    }
 
-            texture_provider.reserve_texture(name, texture.inner_texture);
+    /// A runtime texture's reference count and when it was last released, wherever it is.
+    fn references_mut(&mut self, name: &str) -> Option<(&mut usize, &mut u64)> {
+        if let Some(texture) = self.dynamic_textures.get_mut(name) {
+            return Some((&mut texture.references, &mut texture.released_at));
         }
+        self.compressed_atlases
+            .iter_mut()
+            .find_map(|(_, atlas)| atlas.textures.get_mut(name))
+            .map(|texture| (&mut texture.references, &mut texture.released_at))
+    }
 
-        texture_provider.pack();
+    /// Throws out every texture that isn't referenced anymore, returning how many there were.
+    pub fn evict_unused(&mut self) -> usize {
+        let mut evicted = 0;
+        while self.evict_least_recently_used() {
+            evicted += 1;
+        }
+        if evicted > 0 {
+            self.repack_dynamic();
+        }
 
-        let mut models = BTreeMap::new();
-        for (name, data) in MODEL_DATA.iter() {
-            let texture_section = texture_provider.get_section(name);
-            let mut vertices =
-                IndexedContainer::with_capacity(data.position.len(), data.position.len());
-
-            for object in data.objects.iter() {
-                for group in object.groups.iter() {
-                    for SimplePolygon(tuples) in group.polys.iter() {
-                        if tuples.len() == 3 {
-                            for &IndexTuple(position_index, uv_index, normal_index) in tuples.iter()
-                            {
-                                let position = data.position[position_index];
-                                let uv = data
-                                    .texture
-                                    .get(uv_index.unwrap_or_default())
-                                    .copied()
-                                    .unwrap_or([0.0, 0.0]);
-                                let normal = data
-                                    .normal
-                                    .get(normal_index.unwrap_or_default())
-                                    .copied()
-                                    .unwrap_or([1.0, 0.0, 0.0]);
-
-                                // this kinda sucks because we don't take advantage of vertex indexing
-                                // but i don't feel like writing an algorithm to convert the seperately indexed positions,
-                                // texture coords, and surface normals into a shared-index container
-                                vertices.items.push(Vertex3D {
-                                    pos: position,
-                                    uv: texture_section.section.local_point(uv.into()).into(),
-                                    tex_index: texture_section.section.layer_index,
-                                    normal,
-                                });
-                                vertices.indices.push(vertices.indices.len() as u32);
-                            }
-                        }
-                    }
-                }
+        let formats = self.compressed_atlases.keys().copied().collect::<Vec<_>>();
+        for format in formats {
+            let atlas = self.compressed_atlases.get_mut(&format).unwrap();
+            let unused = atlas
+                .textures
+                .iter()
+                .filter(|(_, texture)| texture.references == 0)
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<_>>();
+            for name in &unused {
+                atlas.textures.remove(name);
+                atlas.sections.remove(name);
+            }
+            if !unused.is_empty() {
+                evicted += unused.len();
+                self.repack_compressed(format);
             }
+        }
+        evicted
+    }
 
-            models.insert(
-                name.to_owned(),
-                Model {
-                    vertices: IndexedVertices::from_contents(&graphics_controller, vertices),
-                },
-            );
+    /// How many textures added at runtime are in (compressed ones included), and how many of those are referenced.
+    pub fn dynamic_texture_counts(&self) -> (usize, usize) {
+        let mut total = self.dynamic_textures.len();
+        let mut referenced = self
+            .dynamic_textures
+            .values()
+            .filter(|texture| texture.references > 0)
+            .count();
+        for atlas in self.compressed_atlases.values() {
+            total += atlas.textures.len();
+            referenced += atlas
+                .textures
+                .values()
+                .filter(|texture| texture.references > 0)
+                .count();
         }
+        (total, referenced)
+    }
 
-        // 3D
+    /// Forgets the unreferenced texture that's gone unused the longest. Doesn't repack. Returns `false` if
+    /// everything's still referenced.
+    fn evict_least_recently_used(&mut self) -> bool {
+        let Some(name) = least_recently_used(&self.dynamic_textures) else {
+            return false;
+        };
 
-        let pipeline_3d = Pipeline::new(
-            &graphics_controller,
-            PipelineDescriptor {
-                name: "3D Pipeline",
-                shader_source: include_str!("../graphics/shaders/main_3d.wgsl"),
-                vertex_shader_entry_point: "vert_main",
-                vertex_format: Vertex3D::VERTEX_FORMAT,
-                instance_format: Some(EntityInstance::INSTANCE_FORMAT),
-                fragment_shader_entry_point: "frag_main",
-                target_format: None,
-                bind_groups: &[
-                    Texture::ARRAY_BIND_GROUP_LAYOUT,
-                    &[(
-                        wgpu::ShaderStages::VERTEX,
-                        wgpu::BindingType::Buffer {
-                            ty: wgpu::BufferBindingType::Uniform,
-                            has_dynamic_offset: false,
-                            min_binding_size: None,
-                        },
-                    )],
-                ],
-                use_depth: true,
-                alpha_to_coverage_enabled: true,
-            },
-        );
+        debug!("evicting {name} from the texture atlas");
+        self.forget_dynamic(&name);
+        true
+    }
 
-        let instance_buffer = graphics_controller.vertex_vec(vec![]);
-        let entity_model_instances = BTreeMap::new();
-        let camera_uniform = pipeline_3d.binded_buffer(
-            1,
-            graphics_controller.uniform_vec(vec![Camera::default().uniform(1.0)]),
+    fn forget_dynamic(&mut self, name: &str) {
+        self.dynamic_textures.remove(name);
+        if let Some(section) = self
+            .texture_sections
+            .get_mut(TextureId::intern(name).index())
+        {
+            *section = None;
+        }
+    }
+
+    /// Packs every texture added at runtime into the layers after the static ones, keeping the static ones as they
+    /// are. Leaves everything alone and returns `false` if they'd take more than [`Self::MAX_DYNAMIC_LAYERS`].
+    fn repack_dynamic(&mut self) -> bool {
+        let mut packer = Packer::new(
+            Self::TEXTURE_SIDE_LENGTH,
+            Self::TEXTURE_SIDE_LENGTH,
+            Self::PADDING,
+        );
+        for (name, texture) in self.dynamic_textures.iter() {
+            packer.reserve(name, texture.content.width(), texture.content.height());
+        }
+        let PackResult {
+            total_layers,
+            sections,
+        } = packer.pack();
+        let dynamic_layers = if sections.is_empty() { 0 } else { total_layers };
+        if dynamic_layers > Self::MAX_DYNAMIC_LAYERS {
+            return false;
+        }
+
+        // the static layers get carried over as they are, since what went into them is long gone
+        let previous_texture = std::mem::replace(
+            &mut self.main_texture,
+            self.handle.binded_texture(
+                &self
+                    .handle
+                    .create_bind_group_layout(Texture::ARRAY_BIND_GROUP_LAYOUT),
+                Texture::new(
+                    &self.handle,
+                    &Self::texture_descriptor(self.static_layers + dynamic_layers),
+                    &texture::SAMPLER_PIXELATED,
+                ),
+            ),
+        );
+        if self.static_layers > 0 {
+            let mut encoder = self
+                .handle
+                .device
+                .create_command_encoder(&Default::default());
+            encoder.copy_texture_to_texture(
+                previous_texture.texture.inner_texture.as_image_copy(),
+                self.main_texture.texture.inner_texture.as_image_copy(),
+                wgpu::Extent3d {
+                    width: Self::TEXTURE_SIDE_LENGTH,
+                    height: Self::TEXTURE_SIDE_LENGTH,
+                    depth_or_array_layers: self.static_layers,
+                },
+            );
+            self.handle.queue.submit(std::iter::once(encoder.finish()));
+        }
+
+        for (name, section) in sections {
+            let texture_id = TextureId::intern(&name);
+            if self.texture_sections.len() <= texture_id.index() {
+                self.texture_sections.resize(texture_id.index() + 1, None);
+            }
+            self.texture_sections[texture_id.index()] = Some(PackedSection {
+                layer_index: section.layer_index + self.static_layers,
+                ..section
+            });
+        }
+        for (name, texture) in self.dynamic_textures.iter() {
+            self.write_texture(name, &texture.content);
+        }
+        true
+    }
+
+    /// The biggest a compressed atlas can be while still being a whole number of blocks across and down.
+    fn compressed_side_lengths(format: wgpu::TextureFormat) -> (u32, u32) {
+        let (block_width, block_height) = format.block_dimensions();
+        (
+            Self::TEXTURE_SIDE_LENGTH / block_width * block_width,
+            Self::TEXTURE_SIDE_LENGTH / block_height * block_height,
+        )
+    }
+
+    fn compressed_atlas_texture(&self, format: wgpu::TextureFormat, layers: u32) -> BindedTexture {
+        let (width, height) = Self::compressed_side_lengths(format);
+        self.handle.binded_texture(
+            &self
+                .handle
+                .create_bind_group_layout(Texture::ARRAY_BIND_GROUP_LAYOUT),
+            Texture::new(
+                &self.handle,
+                &wgpu::TextureDescriptor {
+                    format,
+                    usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                    size: wgpu::Extent3d {
+                        width,
+                        height,
+                        // same as the main atlas, so the view comes out as an array
+                        depth_or_array_layers: layers.max(2),
+                    },
+                    ..*texture::TEXTURE_IMAGE
+                },
+                &texture::SAMPLER_PIXELATED,
+            ),
+        )
+    }
+
+    /// Packs a format's atlas from scratch. Compressed textures can only be copied a whole block at a time, so
+    /// they're packed in blocks rather than pixels. Leaves everything alone and returns `false` if they'd take more
+    /// than [`Self::MAX_DYNAMIC_LAYERS`].
+    fn repack_compressed(&mut self, format: wgpu::TextureFormat) -> bool {
+        let (block_width, block_height) = format.block_dimensions();
+        let (side_x, side_y) = Self::compressed_side_lengths(format);
+        let Some(atlas) = self.compressed_atlases.get(&format) else {
+            return true;
+        };
+
+        let mut packer = Packer::new(side_x / block_width, side_y / block_height, 1);
+        for (name, texture) in atlas.textures.iter() {
+            let (blocks_x, blocks_y) = texture.content.block_count();
+            packer.reserve(name, blocks_x, blocks_y);
+        }
+        let PackResult {
+            total_layers,
+            sections,
+        } = packer.pack();
+        let layers = if sections.is_empty() { 0 } else { total_layers };
+        if layers > Self::MAX_DYNAMIC_LAYERS {
+            return false;
+        }
+
+        let texture = self.compressed_atlas_texture(format, layers);
+        let atlas = self.compressed_atlases.get_mut(&format).unwrap();
+        atlas.texture = texture;
+        atlas.sections.clear();
+        for (name, section) in sections {
+            let image = &atlas.textures[&name].content;
+            let [min_u, min_v] = section.uv.min();
+            let origin_x = (min_u * side_x as f32).round() as u32;
+            let origin_y = (min_v * side_y as f32).round() as u32;
+            let (blocks_x, blocks_y) = image.block_count();
+            self.handle.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &atlas.texture.texture.inner_texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: origin_x,
+                        y: origin_y,
+                        z: section.layer_index,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &image.data,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(image.bytes_per_row()),
+                    rows_per_image: Some(blocks_y),
+                },
+                wgpu::Extent3d {
+                    width: blocks_x * block_width,
+                    height: blocks_y * block_height,
+                    depth_or_array_layers: 1,
+                },
+            );
+
+            // the last row and column of blocks can hang off the edge of the actual image
+            atlas.sections.insert(
+                name,
+                PackedSection {
+                    layer_index: section.layer_index,
+                    uv: bbox!(
+                        vec2(
+                            origin_x as f32 / side_x as f32,
+                            origin_y as f32 / side_y as f32
+                        ),
+                        vec2(
+                            (origin_x + image.width) as f32 / side_x as f32,
+                            (origin_y + image.height) as f32 / side_y as f32
+                        )
+                    ),
+                },
+            );
+        }
+        true
+    }
+
+    fn packed_section(&self, texture_id: TextureId) -> Option<PackedSection> {
+        self.texture_sections
+            .get(texture_id.index())
+            .copied()
+            .flatten()
+    }
+
+    pub fn write_texture(&self, name: impl Into<String>, texture: &wgpu::Texture) -> bool {
+        if let Some(section) = self.packed_section(TextureId::intern(&name.into())) {
+            if section.layer_index < self.layer_count() {
+                let mut encoder = self
+                    .handle
+                    .device
+                    .create_command_encoder(&Default::default());
+
+                encoder.copy_texture_to_texture(
+                    texture.as_image_copy(),
+                    wgpu::ImageCopyTexture {
+                        texture: &self.main_texture.texture.inner_texture,
+                        mip_level: 0,
+                        origin: wgpu::Origin3d {
+                            x: (section.uv.min()[0] * Self::TEXTURE_SIDE_LENGTH as f32) as u32,
+                            y: (section.uv.min()[1] * Self::TEXTURE_SIDE_LENGTH as f32) as u32,
+                            z: section.layer_index,
+                        },
+                        aspect: wgpu::TextureAspect::All,
+                    },
+                    texture.size(),
+                );
+
+                self.handle.queue.submit(std::iter::once(encoder.finish()));
+
+                true
+            } else {
+                false
+            }
+        } else {
+            false
+        }
+    }
+
+    pub fn get_packed_section(&self, name: &str) -> PackedSection {
+        self.get_packed_section_by_id(TextureId::intern(name))
+    }
+
+    pub fn get_section(&self, name: &str) -> OrientedSection {
+        self.get_packed_section(name).unoriented()
+    }
+
+    /// Skips looking the name up, which matters for anything that gets drawn every frame.
+    pub fn get_packed_section_by_id(&self, texture_id: TextureId) -> PackedSection {
+        self.packed_section(texture_id)
+            .or_else(|| self.packed_section(self.fallback_texture))
+            .unwrap()
+    }
+
+    pub fn get_section_by_id(&self, texture_id: TextureId) -> OrientedSection {
+        self.get_packed_section_by_id(texture_id).unoriented()
+    }
+}
+
+/// What [`AppState::render_entity_instances()`] draws with.
+#[derive(Debug, Clone, Copy)]
+enum EntityPass<'a> {
+    /// The 3D pipeline, as it's drawn on screen.
+    Color(&'a PipelineVariant),
+    /// How far everything moved on screen, for motion blur.
+    Velocity,
+}
+
+#[derive(Debug)]
+struct AppStateGraphics {
+    pub texture_provider: TextureProvider,
+    pub models: BTreeMap<ModelId, Model>,
+
+    pub generic_quad_indices: GpuVec<u32>,
+    pub generic_vertices_2d: GpuVec<Vertex2D>,
+
+    /// Variants for each combination of [`ShaderFeatures`](super::settings::ShaderFeatures).
+    pub pipeline_3d: PipelineVariants<Vertex3D, EntityInstance>,
+    pub instance_buffer: GpuVec<EntityInstance>,
+    pub entity_model_instances: BTreeMap<ModelId, Vec<EntityInstance>>,
+    /// What entity instances are positioned relative to, which is wherever the main camera is. Cameras get shifted
+    /// to match in [`AppState::update_camera_uniform()`].
+    pub instance_origin: Vector3<f32>,
+    /// Every camera drawn with this frame, one for each view.
+    pub camera_uniforms: UniformPool<CameraUniform>,
+    /// The one [`AppState::render_entities()`] uses.
+    pub camera_slot: UniformSlot,
+
+    pub pipeline_2d: Pipeline<Vertex2D>,
+    pub gui_vertices: IndexedVertices<Vertex2D>,
+
+    pub photo_filter_pipelines: BTreeMap<PhotoFilter, Pipeline<Vertex2D>>,
+    /// Puts the two eyes' views together, for each stereo mode that has them.
+    pub stereo_pipelines: BTreeMap<StereoMode, Pipeline<Vertex2D>>,
+    /// Draws how far each pixel moved on screen, for motion blur.
+    pub velocity_pipeline: Pipeline<Vertex3D, EntityInstance>,
+    pub motion_blur_pipeline: Pipeline<Vertex2D>,
+    /// Works out how bright the 3D view is, for auto exposure.
+    pub luminance_pipeline: Pipeline<Vertex2D>,
+    pub post_effects_pipeline: Pipeline<Vertex2D>,
+    pub post_effects_uniforms: UniformPool<PostEffectsUniform>,
+    /// With and without logarithmic depth, to match the depth buffer it reads.
+    pub lens_flare_pipeline: PipelineVariants<Vertex2D, FlareInstance>,
+    /// The quad each flare sprite gets drawn with.
+    pub flare_quad: GpuVec<Vertex2D>,
+    /// Every sprite of every lens flare this frame, from [`AppState::update_entity_model_instances()`].
+    pub flare_instances: Vec<FlareInstance>,
+    pub flare_instance_buffer: GpuVec<FlareInstance>,
+}
+
+impl AppStateGraphics {
+    /// Puts a model in, holding a reference to its texture for as long as it's there so it won't get evicted. The
+    /// model it replaces (if there was one) lets go of its reference.
+    fn insert_model(&mut self, model_id: ModelId, model: Model) {
+        let texture_id = TextureId::intern(model_id.name());
+        self.texture_provider.retain(texture_id);
+        if self.models.insert(model_id, model).is_some() {
+            self.texture_provider.release(texture_id);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct AppState {
+    pub graphics_controller: GraphicsController,
+    pub input_controller: InputController,
+    pub gui: RootComponent,
+    pub timeline: TimelineScrubber,
+    pub message_log: MessageLog,
+    pub quick_actions: RadialMenu<QuickAction>,
+    pub cursor_style: CursorStyle,
+    pub audio: AudioSystem,
+    pub settings: Settings,
+    pub hud_editor: HudLayoutEditor,
+    #[cfg(feature = "gamepad")]
+    gamepad: Option<GamepadPoller>,
+    pub physics: PhysicsThread,
+    frame_pacer: FramePacer,
+    /// Background work, with callbacks run at the start of every frame.
+    pub tasks: TaskPool<AppState>,
+    /// Counts up with every save, so a save that got overtaken by a newer one doesn't write stale settings.
+    settings_save_generation: Arc<Mutex<u64>>,
+    pub player_controller: PlayerController,
+    /// Entity bounds where they're seen, in the user's frame (so light-delayed and length-contracted).
+    pub visible_entities: SpatialIndex,
+    /// Callouts from the scenario, drawn over the entities they're attached to.
+    pub annotations: Vec<Annotation>,
+    pub tour: GuidedTour,
+    pub docking: DockingChallenge,
+    pub scenario_name: String,
+    /// The scenario's script, if it has one.
+    pub script: Option<ScriptEngine>,
+    /// Signals scripts have sent that haven't gotten to everyone yet.
+    pub signals: SignalNetwork,
+    pub plugins: Vec<Plugin>,
+    replay_recorder: Option<ReplayRecorder>,
+    /// Set while watching a replay instead of the live universe.
+    pub replay_player: Option<ReplayPlayer>,
+    pub replay_scrubber: ReplayScrubber,
+    pub spawn_palette: SpawnPalette,
+    /// Picked by clicking on it, for deleting and the like.
+    pub selected_entity: Option<EntityId>,
+    /// Entities selected along with it, for editing a bunch at once.
+    pub selection_group: EntityGroup,
+    /// What Ctrl+C copied last.
+    pub entity_clipboard: EntityClipboard,
+    /// Entities pinned for comparing clocks with.
+    pub observers: ObserverEnsemble,
+    pub ruler_tool: RulerTool,
+    /// Whether the panel comparing the selected entity's apparent and actual angular size is up.
+    pub show_angular_size: bool,
+    /// Whether the sparkline of the user's Lorentz factor over the last minute of their proper time is up.
+    pub show_gamma_history: bool,
+    /// Events marked for seeing how different observers would order them.
+    pub event_ordering: EventOrdering,
+    pub causal_graph: CausalGraph,
+    ordering_slider: Slider,
+    /// Warns about anything that's about to come close.
+    pub proximity: ProximityMonitor,
+    pub maneuver: ManeuverPlanner,
+    pub frame_transition: FrameTransition,
+    pub motion_history: MotionHistory,
+    /// The scenario's, until they're changed with `/posteffects`.
+    pub post_effects: PostEffects,
+    /// Changes made by hand, like spawning and deleting.
+    pub undo_stack: UndoStack,
+    /// The connection to a multiplayer server, if there is one.
+    pub net: Option<NetClient>,
+    multiplayer_launch: Option<MultiplayerLaunch>,
+    /// What other players see us as. Empty leaves it up to the server.
+    pub player_name: String,
+    pub chat_box: ChatBox,
+    pub lobby: LobbyPanel,
+    /// The second window, while it's open.
+    pub auxiliary_view: Option<AuxiliaryView>,
+    /// Whether the user asked for the second window. It's up to the event loop to actually open it, see
+    /// [`AppState::open_auxiliary_window()`].
+    auxiliary_window_wanted: bool,
+    /// Set while in photo mode.
+    pub photo_mode: Option<PhotoMode>,
+    /// Set while the camera's circling an entity instead of riding along with the user.
+    pub orbit_camera: Option<OrbitCamera>,
+    pub session_restore: SessionRestorePrompt,
+    /// Real time since the last autosave.
+    autosave_timer: f64,
+    /// The event picked on the timeline as of the last frame, so a newly picked one can be told apart.
+    timeline_event: Option<usize>,
+    /// Real time since unused textures were last evicted.
+    texture_eviction_timer: f64,
+    /// The plugin icons being drawn, each holding a reference to its texture.
+    shown_plugin_icons: Vec<TextureId>,
+
+    frame_counter: PerformanceCounter,
+    pub profiler: Profiler,
+    last_performance_report: (Instant, Option<PerformanceReport>),
+    debug_page: DebugPage,
+    /// To size next frame's [`GuiBuilder`](crate::gui::builder::GuiBuilder) up front.
+    last_gui_primitive_count: usize,
+
+    graphics: AppStateGraphics,
+    loader: Option<StartupLoader>,
+    input_player: Option<InputPlayer>,
+    /// Set for `--benchmark` runs.
+    pub benchmark: Option<Benchmark>,
+    /// Whether frames all take [`FIXED_FRAME_TIME`] and physics stays in lockstep with them, so the same input
+    /// always plays out the same way.
+    deterministic: bool,
+}
+
+/// The pipeline everything in the GUI is drawn with, textured from a [`TextureProvider`].
+pub fn create_pipeline_2d(graphics_controller: &GraphicsController) -> Pipeline<Vertex2D> {
+    Pipeline::new(
+        graphics_controller,
+        PipelineDescriptor {
+            name: "2D Pipeline",
+            shader_source: include_str!("../graphics/shaders/main_2d.wgsl"),
+            shader_defines: Vec::new(),
+            vertex_shader_entry_point: "vert_main",
+            vertex_format: Vertex2D::VERTEX_FORMAT,
+            instance_format: None,
+            fragment_shader_entry_point: "frag_main",
+            target_format: None,
+            bind_groups: &[Texture::ARRAY_BIND_GROUP_LAYOUT],
+            push_constant_ranges: &[],
+            use_depth: false,
+            alpha_to_coverage_enabled: false,
+            premultiplied_alpha: false,
+            wireframe: false,
+            depth_mode: DepthMode::Normal,
+        },
+    )
+}
+
+impl AppState {
+    /// Without a scenario given on the command line, the [`DEFAULT_SCENARIO`] (or the [`BENCHMARK_SCENARIO`]) gets
+    /// loaded.
+    pub fn new(window: Arc<Window>, cli: &Cli) -> Result<Self> {
+        let graphics_controller = GraphicsController::new(window, cli.adapter.as_deref())?;
+        let adapter_info = graphics_controller.adapter_info();
+        let adapter = format!("{} ({:?})", adapter_info.name, adapter_info.backend);
+        update_crash_context(|context| context.adapter = Some(adapter));
+        let settings = Settings::load(&cli.config);
+        let mut audio = AudioSystem::default();
+        audio.apply_settings(&settings.sound);
+        let mut input_controller = InputController::new();
+        input_controller.bindings = settings.bindings.clone();
+        input_controller.gamepad_settings = settings.gamepad;
+        input_controller.key_repeat = settings.key_repeat;
+        set_language(&settings.language);
+        let gui = RootComponent::default();
+
+        let generic_quad_indices = graphics_controller.index_vec(vec![0, 1, 2, 2, 3, 0]);
+        let generic_vertices_2d = graphics_controller.vertex_vec(vec![]);
+
+        let texture_provider =
+            TextureProvider::with_texture_images(graphics_controller.handle_arc());
+
+        let models = BTreeMap::new();
+        let tasks = TaskPool::new();
+        // plugin prefabs have to be in before the scenario gets built
+        let plugins = load_plugins();
+        let loader = StartupLoader::start(
+            &tasks,
+            MODEL_DATA
+                .keys()
+                .map(|name| (name.to_owned(), texture_provider.get_model_section(name).1))
+                .collect(),
+            cli.scenario.clone(),
+            if cli.benchmark {
+                BENCHMARK_SCENARIO
+            } else {
+                DEFAULT_SCENARIO
+            },
         );
 
+        // 3D
+
+        let shader_features = settings.shader_features;
+        let mut pipeline_3d = PipelineVariants::new(Pipeline::new(
+            &graphics_controller,
+            PipelineDescriptor {
+                name: "3D Pipeline",
+                shader_source: include_str!("../graphics/shaders/main_3d.wgsl"),
+                shader_defines: ShaderFeatures::default().defines(),
+                vertex_shader_entry_point: "vert_main",
+                vertex_format: Vertex3D::VERTEX_FORMAT,
+                instance_format: Some(EntityInstance::INSTANCE_FORMAT),
+                fragment_shader_entry_point: "frag_main",
+                target_format: None,
+                bind_groups: &[
+                    Texture::ARRAY_BIND_GROUP_LAYOUT,
+                    CameraUniform::BIND_GROUP_LAYOUT,
+                ],
+                push_constant_ranges: &[],
+                use_depth: true,
+                alpha_to_coverage_enabled: true,
+                premultiplied_alpha: false,
+                wireframe: false,
+                depth_mode: DepthMode::Normal,
+            },
+        ));
+        for variant in shader_features.pipeline_variants() {
+            pipeline_3d.prepare(&variant);
+        }
+
+        let instance_buffer = graphics_controller.vertex_vec(vec![]);
+        let entity_model_instances = BTreeMap::new();
+        let mut camera_uniforms =
+            UniformPool::new(&graphics_controller, CameraUniform::BIND_GROUP_LAYOUT);
+        let camera_slot = camera_uniforms.push(Camera::default().uniform(1.0));
+
         // 2D
 
-        let pipeline_2d = Pipeline::new(
+        let pipeline_2d = create_pipeline_2d(&graphics_controller);
+
+        let gui_vertices = IndexedVertices::new(&graphics_controller);
+
+        // photo mode
+
+        let photo_filter_pipelines = PhotoFilter::ALL
+            .into_iter()
+            .filter_map(|filter| {
+                let entry_point = filter.entry_point()?;
+                let pipeline = Pipeline::new(
+                    &graphics_controller,
+                    PipelineDescriptor {
+                        name: "Photo Filter Pipeline",
+                        shader_source: include_str!("../graphics/shaders/photo_filter.wgsl"),
+                        shader_defines: Vec::new(),
+                        vertex_shader_entry_point: "vert_main",
+                        vertex_format: Vertex2D::VERTEX_FORMAT,
+                        instance_format: None,
+                        fragment_shader_entry_point: entry_point,
+                        target_format: None,
+                        bind_groups: &[Texture::STANDARD_BIND_GROUP_LAYOUT],
+                        push_constant_ranges: &[],
+                        use_depth: false,
+                        alpha_to_coverage_enabled: false,
+                        premultiplied_alpha: false,
+                        wireframe: false,
+                        depth_mode: DepthMode::Normal,
+                    },
+                );
+                Some((filter, pipeline))
+            })
+            .collect();
+
+        // stereo
+
+        let stereo_pipelines = StereoMode::ALL
+            .into_iter()
+            .filter_map(|mode| {
+                let entry_point = mode.entry_point()?;
+                let pipeline = Pipeline::new(
+                    &graphics_controller,
+                    PipelineDescriptor {
+                        name: "Stereo Pipeline",
+                        shader_source: include_str!("../graphics/shaders/stereo.wgsl"),
+                        shader_defines: Vec::new(),
+                        vertex_shader_entry_point: "vert_main",
+                        vertex_format: Vertex2D::VERTEX_FORMAT,
+                        instance_format: None,
+                        fragment_shader_entry_point: entry_point,
+                        target_format: None,
+                        bind_groups: &[
+                            Texture::STANDARD_BIND_GROUP_LAYOUT,
+                            Texture::STANDARD_BIND_GROUP_LAYOUT,
+                        ],
+                        push_constant_ranges: &[],
+                        use_depth: false,
+                        alpha_to_coverage_enabled: false,
+                        premultiplied_alpha: false,
+                        wireframe: false,
+                        depth_mode: DepthMode::Normal,
+                    },
+                );
+                Some((mode, pipeline))
+            })
+            .collect();
+
+        // motion blur
+
+        let velocity_pipeline = Pipeline::new(
+            &graphics_controller,
+            PipelineDescriptor {
+                name: "Velocity Pipeline",
+                shader_source: include_str!("../graphics/shaders/main_3d.wgsl"),
+                shader_defines: vec!["VELOCITY"],
+                vertex_shader_entry_point: "vert_main",
+                vertex_format: Vertex3D::VERTEX_FORMAT,
+                instance_format: Some(EntityInstance::INSTANCE_FORMAT),
+                fragment_shader_entry_point: "velocity_main",
+                target_format: Some(VELOCITY_TARGET_FORMAT),
+                bind_groups: &[
+                    Texture::ARRAY_BIND_GROUP_LAYOUT,
+                    CameraUniform::BIND_GROUP_LAYOUT,
+                ],
+                push_constant_ranges: &[],
+                use_depth: true,
+                alpha_to_coverage_enabled: false,
+                premultiplied_alpha: false,
+                wireframe: false,
+                depth_mode: DepthMode::Normal,
+            },
+        );
+        let motion_blur_pipeline = Pipeline::new(
             &graphics_controller,
             PipelineDescriptor {
-                name: "2D Pipeline",
-                shader_source: include_str!("../graphics/shaders/main_2d.wgsl"),
+                name: "Motion Blur Pipeline",
+                shader_source: include_str!("../graphics/shaders/motion_blur.wgsl"),
+                shader_defines: Vec::new(),
                 vertex_shader_entry_point: "vert_main",
                 vertex_format: Vertex2D::VERTEX_FORMAT,
                 instance_format: None,
                 fragment_shader_entry_point: "frag_main",
                 target_format: None,
-                bind_groups: &[Texture::ARRAY_BIND_GROUP_LAYOUT],
+                bind_groups: &[
+                    Texture::STANDARD_BIND_GROUP_LAYOUT,
+                    Texture::STANDARD_BIND_GROUP_LAYOUT,
+                ],
+                push_constant_ranges: &[],
                 use_depth: false,
                 alpha_to_coverage_enabled: false,
+                premultiplied_alpha: false,
+                wireframe: false,
+                depth_mode: DepthMode::Normal,
             },
         );
 
-        let gui_vertices = IndexedVertices::new(&graphics_controller);
+        // post effects
+
+        let luminance_pipeline = Pipeline::new(
+            &graphics_controller,
+            PipelineDescriptor {
+                name: "Luminance Pipeline",
+                shader_source: include_str!("../graphics/shaders/post_effects.wgsl"),
+                shader_defines: Vec::new(),
+                vertex_shader_entry_point: "vert_main",
+                vertex_format: Vertex2D::VERTEX_FORMAT,
+                instance_format: None,
+                fragment_shader_entry_point: "frag_luminance",
+                target_format: Some(LUMINANCE_TARGET_FORMAT),
+                // it draws into the luminance target, so it can't have it bound too
+                bind_groups: &[
+                    Texture::STANDARD_BIND_GROUP_LAYOUT,
+                    PostEffectsUniform::BIND_GROUP_LAYOUT,
+                ],
+                push_constant_ranges: &[],
+                use_depth: false,
+                alpha_to_coverage_enabled: false,
+                premultiplied_alpha: false,
+                wireframe: false,
+                depth_mode: DepthMode::Normal,
+            },
+        );
+        let post_effects_pipeline = Pipeline::new(
+            &graphics_controller,
+            PipelineDescriptor {
+                name: "Post Effects Pipeline",
+                shader_source: include_str!("../graphics/shaders/post_effects.wgsl"),
+                shader_defines: Vec::new(),
+                vertex_shader_entry_point: "vert_main",
+                vertex_format: Vertex2D::VERTEX_FORMAT,
+                instance_format: None,
+                fragment_shader_entry_point: "frag_main",
+                target_format: None,
+                bind_groups: &[
+                    Texture::STANDARD_BIND_GROUP_LAYOUT,
+                    PostEffectsUniform::BIND_GROUP_LAYOUT,
+                    Texture::STANDARD_BIND_GROUP_LAYOUT,
+                ],
+                push_constant_ranges: &[],
+                use_depth: false,
+                alpha_to_coverage_enabled: false,
+                premultiplied_alpha: false,
+                wireframe: false,
+                depth_mode: DepthMode::Normal,
+            },
+        );
+        let post_effects_uniforms =
+            UniformPool::new(&graphics_controller, PostEffectsUniform::BIND_GROUP_LAYOUT);
+
+        // lens flares
+
+        let mut lens_flare_pipeline = PipelineVariants::new(Pipeline::new(
+            &graphics_controller,
+            PipelineDescriptor {
+                name: "Lens Flare Pipeline",
+                shader_source: include_str!("../graphics/shaders/lens_flare.wgsl"),
+                shader_defines: Vec::new(),
+                vertex_shader_entry_point: "vert_main",
+                vertex_format: Vertex2D::VERTEX_FORMAT,
+                instance_format: Some(FlareInstance::INSTANCE_FORMAT),
+                fragment_shader_entry_point: "frag_main",
+                target_format: None,
+                bind_groups: &[
+                    Texture::DEPTH_BIND_GROUP_LAYOUT,
+                    CameraUniform::BIND_GROUP_LAYOUT,
+                ],
+                push_constant_ranges: &[],
+                // it reads the depth buffer instead
+                use_depth: false,
+                alpha_to_coverage_enabled: false,
+                premultiplied_alpha: true,
+                wireframe: false,
+                depth_mode: DepthMode::Normal,
+            },
+        ));
+        lens_flare_pipeline.prepare(&flare_pipeline_variant(true));
+        let flare_quad = graphics_controller.vertex_vec(
+            Vertex2D::fill_screen(
+                GuiColor::WHITE,
+                texture_provider.get_section_by_id(*WHITE_TEXTURE),
+            )
+            .to_vec(),
+        );
+        let flare_instance_buffer = graphics_controller.vertex_vec(vec![]);
 
         let graphics = AppStateGraphics {
             texture_provider,
@@ -395,320 +1910,5000 @@ impl AppState {
             pipeline_3d,
             instance_buffer,
             entity_model_instances,
-            camera_uniform,
+            instance_origin: vec3(0.0, 0.0, 0.0),
+            camera_uniforms,
+            camera_slot,
 
             pipeline_2d,
             gui_vertices,
+
+            photo_filter_pipelines,
+            stereo_pipelines,
+            velocity_pipeline,
+            motion_blur_pipeline,
+            luminance_pipeline,
+            post_effects_pipeline,
+            post_effects_uniforms,
+            lens_flare_pipeline,
+            flare_quad,
+            flare_instances: Vec::new(),
+            flare_instance_buffer,
         };
 
-        let mut universe = Universe::default();
-
-        let mut rng = rand::thread_rng();
-        let range = 5;
-        for x in -range..range {
-            for y in -range..range {
-                for z in -range..range {
-                    universe.insert_entity(Entity {
-                        worldline: Worldline::new(InertialFrame {
-                            position: vec4(x as f64 * 50.0, y as f64 * 50.0, z as f64 * 50.0, 0.0),
-                            ..Default::default()
-                        }),
-                        model: Some("subdivided_cube".into()),
-                        model_matrix: Matrix4::from_scale(5.0),
-                        ..Default::default()
-                    });
-                }
+        // filled in by the loader once it's done
+        let physics = PhysicsThread::start(Universe::default());
+
+        let player_controller = PlayerController::default();
+
+        Ok(Self {
+            graphics_controller,
+            input_controller,
+            gui,
+            timeline: TimelineScrubber::new(),
+            message_log: MessageLog::new(),
+            quick_actions: RadialMenu::new(quick_action_options()),
+            cursor_style: CursorStyle::default(),
+            audio,
+            settings,
+            hud_editor: HudLayoutEditor::new(),
+            #[cfg(feature = "gamepad")]
+            gamepad: GamepadPoller::new(),
+            physics,
+            frame_pacer: FramePacer::new(),
+            tasks,
+            settings_save_generation: Default::default(),
+            player_controller,
+            visible_entities: SpatialIndex::default(),
+            annotations: Vec::new(),
+            tour: GuidedTour::default(),
+            docking: DockingChallenge::default(),
+            scenario_name: String::new(),
+            script: None,
+            signals: SignalNetwork::new(),
+            plugins,
+            replay_recorder: None,
+            replay_player: None,
+            replay_scrubber: ReplayScrubber::new(),
+            spawn_palette: SpawnPalette::new(),
+            selected_entity: None,
+            selection_group: EntityGroup::new(),
+            entity_clipboard: EntityClipboard::new(),
+            observers: ObserverEnsemble::new(),
+            ruler_tool: RulerTool::new(),
+            show_angular_size: false,
+            show_gamma_history: false,
+            event_ordering: EventOrdering::new(),
+            causal_graph: CausalGraph::new(),
+            ordering_slider: Slider::new(),
+            proximity: ProximityMonitor::new(),
+            maneuver: ManeuverPlanner::new(),
+            frame_transition: FrameTransition::new(),
+            motion_history: MotionHistory::new(),
+            post_effects: PostEffects::default(),
+            undo_stack: UndoStack::new(),
+            net: None,
+            multiplayer_launch: match (cli.host, &cli.connect) {
+                (Some(port), _) => Some(MultiplayerLaunch::Host {
+                    address: SocketAddr::new(cli.bind, port),
+                    max_players: cli.max_players,
+                }),
+                (None, Some(address)) => Some(MultiplayerLaunch::Connect(address.clone())),
+                (None, None) => None,
+            },
+            player_name: cli.name.clone().unwrap_or_default(),
+            chat_box: ChatBox::new(),
+            lobby: LobbyPanel::new(),
+            auxiliary_view: None,
+            auxiliary_window_wanted: false,
+            photo_mode: None,
+            orbit_camera: None,
+            session_restore: SessionRestorePrompt::new(),
+            autosave_timer: 0.0,
+            timeline_event: None,
+            texture_eviction_timer: 0.0,
+            shown_plugin_icons: Vec::new(),
+
+            frame_counter: PerformanceCounter::new(),
+            profiler: Profiler::new(),
+            last_performance_report: (Instant::now(), None),
+            debug_page: DebugPage::default(),
+            last_gui_primitive_count: 0,
+
+            graphics,
+            loader: Some(loader),
+            input_player: None,
+            deterministic: false,
+            benchmark: cli.benchmark.then(|| Benchmark::new(&cli.benchmark_output)),
+        })
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.loader.is_some()
+    }
+
+    /// Whether the cursor is being drawn in-game, meaning the system cursor should be hidden.
+    pub fn draws_cursor(&self) -> bool {
+        !self.is_loading() && self.cursor_style.draw_cursor
+    }
+
+    /// Where a HUD widget from [`hud_widgets()`] currently goes.
+    pub fn hud_transform(&self, name: &str) -> GuiTransform {
+        let (_, default, size) = hud_widgets()
+            .into_iter()
+            .find(|(widget_name, ..)| *widget_name == name)
+            .expect("unknown HUD widget");
+
+        self.settings
+            .hud_layout
+            .transform(name, default, size, self.settings.gui_scale)
+    }
+
+    /// Records every input event (and how many ticks each frame ran) from the end of loading onwards, to be saved
+    /// to `path` by [`AppState::finish_input_recording()`].
+    pub fn start_input_recording(&mut self, path: impl Into<PathBuf>) {
+        self.input_controller
+            .start_recording(InputRecorder::new(path));
+        // the recorded ticks have to line up exactly with the input
+        self.physics.synchronous = true;
+    }
+
+    pub fn finish_input_recording(&mut self) -> Result<()> {
+        let Some(recorder) = self.input_controller.stop_recording() else {
+            return Ok(());
+        };
+
+        recorder.save()?;
+        info!(
+            "Saved {} frames of input to {}",
+            recorder.frame_count(),
+            recorder.path.display()
+        );
+        Ok(())
+    }
+
+    /// Replays a recording made with [`AppState::start_input_recording()`]. Live input is ignored until it runs out.
+    pub fn start_input_playback(&mut self, path: impl Into<PathBuf>) -> Result<()> {
+        let recording = InputRecording::load(path)?;
+        self.input_player = Some(InputPlayer::new(recording));
+        self.input_controller.set_playing_back(true);
+        self.physics.synchronous = true;
+        Ok(())
+    }
+
+    /// How many frames per second to hold rendering to, if any, given the monitor's refresh rate. Benchmarks always
+    /// run flat out.
+    pub fn frame_rate_limit(&self, refresh_rate: Option<f64>) -> Option<f64> {
+        if self.benchmark.is_some() {
+            return None;
+        }
+        self.settings
+            .frame_pacing
+            .frame_limit
+            .target_fps(refresh_rate, self.graphics_controller.vsync())
+    }
+
+    /// How big the 3D view gets drawn, see [`Settings::render_scale`].
+    fn scene_size(&self) -> PhysicalSize<u32> {
+        let window_size = self.graphics_controller.window_size();
+        let scale = self
+            .settings
+            .render_scale
+            .clamp(MIN_RENDER_SCALE, MAX_RENDER_SCALE);
+        PhysicalSize::new(
+            ((window_size.width as f32 * scale).round() as u32).max(1),
+            ((window_size.height as f32 * scale).round() as u32).max(1),
+        )
+    }
+
+    /// Whether a `--benchmark` run has gone on for [`BENCHMARK_DURATION`], meaning the app should exit.
+    pub fn benchmark_finished(&self) -> bool {
+        self.benchmark
+            .as_ref()
+            .is_some_and(|benchmark| benchmark.is_finished())
+    }
+
+    /// Writes out the benchmark's report, and logs the gist of it.
+    pub fn finish_benchmark(&self) {
+        let Some(benchmark) = &self.benchmark else {
+            return;
+        };
+
+        let adapter_info = self.graphics_controller.adapter_info();
+        let info = BenchmarkInfo {
+            adapter: adapter_info.name.clone(),
+            backend: format!("{:?}", adapter_info.backend),
+            scenario: self.scenario_name.clone(),
+            entity_count: self.physics.universe().entities.len(),
+            depth_prepass: self.settings.shader_features.depth_prepass,
+        };
+        match benchmark.save(info) {
+            Ok(report) => info!(
+                "Benchmark: {} frames, {:.3}ms/{:.1}fps on average, p99 {:.3}ms. Saved to {}",
+                report.frame_count,
+                report.mean,
+                report.mean_fps,
+                report.p99,
+                benchmark.output_dir.display()
+            ),
+            Err(error) => warn!("Couldn't save the benchmark report: {}", error),
+        }
+    }
+
+    /// Makes every frame take [`FIXED_FRAME_TIME`] and keeps physics in lockstep with rendering. For the IDs to
+    /// match up between runs too, [`seed_ids()`](crate::shared::determinism::seed_ids) has to be called before the
+    /// [`AppState`] is even created.
+    pub fn enable_deterministic_mode(&mut self) {
+        self.deterministic = true;
+        self.physics.synchronous = true;
+    }
+
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    pub fn is_playing_back_input(&self) -> bool {
+        self.input_player.is_some()
+    }
+
+    /// Figures out this frame's delta time, picks up the latest physics snapshot and kicks off this frame's physics
+    /// ticks, and gathers up input that doesn't come from window events. When playing back a recording, the input
+    /// and timing come from the recording instead.
+    pub fn begin_frame(&mut self, real_frame_time: f64) -> f64 {
+        for callback in self.tasks.take_callbacks() {
+            callback(self);
+        }
+
+        // how long loading takes isn't deterministic, so recordings start once it's done
+        if self.is_loading() {
+            if let Some(recorder) = self.input_controller.recorder_mut() {
+                recorder.discard_current_frame();
+            }
+            return real_frame_time;
+        }
+
+        self.physics.receive();
+        if let Some(step_time) = self.physics.take_step_time() {
+            self.frame_counter.add_section_time("physics", step_time);
+        }
+
+        let recorded_frame = self
+            .input_player
+            .as_mut()
+            .and_then(|player| player.next_frame().cloned());
+        let (frame_time, ticks) = match recorded_frame {
+            Some(frame) => {
+                for event in frame.events {
+                    self.input_controller.raw_event(event);
+                }
+                (frame.frame_time, frame.ticks)
+            }
+            None => {
+                if self.input_player.take().is_some() {
+                    info!("Input playback finished");
+                    self.input_controller.set_playing_back(false);
+                    self.physics.synchronous =
+                        self.deterministic || self.input_controller.recorder_mut().is_some();
+                }
+
+                #[cfg(feature = "gamepad")]
+                if let Some(gamepad) = self.gamepad.as_mut() {
+                    gamepad.poll(&mut self.input_controller);
+                }
+
+                let frame_time = if self.deterministic {
+                    FIXED_FRAME_TIME
+                } else {
+                    self.settings.frame_pacing.frame_time(real_frame_time)
+                };
+                let ticks = self.frame_pacer.owe_time(
+                    frame_time,
+                    self.physics.paused,
+                    &self.settings.frame_pacing,
+                );
+                (frame_time, ticks)
+            }
+        };
+
+        self.physics.step(ticks);
+        self.physics
+            .advance_render_time(frame_time, self.settings.frame_pacing.max_catch_up_ticks);
+        self.input_controller.advance_key_repeat(frame_time);
+
+        if let Some(recorder) = self.input_controller.recorder_mut() {
+            recorder.end_frame(frame_time, ticks);
+        }
+
+        frame_time
+    }
+
+    pub fn window_focus_changed(&mut self, is_focused: bool) {
+        if !is_focused && !self.is_playing_back_input() {
+            self.input_controller.release_all();
+        }
+    }
+
+    /// Switches to drawing from `camera`, which gets its own spot in the camera uniforms for the rest of the frame.
+    pub fn update_camera_uniform(&mut self, camera: Camera, aspect_ratio: f32) {
+        let camera = camera.relative_to(self.graphics.instance_origin);
+        self.graphics.camera_slot = self
+            .graphics
+            .camera_uniforms
+            .push(camera.uniform(aspect_ratio));
+    }
+
+    /// Like [`AppState::update_camera_uniform()`], for the camera the scene is drawn from. It also gets where it was
+    /// looking last frame, for motion blur.
+    fn update_main_camera_uniform(&mut self, camera: Camera, aspect_ratio: f32) {
+        let origin = self.graphics.instance_origin;
+        let mut uniform = camera.relative_to(origin).uniform(aspect_ratio);
+        if self.motion_blur_enabled() {
+            uniform.previous_view_projection = self
+                .motion_history
+                .advance_camera(origin, uniform.view_projection.into())
+                .into();
+            uniform.shutter_fraction = self.settings.shutter_angle / MAX_SHUTTER_ANGLE;
+        } else {
+            // so turning it back on doesn't make everything look like it jumped
+            self.motion_history.clear();
+        }
+        self.graphics.camera_slot = self.graphics.camera_uniforms.push(uniform);
+    }
+
+    /// Stereo views and photos don't get blurred.
+    fn motion_blur_enabled(&self) -> bool {
+        self.settings.motion_blur
+            && self.settings.stereo_mode == StereoMode::Off
+            && self.photo_mode.is_none()
+    }
+
+    /// Draws how far everything moved on screen since last frame, then smears `target` along that.
+    fn render_motion_blur(&mut self, target: &RenderTarget) {
+        profile_scope!("render_motion_blur");
+
+        let (_, velocity_target) = self.graphics_controller.render_target_with_format(
+            VELOCITY_TARGET_NAME,
+            target.width(),
+            target.height(),
+            VELOCITY_TARGET_FORMAT,
+        );
+        velocity_target.clear();
+        self.render_entity_instances(&velocity_target, EntityPass::Velocity, |_| true);
+
+        let (_, blurred_target) = self.graphics_controller.render_target(
+            BLURRED_TARGET_NAME,
+            target.width(),
+            target.height(),
+        );
+        blurred_target.clear();
+        self.graphics_controller.render_fullscreen_textures(
+            &blurred_target,
+            &self.graphics.motion_blur_pipeline,
+            &[target.texture(), velocity_target.texture()],
+        );
+        // the sky's opaque, so this covers up all of what was there
+        self.graphics_controller
+            .composite(target, blurred_target.texture());
+    }
+
+    /// Draws the vignette, grain, and auto exposure over `target`.
+    fn render_post_effects(&mut self, target: &RenderTarget, delta: f64) {
+        profile_scope!("render_post_effects");
+
+        let effects = self.post_effects;
+        let (recreated, luminance_target) = self.graphics_controller.render_target_with_format(
+            LUMINANCE_TARGET_NAME,
+            1,
+            1,
+            LUMINANCE_TARGET_FORMAT,
+        );
+        // a new one starts out already adapted to whatever's on screen
+        let adaptation = if recreated {
+            1.0
+        } else {
+            effects.adaptation(delta)
+        };
+        let slot = self
+            .graphics
+            .post_effects_uniforms
+            .push(PostEffectsUniform::new(
+                &effects,
+                adaptation,
+                rand::random(),
+            ));
+        let uniform = self.graphics.post_effects_uniforms.binding(slot);
+
+        // never cleared, so it blends over what it's been up until now
+        if effects.auto_exposure {
+            let pipeline = &self.graphics.luminance_pipeline;
+            let scene = pipeline.texture_bind_group(0, target.texture());
+            self.graphics_controller.render_fullscreen_bind_groups(
+                &luminance_target,
+                pipeline,
+                [(&scene).into(), uniform],
+            );
+        }
+
+        let (_, effects_target) = self.graphics_controller.render_target(
+            POST_EFFECTS_TARGET_NAME,
+            target.width(),
+            target.height(),
+        );
+        effects_target.clear();
+        let pipeline = &self.graphics.post_effects_pipeline;
+        let scene = pipeline.texture_bind_group(0, target.texture());
+        let luminance = pipeline.texture_bind_group(2, luminance_target.texture());
+        self.graphics_controller.render_fullscreen_bind_groups(
+            &effects_target,
+            pipeline,
+            [(&scene).into(), uniform, (&luminance).into()],
+        );
+        self.graphics_controller
+            .composite(target, effects_target.texture());
+    }
+
+    pub fn render_simple_sky(&mut self, target: &RenderTarget) {
+        profile_scope!("render_simple_sky");
+
+        let color = GuiColor {
+            r: 0.0,
+            g: 0.0,
+            b: 0.0,
+            a: 1.0,
+        };
+
+        self.graphics.generic_vertices_2d.replace_contents(
+            Vertex2D::fill_screen(
+                color,
+                self.graphics
+                    .texture_provider
+                    .get_section_by_id(*WHITE_TEXTURE),
+            )
+            .to_vec(),
+        );
+
+        self.graphics_controller.render(
+            target,
+            &self.graphics.pipeline_2d,
+            PipelineBuffers {
+                vertices: &self.graphics.generic_vertices_2d,
+                instances: None,
+                indices: Some(&self.graphics.generic_quad_indices),
+                push_constants: &[],
+            },
+            [self.graphics.texture_provider.bind_group()],
+        );
+    }
+
+    /// Where the camera's watching from at `coord_time`: the real frame for working out light delays with, and the
+    /// one things get shown relative to, which can lag behind after a jump. Usually that's the user's, but the orbit
+    /// camera has its own.
+    fn view_frames(&self, coord_time: f64) -> (InertialFrame, InertialFrame) {
+        let universe = self.physics.universe();
+        if let Some(event) = self
+            .orbit_camera
+            .as_ref()
+            .and_then(|orbit_camera| orbit_camera.observer_event(universe, coord_time))
+        {
+            return (event.frame, event.frame);
+        }
+        let user_frame = universe
+            .entity_event_at_time(universe.get_user_entity(), coord_time)
+            .frame;
+        // the light delay's real, but where things end up on screen can ease over after a jump
+        (user_frame, self.frame_transition.apply(user_frame))
+    }
+
+    /// The coordinate time things get drawn as of: the moment being reviewed on the timeline if there is one, and
+    /// [`PhysicsThread::render_time()`] otherwise. The review only counts while the timeline's up to see it.
+    fn view_time(&self) -> f64 {
+        let timeline_shown =
+            self.replay_player.is_none() && !self.input_controller.is_mouse_locked();
+        self.timeline
+            .review_time()
+            .filter(|_| timeline_shown)
+            .unwrap_or_else(|| self.physics.render_time())
+    }
+
+    /// How fast the user's moving relative to the frame things are shown in, for [`Camera::velocity`].
+    fn camera_velocity(&self) -> Vector3<f32> {
+        let universe = self.physics.universe();
+        let (user_frame, shown_frame) = self.view_frames(self.view_time());
+        universe
+            .shown_frame(user_frame)
+            .relative_to(universe.shown_frame(shown_frame))
+            .velocity
+            .map(|v| v as f32)
+    }
+
+    fn update_frame_transition(&mut self, delta: f64) {
+        let universe = self.physics.universe();
+        let user_frame = universe
+            .entity_event_at_time(universe.get_user_entity(), self.view_time())
+            .frame;
+        self.frame_transition.update(
+            universe.user_entity_id,
+            user_frame,
+            delta,
+            self.settings.instant_frame_changes,
+        );
+    }
+
+    pub fn update_entity_model_instances(&mut self) {
+        profile_scope!("update_entity_model_instances");
+
+        for (_, list) in self.graphics.entity_model_instances.iter_mut() {
+            list.clear();
+        }
+
+        let universe = self.physics.universe();
+        // smoothed out between physics snapshots
+        let render_time = self.view_time();
+        let (user_frame, shown_frame) = self.view_frames(render_time);
+        let orbiting = self.orbit_camera.is_some();
+        let origin = self.graphics.instance_origin.cast().unwrap();
+        let motion_blur = self.motion_blur_enabled();
+        let lens_flare = self.settings.shader_features.lens_flare;
+
+        let new_model_instances: Vec<(
+            EntityId,
+            BBox3F64,
+            ModelId,
+            EntityInstance,
+            Option<FlareSource>,
+        )> = universe
+            .entities
+            .par_iter()
+            .filter_map(|(&entity_id, entity)| {
+                // the user's own ship would just be in the way of the camera, unless it's off somewhere else
+                if entity_id == universe.user_entity_id && !orbiting {
+                    return None;
+                }
+                let model_id = entity.model?;
+                let Some(model) = self.graphics.models.get(&model_id) else {
+                    warn!("Model '{}' does not exist", model_id);
+                    return None;
+                };
+
+                let frame = light_delayed_frame(universe, render_time, user_frame, |coord_time| {
+                    universe.entity_event_at_time(entity, coord_time).frame
+                });
+
+                // with light slowed down to the shown speed, which is what the shader expects too
+                let relative_frame = universe
+                    .shown_frame(frame)
+                    .relative_to(universe.shown_frame(shown_frame));
+                let relative_boost = lorentz_boost(relative_frame.velocity);
+
+                let contraction = vec3(
+                    1.0 / (relative_boost * Vector4::unit_x()).x as f32,
+                    1.0 / (relative_boost * Vector4::unit_y()).y as f32,
+                    1.0 / (relative_boost * Vector4::unit_z()).z as f32,
+                );
+
+                let local_matrix =
+                    Matrix4::from_nonuniform_scale(contraction.x, contraction.y, contraction.z)
+                        * entity.model_matrix;
+                let position = relative_frame.position.truncate();
+                let model_matrix =
+                    Matrix4::from_translation(position.map(|v| v as f32)) * local_matrix;
+                let relative_model_matrix =
+                    camera_relative_model_matrix(position, origin, local_matrix);
+                let previous_model_matrix = if motion_blur {
+                    self.motion_history.previous_model_matrix(
+                        entity_id,
+                        self.graphics.instance_origin,
+                        relative_model_matrix,
+                    )
+                } else {
+                    relative_model_matrix
+                };
+
+                let flare_source = if lens_flare {
+                    let offset = position - origin;
+                    let brightness = apparent_brightness(
+                        entity.has_tag(LIGHT_SOURCE_TAG),
+                        relative_frame.velocity,
+                        offset,
+                    );
+                    let size = model.bounds.transformed(&local_matrix).size();
+                    (brightness >= MIN_FLARE_BRIGHTNESS).then(|| FlareSource {
+                        position: offset.map(|v| v as f32),
+                        radius: Vector3::from(size).magnitude() / 2.0,
+                        color: entity.model_color.truncate().into(),
+                        brightness,
+                    })
+                } else {
+                    None
+                };
+
+                Some((
+                    entity_id,
+                    model.bounds.transformed(&model_matrix).into(),
+                    model_id,
+                    EntityInstance {
+                        model_matrix: relative_model_matrix.into(),
+                        velocity: relative_frame.velocity.map(|v| v as f32).into(),
+                        color: entity.model_color.into(),
+                        previous_model_matrix: previous_model_matrix.into(),
+                    },
+                    flare_source,
+                ))
+            })
+            .collect();
+
+        // anything that wasn't just updated isn't visible anymore
+        let visible_entity_ids = new_model_instances
+            .iter()
+            .map(|(entity_id, ..)| *entity_id)
+            .collect::<BTreeSet<_>>();
+        self.visible_entities
+            .retain(|entity_id| visible_entity_ids.contains(&entity_id));
+        if motion_blur {
+            self.motion_history.advance_models(
+                self.graphics.instance_origin,
+                new_model_instances
+                    .iter()
+                    .map(|(entity_id, _, _, instance, _)| {
+                        (*entity_id, instance.model_matrix.into())
+                    })
+                    .collect(),
+            );
+        }
+
+        // only the brightest ones, so a crowd of stars doesn't wash everything out
+        let mut flare_sources = new_model_instances
+            .iter()
+            .filter_map(|(.., flare_source)| *flare_source)
+            .collect::<Vec<_>>();
+        flare_sources.sort_by(|a, b| b.brightness.total_cmp(&a.brightness));
+        self.graphics.flare_instances = flare_sources
+            .into_iter()
+            .take(MAX_FLARES)
+            .flat_map(FlareSource::sprites)
+            .collect();
+
+        for (entity_id, bounds, model_id, instance, _) in new_model_instances {
+            self.visible_entities.update(entity_id, bounds);
+            self.graphics
+                .entity_model_instances
+                .entry(model_id)
+                .or_default()
+                .push(instance);
+        }
+    }
+
+    pub fn render_entities(&mut self, target: &RenderTarget) {
+        profile_scope!("render_entities");
+
+        let shader_features = self.settings.shader_features;
+        if !shader_features.depth_prepass {
+            let variant = shader_features.pipeline_variant(DepthMode::Normal);
+            self.render_entity_instances(target, EntityPass::Color(&variant), |_| true);
+            self.render_lens_flares(target);
+            return;
+        }
+
+        self.frame_counter.begin_section("prepass");
+        let variant = shader_features.pipeline_variant(DepthMode::Prepass);
+        self.render_entity_instances(
+            target,
+            EntityPass::Color(&variant),
+            EntityInstance::is_opaque,
+        );
+        self.frame_counter.end_section("prepass");
+
+        let variant = shader_features.pipeline_variant(DepthMode::Equal);
+        self.render_entity_instances(
+            target,
+            EntityPass::Color(&variant),
+            EntityInstance::is_opaque,
+        );
+        // see-through things can't go in the prepass, or they'd hide whatever's behind them
+        let variant = shader_features.pipeline_variant(DepthMode::Normal);
+        self.render_entity_instances(target, EntityPass::Color(&variant), |instance| {
+            !instance.is_opaque()
+        });
+        self.render_lens_flares(target);
+    }
+
+    /// Adds the flares found in [`AppState::update_entity_model_instances()`] over what's been drawn into `target`,
+    /// fading out wherever its depth buffer has something in front of their sources.
+    fn render_lens_flares(&mut self, target: &RenderTarget) {
+        let shader_features = self.settings.shader_features;
+        if !shader_features.lens_flare || self.graphics.flare_instances.is_empty() {
+            return;
+        }
+        let Some(depth_texture) = target.depth_texture() else {
+            return;
+        };
+
+        self.graphics
+            .flare_instance_buffer
+            .replace_contents(self.graphics.flare_instances.clone());
+        let pipeline = self
+            .graphics
+            .lens_flare_pipeline
+            .get(&flare_pipeline_variant(shader_features.log_depth));
+        let depth_bind_group = pipeline.texture_bind_group(0, depth_texture);
+        self.graphics_controller.render(
+            target,
+            pipeline,
+            PipelineBuffers {
+                vertices: &self.graphics.flare_quad,
+                instances: Some(&self.graphics.flare_instance_buffer),
+                indices: Some(&self.graphics.generic_quad_indices),
+                push_constants: &[],
+            },
+            [
+                (&depth_bind_group).into(),
+                self.graphics
+                    .camera_uniforms
+                    .binding(self.graphics.camera_slot),
+            ],
+        );
+    }
+
+    /// Draws the instances of each model that `filter` lets through, in `pass`.
+    fn render_entity_instances(
+        &mut self,
+        target: &RenderTarget,
+        pass: EntityPass,
+        filter: impl Fn(&EntityInstance) -> bool,
+    ) {
+        let pipeline = match pass {
+            EntityPass::Color(variant) => self.graphics.pipeline_3d.get(variant),
+            EntityPass::Velocity => &self.graphics.velocity_pipeline,
+        };
+
+        for (model_id, instances) in self.graphics.entity_model_instances.iter() {
+            if let Some(model) = self.graphics.models.get(model_id) {
+                let instances = instances
+                    .iter()
+                    .copied()
+                    .filter(|instance| filter(instance))
+                    .collect::<Vec<_>>();
+                if instances.is_empty() {
+                    continue;
+                }
+                self.graphics.instance_buffer.replace_contents(instances);
+                self.graphics_controller.render(
+                    target,
+                    pipeline,
+                    PipelineBuffers {
+                        vertices: &model.vertices.vertices,
+                        instances: Some(&self.graphics.instance_buffer),
+                        indices: Some(&model.vertices.indices),
+                        push_constants: &[],
+                    },
+                    [
+                        self.graphics
+                            .texture_provider
+                            .model_bind_group(model.texture_format)
+                            .into(),
+                        self.graphics
+                            .camera_uniforms
+                            .binding(self.graphics.camera_slot),
+                    ],
+                );
+            } else {
+                warn!("Model '{}' does not exist", model_id);
+            }
+        }
+    }
+
+    /// Receives whatever the [`StartupLoader`] has finished, and draws the loading screen.
+    fn render_loading_screen(&mut self) {
+        let Some(loader) = self.loader.as_mut() else {
+            return;
+        };
+
+        let mut script_loaded = false;
+        for asset in loader.poll() {
+            match asset {
+                LoadedAsset::Model(model_id, vertices) => {
+                    let bounds = vertices.bounds();
+                    let (texture_format, _) = self
+                        .graphics
+                        .texture_provider
+                        .get_model_section(model_id.name());
+                    let vertices =
+                        IndexedVertices::from_contents(&self.graphics_controller, vertices);
+                    self.graphics.insert_model(
+                        model_id,
+                        Model {
+                            bounds,
+                            texture_format,
+                            vertices,
+                        },
+                    );
+                }
+                LoadedAsset::Scenario(scenario) => {
+                    let LoadedScenario {
+                        name,
+                        universe,
+                        annotations,
+                        tour,
+                        docking,
+                        camera_rotation,
+                        script,
+                        post_effects,
+                    } = *scenario;
+                    info!("Loaded scenario '{}'", name);
+                    update_crash_context(|context| context.scenario = Some(name.clone()));
+                    self.scenario_name = name.clone();
+                    self.physics.edit(UniverseEdit::Replace(Box::new(universe)));
+                    self.undo_stack.clear();
+                    self.selected_entity = None;
+                    self.selection_group.clear();
+                    self.observers.clear();
+                    self.event_ordering.clear();
+                    self.causal_graph.clear();
+                    self.proximity.clear();
+                    self.maneuver.set(None);
+                    // the scenario's camera rotation takes over anyway
+                    self.orbit_camera = None;
+                    self.player_controller.rotation = camera_rotation;
+                    self.player_controller.gamma_history.clear();
+                    self.annotations = annotations;
+                    self.signals.clear();
+                    self.tour = GuidedTour::new(tour);
+                    self.docking = DockingChallenge::new(docking);
+                    self.post_effects = post_effects;
+                    self.script = script.and_then(|source| {
+                        ScriptEngine::new(name.as_str(), &source)
+                            .map_err(|error| {
+                                warn!("Couldn't run the script for {}: {}", name, error)
+                            })
+                            .ok()
+                    });
+                    script_loaded = true;
+                }
+            }
+        }
+        let multiplayer_launch = if script_loaded {
+            self.multiplayer_launch.take()
+        } else {
+            None
+        };
+
+        let stage = loader.stage();
+        let progress = loader.progress();
+        if loader.is_finished() {
+            self.loader = None;
+        }
+        if script_loaded {
+            self.run_script_hook(ScriptHook::Start);
+        }
+        if let Some(launch) = multiplayer_launch {
+            self.start_multiplayer(launch);
+        }
+        if self.loader.is_none() && self.benchmark.is_none() && self.net.is_none() {
+            if let Some((slot, saved_at)) = SaveGame::latest_autosave() {
+                self.session_restore.offer(slot, saved_at);
+            }
+        }
+
+        let (_, window_target) = self
+            .graphics_controller
+            .window_sized_render_target("render");
+        window_target.clear();
+
+        self.render_simple_sky(&window_target);
+
+        let mut gui_builder = GuiContext::new(
+            window_target.frame(),
+            &self.graphics.texture_provider,
+            &mut self.input_controller,
+        )
+        .builder();
+
+        gui_builder.element(TextLabel {
+            transform: GuiTransform {
+                position: UDim2::from_scale(0.5, 0.5),
+                size: UDim2::new(0.5, (0.0, 24.0)),
+                anchor_point: vec2(0.5, 1.0),
+                ..Default::default()
+            },
+            text: StyledText::from_format_string(&tr!("loading.stage", stage = stage)),
+            char_pixel_height: 16.0,
+            text_alignment: TextLabel::ALIGN_BOTTOM_CENTER,
+            ..Default::default()
+        });
+        gui_builder.element(ProgressBar {
+            transform: GuiTransform {
+                position: UDim2::new(0.5, (0.5, 8.0)),
+                size: UDim2::new(0.5, (0.0, 16.0)),
+                anchor_point: vec2(0.5, 0.0),
+                ..Default::default()
+            },
+            progress,
+            ..Default::default()
+        });
+
+        self.graphics
+            .gui_vertices
+            .replace_contents(gui_builder.finish());
+        self.graphics_controller.render(
+            &window_target,
+            &self.graphics.pipeline_2d,
+            self.graphics.gui_vertices.as_pipeline_buffers(),
+            [self.graphics.texture_provider.bind_group()],
+        );
+
+        let _ = self
+            .graphics_controller
+            .present_to_screen(window_target.texture());
+    }
+
+    pub fn render(&mut self, delta: f64) {
+        if self.is_loading() {
+            self.render_loading_screen();
+            return;
+        }
+        self.graphics.camera_uniforms.clear();
+        self.graphics.post_effects_uniforms.clear();
+
+        let universe = self.physics.universe();
+        let multiplayer = self.net.as_ref().map(|client| client.address.clone());
+        update_crash_context(|context| {
+            context.entity_count = universe.entities.len();
+            context.coord_time = universe.scenario_time(universe.time);
+            context.paused = self.physics.paused;
+            context.multiplayer = multiplayer;
+        });
+
+        if self
+            .input_controller
+            .action_pressed(Action::ToggleHudEditor)
+        {
+            self.hud_editor.active = !self.hud_editor.active;
+            self.audio.play(GuiSound::Toggle);
+        }
+        if self.hud_editor.active {
+            self.input_controller.report_in_a_menu();
+        }
+
+        if self.input_controller.action_pressed(Action::ToggleProfiler) {
+            self.toggle_profiler();
+        }
+        if self.input_controller.action_pressed(Action::CycleDebugPage) {
+            self.debug_page = self.debug_page.next();
+        }
+        if self.input_controller.action_pressed(Action::QuickSave) {
+            self.save_game(QUICKSAVE_SLOT);
+        }
+        if self.input_controller.action_pressed(Action::QuickLoad) {
+            self.load_game(QUICKSAVE_SLOT);
+        }
+        if self
+            .input_controller
+            .action_pressed(Action::ToggleReplayRecording)
+        {
+            self.toggle_replay_recording();
+            self.audio.play(GuiSound::Toggle);
+        }
+        if self
+            .input_controller
+            .action_pressed(Action::ToggleReplayPlayback)
+        {
+            self.toggle_replay_playback();
+            self.audio.play(GuiSound::Toggle);
+        }
+        // there's no chat box to type in while taking photos
+        if self.photo_mode.is_none() && self.input_controller.action_pressed(Action::OpenChat) {
+            self.chat_box.open(&mut self.input_controller);
+        }
+        if self
+            .input_controller
+            .action_pressed(Action::ToggleAuxiliaryWindow)
+        {
+            if self.auxiliary_window_wanted {
+                self.close_auxiliary_window();
+            } else {
+                self.auxiliary_window_wanted = true;
+            }
+        }
+        if self
+            .input_controller
+            .action_pressed(Action::CycleAuxiliaryView)
+        {
+            if let Some(view) = &mut self.auxiliary_view {
+                view.cycle_mode();
+            }
+        }
+        if self
+            .input_controller
+            .action_pressed(Action::TogglePhotoMode)
+        {
+            self.toggle_photo_mode();
+            self.audio.play(GuiSound::Toggle);
+        }
+        if self
+            .input_controller
+            .action_pressed(Action::ToggleOrbitCamera)
+        {
+            self.toggle_orbit_camera();
+            self.audio.play(GuiSound::Toggle);
+        }
+        if self.input_controller.action_pressed(Action::ToggleDoppler) {
+            let mut features = self.settings.shader_features;
+            features.doppler = !features.doppler;
+            self.set_shader_features(features);
+            self.audio.play(GuiSound::Toggle);
+            self.message_log.push_format(&if features.doppler {
+                tr!("shader.doppler_on")
+            } else {
+                tr!("shader.doppler_off")
+            });
+        }
+        if self.input_controller.action_pressed(Action::ToggleLobby) {
+            if self.net.is_some() {
+                self.lobby.toggle();
+                self.audio.play(GuiSound::Toggle);
+            } else {
+                self.message_log.push_format(&tr!("multiplayer.offline"));
+                self.audio.play(GuiSound::Error);
+            }
+        }
+
+        // the radial menu goes first so it can keep the mouse movement to itself
+        if let Some(action) = self
+            .quick_actions
+            .update(&mut self.input_controller, Action::QuickActionMenu)
+        {
+            let message = self
+                .player_controller
+                .perform_quick_action(action, &mut self.physics);
+            self.message_log.push_format(&message);
+        }
+
+        if let Some(photo_mode) = &mut self.photo_mode {
+            // everything stays frozen, including replays
+            photo_mode.update(&mut self.input_controller, delta);
+        } else if self.replay_player.is_some() {
+            self.update_replay(delta);
+        } else if let Some(benchmark) = &self.benchmark {
+            // benchmarks fly themselves
+            self.player_controller.rotation = benchmark.camera_rotation();
+            self.player_controller.update_camera();
+        } else {
+            self.update_orbit_camera(delta);
+            if self.orbit_camera.is_none() {
+                self.player_controller
+                    .update(&mut self.physics, &mut self.input_controller, delta);
+            }
+
+            if self.script.is_some() || !self.plugins.is_empty() {
+                let pressed_actions: Vec<Action> = self
+                    .input_controller
+                    .bindings
+                    .bindings
+                    .keys()
+                    .copied()
+                    .filter(|&action| self.input_controller.action_pressed(action))
+                    .collect();
+                for action in pressed_actions {
+                    self.run_script_hook(ScriptHook::Action(action));
+                }
+                for delivery in self.signals.update(self.physics.universe()) {
+                    self.run_script_hook(ScriptHook::Signal(delivery));
+                }
+                self.run_script_hook(ScriptHook::Tick(delta));
+            }
+
+            if let Some(recorder) = &mut self.replay_recorder {
+                recorder.record_frame(
+                    self.physics.universe().time,
+                    self.player_controller.rotation,
+                );
+                let removed = self.physics.take_removal_log();
+                recorder.record_removals(self.physics.universe(), removed);
+            }
+        }
+        self.update_multiplayer();
+        self.update_autosave(delta);
+        self.update_timeline_selection();
+        self.update_texture_eviction(delta);
+
+        let scene_size = self.scene_size();
+        let (_, scene_target) =
+            self.graphics_controller
+                .render_target("render", scene_size.width, scene_size.height);
+        scene_target.clear();
+        // with the 3D view at its own resolution, the gui gets a full resolution target to itself, which is put on top
+        // of the 3D view at the end
+        let gui_target = if scene_size == self.graphics_controller.window_size() {
+            Rc::clone(&scene_target)
+        } else {
+            let (_, gui_target) = self.graphics_controller.window_sized_render_target("gui");
+            gui_target.clear();
+            gui_target
+        };
+
+        self.render_simple_sky(&scene_target);
+
+        // online, the only thing anyone gets to change is their own worldline
+        if self.replay_player.is_none()
+            && self.benchmark.is_none()
+            && self.net.is_none()
+            && self.photo_mode.is_none()
+        {
+            // everything here works from the user's point of view, not the orbit camera's
+            if self.orbit_camera.is_none() {
+                self.update_world_editing(gui_target.aspect_ratio(), gui_target.frame());
+            }
+            self.update_tour(delta);
+            self.update_docking();
+        }
+        // replays only show what already happened
+        if self.replay_player.is_some() {
+            self.proximity.clear();
+            self.maneuver.set(None);
+        } else {
+            self.rebase_origin();
+            self.proximity.update(self.physics.universe(), delta);
+            if let Some(job) = self.maneuver.update(self.physics.universe(), delta) {
+                self.tasks.spawn(
+                    move || job.run(),
+                    |app, preview| app.maneuver.finish(preview),
+                );
+            }
+        }
+
+        // 3d rendering
+        {
+            let camera = match &self.photo_mode {
+                Some(photo_mode) => photo_mode.camera(),
+                None => self.player_controller.camera,
+            };
+            let camera = Camera {
+                velocity: self.camera_velocity(),
+                ..camera
+            };
+            self.graphics.instance_origin = camera.position;
+            self.update_main_camera_uniform(camera, scene_target.aspect_ratio());
+            self.frame_counter.begin_section("instances");
+            self.update_frame_transition(delta);
+            self.update_entity_model_instances();
+            self.add_spawn_preview_instance(gui_target.aspect_ratio(), gui_target.frame());
+            self.frame_counter.end_section("instances");
+            self.frame_counter.begin_section("submit");
+            // photos only ever get the one view
+            match self.settings.stereo_mode {
+                StereoMode::Off => self.render_entities(&scene_target),
+                _ if self.photo_mode.is_some() => self.render_entities(&scene_target),
+                mode => self.render_stereo(&scene_target, camera, mode),
+            }
+            self.frame_counter.end_section("submit");
+            if self.motion_blur_enabled() {
+                self.frame_counter.begin_section("motion_blur");
+                self.render_motion_blur(&scene_target);
+                self.frame_counter.end_section("motion_blur");
+            }
+            if self.post_effects.is_active() && self.photo_mode.is_none() {
+                self.render_post_effects(&scene_target, delta);
+            }
+        }
+
+        if self.photo_mode.is_some() {
+            self.render_photo_mode(&scene_target);
+            self.frame_counter.tick();
+            self.profiler.new_frame();
+            return;
+        }
+
+        // 2d rendering
+        {
+            profile_scope!("gui");
+
+            let [debug_transform, messages_transform, timeline_transform, palette_transform, lobby_transform, plugins_transform, session_transform, observers_transform, tour_transform, ordering_transform, proximity_transform, docking_transform, angular_transform, gamma_transform, causal_transform] =
+                [
+                    "debug",
+                    "messages",
+                    "timeline",
+                    "palette",
+                    "lobby",
+                    "plugins",
+                    "session",
+                    "observers",
+                    "tour",
+                    "ordering",
+                    "proximity",
+                    "docking",
+                    "angular",
+                    "gamma",
+                    "causal",
+                ]
+                .map(|name| self.hud_transform(name));
+
+            self.frame_counter.begin_section("gui");
+            let plugin_panel_text = self.plugin_panel_text();
+            self.observers.update(self.physics.universe());
+            let observer_table_text = self.observer_table_text();
+            let tour_panel_text = self.tour_panel_text();
+            let waypoint_markers = self.waypoint_markers();
+            let event_ordering_text = self.event_ordering_text();
+            let causal_graph_text = self.causal_graph_text();
+            let proximity_text = self.proximity_text();
+            let docking_text = self.docking_text();
+            let angular_size_text = self.angular_size_text();
+            let gamma_history_text = self.gamma_history_text();
+            let render_stats = self.graphics_controller.take_render_stats();
+            let debug_page_text = self.debug_page_text(render_stats);
+            let ruler_preview = self.ruler_preview(gui_target.aspect_ratio(), gui_target.frame());
+            let (_, view_frame) = self.view_frames(self.physics.universe().time);
+            let mut gui_builder = GuiContext::new(
+                gui_target.frame(),
+                &self.graphics.texture_provider,
+                &mut self.input_controller,
+            )
+            .with_sound_sender(self.audio.gui_sender())
+            .builder_with_capacity(self.last_gui_primitive_count);
+
+            // trails and annotations sit under everything else
+            if self.replay_player.is_none() {
+                let universe = self.physics.universe();
+                for &entity_id in self.observers.entity_ids.iter() {
+                    let Some((color, _)) = self.observers.color(entity_id) else {
+                        continue;
+                    };
+                    render_observer_trail(
+                        &mut gui_builder,
+                        &self.player_controller.camera,
+                        gui_target.aspect_ratio(),
+                        &self.observers.trail(universe, entity_id, view_frame),
+                        color,
+                    );
+                }
+                render_observer_trail(
+                    &mut gui_builder,
+                    &self.player_controller.camera,
+                    gui_target.aspect_ratio(),
+                    &self.maneuver.trail(view_frame),
+                    MANEUVER_TRAIL_COLOR,
+                );
+            }
+            if let Some((start, end)) = ruler_preview {
+                render_ruler_preview(
+                    &mut gui_builder,
+                    &self.player_controller.camera,
+                    gui_target.aspect_ratio(),
+                    start,
+                    end,
+                );
+            }
+            if let Some(step) = self.tour.current().filter(|_| self.replay_player.is_none()) {
+                if let Some(bounds) = step
+                    .entity_id
+                    .and_then(|entity_id| self.visible_entities.bounds(entity_id))
+                {
+                    render_pointer(
+                        &mut gui_builder,
+                        &self.player_controller.camera,
+                        gui_target.aspect_ratio(),
+                        bounds.to_f32().center().into(),
+                        &localize(&step.text),
+                    );
+                }
+            }
+            if let Some((target, status)) = self
+                .docking
+                .target
+                .filter(|_| self.replay_player.is_none())
+                .zip(self.docking.status(self.physics.universe()))
+            {
+                if let Some(bounds) = self.visible_entities.bounds(target.entity_id) {
+                    render_pointer(
+                        &mut gui_builder,
+                        &self.player_controller.camera,
+                        gui_target.aspect_ratio(),
+                        bounds.to_f32().center().into(),
+                        &tr!(
+                            "docking.pointer",
+                            distance = format!("{:.2}", status.distance()),
+                            closing = format!("{:.4}", status.closing_speed)
+                        ),
+                    );
+                }
+            }
+            for (point, text) in waypoint_markers.iter() {
+                render_pointer(
+                    &mut gui_builder,
+                    &self.player_controller.camera,
+                    gui_target.aspect_ratio(),
+                    *point,
+                    text,
+                );
+            }
+            for annotation in self.annotations.iter() {
+                let Some(bounds) = self.visible_entities.bounds(annotation.entity_id) else {
+                    continue;
+                };
+                render_entity_callout(
+                    &mut gui_builder,
+                    &self.player_controller.camera,
+                    gui_target.aspect_ratio(),
+                    bounds,
+                    &localize(&annotation.text),
+                );
+            }
+            if let Some(client) = &self.net {
+                for player in client.players.iter() {
+                    if Some(player.entity_id) == client.entity_id {
+                        continue;
+                    }
+                    let Some(bounds) = self.visible_entities.bounds(player.entity_id) else {
+                        continue;
+                    };
+                    render_entity_callout(
+                        &mut gui_builder,
+                        &self.player_controller.camera,
+                        gui_target.aspect_ratio(),
+                        bounds,
+                        &format!("§b{}", player.name),
+                    );
+                }
+            }
+            // where it is now, as far as the user can tell
+            for alert in self.proximity.alerts.iter() {
+                let Some(bounds) = self.visible_entities.bounds(alert.entity_id) else {
+                    continue;
+                };
+                render_entity_callout(
+                    &mut gui_builder,
+                    &self.player_controller.camera,
+                    gui_target.aspect_ratio(),
+                    bounds,
+                    &tr!(
+                        "proximity.callout",
+                        time = format!("{:.1}", alert.time_to_closest)
+                    ),
+                );
+            }
+            let gui_frame = gui_target.frame();
+            let white = gui_builder.context.white();
+            for &entity_id in self.selection_group.entity_ids.iter() {
+                let Some(bounds) = self.visible_entities.bounds(entity_id) else {
+                    continue;
+                };
+                let screen_point = self.player_controller.camera.world_to_screen_point(
+                    gui_target.aspect_ratio(),
+                    bounds.to_f32().center().into(),
+                );
+                if screen_point.z <= 0.0 {
+                    continue;
+                }
+                gui_builder.element(TextureFrame {
+                    transform: GuiTransform {
+                        anchor_point: vec2(0.5, 0.5),
+                        ..GuiTransform::from_absolute(
+                            vec2(screen_point.x * gui_frame.x, screen_point.y * gui_frame.y),
+                            vec2(GROUP_MARKER_SIZE, GROUP_MARKER_SIZE),
+                        )
+                    },
+                    color: GuiColor::YELLOW,
+                    section: white,
+                });
+            }
+            let cursor = gui_builder.context.input_controller.cursor_position();
+            if let Some(selection_box) = self
+                .selection_group
+                .selection_box(vec2(cursor.x / gui_frame.x, cursor.y / gui_frame.y))
+            {
+                gui_builder.element(TextureFrame {
+                    transform: GuiTransform {
+                        position: UDim2::from_scale(selection_box.min()[0], selection_box.min()[1]),
+                        size: UDim2::from_scale(selection_box.size()[0], selection_box.size()[1]),
+                        ..Default::default()
+                    },
+                    color: GuiColor::YELLOW.with_alpha(0.2),
+                    section: white,
+                });
+            }
+            if let Some(entity_id) = self.selected_entity {
+                if let Some(bounds) = self.visible_entities.bounds(entity_id) {
+                    let prefab = self
+                        .physics
+                        .universe()
+                        .entities
+                        .get(&entity_id)
+                        .and_then(|entity| entity.prefab);
+                    let text = match prefab {
+                        Some(prefab) => tr!("selection.callout_prefab", prefab = prefab),
+                        None => tr!("selection.callout"),
+                    };
+                    render_entity_callout(
+                        &mut gui_builder,
+                        &self.player_controller.camera,
+                        gui_target.aspect_ratio(),
+                        bounds,
+                        &text,
+                    );
+                }
+            }
+
+            // before the menus, so Escape closes the chat and nothing else
+            let (messages_position, messages_size) =
+                gui_builder.context.absolute(messages_transform);
+            let chat_transform = GuiTransform::from_absolute(
+                messages_position + vec2(0.0, messages_size.y + CHAT_BOX_GAP),
+                vec2(messages_size.x, CHAT_BOX_HEIGHT),
+            );
+            // commands can change anything, so they wait until the gui is done with
+            let mut console_command = None;
+            if let Some(text) = self.chat_box.render(&mut gui_builder, chat_transform) {
+                if text.starts_with('/') {
+                    console_command = Some(text);
+                } else if let Some(client) = &mut self.net {
+                    client.send(&ClientMessage::Chat(text));
+                } else {
+                    self.message_log.push_format(&tr!("chat.offline"));
+                }
+            }
+
+            let session_choice = self
+                .session_restore
+                .render(&mut gui_builder, session_transform);
+
+            self.gui.render(&mut gui_builder);
+            if self.replay_player.is_none() && self.net.is_none() {
+                self.spawn_palette
+                    .render(&mut gui_builder, palette_transform);
+            }
+            if let Some(client) = &self.net {
+                self.lobby.render(
+                    &mut gui_builder,
+                    lobby_transform,
+                    client,
+                    &self.visible_entities,
+                );
+            }
+
+            if let Some(text) = &plugin_panel_text {
+                gui_builder.element(TextLabel {
+                    transform: plugins_transform,
+                    text: StyledText::from_format_string(text),
+                    char_pixel_height: 16.0,
+                    text_alignment: plugins_transform.anchor_point,
+                    background_color: GuiColor::BLACK.with_alpha(0.75),
+                    background_type: TextBackgroundType::BoundingBoxPerLine,
+                    overflow: TextOverflow::Ellipsis,
+                });
+                // in a column down the left side, since the panels can come from more than one plugin
+                let corner = plugins_transform.absolute_position(gui_frame);
+                for (index, &texture_id) in self.shown_plugin_icons.iter().enumerate() {
+                    if !gui_builder.context.texture_provider.has_texture(texture_id) {
+                        continue;
+                    }
+                    gui_builder.element(TextureFrame {
+                        transform: GuiTransform::from_absolute(
+                            corner + vec2(-PLUGIN_ICON_SIZE, index as f32 * PLUGIN_ICON_SIZE),
+                            vec2(PLUGIN_ICON_SIZE, PLUGIN_ICON_SIZE),
+                        ),
+                        color: GuiColor::WHITE,
+                        section: gui_builder
+                            .context
+                            .texture_provider
+                            .get_section_by_id(texture_id),
+                    });
+                }
+            }
+
+            if let Some(text) = &tour_panel_text {
+                gui_builder.element(TextLabel {
+                    transform: tour_transform,
+                    text: StyledText::from_format_string(text),
+                    char_pixel_height: 16.0,
+                    text_alignment: tour_transform.anchor_point,
+                    background_color: GuiColor::BLACK.with_alpha(0.75),
+                    background_type: TextBackgroundType::BoundingBoxPerLine,
+                    overflow: TextOverflow::Ellipsis,
+                });
+            }
+            if let Some(text) = &observer_table_text {
+                gui_builder.element(TextLabel {
+                    transform: observers_transform,
+                    text: StyledText::from_format_string(text),
+                    char_pixel_height: 16.0,
+                    text_alignment: observers_transform.anchor_point,
+                    background_color: GuiColor::BLACK.with_alpha(0.75),
+                    background_type: TextBackgroundType::BoundingBoxPerLine,
+                    overflow: TextOverflow::Ellipsis,
+                });
+            }
+
+            if let Some(text) = &proximity_text {
+                gui_builder.element(TextLabel {
+                    transform: proximity_transform,
+                    text: StyledText::from_format_string(text),
+                    char_pixel_height: 16.0,
+                    text_alignment: proximity_transform.anchor_point,
+                    background_color: GuiColor::BLACK.with_alpha(0.75),
+                    background_type: TextBackgroundType::BoundingBoxPerLine,
+                    overflow: TextOverflow::Ellipsis,
+                });
+            }
+
+            if let Some(text) = &docking_text {
+                gui_builder.element(TextLabel {
+                    transform: docking_transform,
+                    text: StyledText::from_format_string(text),
+                    char_pixel_height: 16.0,
+                    text_alignment: docking_transform.anchor_point,
+                    background_color: GuiColor::BLACK.with_alpha(0.75),
+                    background_type: TextBackgroundType::BoundingBoxPerLine,
+                    overflow: TextOverflow::Ellipsis,
+                });
+            }
+
+            if let Some(text) = &angular_size_text {
+                gui_builder.element(TextLabel {
+                    transform: angular_transform,
+                    text: StyledText::from_format_string(text),
+                    char_pixel_height: 16.0,
+                    text_alignment: angular_transform.anchor_point,
+                    background_color: GuiColor::BLACK.with_alpha(0.75),
+                    background_type: TextBackgroundType::BoundingBoxPerLine,
+                    overflow: TextOverflow::Ellipsis,
+                });
+            }
+
+            if let Some(text) = &gamma_history_text {
+                let (position, size) = gamma_transform.absolute(gui_builder.context.frame);
+                gui_builder.element(TextLabel {
+                    transform: GuiTransform::from_absolute(position, vec2(size.x, 20.0)),
+                    text: StyledText::from_format_string(text),
+                    char_pixel_height: 16.0,
+                    text_alignment: TextLabel::ALIGN_TOP_LEFT,
+                    background_color: GuiColor::BLACK.with_alpha(0.75),
+                    background_type: TextBackgroundType::BoundingBoxPerLine,
+                    overflow: TextOverflow::Ellipsis,
+                });
+                let history = &self.player_controller.gamma_history;
+                // starting from 1, so a flat line at rest stays at the bottom
+                let peak = history
+                    .peak()
+                    .unwrap_or(1.0)
+                    .max(1.0 + GAMMA_GRAPH_MIN_SPAN);
+                gui_builder.element(Graph {
+                    transform: GuiTransform::from_absolute(
+                        position + vec2(0.0, 24.0),
+                        vec2(size.x, size.y - 24.0),
+                    ),
+                    values: history.values().map(|gamma| gamma as f32).collect(),
+                    range: (1.0, peak as f32),
+                    line_color: GuiColor::YELLOW,
+                    background_color: GuiColor::BLACK.with_alpha(0.75),
+                    line_thickness: 2.0,
+                    padding: 4.0,
+                });
+            }
+
+            if let Some(text) = &event_ordering_text {
+                let (position, size) = ordering_transform.absolute(gui_builder.context.frame);
+                gui_builder.element(TextLabel {
+                    transform: GuiTransform::from_absolute(position, vec2(size.x, 100.0)),
+                    text: StyledText::from_format_string(text),
+                    char_pixel_height: 16.0,
+                    text_alignment: TextLabel::ALIGN_TOP_LEFT,
+                    background_color: GuiColor::BLACK.with_alpha(0.75),
+                    background_type: TextBackgroundType::BoundingBoxPerLine,
+                    overflow: TextOverflow::Ellipsis,
+                });
+                let diagram_size = (size.y - 140.0).min(size.x);
+                render_minkowski_diagram(
+                    &mut gui_builder,
+                    position + vec2((size.x - diagram_size) / 2.0, 104.0),
+                    diagram_size,
+                    &self.event_ordering,
+                );
+                let speed = self.event_ordering.observer_speed;
+                if let Some(speed) = self.ordering_slider.render(
+                    &mut gui_builder,
+                    GuiTransform::from_absolute(
+                        position + vec2(0.0, size.y - 28.0),
+                        vec2(size.x, 28.0),
+                    ),
+                    -MAX_OBSERVER_SPEED,
+                    MAX_OBSERVER_SPEED,
+                    speed,
+                    &tr!("ordering.observer_speed", speed = format!("{speed:+.2}")),
+                ) {
+                    self.event_ordering.observer_speed = speed;
+                }
+            }
+
+            if let Some(text) = &causal_graph_text {
+                let (position, size) = causal_transform.absolute(gui_builder.context.frame);
+                let text_height = text.lines().count() as f32 * 20.0 + 4.0;
+                gui_builder.element(TextLabel {
+                    transform: GuiTransform::from_absolute(position, vec2(size.x, text_height)),
+                    text: StyledText::from_format_string(text),
+                    char_pixel_height: 16.0,
+                    text_alignment: TextLabel::ALIGN_TOP_LEFT,
+                    background_color: GuiColor::BLACK.with_alpha(0.75),
+                    background_type: TextBackgroundType::BoundingBoxPerLine,
+                    overflow: TextOverflow::Ellipsis,
+                });
+                render_causal_graph(
+                    &mut gui_builder,
+                    position + vec2(0.0, text_height + 4.0),
+                    vec2(size.x, size.y - text_height - 4.0),
+                    &self.causal_graph,
+                );
+            }
+
+            self.message_log.pull_console_messages();
+            let clicked_link =
+                self.message_log
+                    .render(&mut gui_builder, messages_transform, self.chat_box.open);
+            self.quick_actions.render(&mut gui_builder);
+
+            if let Some(player) = &mut self.replay_player {
+                let observer_index = player
+                    .replay
+                    .universe
+                    .entities
+                    .range(..player.observer)
+                    .count();
+                let observer_label = if player.observer == player.replay.universe.user_entity_id {
+                    tr!("replay.recorded_frame")
+                } else {
+                    tr!(
+                        "replay.entity_frame",
+                        index = observer_index + 1,
+                        count = player.replay.universe.entities.len()
+                    )
+                };
+                let label = tr!(
+                    "replay.label",
+                    name = player.name,
+                    time = format!("{:.1}", player.time - player.replay.start_time),
+                    duration = format!("{:.1}", player.replay.duration()),
+                    speed = player.speed,
+                    paused = if player.playing {
+                        String::new()
+                    } else {
+                        tr!("replay.paused")
+                    },
+                    observer = observer_label,
+                    free_camera = if player.free_camera {
+                        tr!("replay.free_camera")
+                    } else {
+                        String::new()
+                    },
+                );
+                if let Some(seek_time) = self.replay_scrubber.render(
+                    &mut gui_builder,
+                    timeline_transform,
+                    player.replay.start_time,
+                    player.replay.end_time,
+                    player.time,
+                    &label,
+                ) {
+                    player.seek(seek_time);
+                }
+            } else if !gui_builder.context.input_controller.is_mouse_locked() {
+                self.timeline.render(
+                    &mut gui_builder,
+                    timeline_transform,
+                    &self.physics.universe().get_user_entity().worldline,
+                    self.physics.universe().time,
+                );
+            }
+
+            self.frame_counter.tick();
+            if let Some(benchmark) = &mut self.benchmark {
+                benchmark.record_frame(&self.frame_counter, render_stats);
+            }
+
+            let report_string = if let Some(PerformanceReport {
+                mean,
+                slowest,
+                fastest,
+                p50,
+                p95,
+                p99,
+                ref sections,
+                ..
+            }) = self.last_performance_report.1
+            {
+                let ms = |time: Duration| time.as_micros() as f64 / 1000.0;
+                let mean_ms = ms(mean);
+                let slowest_ms = ms(slowest);
+                let fastest_ms = ms(fastest);
+
+                let mean_fps = (1.0 / mean.as_secs_f64()) as u32;
+                let slowest_fps = (1.0 / slowest.as_secs_f64()) as u32;
+                let fastest_fps = (1.0 / fastest.as_secs_f64()) as u32;
+
+                let section_breakdown = sections
+                    .iter()
+                    .map(|&(name, time)| format!("§7{name} §f{}ms", ms(time)))
+                    .collect::<Vec<_>>()
+                    .join(" §8| ");
+
+                format!("§b{mean_ms}ms/{mean_fps}fps §r(§a↑{fastest_ms}ms/{fastest_fps}fps§r | §c↓{slowest_ms}ms/{slowest_fps}fps§r)\n§7p50 §f{}ms §8| §7p95 §f{}ms §8| §7p99 §f{}ms\n{section_breakdown}", ms(p50), ms(p95), ms(p99))
+            } else {
+                "...".to_owned()
+            };
+
+            if self.last_performance_report.0.elapsed() > Duration::from_millis(1000) {
+                self.last_performance_report.1 = self.frame_counter.flush();
+                self.last_performance_report.0 = Instant::now();
+
+                debug!("{}", StyledText::from_format_string(&report_string));
+            }
+
+            if let Some(debug_text) = &debug_page_text {
+                gui_builder.element(TextLabel {
+                    transform: debug_transform,
+                    text: StyledText::from_format_string(&format!("{debug_text}\n{report_string}")),
+                    char_pixel_height: 16.0,
+                    text_alignment: debug_transform.anchor_point,
+                    background_color: GuiColor::BLACK.with_alpha(0.75),
+                    background_type: TextBackgroundType::BoundingBoxPerLine,
+                    overflow: TextOverflow::Ellipsis,
+                });
+            }
+
+            let mut layout_changed = false;
+            if self.hud_editor.active {
+                for (name, default, size) in hud_widgets() {
+                    layout_changed |= self.hud_editor.edit_widget(
+                        &mut gui_builder,
+                        &mut self.settings.hud_layout,
+                        name,
+                        default,
+                        size,
+                        self.settings.gui_scale,
+                    );
+                }
+            }
+
+            self.cursor_style.render(&mut gui_builder);
+            self.audio.update();
+
+            self.last_gui_primitive_count = gui_builder.primitive_count();
+            let finished_vertices = gui_builder.finish();
+            if layout_changed {
+                self.save_settings();
+            }
+            if let Some(text) = console_command {
+                self.message_log.echo_command(&text);
+                self.run_console_command(&text[1..]);
+            }
+            if let Some(link) = clicked_link {
+                self.follow_message_link(link);
+            }
+            if let Some(SessionRestoreChoice::Restore(slot)) = session_choice {
+                self.load_game(&slot);
+            }
+            self.frame_counter.end_section("gui");
+
+            self.frame_counter.begin_section("submit");
+            profile_scope!("render_gui");
+            self.graphics
+                .gui_vertices
+                .replace_contents(finished_vertices);
+            self.graphics_controller.render(
+                &gui_target,
+                &self.graphics.pipeline_2d,
+                self.graphics.gui_vertices.as_pipeline_buffers(),
+                [self.graphics.texture_provider.bind_group()],
+            );
+        }
+
+        let screen_target = if Rc::ptr_eq(&gui_target, &scene_target) {
+            scene_target
+        } else {
+            profile_scope!("composite");
+            let (_, composite_target) = self
+                .graphics_controller
+                .window_sized_render_target("composite");
+            composite_target.clear();
+            self.graphics_controller
+                .composite(&composite_target, scene_target.texture());
+            self.graphics_controller
+                .composite(&composite_target, gui_target.texture());
+            composite_target
+        };
+        {
+            profile_scope!("present_to_screen");
+            let _ = self
+                .graphics_controller
+                .present_to_screen(screen_target.texture());
+        }
+        self.render_auxiliary_view();
+        self.frame_counter.end_section("submit");
+
+        self.profiler.new_frame();
+    }
+
+    /// Saves the settings in the background.
+    pub fn save_settings(&mut self) {
+        let settings = self.settings.clone();
+        let generation = Arc::clone(&self.settings_save_generation);
+        let this_generation = {
+            let mut generation = generation.lock().unwrap();
+            *generation += 1;
+            *generation
+        };
+
+        self.tasks.spawn(
+            move || {
+                let generation = generation.lock().unwrap();
+                if *generation == this_generation {
+                    settings.save()
+                } else {
+                    Ok(())
+                }
+            },
+            |_, result| {
+                if let Err(error) = result {
+                    warn!("Couldn't save settings: {}", error);
+                }
+            },
+        );
+    }
+
+    /// Saves the universe and player to a save slot. The writing happens in the background.
+    pub fn save_game(&mut self, slot: &str) {
+        if self.is_loading() {
+            return;
+        }
+        if self.replay_player.is_some() {
+            self.message_log.push_format(&tr!("save.replay_active"));
+            return;
+        }
+
+        let save_game = SaveGame::new(
+            self.physics.universe().clone(),
+            self.player_controller.to_save(),
+        );
+        let slot = slot.to_owned();
+        self.tasks.spawn(
+            move || {
+                let result = save_game.save(&slot);
+                (slot, result)
+            },
+            |app, (slot, result)| match result {
+                Ok(()) => app.message_log.push_format(&tr!("save.saved", slot = slot)),
+                Err(error) => warn!("Couldn't save to slot {}: {}", slot, error),
+            },
+        );
+    }
+
+    /// What gets autosaved, or `None` if now's not a good time.
+    fn autosave_game(&self) -> Option<SaveGame> {
+        // replays and benchmarks aren't the user's session, and online the universe isn't theirs to keep
+        if !self.settings.autosave.enabled
+            || self.is_loading()
+            || self.replay_player.is_some()
+            || self.benchmark.is_some()
+            || self.net.is_some()
+        {
+            return None;
+        }
+        Some(SaveGame::new(
+            self.physics.universe().clone(),
+            self.player_controller.to_save(),
+        ))
+    }
+
+    /// When an event's picked on the timeline, jumps the view back to it and says what happened there.
+    fn update_timeline_selection(&mut self) {
+        let selected = self.timeline.selected_event_index();
+        if selected == self.timeline_event {
+            return;
+        }
+        self.timeline_event = selected;
+        let Some(index) = selected else {
+            return;
+        };
+        let Some(&event) = self
+            .physics
+            .universe()
+            .get_user_entity()
+            .worldline
+            .events()
+            .get(index)
+        else {
+            return;
+        };
+
+        self.timeline.set_review_time(event.frame.position.w);
+        let kind = match event.kind {
+            WorldlineEventKind::Inertial => tr!("timeline.event_inertial"),
+            WorldlineEventKind::Acceleration(acceleration) => tr!(
+                "timeline.event_acceleration",
+                acceleration = format!("{:.3}", acceleration.magnitude())
+            ),
+            WorldlineEventKind::Circular(_) => tr!("timeline.event_circular"),
+        };
+        self.message_log.push_format(&tr!(
+            "timeline.event",
+            index = index,
+            kind = kind,
+            proper_time = format!("{:.2}", event.proper_time)
+        ));
+    }
+
+    /// Throws unused textures out of the atlas every [`TEXTURE_EVICTION_INTERVAL`], once everything's loaded.
+    fn update_texture_eviction(&mut self, delta: f64) {
+        if self.loader.is_some() {
+            return;
+        }
+        self.texture_eviction_timer += delta;
+        if self.texture_eviction_timer < TEXTURE_EVICTION_INTERVAL {
+            return;
+        }
+        self.texture_eviction_timer = 0.0;
+
+        let evicted = self.graphics.texture_provider.evict_unused();
+        if evicted > 0 {
+            debug!("Evicted {} unused textures", evicted);
+        }
+    }
+
+    /// Autosaves in the background every so often, going by the autosave settings.
+    fn update_autosave(&mut self, delta: f64) {
+        self.autosave_timer += delta;
+        if self.autosave_timer < self.settings.autosave.interval {
+            return;
+        }
+        self.autosave_timer = 0.0;
+
+        let Some(save_game) = self.autosave_game() else {
+            return;
+        };
+        let retention = self.settings.autosave.retention;
+        self.tasks.spawn(
+            move || save_game.autosave(retention),
+            |_, result| match result {
+                Ok(slot) => info!("Autosaved to slot {}", slot),
+                Err(error) => warn!("Couldn't autosave: {}", error),
+            },
+        );
+    }
+
+    /// Autosaves one last time, right away, so the session can be restored next time.
+    pub fn save_session(&self) {
+        let Some(save_game) = self.autosave_game() else {
+            return;
+        };
+        match save_game.autosave(self.settings.autosave.retention) {
+            Ok(slot) => info!("Saved the session to slot {}", slot),
+            Err(error) => warn!("Couldn't save the session: {}", error),
+        }
+    }
+
+    /// Loads a save slot in the background, replacing the universe once it's read.
+    pub fn load_game(&mut self, slot: &str) {
+        if self.is_loading() {
+            return;
+        }
+        if self.net.is_some() {
+            self.message_log.push_format(&tr!("save.load_multiplayer"));
+            return;
+        }
+        // loading anything else means the last session's been passed on
+        self.session_restore.slot = None;
+        self.leave_photo_mode();
+
+        let slot = slot.to_owned();
+        self.tasks.spawn(
+            move || {
+                let result = SaveGame::load(&slot);
+                (slot, result)
+            },
+            |app, (slot, result)| match result {
+                Ok(save_game) => {
+                    app.stop_replay();
+                    app.finish_replay_recording();
+                    app.physics
+                        .edit(UniverseEdit::Replace(Box::new(save_game.universe)));
+                    app.undo_stack.clear();
+                    app.selected_entity = None;
+                    app.selection_group.clear();
+                    app.observers.clear();
+                    app.event_ordering.clear();
+                    app.causal_graph.clear();
+                    app.proximity.clear();
+                    app.maneuver.set(None);
+                    app.leave_orbit_camera();
+                    app.player_controller.load_save(save_game.player);
+                    app.timeline.clear();
+                    app.message_log
+                        .push_format(&tr!("save.loaded", slot = slot));
+                }
+                Err(error) => {
+                    let slots = SaveGame::slots();
+                    if slots.is_empty() {
+                        warn!("Couldn't load slot {}: {}", slot, error);
+                    } else {
+                        warn!(
+                            "Couldn't load slot {}: {} (saved slots: {})",
+                            slot,
+                            error,
+                            slots.join(", ")
+                        );
+                    }
+                }
+            },
+        );
+    }
+
+    /// Starts recording a replay, or stops and saves the one being recorded.
+    pub fn toggle_replay_recording(&mut self) {
+        if self.replay_player.is_some() {
+            self.message_log
+                .push_format(&tr!("replay.record_while_watching"));
+            return;
+        }
+
+        if self.finish_replay_recording().is_some() {
+            return;
+        }
+
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let name = format!("replay-{timestamp}");
+        self.message_log
+            .push_format(&tr!("replay.recording", name = name));
+        self.replay_recorder = Some(ReplayRecorder::new(name, self.physics.universe().time));
+        self.physics.start_removal_log();
+    }
+
+    /// Stops recording, if a replay is being recorded, and writes it out in the background. Returns the replay so
+    /// it can be watched straight away.
+    fn finish_replay_recording(&mut self) -> Option<(String, Replay)> {
+        let mut recorder = self.replay_recorder.take()?;
+        let removed = self.physics.take_removal_log();
+        recorder.record_removals(self.physics.universe(), removed);
+        self.physics.stop_removal_log();
+        let name = recorder.name.clone();
+        let replay = recorder.finish(self.physics.universe());
+
+        let saved_name = name.clone();
+        let saved_replay = replay.clone();
+        self.tasks.spawn(
+            move || {
+                let result = saved_replay.save(&saved_name);
+                (saved_name, result)
+            },
+            |app, (name, result)| match result {
+                Ok(()) => app
+                    .message_log
+                    .push_format(&tr!("replay.saved", name = name)),
+                Err(error) => warn!("Couldn't save replay {}: {}", name, error),
+            },
+        );
+
+        Some((name, replay))
+    }
+
+    /// Watches the replay being recorded, or else the newest one on disk. If a replay is already being watched,
+    /// goes back to the live universe instead.
+    pub fn toggle_replay_playback(&mut self) {
+        self.leave_photo_mode();
+        self.leave_orbit_camera();
+        if self.replay_player.is_some() {
+            self.stop_replay();
+            return;
+        }
+        // the server would keep changing the universe out from under it
+        if self.net.is_some() {
+            self.message_log.push_format(&tr!("replay.multiplayer"));
+            return;
+        }
+
+        if let Some((name, replay)) = self.finish_replay_recording() {
+            self.start_replay(name, replay);
+            return;
+        }
+
+        let Some(name) = Replay::names().pop() else {
+            self.message_log.push_format(&tr!("replay.none"));
+            return;
+        };
+        self.tasks.spawn(
+            move || {
+                let result = Replay::load(&name);
+                (name, result)
+            },
+            |app, (name, result)| match result {
+                Ok(replay) => app.start_replay(name, replay),
+                Err(error) => warn!("Couldn't load replay {}: {}", name, error),
+            },
+        );
+    }
+
+    /// Sets the live universe aside and starts watching a replay.
+    pub fn start_replay(&mut self, name: impl Into<String>, replay: Replay) {
+        if self.is_loading() {
+            return;
+        }
+        self.stop_replay();
+
+        let name = name.into();
+        let live = SaveGame::new(
+            self.physics.universe().clone(),
+            self.player_controller.to_save(),
+        );
+        let player = ReplayPlayer::new(name.as_str(), replay, live, self.physics.paused);
+
+        self.physics.paused = true;
+        self.physics.edit(UniverseEdit::Replace(Box::new(
+            player.replay.universe.clone(),
+        )));
+        self.timeline.clear();
+        self.message_log
+            .push_format(&tr!("replay.watching", name = name));
+        self.replay_player = Some(player);
+    }
+
+    /// Goes back to the live universe, right where it was left.
+    pub fn stop_replay(&mut self) {
+        let Some(player) = self.replay_player.take() else {
+            return;
+        };
+
+        self.physics
+            .edit(UniverseEdit::Replace(Box::new(player.live.universe)));
+        self.physics.paused = player.live_paused;
+        self.player_controller.load_save(player.live.player);
+        self.timeline.clear();
+        self.message_log.push_format(&tr!("replay.back_to_live"));
+    }
+
+    /// Moves the replay along and points the camera, in place of the usual player controls.
+    fn update_replay(&mut self, delta: f64) {
+        let Some(player) = &mut self.replay_player else {
+            return;
+        };
+
+        if self
+            .input_controller
+            .action_pressed(Action::ReplayPlayPause)
+        {
+            player.toggle_playing();
+        }
+        if self
+            .input_controller
+            .action_pressed(Action::ReplayCycleObserver)
+        {
+            player.cycle_observer();
+        }
+        if self
+            .input_controller
+            .action_pressed(Action::ReplayFreeCamera)
+        {
+            player.free_camera = !player.free_camera;
+        }
+        // scrolling changes the speed rather than the throttle
+        let scroll = self.input_controller.uncaptured_scroll_delta() as f64;
+        if scroll != 0.0 {
+            player.change_speed(scroll);
+        }
+
+        player.advance(delta);
+        for edit in player.removal_edits(self.physics.universe()) {
+            self.physics.edit(edit);
+        }
+        self.physics.edit(UniverseEdit::SetView {
+            time: player.time,
+            user_entity_id: player.observer,
+        });
+
+        match player.recorded_camera_rotation() {
+            Some(rotation) => {
+                self.player_controller.rotation = rotation;
+                self.player_controller.update_camera();
+            }
+            None => self
+                .player_controller
+                .update_free_camera(&mut self.input_controller, delta),
+        }
+    }
+
+    /// Which way the cursor points from the camera, in the user's rest frame. With a locked mouse, that's straight
+    /// ahead.
+    fn cursor_direction(&self, aspect_ratio: f32, frame: Vector2<f32>) -> Vector3<f64> {
+        let screen_point = if self.input_controller.is_mouse_locked() {
+            vec2(0.5, 0.5)
+        } else {
+            let cursor = self.input_controller.cursor_position();
+            vec2(cursor.x / frame.x, cursor.y / frame.y)
+        };
+        self.player_controller
+            .camera
+            .screen_to_world_direction(aspect_ratio, screen_point)
+            .cast()
+            .unwrap()
+    }
+
+    /// Picking entities, spawning from the palette, deleting, and undoing and redoing all of that.
+    fn update_world_editing(&mut self, aspect_ratio: f32, frame: Vector2<f32>) {
+        if self
+            .input_controller
+            .action_pressed(Action::ToggleSpawnPalette)
+        {
+            self.spawn_palette.toggle();
+            self.audio.play(GuiSound::Toggle);
+        }
+        if self.spawn_palette.open {
+            self.input_controller.report_in_a_menu();
+        }
+
+        if self.input_controller.action_pressed(Action::Undo) {
+            let message = match self.undo_stack.undo(&mut self.physics) {
+                Some(label) => tr!("undo.undid", label = label),
+                None => {
+                    self.audio.play(GuiSound::Error);
+                    tr!("undo.nothing_to_undo")
+                }
+            };
+            self.message_log.push_format(&message);
+        }
+        if self.input_controller.action_pressed(Action::Redo) {
+            let message = match self.undo_stack.redo(&mut self.physics) {
+                Some(label) => tr!("undo.redid", label = label),
+                None => {
+                    self.audio.play(GuiSound::Error);
+                    tr!("undo.nothing_to_redo")
+                }
+            };
+            self.message_log.push_format(&message);
+        }
+        if self.input_controller.action_pressed(Action::DeleteSelected) {
+            self.delete_selected_entities();
+        }
+        if self.input_controller.action_pressed(Action::CopySelected) {
+            self.copy_selected_entities();
+        }
+        if self.input_controller.action_pressed(Action::Paste) {
+            let offset = self.cursor_direction(aspect_ratio, frame) * SPAWN_DISTANCE;
+            self.paste_entities(offset);
+        }
+        if self.input_controller.action_pressed(Action::PinObserver) {
+            self.toggle_pinned_observer();
+            self.audio.play(GuiSound::Toggle);
+        }
+        if self
+            .input_controller
+            .action_pressed(Action::ToggleRulerTool)
+        {
+            self.toggle_ruler_tool();
+            self.audio.play(GuiSound::Toggle);
+        }
+        if self.input_controller.action_pressed(Action::MarkEvent) {
+            self.mark_event();
+        }
+
+        // undoing a spawn takes the entity away
+        if self
+            .selected_entity
+            .is_some_and(|entity_id| !self.physics.universe().entities.contains_key(&entity_id))
+        {
+            self.selected_entity = None;
+        }
+        let entities = &self.physics.universe().entities;
+        self.selection_group
+            .entity_ids
+            .retain(|entity_id| entities.contains_key(entity_id));
+
+        if self.ruler_tool.active {
+            self.update_ruler_tool(aspect_ratio, frame);
+            return;
+        }
+
+        if self
+            .input_controller
+            .modifier_held(ModifierKey::Control.into())
+            && self.spawn_palette.selected.is_none()
+        {
+            self.update_group_selection(aspect_ratio, frame);
+            return;
+        }
+        self.selection_group.box_start = None;
+
+        if !self.input_controller.pressed(MouseButton::Left)
+            || self.input_controller.hovered_component_id().is_some()
+        {
+            return;
+        }
+
+        let direction = self.cursor_direction(aspect_ratio, frame);
+        if let Some(prefab) = self.spawn_palette.selected.clone() {
+            self.spawn_prefab_in_view(&prefab, direction * SPAWN_DISTANCE);
+        } else {
+            self.selected_entity = self.pick_entity(direction);
+            self.selection_group.clear();
+        }
+    }
+
+    /// The nearest entity (other than the user) in `direction` from the camera.
+    fn pick_entity(&self, direction: Vector3<f64>) -> Option<EntityId> {
+        let user_entity_id = self.physics.universe().user_entity_id;
+        self.visible_entities
+            .raycast([0.0; 3], direction.into(), MAX_PICK_DISTANCE)
+            .into_iter()
+            .map(|(entity_id, _)| entity_id)
+            .find(|&entity_id| entity_id != user_entity_id)
+    }
+
+    /// While Ctrl's held, clicking adds an entity to the selection (or takes it back out), and dragging adds
+    /// everything in a box.
+    fn update_group_selection(&mut self, aspect_ratio: f32, frame: Vector2<f32>) {
+        let to_screen = |point: Vector2<f32>| vec2(point.x / frame.x, point.y / frame.y);
+        if self.input_controller.drag_started(MouseButton::Left)
+            && self.input_controller.hovered_component_id().is_none()
+            && !self.input_controller.is_mouse_locked()
+        {
+            self.selection_group.box_start = self
+                .input_controller
+                .press_origin(MouseButton::Left)
+                .map(to_screen);
+        }
+
+        if self.input_controller.drag_ended(MouseButton::Left) {
+            let cursor = to_screen(self.input_controller.cursor_position());
+            let Some(selection_box) = self.selection_group.selection_box(cursor) else {
+                return;
+            };
+            self.selection_group.box_start = None;
+
+            let user_entity_id = self.physics.universe().user_entity_id;
+            let camera = &self.player_controller.camera;
+            let boxed: Vec<EntityId> = self
+                .visible_entities
+                .iter()
+                .filter(|&(entity_id, bounds)| {
+                    let screen_point =
+                        camera.world_to_screen_point(aspect_ratio, bounds.to_f32().center().into());
+                    entity_id != user_entity_id
+                        && screen_point.z > 0.0
+                        && selection_box.point_is_within([screen_point.x, screen_point.y])
+                })
+                .map(|(entity_id, _)| entity_id)
+                .collect();
+            self.selected_entity = self.selection_group.extend(self.selected_entity, boxed);
+            self.message_log.push_format(&tr!(
+                "group.selected",
+                count = self
+                    .selection_group
+                    .with_selected(self.selected_entity)
+                    .len()
+            ));
+            return;
+        }
+
+        if !self.input_controller.clicked(MouseButton::Left)
+            || self.input_controller.hovered_component_id().is_some()
+        {
+            return;
+        }
+        let direction = self.cursor_direction(aspect_ratio, frame);
+        if let Some(entity_id) = self.pick_entity(direction) {
+            self.selected_entity = self.selection_group.toggle(self.selected_entity, entity_id);
+        }
+    }
+
+    pub fn toggle_ruler_tool(&mut self) {
+        self.ruler_tool.toggle();
+        let message = if !self.ruler_tool.active {
+            tr!("ruler.off")
+        } else if let Some(entity_id) = self.selected_entity {
+            let name = match self
+                .physics
+                .universe()
+                .entities
+                .get(&entity_id)
+                .and_then(|entity| entity.prefab)
+            {
+                Some(prefab) => prefab.to_string(),
+                None => "entity".to_owned(),
+            };
+            tr!("ruler.on_selected", name = name)
+        } else {
+            tr!("ruler.on")
+        };
+        self.message_log.push_format(&message);
+    }
+
+    /// Dragging lays down a ruler at rest in the selected entity's frame, or the user's if nothing's selected.
+    fn update_ruler_tool(&mut self, aspect_ratio: f32, frame: Vector2<f32>) {
+        if self.input_controller.pressed(MouseButton::Left)
+            && self.input_controller.hovered_component_id().is_none()
+        {
+            self.ruler_tool.start =
+                Some(self.cursor_direction(aspect_ratio, frame) * SPAWN_DISTANCE);
+        }
+        if self.input_controller.clicked(MouseButton::Left) {
+            self.ruler_tool.start = None;
+        }
+        if !self.input_controller.drag_ended(MouseButton::Left) {
+            return;
+        }
+        let Some((start, end)) = self.ruler_preview(aspect_ratio, frame) else {
+            return;
+        };
+        self.ruler_tool.start = None;
+
+        let universe = self.physics.universe();
+        let velocity = self
+            .selected_entity
+            .and_then(|entity_id| universe.get_event_at_time(entity_id, universe.time))
+            .unwrap_or_else(|| universe.user_event_now())
+            .frame
+            .velocity;
+        let Some(ruler) = place_ruler(universe, start, end, velocity) else {
+            self.message_log.push_format(&tr!("ruler.too_short"));
+            return;
+        };
+
+        self.undo_stack.perform(
+            &mut self.physics,
+            UndoEntry::new(tr!("undo.ruler"), ruler.edits, ruler.undo),
+        );
+        self.message_log.push_format(&tr!(
+            "ruler.placed",
+            length = format!("{:.1}", ruler.length)
+        ));
+    }
+
+    /// Where the ruler being dragged out starts and ends so far, relative to the user in their rest frame. The end's
+    /// as far away as the start, so it's easy to tell how long it'll come out from how far apart they look.
+    fn ruler_preview(
+        &self,
+        aspect_ratio: f32,
+        frame: Vector2<f32>,
+    ) -> Option<(Vector3<f64>, Vector3<f64>)> {
+        let start = self.ruler_tool.start?;
+        if !self.ruler_tool.active || self.replay_player.is_some() {
+            return None;
+        }
+        let end = self.cursor_direction(aspect_ratio, frame) * start.magnitude();
+        Some((start, end))
+    }
+
+    /// Spawns a prefab `offset` away from the user in their rest frame, moving along with them. Can be undone.
+    pub fn spawn_prefab_in_view(&mut self, name: &str, offset: Vector3<f64>) {
+        let event = self.physics.universe().user_event_now().comoving(offset);
+        let prefabs = PREFABS.read().unwrap();
+        let Some(entity) = prefabs.instantiate(name, event.frame) else {
+            warn!("There's no prefab called '{}'", name);
+            return;
+        };
+
+        let entity_id = EntityId::generate();
+        let mut edits = vec![UniverseEdit::InsertEntity {
+            entity_id,
+            entity: Box::new(entity),
+        }];
+        // parts come out first, the same as deleting
+        let mut undo = Vec::new();
+        for (part_id, part) in prefabs.instantiate_parts(name, entity_id, event.frame) {
+            edits.push(UniverseEdit::InsertEntity {
+                entity_id: part_id,
+                entity: Box::new(part),
+            });
+            undo.push(UniverseEdit::RemoveEntity(part_id));
+        }
+        undo.push(UniverseEdit::RemoveEntity(entity_id));
+        drop(prefabs);
+
+        self.undo_stack.perform(
+            &mut self.physics,
+            UndoEntry::new(tr!("undo.spawn", name = name), edits, undo),
+        );
+        self.selected_entity = Some(entity_id);
+    }
+
+    /// Plans a burn to match velocity with the selected entity, optionally taking a given amount of the user's proper
+    /// time.
+    fn plan_velocity_match(&mut self, args: &[String]) {
+        if self.replay_player.is_some() {
+            self.message_log.push_format(&tr!("match.replay_active"));
+            return;
+        }
+        let proper_time = match args.first().map(|arg| arg.parse::<f64>()) {
+            None => None,
+            Some(Ok(proper_time)) if proper_time > 0.0 => Some(proper_time),
+            Some(_) => {
+                self.message_log.push_format(&tr!("match.usage"));
+                return;
+            }
+        };
+        let Some(target_id) = self
+            .selected_entity
+            .filter(|&entity_id| entity_id != self.physics.universe().user_entity_id)
+        else {
+            self.message_log.push_format(&tr!("match.nothing_selected"));
+            return;
+        };
+
+        let job = match self.player_controller.plan_velocity_match(
+            target_id,
+            proper_time,
+            self.physics.universe(),
+        ) {
+            Ok(job) => job,
+            Err(message) => {
+                self.message_log.push_format(&message);
+                return;
+            }
+        };
+        self.message_log.push_format(&tr!("match.planning"));
+        self.tasks.spawn(
+            move || job.solve(),
+            |app, plan| {
+                // a replay could've started in the meantime
+                if app.replay_player.is_some() {
+                    app.player_controller.pending_velocity_match = None;
+                    return;
+                }
+                if let Some(message) = app
+                    .player_controller
+                    .finish_velocity_match(plan, &mut app.physics)
+                {
+                    app.message_log.push_format(&message);
+                }
+            },
+        );
+    }
+
+    fn maneuver_message(&self) -> String {
+        let universe = self.physics.universe();
+        let Some(burn) = self.maneuver.burn(&universe.get_user_entity().worldline) else {
+            return tr!("maneuver.none");
+        };
+        let (node, end_event, duration) = (burn.node, burn.end, burn.duration);
+        tr!(
+            "maneuver.current",
+            time = format!("{:.2}", node.coord_time - universe.time),
+            acceleration = format!("{:.3}", node.acceleration.magnitude()),
+            proper_time = format!("{:.2}", node.proper_time),
+            duration = format!("{duration:.2}"),
+            speed = format!("{:.3}", end_event.frame.velocity.magnitude())
+        )
+    }
+
+    /// Places, adjusts, or carries out a maneuver node on the user's worldline. Burns are given in the directions the
+    /// user's facing right now, as right, up, and forward.
+    fn configure_maneuver(&mut self, args: &[String]) {
+        if self.replay_player.is_some() {
+            self.message_log.push_format(&tr!("match.replay_active"));
+            return;
+        }
+        let numbers = args
+            .iter()
+            .skip(1)
+            .map(|arg| arg.parse::<f64>().ok().filter(|number| number.is_finite()))
+            .collect::<Option<Vec<_>>>();
+        let now = self.physics.universe().time;
+
+        match (args.first().map(String::as_str), numbers.as_deref()) {
+            (None, _) => {}
+            (Some("place"), Some(&[time])) if time >= 0.0 => {
+                let node = match self.maneuver.node {
+                    Some(node) => ManeuverNode {
+                        coord_time: now + time,
+                        ..node
+                    },
+                    None => ManeuverNode::new(now + time),
+                };
+                self.maneuver.set(Some(node));
+            }
+            (Some("burn"), Some(&[right, up, forward, proper_time])) if proper_time >= 0.0 => {
+                let Some(node) = self.maneuver.node else {
+                    self.message_log.push_format(&tr!("maneuver.none"));
+                    return;
+                };
+                let acceleration = self.player_controller.rotation * vec3(right, up, -forward);
+                if acceleration.magnitude() > MAX_ACCELERATION {
+                    self.message_log
+                        .push_format(&tr!("maneuver.too_fast", max = MAX_ACCELERATION));
+                    return;
+                }
+                self.maneuver.set(Some(ManeuverNode {
+                    acceleration,
+                    proper_time,
+                    ..node
+                }));
+            }
+            (Some("execute"), Some([])) => {
+                let Some(burn) = self
+                    .maneuver
+                    .burn(&self.physics.universe().get_user_entity().worldline)
+                else {
+                    self.message_log.push_format(&tr!("maneuver.none"));
+                    return;
+                };
+                let message = self
+                    .player_controller
+                    .execute_maneuver(burn, &mut self.physics);
+                self.maneuver.set(None);
+                self.message_log.push_format(&message);
+                return;
+            }
+            (Some("clear"), Some([])) => {
+                self.maneuver.set(None);
+                self.message_log.push_format(&tr!("maneuver.cleared"));
+                return;
+            }
+            _ => {
+                self.message_log.push_format(&tr!("maneuver.usage"));
+                return;
+            }
+        }
+        self.message_log.push_format(&self.maneuver_message());
+    }
+
+    /// Rescales how fast light looks, as a portion of its real speed. Can be undone. With no arguments, just says
+    /// what it's at.
+    fn set_speed_of_light(&mut self, args: &[String]) {
+        let current = self.physics.universe().speed_of_light;
+        let Some(arg) = args.first() else {
+            self.message_log
+                .push_format(&tr!("lightspeed.current", speed = format!("{current:.3}")));
+            return;
+        };
+        let Some(speed_of_light) = arg.parse::<f64>().ok().filter(|speed| speed.is_finite()) else {
+            self.message_log.push_format(&tr!("lightspeed.usage"));
+            return;
+        };
+        if self.net.is_some() || self.replay_player.is_some() {
+            self.message_log.push_format(&tr!("lightspeed.unavailable"));
+            return;
+        }
+
+        self.undo_stack.perform(
+            &mut self.physics,
+            UndoEntry::new(
+                tr!("undo.speed_of_light"),
+                vec![UniverseEdit::SetSpeedOfLight(speed_of_light)],
+                vec![UniverseEdit::SetSpeedOfLight(current)],
+            ),
+        );
+        self.message_log.push_format(&tr!(
+            "lightspeed.set",
+            speed = format!("{:.3}", self.physics.universe().speed_of_light)
+        ));
+    }
+
+    fn update_tour(&mut self, delta: f64) {
+        let advance = self.input_controller.action_pressed(Action::AdvanceTour);
+        if let Some(paused) =
+            self.tour
+                .update(self.physics.universe(), self.physics.paused, delta, advance)
+        {
+            self.physics.paused = paused;
+        }
+    }
+
+    /// What the tour panel says, if there's a tour going.
+    fn tour_panel_text(&self) -> Option<String> {
+        if self.tour.is_finished() || self.replay_player.is_some() {
+            return None;
+        }
+
+        let mut text = tr!(
+            "tour.progress",
+            step = self.tour.index + 1,
+            count = self.tour.steps.len()
+        );
+        match self.tour.current() {
+            Some(step) => {
+                // steps pointing at something put their text there instead
+                let pointing = step
+                    .entity_id
+                    .is_some_and(|entity_id| self.visible_entities.bounds(entity_id).is_some());
+                if !pointing {
+                    text.push('\n');
+                    text.push_str(&localize(&step.text));
+                }
+                text.push('\n');
+                text.push_str(&if step.pause {
+                    tr!("tour.continue")
+                } else {
+                    tr!("tour.skip")
+                });
+            }
+            None => {
+                text.push('\n');
+                text.push_str(&tr!("tour.waiting"));
+            }
+        }
+        Some(text)
+    }
+
+    /// Moves the origin over to the user once they've gone far enough from it. See [`Universe::rebase()`].
+    fn rebase_origin(&mut self) {
+        // everyone online has to agree on where things are
+        if self.net.is_some() {
+            return;
+        }
+        let position = self.physics.universe().user_event_now().frame.position;
+        if position.truncate().magnitude() < ORIGIN_REBASE_DISTANCE
+            && position.w.abs() < ORIGIN_REBASE_DISTANCE
+        {
+            return;
+        }
+
+        let offset =
+            position.map(|v| (v / ORIGIN_REBASE_GRANULARITY).round() * ORIGIN_REBASE_GRANULARITY);
+        self.physics.edit(UniverseEdit::Rebase(offset));
+        self.undo_stack.rebase(offset);
+        self.player_controller.rebase(offset);
+        self.event_ordering.rebase(offset);
+        self.causal_graph.rebase(offset);
+        self.frame_transition.rebase(offset);
+        self.observers.rebase(offset.w);
+        self.timeline.rebase(offset.w);
+        if let Some(recorder) = &mut self.replay_recorder {
+            recorder.rebase(offset);
+        }
+        // these get redone from the rebased universe right away
+        self.maneuver
+            .set(self.maneuver.node.map(|node| ManeuverNode {
+                coord_time: node.coord_time - offset.w,
+                ..node
+            }));
+        self.proximity.clear();
+        info!(
+            "Moved the origin by ({:.0}, {:.0}, {:.0}) and {:.0}s",
+            offset.x, offset.y, offset.z, offset.w
+        );
+    }
+
+    fn update_docking(&mut self) {
+        if self.docking.update(self.physics.universe()) {
+            let docked_after = self.docking.docked_after.unwrap_or_default();
+            self.message_log
+                .push_format(&tr!("docking.success", time = format!("{docked_after:.1}")));
+        }
+    }
+
+    /// What the docking panel says, if the scenario has a docking challenge.
+    fn docking_text(&self) -> Option<String> {
+        if self.replay_player.is_some() {
+            return None;
+        }
+        let target = self.docking.target?;
+        if let Some(docked_after) = self.docking.docked_after {
+            return Some(tr!("docking.docked", time = format!("{docked_after:.1}")));
+        }
+        let status = self.docking.status(self.physics.universe())?;
+
+        // in the directions the user's facing, same as maneuver burns
+        let velocity = self
+            .player_controller
+            .rotation
+            .conjugate()
+            .rotate_vector(status.relative_velocity);
+        Some(
+            [
+                tr!("docking.title"),
+                tr!(
+                    "docking.distance",
+                    distance = format!("{:.2}", status.distance()),
+                    max = target.distance
+                ),
+                tr!(
+                    "docking.velocity",
+                    right = format!("{:+.4}", velocity.x),
+                    up = format!("{:+.4}", velocity.y),
+                    forward = format!("{:+.4}", -velocity.z),
+                    closing = format!("{:.4}", status.closing_speed),
+                    max = target.speed
+                ),
+            ]
+            .join("\n"),
+        )
+    }
+
+    /// Where each waypoint looks like it is after the light delay, relative to the shown frame, along with what its
+    /// marker says.
+    fn waypoint_markers(&self) -> Vec<(Vector3<f32>, String)> {
+        if self.replay_player.is_some() {
+            return Vec::new();
+        }
+        let universe = self.physics.universe();
+        let render_time = self.view_time();
+        let (user_frame, shown_frame) = self.view_frames(render_time);
+
+        self.player_controller
+            .waypoints
+            .iter()
+            .filter_map(|waypoint| {
+                let frame_now = waypoint.frame_at_time(universe, render_time)?;
+                let seen_frame = universe.shown_frame(light_delayed_frame(
+                    universe,
+                    render_time,
+                    user_frame,
+                    |t| waypoint.frame_at_time(universe, t).unwrap_or(frame_now),
+                ));
+                let seen_distance = seen_frame
+                    .relative_to(universe.shown_frame(user_frame))
+                    .position
+                    .truncate()
+                    .magnitude();
+                let distance = frame_now
+                    .relative_to(user_frame)
+                    .position
+                    .truncate()
+                    .magnitude();
+                let text = tr!(
+                    "waypoint.marker",
+                    name = waypoint.name,
+                    distance = format!("{seen_distance:.1}"),
+                    delay = format!("{:.1}", seen_distance / universe.speed_of_light),
+                    time = format!(
+                        "{:.1}",
+                        travel_proper_time(distance, self.player_controller.acceleration)
+                    )
+                );
+                let point = seen_frame
+                    .relative_to(universe.shown_frame(shown_frame))
+                    .position
+                    .truncate()
+                    .map(|v| v as f32);
+                Some((point, text))
+            })
+            .collect()
+    }
+
+    /// Lists, adds, or removes waypoints. New ones go on the selected entity, or where the user is right now if
+    /// nothing's selected.
+    fn configure_waypoints(&mut self, args: &[String]) {
+        let universe = self.physics.universe();
+        let waypoints = &mut self.player_controller.waypoints;
+        match (args.first().map(String::as_str), args.get(1)) {
+            (None, _) => {
+                if waypoints.is_empty() {
+                    self.message_log.push_format(&tr!("waypoint.none"));
+                }
+                let user_frame = universe.user_event_now().frame;
+                for waypoint in waypoints.iter() {
+                    let Some(frame) = waypoint.frame_at_time(universe, universe.time) else {
+                        self.message_log
+                            .push_format(&tr!("waypoint.lost", name = waypoint.name));
+                        continue;
+                    };
+                    let distance = frame
+                        .relative_to(user_frame)
+                        .position
+                        .truncate()
+                        .magnitude();
+                    self.message_log.push_format(&tr!(
+                        "waypoint.entry",
+                        name = waypoint.name,
+                        distance = format!("{distance:.1}"),
+                        time = format!(
+                            "{:.1}",
+                            travel_proper_time(distance, self.player_controller.acceleration)
+                        )
+                    ));
+                }
+            }
+            (Some("add"), Some(name)) if args.len() == 2 => {
+                let target = match self
+                    .selected_entity
+                    .filter(|&entity_id| entity_id != universe.user_entity_id)
+                {
+                    Some(entity_id) => WaypointTarget::Entity(entity_id),
+                    None => WaypointTarget::Frame(universe.user_event_now().frame),
+                };
+                // adding one with the same name again moves it
+                if let Some(waypoint) = waypoints.iter_mut().find(|waypoint| &waypoint.name == name)
+                {
+                    waypoint.target = target;
+                } else if waypoints.len() >= MAX_WAYPOINTS {
+                    self.message_log
+                        .push_format(&tr!("waypoint.full", max = MAX_WAYPOINTS));
+                    return;
+                } else {
+                    waypoints.push(Waypoint {
+                        name: name.clone(),
+                        target,
+                    });
+                }
+                self.message_log
+                    .push_format(&tr!("waypoint.added", name = name));
+            }
+            (Some("remove"), Some(name)) if args.len() == 2 => {
+                let count = waypoints.len();
+                waypoints.retain(|waypoint| &waypoint.name != name);
+                let message = if waypoints.len() < count {
+                    tr!("waypoint.removed", name = name)
+                } else {
+                    tr!("waypoint.unknown", name = name)
+                };
+                self.message_log.push_format(&message);
+            }
+            (Some("clear"), None) => {
+                waypoints.clear();
+                self.message_log.push_format(&tr!("waypoint.cleared"));
+            }
+            _ => self.message_log.push_format(&tr!("waypoint.usage")),
+        }
+    }
+
+    /// Edits everything selected at once: recoloring, tagging, moving, deleting, or giving it all the user's planned
+    /// maneuver. `select` adds everything with a tag to the selection.
+    fn configure_selection_group(&mut self, args: &[String]) {
+        if self.replay_player.is_some() {
+            self.message_log.push_format(&tr!("match.replay_active"));
+            return;
+        }
+        let numbers = args
+            .iter()
+            .skip(1)
+            .map(|arg| arg.parse::<f64>().ok().filter(|number| number.is_finite()))
+            .collect::<Option<Vec<_>>>();
+        let entity_ids = self.selection_group.with_selected(self.selected_entity);
+        let universe = self.physics.universe();
+
+        let (undo_key, group_edit) = match (args.first().map(String::as_str), numbers.as_deref()) {
+            (None, _) => {
+                self.message_log
+                    .push_format(&tr!("group.count", count = entity_ids.len()));
+                return;
+            }
+            (Some("select"), _) if args.len() == 2 => {
+                let tagged: Vec<EntityId> = universe
+                    .entities
+                    .iter()
+                    .filter(|&(&entity_id, entity)| {
+                        entity_id != universe.user_entity_id && entity.has_tag(&args[1])
+                    })
+                    .map(|(&entity_id, _)| entity_id)
+                    .collect();
+                self.selected_entity = self.selection_group.extend(self.selected_entity, tagged);
+                self.message_log.push_format(&tr!(
+                    "group.selected",
+                    count = self
+                        .selection_group
+                        .with_selected(self.selected_entity)
+                        .len()
+                ));
+                return;
+            }
+            (Some("clear"), Some([])) => {
+                self.selected_entity = None;
+                self.selection_group.clear();
+                self.message_log.push_format(&tr!("group.cleared"));
+                return;
+            }
+            (Some("delete"), Some([])) => {
+                self.delete_selected_entities();
+                return;
+            }
+            (Some("color" | "tag" | "offset" | "maneuver"), _) if entity_ids.is_empty() => {
+                self.message_log.push_format(&tr!("group.nothing_selected"));
+                return;
+            }
+            (Some("color"), Some(channels)) if matches!(channels.len(), 3 | 4) => {
+                let channel = |index: usize| channels.get(index).copied().unwrap_or(1.0) as f32;
+                let color = vec4(channel(0), channel(1), channel(2), channel(3));
+                (
+                    "undo.group_color",
+                    selection::recolor(universe, &entity_ids, color),
+                )
+            }
+            (Some("tag"), _) if args.len() == 2 => (
+                "undo.group_tag",
+                selection::add_tag(universe, &entity_ids, &args[1]),
+            ),
+            (Some("offset"), Some(&[x, y, z])) => (
+                "undo.group_offset",
+                selection::offset(universe, &entity_ids, vec3(x, y, z)),
+            ),
+            (Some("maneuver"), Some([])) => {
+                let Some(node) = self.maneuver.node else {
+                    self.message_log.push_format(&tr!("maneuver.none"));
+                    return;
+                };
+                if node.coord_time < universe.time {
+                    self.message_log.push_format(&tr!("maneuver.passed"));
+                    return;
+                }
+                (
+                    "undo.group_maneuver",
+                    selection::share_maneuver(universe, &entity_ids, node),
+                )
+            }
+            _ => {
+                self.message_log.push_format(&tr!("group.usage"));
+                return;
+            }
+        };
+
+        if group_edit.count == 0 {
+            self.message_log.push_format(&tr!("group.unchanged"));
+            return;
+        }
+        let count = group_edit.count;
+        self.undo_stack.perform(
+            &mut self.physics,
+            UndoEntry::new(
+                tr!(undo_key, count = count),
+                group_edit.edits,
+                group_edit.undo,
+            ),
+        );
+        self.message_log
+            .push_format(&tr!("group.edited", count = count));
+    }
+
+    /// Pins the selected entity to compare clocks with, or unpins it if it already is.
+    pub fn toggle_pinned_observer(&mut self) {
+        let Some(entity_id) = self.selected_entity else {
+            self.message_log
+                .push_format(&tr!("observers.nothing_selected"));
+            return;
+        };
+
+        let message = if self.observers.unpin(entity_id) {
+            tr!("observers.unpinned")
+        } else if self.observers.pin(entity_id, self.physics.universe().time) {
+            tr!("observers.pinned")
+        } else {
+            tr!("observers.full", max = MAX_PINNED_OBSERVERS)
+        };
+        self.message_log.push_format(&message);
+    }
+
+    /// Marks where the selected entity (or the user, if nothing's selected) is right now.
+    pub fn mark_event(&mut self) {
+        let universe = self.physics.universe();
+        let entity_id = self.selected_entity.unwrap_or(universe.user_entity_id);
+        let Some(event) = universe.get_event_at_time(entity_id, universe.time) else {
+            return;
+        };
+        let name = match universe.entities[&entity_id].prefab {
+            Some(prefab) => prefab.to_string(),
+            None => "entity".to_owned(),
+        };
+        let index = self.event_ordering.mark(MarkedEvent {
+            entity_id,
+            position: event.frame.position,
+        });
+        let (_, color_code) = MARKED_EVENT_COLORS[index];
+        let text = tr!(
+            "ordering.marked",
+            label = format!("§{color_code}{}", ['A', 'B'][index]),
+            name = name,
+            time = format!("{:.2}", universe.scenario_time(universe.time))
+        );
+        self.message_log
+            .push_linked(&text, vec![(name, MessageLink::Entity(entity_id))]);
+    }
+
+    /// What the proximity panel says, if anything's about to come close.
+    fn proximity_text(&self) -> Option<String> {
+        if self.proximity.alerts.is_empty() {
+            return None;
+        }
+
+        let universe = self.physics.universe();
+        let lines = self.proximity.alerts.iter().map(|alert| {
+            let name = match universe
+                .entities
+                .get(&alert.entity_id)
+                .and_then(|entity| entity.prefab)
+            {
+                Some(prefab) => prefab.to_string(),
+                None => "entity".to_owned(),
+            };
+            let key = if alert.is_collision() {
+                "proximity.collision"
+            } else {
+                "proximity.approach"
+            };
+            tr!(
+                key,
+                name = name,
+                time = format!("{:.1}", alert.time_to_closest),
+                distance = format!("{:.2}", alert.distance.max(0.0)),
+                seen = format!("{:.1}", alert.time_to_seen)
+            )
+        });
+        Some(
+            std::iter::once(tr!("proximity.title"))
+                .chain(lines)
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )
+    }
+
+    /// Turns proximity warnings on or off, or sets how far ahead they look.
+    fn configure_proximity(&mut self, args: &[String]) {
+        let message = match args.first().map(String::as_str) {
+            Some("on") => {
+                self.proximity.enabled = true;
+                tr!("proximity.enabled")
+            }
+            Some("off") => {
+                self.proximity.enabled = false;
+                tr!("proximity.disabled")
+            }
+            Some(arg) => match arg.parse::<f64>() {
+                Ok(lookahead) if lookahead > 0.0 && lookahead <= MAX_PROXIMITY_LOOKAHEAD => {
+                    self.proximity.lookahead = lookahead;
+                    self.proximity.clear();
+                    tr!("proximity.lookahead", time = lookahead)
+                }
+                _ => tr!("proximity.usage", max = MAX_PROXIMITY_LOOKAHEAD),
+            },
+            None => tr!("proximity.usage", max = MAX_PROXIMITY_LOOKAHEAD),
+        };
+        self.message_log.push_format(&message);
+    }
+
+    /// What the event ordering panel says, once two events are marked.
+    fn event_ordering_text(&self) -> Option<String> {
+        if !self.event_ordering.is_ready() || self.replay_player.is_some() {
+            return None;
+        }
+        let ordering = &self.event_ordering;
+        let interval2 = ordering.interval2()?;
+        let kind = ordering.interval_kind()?;
+        let separation = ordering.separation_in_frame(ordering.observer_speed)?;
+
+        let mut text = tr!("ordering.title");
+        text.push('\n');
+        text.push_str(&tr!(
+            "ordering.interval",
+            interval = format!("{:.2}", interval2.abs().sqrt()),
+            kind = match kind {
+                IntervalKind::Timelike => tr!("ordering.timelike"),
+                IntervalKind::Lightlike => tr!("ordering.lightlike"),
+                IntervalKind::Spacelike => tr!("ordering.spacelike"),
+            }
+        ));
+        text.push('\n');
+        text.push_str(&tr!(
+            "ordering.separation",
+            time = format!("{:.2}", separation.y),
+            distance = format!("{:.2}", separation.x)
+        ));
+        text.push('\n');
+        // rounding shouldn't make simultaneous events flicker back and forth
+        let tolerance = 1e-9 * separation.x.abs().max(1.0);
+        text.push_str(&if separation.y > tolerance {
+            tr!("ordering.first_before")
+        } else if separation.y < -tolerance {
+            tr!("ordering.second_before")
+        } else {
+            tr!("ordering.simultaneous")
+        });
+        text.push('\n');
+        text.push_str(&match ordering.simultaneity_speed() {
+            Some(speed) => tr!("ordering.flips", speed = format!("{speed:+.2}")),
+            None => tr!("ordering.fixed"),
+        });
+        Some(text)
+    }
+
+    /// Adds, removes or forgets events in the causal graph. New ones go where the selected entity (or the user, if
+    /// nothing's selected) is at some coordinate time, defaulting to now.
+    fn edit_causal_graph(&mut self, args: &[String]) {
+        let universe = self.physics.universe();
+        let mut links = Vec::new();
+        let message = match (
+            args.first().map(String::as_str),
+            args.get(1).map(|arg| arg.parse::<f64>()),
+        ) {
+            (Some("add"), None | Some(Ok(_))) => {
+                // typed in and shown in the scenario's time, which a rebase doesn't change
+                let time = match args.get(1) {
+                    Some(arg) => arg.parse().unwrap_or(universe.scenario_time(universe.time)),
+                    None => universe.scenario_time(universe.time),
+                };
+                let entity_id = self.selected_entity.unwrap_or(universe.user_entity_id);
+                let Some(event) =
+                    universe.get_event_at_time(entity_id, universe.from_scenario_time(time))
+                else {
+                    return;
+                };
+                let name = match universe.entities[&entity_id].prefab {
+                    Some(prefab) => prefab.to_string(),
+                    None => "entity".to_owned(),
+                };
+                match self.causal_graph.add(entity_id, event.frame.position) {
+                    Some(label) => {
+                        let text = tr!(
+                            "causal.added",
+                            label = label,
+                            name = name,
+                            time = format!("{time:.2}")
+                        );
+                        links.push((name, MessageLink::Entity(entity_id)));
+                        text
+                    }
+                    None => tr!("causal.full", max = MAX_CAUSAL_EVENTS),
+                }
+            }
+            (Some("remove"), Some(Ok(label))) => {
+                let label = label as usize;
+                if self.causal_graph.remove(label) {
+                    tr!("causal.removed", label = label)
+                } else {
+                    tr!("causal.missing", label = label)
+                }
+            }
+            (Some("clear"), None) => {
+                self.causal_graph.clear();
+                tr!("causal.cleared")
+            }
+            _ => tr!("causal.usage"),
+        };
+        self.message_log.push_linked(&message, links);
+    }
+
+    /// What the causal graph panel says, once there's something in it.
+    fn causal_graph_text(&self) -> Option<String> {
+        if self.causal_graph.events.is_empty() || self.replay_player.is_some() {
+            return None;
+        }
+        let graph = &self.causal_graph;
+        let universe = self.physics.universe();
+
+        let mut lines = vec![tr!("causal.title")];
+        lines.extend(graph.events.iter().map(|event| {
+            let name = match universe
+                .entities
+                .get(&event.entity_id)
+                .and_then(|entity| entity.prefab)
+            {
+                Some(prefab) => prefab.to_string(),
+                None => "entity".to_owned(),
+            };
+            tr!(
+                "causal.event",
+                label = event.label,
+                name = name,
+                time = format!("{:.2}", event.position.w)
+            )
+        }));
+        let unrelated = graph.unrelated_pairs();
+        if graph.events.len() > 1 {
+            lines.push(if unrelated.is_empty() {
+                tr!("causal.all_related")
+            } else {
+                tr!(
+                    "causal.unrelated",
+                    pairs = unrelated
+                        .iter()
+                        .map(|&(a, b)| format!(
+                            "{}-{}",
+                            graph.events[a].label, graph.events[b].label
+                        ))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            });
+        }
+        Some(lines.join("\n"))
+    }
+
+    /// What the debug overlay's current page says, if it isn't hidden. `render_stats` covers the last frame.
+    fn debug_page_text(&self, render_stats: RenderStats) -> Option<String> {
+        let page_number = self.debug_page.number()?;
+        let universe = self.physics.universe();
+        let lines = match self.debug_page {
+            DebugPage::Kinematics => {
+                let user_event = universe.user_event_now();
+                let pos = user_event.frame.position.truncate();
+                let vel = user_event.frame.velocity;
+                let mut lines = vec![
+                    format!(
+                        "Displacement: {:.3}, {:.3}, {:.3} ({:.3}cs from origin)",
+                        pos.x,
+                        pos.y,
+                        pos.z,
+                        pos.magnitude()
+                    ),
+                    format!(
+                        "Velocity: {:.3}c ({:.3}, {:.3}, {:.3})",
+                        vel.magnitude(),
+                        vel.x,
+                        vel.y,
+                        vel.z
+                    ),
+                    format!("Lorentz factor: {:.3}", lorentz_factor(vel)),
+                    format!("Proper time: {:.3}s", user_event.proper_time),
+                    format!(
+                        "Coordinate time: {:.3}s",
+                        universe.scenario_time(universe.time)
+                    ),
+                    format!("Throttle: {:.3}c/s", self.player_controller.acceleration),
+                ];
+                if universe.speed_of_light < 1.0 {
+                    lines.push(format!("Speed of light: {:.3}c", universe.speed_of_light));
+                }
+                lines
+            }
+            DebugPage::Renderer => {
+                let instances = self
+                    .graphics
+                    .entity_model_instances
+                    .values()
+                    .map(Vec::len)
+                    .sum::<usize>();
+                let (runtime_textures, runtime_textures_in_use) =
+                    self.graphics.texture_provider.dynamic_texture_counts();
+                let buffer_kib = |capacity: u64, size: usize| capacity * size as u64 / 1024;
+                vec![
+                    format!(
+                        "Draw calls: {} in {} passes",
+                        render_stats.draw_calls, render_stats.render_passes
+                    ),
+                    format!(
+                        "Instances drawn: {} ({} vertices)",
+                        render_stats.instances, render_stats.vertices
+                    ),
+                    format!(
+                        "Buffer uploads: {:.1} KiB §8| §rtargets recreated: {}",
+                        render_stats.buffer_bytes_written as f64 / 1024.0,
+                        render_stats.render_targets_recreated
+                    ),
+                    format!(
+                        "Entity instances: {} across {} models",
+                        instances,
+                        self.graphics.entity_model_instances.len()
+                    ),
+                    format!(
+                        "Culled: {} of {} entities",
+                        universe
+                            .entities
+                            .len()
+                            .saturating_sub(self.visible_entities.len()),
+                        universe.entities.len()
+                    ),
+                    format!(
+                        "Instance buffer: {} KiB",
+                        buffer_kib(
+                            self.graphics.instance_buffer.capacity(),
+                            mem::size_of::<EntityInstance>()
+                        )
+                    ),
+                    format!(
+                        "GUI: {} primitives, {} KiB vertices, {} KiB indices",
+                        self.last_gui_primitive_count,
+                        buffer_kib(
+                            self.graphics.gui_vertices.vertices.capacity(),
+                            mem::size_of::<Vertex2D>()
+                        ),
+                        buffer_kib(
+                            self.graphics.gui_vertices.indices.capacity(),
+                            mem::size_of::<u32>()
+                        )
+                    ),
+                    format!(
+                        "Atlas: {} layers, {} runtime textures ({} in use)",
+                        self.graphics.texture_provider.layer_count(),
+                        runtime_textures,
+                        runtime_textures_in_use
+                    ),
+                ]
+            }
+            DebugPage::Memory => {
+                let user_worldline = &universe.get_user_entity().worldline;
+                let (total_events, total_coalesced) =
+                    universe
+                        .entities
+                        .values()
+                        .fold((0, 0), |(events, coalesced), entity| {
+                            (
+                                events + entity.worldline.events().len(),
+                                coalesced + entity.worldline.coalesced_events(),
+                            )
+                        });
+                vec![
+                    format!("Entities: {}", universe.entities.len()),
+                    format!(
+                        "Your events: {} ({} coalesced)",
+                        user_worldline.events().len(),
+                        user_worldline.coalesced_events()
+                    ),
+                    format!(
+                        "All events: {} ({} coalesced)",
+                        total_events, total_coalesced
+                    ),
+                    format!(
+                        "Worldline memory: {} KiB",
+                        total_events * mem::size_of::<WorldlineEvent>() / 1024
+                    ),
+                    format!("Spatial index: {} entities", self.visible_entities.len()),
+                    format!(
+                        "Dropped physics time: {:.3}s",
+                        self.frame_pacer.dropped_time()
+                    ),
+                ]
+            }
+            DebugPage::Input => {
+                let input_controller = &self.input_controller;
+                let cursor = input_controller.cursor_position();
+                let mouse_delta = input_controller.mouse_delta();
+                let list = |names: Vec<String>| {
+                    if names.is_empty() {
+                        "none".to_owned()
+                    } else {
+                        names.join(", ")
+                    }
+                };
+                vec![
+                    format!(
+                        "Mouse: {} §8| §rcursor {:.0}, {:.0} §8| §rdelta {:.1}, {:.1} §8| §rscroll {:.1}",
+                        if input_controller.is_mouse_locked() {
+                            "locked"
+                        } else {
+                            "free"
+                        },
+                        cursor.x,
+                        cursor.y,
+                        mouse_delta.x,
+                        mouse_delta.y,
+                        input_controller.scroll_delta()
+                    ),
+                    format!(
+                        "In a menu: {} §8| §rfocused: {:?}",
+                        input_controller.in_a_menu(),
+                        input_controller.focused_component_id()
+                    ),
+                    format!(
+                        "Modifiers: {}",
+                        list(
+                            input_controller
+                                .held_modifiers()
+                                .map(|(key, side)| format!("{side:?} {key:?}"))
+                                .collect()
+                        )
+                    ),
+                    format!(
+                        "Held: {}",
+                        list(input_controller.all_held().iter().map(input_name).collect())
+                    ),
+                    format!(
+                        "Actions: {}",
+                        list(
+                            input_controller
+                                .held_actions()
+                                .into_iter()
+                                .map(|action| format!("{action:?}"))
+                                .collect()
+                        )
+                    ),
+                ]
+            }
+            DebugPage::Hidden => return None,
+        };
+
+        Some(format!(
+            "§e{} §8({}/{})\n§r{}",
+            self.debug_page.title(),
+            page_number,
+            DebugPage::PAGES.len(),
+            lines.join("\n")
+        ))
+    }
+
+    /// How big the selected entity looks against how big it is right now, for showing off the headlight effect and
+    /// aberration. Measures the sphere around its model.
+    fn angular_size_text(&self) -> Option<String> {
+        if !self.show_angular_size || self.replay_player.is_some() {
+            return None;
+        }
+        let universe = self.physics.universe();
+        let Some(entity) = self
+            .selected_entity
+            .filter(|&entity_id| entity_id != universe.user_entity_id)
+            .and_then(|entity_id| universe.entities.get(&entity_id))
+        else {
+            return Some(tr!("angular.nothing_selected"));
+        };
+        let Some(model) = entity
+            .model
+            .and_then(|model_id| self.graphics.models.get(&model_id))
+        else {
+            return Some(tr!("angular.no_model"));
+        };
+        let radius = model
+            .bounds
+            .transformed(&entity.model_matrix)
+            .corners()
+            .map(|corner| Vector3::from(corner).magnitude())
+            .fold(0.0, f32::max) as f64;
+
+        let render_time = self.view_time();
+        let (user_frame, _) = self.view_frames(render_time);
+        let frame_at = |coord_time| universe.entity_event_at_time(entity, coord_time).frame;
+        let seen_frame = universe
+            .shown_frame(light_delayed_frame(
+                universe,
+                render_time,
+                user_frame,
+                frame_at,
+            ))
+            .relative_to(universe.shown_frame(user_frame));
+        let frame_now = simultaneous_frame(user_frame, frame_at).relative_to(user_frame);
+        let seen_distance = seen_frame.position.truncate().magnitude();
+        let distance = frame_now.position.truncate().magnitude();
+        let size = AngularSize {
+            apparent: apparent_angular_size(
+                radius,
+                seen_frame.position.truncate(),
+                seen_frame.velocity,
+            ),
+            instantaneous: sphere_angular_size(radius, distance),
+        };
+
+        let name = match entity.prefab {
+            Some(prefab) => prefab.to_string(),
+            None => "entity".to_owned(),
+        };
+        Some(
+            [
+                tr!("angular.title", name = name),
+                tr!(
+                    "angular.apparent",
+                    size = format!("{:.3}", size.apparent.to_degrees()),
+                    delay = format!("{:.1}", seen_distance / universe.speed_of_light)
+                ),
+                tr!(
+                    "angular.instantaneous",
+                    size = format!("{:.3}", size.instantaneous.to_degrees()),
+                    distance = format!("{distance:.1}")
+                ),
+                tr!(
+                    "angular.magnification",
+                    ratio = format!("{:.3}", size.magnification())
+                ),
+            ]
+            .join("\n"),
+        )
+    }
+
+    /// The heading over the Lorentz factor sparkline.
+    fn gamma_history_text(&self) -> Option<String> {
+        if !self.show_gamma_history || self.replay_player.is_some() {
+            return None;
+        }
+        let history = &self.player_controller.gamma_history;
+        let (Some(gamma), Some(peak)) = (history.latest(), history.peak()) else {
+            return Some(tr!("gamma.empty"));
+        };
+        Some(tr!(
+            "gamma.current",
+            gamma = format!("{gamma:.4}"),
+            peak = format!("{peak:.4}"),
+            duration = GAMMA_HISTORY_DURATION
+        ))
+    }
+
+    /// The comparison table for pinned observers, with the user's own clock first.
+    fn observer_table_text(&self) -> Option<String> {
+        if self.observers.is_empty() || self.replay_player.is_some() {
+            return None;
+        }
+
+        let universe = self.physics.universe();
+        let comparisons = self.observers.compare(universe);
+        let user_elapsed_proper_time = comparisons.first()?.user_elapsed_proper_time;
+
+        let mut text = tr!(
+            "observers.title",
+            since = format!("{:.1}", self.observers.since)
+        );
+        text.push('\n');
+        text.push_str(&tr!(
+            "observers.user_row",
+            elapsed = format!("{user_elapsed_proper_time:.2}")
+        ));
+        for (index, comparison) in comparisons.iter().enumerate() {
+            let (_, color_code) = self.observers.color(comparison.entity_id)?;
+            let name = match &comparison.prefab {
+                Some(prefab) => prefab.clone(),
+                None => tr!("observers.unnamed", index = index + 1),
+            };
+            text.push('\n');
+            text.push_str(&tr!(
+                "observers.row",
+                color = format!("§{color_code}"),
+                name = name,
+                elapsed = format!("{:.2}", comparison.elapsed_proper_time),
+                difference = format!("{:+.2}", comparison.proper_time_difference()),
+                gamma = format!("{:.4}", comparison.relative_gamma),
+                distance = format!("{:.1}", comparison.distance)
+            ));
+        }
+        Some(text)
+    }
+
+    /// Removes the selected entity, along with the rest of the selection group. Can be undone, which puts back
+    /// anything that was attached to them too.
+    pub fn delete_selected_entities(&mut self) {
+        let entity_ids = self
+            .selection_group
+            .with_selected(self.selected_entity.take());
+        self.selection_group.clear();
+        let universe = self.physics.universe();
+        if entity_ids.contains(&universe.user_entity_id) {
+            self.message_log.push_format(&tr!("delete.self"));
+            return;
+        }
+        let prefabs = PREFABS.read().unwrap();
+        // a part on its own goes with the rest of what it's part of
+        let root_ids = part_roots(universe, &prefabs, entity_ids);
+
+        let name = match root_ids.iter().next() {
+            None => return,
+            Some(_) if root_ids.len() > 1 => tr!("delete.group", count = root_ids.len()),
+            Some(entity_id) => match universe.entities[entity_id].prefab {
+                Some(prefab) => prefab.to_string(),
+                None => "entity".to_owned(),
+            },
+        };
+        let mut edits = Vec::new();
+        let mut undo = Vec::new();
+        for &entity_id in root_ids.iter() {
+            undo.push(UniverseEdit::InsertEntity {
+                entity_id,
+                entity: Box::new(universe.entities[&entity_id].clone()),
+            });
+            // parts have to come out first, since taking the parent away lets go of them
+            for child_id in universe.children(entity_id) {
+                let child = &universe.entities[&child_id];
+                if is_part(&prefabs, child) {
+                    edits.push(UniverseEdit::RemoveEntity(child_id));
+                    undo.push(UniverseEdit::InsertEntity {
+                        entity_id: child_id,
+                        entity: Box::new(child.clone()),
+                    });
+                } else if let Some(attachment) = child.attachment {
+                    undo.push(UniverseEdit::Attach {
+                        entity_id: child_id,
+                        parent_id: entity_id,
+                        offset: attachment.offset,
+                    });
+                }
+            }
+            edits.push(UniverseEdit::RemoveEntity(entity_id));
+        }
+        drop(prefabs);
+
+        self.undo_stack.perform(
+            &mut self.physics,
+            UndoEntry::new(tr!("undo.delete", name = name), edits, undo),
+        );
+        self.message_log
+            .push_format(&tr!("delete.deleted", name = name));
+    }
+
+    /// Copies the selected entity and the rest of the selection group, both for pasting here and (as JSON on the OS
+    /// clipboard) into another session.
+    pub fn copy_selected_entities(&mut self) {
+        let entity_ids = self.selection_group.with_selected(self.selected_entity);
+        let clipboard = EntityClipboard::copy(
+            self.physics.universe(),
+            &PREFABS.read().unwrap(),
+            &entity_ids,
+        );
+        if clipboard.is_empty() {
+            self.message_log
+                .push_format(&tr!("clipboard.nothing_selected"));
+            return;
+        }
+
+        match clipboard.to_json() {
+            Ok(json) => {
+                if let Err(err) = clipboard_anywhere::set_clipboard(&json) {
+                    warn!(
+                        "Couldn't put the copied entities on the OS clipboard: {}",
+                        err
+                    );
+                }
+            }
+            Err(err) => warn!("Couldn't serialize the copied entities: {}", err),
+        }
+        self.message_log
+            .push_format(&tr!("clipboard.copied", count = clipboard.len()));
+        self.entity_clipboard = clipboard;
+    }
+
+    /// Pastes what was copied last, centered `offset` away from the user (in their rest frame), and selects it. Entities
+    /// copied in another session are picked up off the OS clipboard. Can be undone.
+    pub fn paste_entities(&mut self, offset: Vector3<f64>) {
+        if let Ok(text) = clipboard_anywhere::get_clipboard() {
+            match EntityClipboard::from_json(&text) {
+                Ok(clipboard) => self.entity_clipboard = clipboard,
+                // it's just something else that was copied
+                Err(err) if err.is::<serde_json::Error>() => {}
+                Err(err) => {
+                    self.message_log
+                        .push_format(&tr!("clipboard.invalid", reason = err));
+                    return;
+                }
+            }
+        }
+        if self.entity_clipboard.is_empty() {
+            self.message_log.push_format(&tr!("clipboard.empty"));
+            return;
+        }
+
+        let anchor = self.physics.universe().user_event_now().comoving(offset);
+        let pasted = match self.entity_clipboard.paste(anchor) {
+            Ok(pasted) => pasted,
+            Err(err) => {
+                self.message_log
+                    .push_format(&tr!("clipboard.invalid", reason = err));
+                return;
+            }
+        };
+        let count = pasted.len();
+        let root_ids: Vec<EntityId> = pasted
+            .iter()
+            .filter(|(_, entity)| entity.attachment.is_none())
+            .map(|&(entity_id, _)| entity_id)
+            .collect();
+        // attached ones come out first, the same as deleting
+        let undo = pasted
+            .iter()
+            .rev()
+            .map(|&(entity_id, _)| UniverseEdit::RemoveEntity(entity_id))
+            .collect();
+        let edits = pasted
+            .into_iter()
+            .map(|(entity_id, entity)| UniverseEdit::InsertEntity {
+                entity_id,
+                entity: Box::new(entity),
+            })
+            .collect();
+
+        self.undo_stack.perform(
+            &mut self.physics,
+            UndoEntry::new(tr!("undo.paste", count = count), edits, undo),
+        );
+        self.selection_group.clear();
+        self.selected_entity = self.selection_group.extend(None, root_ids);
+        self.message_log
+            .push_format(&tr!("clipboard.pasted", count = count));
+    }
+
+    /// Draws a see-through copy of the palette's prefab where clicking would spawn it.
+    fn add_spawn_preview_instance(&mut self, aspect_ratio: f32, frame: Vector2<f32>) {
+        let Some(name) = &self.spawn_palette.selected else {
+            return;
+        };
+        // over the palette itself
+        if self.input_controller.hovered_component_id().is_some() {
+            return;
+        }
+
+        let prefabs = PREFABS.read().unwrap();
+        let Some(prefab) = prefabs.get(name) else {
+            return;
+        };
+        let Some(model_id) = prefab.model else {
+            return;
+        };
+
+        let offset = self.cursor_direction(aspect_ratio, frame) * SPAWN_DISTANCE;
+        let model_matrix = camera_relative_model_matrix(
+            offset,
+            self.graphics.instance_origin.cast().unwrap(),
+            Matrix4::from_scale(prefab.scale),
+        );
+        let [r, g, b, _] = prefab.color;
+        self.graphics
+            .entity_model_instances
+            .entry(model_id)
+            .or_default()
+            .push(EntityInstance {
+                model_matrix: model_matrix.into(),
+                velocity: [0.0; 3],
+                color: [r, g, b, SPAWN_PREVIEW_ALPHA],
+                // there's no telling where it was last frame
+                previous_model_matrix: model_matrix.into(),
+            });
+    }
+
+    /// Hosts a server with the universe as it is now and joins it, or joins someone else's. Connecting happens
+    /// in the background.
+    pub fn start_multiplayer(&mut self, launch: MultiplayerLaunch) {
+        let address = match launch {
+            MultiplayerLaunch::Host {
+                address,
+                max_players,
+            } => {
+                let mut server =
+                    Server::new(self.scenario_name.clone(), self.physics.universe().clone());
+                server.max_players = max_players;
+                match spawn_server(address, server) {
+                    Ok(address) => format!("localhost:{}", address.port()),
+                    Err(error) => {
+                        warn!("Couldn't host on {}: {}", address, error);
+                        return;
+                    }
+                }
+            }
+            MultiplayerLaunch::Connect(address) => address,
+        };
+
+        self.message_log
+            .push_format(&tr!("multiplayer.connecting", address = address));
+        let name = self.player_name.clone();
+        self.tasks.spawn(
+            move || {
+                let result = NetClient::connect(&address, &name);
+                (address, result)
+            },
+            |app, (address, result)| match result {
+                Ok(client) => {
+                    // what the user does to their own worldline gets picked out of this
+                    app.physics.start_edit_log();
+                    app.net = Some(client);
+                }
+                Err(error) => warn!("Couldn't connect to {}: {}", address, error),
+            },
+        );
+    }
+
+    /// Applies whatever the server sent, and sends out what the user did.
+    fn update_multiplayer(&mut self) {
+        let Some(client) = &mut self.net else {
+            return;
+        };
+
+        for event in client.poll() {
+            match event {
+                NetEvent::Message(message) => self.handle_server_message(message),
+                NetEvent::Disconnected(reason) => {
+                    if let Some(client) = &self.net {
+                        self.message_log.push_format(&tr!(
+                            "multiplayer.disconnected",
+                            address = client.address,
+                            reason = reason
+                        ));
+                    }
+                    self.net = None;
+                    self.physics.stop_edit_log();
+                    self.chat_box.close(&mut self.input_controller);
+                    return;
+                }
+            }
+        }
+
+        if let Some(client) = &mut self.net {
+            client.send_own_events(&mut self.physics);
+        }
+    }
+
+    fn handle_server_message(&mut self, message: ServerMessage) {
+        let Some(client) = &mut self.net else {
+            return;
+        };
+
+        match message {
+            ServerMessage::Welcome {
+                entity_id,
+                scenario,
+                universe,
+                players,
+            } => {
+                client.entity_id = Some(entity_id);
+                client.scenario.clone_from(&scenario);
+                client.players = players;
+                let joined_message = tr!(
+                    "multiplayer.joined",
+                    address = client.address,
+                    name = client.player_name(entity_id).unwrap_or_default()
+                );
+
+                // the scenario on our end doesn't matter anymore
+                self.finish_replay_recording();
+                self.physics.edit(UniverseEdit::Replace(universe));
+                self.undo_stack.clear();
+                self.selected_entity = None;
+                self.selection_group.clear();
+                self.observers.clear();
+                self.proximity.clear();
+                self.leave_orbit_camera();
+                self.timeline.clear();
+                self.annotations.clear();
+                self.signals.clear();
+                self.tour = GuidedTour::default();
+                self.docking = DockingChallenge::default();
+                self.script = None;
+                self.scenario_name = scenario;
+                self.message_log.push_format(&joined_message);
+            }
+            ServerMessage::Rejected(reason) => {
+                self.message_log
+                    .push_format(&tr!("multiplayer.rejected", reason = reason));
+            }
+            ServerMessage::PlayerJoined { player, entity } => {
+                self.message_log
+                    .push_format(&tr!("multiplayer.player_joined", name = player.name));
+                self.physics.edit(UniverseEdit::InsertEntity {
+                    entity_id: player.entity_id,
+                    entity,
+                });
+                client.players.push(player);
+            }
+            ServerMessage::PlayerLeft(entity_id) => {
+                if let Some(name) = client.player_name(entity_id) {
+                    self.message_log
+                        .push_format(&tr!("multiplayer.player_left", name = name));
+                }
+                client
+                    .players
+                    .retain(|player| player.entity_id != entity_id);
+                self.physics.edit(UniverseEdit::RemoveEntity(entity_id));
+            }
+            ServerMessage::Event { entity_id, event } => {
+                self.physics
+                    .edit(UniverseEdit::InsertExactEvent { entity_id, event });
+            }
+            ServerMessage::Chat { from, text } => {
+                self.message_log
+                    .push_format(&format!("§b{from}§8: §f{text}"));
+            }
+        }
+    }
+
+    /// Runs one of the scenario script's hooks, then the same hook for every plugin, and carries out whatever they
+    /// asked for.
+    pub fn run_script_hook(&mut self, hook: ScriptHook) {
+        let mut commands = Vec::new();
+        if let Some(script) = &mut self.script {
+            commands.extend(script.run_hook(&hook, self.physics.universe()));
+        }
+        for script in self
+            .plugins
+            .iter_mut()
+            .filter_map(|plugin| plugin.script.as_mut())
+        {
+            commands.extend(script.run_hook(&hook, self.physics.universe()));
+        }
+        self.apply_script_commands(commands);
+    }
+
+    fn apply_script_commands(&mut self, commands: Vec<ScriptCommand>) {
+        for command in commands {
+            match command {
+                ScriptCommand::Spawn { entity_id, entity } => {
+                    self.physics
+                        .edit(UniverseEdit::InsertEntity { entity_id, entity });
+                }
+                ScriptCommand::InsertEvent {
+                    entity_id,
+                    coord_time,
+                    kind,
+                } => {
+                    self.physics.edit(UniverseEdit::InsertEvent {
+                        entity_id,
+                        coord_time,
+                        kind,
+                    });
+                }
+                ScriptCommand::Attach {
+                    entity_id,
+                    parent_id,
+                    offset,
+                } => {
+                    self.physics.edit(UniverseEdit::Attach {
+                        entity_id,
+                        parent_id,
+                        offset,
+                    });
+                }
+                ScriptCommand::Detach(entity_id) => {
+                    self.physics.edit(UniverseEdit::Detach(entity_id));
+                }
+                ScriptCommand::Annotate(annotation) => {
+                    self.annotations
+                        .retain(|existing| existing.entity_id != annotation.entity_id);
+                    self.annotations.push(annotation);
+                }
+                ScriptCommand::Signal {
+                    sender,
+                    receiver,
+                    channel,
+                    message,
+                } => {
+                    self.signals
+                        .send(self.physics.universe(), sender, receiver, channel, message);
+                }
+                ScriptCommand::SetPaused(paused) => self.physics.paused = paused,
+                ScriptCommand::Toast(text) => self.message_log.push_format(&localize(&text)),
+            }
+        }
+    }
+
+    /// Does whatever clicking a link in the message log should.
+    fn follow_message_link(&mut self, link: MessageLink) {
+        match link {
+            MessageLink::Command(command) => {
+                self.chat_box
+                    .open_with(&mut self.input_controller, &format!("/{command} "));
+            }
+            MessageLink::Entity(entity_id) => {
+                if self.physics.universe().entities.contains_key(&entity_id) {
+                    self.selection_group.clear();
+                    self.selected_entity = Some(entity_id);
+                    self.audio.play(GuiSound::Click);
+                } else {
+                    self.audio.play(GuiSound::Error);
+                }
+            }
+        }
+    }
+
+    /// Runs a console command (without the leading `/`), either a built-in one or one from a plugin.
+    pub fn run_console_command(&mut self, command: &str) {
+        let mut words = command.split_whitespace();
+        let Some(name) = words.next() else {
+            return;
+        };
+        let args: Vec<String> = words.map(str::to_owned).collect();
+
+        match name {
+            "help" => {
+                self.message_log.push_command_links(&tr!("command.help"));
+                for plugin in self.plugins.iter() {
+                    for command in plugin.commands.iter() {
+                        self.message_log.push_command_links(&format!(
+                            "§f/{} §7{} §8({})",
+                            command.name, command.help, plugin.manifest.name
+                        ));
+                    }
+                }
+            }
+            "plugins" => {
+                if self.plugins.is_empty() {
+                    self.message_log
+                        .push_format(&tr!("command.no_plugins", path = PLUGINS_PATH));
+                }
+                for plugin in self.plugins.iter() {
+                    let status = if plugin.script.is_none() || plugin.is_running() {
+                        String::new()
+                    } else {
+                        tr!("command.plugin_stopped")
+                    };
+                    self.message_log.push_format(&format!(
+                        "§f{} §7{}{} §8| §7{}",
+                        plugin.manifest.name,
+                        plugin.manifest.version,
+                        status,
+                        plugin.manifest.description
+                    ));
+                }
+            }
+            "export" => self.export_trajectories(&args),
+            "transitions" => match args.first().map(String::as_str) {
+                Some(mode @ ("smooth" | "instant")) => {
+                    self.settings.instant_frame_changes = mode == "instant";
+                    self.save_settings();
+                    self.message_log
+                        .push_format(&tr!("command.transitions_set", mode = mode));
+                }
+                _ => self
+                    .message_log
+                    .push_format(&tr!("command.transitions_usage")),
+            },
+            "lightspeed" => self.set_speed_of_light(&args),
+            "match" => self.plan_velocity_match(&args),
+            "maneuver" => self.configure_maneuver(&args),
+            "waypoint" => self.configure_waypoints(&args),
+            "group" => self.configure_selection_group(&args),
+            "paste" => {
+                let offset = args
+                    .iter()
+                    .map(|arg| arg.parse::<f64>().ok().filter(|number| number.is_finite()))
+                    .collect::<Option<Vec<_>>>();
+                match offset.as_deref() {
+                    Some(&[x, y, z]) => self.paste_entities(vec3(x, y, z)),
+                    _ => self.message_log.push_format(&tr!("clipboard.usage")),
+                }
+            }
+            "proximity" => self.configure_proximity(&args),
+            "pacing" => self.configure_frame_pacing(&args),
+            "renderscale" => self.configure_render_scale(&args),
+            "motionblur" => self.configure_motion_blur(&args),
+            "sound" => self.configure_sound(&args),
+            "save" | "load" | "saves" => self.manage_save_slots(name, &args),
+            "posteffects" => self.configure_post_effects(&args),
+            "shader" => self.configure_shader_features(&args),
+            "stereo" => self.configure_stereo(&args),
+            "angularsize" => {
+                self.show_angular_size = !self.show_angular_size;
+                self.message_log.push_format(&if self.show_angular_size {
+                    tr!("angular.shown")
+                } else {
+                    tr!("angular.hidden")
+                });
+            }
+            "gamma" => {
+                self.show_gamma_history = !self.show_gamma_history;
+                self.message_log.push_format(&if self.show_gamma_history {
+                    tr!("gamma.shown")
+                } else {
+                    tr!("gamma.hidden")
+                });
+            }
+            "tour" => {
+                let was_paused = match args.first().map(String::as_str) {
+                    Some("skip") => self.tour.skip(),
+                    Some("stop") => self.tour.stop(),
+                    Some("restart") => self.tour.restart(),
+                    _ => {
+                        self.message_log.push_format(&tr!("tour.usage"));
+                        None
+                    }
+                };
+                if let Some(paused) = was_paused {
+                    self.physics.paused = paused;
+                }
+            }
+            "observers" => match args.first().map(String::as_str) {
+                Some("clear") => {
+                    self.observers.clear();
+                    self.message_log.push_format(&tr!("observers.cleared"));
+                }
+                Some("reset") => {
+                    self.observers.reset(self.physics.universe().time);
+                    self.message_log.push_format(&tr!("observers.reset"));
+                }
+                _ => self.message_log.push_format(&tr!("observers.usage")),
+            },
+            "ordering" => match args.first().map(String::as_str) {
+                Some("clear") => {
+                    self.event_ordering.clear();
+                    self.message_log.push_format(&tr!("ordering.cleared"));
+                }
+                _ => self.message_log.push_format(&tr!("ordering.usage")),
+            },
+            "causal" => self.edit_causal_graph(&args),
+            "restore" => match SaveGame::latest_autosave() {
+                Some((slot, _)) => self.load_game(&slot),
+                None => self.message_log.push_format(&tr!("session.no_autosaves")),
+            },
+            "language" => match args.first() {
+                Some(language) => {
+                    if LOCALIZATION.write().unwrap().set_language(language) {
+                        self.settings.language = language.clone();
+                        self.save_settings();
+                        self.quick_actions.options = quick_action_options();
+                        self.message_log
+                            .push_format(&tr!("command.language_set", language = language));
+                    } else {
+                        self.message_log
+                            .push_format(&tr!("command.language_unknown", language = language));
+                    }
+                }
+                None => {
+                    let (language, languages) = {
+                        let localization = LOCALIZATION.read().unwrap();
+                        let languages: Vec<&str> = localization.languages().collect();
+                        (localization.language().to_owned(), languages.join(", "))
+                    };
+                    self.message_log.push_format(&tr!(
+                        "command.language_list",
+                        language = language,
+                        languages = languages
+                    ));
+                }
+            },
+            _ => {
+                let Some(plugin) = self
+                    .plugins
+                    .iter_mut()
+                    .find(|plugin| plugin.has_command(name))
+                else {
+                    self.message_log
+                        .push_format(&tr!("command.unknown", name = name));
+                    self.audio.play(GuiSound::Error);
+                    return;
+                };
+                let commands = plugin.run_command(name, args, self.physics.universe());
+                self.apply_script_commands(commands);
             }
         }
-        // for _ in 0..500 {
-        //     universe.insert_entity(Entity {
-        //         worldline: Worldline::new(InertialFrame {
-        //             position: vec4(
-        //                 rng.gen_range(-500.0..500.0),
-        //                 rng.gen_range(-500.0..500.0),
-        //                 rng.gen_range(-500.0..500.0),
-        //                 0.0,
-        //             ),
-        //             ..Default::default()
-        //         }),
-        //         model: Some("subdivided_cube".into()),
-        //         model_matrix: Matrix4::from_scale(5.0),
-        //         ..Default::default()
-        //     });
-        // }
+    }
+
+    /// Samples worldlines and writes them out in the background. `args` can have `all` (for every entity instead
+    /// of just the user and the selected one), `csv` or `json`, and a cadence in seconds, in any order.
+    fn export_trajectories(&mut self, args: &[String]) {
+        let mut all = false;
+        let mut format = ExportFormat::default();
+        let mut cadence = DEFAULT_EXPORT_CADENCE;
+        for arg in args {
+            if arg == "all" {
+                all = true;
+            } else if let Some(arg_format) = ExportFormat::from_name(arg) {
+                format = arg_format;
+            } else if let Some(arg_cadence) =
+                arg.parse::<f64>().ok().filter(|&cadence| cadence > 0.0)
+            {
+                cadence = arg_cadence;
+            } else {
+                self.message_log.push_format(&tr!("export.usage"));
+                return;
+            }
+        }
+
+        let universe = self.physics.universe();
+        let entity_ids: Vec<EntityId> = if all {
+            universe.entities.keys().copied().collect()
+        } else {
+            let mut entity_ids = vec![universe.user_entity_id];
+            entity_ids.extend(
+                self.selected_entity
+                    .filter(|&id| id != universe.user_entity_id),
+            );
+            entity_ids
+        };
+        let export = TrajectoryExport::sample(universe, &entity_ids, cadence);
+
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let name = format!("trajectory-{timestamp}");
+        self.tasks.spawn(
+            move || (export.sample_count(), export.save(&name, format)),
+            |app, (count, result)| match result {
+                Ok(path) => app.message_log.push_format(&tr!(
+                    "export.saved",
+                    count = count,
+                    path = path.display()
+                )),
+                Err(error) => warn!("Couldn't export trajectories: {}", error),
+            },
+        );
+    }
+
+    /// Every plugin panel that's showing, stacked together, or `None` if there aren't any. The icons of the plugins
+    /// they're from get shown along with them.
+    fn plugin_panel_text(&mut self) -> Option<String> {
+        let mut text = String::new();
+        let mut commands = Vec::new();
+        let mut icons = Vec::new();
+        for plugin in self.plugins.iter_mut() {
+            let (panels, panel_commands) = plugin.render_panels(self.physics.universe());
+            commands.extend(panel_commands);
+            if !panels.is_empty() && plugin.icon.is_some() {
+                icons.push(plugin.icon_texture);
+            }
+            for (title, panel_text) in panels {
+                if !text.is_empty() {
+                    text.push_str("\n\n");
+                }
+                let _ = write!(text, "§e{title}\n§f{panel_text}");
+            }
+        }
+        self.apply_script_commands(commands);
+        self.show_plugin_icons(icons);
+
+        (!text.is_empty()).then_some(text)
+    }
+
+    /// Holds onto the textures of the plugin icons in `icons` (putting back any that were evicted), and lets go of the
+    /// ones that aren't showing anymore.
+    fn show_plugin_icons(&mut self, icons: Vec<TextureId>) {
+        let texture_provider = &mut self.graphics.texture_provider;
+        for &texture_id in icons.iter() {
+            if self.shown_plugin_icons.contains(&texture_id) {
+                continue;
+            }
+            if texture_provider.has_texture(texture_id) {
+                texture_provider.retain(texture_id);
+                continue;
+            }
+            let Some(icon) = self
+                .plugins
+                .iter()
+                .find(|plugin| plugin.icon_texture == texture_id)
+                .and_then(|plugin| plugin.icon.as_ref())
+            else {
+                continue;
+            };
+            if !texture_provider.add_image(texture_id.name(), icon) {
+                warn!("Couldn't fit {} in the texture atlas", texture_id);
+            }
+        }
+        for &texture_id in self.shown_plugin_icons.iter() {
+            if !icons.contains(&texture_id) {
+                texture_provider.release(texture_id);
+            }
+        }
+        self.shown_plugin_icons = icons;
+    }
+
+    /// Starts profiling, or stops and saves the profile to [`PROFILE_PATH`].
+    pub fn toggle_profiler(&mut self) {
+        if !Profiler::is_available() {
+            warn!("Profiling isn't available, build with the profiler feature to use it");
+            return;
+        }
 
-        let player_controller = PlayerController::default();
+        if self.profiler.is_enabled() {
+            self.profiler.set_enabled(false);
+            match self.profiler.save(PROFILE_PATH) {
+                Ok(()) => info!("Saved profile to {}", PROFILE_PATH),
+                Err(error) => warn!("Couldn't save profile: {}", error),
+            }
+        } else {
+            self.profiler.set_enabled(true);
+            info!("Profiling started");
+        }
+    }
 
-        Ok(Self {
-            graphics_controller,
-            input_controller,
-            gui,
-            universe,
-            player_controller,
+    pub fn winit_event(&mut self, event: WinitEvent) {
+        self.input_controller.winit_event(event);
+    }
 
-            frame_counter: PerformanceCounter::new(),
-            last_performance_report: (Instant::now(), None),
+    pub fn wants_auxiliary_window(&self) -> bool {
+        self.auxiliary_window_wanted
+    }
 
-            graphics,
-        })
+    pub fn auxiliary_window_id(&self) -> Option<WindowId> {
+        self.auxiliary_view
+            .as_ref()
+            .map(|view| view.surface.window.id())
     }
 
-    pub fn phys_tick(&mut self) {
-        self.universe.step(PHYS_TIME_STEP);
+    /// Starts drawing to `window`, which the event loop opened because of
+    /// [`AppState::wants_auxiliary_window()`].
+    pub fn open_auxiliary_window(&mut self, window: Arc<Window>) {
+        match self.graphics_controller.create_auxiliary_surface(window) {
+            Ok(surface) => {
+                self.auxiliary_view = Some(AuxiliaryView::new(surface));
+                self.message_log.push_format(&tr!("auxiliary.opened"));
+            }
+            Err(error) => {
+                warn!("Couldn't draw to the second window: {}", error);
+                self.auxiliary_window_wanted = false;
+            }
+        }
     }
 
-    pub fn window_focus_changed(&mut self, is_focused: bool) {}
+    /// Dropping the view closes its window too.
+    pub fn close_auxiliary_window(&mut self) {
+        self.auxiliary_window_wanted = false;
+        self.auxiliary_view = None;
+    }
 
-    pub fn update_camera_uniform(&mut self, camera: Camera, aspect_ratio: f32) {
-        self.graphics
-            .camera_uniform
-            .buffer
-            .replace_contents(vec![camera.uniform(aspect_ratio)]);
+    pub fn resize_auxiliary_window(&mut self, new_size: PhysicalSize<u32>) {
+        if let Some(view) = &mut self.auxiliary_view {
+            self.graphics_controller
+                .resize_auxiliary_surface(&mut view.surface, new_size);
+        }
     }
 
-    pub fn render_simple_sky(&mut self, target: &RenderTarget) {
-        let color = GuiColor {
-            r: 0.0,
-            g: 0.0,
-            b: 0.0,
-            a: 1.0,
+    /// Draws the entities again with the second window's camera. Has to come after everything in the main window,
+    /// since it changes the camera uniform.
+    fn render_auxiliary_view(&mut self) {
+        let Some(view) = &self.auxiliary_view else {
+            return;
         };
+        profile_scope!("render_auxiliary_view");
 
-        self.graphics.generic_vertices_2d.replace_contents(
-            Vertex2D::fill_screen(color, self.graphics.texture_provider.get_section("white"))
-                .to_vec(),
-        );
+        let tracked = self
+            .selected_entity
+            .and_then(|entity_id| self.visible_entities.bounds(entity_id))
+            .map(|bounds| Vector3::from(bounds.center()).cast().unwrap());
+        let camera = view.camera(&self.player_controller.camera, tracked);
+        let aspect_ratio = view.aspect_ratio();
+        let size = view.surface.size();
 
-        self.graphics_controller.render(
-            target,
-            &self.graphics.pipeline_2d,
-            PipelineBuffers {
-                vertices: &self.graphics.generic_vertices_2d,
-                instances: None,
-                indices: Some(&self.graphics.generic_quad_indices),
-            },
-            [self.graphics.texture_provider.bind_group()],
-        );
+        self.update_camera_uniform(camera, aspect_ratio);
+        let (_, target) =
+            self.graphics_controller
+                .render_target("auxiliary", size.width, size.height);
+        target.clear();
+        self.render_simple_sky(&target);
+        self.render_entities(&target);
+
+        if let Some(view) = &self.auxiliary_view {
+            if let Err(error) = self
+                .graphics_controller
+                .present_to_auxiliary(&view.surface, target.texture())
+            {
+                warn!("Couldn't draw the second window: {}", error);
+            }
+        }
     }
 
-    pub fn update_entity_model_instances(&mut self) {
-        for (_, list) in self.graphics.entity_model_instances.iter_mut() {
-            list.clear();
+    /// Draws the entities once for each eye, then puts the two together over all of `target`.
+    fn render_stereo(&mut self, target: &RenderTarget, camera: Camera, mode: StereoMode) {
+        if !self.graphics.stereo_pipelines.contains_key(&mode) {
+            self.render_entities(target);
+            return;
         }
+        profile_scope!("render_stereo");
 
-        let user_entity = self.universe.get_user_entity();
-        let user_event = user_entity.worldline.get_event_at_time(self.universe.time);
-        let user_frame = user_event.frame;
+        let (width, height) = mode.eye_size(target.width(), target.height());
+        let eye_targets = STEREO_TARGET_NAMES.map(|name| {
+            let (_, eye_target) = self.graphics_controller.render_target(name, width, height);
+            eye_target
+        });
+        let middle_slot = self.graphics.camera_slot;
+        let eyes = eye_cameras(&camera, self.settings.eye_separation as f32);
+        for (eye_target, eye_camera) in eye_targets.iter().zip(eyes) {
+            eye_target.clear();
+            self.update_camera_uniform(eye_camera, eye_target.aspect_ratio());
+            self.render_simple_sky(eye_target);
+            self.render_entities(eye_target);
+        }
+        // back to the middle, for anything else that goes by the camera
+        self.graphics.camera_slot = middle_slot;
 
-        let new_model_instances: Vec<(String, EntityInstance)> = self
-            .universe
-            .entities
-            .par_iter()
-            .filter_map(|(_, entity)| {
-                let model_name = entity.model.as_ref()?;
-                if !self.graphics.models.contains_key(model_name) {
-                    warn!("Model '{}' does not exist", model_name);
-                    return None;
-                }
+        self.graphics_controller.render_fullscreen_textures(
+            target,
+            &self.graphics.stereo_pipelines[&mode],
+            &[eye_targets[0].texture(), eye_targets[1].texture()],
+        );
+    }
 
-                // lightspeed delay
-                let event = {
-                    // use newton's method for finding the event whose delay matches the expected
-                    // delay given its distance
-                    let mut estimated_event =
-                        entity.worldline.get_event_at_time(self.universe.time);
-                    let mut prev_offset: Option<f64> = None;
-                    let mut prev_change: Option<f64> = None;
-                    for _ in 0..30 {
-                        let relative_frame = estimated_event.frame.relative_to(user_frame);
-                        let relative_gamma = lorentz_factor(relative_frame.velocity);
-                        let travel_time = (estimated_event.frame.position - user_frame.position)
-                            .truncate()
-                            .magnitude();
-                        let timeline_delay = self.universe.time - estimated_event.frame.position.w;
-                        let offset = timeline_delay - travel_time;
-
-                        let change = if let (Some(prev_offset), Some(prev_change)) =
-                            (prev_offset, prev_change)
-                        {
-                            let derivative = (prev_offset - offset) / prev_change;
-
-                            offset / derivative
-                        } else {
-                            offset / relative_gamma
-                        };
+    /// Switches how the two eyes' views get shown, and optionally how far apart they are.
+    fn configure_stereo(&mut self, args: &[String]) {
+        let Some(mode) = args.first() else {
+            self.message_log.push_format(&tr!(
+                "stereo.current",
+                mode = self.settings.stereo_mode.name(),
+                separation = format!("{:.2}", self.settings.eye_separation)
+            ));
+            return;
+        };
+        let Some(mode) = StereoMode::from_id(mode) else {
+            self.message_log
+                .push_format(&tr!("stereo.usage", max = MAX_EYE_SEPARATION));
+            return;
+        };
+        let separation = match args.get(1).map(|arg| arg.parse::<f64>()) {
+            None => self.settings.eye_separation,
+            Some(Ok(separation)) if separation > 0.0 && separation <= MAX_EYE_SEPARATION => {
+                separation
+            }
+            Some(_) => {
+                self.message_log
+                    .push_format(&tr!("stereo.usage", max = MAX_EYE_SEPARATION));
+                return;
+            }
+        };
 
-                        prev_offset = Some(offset);
-                        prev_change = Some(change);
+        self.settings.stereo_mode = mode;
+        self.settings.eye_separation = separation;
+        self.save_settings();
+        self.message_log.push_format(&tr!(
+            "stereo.current",
+            mode = mode.name(),
+            separation = format!("{separation:.2}")
+        ));
+    }
 
-                        if offset.abs() < 0.001 {
-                            break;
-                        }
+    fn shader_features_message(&self) -> String {
+        let features = self.settings.shader_features;
+        let state = |enabled: bool| {
+            if enabled {
+                tr!("shader.on")
+            } else {
+                tr!("shader.off")
+            }
+        };
+        tr!(
+            "shader.current",
+            doppler = state(features.doppler),
+            shading = state(features.shading),
+            wireframe = state(features.wireframe),
+            prepass = state(features.depth_prepass),
+            log_depth = state(features.log_depth),
+            lens_flare = state(features.lens_flare)
+        )
+    }
 
-                        estimated_event = entity
-                            .worldline
-                            .get_event_at_time(estimated_event.frame.position.w + change);
-                    }
-                    estimated_event
-                };
+    fn render_scale_message(&self) -> String {
+        let size = self.scene_size();
+        tr!(
+            "render_scale.current",
+            scale = self.settings.render_scale,
+            width = size.width,
+            height = size.height
+        )
+    }
 
-                let relative_frame = event.frame.relative_to(user_frame);
-                let relative_boost = lorentz_boost(relative_frame.velocity);
+    fn motion_blur_message(&self) -> String {
+        if !self.settings.motion_blur {
+            return tr!("motion_blur.off");
+        }
+        tr!(
+            "motion_blur.current",
+            angle = format!("{:.0}", self.settings.shutter_angle)
+        )
+    }
 
-                let contraction = vec3(
-                    1.0 / (relative_boost * Vector4::unit_x()).x as f32,
-                    1.0 / (relative_boost * Vector4::unit_y()).y as f32,
-                    1.0 / (relative_boost * Vector4::unit_z()).z as f32,
-                );
+    /// Saves to or loads from the slot in `args` (the quicksave one if there isn't one), or with `saves`, lists every
+    /// slot there is.
+    fn manage_save_slots(&mut self, command: &str, args: &[String]) {
+        if command == "saves" {
+            let slots = SaveGame::slots();
+            if slots.is_empty() {
+                self.message_log.push_format(&tr!("save.no_slots"));
+            } else {
+                self.message_log
+                    .push_format(&tr!("save.slots", slots = slots.join(", ")));
+            }
+            return;
+        }
 
-                let contraction_matrix =
-                    Matrix4::from_nonuniform_scale(contraction.x, contraction.y, contraction.z);
-                let model_matrix =
-                    Matrix4::from_translation(relative_frame.position.truncate().map(|v| v as f32))
-                        * contraction_matrix
-                        * entity.model_matrix;
+        let slot = match args {
+            [] => QUICKSAVE_SLOT,
+            [slot] if SaveGame::is_valid_slot(slot) => slot,
+            _ => {
+                self.message_log.push_format(&tr!("save.usage"));
+                self.audio.play(GuiSound::Error);
+                return;
+            }
+        };
+        if command == "save" {
+            self.save_game(slot);
+        } else if SaveGame::slots().iter().any(|existing| existing == slot) {
+            self.load_game(slot);
+        } else {
+            self.message_log
+                .push_format(&tr!("save.no_slot", slot = slot));
+            self.audio.play(GuiSound::Error);
+        }
+    }
 
-                Some((
-                    model_name.to_owned(),
-                    EntityInstance {
-                        model_matrix: model_matrix.into(),
-                        velocity: relative_frame.velocity.map(|v| v as f32).into(),
-                        color: entity.model_color.into(),
-                    },
-                ))
-            })
-            .collect();
+    /// Picks a GUI sound theme or sets the volume, and saves it to the settings.
+    fn configure_sound(&mut self, args: &[String]) {
+        let usage = tr!("sound.usage", themes = GuiSoundTheme::NAMES.join(", "));
+        match args {
+            [] => {}
+            [arg] => match arg.parse::<f32>() {
+                Ok(volume) if (0.0..=1.0).contains(&volume) => self.settings.sound.volume = volume,
+                _ if GuiSoundTheme::named(arg).is_some() => self.settings.sound.theme = arg.clone(),
+                _ => {
+                    self.message_log.push_format(&usage);
+                    self.audio.play(GuiSound::Error);
+                    return;
+                }
+            },
+            _ => {
+                self.message_log.push_format(&usage);
+                self.audio.play(GuiSound::Error);
+                return;
+            }
+        }
+        if !args.is_empty() {
+            self.audio.apply_settings(&self.settings.sound);
+            self.save_settings();
+        }
+        self.message_log.push_format(&tr!(
+            "sound.current",
+            theme = self.settings.sound.theme,
+            volume = format!("{:.0}%", self.settings.sound.volume * 100.0)
+        ));
+    }
 
-        for (model_name, instance) in new_model_instances {
-            self.graphics
-                .entity_model_instances
-                .entry(model_name)
-                .or_default()
-                .push(instance);
+    /// Turns motion blur on or off, or sets its shutter angle (which turns it on).
+    fn configure_motion_blur(&mut self, args: &[String]) {
+        let usage = tr!("motion_blur.usage", max = MAX_SHUTTER_ANGLE);
+        match args {
+            [] => {}
+            [state] if state == "on" => self.settings.motion_blur = true,
+            [state] if state == "off" => self.settings.motion_blur = false,
+            [angle] => match angle.parse::<f32>() {
+                Ok(angle) if angle > 0.0 && angle <= MAX_SHUTTER_ANGLE => {
+                    self.settings.motion_blur = true;
+                    self.settings.shutter_angle = angle;
+                }
+                _ => {
+                    self.message_log.push_format(&usage);
+                    return;
+                }
+            },
+            _ => {
+                self.message_log.push_format(&usage);
+                return;
+            }
+        }
+        if !args.is_empty() {
+            self.save_settings();
+        }
+        self.message_log.push_format(&self.motion_blur_message());
+        if self.settings.motion_blur && self.settings.stereo_mode != StereoMode::Off {
+            self.message_log.push_format(&tr!("motion_blur.stereo"));
         }
     }
 
-    pub fn render_entities(&mut self, target: &RenderTarget) {
-        for (model_name, instances) in self.graphics.entity_model_instances.iter() {
-            if let Some(model) = self.graphics.models.get(model_name) {
-                self.graphics
-                    .instance_buffer
-                    .replace_contents(instances.clone());
-                self.graphics_controller.render(
-                    target,
-                    &self.graphics.pipeline_3d,
-                    PipelineBuffers {
-                        vertices: &model.vertices.vertices,
-                        instances: Some(&self.graphics.instance_buffer),
-                        indices: Some(&model.vertices.indices),
-                    },
-                    [
-                        self.graphics.texture_provider.bind_group(),
-                        &self.graphics.camera_uniform.bind_group,
-                    ],
-                );
+    fn post_effects_message(&self) -> String {
+        let effects = self.post_effects;
+        tr!(
+            "post_effects.current",
+            vignette = format!("{:.2}", effects.vignette),
+            grain = format!("{:.2}", effects.grain),
+            exposure = if effects.auto_exposure {
+                tr!("shader.on")
             } else {
-                warn!("Model '{}' does not exist", model_name);
+                tr!("shader.off")
+            }
+        )
+    }
+
+    /// Tweaks the scenario's post effects for the rest of the session, e.g. to get a recording just right.
+    fn configure_post_effects(&mut self, args: &[String]) {
+        let usage = tr!("post_effects.usage");
+        match args {
+            [] => {}
+            [off] if off == "off" => self.post_effects = PostEffects::default(),
+            [name, value] => {
+                let amount = value
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|amount| (0.0..=1.0).contains(amount));
+                match (name.as_str(), value.as_str(), amount) {
+                    ("vignette", _, Some(amount)) => self.post_effects.vignette = amount,
+                    ("grain", _, Some(amount)) => self.post_effects.grain = amount,
+                    ("exposure", "on", _) => self.post_effects.auto_exposure = true,
+                    ("exposure", "off", _) => self.post_effects.auto_exposure = false,
+                    _ => {
+                        self.message_log.push_format(&usage);
+                        return;
+                    }
+                }
+            }
+            _ => {
+                self.message_log.push_format(&usage);
+                return;
             }
         }
+        self.message_log.push_format(&self.post_effects_message());
     }
 
-    pub fn render(&mut self, delta: f64) {
-        self.player_controller
-            .update(&mut self.universe, &mut self.input_controller, delta);
+    /// Changes the resolution the 3D view is drawn at, relative to the window's.
+    fn configure_render_scale(&mut self, args: &[String]) {
+        let usage = tr!(
+            "render_scale.usage",
+            min = MIN_RENDER_SCALE,
+            max = MAX_RENDER_SCALE
+        );
+        match args {
+            [] => {}
+            [scale] => match scale.parse::<f32>() {
+                Ok(scale) if (MIN_RENDER_SCALE..=MAX_RENDER_SCALE).contains(&scale) => {
+                    self.settings.render_scale = scale;
+                    self.save_settings();
+                }
+                _ => {
+                    self.message_log.push_format(&usage);
+                    return;
+                }
+            },
+            _ => {
+                self.message_log.push_format(&usage);
+                return;
+            }
+        }
+        self.message_log.push_format(&self.render_scale_message());
+    }
 
-        let (_, window_target) = self
-            .graphics_controller
-            .window_sized_render_target("render");
-        window_target.clear();
+    /// Switches parts of the 3D shader on or off, building the pipeline for the new combination if it's the first
+    /// time it's been used.
+    fn configure_shader_features(&mut self, args: &[String]) {
+        let [name, state] = args else {
+            if args.is_empty() {
+                self.message_log
+                    .push_format(&self.shader_features_message());
+            } else {
+                self.message_log.push_format(&tr!("shader.usage"));
+            }
+            return;
+        };
+        let enabled = match state.as_str() {
+            "on" => true,
+            "off" => false,
+            _ => {
+                self.message_log.push_format(&tr!("shader.usage"));
+                return;
+            }
+        };
+        let mut features = self.settings.shader_features;
+        let Some(flag) = features.flag_mut(name) else {
+            self.message_log.push_format(&tr!("shader.usage"));
+            return;
+        };
+        *flag = enabled;
 
-        self.render_simple_sky(&window_target);
+        self.set_shader_features(features);
+        self.message_log
+            .push_format(&self.shader_features_message());
+    }
 
-        // 3d rendering
-        {
-            self.update_camera_uniform(self.player_controller.camera, window_target.aspect_ratio());
-            self.update_entity_model_instances();
-            self.render_entities(&window_target);
+    /// Switches to `features`, preparing whatever pipeline variants they need first.
+    fn set_shader_features(&mut self, features: ShaderFeatures) {
+        for variant in features.pipeline_variants() {
+            self.graphics.pipeline_3d.prepare(&variant);
         }
+        self.settings.shader_features = features;
+        self.save_settings();
+    }
 
-        // 2d rendering
-        {
-            let mut gui_builder = GuiContext::new(
-                window_target.frame(),
-                &self.graphics.texture_provider,
-                &mut self.input_controller,
-            )
-            .builder();
+    fn frame_pacing_message(&self) -> String {
+        let pacing = self.settings.frame_pacing;
+        tr!(
+            "pacing.current",
+            ticks = pacing.max_catch_up_ticks,
+            cadence = match pacing.render_cadence {
+                Some(cadence) => tr!("pacing.cadence_fixed", fps = cadence),
+                None => tr!("pacing.cadence_real"),
+            },
+            limit = match pacing.frame_limit {
+                FrameLimit::Auto => tr!("pacing.limit_auto"),
+                FrameLimit::Off => tr!("pacing.limit_off"),
+                FrameLimit::Fps(fps) => tr!("pacing.limit_fps", fps = fps),
+            },
+            dropped = format!("{:.3}", self.frame_pacer.dropped_time())
+        )
+    }
 
-            self.gui.render(&mut gui_builder);
+    /// Changes how many physics ticks a frame can catch up on, fixes how long each frame counts as, or caps the frame
+    /// rate.
+    fn configure_frame_pacing(&mut self, args: &[String]) {
+        let pacing = &mut self.settings.frame_pacing;
+        match args {
+            [] => {}
+            [setting, value] if setting == "catchup" => match value.parse::<u32>() {
+                Ok(ticks) if ticks > 0 => pacing.max_catch_up_ticks = ticks,
+                _ => {
+                    self.message_log.push_format(&tr!("pacing.usage"));
+                    return;
+                }
+            },
+            [setting, value] if setting == "cadence" => {
+                pacing.render_cadence = match value.as_str() {
+                    "off" => None,
+                    _ => match value.parse::<f64>() {
+                        Ok(fps) if fps.is_finite() && fps > 0.0 => Some(fps),
+                        _ => {
+                            self.message_log.push_format(&tr!("pacing.usage"));
+                            return;
+                        }
+                    },
+                };
+            }
+            [setting, value] if setting == "limit" => {
+                pacing.frame_limit = match value.as_str() {
+                    "auto" => FrameLimit::Auto,
+                    "off" => FrameLimit::Off,
+                    _ => match value.parse::<f64>() {
+                        Ok(fps) if fps.is_finite() && fps > 0.0 => FrameLimit::Fps(fps),
+                        _ => {
+                            self.message_log.push_format(&tr!("pacing.usage"));
+                            return;
+                        }
+                    },
+                };
+            }
+            _ => {
+                self.message_log.push_format(&tr!("pacing.usage"));
+                return;
+            }
+        }
 
-            self.frame_counter.tick();
+        if !args.is_empty() {
+            self.save_settings();
+        }
+        self.message_log.push_format(&self.frame_pacing_message());
+    }
 
-            let report_string = if let Some(PerformanceReport {
-                mean,
-                slowest,
-                fastest,
-                ..
-            }) = self.last_performance_report.1
-            {
-                let mean_ms = mean.as_micros() as f64 / 1000.0;
-                let slowest_ms = slowest.as_micros() as f64 / 1000.0;
-                let fastest_ms = fastest.as_micros() as f64 / 1000.0;
+    /// Starts circling the selected entity, or goes back to the user's point of view.
+    pub fn toggle_orbit_camera(&mut self) {
+        if self.orbit_camera.is_some() {
+            self.leave_orbit_camera();
+            self.message_log.push_format(&tr!("orbit.off"));
+            return;
+        }
+        if self.photo_mode.is_some() || self.replay_player.is_some() {
+            self.message_log.push_format(&tr!("orbit.unavailable"));
+            return;
+        }
+        let Some(entity_id) = self
+            .selected_entity
+            .filter(|&entity_id| entity_id != self.physics.universe().user_entity_id)
+        else {
+            self.message_log.push_format(&tr!("orbit.nothing_selected"));
+            return;
+        };
 
-                let mean_fps = (1.0 / mean.as_secs_f64()) as u32;
-                let slowest_fps = (1.0 / slowest.as_secs_f64()) as u32;
-                let fastest_fps = (1.0 / fastest.as_secs_f64()) as u32;
+        let orbit_camera = OrbitCamera::new(entity_id, &self.player_controller);
+        self.player_controller.camera = orbit_camera.camera();
+        self.orbit_camera = Some(orbit_camera);
+        self.message_log.push_format(&tr!("orbit.on"));
+    }
 
-                format!("§b{mean_ms}ms/{mean_fps}fps §r(§a↑{fastest_ms}ms/{fastest_fps}fps§r | §c↓{slowest_ms}ms/{slowest_fps}fps§r)")
-            } else {
-                "...".to_owned()
-            };
+    pub fn leave_orbit_camera(&mut self) {
+        if let Some(orbit_camera) = self.orbit_camera.take() {
+            self.player_controller.rotation = orbit_camera.previous_rotation;
+            self.player_controller.update_camera();
+        }
+    }
 
-            if self.last_performance_report.0.elapsed() > Duration::from_millis(1000) {
-                self.last_performance_report.1 = self.frame_counter.flush();
-                self.last_performance_report.0 = Instant::now();
+    /// Turns and zooms the orbit camera, and lets go once what it's circling is gone.
+    fn update_orbit_camera(&mut self, delta: f64) {
+        let Some(orbit_camera) = &mut self.orbit_camera else {
+            return;
+        };
+        if !self
+            .physics
+            .universe()
+            .entities
+            .contains_key(&orbit_camera.target)
+        {
+            self.leave_orbit_camera();
+            self.message_log.push_format(&tr!("orbit.target_gone"));
+            return;
+        }
+        orbit_camera.update(&mut self.input_controller, delta);
+        self.player_controller.camera = orbit_camera.camera();
+    }
 
-                debug!("{}", StyledText::from_format_string(&report_string));
-            }
+    /// Goes into photo mode, or back out of it.
+    pub fn toggle_photo_mode(&mut self) {
+        if self.photo_mode.is_some() {
+            self.leave_photo_mode();
+            return;
+        }
+        // everyone else's universe keeps going
+        if self.net.is_some() {
+            self.message_log.push_format(&tr!("photo.multiplayer"));
+            return;
+        }
 
-            let user_event = self.universe.user_event_now();
-            let pos = user_event.frame.position.truncate();
-            let vel = user_event.frame.velocity;
-            let debug_text = format!(
-                "Displacement: {:.3}, {:.3}, {:.3} ({:.3}cs from origin)\nVelocity: {:.3}c ({:.3}, {:.3}, {:.3})\nLorentz factor: {:.3}\n{}",
-                pos.x, pos.y, pos.z, pos.magnitude(), vel.magnitude(), vel.x, vel.y, vel.z, lorentz_factor(vel), report_string,);
+        // it starts from wherever the user is
+        self.leave_orbit_camera();
+        self.photo_mode = Some(PhotoMode::new(&self.player_controller, self.physics.paused));
+        self.physics.paused = true;
+        self.chat_box.close(&mut self.input_controller);
+    }
 
-            gui_builder.element(TextLabel {
-                transform: GuiTransform {
-                    size: UDim2::from_scale(1.0, 1.0),
-                    ..Default::default()
-                },
-                text: StyledText::from_format_string(&debug_text),
-                char_pixel_height: 16.0,
-                text_alignment: vec2(0.0, 0.0),
-                background_color: GuiColor::BLACK.with_alpha(0.75),
-                background_type: TextBackgroundType::BoundingBoxPerLine,
-            });
+    pub fn leave_photo_mode(&mut self) {
+        if let Some(photo_mode) = self.photo_mode.take() {
+            self.physics.paused = photo_mode.was_paused;
+        }
+    }
 
-            let finished_vertices = gui_builder.finish();
+    /// Instead of the HUD, there's just a line of help at the bottom, which doesn't end up in photos.
+    fn render_photo_mode(&mut self, scene_target: &Rc<RenderTarget>) {
+        let Some(photo_mode) = &self.photo_mode else {
+            return;
+        };
+        let filter = photo_mode.filter;
+        let hint = tr!(
+            "photo.hint",
+            fov = format!("{:.0}", photo_mode.fov.0),
+            roll = format!("{:.0}", photo_mode.roll.0),
+            filter = filter.name()
+        );
 
-            self.graphics
-                .gui_vertices
-                .replace_contents(finished_vertices);
-            self.graphics_controller.render(
-                &window_target,
-                &self.graphics.pipeline_2d,
-                self.graphics.gui_vertices.as_pipeline_buffers(),
-                [self.graphics.texture_provider.bind_group()],
-            );
+        if self.input_controller.action_pressed(Action::CapturePhoto) {
+            self.capture_photo();
         }
 
+        let shown_target = match self.graphics.photo_filter_pipelines.get(&filter) {
+            Some(pipeline) => {
+                let (_, filtered_target) = self
+                    .graphics_controller
+                    .window_sized_render_target("photo_preview");
+                filtered_target.clear();
+                self.graphics_controller.render_fullscreen(
+                    &filtered_target,
+                    pipeline,
+                    scene_target.texture(),
+                );
+                filtered_target
+            }
+            None => Rc::clone(scene_target),
+        };
+
+        let mut gui_builder = GuiContext::new(
+            shown_target.frame(),
+            &self.graphics.texture_provider,
+            &mut self.input_controller,
+        )
+        .builder();
+        gui_builder.element(TextLabel {
+            transform: GuiTransform {
+                position: UDim2::new(0.5, (1.0, -8.0)),
+                size: UDim2::new(1.0, (0.0, 16.0)),
+                anchor_point: vec2(0.5, 1.0),
+                ..Default::default()
+            },
+            text: StyledText::from_format_string(&hint),
+            char_pixel_height: 16.0,
+            text_alignment: TextLabel::ALIGN_BOTTOM_CENTER,
+            background_color: GuiColor::BLACK.with_alpha(0.75),
+            background_type: TextBackgroundType::BoundingBoxPerLine,
+            ..Default::default()
+        });
+        self.graphics
+            .gui_vertices
+            .replace_contents(gui_builder.finish());
+        self.graphics_controller.render(
+            &shown_target,
+            &self.graphics.pipeline_2d,
+            self.graphics.gui_vertices.as_pipeline_buffers(),
+            [self.graphics.texture_provider.bind_group()],
+        );
+
         let _ = self
             .graphics_controller
-            .present_to_screen(window_target.texture());
+            .present_to_screen(shown_target.texture());
+        self.render_auxiliary_view();
     }
 
-    pub fn winit_event(&mut self, event: WinitEvent) {
-        self.input_controller.winit_event(event);
+    /// Renders the photo mode camera's view at [`PHOTO_SCALE`](super::photo_mode::PHOTO_SCALE) times the window's
+    /// size, with the filter on top, and saves it in the background.
+    fn capture_photo(&mut self) {
+        let Some(photo_mode) = &self.photo_mode else {
+            return;
+        };
+        let camera = photo_mode.camera();
+        let filter = photo_mode.filter;
+        let size = PhotoMode::photo_size(self.graphics_controller.window_size());
+
+        let (_, photo_target) =
+            self.graphics_controller
+                .render_target("photo", size.width, size.height);
+        photo_target.clear();
+        self.update_camera_uniform(camera, photo_target.aspect_ratio());
+        self.render_simple_sky(&photo_target);
+        self.render_entities(&photo_target);
+
+        let photo_target = match self.graphics.photo_filter_pipelines.get(&filter) {
+            Some(pipeline) => {
+                let (_, filtered_target) = self.graphics_controller.render_target(
+                    "photo_filtered",
+                    size.width,
+                    size.height,
+                );
+                filtered_target.clear();
+                self.graphics_controller.render_fullscreen(
+                    &filtered_target,
+                    pipeline,
+                    photo_target.texture(),
+                );
+                filtered_target
+            }
+            None => photo_target,
+        };
+        let image = self
+            .graphics_controller
+            .handle()
+            .read_texture_to_image(&photo_target.texture().inner_texture);
+
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let name = format!("photo-{timestamp}");
+        self.tasks.spawn(
+            move || PhotoMode::save_photo(&image, &name),
+            |app, result| match result {
+                Ok(path) => app
+                    .message_log
+                    .push_format(&tr!("photo.saved", path = path.display())),
+                Err(error) => warn!("Couldn't save the photo: {}", error),
+            },
+        );
     }
 }
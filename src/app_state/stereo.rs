@@ -0,0 +1,72 @@
+use crate::{graphics::camera::Camera, shared::localization::tr};
+use cgmath::vec3;
+use serde::{Deserialize, Serialize};
+
+/// How far apart the eyes are by default, in light-seconds. Things get spawned 30 light-seconds away, and this is
+/// about right for that.
+pub const DEFAULT_EYE_SEPARATION: f64 = 1.0;
+pub const MAX_EYE_SEPARATION: f64 = 20.0;
+/// The render targets each eye gets drawn into before they're put together.
+pub const STEREO_TARGET_NAMES: [&str; 2] = ["stereo_left", "stereo_right"];
+
+/// How the two eyes' views get put together on one screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+pub enum StereoMode {
+    #[default]
+    Off,
+    /// Red for the left eye and cyan for the right, for red-cyan glasses.
+    Anaglyph,
+    /// Each eye on its own half of the screen, for viewers and cross-eyed viewing.
+    SideBySide,
+}
+
+impl StereoMode {
+    pub const ALL: [Self; 3] = [Self::Off, Self::Anaglyph, Self::SideBySide];
+
+    /// What it's called in the `/stereo` command.
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Anaglyph => "anaglyph",
+            Self::SideBySide => "side_by_side",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.id() == id)
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            Self::Off => tr!("stereo.mode.off"),
+            Self::Anaglyph => tr!("stereo.mode.anaglyph"),
+            Self::SideBySide => tr!("stereo.mode.side_by_side"),
+        }
+    }
+
+    /// Its fragment shader in `stereo.wgsl`, or `None` if there's only the one view.
+    pub fn entry_point(self) -> Option<&'static str> {
+        match self {
+            Self::Off => None,
+            Self::Anaglyph => Some("frag_anaglyph"),
+            Self::SideBySide => Some("frag_side_by_side"),
+        }
+    }
+
+    /// How big each eye's view gets drawn, for a screen of `width` by `height`.
+    pub fn eye_size(self, width: u32, height: u32) -> (u32, u32) {
+        match self {
+            Self::SideBySide => ((width / 2).max(1), height),
+            _ => (width, height),
+        }
+    }
+}
+
+/// The left and right eyes' cameras, side by side and looking the same way as `camera`. They're parallel rather than
+/// turned in, so there's no keystone distortion, and whatever's infinitely far away lines up.
+pub fn eye_cameras(camera: &Camera, separation: f32) -> [Camera; 2] {
+    [-0.5, 0.5].map(|side| Camera {
+        position: camera.position + camera.rotation * vec3(side * separation, 0.0, 0.0),
+        ..*camera
+    })
+}
@@ -0,0 +1,115 @@
+use super::scenario::{TourStep, TourTrigger};
+use crate::special::universe::Universe;
+use cgmath::InnerSpace;
+
+/// Walks through a scenario's [`TourStep`]s one at a time.
+#[derive(Debug, Clone, Default)]
+pub struct GuidedTour {
+    pub steps: Vec<TourStep>,
+    /// The step that's up, or waiting for its trigger.
+    pub index: usize,
+    /// Real time the current step has been up for, or `None` while it's waiting for its trigger.
+    shown_for: Option<f64>,
+    /// Whether the universe was already paused when the current step paused it.
+    was_paused: Option<bool>,
+}
+
+impl GuidedTour {
+    pub fn new(steps: Vec<TourStep>) -> Self {
+        Self {
+            steps,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.index >= self.steps.len()
+    }
+
+    /// The step that's up right now, if there is one.
+    pub fn current(&self) -> Option<&TourStep> {
+        self.shown_for?;
+        self.steps.get(self.index)
+    }
+
+    fn trigger_met(step: &TourStep, universe: &Universe) -> bool {
+        match step.trigger {
+            TourTrigger::Immediately => true,
+            TourTrigger::Time(time) => universe.scenario_time(universe.time) >= time,
+            TourTrigger::Near(distance) => {
+                let Some(entity_event) = step
+                    .entity_id
+                    .and_then(|entity_id| universe.get_event_at_time(entity_id, universe.time))
+                else {
+                    return true;
+                };
+                let user_position = universe.user_event_now().frame.position;
+                (entity_event.frame.position - user_position)
+                    .truncate()
+                    .magnitude()
+                    <= distance
+            }
+            TourTrigger::Speed(speed) => {
+                universe.user_event_now().frame.velocity.magnitude() >= speed
+            }
+        }
+    }
+
+    /// Shows the next step once its trigger's met, and moves on from the current one once it's been up long enough
+    /// (or right away with `advance`). Returns what the universe's paused state should change to, if anything.
+    pub fn update(
+        &mut self,
+        universe: &Universe,
+        paused: bool,
+        delta: f64,
+        advance: bool,
+    ) -> Option<bool> {
+        let step = self.steps.get(self.index)?;
+        match self.shown_for {
+            None => {
+                if !Self::trigger_met(step, universe) {
+                    return None;
+                }
+                self.shown_for = Some(0.0);
+                if step.pause {
+                    self.was_paused = Some(paused);
+                    return Some(true);
+                }
+                None
+            }
+            Some(shown_for) => {
+                let shown_for = shown_for + delta;
+                if advance || (!step.pause && shown_for >= step.duration) {
+                    return self.skip();
+                }
+                self.shown_for = Some(shown_for);
+                None
+            }
+        }
+    }
+
+    /// Moves on to the next step, even if the current one's still waiting for its trigger. Returns what the universe's
+    /// paused state should go back to, if the step paused it.
+    pub fn skip(&mut self) -> Option<bool> {
+        if self.is_finished() {
+            return None;
+        }
+        self.index += 1;
+        self.shown_for = None;
+        self.was_paused.take()
+    }
+
+    /// Ends the tour early.
+    pub fn stop(&mut self) -> Option<bool> {
+        let was_paused = self.skip();
+        self.index = self.steps.len();
+        was_paused
+    }
+
+    /// Starts over from the first step.
+    pub fn restart(&mut self) -> Option<bool> {
+        let was_paused = self.stop();
+        self.index = 0;
+        was_paused
+    }
+}
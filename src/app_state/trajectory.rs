@@ -0,0 +1,162 @@
+use crate::special::{
+    transform::lorentz_factor,
+    universe::{EntityId, Universe},
+};
+use anyhow::Result;
+use serde::Serialize;
+use std::{fmt::Write, fs, path::PathBuf};
+
+pub const EXPORTS_DIR: &str = "exports";
+/// Coordinate time between samples, in seconds, unless another cadence is asked for.
+pub const DEFAULT_EXPORT_CADENCE: f64 = 0.1;
+/// Per entity, so a tiny cadence over a long run doesn't eat all the memory.
+pub const MAX_SAMPLES_PER_ENTITY: usize = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "csv" => Some(Self::Csv),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// Where an entity was at one coordinate time, in the universe's rest frame.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TrajectorySample {
+    pub coord_time: f64,
+    pub position: [f64; 3],
+    pub velocity: [f64; 3],
+    pub gamma: f64,
+    pub proper_time: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Trajectory {
+    pub entity_id: EntityId,
+    /// The prefab it was made from, if any, to tell entities apart by more than their IDs.
+    pub prefab: Option<String>,
+    pub samples: Vec<TrajectorySample>,
+}
+
+/// A batch of sampled worldlines, ready to write out for plotting elsewhere.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrajectoryExport {
+    /// Coordinate time between samples.
+    pub cadence: f64,
+    pub trajectories: Vec<Trajectory>,
+}
+
+impl TrajectoryExport {
+    /// Samples each entity's worldline every `cadence` seconds of coordinate time, from its first keyframe event up
+    /// to now. Entities that don't exist are skipped.
+    pub fn sample(universe: &Universe, entity_ids: &[EntityId], cadence: f64) -> Self {
+        let trajectories = entity_ids
+            .iter()
+            .filter_map(|&entity_id| {
+                let entity = universe.entities.get(&entity_id)?;
+                let worldline = &entity.worldline;
+                let first_event = worldline.events().front()?;
+                let start_time = first_event.frame.position.w.min(universe.time);
+
+                let count = (((universe.time - start_time) / cadence).floor() as usize + 1)
+                    .min(MAX_SAMPLES_PER_ENTITY);
+                let mut times: Vec<f64> = (0..count)
+                    .map(|i| start_time + cadence * i as f64)
+                    .collect();
+                // the last sample always lands on now, even if the cadence doesn't divide evenly
+                if times.last().is_some_and(|&time| time < universe.time) {
+                    times.push(universe.time);
+                }
+
+                let samples = times
+                    .into_iter()
+                    .map(|coord_time| {
+                        let event = worldline.get_event_at_time(coord_time);
+                        let velocity = event.frame.velocity;
+                        // in the scenario's own coordinates, however far the origin has moved since
+                        TrajectorySample {
+                            coord_time: universe.scenario_time(coord_time),
+                            position: (event.frame.position.truncate() + universe.origin).into(),
+                            velocity: velocity.into(),
+                            gamma: lorentz_factor(velocity),
+                            proper_time: event.proper_time,
+                        }
+                    })
+                    .collect();
+
+                Some(Trajectory {
+                    entity_id,
+                    prefab: entity.prefab.map(|prefab| prefab.name().to_owned()),
+                    samples,
+                })
+            })
+            .collect();
+
+        Self {
+            cadence,
+            trajectories,
+        }
+    }
+
+    pub fn sample_count(&self) -> usize {
+        self.trajectories
+            .iter()
+            .map(|trajectory| trajectory.samples.len())
+            .sum()
+    }
+
+    /// One row per sample, with every entity in the same table.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("entity,prefab,coord_time,x,y,z,vx,vy,vz,gamma,proper_time\n");
+        for trajectory in self.trajectories.iter() {
+            let entity_id = String::from(trajectory.entity_id);
+            let prefab = trajectory.prefab.as_deref().unwrap_or("");
+            for sample in trajectory.samples.iter() {
+                let [x, y, z] = sample.position;
+                let [vx, vy, vz] = sample.velocity;
+                let _ = writeln!(
+                    csv,
+                    "{entity_id},{prefab},{},{x},{y},{z},{vx},{vy},{vz},{},{}",
+                    sample.coord_time, sample.gamma, sample.proper_time
+                );
+            }
+        }
+        csv
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn path(name: &str, format: ExportFormat) -> PathBuf {
+        PathBuf::from(EXPORTS_DIR).join(format!("{name}.{}", format.extension()))
+    }
+
+    /// Writes it to [`EXPORTS_DIR`], returning where it went.
+    pub fn save(&self, name: &str, format: ExportFormat) -> Result<PathBuf> {
+        let contents = match format {
+            ExportFormat::Csv => self.to_csv(),
+            ExportFormat::Json => self.to_json()?,
+        };
+        fs::create_dir_all(EXPORTS_DIR)?;
+        let path = Self::path(name, format);
+        fs::write(&path, contents)?;
+        Ok(path)
+    }
+}
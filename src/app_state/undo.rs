@@ -0,0 +1,87 @@
+use super::physics::{PhysicsThread, UniverseEdit};
+use cgmath::Vector4;
+
+/// How many changes can be undone before the oldest ones are forgotten.
+pub const MAX_UNDO_ENTRIES: usize = 100;
+
+/// A change that can be undone, as the edits that make it and the edits that take it back.
+#[derive(Debug, Clone)]
+pub struct UndoEntry {
+    /// What gets shown when it's undone or redone, e.g. "Spawn beacon".
+    pub label: String,
+    pub redo: Vec<UniverseEdit>,
+    pub undo: Vec<UniverseEdit>,
+}
+
+impl UndoEntry {
+    pub fn new(label: impl Into<String>, redo: Vec<UniverseEdit>, undo: Vec<UniverseEdit>) -> Self {
+        Self {
+            label: label.into(),
+            redo,
+            undo,
+        }
+    }
+}
+
+/// Edits made by hand that can be undone and redone. Anything that replaces the whole universe should
+/// [`clear()`](UndoStack::clear) it, since the edits won't make sense anymore.
+#[derive(Debug, Default)]
+pub struct UndoStack {
+    undo: Vec<UndoEntry>,
+    redo: Vec<UndoEntry>,
+}
+
+impl UndoStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes the change, and forgets anything that was undone before it.
+    pub fn perform(&mut self, physics: &mut PhysicsThread, entry: UndoEntry) {
+        for edit in entry.redo.iter() {
+            physics.edit(edit.clone());
+        }
+
+        self.redo.clear();
+        self.undo.push(entry);
+        if self.undo.len() > MAX_UNDO_ENTRIES {
+            self.undo.remove(0);
+        }
+    }
+
+    /// Takes back the last change, returning its label.
+    pub fn undo(&mut self, physics: &mut PhysicsThread) -> Option<&str> {
+        let entry = self.undo.pop()?;
+        for edit in entry.undo.iter() {
+            physics.edit(edit.clone());
+        }
+
+        self.redo.push(entry);
+        self.redo.last().map(|entry| entry.label.as_str())
+    }
+
+    /// Makes the last undone change again, returning its label.
+    pub fn redo(&mut self, physics: &mut PhysicsThread) -> Option<&str> {
+        let entry = self.redo.pop()?;
+        for edit in entry.redo.iter() {
+            physics.edit(edit.clone());
+        }
+
+        self.undo.push(entry);
+        self.undo.last().map(|entry| entry.label.as_str())
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// Keeps every entry lined up with the universe after it's been rebased by `offset`.
+    pub fn rebase(&mut self, offset: Vector4<f64>) {
+        for entry in self.undo.iter_mut().chain(self.redo.iter_mut()) {
+            for edit in entry.redo.iter_mut().chain(entry.undo.iter_mut()) {
+                edit.translate(-offset);
+            }
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use crate::special::{
+    inertial_frame::InertialFrame,
+    universe::{EntityId, Universe},
+};
+use cgmath::Vector4;
+use serde::{Deserialize, Serialize};
+
+pub const MAX_WAYPOINTS: usize = 16;
+
+/// What a waypoint's pinned to.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum WaypointTarget {
+    /// Follows an entity around.
+    Entity(EntityId),
+    /// Stays put in an inertial frame, going through this event.
+    Frame(InertialFrame),
+}
+
+/// A named place to navigate to, marked on the HUD.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Waypoint {
+    pub name: String,
+    pub target: WaypointTarget,
+}
+
+impl Waypoint {
+    /// Keeps a waypoint pinned to a frame in the same place after the universe is rebased by `offset`.
+    pub fn rebase(&mut self, offset: Vector4<f64>) {
+        if let WaypointTarget::Frame(frame) = &mut self.target {
+            frame.position -= offset;
+        }
+    }
+
+    /// Where the waypoint is at `coord_time`, or `None` if its entity isn't around anymore.
+    pub fn frame_at_time(&self, universe: &Universe, coord_time: f64) -> Option<InertialFrame> {
+        match self.target {
+            WaypointTarget::Entity(entity_id) => universe
+                .get_event_at_time(entity_id, coord_time)
+                .map(|event| event.frame),
+            WaypointTarget::Frame(frame) => Some(frame.predict(coord_time - frame.position.w)),
+        }
+    }
+}
+
+/// How much proper time getting `distance` light-seconds away takes at a constant proper `acceleration`, speeding up
+/// for the first half and slowing back down for the second, from rest and ending at rest.
+pub fn travel_proper_time(distance: f64, acceleration: f64) -> f64 {
+    // each half is a hyperbolic motion, covering (cosh(a·τ) - 1) / a
+    2.0 / acceleration * (1.0 + acceleration * distance / 2.0).acosh()
+}
@@ -0,0 +1,245 @@
+use crate::gui::sound::{GuiSound, GuiSoundTheme};
+use log::{trace, warn};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Debug,
+    sync::mpsc::{self, Receiver, Sender},
+};
+
+#[cfg(feature = "audio")]
+pub use rodio_output::RodioSoundOutput;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundSettings {
+    /// One of [`GuiSoundTheme::NAMES`].
+    pub theme: String,
+    /// From `0.0` to `1.0`.
+    pub volume: f32,
+}
+
+impl Default for SoundSettings {
+    fn default() -> Self {
+        Self {
+            theme: "default".into(),
+            volume: 0.5,
+        }
+    }
+}
+
+/// Whatever actually makes noise.
+pub trait SoundOutput: Debug + Send {
+    fn play(&mut self, sound_name: &str, volume: f32);
+}
+
+/// Doesn't play anything, just logs which sounds would've played. Used when there's no audio device, or when
+/// built without the `audio` feature.
+#[derive(Debug, Default)]
+pub struct LogSoundOutput;
+
+impl SoundOutput for LogSoundOutput {
+    fn play(&mut self, sound_name: &str, volume: f32) {
+        trace!("Playing sound '{}' at volume {:.2}", sound_name, volume);
+    }
+}
+
+#[derive(Debug)]
+pub struct AudioSystem {
+    gui_sender: Sender<GuiSound>,
+    gui_receiver: Receiver<GuiSound>,
+    output: Box<dyn SoundOutput>,
+
+    pub gui_theme: GuiSoundTheme,
+    pub gui_volume: f32,
+}
+
+impl Default for AudioSystem {
+    /// Plays through the default audio device, if there is one.
+    fn default() -> Self {
+        #[cfg(feature = "audio")]
+        if let Some(output) = RodioSoundOutput::new() {
+            return Self::new(Box::new(output));
+        }
+        Self::new(Box::new(LogSoundOutput))
+    }
+}
+
+impl AudioSystem {
+    pub fn new(output: Box<dyn SoundOutput>) -> Self {
+        let (gui_sender, gui_receiver) = mpsc::channel();
+
+        Self {
+            gui_sender,
+            gui_receiver,
+            output,
+
+            gui_theme: GuiSoundTheme::default(),
+            gui_volume: 0.5,
+        }
+    }
+
+    /// A sender that GUI components can use to request sounds, see [`GuiContext::play_sound()`].
+    ///
+    /// [`GuiContext::play_sound()`]: crate::gui::element::GuiContext::play_sound
+    pub fn gui_sender(&self) -> Sender<GuiSound> {
+        self.gui_sender.clone()
+    }
+
+    /// Switches to the theme and volume in `settings`, or the default theme if it doesn't know the one named there.
+    pub fn apply_settings(&mut self, settings: &SoundSettings) {
+        self.gui_theme = GuiSoundTheme::named(&settings.theme).unwrap_or_else(|| {
+            warn!("Unknown sound theme '{}'", settings.theme);
+            GuiSoundTheme::default()
+        });
+        self.gui_volume = settings.volume.clamp(0.0, 1.0);
+    }
+
+    /// Requests a GUI sound from outside the GUI, like when a keybind toggles something. It plays on the next update,
+    /// the same as ones from GUI components.
+    pub fn play(&self, sound: GuiSound) {
+        let _ = self.gui_sender.send(sound);
+    }
+
+    /// Plays everything that was requested since the last update. Each kind of GUI sound only plays once per
+    /// update, so a bunch of buttons getting hovered at once doesn't get loud.
+    pub fn update(&mut self) {
+        let mut requested = Vec::new();
+        for sound in self.gui_receiver.try_iter() {
+            if !requested.contains(&sound) {
+                requested.push(sound);
+            }
+        }
+
+        for sound in requested {
+            if let Some(sound_name) = self.gui_theme.resolve(sound) {
+                self.output.play(sound_name, self.gui_volume);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "audio")]
+mod rodio_output {
+    use super::SoundOutput;
+    use log::{debug, warn};
+    use rodio::{
+        source::{Buffered, SineWave},
+        Decoder, OutputStream, OutputStreamHandle, Source,
+    };
+    use std::{
+        collections::HashMap,
+        fs::File,
+        io::BufReader,
+        path::Path,
+        sync::mpsc::{self, Sender},
+        thread,
+        time::Duration,
+    };
+
+    /// Where sound files go. A sound named `gui/click` plays `sounds/gui/click.wav` if it's there, and a built-in
+    /// tone otherwise.
+    pub const SOUNDS_PATH: &str = "sounds";
+
+    type SoundFile = Buffered<Decoder<BufReader<File>>>;
+
+    /// Plays sounds through the default audio device with rodio. The device can't leave the thread it was opened on,
+    /// so it gets a thread of its own, and sounds are sent over to it.
+    #[derive(Debug)]
+    pub struct RodioSoundOutput {
+        sender: Sender<(String, f32)>,
+    }
+
+    impl RodioSoundOutput {
+        pub fn new() -> Option<Self> {
+            let (sender, receiver) = mpsc::channel::<(String, f32)>();
+            let (ready_sender, ready_receiver) = mpsc::channel();
+            let spawned = thread::Builder::new().name("audio".into()).spawn(move || {
+                let (_stream, handle) = match OutputStream::try_default() {
+                    Ok(output) => {
+                        let _ = ready_sender.send(Ok(()));
+                        output
+                    }
+                    Err(error) => {
+                        let _ = ready_sender.send(Err(error));
+                        return;
+                    }
+                };
+                let mut files = HashMap::new();
+                for (sound_name, volume) in receiver {
+                    play(&handle, &mut files, &sound_name, volume);
+                }
+            });
+            if let Err(error) = spawned {
+                warn!("Sound is unavailable: {}", error);
+                return None;
+            }
+
+            match ready_receiver.recv() {
+                Ok(Ok(())) => Some(Self { sender }),
+                Ok(Err(error)) => {
+                    warn!("Sound is unavailable: {}", error);
+                    None
+                }
+                Err(_) => None,
+            }
+        }
+    }
+
+    impl SoundOutput for RodioSoundOutput {
+        fn play(&mut self, sound_name: &str, volume: f32) {
+            let _ = self.sender.send((sound_name.to_owned(), volume));
+        }
+    }
+
+    /// A short beep for each of the default GUI sounds, as a frequency in Hz and a length in seconds, so there's
+    /// something to hear without any sound files.
+    fn builtin_tone(sound_name: &str) -> Option<(f32, f32)> {
+        Some(match sound_name {
+            "gui/hover" => (1760.0, 0.015),
+            "gui/click" => (880.0, 0.04),
+            "gui/toggle" => (660.0, 0.06),
+            "gui/error" => (220.0, 0.15),
+            _ => return None,
+        })
+    }
+
+    /// Loads `sound_name`'s file from [`SOUNDS_PATH`] the first time it's played. `None` if there isn't one, or it
+    /// couldn't be read.
+    fn load_file(sound_name: &str) -> Option<SoundFile> {
+        let path = Path::new(SOUNDS_PATH).join(format!("{sound_name}.wav"));
+        let file = File::open(&path).ok()?;
+        match Decoder::new(BufReader::new(file)) {
+            Ok(decoder) => Some(decoder.buffered()),
+            Err(error) => {
+                warn!("Couldn't read {}: {}", path.display(), error);
+                None
+            }
+        }
+    }
+
+    fn play(
+        handle: &OutputStreamHandle,
+        files: &mut HashMap<String, Option<SoundFile>>,
+        sound_name: &str,
+        volume: f32,
+    ) {
+        let file = files
+            .entry(sound_name.to_owned())
+            .or_insert_with(|| load_file(sound_name));
+        let result = if let Some(file) = file {
+            handle.play_raw(file.clone().amplify(volume).convert_samples())
+        } else if let Some((frequency, length)) = builtin_tone(sound_name) {
+            handle.play_raw(
+                SineWave::new(frequency)
+                    .take_duration(Duration::from_secs_f32(length))
+                    .amplify(volume * 0.25),
+            )
+        } else {
+            debug!("There's no sound called '{}'", sound_name);
+            return;
+        };
+        if let Err(error) = result {
+            warn!("Couldn't play sound '{}': {}", sound_name, error);
+        }
+    }
+}
@@ -0,0 +1,125 @@
+#[cfg(feature = "client")]
+use crate::app_state::settings::SETTINGS_PATH;
+use clap::Parser;
+use log::LevelFilter;
+use std::{net::IpAddr, path::PathBuf};
+
+/// Launch options. Most of them can also be set through environment variables, which is handy for CI.
+#[derive(Debug, Clone, Parser)]
+#[command(name = "worldline", version, about = "A special relativity sandbox")]
+pub struct Cli {
+    /// A scenario file to start in, instead of the default one.
+    #[arg(env = "WORLDLINE_SCENARIO")]
+    pub scenario: Option<PathBuf>,
+
+    /// Where settings are loaded from and saved to.
+    #[cfg(feature = "client")]
+    #[arg(long, env = "WORLDLINE_CONFIG", default_value = SETTINGS_PATH)]
+    pub config: PathBuf,
+
+    /// Initial window width, in physical pixels.
+    #[arg(long, requires = "height")]
+    pub width: Option<u32>,
+
+    /// Initial window height, in physical pixels.
+    #[arg(long, requires = "width")]
+    pub height: Option<u32>,
+
+    /// Borderless fullscreen on the current monitor.
+    #[arg(long, conflicts_with_all = ["width", "height"])]
+    pub fullscreen: bool,
+
+    /// Use the first graphics adapter whose name contains this (ignoring case), e.g. "nvidia" or "llvmpipe".
+    #[arg(long, env = "WORLDLINE_ADAPTER")]
+    pub adapter: Option<String>,
+
+    /// Overrides RUST_LOG, e.g. "debug" or "warn".
+    #[arg(long, env = "WORLDLINE_LOG_LEVEL")]
+    pub log_level: Option<LevelFilter>,
+
+    /// Simulate the scenario without opening a window, then exit.
+    #[arg(long, conflicts_with_all = ["benchmark", "record_input", "playback_input", "serve"])]
+    pub headless: bool,
+
+    /// How much simulated time a headless run covers, in seconds.
+    #[arg(long, default_value_t = 60.0, requires = "headless")]
+    pub headless_duration: f64,
+
+    /// Check the physics against closed-form solutions (constant acceleration, circular motion, a twin trip, and a
+    /// lattice of clocks), report how far off it is, then exit. Fails if anything's out of tolerance.
+    #[arg(long, conflicts_with_all = ["headless", "benchmark", "record_input", "playback_input", "serve"])]
+    pub verify: bool,
+
+    /// Draw pieces of the GUI offscreen and compare them to the golden images in this directory, then exit. Fails
+    /// if any of them changed.
+    #[arg(long, conflicts_with_all = ["headless", "verify", "benchmark", "record_input", "playback_input", "serve"])]
+    pub gui_snapshots: Option<PathBuf>,
+
+    /// Write the golden images for --gui-snapshots instead of comparing against them.
+    #[arg(long, requires = "gui_snapshots")]
+    pub update_snapshots: bool,
+
+    /// Fly through a stress scene (or the given scenario) for a fixed amount of time, write a report of how it
+    /// performed, then exit.
+    #[arg(long)]
+    pub benchmark: bool,
+
+    /// Where --benchmark writes report.json and frames.csv.
+    #[arg(long, default_value = "benchmark", requires = "benchmark")]
+    pub benchmark_output: PathBuf,
+
+    /// Seeds ID generation and fixes the frame time, so runs can be reproduced exactly.
+    #[arg(long, env = "WORLDLINE_SEED")]
+    pub seed: Option<u64>,
+
+    /// Record every input to this file, to be played back with --playback-input.
+    #[arg(long, env = "WORLDLINE_RECORD_INPUT")]
+    pub record_input: Option<PathBuf>,
+
+    /// Play back inputs recorded with --record-input.
+    #[arg(
+        long,
+        env = "WORLDLINE_PLAYBACK_INPUT",
+        conflicts_with = "record_input"
+    )]
+    pub playback_input: Option<PathBuf>,
+
+    /// Join a multiplayer server, e.g. "192.168.1.20" or "example.com:7905".
+    #[arg(
+        long,
+        env = "WORLDLINE_CONNECT",
+        conflicts_with_all = ["headless", "benchmark", "record_input", "playback_input", "serve"]
+    )]
+    pub connect: Option<String>,
+
+    /// Host a multiplayer server with the scenario, and join it. The port can be left out.
+    #[arg(
+        long,
+        num_args = 0..=1,
+        default_missing_value = "7905",
+        conflicts_with_all = ["connect", "headless", "benchmark", "record_input", "playback_input", "serve"]
+    )]
+    pub host: Option<u16>,
+
+    /// Run a dedicated multiplayer server for the scenario, with no window, until it's killed. The port can be
+    /// left out.
+    #[arg(
+        long,
+        num_args = 0..=1,
+        default_missing_value = "7905",
+        conflicts_with_all = ["benchmark", "record_input", "playback_input"]
+    )]
+    pub serve: Option<u16>,
+
+    /// The address a server (from --serve or --host) listens on.
+    #[arg(long, env = "WORLDLINE_BIND", default_value = "0.0.0.0")]
+    pub bind: IpAddr,
+
+    /// Turn away anyone past this many players.
+    #[arg(long, env = "WORLDLINE_MAX_PLAYERS")]
+    pub max_players: Option<usize>,
+
+    /// What other players see you as. The server picks something if it's left out.
+    #[arg(long, env = "WORLDLINE_NAME")]
+    pub name: Option<String>,
+}
@@ -0,0 +1,45 @@
+use crate::{
+    app_state::scenario::load_scenario_or_default,
+    cli::Cli,
+    net::{protocol::DEFAULT_PORT, server::Server},
+    shared::{crash::update_crash_context, determinism::seed_ids},
+};
+use anyhow::Result;
+use log::{info, warn};
+use std::net::{SocketAddr, TcpListener};
+
+/// Runs a [`Server`] for the scenario on the current thread, with no window or GPU involved, until the process is
+/// killed.
+pub fn run_dedicated_server(cli: &Cli) -> Result<()> {
+    seed_ids(cli.seed);
+
+    let scenario = load_scenario_or_default(cli.scenario.as_deref());
+    let loaded = scenario.build();
+    update_crash_context(|context| {
+        context.scenario = Some(scenario.name.clone());
+        context.entity_count = loaded.universe.entities.len();
+    });
+    if loaded.script.is_some() {
+        warn!(
+            "The script for '{}' won't run, scripts only run on clients",
+            scenario.name
+        );
+    }
+
+    let address = SocketAddr::new(cli.bind, cli.serve.unwrap_or(DEFAULT_PORT));
+    let listener = TcpListener::bind(address)?;
+    info!(
+        "Serving '{}' ({} entities) on {}{}",
+        scenario.name,
+        loaded.universe.entities.len(),
+        listener.local_addr()?,
+        match cli.max_players {
+            Some(max_players) => format!(", for up to {max_players} players"),
+            None => String::new(),
+        }
+    );
+
+    let mut server = Server::new(scenario.name, loaded.universe);
+    server.max_players = cli.max_players;
+    server.run(listener)
+}
@@ -1,4 +1,7 @@
-use cgmath::{vec3, Deg, Matrix4, Quaternion, SquareMatrix, Vector3};
+use super::graphics_controller::{BindGroupFormat, BindingFormat};
+use cgmath::{
+    vec3, Angle, Deg, InnerSpace, Matrix4, Quaternion, Rad, SquareMatrix, Vector2, Vector3,
+};
 
 #[rustfmt::skip]
 /// Since cgmath uses OpenGL's NDC space which has a range of [-1.0, +1.0] for the z-axis, but wgpu uses [0.0, +1.0],
@@ -15,8 +18,35 @@ pub const OPENGL_TO_WGPU_MATRIX: Matrix4<f32> = Matrix4::new(
 #[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct CameraUniform {
     pub view_projection: [[f32; 4]; 4],
-    pub _padding: [u32; 3], // this is the worst thing on the planet
+    pub view: [[f32; 4]; 4],
+    pub projection: [[f32; 4]; 4],
+    /// Camera space to world space.
+    pub inverse_view: [[f32; 4]; 4],
+    /// Clip space to camera space, for getting positions back from depth.
+    pub inverse_projection: [[f32; 4]; 4],
+    pub position: [f32; 3],
+    /// For logarithmic depth, when the shader has it on.
+    pub log_depth_coefficient: f32,
+    /// See [`Camera::velocity`].
+    pub velocity: [f32; 3],
     pub aspect_ratio: f32,
+    /// Last frame's `view_projection`, shifted to go from this frame's camera-relative space, for working out how far
+    /// things moved on screen. The same as `view_projection` for anything that isn't motion blurred.
+    pub previous_view_projection: [[f32; 4]; 4],
+    /// How much of the time between frames the shutter is open for, from 0 to 1. Zero means no motion blur.
+    pub shutter_fraction: f32,
+    pub _padding: [f32; 3],
+}
+
+impl CameraUniform {
+    pub const BIND_GROUP_LAYOUT: &'static BindGroupFormat = &BindGroupFormat {
+        label: "Camera",
+        bindings: &[BindingFormat::dynamic_uniform(
+            "camera",
+            wgpu::ShaderStages::VERTEX_FRAGMENT,
+            std::mem::size_of::<CameraUniform>() as u64,
+        )],
+    };
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -26,6 +56,9 @@ pub struct Camera {
     pub vertical_fov: Deg<f32>,
     pub near_plane: f32,
     pub far_plane: f32,
+    /// Relative to the frame everything's shown in, as a portion of the shown speed of light. Only ever not zero while
+    /// a change of frame is easing over.
+    pub velocity: Vector3<f32>,
 }
 
 impl Default for Camera {
@@ -36,17 +69,43 @@ impl Default for Camera {
             vertical_fov: Deg(40.0),
             near_plane: 0.001,
             far_plane: 15000.0,
+            velocity: vec3(0.0, 0.0, 0.0),
         }
     }
 }
 
+/// A model matrix for something at `position`, as seen from `origin`. The big translation gets taken out in f64 before
+/// anything's cast down to f32, so things that are far away don't jitter.
+pub fn camera_relative_model_matrix(
+    position: Vector3<f64>,
+    origin: Vector3<f64>,
+    local_matrix: Matrix4<f32>,
+) -> Matrix4<f32> {
+    (Matrix4::from_translation(position - origin) * local_matrix.cast::<f64>().unwrap())
+        .cast()
+        .unwrap()
+}
+
 impl Camera {
+    /// The same camera, with everything shifted so `origin` is at zero. Goes with
+    /// [`camera_relative_model_matrix()`].
+    pub fn relative_to(&self, origin: Vector3<f32>) -> Self {
+        Self {
+            position: self.position - origin,
+            ..*self
+        }
+    }
+
     pub fn get_transform(&self) -> Matrix4<f32> {
         Matrix4::from_translation(self.position) * Matrix4::from(self.rotation)
     }
 
-    pub fn build_view_projection_matrix(&self, aspect_ratio: f32) -> Matrix4<f32> {
-        let view_matrix = self.get_transform().invert().unwrap();
+    pub fn build_view_matrix(&self) -> Matrix4<f32> {
+        self.get_transform().invert().unwrap()
+    }
+
+    /// Already in wgpu's NDC space.
+    pub fn build_projection_matrix(&self, aspect_ratio: f32) -> Matrix4<f32> {
         let projection_matrix = cgmath::perspective(
             self.vertical_fov,
             aspect_ratio,
@@ -54,14 +113,31 @@ impl Camera {
             self.far_plane,
         );
 
-        OPENGL_TO_WGPU_MATRIX * projection_matrix * view_matrix
+        OPENGL_TO_WGPU_MATRIX * projection_matrix
+    }
+
+    pub fn build_view_projection_matrix(&self, aspect_ratio: f32) -> Matrix4<f32> {
+        self.build_projection_matrix(aspect_ratio) * self.build_view_matrix()
     }
 
     pub fn uniform(&self, aspect_ratio: f32) -> CameraUniform {
+        let view = self.build_view_matrix();
+        let projection = self.build_projection_matrix(aspect_ratio);
+        let view_projection = (projection * view).into();
         CameraUniform {
-            view_projection: self.build_view_projection_matrix(aspect_ratio).into(),
-            _padding: [0; 3],
+            view_projection,
+            view: view.into(),
+            projection: projection.into(),
+            inverse_view: self.get_transform().into(),
+            inverse_projection: projection.invert().unwrap().into(),
+            position: self.position.into(),
+            // maps the far plane to a depth of 1
+            log_depth_coefficient: 1.0 / (self.far_plane + 1.0).log2(),
+            velocity: self.velocity.into(),
             aspect_ratio,
+            previous_view_projection: view_projection,
+            shutter_fraction: 0.0,
+            _padding: [0.0; 3],
         }
     }
 
@@ -74,4 +150,20 @@ impl Camera {
             transformed.z,
         )
     }
+
+    /// The opposite of [`Camera::world_to_screen_point()`]: which way a point on the screen (from `(0.0, 0.0)` at
+    /// the top left to `(1.0, 1.0)` at the bottom right) is from the camera. Normalized.
+    pub fn screen_to_world_direction(
+        &self,
+        aspect_ratio: f32,
+        screen_point: Vector2<f32>,
+    ) -> Vector3<f32> {
+        let half_height = (Rad::from(self.vertical_fov) / 2.0).tan();
+        let view_direction = vec3(
+            (screen_point.x * 2.0 - 1.0) * half_height * aspect_ratio,
+            (1.0 - screen_point.y * 2.0) * half_height,
+            -1.0,
+        );
+        (self.rotation * view_direction).normalize()
+    }
 }
@@ -1,57 +1,402 @@
+use super::shader_preprocessor::preprocess;
 use super::texture::Texture;
 use super::vertex::Vertex2D;
 use crate::gui::color::GuiColor;
 use crate::shared::bounding_box::bbox;
+use crate::shared::performance_counter::RenderStats;
 use anyhow::{anyhow, Result};
 use cgmath::{vec2, Vector2};
 use futures::channel::oneshot;
 use futures::executor;
 use image::RgbaImage;
 use linear_map::LinearMap;
+use log::{info, warn};
 use std::cell::Cell;
+use std::collections::BTreeMap;
 use std::marker::PhantomData;
 use std::rc::Rc;
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicU32, AtomicU64, Ordering},
+    Arc,
+};
 use std::{mem, ops::Range};
 use wgpu::util::DeviceExt;
 use winit::{dpi::PhysicalSize, window::Window};
 
-pub type BindGroupFormat = [(wgpu::ShaderStages, wgpu::BindingType)];
-
-pub fn bind_group_format_to_layout_entries(
-    format: &BindGroupFormat,
-) -> Vec<wgpu::BindGroupLayoutEntry> {
-    format
-        .iter()
-        .copied()
-        .enumerate()
-        .map(|(i, (stages, binding_type))| wgpu::BindGroupLayoutEntry {
-            binding: i as u32,
-            visibility: stages,
-            ty: binding_type,
-            count: None,
-        })
-        .collect()
+/// One binding in a [`BindGroupFormat`]. The name is only for finding it by and for error messages, so it's best to
+/// match the shader.
+#[derive(Debug, Clone, Copy)]
+pub struct BindingFormat {
+    pub name: &'static str,
+    pub visibility: wgpu::ShaderStages,
+    pub ty: wgpu::BindingType,
+}
+
+impl BindingFormat {
+    pub const fn texture(name: &'static str, view_dimension: wgpu::TextureViewDimension) -> Self {
+        Self {
+            name,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension,
+                multisampled: false,
+            },
+        }
+    }
+
+    /// A depth texture, to be read with a [comparison sampler](BindingFormat::comparison_sampler()).
+    pub const fn depth_texture(name: &'static str, visibility: wgpu::ShaderStages) -> Self {
+        Self {
+            name,
+            visibility,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Depth,
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+        }
+    }
+
+    pub const fn sampler(name: &'static str) -> Self {
+        Self {
+            name,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+        }
+    }
+
+    /// A sampler that compares what it reads against a given value, like [`SAMPLER_DEPTH`](super::texture::SAMPLER_DEPTH).
+    pub const fn comparison_sampler(name: &'static str, visibility: wgpu::ShaderStages) -> Self {
+        Self {
+            name,
+            visibility,
+            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+        }
+    }
+
+    pub const fn uniform(name: &'static str, visibility: wgpu::ShaderStages) -> Self {
+        Self {
+            name,
+            visibility,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+        }
+    }
+
+    /// A uniform that gets picked out of a bigger buffer by an offset given when drawing, for a
+    /// [`UniformPool`](super::uniforms::UniformPool). `size` is how big each value is.
+    pub const fn dynamic_uniform(
+        name: &'static str,
+        visibility: wgpu::ShaderStages,
+        size: u64,
+    ) -> Self {
+        Self {
+            name,
+            visibility,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: true,
+                min_binding_size: wgpu::BufferSize::new(size),
+            },
+        }
+    }
+
+    /// What kind of resource it takes, for error messages.
+    fn kind(&self) -> &'static str {
+        match self.ty {
+            wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                ..
+            } => "uniform buffer",
+            wgpu::BindingType::Buffer { .. } => "storage buffer",
+            wgpu::BindingType::Sampler(_) => "sampler",
+            wgpu::BindingType::Texture { .. } => "texture view",
+            wgpu::BindingType::StorageTexture { .. } => "storage texture view",
+            wgpu::BindingType::AccelerationStructure => "acceleration structure",
+        }
+    }
+
+    /// Whether `resource` is the right kind of thing for this binding, and big enough if it's a buffer.
+    fn check(&self, resource: &wgpu::BindingResource) -> Result<()> {
+        let matches = match (&self.ty, resource) {
+            (
+                wgpu::BindingType::Buffer {
+                    min_binding_size, ..
+                },
+                wgpu::BindingResource::Buffer(binding),
+            ) => {
+                let size = binding
+                    .size
+                    .map_or(binding.buffer.size() - binding.offset, |size| size.get());
+                if let Some(min_binding_size) = min_binding_size {
+                    if size < min_binding_size.get() {
+                        anyhow::bail!(
+                            "'{}' needs at least {min_binding_size} bytes, but the buffer only has {size}",
+                            self.name
+                        );
+                    }
+                }
+                true
+            }
+            (wgpu::BindingType::Sampler(_), wgpu::BindingResource::Sampler(_)) => true,
+            (
+                wgpu::BindingType::Texture { .. } | wgpu::BindingType::StorageTexture { .. },
+                wgpu::BindingResource::TextureView(_),
+            ) => true,
+            _ => false,
+        };
+        if !matches {
+            let given = match resource {
+                wgpu::BindingResource::Buffer(_) => "buffer",
+                wgpu::BindingResource::BufferArray(_) => "buffer array",
+                wgpu::BindingResource::Sampler(_) => "sampler",
+                wgpu::BindingResource::SamplerArray(_) => "sampler array",
+                wgpu::BindingResource::TextureView(_) => "texture view",
+                wgpu::BindingResource::TextureViewArray(_) => "texture view array",
+                _ => "something else",
+            };
+            anyhow::bail!("'{}' takes a {}, but got a {given}", self.name, self.kind());
+        }
+        Ok(())
+    }
+}
+
+/// The layout of a bind group. Bindings are numbered in the order they're listed in.
+#[derive(Debug, Clone, Copy)]
+pub struct BindGroupFormat {
+    /// Shows up in graphics debuggers and error messages.
+    pub label: &'static str,
+    pub bindings: &'static [BindingFormat],
+}
+
+impl BindGroupFormat {
+    pub fn layout_entries(&self) -> Vec<wgpu::BindGroupLayoutEntry> {
+        self.bindings
+            .iter()
+            .enumerate()
+            .map(|(i, binding)| wgpu::BindGroupLayoutEntry {
+                binding: i as u32,
+                visibility: binding.visibility,
+                ty: binding.ty,
+                count: None,
+            })
+            .collect()
+    }
+
+    pub fn binding_index(&self, name: &str) -> Option<usize> {
+        self.bindings
+            .iter()
+            .position(|binding| binding.name == name)
+    }
+
+    /// Makes sure there's exactly one resource for each binding, in order, and that each is the kind the binding
+    /// takes. wgpu checks too, but only once it's too late to say which binding was wrong.
+    pub fn validate(&self, resources: &[wgpu::BindingResource]) -> Result<()> {
+        if resources.len() != self.bindings.len() {
+            let names = self
+                .bindings
+                .iter()
+                .map(|binding| binding.name)
+                .collect::<Vec<_>>()
+                .join(", ");
+            anyhow::bail!(
+                "bind group '{}' has {} bindings ({names}), but got {} resources",
+                self.label,
+                self.bindings.len(),
+                resources.len()
+            );
+        }
+        for (index, (binding, resource)) in self.bindings.iter().zip(resources).enumerate() {
+            binding.check(resource).map_err(|error| {
+                anyhow::anyhow!("bind group '{}' binding {index}: {error}", self.label)
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// A bind group layout along with the format it was made from, so bind groups made with it can be checked against it.
+#[derive(Debug)]
+pub struct BindGroupLayout {
+    pub format: &'static BindGroupFormat,
+    pub layout: wgpu::BindGroupLayout,
+}
+
+/// Fills in a bind group by binding name, in any order. Made with [`GpuHandle::bind_group()`] or
+/// [`Pipeline::bind_group()`].
+#[derive(Debug)]
+pub struct BindGroupBuilder<'a> {
+    handle: &'a GpuHandle,
+    layout: &'a BindGroupLayout,
+    resources: Vec<Option<wgpu::BindingResource<'a>>>,
+    error: Option<anyhow::Error>,
+}
+
+impl<'a> BindGroupBuilder<'a> {
+    pub fn new(handle: &'a GpuHandle, layout: &'a BindGroupLayout) -> Self {
+        Self {
+            handle,
+            layout,
+            resources: vec![None; layout.format.bindings.len()],
+            error: None,
+        }
+    }
+
+    pub fn bind(mut self, name: &str, resource: wgpu::BindingResource<'a>) -> Self {
+        if self.error.is_some() {
+            return self;
+        }
+        let label = self.layout.format.label;
+        match self.layout.format.binding_index(name) {
+            Some(index) if self.resources[index].is_some() => {
+                self.error = Some(anyhow::anyhow!(
+                    "bind group '{label}' got '{name}' more than once"
+                ));
+            }
+            Some(index) => self.resources[index] = Some(resource),
+            None => {
+                self.error = Some(anyhow::anyhow!(
+                    "bind group '{label}' has no binding called '{name}'"
+                ));
+            }
+        }
+        self
+    }
+
+    pub fn texture_view(self, name: &str, view: &'a wgpu::TextureView) -> Self {
+        self.bind(name, wgpu::BindingResource::TextureView(view))
+    }
+
+    pub fn sampler(self, name: &str, sampler: &'a wgpu::Sampler) -> Self {
+        self.bind(name, wgpu::BindingResource::Sampler(sampler))
+    }
+
+    pub fn buffer(self, name: &str, buffer: &'a wgpu::Buffer) -> Self {
+        self.bind(name, buffer.as_entire_binding())
+    }
+
+    pub fn build(self) -> Result<wgpu::BindGroup> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        let format = self.layout.format;
+        let resources = self
+            .resources
+            .into_iter()
+            .zip(format.bindings)
+            .map(|(resource, binding)| {
+                resource.ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "bind group '{}' is missing '{}'",
+                        format.label,
+                        binding.name
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        format.validate(&resources)?;
+        Ok(self
+            .handle
+            .create_validated_bind_group(self.layout, resources))
+    }
+}
+
+/// Running totals behind [`RenderStats`], counted by everything that shares a [`GpuHandle`].
+#[derive(Debug, Default)]
+pub struct RenderCounters {
+    render_passes: AtomicU32,
+    draw_calls: AtomicU32,
+    instances: AtomicU64,
+    vertices: AtomicU64,
+    buffer_bytes_written: AtomicU64,
+    render_targets_recreated: AtomicU32,
+}
+
+impl RenderCounters {
+    fn count_draw(&self, instances: u64, vertices: u64) {
+        self.draw_calls.fetch_add(1, Ordering::Relaxed);
+        self.instances.fetch_add(instances, Ordering::Relaxed);
+        self.vertices.fetch_add(vertices, Ordering::Relaxed);
+    }
+
+    fn count_buffer_write(&self, bytes: usize) {
+        self.buffer_bytes_written
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Everything counted so far, starting back over from zero.
+    pub fn take(&self) -> RenderStats {
+        RenderStats {
+            render_passes: self.render_passes.swap(0, Ordering::Relaxed),
+            draw_calls: self.draw_calls.swap(0, Ordering::Relaxed),
+            instances: self.instances.swap(0, Ordering::Relaxed),
+            vertices: self.vertices.swap(0, Ordering::Relaxed),
+            buffer_bytes_written: self.buffer_bytes_written.swap(0, Ordering::Relaxed),
+            render_targets_recreated: self.render_targets_recreated.swap(0, Ordering::Relaxed),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct GpuHandle {
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
+    pub render_counters: RenderCounters,
 }
 
 impl GpuHandle {
-    pub fn create_bind_group_layout(&self, format: &BindGroupFormat) -> wgpu::BindGroupLayout {
-        self.device
-            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: None,
-                entries: &bind_group_format_to_layout_entries(format),
-            })
+    /// [`wgpu::Queue::write_buffer()`], counted in [`RenderStats::buffer_bytes_written`].
+    pub fn write_buffer(&self, buffer: &wgpu::Buffer, offset: wgpu::BufferAddress, data: &[u8]) {
+        self.render_counters.count_buffer_write(data.len());
+        self.queue.write_buffer(buffer, offset, data);
     }
 
+    /// In bytes. 0 if there's no push constants at all, which for now is always: nothing draws with them yet, so the
+    /// device doesn't get asked for them (see [`GraphicsController::new()`]).
+    pub fn max_push_constant_size(&self) -> u32 {
+        if self
+            .device
+            .features()
+            .contains(wgpu::Features::PUSH_CONSTANTS)
+        {
+            self.device.limits().max_push_constant_size
+        } else {
+            0
+        }
+    }
+
+    pub fn create_bind_group_layout(&self, format: &'static BindGroupFormat) -> BindGroupLayout {
+        BindGroupLayout {
+            format,
+            layout: self
+                .device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some(format.label),
+                    entries: &format.layout_entries(),
+                }),
+        }
+    }
+
+    /// Makes a bind group from resources given in binding order. Panics with a message saying which binding's wrong
+    /// if they don't match the layout. [`GpuHandle::bind_group()`] goes by name instead.
     pub fn create_bind_group(
         &self,
-        layout: &wgpu::BindGroupLayout,
+        layout: &BindGroupLayout,
+        resources: Vec<wgpu::BindingResource>,
+    ) -> wgpu::BindGroup {
+        if let Err(error) = layout.format.validate(&resources) {
+            panic!("{error}");
+        }
+        self.create_validated_bind_group(layout, resources)
+    }
+
+    fn create_validated_bind_group(
+        &self,
+        layout: &BindGroupLayout,
         resources: Vec<wgpu::BindingResource>,
     ) -> wgpu::BindGroup {
         let entries: Vec<wgpu::BindGroupEntry<'_>> = resources
@@ -64,35 +409,31 @@ impl GpuHandle {
             .collect();
 
         self.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: None,
-            layout,
+            label: Some(layout.format.label),
+            layout: &layout.layout,
             entries: &entries,
         })
     }
 
-    pub fn binded_texture(
-        &self,
-        layout: &wgpu::BindGroupLayout,
-        texture: Texture,
-    ) -> BindedTexture {
-        let bind_group = self.create_bind_group(
-            layout,
-            vec![
-                wgpu::BindingResource::TextureView(&texture.view),
-                wgpu::BindingResource::Sampler(&texture.sampler),
-            ],
-        );
+    pub fn bind_group<'a>(&'a self, layout: &'a BindGroupLayout) -> BindGroupBuilder<'a> {
+        BindGroupBuilder::new(self, layout)
+    }
+
+    /// `layout` needs a `texture` and a `sampler`, like [`Texture::STANDARD_BIND_GROUP_LAYOUT`].
+    pub fn binded_texture(&self, layout: &BindGroupLayout, texture: Texture) -> BindedTexture {
+        let bind_group = self
+            .bind_group(layout)
+            .texture_view("texture", &texture.view)
+            .sampler("sampler", &texture.sampler)
+            .build()
+            .unwrap_or_else(|error| panic!("{error}"));
         BindedTexture {
             texture,
             bind_group,
         }
     }
 
-    pub fn binded_buffer<T>(
-        &self,
-        layout: &wgpu::BindGroupLayout,
-        buffer: GpuVec<T>,
-    ) -> BindedBuffer<T>
+    pub fn binded_buffer<T>(&self, layout: &BindGroupLayout, buffer: GpuVec<T>) -> BindedBuffer<T>
     where
         T: bytemuck::NoUninit,
     {
@@ -176,22 +517,23 @@ where
         usage: wgpu::BufferUsages,
         inner_vec: &Vec<T>,
     ) -> wgpu::Buffer {
-        handle
-            .device
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: None,
-                contents: unsafe {
-                    // SAFETY:
-                    // - contents of the buffer beyond the range of inner_vec are allowed to be undefined,
-                    // as long as there is no public way to retrieve a slice of a GpuVec's inner_buffer that goes
-                    // beyond the range of inner_vec
-                    // - we're still only getting a slice up to inner_vec's capacity, which means it's allocated
-                    // (and that's good i think)
-
-                    bytemuck::cast_slice(inner_vec.get_unchecked(..inner_vec.capacity()))
-                },
-                usage: usage | wgpu::BufferUsages::COPY_DST,
-            })
+        // the buffer is as big as inner_vec's capacity, so pushing doesn't have to recreate it every time. What's past
+        // inner_vec's length is left zeroed, since there's no public way to get at it
+        let size = (inner_vec.capacity() * mem::size_of::<T>()) as wgpu::BufferAddress;
+        let buffer = handle.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: size.next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT),
+            usage: usage | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: size > 0,
+        });
+
+        if size > 0 {
+            let contents: &[u8] = bytemuck::cast_slice(inner_vec);
+            buffer.slice(..).get_mapped_range_mut()[..contents.len()].copy_from_slice(contents);
+            handle.render_counters.count_buffer_write(contents.len());
+            buffer.unmap();
+        }
+        buffer
     }
 
     pub fn new(handle_arc: Arc<GpuHandle>, usage: wgpu::BufferUsages, contents: Vec<T>) -> Self {
@@ -271,7 +613,7 @@ where
             return;
         }
 
-        self.handle.queue.write_buffer(
+        self.handle.write_buffer(
             &self.inner_buffer,
             (range.start * mem::size_of::<T>()) as wgpu::BufferAddress,
             bytemuck::cast_slice(&self.inner_vec[range]),
@@ -398,11 +740,47 @@ where
     }
 }
 
+/// How a pipeline uses the depth buffer, if it has one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum DepthMode {
+    /// Tests against and writes depth like usual.
+    #[default]
+    Normal,
+    /// Only writes depth, without any color. Drawing everything opaque this way first means the real pass after it
+    /// only has to shade what ends up on top.
+    Prepass,
+    /// Only draws what's exactly at the depth a [`DepthMode::Prepass`] left behind.
+    Equal,
+}
+
+/// What sets a [`Pipeline::variant()`] apart from the pipeline it's made from.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct PipelineVariant {
+    pub shader_defines: Vec<&'static str>,
+    pub wireframe: bool,
+    pub depth_mode: DepthMode,
+}
+
+impl PipelineVariant {
+    /// The same, with the defines sorted and deduplicated, so variants that only differ in order match up.
+    fn normalized(&self) -> Self {
+        let mut shader_defines = self.shader_defines.clone();
+        shader_defines.sort_unstable();
+        shader_defines.dedup();
+        Self {
+            shader_defines,
+            ..self.clone()
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct PipelineDescriptor {
     pub name: &'static str,
 
     pub shader_source: &'static str,
+    /// Defined for the shader's `#ifdef`s. See [`preprocess()`].
+    pub shader_defines: Vec<&'static str>,
 
     pub vertex_shader_entry_point: &'static str,
     pub vertex_format: &'static [wgpu::VertexFormat],
@@ -412,9 +790,18 @@ pub struct PipelineDescriptor {
     pub target_format: Option<wgpu::TextureFormat>,
 
     pub bind_groups: &'static [&'static BindGroupFormat],
+    /// For small bits of data that change with every draw. Only some GPUs have them, see
+    /// [`GpuHandle::max_push_constant_size()`].
+    pub push_constant_ranges: &'static [wgpu::PushConstantRange],
 
     pub use_depth: bool,
     pub alpha_to_coverage_enabled: bool,
+    /// Blends as if the shader's output already has its color multiplied by its alpha, which is what drawing over a
+    /// transparent target leaves behind. See [`GraphicsController::composite()`].
+    pub premultiplied_alpha: bool,
+    /// Draws just the edges of triangles, if the GPU can.
+    pub wireframe: bool,
+    pub depth_mode: DepthMode,
 }
 
 impl Default for PipelineDescriptor {
@@ -423,6 +810,7 @@ impl Default for PipelineDescriptor {
             name: "",
 
             shader_source: "",
+            shader_defines: Vec::new(),
 
             vertex_shader_entry_point: "vert_main",
             vertex_format: &[],
@@ -432,9 +820,13 @@ impl Default for PipelineDescriptor {
             target_format: None,
 
             bind_groups: &[],
+            push_constant_ranges: &[],
 
             use_depth: true,
             alpha_to_coverage_enabled: false,
+            premultiplied_alpha: false,
+            wireframe: false,
+            depth_mode: DepthMode::Normal,
         }
     }
 }
@@ -459,6 +851,22 @@ fn generate_vertex_attributes(
     (array_stride, attributes)
 }
 
+/// A bind group to draw with, along with its dynamic offset if it has one.
+#[derive(Debug, Clone, Copy)]
+pub struct BindGroupBinding<'a> {
+    pub bind_group: &'a wgpu::BindGroup,
+    pub dynamic_offset: Option<u32>,
+}
+
+impl<'a> From<&'a wgpu::BindGroup> for BindGroupBinding<'a> {
+    fn from(bind_group: &'a wgpu::BindGroup) -> Self {
+        Self {
+            bind_group,
+            dynamic_offset: None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct BindedTexture {
     pub texture: Texture,
@@ -483,6 +891,9 @@ where
     pub vertices: &'a GpuVec<V>,
     pub instances: Option<&'a GpuVec<I>>,
     pub indices: Option<&'a GpuVec<u32>>,
+    /// Set before drawing, starting from byte 0 of the pipeline's push constant ranges (e.g. made with
+    /// [`bytemuck::bytes_of()`]). Empty if there aren't any.
+    pub push_constants: &'a [u8],
 }
 
 impl<'a, V, I> IntoIterator for PipelineBuffers<'a, V, I>
@@ -513,7 +924,8 @@ where
     dummy_vertex_buffer: wgpu::Buffer,
     dummy_instance_buffer: wgpu::Buffer,
 
-    bind_group_layouts: Vec<wgpu::BindGroupLayout>,
+    /// Shared with any variants, so bind groups made for one work with all of them.
+    bind_group_layouts: Arc<Vec<BindGroupLayout>>,
 
     _phantom: PhantomData<(V, I)>,
 }
@@ -525,14 +937,81 @@ where
 {
     pub fn new(controller: &GraphicsController, descriptor: PipelineDescriptor) -> Self {
         let handle = controller.handle_arc();
+        let bind_group_layouts = descriptor
+            .bind_groups
+            .iter()
+            .map(|&format| handle.create_bind_group_layout(format))
+            .collect::<Vec<BindGroupLayout>>();
+
+        Self::with_layouts(handle, descriptor, Arc::new(bind_group_layouts))
+    }
+
+    /// The same pipeline, but with different shader defines, as a wireframe, or for a depth prepass. It shares bind
+    /// group layouts with this one, so the same bind groups work for both.
+    pub fn variant(&self, variant: &PipelineVariant) -> Self {
+        Self::with_layouts(
+            self.handle.clone(),
+            PipelineDescriptor {
+                shader_defines: variant.shader_defines.clone(),
+                wireframe: variant.wireframe,
+                depth_mode: variant.depth_mode,
+                ..self.descriptor.clone()
+            },
+            self.bind_group_layouts.clone(),
+        )
+    }
 
+    fn with_layouts(
+        handle: Arc<GpuHandle>,
+        descriptor: PipelineDescriptor,
+        bind_group_layouts: Arc<Vec<BindGroupLayout>>,
+    ) -> Self {
+        assert!(
+            descriptor.use_depth || descriptor.depth_mode == DepthMode::Normal,
+            "'{}' can't have a depth mode without using depth",
+            descriptor.name
+        );
+        let depth_only = descriptor.depth_mode == DepthMode::Prepass;
+
+        let push_constant_size = descriptor
+            .push_constant_ranges
+            .iter()
+            .map(|range| range.range.end)
+            .max()
+            .unwrap_or(0);
+        if push_constant_size > handle.max_push_constant_size() {
+            panic!(
+                "'{}' needs {push_constant_size} bytes of push constants, but only {} are available",
+                descriptor.name,
+                handle.max_push_constant_size()
+            );
+        }
+
+        let shader_source = preprocess(descriptor.shader_source, &descriptor.shader_defines)
+            .unwrap_or_else(|error| panic!("'{}' shader: {error}", descriptor.name));
         let shader_module = handle
             .device
             .create_shader_module(wgpu::ShaderModuleDescriptor {
                 label: Some(descriptor.name),
-                source: wgpu::ShaderSource::Wgsl(descriptor.shader_source.into()),
+                source: wgpu::ShaderSource::Wgsl(shader_source.into()),
             });
 
+        let polygon_mode = if !descriptor.wireframe {
+            wgpu::PolygonMode::Fill
+        } else if handle
+            .device
+            .features()
+            .contains(wgpu::Features::POLYGON_MODE_LINE)
+        {
+            wgpu::PolygonMode::Line
+        } else {
+            warn!(
+                "wireframes aren't supported here, so '{}' is filled in",
+                descriptor.name
+            );
+            wgpu::PolygonMode::Fill
+        };
+
         let (vertex_stride, vertex_attributes) =
             generate_vertex_attributes(descriptor.vertex_format, 0);
         let (instance_stride, instance_attributes) =
@@ -542,12 +1021,6 @@ where
                 (0u64, vec![])
             };
 
-        let bind_group_layouts = descriptor
-            .bind_groups
-            .iter()
-            .map(|&format| handle.create_bind_group_layout(format))
-            .collect::<Vec<wgpu::BindGroupLayout>>();
-
         let gpu_pipeline = handle
             .device
             .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
@@ -559,8 +1032,9 @@ where
                             label: Some(descriptor.name),
                             bind_group_layouts: &bind_group_layouts
                                 .iter()
+                                .map(|layout| &layout.layout)
                                 .collect::<Vec<&wgpu::BindGroupLayout>>(),
-                            push_constant_ranges: &[],
+                            push_constant_ranges: descriptor.push_constant_ranges,
                         }),
                 ),
                 vertex: wgpu::VertexState {
@@ -586,22 +1060,26 @@ where
                     front_face: wgpu::FrontFace::Ccw,
                     cull_mode: Some(wgpu::Face::Back),
                     unclipped_depth: false,
-                    polygon_mode: wgpu::PolygonMode::Fill,
+                    polygon_mode,
                     conservative: false,
                 },
                 depth_stencil: descriptor.use_depth.then_some(wgpu::DepthStencilState {
                     format: wgpu::TextureFormat::Depth32Float,
-                    depth_write_enabled: descriptor.use_depth,
-                    depth_compare: wgpu::CompareFunction::LessEqual,
+                    // it's already there from the prepass
+                    depth_write_enabled: descriptor.depth_mode != DepthMode::Equal,
+                    depth_compare: match descriptor.depth_mode {
+                        DepthMode::Normal | DepthMode::Prepass => wgpu::CompareFunction::LessEqual,
+                        DepthMode::Equal => wgpu::CompareFunction::Equal,
+                    },
                     stencil: Default::default(),
                     bias: Default::default(),
                 }),
                 multisample: wgpu::MultisampleState {
                     count: 1,
                     mask: !0,
-                    alpha_to_coverage_enabled: descriptor.alpha_to_coverage_enabled,
+                    alpha_to_coverage_enabled: descriptor.alpha_to_coverage_enabled && !depth_only,
                 },
-                fragment: Some(wgpu::FragmentState {
+                fragment: (!depth_only).then_some(wgpu::FragmentState {
                     module: &shader_module,
                     entry_point: descriptor.fragment_shader_entry_point,
                     compilation_options: Default::default(),
@@ -609,7 +1087,11 @@ where
                         format: descriptor
                             .target_format
                             .unwrap_or(wgpu::TextureFormat::Rgba8UnormSrgb),
-                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        blend: Some(if descriptor.premultiplied_alpha {
+                            wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING
+                        } else {
+                            wgpu::BlendState::ALPHA_BLENDING
+                        }),
                         write_mask: wgpu::ColorWrites::ALL,
                     })],
                 }),
@@ -657,6 +1139,27 @@ where
             .create_bind_group(&self.bind_group_layouts[group_layout_index], resources)
     }
 
+    /// A bind group with just `texture` and its sampler, for one of this pipeline's texture bind groups.
+    pub fn texture_bind_group(
+        &self,
+        group_layout_index: usize,
+        texture: &Texture,
+    ) -> wgpu::BindGroup {
+        self.create_bind_group(
+            group_layout_index,
+            vec![
+                wgpu::BindingResource::TextureView(&texture.view),
+                wgpu::BindingResource::Sampler(&texture.sampler),
+            ],
+        )
+    }
+
+    /// Like [`GpuHandle::bind_group()`], for one of this pipeline's bind groups.
+    pub fn bind_group(&self, group_layout_index: usize) -> BindGroupBuilder<'_> {
+        self.handle
+            .bind_group(&self.bind_group_layouts[group_layout_index])
+    }
+
     pub fn binded_texture(&self, group_layout_index: usize, texture: Texture) -> BindedTexture {
         self.handle
             .binded_texture(&self.bind_group_layouts[group_layout_index], texture)
@@ -671,6 +1174,53 @@ where
     }
 }
 
+/// A pipeline along with variants of it, each made the first time it's asked for. See [`Pipeline::variant()`].
+#[derive(Debug)]
+pub struct PipelineVariants<V, I = u8>
+where
+    V: bytemuck::NoUninit,
+    I: bytemuck::NoUninit,
+{
+    base: Pipeline<V, I>,
+    /// Keyed by [`PipelineVariant::normalized()`].
+    variants: BTreeMap<PipelineVariant, Pipeline<V, I>>,
+}
+
+impl<V, I> PipelineVariants<V, I>
+where
+    V: bytemuck::NoUninit,
+    I: bytemuck::NoUninit,
+{
+    pub fn new(base: Pipeline<V, I>) -> Self {
+        Self {
+            base,
+            variants: BTreeMap::new(),
+        }
+    }
+
+    /// Makes the variant ahead of time, if it hasn't been already. Building pipelines is slow, so this should only
+    /// happen when something gets switched.
+    pub fn prepare(&mut self, variant: &PipelineVariant) {
+        let key = variant.normalized();
+        if !self.variants.contains_key(&key) {
+            let pipeline = self.base.variant(&key);
+            self.variants.insert(key, pipeline);
+        }
+    }
+
+    /// The variant from [`PipelineVariants::prepare()`], or the base pipeline if it hasn't been made yet.
+    pub fn get(&self, variant: &PipelineVariant) -> &Pipeline<V, I> {
+        self.variants
+            .get(&variant.normalized())
+            .unwrap_or(&self.base)
+    }
+
+    /// The pipeline everything else is a variant of, which bind groups can be made with.
+    pub fn base(&self) -> &Pipeline<V, I> {
+        &self.base
+    }
+}
+
 #[derive(Debug)]
 pub struct RenderTarget {
     texture: Texture,
@@ -741,15 +1291,35 @@ impl RenderTarget {
     }
 }
 
+/// A window other than the main one, drawn to with the same device. See
+/// [`GraphicsController::create_auxiliary_surface()`].
+#[derive(Debug)]
+pub struct AuxiliarySurface {
+    pub window: Arc<Window>,
+    surface: wgpu::Surface<'static>,
+    config: wgpu::SurfaceConfiguration,
+}
+
+impl AuxiliarySurface {
+    pub fn size(&self) -> PhysicalSize<u32> {
+        PhysicalSize::new(self.config.width, self.config.height)
+    }
+}
+
 #[derive(Debug)]
 pub struct GraphicsController {
     handle: Arc<GpuHandle>,
+    instance: wgpu::Instance,
+    adapter: wgpu::Adapter,
+    adapter_info: wgpu::AdapterInfo,
 
-    window_surface: wgpu::Surface<'static>,
+    /// `None` while the app is suspended, see [`GraphicsController::drop_window_surface()`].
+    window_surface: Option<wgpu::Surface<'static>>,
     window_surface_config: wgpu::SurfaceConfiguration,
     window_size: PhysicalSize<u32>,
 
     present_pipeline: Option<Pipeline<Vertex2D>>,
+    composite_pipeline: Option<Pipeline<Vertex2D>>,
     present_vertices: GpuVec<Vertex2D>,
     present_indices: GpuVec<u32>,
 
@@ -757,33 +1327,83 @@ pub struct GraphicsController {
 }
 
 impl GraphicsController {
-    pub fn new(window: Arc<Window>) -> Result<Self> {
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+    /// `adapter_name` picks a specific adapter, see [`GraphicsController::find_adapter()`].
+    pub fn new(window: Arc<Window>, adapter_name: Option<&str>) -> Result<Self> {
+        let instance = Self::create_instance();
+        let window_surface = instance.create_surface(Arc::clone(&window))?;
+        Self::with_surface(
+            instance,
+            Some(window_surface),
+            window.inner_size(),
+            adapter_name,
+        )
+    }
+
+    /// A controller with no window, for drawing into render targets and reading them back (see
+    /// [`GpuHandle::read_texture_to_image()`]). Presenting does nothing, and [`GraphicsController::window_size()`]
+    /// is just `size`.
+    pub fn new_offscreen(size: PhysicalSize<u32>, adapter_name: Option<&str>) -> Result<Self> {
+        Self::with_surface(Self::create_instance(), None, size, adapter_name)
+    }
+
+    fn create_instance() -> wgpu::Instance {
+        wgpu::Instance::new(wgpu::InstanceDescriptor {
             backends: wgpu::Backends::all(),
             ..Default::default()
-        });
+        })
+    }
 
-        let window_surface = instance.create_surface(Arc::clone(&window))?;
-        let adapter = futures::executor::block_on(instance.request_adapter(
-            &wgpu::RequestAdapterOptionsBase {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                force_fallback_adapter: false,
-                compatible_surface: Some(&window_surface),
-            },
-        ))
-        .ok_or(anyhow!("No adapter"))?;
+    fn with_surface(
+        instance: wgpu::Instance,
+        window_surface: Option<wgpu::Surface<'static>>,
+        window_size: PhysicalSize<u32>,
+        adapter_name: Option<&str>,
+    ) -> Result<Self> {
+        let adapter = match adapter_name
+            .and_then(|name| Self::find_adapter(&instance, window_surface.as_ref(), name))
+        {
+            Some(adapter) => adapter,
+            None => futures::executor::block_on(instance.request_adapter(
+                &wgpu::RequestAdapterOptionsBase {
+                    power_preference: wgpu::PowerPreference::HighPerformance,
+                    force_fallback_adapter: false,
+                    compatible_surface: window_surface.as_ref(),
+                },
+            ))
+            .ok_or(anyhow!("No adapter"))?,
+        };
+        let adapter_info = adapter.get_info();
+        info!(
+            "Using adapter {} ({:?})",
+            adapter_info.name, adapter_info.backend
+        );
 
         let (device, queue) = futures::executor::block_on(adapter.request_device(
             &wgpu::DeviceDescriptor {
                 label: None,
-                required_features: wgpu::Features::CLEAR_TEXTURE,
+                // compressed textures and wireframes get used wherever they're available, and skipped otherwise.
+                // push constants aren't asked for until some pipeline actually has a range
+                required_features: wgpu::Features::CLEAR_TEXTURE
+                    | (adapter.features()
+                        & (wgpu::Features::TEXTURE_COMPRESSION_BC
+                            | wgpu::Features::TEXTURE_COMPRESSION_ETC2
+                            | wgpu::Features::TEXTURE_COMPRESSION_ASTC
+                            | wgpu::Features::POLYGON_MODE_LINE)),
                 required_limits: wgpu::Limits::default(),
             },
             None,
         ))?;
 
-        let window_size = window.inner_size();
-        let window_surface_capabilities = window_surface.get_capabilities(&adapter);
+        let window_surface_capabilities = match &window_surface {
+            Some(surface) => surface.get_capabilities(&adapter),
+            // offscreen, so this only matters for the present pipeline, which never gets used
+            None => wgpu::SurfaceCapabilities {
+                formats: vec![wgpu::TextureFormat::Rgba8UnormSrgb],
+                present_modes: vec![wgpu::PresentMode::Fifo],
+                alpha_modes: vec![wgpu::CompositeAlphaMode::Opaque],
+                usages: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            },
+        };
         let window_surface_format = window_surface_capabilities
             .formats
             .iter()
@@ -805,9 +1425,15 @@ impl GraphicsController {
             alpha_mode: window_surface_capabilities.alpha_modes[0],
             view_formats: vec![],
         };
-        window_surface.configure(&device, &window_surface_config);
+        if let Some(surface) = &window_surface {
+            surface.configure(&device, &window_surface_config);
+        }
 
-        let handle = Arc::new(GpuHandle { device, queue });
+        let handle = Arc::new(GpuHandle {
+            device,
+            queue,
+            render_counters: RenderCounters::default(),
+        });
 
         let present_vertices = GpuVec::new(
             Arc::clone(&handle),
@@ -822,12 +1448,16 @@ impl GraphicsController {
 
         let mut controller = Self {
             handle,
+            instance,
+            adapter,
+            adapter_info,
 
             window_surface,
             window_surface_config,
             window_size,
 
             present_pipeline: None,
+            composite_pipeline: None,
             present_vertices,
             present_indices,
 
@@ -839,20 +1469,73 @@ impl GraphicsController {
             PipelineDescriptor {
                 name: "Present to Screen",
                 shader_source: include_str!("shaders/present.wgsl"),
+                shader_defines: Vec::new(),
                 vertex_shader_entry_point: "vert_main",
                 vertex_format: Vertex2D::VERTEX_FORMAT,
                 instance_format: None,
                 fragment_shader_entry_point: "frag_main",
                 target_format: Some(window_surface_format),
                 bind_groups: &[Texture::STANDARD_BIND_GROUP_LAYOUT],
+                push_constant_ranges: &[],
+                use_depth: false,
+                alpha_to_coverage_enabled: false,
+                premultiplied_alpha: false,
+                wireframe: false,
+                depth_mode: DepthMode::Normal,
+            },
+        ));
+        controller.composite_pipeline = Some(Pipeline::new(
+            &controller,
+            PipelineDescriptor {
+                name: "Composite",
+                shader_source: include_str!("shaders/present.wgsl"),
+                shader_defines: Vec::new(),
+                vertex_shader_entry_point: "vert_main",
+                vertex_format: Vertex2D::VERTEX_FORMAT,
+                instance_format: None,
+                fragment_shader_entry_point: "frag_main",
+                target_format: None,
+                bind_groups: &[Texture::STANDARD_BIND_GROUP_LAYOUT],
+                push_constant_ranges: &[],
                 use_depth: false,
                 alpha_to_coverage_enabled: false,
+                premultiplied_alpha: true,
+                wireframe: false,
+                depth_mode: DepthMode::Normal,
             },
         ));
 
         Ok(controller)
     }
 
+    pub fn adapter_info(&self) -> &wgpu::AdapterInfo {
+        &self.adapter_info
+    }
+
+    /// What's been drawn and written since this was last called, e.g. over the last frame.
+    pub fn take_render_stats(&self) -> RenderStats {
+        self.handle.render_counters.take()
+    }
+
+    /// The first adapter whose name contains `name` (ignoring case) that can draw to the surface, if there is one.
+    fn find_adapter(
+        instance: &wgpu::Instance,
+        surface: Option<&wgpu::Surface>,
+        name: &str,
+    ) -> Option<wgpu::Adapter> {
+        let name = name.to_lowercase();
+        let adapters = instance.enumerate_adapters(wgpu::Backends::all());
+        let adapter = adapters.into_iter().find(|adapter| {
+            adapter.get_info().name.to_lowercase().contains(&name)
+                && surface.is_none_or(|surface| adapter.is_surface_supported(surface))
+        });
+
+        if adapter.is_none() {
+            warn!("No adapter matching '{}', using the default one", name);
+        }
+        adapter
+    }
+
     pub fn handle(&self) -> &GpuHandle {
         &self.handle
     }
@@ -869,8 +1552,46 @@ impl GraphicsController {
         self.window_size = new_size;
         self.window_surface_config.width = new_size.width;
         self.window_surface_config.height = new_size.height;
-        self.window_surface
-            .configure(&self.handle.device, &self.window_surface_config);
+        if let Some(surface) = &self.window_surface {
+            surface.configure(&self.handle.device, &self.window_surface_config);
+        }
+    }
+
+    /// Lets go of the main window's surface, e.g. when the app gets suspended and the window might not stick around.
+    /// Presenting does nothing until [`GraphicsController::recreate_window_surface()`].
+    pub fn drop_window_surface(&mut self) {
+        self.window_surface = None;
+    }
+
+    /// Makes a new surface for the main window after [`GraphicsController::drop_window_surface()`]. The window may
+    /// be a different one than before, as long as the adapter can still draw to it.
+    pub fn recreate_window_surface(&mut self, window: Arc<Window>) -> Result<()> {
+        let size = window.inner_size();
+        let surface = self.instance.create_surface(window)?;
+        if !self.adapter.is_surface_supported(&surface) {
+            return Err(anyhow!("the adapter can't draw to the window anymore"));
+        }
+
+        if size.width * size.height > 0 {
+            self.window_size = size;
+            self.window_surface_config.width = size.width;
+            self.window_surface_config.height = size.height;
+        }
+        surface.configure(&self.handle.device, &self.window_surface_config);
+        self.window_surface = Some(surface);
+        Ok(())
+    }
+
+    pub fn window_size(&self) -> PhysicalSize<u32> {
+        self.window_size
+    }
+
+    /// Whether presenting waits for the screen to refresh.
+    pub fn vsync(&self) -> bool {
+        matches!(
+            self.window_surface_config.present_mode,
+            wgpu::PresentMode::AutoVsync | wgpu::PresentMode::Fifo | wgpu::PresentMode::FifoRelaxed
+        )
     }
 
     pub fn window_surface_format(&self) -> wgpu::TextureFormat {
@@ -878,7 +1599,83 @@ impl GraphicsController {
     }
 
     pub fn present_to_screen(&self, texture: &Texture) -> Result<()> {
-        let output = self.window_surface.get_current_texture()?;
+        let Some(surface) = &self.window_surface else {
+            return Ok(());
+        };
+        self.present_to_surface(surface, &self.window_surface_config, texture)
+    }
+
+    /// Makes a surface for another window, e.g. one on a second monitor. It has to be able to use the same format
+    /// as the main window, since presenting goes through the same pipeline.
+    pub fn create_auxiliary_surface(&self, window: Arc<Window>) -> Result<AuxiliarySurface> {
+        let surface = self.instance.create_surface(Arc::clone(&window))?;
+        let capabilities = surface.get_capabilities(&self.adapter);
+        if !capabilities
+            .formats
+            .contains(&self.window_surface_config.format)
+        {
+            return Err(anyhow!(
+                "the window can't use the main window's format ({:?})",
+                self.window_surface_config.format
+            ));
+        }
+
+        let size = window.inner_size();
+        let config = wgpu::SurfaceConfiguration {
+            width: size.width.max(1),
+            height: size.height.max(1),
+            alpha_mode: capabilities.alpha_modes[0],
+            ..self.window_surface_config.clone()
+        };
+        surface.configure(&self.handle.device, &config);
+
+        Ok(AuxiliarySurface {
+            window,
+            surface,
+            config,
+        })
+    }
+
+    pub fn resize_auxiliary_surface(
+        &self,
+        auxiliary: &mut AuxiliarySurface,
+        new_size: PhysicalSize<u32>,
+    ) {
+        if new_size.width * new_size.height == 0 {
+            return;
+        }
+
+        auxiliary.config.width = new_size.width;
+        auxiliary.config.height = new_size.height;
+        auxiliary
+            .surface
+            .configure(&self.handle.device, &auxiliary.config);
+    }
+
+    pub fn present_to_auxiliary(
+        &self,
+        auxiliary: &AuxiliarySurface,
+        texture: &Texture,
+    ) -> Result<()> {
+        self.present_to_surface(&auxiliary.surface, &auxiliary.config, texture)
+    }
+
+    fn present_to_surface(
+        &self,
+        surface: &wgpu::Surface,
+        config: &wgpu::SurfaceConfiguration,
+        texture: &Texture,
+    ) -> Result<()> {
+        let output = match surface.get_current_texture() {
+            Ok(output) => output,
+            // e.g. after coming back from being minimized or moving to another monitor, this frame just gets skipped
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                surface.configure(&self.handle.device, config);
+                return Ok(());
+            }
+            Err(wgpu::SurfaceError::Timeout) => return Ok(()),
+            Err(error) => return Err(error.into()),
+        };
         let output_view = output.texture.create_view(&Default::default());
 
         self.internal_render(
@@ -891,6 +1688,7 @@ impl GraphicsController {
                 vertices: &self.present_vertices,
                 instances: None,
                 indices: Some(&self.present_indices),
+                push_constants: &[],
             }],
             [&self.present_pipeline.as_ref().unwrap().create_bind_group(
                 0,
@@ -906,18 +1704,95 @@ impl GraphicsController {
         Ok(())
     }
 
+    /// Draws `texture` over all of `target` through `pipeline`, e.g. for a post-processing pass. The pipeline takes
+    /// the texture the same way the present pipeline does.
+    pub fn render_fullscreen(
+        &self,
+        target: &RenderTarget,
+        pipeline: &Pipeline<Vertex2D>,
+        texture: &Texture,
+    ) {
+        self.render_fullscreen_textures(target, pipeline, &[texture]);
+    }
+
+    /// Like [`GraphicsController::render_fullscreen()`], but with more than one texture, each in its own bind group
+    /// in order.
+    pub fn render_fullscreen_textures(
+        &self,
+        target: &RenderTarget,
+        pipeline: &Pipeline<Vertex2D>,
+        textures: &[&Texture],
+    ) {
+        let bind_groups = textures
+            .iter()
+            .enumerate()
+            .map(|(index, texture)| pipeline.texture_bind_group(index, texture))
+            .collect::<Vec<_>>();
+        self.render_fullscreen_bind_groups(target, pipeline, &bind_groups);
+    }
+
+    /// Like [`GraphicsController::render_fullscreen()`], but with whatever bind groups `pipeline` takes, e.g. for a
+    /// pass that has a uniform along with its textures.
+    pub fn render_fullscreen_bind_groups<'a>(
+        &self,
+        target: &RenderTarget,
+        pipeline: &Pipeline<Vertex2D>,
+        bind_groups: impl IntoIterator<Item = impl Into<BindGroupBinding<'a>>>,
+    ) {
+        self.render(
+            target,
+            pipeline,
+            [PipelineBuffers {
+                vertices: &self.present_vertices,
+                instances: None,
+                indices: Some(&self.present_indices),
+                push_constants: &[],
+            }],
+            bind_groups,
+        );
+    }
+
+    /// Draws `texture` over all of `target`, stretched to fit, e.g. to put a full resolution GUI over a 3D view drawn at
+    /// a lower one. `texture` is taken to hold premultiplied color, like a target that got cleared to transparent and
+    /// then drawn on, so its see-through edges don't get darkened a second time. Since the quad's texture coordinates
+    /// don't depend on either size, nothing gets cropped when the two differ.
+    pub fn composite(&self, target: &RenderTarget, texture: &Texture) {
+        self.render_fullscreen(target, self.composite_pipeline.as_ref().unwrap(), texture);
+    }
+
     pub fn render_target(
         &mut self,
         name: &'static str,
         width: u32,
         height: u32,
+    ) -> (bool, Rc<RenderTarget>) {
+        self.render_target_with_format(name, width, height, wgpu::TextureFormat::Rgba8UnormSrgb)
+    }
+
+    /// Like [`GraphicsController::render_target()`], but with some other format than 8-bit sRGB, e.g. a float one
+    /// for data that isn't a color. Pipelines drawing into it need a matching
+    /// [`PipelineDescriptor::target_format`].
+    pub fn render_target_with_format(
+        &mut self,
+        name: &'static str,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
     ) -> (bool, Rc<RenderTarget>) {
         let recreate = match self.render_targets.get(name) {
-            Some(target) => target.width() != width || target.height() != height,
+            Some(target) => {
+                target.width() != width
+                    || target.height() != height
+                    || target.texture().inner_texture.format() != format
+            }
             None => true,
         };
 
         if recreate {
+            self.handle
+                .render_counters
+                .render_targets_recreated
+                .fetch_add(1, Ordering::Relaxed);
             self.render_targets.insert(
                 name,
                 Rc::new(RenderTarget::new(
@@ -934,14 +1809,19 @@ impl GraphicsController {
                             mip_level_count: 1,
                             sample_count: 1,
                             dimension: wgpu::TextureDimension::D2,
-                            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                            format,
                             usage: wgpu::TextureUsages::COPY_DST
                                 | wgpu::TextureUsages::COPY_SRC
                                 | wgpu::TextureUsages::TEXTURE_BINDING
                                 | wgpu::TextureUsages::RENDER_ATTACHMENT,
                             view_formats: &[],
                         },
-                        &wgpu::SamplerDescriptor::default(),
+                        // targets get drawn at other sizes when they're composited
+                        &wgpu::SamplerDescriptor {
+                            mag_filter: wgpu::FilterMode::Linear,
+                            min_filter: wgpu::FilterMode::Linear,
+                            ..Default::default()
+                        },
                     ),
                 )),
             );
@@ -982,17 +1862,21 @@ impl GraphicsController {
         self.vec(contents, wgpu::BufferUsages::UNIFORM)
     }
 
-    pub fn render<V, I>(
+    pub fn render<'a, V, I>(
         &self,
         target: &RenderTarget,
         pipeline: &Pipeline<V, I>,
         buffers: impl IntoIterator<Item = PipelineBuffers<V, I>>,
-        bind_groups: impl IntoIterator<Item = &wgpu::BindGroup>,
+        bind_groups: impl IntoIterator<Item = impl Into<BindGroupBinding<'a>>>,
     ) where
         V: bytemuck::NoUninit,
         I: bytemuck::NoUninit,
     {
         let depth_view = target.depth_texture().map(|texture| &texture.view);
+        let depth_only = pipeline.descriptor.depth_mode == DepthMode::Prepass;
+        if depth_only && depth_view.is_none() {
+            return;
+        }
         self.internal_render(
             &target.texture().view,
             depth_view,
@@ -1002,14 +1886,16 @@ impl GraphicsController {
             buffers,
             bind_groups,
         );
-        target.color_cleared.set(true);
+        if !depth_only {
+            target.color_cleared.set(true);
+        }
         if pipeline.descriptor.use_depth && depth_view.is_some() {
             target.depth_cleared.set(true);
         }
     }
 
     #[allow(clippy::too_many_arguments)]
-    fn internal_render<V, I>(
+    fn internal_render<'a, V, I>(
         &self,
         target_view: &wgpu::TextureView,
         depth_view: Option<&wgpu::TextureView>,
@@ -1017,7 +1903,7 @@ impl GraphicsController {
         clear_depth: bool,
         pipeline: &Pipeline<V, I>,
         buffers: impl IntoIterator<Item = PipelineBuffers<V, I>>,
-        bind_groups: impl IntoIterator<Item = &wgpu::BindGroup>,
+        bind_groups: impl IntoIterator<Item = impl Into<BindGroupBinding<'a>>>,
     ) where
         V: bytemuck::NoUninit,
         I: bytemuck::NoUninit,
@@ -1026,27 +1912,34 @@ impl GraphicsController {
             .handle
             .device
             .create_command_encoder(&Default::default());
+        let counters = &self.handle.render_counters;
+        counters.render_passes.fetch_add(1, Ordering::Relaxed);
 
         {
+            let color_attachment = Some(wgpu::RenderPassColorAttachment {
+                view: target_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: if clear_color {
+                        wgpu::LoadOp::Clear(wgpu::Color {
+                            r: 0.0,
+                            g: 0.0,
+                            b: 0.0,
+                            a: 0.0,
+                        })
+                    } else {
+                        wgpu::LoadOp::Load
+                    },
+                    store: wgpu::StoreOp::Store,
+                },
+            });
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some(pipeline.descriptor.name),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: target_view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: if clear_color {
-                            wgpu::LoadOp::Clear(wgpu::Color {
-                                r: 0.0,
-                                g: 0.0,
-                                b: 0.0,
-                                a: 0.0,
-                            })
-                        } else {
-                            wgpu::LoadOp::Load
-                        },
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
+                color_attachments: if pipeline.descriptor.depth_mode == DepthMode::Prepass {
+                    &[]
+                } else {
+                    std::slice::from_ref(&color_attachment)
+                },
                 depth_stencil_attachment: if let Some(depth_view) = depth_view {
                     pipeline.descriptor.use_depth.then_some(
                         wgpu::RenderPassDepthStencilAttachment {
@@ -1069,8 +1962,13 @@ impl GraphicsController {
                 occlusion_query_set: None,
             });
 
-            for (i, bind_group) in bind_groups.into_iter().enumerate() {
-                render_pass.set_bind_group(i as u32, bind_group, &[]);
+            for (i, binding) in bind_groups.into_iter().enumerate() {
+                let binding = binding.into();
+                render_pass.set_bind_group(
+                    i as u32,
+                    binding.bind_group,
+                    binding.dynamic_offset.as_slice(),
+                );
             }
 
             render_pass.set_pipeline(&pipeline.gpu_pipeline);
@@ -1079,8 +1977,19 @@ impl GraphicsController {
                 vertices,
                 instances,
                 indices,
+                push_constants,
             } in buffers
             {
+                for range in pipeline.descriptor.push_constant_ranges {
+                    let end = (range.range.end as usize).min(push_constants.len());
+                    if let Some(data) = push_constants
+                        .get(range.range.start as usize..end)
+                        .filter(|data| !data.is_empty())
+                    {
+                        render_pass.set_push_constants(range.stages, range.range.start, data);
+                    }
+                }
+
                 if let Some(vertex_buffer_slice) = vertices.borrow_buffer() {
                     render_pass.set_vertex_buffer(0, vertex_buffer_slice);
 
@@ -1109,15 +2018,17 @@ impl GraphicsController {
                         1
                     };
 
-                    if let Some(index_count) = index_count {
+                    let vertex_count = index_count.unwrap_or(vertices.len());
+                    if index_count.is_some() {
                         render_pass.draw_indexed(
-                            0..index_count as u32,
+                            0..vertex_count as u32,
                             0,
                             0..instance_count as u32,
                         );
                     } else {
-                        render_pass.draw(0..vertices.len() as u32, 0..instance_count as u32);
+                        render_pass.draw(0..vertex_count as u32, 0..instance_count as u32);
                     }
+                    counters.count_draw(instance_count, vertex_count * instance_count);
                 }
             }
         }
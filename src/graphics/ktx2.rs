@@ -0,0 +1,154 @@
+use anyhow::{Context, Result};
+
+const IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+const HEADER_LENGTH: usize = 80;
+const LEVEL_INDEX_ENTRY_LENGTH: usize = 24;
+
+/// The base level of a block-compressed KTX2 texture, ready to be copied straight onto the GPU.
+#[derive(Debug, Clone)]
+pub struct Ktx2Image {
+    pub format: wgpu::TextureFormat,
+    pub width: u32,
+    pub height: u32,
+    /// Rows of blocks, tightly packed.
+    pub data: Vec<u8>,
+}
+
+impl Ktx2Image {
+    /// How many blocks across and down it is.
+    pub fn block_count(&self) -> (u32, u32) {
+        let (block_width, block_height) = self.format.block_dimensions();
+        (
+            self.width.div_ceil(block_width),
+            self.height.div_ceil(block_height),
+        )
+    }
+
+    pub fn bytes_per_row(&self) -> u32 {
+        self.block_count().0 * self.format.block_copy_size(None).unwrap()
+    }
+}
+
+/// The matching format for a `VkFormat`, for the BC, ETC2 and (LDR) ASTC ones.
+fn texture_format(vk_format: u32) -> Option<wgpu::TextureFormat> {
+    use wgpu::{AstcBlock, AstcChannel, TextureFormat};
+
+    const ASTC_BLOCKS: [AstcBlock; 14] = [
+        AstcBlock::B4x4,
+        AstcBlock::B5x4,
+        AstcBlock::B5x5,
+        AstcBlock::B6x5,
+        AstcBlock::B6x6,
+        AstcBlock::B8x5,
+        AstcBlock::B8x6,
+        AstcBlock::B8x8,
+        AstcBlock::B10x5,
+        AstcBlock::B10x6,
+        AstcBlock::B10x8,
+        AstcBlock::B10x10,
+        AstcBlock::B12x10,
+        AstcBlock::B12x12,
+    ];
+
+    Some(match vk_format {
+        // BC1 without alpha decodes the same, just with it always opaque
+        131 | 133 => TextureFormat::Bc1RgbaUnorm,
+        132 | 134 => TextureFormat::Bc1RgbaUnormSrgb,
+        135 => TextureFormat::Bc2RgbaUnorm,
+        136 => TextureFormat::Bc2RgbaUnormSrgb,
+        137 => TextureFormat::Bc3RgbaUnorm,
+        138 => TextureFormat::Bc3RgbaUnormSrgb,
+        139 => TextureFormat::Bc4RUnorm,
+        140 => TextureFormat::Bc4RSnorm,
+        141 => TextureFormat::Bc5RgUnorm,
+        142 => TextureFormat::Bc5RgSnorm,
+        143 => TextureFormat::Bc6hRgbUfloat,
+        144 => TextureFormat::Bc6hRgbFloat,
+        145 => TextureFormat::Bc7RgbaUnorm,
+        146 => TextureFormat::Bc7RgbaUnormSrgb,
+        147 => TextureFormat::Etc2Rgb8Unorm,
+        148 => TextureFormat::Etc2Rgb8UnormSrgb,
+        149 => TextureFormat::Etc2Rgb8A1Unorm,
+        150 => TextureFormat::Etc2Rgb8A1UnormSrgb,
+        151 => TextureFormat::Etc2Rgba8Unorm,
+        152 => TextureFormat::Etc2Rgba8UnormSrgb,
+        153 => TextureFormat::EacR11Unorm,
+        154 => TextureFormat::EacR11Snorm,
+        155 => TextureFormat::EacRg11Unorm,
+        156 => TextureFormat::EacRg11Snorm,
+        // every block size comes as a unorm and srgb pair, in the same order as AstcBlock
+        157..=184 => TextureFormat::Astc {
+            block: ASTC_BLOCKS[(vk_format - 157) as usize / 2],
+            channel: if vk_format % 2 == 1 {
+                AstcChannel::Unorm
+            } else {
+                AstcChannel::UnormSrgb
+            },
+        },
+        _ => return None,
+    })
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+/// Reads the base level of a KTX2 file holding a single 2D BC, ETC2 or ASTC texture. Supercompressed files (including Basis
+/// Universal ones) would need transcoding first, so they aren't supported.
+pub fn load_ktx2(bytes: &[u8]) -> Result<Ktx2Image> {
+    if bytes.len() < HEADER_LENGTH + LEVEL_INDEX_ENTRY_LENGTH || bytes[..12] != IDENTIFIER {
+        anyhow::bail!("not a KTX2 file");
+    }
+
+    let vk_format = read_u32(bytes, 12);
+    let width = read_u32(bytes, 20);
+    let height = read_u32(bytes, 24);
+    let depth = read_u32(bytes, 28);
+    let layer_count = read_u32(bytes, 32);
+    let face_count = read_u32(bytes, 36);
+    let supercompression_scheme = read_u32(bytes, 44);
+
+    if supercompression_scheme != 0 {
+        anyhow::bail!(
+            "supercompressed KTX2 files (scheme {supercompression_scheme}) aren't supported"
+        );
+    }
+    if depth > 1 || layer_count > 1 || face_count != 1 {
+        anyhow::bail!(
+            "only single 2D textures are supported, not arrays, cubemaps, or 3D textures"
+        );
+    }
+    let format = texture_format(vk_format)
+        .with_context(|| format!("VkFormat {vk_format} isn't a supported compressed format"))?;
+
+    let image = Ktx2Image {
+        format,
+        width,
+        height,
+        data: Vec::new(),
+    };
+    let (_, block_rows) = image.block_count();
+    let expected_length = image.bytes_per_row() as usize * block_rows as usize;
+
+    // the base level always comes first in the level index
+    let offset = read_u64(bytes, HEADER_LENGTH) as usize;
+    let length = read_u64(bytes, HEADER_LENGTH + 8) as usize;
+    if length != expected_length {
+        anyhow::bail!("base level is {length} bytes, but a {width}x{height} {format:?} texture needs {expected_length}");
+    }
+    let data = offset
+        .checked_add(length)
+        .and_then(|end| bytes.get(offset..end))
+        .context("base level runs past the end of the file")?;
+
+    Ok(Ktx2Image {
+        data: data.to_vec(),
+        ..image
+    })
+}
@@ -0,0 +1,162 @@
+use super::vertex::Vertex3D;
+use crate::shared::{bounding_box::BBox3, indexed_container::IndexedContainer};
+use cgmath::{vec3, InnerSpace, Vector2, Vector3, Zero};
+use std::collections::HashMap;
+
+/// Used for vertices that aren't part of any (non-degenerate) triangle.
+pub const FALLBACK_NORMAL: [f32; 3] = [1.0, 0.0, 0.0];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NormalMode {
+    /// Every triangle gets its own face normal. Vertices shared between triangles get split up.
+    Flat,
+    /// Vertices in the same spot share a normal, averaged from the triangles around them and weighted by the
+    /// angle each triangle makes at that corner (so how finely a face happens to be triangulated doesn't matter).
+    #[default]
+    Smooth,
+}
+
+fn position_key(position: [f32; 3]) -> [u32; 3] {
+    // +0.0 and -0.0 are the same spot
+    position.map(|value| (value + 0.0).to_bits())
+}
+
+fn normalize_or(vector: Vector3<f32>, fallback: Vector3<f32>) -> Vector3<f32> {
+    if vector.magnitude2() > 0.0 {
+        vector.normalize()
+    } else {
+        fallback
+    }
+}
+
+impl IndexedContainer<Vertex3D> {
+    fn triangles(&self) -> impl Iterator<Item = [usize; 3]> + '_ {
+        self.indices
+            .chunks_exact(3)
+            .map(|triangle| [0, 1, 2].map(|corner| triangle[corner] as usize))
+    }
+
+    fn positions(&self, triangle: [usize; 3]) -> [Vector3<f32>; 3] {
+        triangle.map(|index| Vector3::from(self.items[index].pos))
+    }
+
+    /// The smallest box containing every vertex.
+    pub fn bounds(&self) -> BBox3 {
+        BBox3::new(self.items.iter().map(|vertex| vertex.pos))
+    }
+
+    /// Overwrites every vertex's normal based on the triangles it's a part of.
+    pub fn compute_normals(&mut self, mode: NormalMode) {
+        match mode {
+            NormalMode::Flat => self.compute_flat_normals(),
+            NormalMode::Smooth => self.compute_smooth_normals(),
+        }
+    }
+
+    fn compute_flat_normals(&mut self) {
+        let triangles: Vec<[usize; 3]> = self.triangles().collect();
+
+        let mut items = Vec::with_capacity(triangles.len() * 3);
+        for triangle in triangles {
+            let [a, b, c] = self.positions(triangle);
+            let normal = normalize_or((b - a).cross(c - a), FALLBACK_NORMAL.into());
+            items.extend(triangle.map(|index| Vertex3D {
+                normal: normal.into(),
+                ..self.items[index]
+            }));
+        }
+
+        self.indices = (0..items.len() as u32).collect();
+        self.items = items;
+    }
+
+    fn compute_smooth_normals(&mut self) {
+        // models are usually loaded with every corner as its own vertex, so vertices get matched up by position
+        let mut normal_sums = HashMap::<[u32; 3], Vector3<f32>>::new();
+
+        for triangle in self.triangles() {
+            let positions = self.positions(triangle);
+            let [a, b, c] = positions;
+            let face_normal = (b - a).cross(c - a);
+            if face_normal.magnitude2() == 0.0 {
+                continue;
+            }
+            let face_normal = face_normal.normalize();
+
+            for corner in 0..3 {
+                let to_next = positions[(corner + 1) % 3] - positions[corner];
+                let to_previous = positions[(corner + 2) % 3] - positions[corner];
+                if to_next.magnitude2() == 0.0 || to_previous.magnitude2() == 0.0 {
+                    continue;
+                }
+                let angle = to_next
+                    .normalize()
+                    .dot(to_previous.normalize())
+                    .clamp(-1.0, 1.0)
+                    .acos();
+
+                *normal_sums
+                    .entry(position_key(positions[corner].into()))
+                    .or_insert_with(Vector3::zero) += face_normal * angle;
+            }
+        }
+
+        for vertex in self.items.iter_mut() {
+            let normal_sum = normal_sums
+                .get(&position_key(vertex.pos))
+                .copied()
+                .unwrap_or_else(Vector3::zero);
+            vertex.normal = normalize_or(normal_sum, FALLBACK_NORMAL.into()).into();
+        }
+    }
+
+    /// Overwrites every vertex's tangent (for normal mapping) based on the UVs. The `w` component is the handedness
+    /// of the bitangent (`bitangent = cross(normal, tangent) * w`). Expects the normals to already be in place.
+    pub fn compute_tangents(&mut self) {
+        let mut tangents = vec![Vector3::<f32>::zero(); self.items.len()];
+        let mut bitangents = vec![Vector3::<f32>::zero(); self.items.len()];
+
+        for triangle in self.triangles() {
+            let [a, b, c] = self.positions(triangle);
+            let [uv_a, uv_b, uv_c] = triangle.map(|index| Vector2::from(self.items[index].uv));
+
+            let edge_1 = b - a;
+            let edge_2 = c - a;
+            let delta_uv_1 = uv_b - uv_a;
+            let delta_uv_2 = uv_c - uv_a;
+
+            let determinant = delta_uv_1.x * delta_uv_2.y - delta_uv_2.x * delta_uv_1.y;
+            if determinant == 0.0 {
+                continue;
+            }
+            let r = determinant.recip();
+            let tangent = (edge_1 * delta_uv_2.y - edge_2 * delta_uv_1.y) * r;
+            let bitangent = (edge_2 * delta_uv_1.x - edge_1 * delta_uv_2.x) * r;
+
+            for index in triangle {
+                tangents[index] += tangent;
+                bitangents[index] += bitangent;
+            }
+        }
+
+        for (vertex, (tangent, bitangent)) in self
+            .items
+            .iter_mut()
+            .zip(tangents.into_iter().zip(bitangents))
+        {
+            let normal = Vector3::from(vertex.normal);
+
+            // gram-schmidt, so the tangent is perpendicular to the normal
+            let fallback = normal.cross(vec3(0.0, 1.0, 0.0));
+            let fallback = normalize_or(fallback, normal.cross(vec3(0.0, 0.0, 1.0)));
+            let tangent = normalize_or(tangent - normal * normal.dot(tangent), fallback);
+
+            let handedness = if normal.cross(tangent).dot(bitangent) < 0.0 {
+                -1.0
+            } else {
+                1.0
+            };
+            vertex.tangent = tangent.extend(handedness).into();
+        }
+    }
+}
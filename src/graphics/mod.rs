@@ -1,7 +1,11 @@
 pub mod camera;
 pub mod graphics_controller;
+pub mod ktx2;
+pub mod mesh;
 pub mod model;
 pub mod packing;
+pub mod post_effects;
+pub mod shader_preprocessor;
 pub mod texture;
 pub mod uniforms;
 pub mod vertex;
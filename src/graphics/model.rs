@@ -1,11 +1,12 @@
 use std::collections::BTreeMap;
 
-use super::vertex::Vertex3D;
+use super::{mesh::NormalMode, vertex::Vertex3D};
 use crate::shared::{
+    bounding_box::BBox3,
     f32_util::IsSmall,
     indexed_container::{IndexedContainer, IndexedVertices},
 };
-use cgmath::{vec3, InnerSpace, Matrix3, Matrix4, SquareMatrix, Vector3};
+use cgmath::{vec3, InnerSpace, Matrix3, Matrix4, SquareMatrix, Vector3, Vector4};
 use include_dir::include_dir;
 use lazy_static::lazy_static;
 use obj::ObjData;
@@ -50,11 +51,18 @@ impl Shape {
                         } else {
                             normal.normalize()
                         };
+                        let tangent = rotation * Vector4::from(vertex.tangent).truncate();
+                        let tangent = if tangent.is_small() {
+                            vec3(0.0, 0.0, 1.0)
+                        } else {
+                            tangent.normalize()
+                        };
                         output_container.push(Vertex3D {
                             pos: (transform * Vector3::from(vertex.pos).extend(1.0))
                                 .truncate()
                                 .into(),
                             normal: normal.into(),
+                            tangent: tangent.extend(vertex.tangent[3]).into(),
                             ..vertex
                         })
                     }
@@ -71,10 +79,36 @@ impl Shape {
 #[derive(Debug)]
 pub struct Model {
     pub vertices: IndexedVertices<Vertex3D>,
+    /// In model space.
+    pub bounds: BBox3,
+    /// Which compressed atlas the texture its UVs point into is in, or `None` for the main one. See
+    /// [`TextureProvider::get_model_section()`](crate::app_state::TextureProvider::get_model_section).
+    pub texture_format: Option<wgpu::TextureFormat>,
+}
+
+/// A model as it was read from its `.obj` file.
+#[derive(Debug)]
+pub struct ModelData {
+    pub obj: ObjData,
+    /// How normals get filled in if the file doesn't have them. The obj crate skips over smoothing groups, so
+    /// this is just whether the file has any (`s off` or `s 0` alone means flat shading).
+    pub normal_mode: NormalMode,
+}
+
+fn smoothing_normal_mode(source: &[u8]) -> NormalMode {
+    let smoothed = String::from_utf8_lossy(source).lines().any(|line| {
+        let mut words = line.split_whitespace();
+        words.next() == Some("s") && !matches!(words.next(), Some("off" | "0") | None)
+    });
+    if smoothed {
+        NormalMode::Smooth
+    } else {
+        NormalMode::Flat
+    }
 }
 
 lazy_static! {
-    pub static ref MODEL_DATA: BTreeMap<String, ObjData> = {
+    pub static ref MODEL_DATA: BTreeMap<String, ModelData> = {
         const MODEL_DIR: include_dir::Dir = include_dir!("$CARGO_MANIFEST_DIR/src/graphics/models");
 
         fn extract_files<'a>(
@@ -99,14 +133,17 @@ lazy_static! {
         let mut model_data = BTreeMap::new();
 
         for file in files {
-            if let Ok(data) = ObjData::load_buf(file.contents()) {
+            if let Ok(obj) = ObjData::load_buf(file.contents()) {
                 model_data.insert(
                     file.path()
                         .file_stem()
                         .unwrap()
                         .to_string_lossy()
                         .to_string(),
-                    data,
+                    ModelData {
+                        obj,
+                        normal_mode: smoothing_normal_mode(file.contents()),
+                    },
                 );
             }
         }
@@ -0,0 +1,47 @@
+use super::graphics_controller::{BindGroupFormat, BindingFormat};
+use crate::app_state::post_effects::PostEffects;
+
+/// The render target that keeps track of how bright the view has been lately. It's one pixel, and carries over from
+/// frame to frame so it can ease over.
+pub const LUMINANCE_TARGET_NAME: &str = "adapted_luminance";
+pub const LUMINANCE_TARGET_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+/// What the 3D view gets drawn into with the effects on, before it's put back.
+pub const POST_EFFECTS_TARGET_NAME: &str = "post_effects";
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PostEffectsUniform {
+    pub vignette: f32,
+    pub grain: f32,
+    /// 1 if it's on, 0 if it isn't.
+    pub auto_exposure: f32,
+    pub max_exposure: f32,
+    /// How much of the way the adapted luminance goes to this frame's. See [`PostEffects::adaptation()`].
+    pub adaptation: f32,
+    /// Changes every frame, so the grain does too.
+    pub seed: f32,
+    pub _padding: [f32; 2],
+}
+
+impl PostEffectsUniform {
+    pub const BIND_GROUP_LAYOUT: &'static BindGroupFormat = &BindGroupFormat {
+        label: "Post Effects",
+        bindings: &[BindingFormat::dynamic_uniform(
+            "post_effects",
+            wgpu::ShaderStages::FRAGMENT,
+            std::mem::size_of::<PostEffectsUniform>() as u64,
+        )],
+    };
+
+    pub fn new(effects: &PostEffects, adaptation: f32, seed: f32) -> Self {
+        Self {
+            vignette: effects.vignette.clamp(0.0, 1.0),
+            grain: effects.grain.clamp(0.0, 1.0),
+            auto_exposure: if effects.auto_exposure { 1.0 } else { 0.0 },
+            max_exposure: effects.max_exposure.max(1.0),
+            adaptation,
+            seed,
+            _padding: [0.0; 2],
+        }
+    }
+}
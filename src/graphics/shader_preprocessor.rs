@@ -0,0 +1,127 @@
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Debug, Clone, Copy)]
+struct Conditional {
+    /// Whether the current branch gets kept.
+    active: bool,
+    /// Whether the whole `#ifdef` is inside a branch that gets kept.
+    parent_active: bool,
+    seen_else: bool,
+    line: usize,
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Replaces each defined name that has a value, wherever it's a whole word.
+fn substitute(line: &str, values: &BTreeMap<String, String>) -> String {
+    if values.is_empty() {
+        return line.to_owned();
+    }
+
+    let mut out = String::with_capacity(line.len());
+    let mut word = String::new();
+    for c in line.chars().chain(std::iter::once('\n')) {
+        if is_word_char(c) {
+            word.push(c);
+            continue;
+        }
+        match values.get(&word) {
+            Some(value) => out.push_str(value),
+            None => out.push_str(&word),
+        }
+        word.clear();
+        if c != '\n' {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Runs a tiny C-style preprocessor over WGSL `source`, with each of `defines` already defined, so one shader can have
+/// features switched on and off. It understands `#define NAME [value]`, `#ifdef NAME`, `#ifndef NAME`, `#else` and
+/// `#endif`, each on its own line. Directives and whatever gets left out turn into blank lines, so line numbers in
+/// shader errors still match the file.
+pub fn preprocess(source: &str, defines: &[&str]) -> Result<String> {
+    let mut defined = defines
+        .iter()
+        .map(|&name| name.to_owned())
+        .collect::<BTreeSet<String>>();
+    let mut values = BTreeMap::new();
+    let mut conditionals: Vec<Conditional> = Vec::new();
+    let mut out = String::with_capacity(source.len());
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let active = conditionals
+            .last()
+            .is_none_or(|conditional| conditional.active);
+
+        let Some(directive) = line.trim_start().strip_prefix('#') else {
+            if active {
+                out.push_str(&substitute(line, &values));
+            }
+            out.push('\n');
+            continue;
+        };
+        out.push('\n');
+
+        let mut parts = directive.split_whitespace();
+        let keyword = parts.next().unwrap_or_default();
+        let name = parts.next();
+        match (keyword, name) {
+            ("define", Some(name)) => {
+                if active {
+                    let value = parts.collect::<Vec<_>>().join(" ");
+                    if value.is_empty() {
+                        values.remove(name);
+                    } else {
+                        values.insert(name.to_owned(), value);
+                    }
+                    defined.insert(name.to_owned());
+                }
+            }
+            ("ifdef" | "ifndef", Some(name)) => {
+                let condition = defined.contains(name) == (keyword == "ifdef");
+                conditionals.push(Conditional {
+                    active: active && condition,
+                    parent_active: active,
+                    seen_else: false,
+                    line: line_number,
+                });
+            }
+            ("else", None) => {
+                let Some(conditional) = conditionals.last_mut() else {
+                    anyhow::bail!("line {line_number}: #else without an #ifdef");
+                };
+                if conditional.seen_else {
+                    anyhow::bail!(
+                        "line {line_number}: second #else for the #ifdef on line {}",
+                        conditional.line
+                    );
+                }
+                conditional.seen_else = true;
+                conditional.active = conditional.parent_active && !conditional.active;
+            }
+            ("endif", None) => {
+                if conditionals.pop().is_none() {
+                    anyhow::bail!("line {line_number}: #endif without an #ifdef");
+                }
+            }
+            ("define" | "ifdef" | "ifndef", None) => {
+                anyhow::bail!("line {line_number}: #{keyword} needs a name");
+            }
+            ("else" | "endif", Some(_)) => {
+                anyhow::bail!("line {line_number}: #{keyword} doesn't take anything after it");
+            }
+            _ => anyhow::bail!("line {line_number}: unknown directive #{keyword}"),
+        }
+    }
+
+    if let Some(conditional) = conditionals.last() {
+        anyhow::bail!("the #ifdef on line {} is never closed", conditional.line);
+    }
+    Ok(out)
+}
@@ -0,0 +1,135 @@
+// how many depth samples across and down get checked around each source, so its flare fades out as it goes behind something
+const OCCLUSION_GRID: i32 = 5;
+// in pixels, between each of those samples
+const OCCLUSION_SPACING: f32 = 3.0;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4f,
+    // from -1 to 1 across the sprite
+    @location(0) offset: vec2f,
+    @location(1) color: vec3f,
+    @location(2) ghost: f32,
+}
+
+struct VertexInput {
+    @location(0) position: vec2f,
+    @location(1) uv: vec2f,
+    @location(2) tex_index: u32,
+    @location(3) color: vec4f,
+}
+
+struct InstanceInput {
+    @location(4) position: vec3f,
+    @location(5) radius: f32,
+    @location(6) color: vec3f,
+    @location(7) intensity: f32,
+    // 0 for the glare on the source itself, 1 for the middle of the screen and 2 for straight across from the source
+    @location(8) ghost: f32,
+    // as a portion of the screen's height
+    @location(9) size: f32,
+}
+
+struct CameraUniform {
+    view_projection: mat4x4f,
+    view: mat4x4f,
+    projection: mat4x4f,
+    inverse_view: mat4x4f,
+    inverse_projection: mat4x4f,
+    position: vec3f,
+    log_depth_coefficient: f32,
+    velocity: vec3f,
+    aspect_ratio: f32,
+    previous_view_projection: mat4x4f,
+    shutter_fraction: f32,
+}
+
+// the depth buffer the 3D view was just drawn with
+@group(0) @binding(0)
+var texture_depth: texture_depth_2d;
+// passes wherever the depth buffer's at or past the depth it's given
+@group(0) @binding(1)
+var sampler_depth: sampler_comparison;
+@group(1) @binding(0)
+var<uniform> camera: CameraUniform;
+
+// what the 3D pipeline would've written to the depth buffer for something at `clip`
+fn depth_at(clip: vec4f) -> f32 {
+#ifdef LOG_DEPTH
+    return log2(max(1e-6, 1.0 + clip.w)) * camera.log_depth_coefficient;
+#else
+    return clip.z / clip.w;
+#endif
+}
+
+// how much of the area around `ndc` doesn't have anything closer than `depth` in front of it, from 0 to 1
+fn visibility(ndc: vec2f, depth: f32) -> f32 {
+    let pixel_size = 1.0 / vec2f(textureDimensions(texture_depth));
+    let uv = ndc * vec2f(0.5, -0.5) + 0.5;
+    var visible = 0.0;
+    for (var y = 0; y < OCCLUSION_GRID; y++) {
+        for (var x = 0; x < OCCLUSION_GRID; x++) {
+            let from_center = vec2f(f32(x), f32(y)) - f32(OCCLUSION_GRID - 1) * 0.5;
+            let sample = uv + from_center * OCCLUSION_SPACING * pixel_size;
+            // past the edge of the screen counts as hidden, so flares fade out going off it
+            if all(sample >= vec2f(0.0)) && all(sample <= vec2f(1.0)) {
+                visible += textureSampleCompareLevel(texture_depth, sampler_depth, sample, depth);
+            }
+        }
+    }
+    return visible / f32(OCCLUSION_GRID * OCCLUSION_GRID);
+}
+
+@vertex
+fn vert_main(
+    model: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.offset = vec2f(model.position.x * 2.0 - 1.0, 1.0 - model.position.y * 2.0);
+    out.color = vec3f(0.0);
+    out.ghost = instance.ghost;
+    // outside the depth range, so it gets clipped unless it turns out to be visible
+    out.clip_position = vec4f(0.0, 0.0, -1.0, 1.0);
+
+    let to_source = instance.position - camera.position;
+    let distance = length(to_source);
+    let source_clip = camera.view_projection * vec4f(instance.position, 1.0);
+    if source_clip.w <= 0.0 || distance <= instance.radius {
+        return out;
+    }
+
+    // the closest its surface could be, so it doesn't hide behind itself
+    let surface = instance.position - to_source / distance * instance.radius;
+    let source_ndc = source_clip.xy / source_clip.w;
+    let visible = visibility(source_ndc, depth_at(camera.view_projection * vec4f(surface, 1.0)));
+    if visible <= 0.0 {
+        return out;
+    }
+
+    let center = source_ndc * (1.0 - instance.ghost);
+    let extent = vec2f(instance.size / camera.aspect_ratio, instance.size);
+    out.clip_position = vec4f(center + out.offset * extent, 0.0, 1.0);
+    out.color = instance.color * instance.intensity * visible;
+
+    return out;
+}
+
+@fragment
+fn frag_main(in: VertexOutput) -> @location(0) vec4f {
+    let r = length(in.offset);
+    var shape = 0.0;
+    if in.ghost == 0.0 {
+        // a bright core with a softer halo around it, and streaks out to the sides and up and down
+        let core = exp(-r * 12.0);
+        let halo = exp(-r * 4.0) * 0.3;
+        let horizontal = pow(max(0.0, 1.0 - abs(in.offset.y) * 30.0), 2.0) * (1.0 - abs(in.offset.x));
+        let vertical = pow(max(0.0, 1.0 - abs(in.offset.x) * 30.0), 2.0) * (1.0 - abs(in.offset.y));
+        shape = core + halo * (1.0 - smoothstep(0.7, 1.0, r)) + (horizontal + vertical) * 0.5;
+    } else {
+        // a soft disc that's brighter around the rim, like the reflections inside a real lens
+        shape = (1.0 - smoothstep(0.85, 1.0, r)) * (0.5 + 0.5 * smoothstep(0.3, 0.9, r));
+    }
+
+    // with no alpha, blending just adds it on top of what's there
+    return vec4f(in.color * shape, 0.0);
+}
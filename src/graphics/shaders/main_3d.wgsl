@@ -1,10 +1,17 @@
 struct VertexOutput {
-    @builtin(position) clip_position: vec4f,
+    // has to come out exactly the same in the depth prepass and the pass after it
+    @builtin(position) @invariant clip_position: vec4f,
     @location(0) uv: vec2f,
     @location(1) tex_index: u32,
     @location(2) color: vec4f,
     @location(3) normal: vec3f,
-    @location(4) radial_proper_velocity: f32,
+    // how many times higher the frequency of its light is by the time it reaches the camera
+    @location(4) doppler_factor: f32,
+#ifdef VELOCITY
+    // where it is on screen now and where it was last frame, interpolated as they are so they can be divided per pixel
+    @location(5) current_clip_position: vec4f,
+    @location(6) previous_clip_position: vec4f,
+#endif
 }
 
 struct VertexInput {
@@ -12,77 +19,70 @@ struct VertexInput {
     @location(1) uv: vec2f,
     @location(2) tex_index: u32,
     @location(3) normal: vec3f,
+    @location(4) tangent: vec4f,
 }
 
 struct InstanceInput {
-    @location(4) model_matrix_0: vec4f,
-    @location(5) model_matrix_1: vec4f,
-    @location(6) model_matrix_2: vec4f,
-    @location(7) model_matrix_3: vec4f,
-    @location(8) velocity: vec3f,
-    @location(9) color: vec4f,
+    @location(5) model_matrix_0: vec4f,
+    @location(6) model_matrix_1: vec4f,
+    @location(7) model_matrix_2: vec4f,
+    @location(8) model_matrix_3: vec4f,
+    // relative to the camera, as a portion of the shown speed of light (which isn't always the real one)
+    @location(9) velocity: vec3f,
+    @location(10) color: vec4f,
+    @location(11) previous_model_matrix_0: vec4f,
+    @location(12) previous_model_matrix_1: vec4f,
+    @location(13) previous_model_matrix_2: vec4f,
+    @location(14) previous_model_matrix_3: vec4f,
 }
 
 struct CameraUniform {
     view_projection: mat4x4f,
-    _padding: vec3u, // this is dumb
+    view: mat4x4f,
+    projection: mat4x4f,
+    inverse_view: mat4x4f,
+    inverse_projection: mat4x4f,
+    position: vec3f,
+    log_depth_coefficient: f32,
+    velocity: vec3f,
     aspect_ratio: f32,
+    // last frame's, from this frame's camera-relative space
+    previous_view_projection: mat4x4f,
+    shutter_fraction: f32,
 }
 @group(1) @binding(0)
 var<uniform> camera: CameraUniform;
 
-fn rgb_to_hsv(c: vec3f) -> vec3f {
-    let cmin = min(min(c.r, c.g), c.b);
-    let cmax = max(max(c.r, c.g), c.b);
-    let diff = cmax - cmin;
-    var h = 0.0;
-    var s = 0.0;
+// roughly the wavelength (in nanometres) each color channel stands for
+const CHANNEL_WAVELENGTHS = vec3f(610.0, 550.0, 465.0);
+// past these, light's out of what can be seen, into infrared or ultraviolet
+const LONGEST_WAVELENGTH: f32 = 700.0;
+const SHORTEST_WAVELENGTH: f32 = 380.0;
 
-    if (cmax > 0.0) {
-        s = diff / cmax;
-    }
-
-    if (diff > 0.0) {
-        if (cmax == c.r) {
-            h = (c.g - c.b) / diff;
-            if (h < 0.0) {
-                h += 6.0;
-            }
-        } else if (cmax == c.g) {
-            h = 2.0 + (c.b - c.r) / diff;
-        } else {
-            h = 4.0 + (c.r - c.g) / diff;
-        }
+// rises from `low` up to 1 at `peak`, then falls back down to 0 at `high`
+fn hat(x: f32, low: f32, peak: f32, high: f32) -> f32 {
+    if x < peak {
+        return clamp((x - low) / (peak - low), 0.0, 1.0);
     }
+    return clamp((high - x) / (high - peak), 0.0, 1.0);
+}
 
-    h /= 6.0;
-    return vec3f(h, s, cmax);
+// how much light at `wavelength` shows up in each channel. each channel's own wavelength only lights up that one
+// channel, so nothing changes unless the light's been shifted
+fn channel_response(wavelength: f32) -> vec3f {
+    return vec3f(
+        hat(wavelength, CHANNEL_WAVELENGTHS.g, CHANNEL_WAVELENGTHS.r, LONGEST_WAVELENGTH),
+        hat(wavelength, CHANNEL_WAVELENGTHS.b, CHANNEL_WAVELENGTHS.g, CHANNEL_WAVELENGTHS.r),
+        hat(wavelength, SHORTEST_WAVELENGTH, CHANNEL_WAVELENGTHS.b, CHANNEL_WAVELENGTHS.g),
+    );
 }
 
-fn hsv_to_rgb(c: vec3f) -> vec3f {
-    let h = c.x * 6.0;
-    let s = c.y;
-    let v = c.z;
-
-    let i = floor(h);
-    let f = h - i;
-    let p = v * (1.0 - s);
-    let q = v * (1.0 - s * f);
-    let t = v * (1.0 - s * (1.0 - f));
-
-    if (i == 0.0) {
-        return vec3f(v, t, p);
-    } else if (i == 1.0) {
-        return vec3f(q, v, p);
-    } else if (i == 2.0) {
-        return vec3f(p, v, t);
-    } else if (i == 3.0) {
-        return vec3f(p, q, v);
-    } else if (i == 4.0) {
-        return vec3f(t, p, v);
-    } else {
-        return vec3f(v, p, q);
-    }
+// moves each channel's light along the spectrum by `factor`, bluer over 1 and redder under it
+fn doppler_shift(color: vec3f, factor: f32) -> vec3f {
+    let shifted = CHANNEL_WAVELENGTHS / factor;
+    return color.r * channel_response(shifted.r)
+        + color.g * channel_response(shifted.g)
+        + color.b * channel_response(shifted.b);
 }
 
 @vertex
@@ -109,16 +109,38 @@ fn vert_main(
     let light_delay_offset = length(actual_position.xyz) - length(origin_position.xyz);
     let apparent_position = vec4f(actual_position.xyz - instance.velocity * light_delay_offset, 1.0);
 
-    let radial_velocity = dot(apparent_position.xyz, -instance.velocity) / length(apparent_position.xyz);
+    // how fast it's coming towards the camera, along the line the light takes
+    let approach = dot(apparent_position.xyz, -instance.velocity) / length(apparent_position.xyz);
+    let gamma = 1.0 / sqrt(max(1e-6, 1.0 - dot(instance.velocity, instance.velocity)));
 
     var out: VertexOutput;
 
     out.clip_position = camera.view_projection * apparent_position;
+#ifdef VELOCITY
+    let previous_model_matrix = mat4x4f(
+        instance.previous_model_matrix_0,
+        instance.previous_model_matrix_1,
+        instance.previous_model_matrix_2,
+        instance.previous_model_matrix_3,
+    );
+    let previous_origin_position = previous_model_matrix * vec4f(0.0, 0.0, 0.0, 1.0);
+    let previous_actual_position = previous_model_matrix * vec4f(model.position, 1.0);
+    let previous_light_delay_offset = length(previous_actual_position.xyz) - length(previous_origin_position.xyz);
+    let previous_apparent_position = vec4f(previous_actual_position.xyz - instance.velocity * previous_light_delay_offset, 1.0);
+
+    out.current_clip_position = out.clip_position;
+    out.previous_clip_position = camera.previous_view_projection * previous_apparent_position;
+#endif
+#ifdef LOG_DEPTH
+    // spreads depth precision out evenly over orders of magnitude, instead of bunching nearly all of it up close by
+    out.clip_position.z = log2(max(1e-6, 1.0 + out.clip_position.w)) * camera.log_depth_coefficient * out.clip_position.w;
+#endif
     out.uv = model.uv;
     out.tex_index = model.tex_index;
     out.color = instance.color;
     out.normal = normalize(rotation_matrix * model.normal);
-    out.radial_proper_velocity = radial_velocity / sqrt(1.0 - length(radial_velocity));
+    // the relativistic doppler factor, so even something going straight across gets a little redder from time dilation
+    out.doppler_factor = 1.0 / (gamma * max(1e-6, 1.0 - approach));
 
     return out;
 }
@@ -130,6 +152,7 @@ var sampler_diffuse: sampler;
 
 @fragment
 fn frag_main(in: VertexOutput) -> @location(0) vec4f {
+#ifdef SHADING
     var directions = array<vec3f, 6>(vec3f(1.0, 0.0, 0.0), vec3f(0.0, 1.0, 0.0), vec3f(0.0, 0.0, 1.0), vec3f(-1.0, 0.0, 0.0), vec3f(0.0, -1.0, 0.0), vec3f(0.0, 0.0, -1.0));
     var brightnesses = array<f32, 6>(0.8, 1.0, 0.7, 0.6, 0.4, 0.75);
 
@@ -137,21 +160,30 @@ fn frag_main(in: VertexOutput) -> @location(0) vec4f {
     for (var i = 0; i < 6; i++) {
         color_multiplier += (max(dot(normalize(in.normal), directions[i]) * brightnesses[i], 0.0));
     }
+#else
+    let color_multiplier = 1.0;
+#endif
 
     let pixel_color = textureSample(texture_diffuse, sampler_diffuse, in.uv, in.tex_index) * in.color * vec4f(vec3f(color_multiplier), 1.0);
 
-    // red/blue shift
-    var red = rgb_to_hsv(vec3f(1.0, 0.0, 0.0));
-    var green = rgb_to_hsv(vec3f(0.0, 1.0, 0.0));
-    var blue = rgb_to_hsv(vec3f(0.0, 0.0, 1.0));
-
-    let shift = in.radial_proper_velocity;
-
-    red.x = clamp(red.x + shift, 0.0, 1.0);
-    green.x = clamp(green.x + shift, 0.0, 1.0);
-    blue.x += clamp(blue.x + shift, 0.0, 1.0);
+#ifdef DOPPLER
+    return vec4f(doppler_shift(pixel_color.rgb, in.doppler_factor), pixel_color.a);
+#else
+    return pixel_color;
+#endif
+}
 
-    var shifted_color = hsv_to_rgb(red) * pixel_color.x + hsv_to_rgb(green) * pixel_color.y + hsv_to_rgb(blue) * pixel_color.z;
-    shifted_color /= max(max(max(shifted_color.x, shifted_color.y), shifted_color.z), 1.0);
-    return vec4f(shifted_color, pixel_color.w);
-}
\ No newline at end of file
+#ifdef VELOCITY
+// how far this pixel moved on screen while the shutter was open, in texture coordinates
+@fragment
+fn velocity_main(in: VertexOutput) -> @location(0) vec4f {
+    // it was behind the camera, so there's no saying where on screen it came from
+    if (in.previous_clip_position.w <= 0.0) {
+        return vec4f(0.0, 0.0, 0.0, 1.0);
+    }
+    let current = in.current_clip_position.xy / in.current_clip_position.w;
+    let previous = in.previous_clip_position.xy / in.previous_clip_position.w;
+    let velocity = (current - previous) * vec2f(0.5, -0.5) * camera.shutter_fraction;
+    return vec4f(velocity, 0.0, 1.0);
+}
+#endif
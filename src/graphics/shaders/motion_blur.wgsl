@@ -0,0 +1,59 @@
+// how many times the scene gets sampled along each pixel's velocity
+const SAMPLES: i32 = 12;
+// as a portion of the screen, so something flung across it in one frame doesn't smear over all of it
+const MAX_BLUR_LENGTH: f32 = 0.1;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) tex_index: u32,
+    @location(3) color: vec4<f32>,
+}
+
+@vertex
+fn vert_main(
+    model: VertexInput,
+) -> VertexOutput {
+    let x = model.position.x;
+    let y = model.position.y;
+
+    var out: VertexOutput;
+
+    out.clip_position = vec4<f32>(x * 2.0 - 1.0, 1.0 - y * 2.0, 0.0, 1.0);
+    out.uv = model.uv;
+    out.color = model.color;
+
+    return out;
+}
+
+@group(0) @binding(0)
+var texture_scene: texture_2d<f32>;
+@group(0) @binding(1)
+var sampler_scene: sampler;
+@group(1) @binding(0)
+var texture_velocity: texture_2d<f32>;
+@group(1) @binding(1)
+var sampler_velocity: sampler;
+
+@fragment
+fn frag_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var velocity = textureSample(texture_velocity, sampler_velocity, in.uv).xy;
+    let blur_length = length(velocity);
+    if blur_length > MAX_BLUR_LENGTH {
+        velocity *= MAX_BLUR_LENGTH / blur_length;
+    }
+
+    // centered on where it is now, so the streak is spread over both sides of it
+    var color = vec4<f32>();
+    for (var i = 0; i < SAMPLES; i++) {
+        let t = f32(i) / f32(SAMPLES - 1) - 0.5;
+        color += textureSample(texture_scene, sampler_scene, in.uv - velocity * t);
+    }
+    return color / f32(SAMPLES);
+}
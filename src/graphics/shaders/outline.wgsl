@@ -15,8 +15,16 @@ struct Instance {
 
 struct CameraUniform {
     view_projection: mat4x4<f32>,
-    _padding: vec3<u32>,
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    inverse_view: mat4x4<f32>,
+    inverse_projection: mat4x4<f32>,
+    position: vec3<f32>,
+    log_depth_coefficient: f32,
+    velocity: vec3<f32>,
     aspect_ratio: f32,
+    previous_view_projection: mat4x4<f32>,
+    shutter_fraction: f32,
 }
 
 @group(0) @binding(0)
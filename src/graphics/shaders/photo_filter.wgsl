@@ -0,0 +1,67 @@
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) tex_index: u32,
+    @location(3) color: vec4<f32>,
+}
+
+@vertex
+fn vert_main(
+    model: VertexInput,
+) -> VertexOutput {
+    let x = model.position.x;
+    let y = model.position.y;
+
+    var out: VertexOutput;
+
+    out.clip_position = vec4<f32>(x * 2.0 - 1.0, 1.0 - y * 2.0, 0.0, 1.0);
+    out.uv = model.uv;
+    out.color = model.color;
+
+    return out;
+}
+
+@group(0) @binding(0)
+var texture_diffuse: texture_2d<f32>;
+@group(0) @binding(1)
+var sampler_diffuse: sampler;
+
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+@fragment
+fn frag_grayscale(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(texture_diffuse, sampler_diffuse, in.uv);
+    return vec4<f32>(vec3<f32>(luminance(color.rgb)), color.a);
+}
+
+@fragment
+fn frag_sepia(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(texture_diffuse, sampler_diffuse, in.uv);
+    let sepia = vec3<f32>(
+        dot(color.rgb, vec3<f32>(0.393, 0.769, 0.189)),
+        dot(color.rgb, vec3<f32>(0.349, 0.686, 0.168)),
+        dot(color.rgb, vec3<f32>(0.272, 0.534, 0.131)),
+    );
+    return vec4<f32>(min(sepia, vec3<f32>(1.0)), color.a);
+}
+
+@fragment
+fn frag_high_contrast(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(texture_diffuse, sampler_diffuse, in.uv);
+    // smoothstep pushes everything away from the middle
+    return vec4<f32>(smoothstep(vec3<f32>(0.0), vec3<f32>(1.0), color.rgb), color.a);
+}
+
+@fragment
+fn frag_inverted(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(texture_diffuse, sampler_diffuse, in.uv);
+    return vec4<f32>(vec3<f32>(1.0) - color.rgb, color.a);
+}
@@ -0,0 +1,103 @@
+// what auto exposure brings the average brightness to
+const MIDDLE_GRAY: f32 = 0.18;
+// how many samples across and down the luminance pass takes
+const LUMINANCE_GRID: i32 = 8;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) tex_index: u32,
+    @location(3) color: vec4<f32>,
+}
+
+struct PostEffectsUniform {
+    vignette: f32,
+    grain: f32,
+    auto_exposure: f32,
+    max_exposure: f32,
+    adaptation: f32,
+    seed: f32,
+}
+
+@vertex
+fn vert_main(
+    model: VertexInput,
+) -> VertexOutput {
+    let x = model.position.x;
+    let y = model.position.y;
+
+    var out: VertexOutput;
+
+    out.clip_position = vec4<f32>(x * 2.0 - 1.0, 1.0 - y * 2.0, 0.0, 1.0);
+    out.uv = model.uv;
+    out.color = model.color;
+
+    return out;
+}
+
+@group(0) @binding(0)
+var texture_scene: texture_2d<f32>;
+@group(0) @binding(1)
+var sampler_scene: sampler;
+@group(1) @binding(0)
+var<uniform> effects: PostEffectsUniform;
+// only frag_main has this, since frag_luminance draws into it
+@group(2) @binding(0)
+var texture_luminance: texture_2d<f32>;
+@group(2) @binding(1)
+var sampler_luminance: sampler;
+
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+fn hash(p: vec2<f32>) -> f32 {
+    let q = fract(p * vec2<f32>(123.34, 456.21));
+    let r = q + dot(q, q + 45.32);
+    return fract(r.x * r.y);
+}
+
+// the average brightness of the scene, blended over what it's been so far by the adaptation amount
+@fragment
+fn frag_luminance(in: VertexOutput) -> @location(0) vec4<f32> {
+    var total = 0.0;
+    for (var y = 0; y < LUMINANCE_GRID; y++) {
+        for (var x = 0; x < LUMINANCE_GRID; x++) {
+            let uv = (vec2<f32>(f32(x), f32(y)) + 0.5) / f32(LUMINANCE_GRID);
+            total += luminance(textureSample(texture_scene, sampler_scene, uv).rgb);
+        }
+    }
+    let average = total / f32(LUMINANCE_GRID * LUMINANCE_GRID);
+    return vec4<f32>(average, 0.0, 0.0, effects.adaptation);
+}
+
+@fragment
+fn frag_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let scene = textureSample(texture_scene, sampler_scene, in.uv);
+    let adapted = textureSample(texture_luminance, sampler_luminance, vec2<f32>(0.5, 0.5)).r;
+    var color = scene.rgb;
+
+    if effects.auto_exposure > 0.0 {
+        let exposure = clamp(MIDDLE_GRAY / max(adapted, 1e-4), 1.0 / effects.max_exposure, effects.max_exposure);
+        color *= exposure;
+    }
+
+    // darker towards the corners, the same amount all the way around no matter the aspect ratio
+    let size = vec2<f32>(textureDimensions(texture_scene));
+    let from_center = (in.uv - 0.5) * size / size.y;
+    let corner = length(vec2<f32>(size.x / size.y, 1.0) * 0.5);
+    let edge = smoothstep(0.4, 1.0, length(from_center) / corner);
+    color *= 1.0 - effects.vignette * edge;
+
+    // a different speck of noise for each pixel, every frame
+    let noise = hash(floor(in.uv * size) + effects.seed * 1000.0) - 0.5;
+    color += noise * effects.grain * 0.25;
+
+    return vec4<f32>(clamp(color, vec3<f32>(0.0), vec3<f32>(1.0)), scene.a);
+}
@@ -0,0 +1,57 @@
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) tex_index: u32,
+    @location(3) color: vec4<f32>,
+}
+
+@vertex
+fn vert_main(
+    model: VertexInput,
+) -> VertexOutput {
+    let x = model.position.x;
+    let y = model.position.y;
+
+    var out: VertexOutput;
+
+    out.clip_position = vec4<f32>(x * 2.0 - 1.0, 1.0 - y * 2.0, 0.0, 1.0);
+    out.uv = model.uv;
+    out.color = model.color;
+
+    return out;
+}
+
+@group(0) @binding(0)
+var texture_left: texture_2d<f32>;
+@group(0) @binding(1)
+var sampler_left: sampler;
+@group(1) @binding(0)
+var texture_right: texture_2d<f32>;
+@group(1) @binding(1)
+var sampler_right: sampler;
+
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+@fragment
+fn frag_anaglyph(in: VertexOutput) -> @location(0) vec4<f32> {
+    let left = textureSample(texture_left, sampler_left, in.uv);
+    let right = textureSample(texture_right, sampler_right, in.uv);
+    // half-color: the red filter only lets brightness through, but the cyan one keeps what color it can
+    return vec4<f32>(luminance(left.rgb), right.g, right.b, max(left.a, right.a));
+}
+
+@fragment
+fn frag_side_by_side(in: VertexOutput) -> @location(0) vec4<f32> {
+    // both have to be sampled outside of the branch, since sampling needs uniform control flow
+    let left = textureSample(texture_left, sampler_left, vec2<f32>(in.uv.x * 2.0, in.uv.y));
+    let right = textureSample(texture_right, sampler_right, vec2<f32>(in.uv.x * 2.0 - 1.0, in.uv.y));
+    return select(right, left, in.uv.x < 0.5);
+}
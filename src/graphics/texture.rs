@@ -1,9 +1,14 @@
-use super::{graphics_controller::GpuHandle, packing::PackedSection};
+use super::{
+    graphics_controller::{BindGroupFormat, BindingFormat, GpuHandle},
+    ktx2::{load_ktx2, Ktx2Image},
+    packing::PackedSection,
+};
 use crate::shared::bounding_box::{bbox, BBox2};
 use derive_more::*;
 use image::{DynamicImage, GenericImageView};
 use include_dir::include_dir;
 use lazy_static::lazy_static;
+use log::warn;
 use std::{collections::BTreeMap, mem};
 
 #[derive(Debug)]
@@ -64,42 +69,61 @@ lazy_static! {
     };
 }
 
-lazy_static! {
-    pub static ref TEXTURE_IMAGES: BTreeMap<String, DynamicImage> = {
-        const TEXTURE_DIR: include_dir::Dir =
-            include_dir!("$CARGO_MANIFEST_DIR/src/graphics/textures");
-
-        fn extract_files<'a>(
-            out: &mut Vec<include_dir::File<'a>>,
-            entry: include_dir::DirEntry<'a>,
-        ) {
-            match entry {
-                include_dir::DirEntry::Dir(dir) => {
-                    for child_entry in dir.entries() {
-                        extract_files(out, child_entry.to_owned());
-                    }
+/// Every file in the built-in textures folder, however deeply nested.
+fn texture_files() -> Vec<include_dir::File<'static>> {
+    const TEXTURE_DIR: include_dir::Dir = include_dir!("$CARGO_MANIFEST_DIR/src/graphics/textures");
+
+    fn extract_files<'a>(out: &mut Vec<include_dir::File<'a>>, entry: include_dir::DirEntry<'a>) {
+        match entry {
+            include_dir::DirEntry::Dir(dir) => {
+                for child_entry in dir.entries() {
+                    extract_files(out, child_entry.to_owned());
                 }
-                include_dir::DirEntry::File(file) => out.push(file),
             }
+            include_dir::DirEntry::File(file) => out.push(file),
         }
+    }
 
-        let mut files = Vec::<include_dir::File>::new();
-        for entry in TEXTURE_DIR.entries() {
-            extract_files(&mut files, entry.to_owned());
-        }
+    let mut files = Vec::<include_dir::File>::new();
+    for entry in TEXTURE_DIR.entries() {
+        extract_files(&mut files, entry.to_owned());
+    }
+    files
+}
+
+fn texture_name(file: &include_dir::File) -> String {
+    file.path()
+        .file_stem()
+        .unwrap()
+        .to_string_lossy()
+        .to_string()
+}
 
+lazy_static! {
+    pub static ref TEXTURE_IMAGES: BTreeMap<String, DynamicImage> = {
         let mut images = BTreeMap::new();
 
-        for file in files {
+        for file in texture_files() {
             if let Ok(img) = image::load_from_memory(file.contents()) {
-                images.insert(
-                    file.path()
-                        .file_stem()
-                        .unwrap()
-                        .to_string_lossy()
-                        .to_string(),
-                    img,
-                );
+                images.insert(texture_name(&file), img);
+            }
+        }
+
+        images
+    };
+    /// Compressed versions of built-in textures, from `.ktx2` files next to them. They're used instead of the ones in
+    /// [`TEXTURE_IMAGES`] with the same name wherever the GPU supports their format, so those still need to be there.
+    pub static ref KTX2_IMAGES: BTreeMap<String, Ktx2Image> = {
+        let mut images = BTreeMap::new();
+
+        for file in texture_files() {
+            if file.path().extension().is_some_and(|extension| extension == "ktx2") {
+                match load_ktx2(file.contents()) {
+                    Ok(image) => {
+                        images.insert(texture_name(&file), image);
+                    }
+                    Err(error) => warn!("Couldn't load {}: {}", file.path().display(), error),
+                }
             }
         }
 
@@ -108,34 +132,28 @@ lazy_static! {
 }
 
 impl Texture {
-    pub const STANDARD_BIND_GROUP_LAYOUT: &'static [(wgpu::ShaderStages, wgpu::BindingType)] = &[
-        (
-            wgpu::ShaderStages::FRAGMENT,
-            wgpu::BindingType::Texture {
-                sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                view_dimension: wgpu::TextureViewDimension::D2,
-                multisampled: false,
-            },
-        ),
-        (
-            wgpu::ShaderStages::FRAGMENT,
-            wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-        ),
-    ];
-    pub const ARRAY_BIND_GROUP_LAYOUT: &'static [(wgpu::ShaderStages, wgpu::BindingType)] = &[
-        (
-            wgpu::ShaderStages::FRAGMENT,
-            wgpu::BindingType::Texture {
-                sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                view_dimension: wgpu::TextureViewDimension::D2Array,
-                multisampled: false,
-            },
-        ),
-        (
-            wgpu::ShaderStages::FRAGMENT,
-            wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-        ),
-    ];
+    pub const STANDARD_BIND_GROUP_LAYOUT: &'static BindGroupFormat = &BindGroupFormat {
+        label: "Texture",
+        bindings: &[
+            BindingFormat::texture("texture", wgpu::TextureViewDimension::D2),
+            BindingFormat::sampler("sampler"),
+        ],
+    };
+    pub const ARRAY_BIND_GROUP_LAYOUT: &'static BindGroupFormat = &BindGroupFormat {
+        label: "Texture Array",
+        bindings: &[
+            BindingFormat::texture("texture", wgpu::TextureViewDimension::D2Array),
+            BindingFormat::sampler("sampler"),
+        ],
+    };
+    /// A render target's depth buffer, for seeing what's in front of what without drawing with it.
+    pub const DEPTH_BIND_GROUP_LAYOUT: &'static BindGroupFormat = &BindGroupFormat {
+        label: "Depth Texture",
+        bindings: &[
+            BindingFormat::depth_texture("depth", wgpu::ShaderStages::VERTEX),
+            BindingFormat::comparison_sampler("sampler", wgpu::ShaderStages::VERTEX),
+        ],
+    };
 
     pub fn new(
         handle: &GpuHandle,
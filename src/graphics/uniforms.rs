@@ -1 +1,172 @@
+use super::graphics_controller::{
+    BindGroupBinding, BindGroupFormat, BindGroupLayout, BindingFormat, GpuHandle,
+    GraphicsController,
+};
+use std::{marker::PhantomData, mem, sync::Arc};
 
+/// How many values a pool has room for at first. It doubles whenever it runs out.
+const INITIAL_CAPACITY: u64 = 16;
+
+/// Where a value went in a [`UniformPool`]. Only good until the pool gets cleared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct UniformSlot {
+    offset: u32,
+}
+
+/// One uniform buffer that a frame's worth of small uniforms of the same type (a camera for each view, a light for
+/// each pass, ...) all get written into, each at its own dynamic offset. They share a single bind group, so nothing
+/// has to be made per view, or per frame.
+///
+/// The format has to have just the one binding, made with [`BindingFormat::dynamic_uniform()`].
+#[derive(Debug)]
+pub struct UniformPool<T>
+where
+    T: bytemuck::NoUninit,
+{
+    handle: Arc<GpuHandle>,
+    layout: BindGroupLayout,
+    /// The size of `T`, rounded up to the device's offset alignment.
+    stride: u64,
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    len: u64,
+
+    _phantom: PhantomData<T>,
+}
+
+impl<T> UniformPool<T>
+where
+    T: bytemuck::NoUninit,
+{
+    pub fn new(controller: &GraphicsController, format: &'static BindGroupFormat) -> Self {
+        assert!(
+            matches!(
+                format.bindings,
+                [BindingFormat {
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        ..
+                    },
+                    ..
+                }]
+            ),
+            "'{}' needs exactly one dynamic uniform binding to be pooled",
+            format.label
+        );
+        assert!(
+            mem::size_of::<T>() > 0,
+            "Element type must not be zero-sized"
+        );
+
+        let handle = controller.handle_arc();
+        let layout = handle.create_bind_group_layout(format);
+        let alignment = handle.device.limits().min_uniform_buffer_offset_alignment as u64;
+        let stride = wgpu::util::align_to(mem::size_of::<T>() as u64, alignment);
+        let buffer = Self::create_buffer(&handle, format.label, stride * INITIAL_CAPACITY);
+        let bind_group = Self::create_bind_group(&handle, &layout, &buffer);
+
+        Self {
+            handle,
+            layout,
+            stride,
+            buffer,
+            bind_group,
+            len: 0,
+
+            _phantom: PhantomData,
+        }
+    }
+
+    fn create_buffer(handle: &GpuHandle, label: &str, size: u64) -> wgpu::Buffer {
+        handle.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size,
+            usage: wgpu::BufferUsages::UNIFORM
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_bind_group(
+        handle: &GpuHandle,
+        layout: &BindGroupLayout,
+        buffer: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        handle.create_bind_group(
+            layout,
+            vec![wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                buffer,
+                offset: 0,
+                size: wgpu::BufferSize::new(mem::size_of::<T>() as u64),
+            })],
+        )
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> u64 {
+        self.buffer.size() / self.stride
+    }
+
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Twice as big, with everything that's already in it copied over. Anything drawn before still uses the old
+    /// buffer, which sticks around until the GPU's done with it.
+    fn grow(&mut self) {
+        let buffer = Self::create_buffer(
+            &self.handle,
+            self.layout.format.label,
+            self.buffer.size() * 2,
+        );
+
+        let mut encoder = self
+            .handle
+            .device
+            .create_command_encoder(&Default::default());
+        encoder.copy_buffer_to_buffer(&self.buffer, 0, &buffer, 0, self.len * self.stride);
+        self.handle.queue.submit(std::iter::once(encoder.finish()));
+
+        self.bind_group = Self::create_bind_group(&self.handle, &self.layout, &buffer);
+        self.buffer = buffer;
+    }
+
+    /// Writes `value` after everything else this frame. Since each render gets submitted on its own, it can be
+    /// drawn with right away.
+    pub fn push(&mut self, value: T) -> UniformSlot {
+        if self.len == self.capacity() {
+            self.grow();
+        }
+
+        let offset = self.len * self.stride;
+        self.handle
+            .write_buffer(&self.buffer, offset, bytemuck::bytes_of(&value));
+        self.len += 1;
+
+        UniformSlot {
+            offset: offset as u32,
+        }
+    }
+
+    /// Starts over from the beginning, e.g. at the start of a frame. Anything already drawn keeps the values it was
+    /// drawn with.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// The bind group, set to `slot`'s value.
+    pub fn binding(&self, slot: UniformSlot) -> BindGroupBinding<'_> {
+        BindGroupBinding {
+            bind_group: &self.bind_group,
+            dynamic_offset: Some(slot.offset),
+        }
+    }
+}
@@ -61,19 +61,25 @@ pub struct Vertex3D {
     pub uv: [f32; 2],
     pub tex_index: u32,
     pub normal: [f32; 3],
+    /// See [`IndexedContainer::compute_tangents()`](crate::shared::indexed_container::IndexedContainer::compute_tangents).
+    pub tangent: [f32; 4],
 }
 
 impl Vertex3D {
     pub const VERTEX_FORMAT: &'static [wgpu::VertexFormat] =
-        &[Float32x3, Float32x2, Uint32, Float32x3];
+        &[Float32x3, Float32x2, Uint32, Float32x3, Float32x4];
 }
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct EntityInstance {
     pub model_matrix: [[f32; 4]; 4],
+    /// Relative to the camera, as a portion of the shown speed of light (see `Universe::speed_of_light`).
     pub velocity: [f32; 3],
     pub color: [f32; 4],
+    /// `model_matrix` as it was last frame, but relative to where the camera is this frame, for motion blur. The same
+    /// as `model_matrix` if it wasn't drawn last frame.
+    pub previous_model_matrix: [[f32; 4]; 4],
 }
 
 impl Default for EntityInstance {
@@ -82,12 +88,42 @@ impl Default for EntityInstance {
             model_matrix: Matrix4::identity().into(),
             velocity: [0.0; 3],
             color: [1.0; 4],
+            previous_model_matrix: Matrix4::identity().into(),
         }
     }
 }
 
 impl EntityInstance {
     pub const INSTANCE_FORMAT: &'static [wgpu::VertexFormat] = &[
-        Float32x4, Float32x4, Float32x4, Float32x4, Float32x3, Float32x4,
+        Float32x4, Float32x4, Float32x4, Float32x4, Float32x3, Float32x4, Float32x4, Float32x4,
+        Float32x4, Float32x4,
     ];
+
+    /// Whether nothing behind it shows through, going by its color.
+    pub fn is_opaque(&self) -> bool {
+        self.color[3] >= 1.0
+    }
+}
+
+/// One sprite of a lens flare, drawn over the 3D view with the quad from [`Vertex2D::fill_screen()`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct FlareInstance {
+    /// Where the source is, relative to the instance origin.
+    pub position: [f32; 3],
+    /// How far out its surface is from `position`, so it only counts as hidden once something's in front of that.
+    pub radius: f32,
+    pub color: [f32; 3],
+    /// Compared to a star at rest.
+    pub intensity: f32,
+    /// How far along the line from the source through the middle of the screen the sprite is: 0 for the glare on the
+    /// source itself, 1 for the middle of the screen and 2 for straight across from it.
+    pub ghost: f32,
+    /// As a portion of the screen's height.
+    pub size: f32,
+}
+
+impl FlareInstance {
+    pub const INSTANCE_FORMAT: &'static [wgpu::VertexFormat] =
+        &[Float32x3, Float32, Float32x3, Float32, Float32, Float32];
 }
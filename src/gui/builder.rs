@@ -1,16 +1,28 @@
-use super::element::{GuiContext, GuiElement};
+use super::element::{GuiContext, GuiElement, GuiPrimitive, QUAD_INDICES};
 use crate::{graphics::vertex::Vertex2D, shared::indexed_container::IndexedContainer};
+use cgmath::Vector2;
+use rayon::prelude::*;
+
+/// Below this many primitives, handing the work off to other threads costs more than it saves.
+pub const PARALLEL_TESSELLATION_THRESHOLD: usize = 512;
 
 #[derive(Debug)]
 pub struct GuiBuilder<'a> {
-    vertices: IndexedContainer<Vertex2D>,
+    /// Primitives (already offset) along with the frame they were rendered in, in draw order. They're only turned
+    /// into vertices in [`GuiBuilder::finish()`], so it can be done all at once.
+    primitives: Vec<(GuiPrimitive, Vector2<f32>)>,
     pub context: GuiContext<'a>,
 }
 
 impl<'a> GuiBuilder<'a> {
     pub fn new(context: GuiContext<'a>) -> Self {
+        Self::with_capacity(context, 0)
+    }
+
+    /// Passing in last frame's [`GuiBuilder::primitive_count()`] saves on reallocating as the GUI gets built.
+    pub fn with_capacity(context: GuiContext<'a>, primitive_capacity: usize) -> Self {
         Self {
-            vertices: Default::default(),
+            primitives: Vec::with_capacity(primitive_capacity),
             context,
         }
     }
@@ -18,13 +30,13 @@ impl<'a> GuiBuilder<'a> {
     pub fn element(&mut self, element: impl GuiElement) -> &mut Self {
         let primitives = element.render(&mut self.context);
 
-        self.vertices.items.reserve(primitives.len() * 4);
-        self.vertices.indices.reserve(primitives.len() * 6);
-        for mut primitive in primitives {
-            primitive.absolute_position += self.context.offset;
-            self.vertices
-                .push_container(primitive.vertices(self.context.frame));
-        }
+        let offset = self.context.offset;
+        let frame = self.context.frame;
+        self.primitives
+            .extend(primitives.into_iter().map(|mut primitive| {
+                primitive.absolute_position += offset;
+                (primitive, frame)
+            }));
         self
     }
 
@@ -51,7 +63,35 @@ impl<'a> GuiBuilder<'a> {
         self
     }
 
+    /// How many primitives have been added so far, including invisible ones.
+    pub fn primitive_count(&self) -> usize {
+        self.primitives.len()
+    }
+
+    /// Tessellates everything, in parallel if there's enough of it. Draw order is kept either way.
     pub fn finish(self) -> IndexedContainer<Vertex2D> {
-        self.vertices
+        let quads: Vec<[Vertex2D; 4]> = if self.primitives.len() >= PARALLEL_TESSELLATION_THRESHOLD
+        {
+            self.primitives
+                .par_iter()
+                .filter_map(|(primitive, frame)| primitive.quad(*frame))
+                .collect()
+        } else {
+            self.primitives
+                .iter()
+                .filter_map(|(primitive, frame)| primitive.quad(*frame))
+                .collect()
+        };
+
+        let mut indices = Vec::with_capacity(quads.len() * QUAD_INDICES.len());
+        indices.extend(
+            (0..quads.len() as u32)
+                .flat_map(|quad_index| QUAD_INDICES.map(|index| index + quad_index * 4)),
+        );
+
+        IndexedContainer {
+            items: quads.into_flattened(),
+            indices,
+        }
     }
 }
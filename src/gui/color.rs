@@ -65,6 +65,36 @@ impl GuiColor {
         Self { r, g, b, a: 1.0 }
     }
 
+    /// Parses a hex color in the form of `RRGGBB` or `RRGGBBAA`, with an optional leading `#`.
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        if !(hex.len() == 6 || hex.len() == 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        let channel = |index: usize| -> Option<f32> {
+            Some(u8::from_str_radix(hex.get(index * 2..index * 2 + 2)?, 16).ok()? as f32 / 255.0)
+        };
+
+        Some(Self {
+            r: channel(0)?,
+            g: channel(1)?,
+            b: channel(2)?,
+            a: if hex.len() == 8 { channel(3)? } else { 1.0 },
+        })
+    }
+
+    /// Formats the color as `RRGGBBAA`, or `RRGGBB` if it's fully opaque.
+    pub fn to_hex(self) -> String {
+        let [r, g, b, a] =
+            [self.r, self.g, self.b, self.a].map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8);
+        if a == 255 {
+            format!("{r:02X}{g:02X}{b:02X}")
+        } else {
+            format!("{r:02X}{g:02X}{b:02X}{a:02X}")
+        }
+    }
+
     pub const fn with_red(mut self, r: f32) -> Self {
         self.r = r;
         self
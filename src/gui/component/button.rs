@@ -1,6 +1,6 @@
 use super::GuiComponentId;
 use crate::{
-    gui::{element::GuiContext, transform::GuiTransform},
+    gui::{element::GuiContext, sound::GuiSound, transform::GuiTransform},
     shared::bounding_box::bbox,
 };
 use winit::event::MouseButton;
@@ -47,14 +47,20 @@ impl Button {
         context
             .input_controller
             .contest_mouse_hover(self.id, bounding_box);
-
-        let hovered = context.input_controller.component_is_hovered(self.id);
-        let left_held = hovered
-            && if self.left_held {
-                context.input_controller.held(MouseButton::Left)
-            } else {
-                context.input_controller.pressed(MouseButton::Left)
-            };
+        context
+            .input_controller
+            .register_navigable(self.id, bounding_box);
+
+        let hovered = context.input_controller.component_is_hovered(self.id)
+            || context.input_controller.component_is_nav_selected(self.id);
+        // navigation activation counts as a single-frame click
+        let left_held = context.input_controller.component_nav_activated(self.id)
+            || hovered
+                && if self.left_held {
+                    context.input_controller.held(MouseButton::Left)
+                } else {
+                    context.input_controller.pressed(MouseButton::Left)
+                };
         let right_held = hovered
             && if self.right_held {
                 context.input_controller.held(MouseButton::Right)
@@ -69,6 +75,13 @@ impl Button {
         self.left_held = left_held;
         self.right_held = right_held;
         self.hovering = hovered;
+
+        if self.hover_started() {
+            context.play_sound(GuiSound::Hover);
+        }
+        if self.left_pressed() || self.right_pressed() {
+            context.play_sound(GuiSound::Click);
+        }
     }
 
     pub fn reset(&mut self) {
@@ -0,0 +1,97 @@
+use super::text_box::{TextBox, TextBoxDescriptor};
+use crate::{
+    gui::{
+        builder::GuiBuilder,
+        color::GuiColor,
+        text::{TextBackgroundType, TextLabel},
+        transform::GuiTransform,
+    },
+    net::protocol::MAX_CHAT_LENGTH,
+    shared::input::InputController,
+};
+use winit::keyboard::NamedKey;
+
+/// A line to type chat messages and `/` commands into. Enter sends, Escape gives up.
+#[derive(Debug)]
+pub struct ChatBox {
+    pub open: bool,
+    text_box: TextBox,
+    /// The key that opened it shouldn't also get typed into it, or send it right away.
+    just_opened: bool,
+}
+
+impl Default for ChatBox {
+    fn default() -> Self {
+        Self {
+            open: false,
+            text_box: TextBox::new(TextBoxDescriptor {
+                max_chars: MAX_CHAT_LENGTH as u32,
+                allow_newlines: false,
+                ..Default::default()
+            }),
+            just_opened: false,
+        }
+    }
+}
+
+impl ChatBox {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn open(&mut self, input_controller: &mut InputController) {
+        self.open = true;
+        self.just_opened = true;
+        self.text_box.clear();
+        input_controller.set_focus(self.text_box.id());
+    }
+
+    /// Opens with `text` already typed in, and the cursor after it.
+    pub fn open_with(&mut self, input_controller: &mut InputController, text: &str) {
+        self.open(input_controller);
+        self.text_box.current_input = text.to_owned();
+        self.text_box.cursor_position = text.chars().count() as u32;
+        self.text_box.selection_anchor = self.text_box.cursor_position;
+    }
+
+    pub fn close(&mut self, input_controller: &mut InputController) {
+        self.open = false;
+        input_controller.unfocus_component(self.text_box.id());
+    }
+
+    /// Returns the message once Enter is pressed, if there's anything to send.
+    pub fn render(&mut self, builder: &mut GuiBuilder, transform: GuiTransform) -> Option<String> {
+        if !self.open {
+            return None;
+        }
+
+        let input_controller = &mut builder.context.input_controller;
+        if input_controller.consume_input(NamedKey::Escape)
+            || !input_controller.component_is_focused(self.text_box.id())
+        {
+            self.close(input_controller);
+            return None;
+        }
+
+        if self.just_opened {
+            self.just_opened = false;
+        } else {
+            self.text_box.update(input_controller);
+            if input_controller.consume_input(NamedKey::Enter) {
+                let text = self.text_box.current_input.trim().to_owned();
+                self.close(input_controller);
+                return (!text.is_empty()).then_some(text);
+            }
+        }
+
+        builder.element(self.text_box.wrap(TextLabel {
+            transform,
+            char_pixel_height: 16.0,
+            text_alignment: TextLabel::ALIGN_MIDDLE_LEFT,
+            background_color: GuiColor::BLACK.with_alpha(0.75),
+            background_type: TextBackgroundType::Full,
+            ..Default::default()
+        }));
+        None
+    }
+}
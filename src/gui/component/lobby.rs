@@ -0,0 +1,73 @@
+use crate::{
+    gui::{
+        builder::GuiBuilder,
+        color::GuiColor,
+        text::{StyledText, TextBackgroundType, TextLabel, TextOverflow},
+        transform::GuiTransform,
+    },
+    net::client::NetClient,
+    shared::localization::tr,
+    special::spatial_index::SpatialIndex,
+};
+use cgmath::{InnerSpace, Vector3};
+use std::fmt::Write;
+
+/// Who's on the server. Distances are to where each player looks like they are, light delay and all.
+#[derive(Debug, Default)]
+pub struct LobbyPanel {
+    pub open: bool,
+}
+
+impl LobbyPanel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    pub fn render(
+        &self,
+        builder: &mut GuiBuilder,
+        transform: GuiTransform,
+        client: &NetClient,
+        visible_entities: &SpatialIndex,
+    ) {
+        if !self.open {
+            return;
+        }
+
+        let mut text = if client.entity_id.is_none() {
+            tr!("lobby.joining", address = client.address)
+        } else {
+            tr!(
+                "lobby.title",
+                address = client.address,
+                scenario = client.scenario,
+                count = client.players.len()
+            )
+        };
+        for player in client.players.iter() {
+            let _ = write!(text, "\n§f{}", player.name);
+            if Some(player.entity_id) == client.entity_id {
+                text.push_str(&tr!("lobby.you"));
+            } else if let Some(bounds) = visible_entities.bounds(player.entity_id) {
+                let distance = Vector3::from(bounds.center()).magnitude();
+                text.push_str(&tr!("lobby.distance", distance = format!("{distance:.1}")));
+            } else {
+                text.push_str(&tr!("lobby.out_of_sight"));
+            }
+        }
+
+        builder.element(TextLabel {
+            transform,
+            text: StyledText::from_format_string(&text),
+            char_pixel_height: 16.0,
+            text_alignment: transform.anchor_point,
+            background_color: GuiColor::BLACK.with_alpha(0.75),
+            background_type: TextBackgroundType::BoundingBoxPerLine,
+            overflow: TextOverflow::Ellipsis,
+        });
+    }
+}
@@ -1,10 +1,13 @@
 use super::button::Button;
-use crate::gui::{
-    builder::GuiBuilder,
-    color::GuiColor,
-    text::{StyledText, TextBackgroundType, TextLabel},
-    texture_frame::TextureFrame,
-    transform::GuiTransform,
+use crate::{
+    gui::{
+        builder::GuiBuilder,
+        color::GuiColor,
+        text::{StyledText, TextBackgroundType, TextLabel},
+        texture_frame::TextureFrame,
+        transform::GuiTransform,
+    },
+    shared::navigation::NavigationInput,
 };
 use cgmath::vec2;
 
@@ -131,7 +134,7 @@ macro_rules! tb {
                 TextStyling {
                     text_color: GuiColor::WHITE,
                     drop_shadow_color: GuiColor::INVISIBLE,
-                    bold: false,
+                    ..Default::default()
                 },
             ),
             ..Default::default()
@@ -143,7 +146,15 @@ macro_rules! tb {
 pub struct RootComponent {}
 
 impl RootComponent {
-    pub fn render(&mut self, builder: &mut GuiBuilder) {}
+    pub fn render(&mut self, builder: &mut GuiBuilder) {
+        if builder
+            .context
+            .input_controller
+            .navigation_pressed(NavigationInput::Back)
+        {
+            self.close_menus();
+        }
+    }
 
     pub fn close_menus(&mut self) {}
 }
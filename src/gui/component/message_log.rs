@@ -0,0 +1,285 @@
+use super::GuiComponentId;
+use crate::{
+    gui::{
+        builder::GuiBuilder,
+        color::GuiColor,
+        text::{
+            StyledText, TextBackgroundType, TextInteractionId, TextLabel, TextOverflow, TextStyling,
+        },
+        transform::GuiTransform,
+    },
+    shared::{bounding_box::bbox, console::drain_captured_messages},
+    special::universe::EntityId,
+};
+use cgmath::vec2;
+use log::Level;
+use std::{
+    collections::VecDeque,
+    ops::Range,
+    time::{Duration, Instant},
+};
+
+/// What clicking on part of a message does.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MessageLink {
+    /// Puts `/` and the command (its name, and maybe some arguments) into the chat box, to finish or run again.
+    Command(String),
+    /// Selects the entity.
+    Entity(EntityId),
+}
+
+#[derive(Debug, Clone)]
+pub struct LogMessage {
+    pub text: StyledText,
+    pub received: Instant,
+    /// The parts of the text that can be clicked, by the interaction id they were given.
+    pub links: Vec<(TextInteractionId, MessageLink)>,
+}
+
+/// A scrolling list of recent messages (console output, notifications, echoed commands, and eventually chat)
+/// that fade out after a while. Newest messages are at the bottom. Scrolling over it looks back through history.
+#[derive(Debug)]
+pub struct MessageLog {
+    id: GuiComponentId,
+    messages: VecDeque<LogMessage>,
+    /// How many lines back from the newest message the view is scrolled.
+    scroll_offset: usize,
+    /// The most messages kept in history.
+    pub capacity: usize,
+    /// The most messages shown at once.
+    pub visible_lines: usize,
+    /// How long a message stays fully visible.
+    pub lifetime: Duration,
+    /// How long a message takes to fade out once its lifetime is over.
+    pub fade_duration: Duration,
+    pub char_pixel_height: f32,
+    /// The last interaction id handed out to a [`MessageLink`].
+    next_link_id: u64,
+}
+
+impl Default for MessageLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MessageLog {
+    pub fn new() -> Self {
+        Self {
+            id: GuiComponentId::generate(),
+            messages: VecDeque::new(),
+            scroll_offset: 0,
+            capacity: 100,
+            visible_lines: 10,
+            lifetime: Duration::from_secs(8),
+            fade_duration: Duration::from_secs(2),
+            char_pixel_height: 16.0,
+            next_link_id: 0,
+        }
+    }
+
+    pub fn push(&mut self, text: StyledText) {
+        self.push_with_links(text, Vec::new());
+    }
+
+    fn push_with_links(&mut self, text: StyledText, links: Vec<(TextInteractionId, MessageLink)>) {
+        if self.messages.len() >= self.capacity {
+            self.messages.pop_front();
+        } else if self.scroll_offset > 0 {
+            // keep the same messages in view
+            self.scroll_offset += 1;
+        }
+        self.messages.push_back(LogMessage {
+            text,
+            received: Instant::now(),
+            links,
+        });
+    }
+
+    /// Pushes a message written with `§` formatting codes.
+    pub fn push_format(&mut self, text: &str) {
+        self.push(StyledText::from_format_string(text));
+    }
+
+    /// Pushes a message written with `§` formatting codes, where the first place each link's text shows up can be
+    /// clicked.
+    pub fn push_linked(&mut self, text: &str, links: Vec<(String, MessageLink)>) {
+        let links = links
+            .into_iter()
+            .filter_map(|(link_text, link)| {
+                let start = text.find(&link_text)?;
+                Some((start..start + link_text.len(), link))
+            })
+            .collect();
+        self.push_linked_ranges(text, links);
+    }
+
+    /// Pushes a message written with `§` formatting codes, where every `§f/command` in it can be clicked to start
+    /// typing that command.
+    pub fn push_command_links(&mut self, text: &str) {
+        let links = text
+            .match_indices("§f/")
+            .filter_map(|(start, prefix)| {
+                let command = text[start + prefix.len()..]
+                    .split(|character: char| character.is_whitespace() || character == '§')
+                    .next()
+                    .filter(|command| !command.is_empty())?;
+                Some((
+                    start..start + prefix.len() + command.len(),
+                    MessageLink::Command(command.to_owned()),
+                ))
+            })
+            .collect();
+        self.push_linked_ranges(text, links);
+    }
+
+    /// Links are by byte range in `text`.
+    fn push_linked_ranges(&mut self, text: &str, links: Vec<(Range<usize>, MessageLink)>) {
+        let mut styled_text = StyledText::from_format_string(text);
+        let mut message_links = Vec::new();
+        for (range, link) in links {
+            let id = self.link_id();
+            styled_text = styled_text.with_interaction_in(range, id);
+            message_links.push((id, link));
+        }
+        self.push_with_links(styled_text, message_links);
+    }
+
+    /// Echoes a command back to the user as they typed it. Clicking it brings it back up to run again.
+    pub fn echo_command(&mut self, command: &str) {
+        let id = self.link_id();
+        // the command itself is pushed raw so formatting codes in it show up as typed
+        let mut text = StyledText::from_format_string("§7> ");
+        text.extend(
+            &StyledText::single_section(command, TextStyling::default()).with_interaction(id),
+        );
+        let link = MessageLink::Command(command.trim_start_matches('/').to_owned());
+        self.push_with_links(text, vec![(id, link)]);
+    }
+
+    fn link_id(&mut self) -> TextInteractionId {
+        self.next_link_id += 1;
+        TextInteractionId(self.next_link_id)
+    }
+
+    /// Pulls in everything the [`CapturingLogger`](crate::shared::console::CapturingLogger) has seen since the
+    /// last call.
+    pub fn pull_console_messages(&mut self) {
+        for (level, message) in drain_captured_messages() {
+            let prefix = match level {
+                Level::Error => "§c",
+                Level::Warn => "§e",
+                Level::Info => "§f",
+                Level::Debug | Level::Trace => "§7",
+            };
+            self.push_format(&format!("{prefix}{message}"));
+        }
+    }
+
+    pub fn messages(&self) -> &VecDeque<LogMessage> {
+        &self.messages
+    }
+
+    pub fn clear(&mut self) {
+        self.messages.clear();
+        self.scroll_offset = 0;
+    }
+
+    pub fn scroll_offset(&self) -> usize {
+        self.scroll_offset
+    }
+
+    pub fn scroll_to_bottom(&mut self) {
+        self.scroll_offset = 0;
+    }
+
+    fn opacity(&self, message: &LogMessage, now: Instant) -> f32 {
+        let age = now.saturating_duration_since(message.received);
+        if age <= self.lifetime {
+            return 1.0;
+        }
+
+        let fade_progress = (age - self.lifetime).as_secs_f32() / self.fade_duration.as_secs_f32();
+        (1.0 - fade_progress).clamp(0.0, 1.0)
+    }
+
+    /// Renders the newest messages into `container`, anchored to its bottom-left corner. If `show_all` is set,
+    /// faded messages are shown too (e.g. while typing in chat). Returns the link that was clicked, if one was.
+    pub fn render(
+        &mut self,
+        builder: &mut GuiBuilder,
+        container: GuiTransform,
+        show_all: bool,
+    ) -> Option<MessageLink> {
+        let (context_position, context_size) = builder.context.absolute(container);
+        builder.context.input_controller.contest_scroll(
+            self.id,
+            bbox!(context_position, context_position + context_size),
+        );
+
+        let scroll_delta = builder
+            .context
+            .input_controller
+            .component_scroll_delta(self.id)
+            .round() as isize;
+        let max_scroll_offset = self.messages.len().saturating_sub(self.visible_lines);
+        self.scroll_offset = self
+            .scroll_offset
+            .saturating_add_signed(scroll_delta)
+            .min(max_scroll_offset);
+        // looking back through history shows everything, faded or not
+        let show_all = show_all || self.scroll_offset > 0;
+
+        let now = Instant::now();
+        let (absolute_position, absolute_size) = container.absolute(builder.context.frame);
+        let line_height =
+            TextLabel::get_max_char_pixel_height(1.0, 1).recip() * self.char_pixel_height;
+
+        let mut clicked = None;
+        let mut bottom = absolute_position.y + absolute_size.y;
+        for message in self
+            .messages
+            .iter()
+            .rev()
+            .skip(self.scroll_offset)
+            .take(self.visible_lines)
+        {
+            let opacity = if show_all {
+                1.0
+            } else {
+                self.opacity(message, now)
+            };
+            if opacity <= 0.0 {
+                // everything older is faded out too
+                break;
+            }
+            if bottom - line_height < absolute_position.y {
+                break;
+            }
+
+            builder.element(TextLabel {
+                transform: GuiTransform::from_absolute(
+                    vec2(absolute_position.x, bottom - line_height),
+                    vec2(absolute_size.x, line_height),
+                ),
+                text: message.text.clone().with_opacity(opacity),
+                char_pixel_height: self.char_pixel_height,
+                text_alignment: TextLabel::ALIGN_BOTTOM_LEFT,
+                background_color: GuiColor::BLACK.with_alpha(0.5 * opacity),
+                background_type: TextBackgroundType::BoundingBoxPerLine,
+                overflow: TextOverflow::Ellipsis,
+            });
+
+            clicked = clicked.or_else(|| {
+                message
+                    .links
+                    .iter()
+                    .find(|&&(id, _)| builder.context.text_span_clicked(id))
+                    .map(|(_, link)| link.clone())
+            });
+
+            bottom -= line_height;
+        }
+        clicked
+    }
+}
@@ -1,3 +1,4 @@
+use crate::shared::determinism::GUI_COMPONENT_IDS;
 use derive_more::*;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, From, Into)]
@@ -11,10 +12,19 @@ impl Default for GuiComponentId {
 
 impl GuiComponentId {
     pub fn generate() -> Self {
-        Self(rand::random())
+        Self(GUI_COMPONENT_IDS.next_id())
     }
 }
 
 pub mod button;
+pub mod chat_box;
+pub mod lobby;
 pub mod menu;
+pub mod message_log;
+pub mod radial_menu;
+pub mod replay_scrubber;
+pub mod session_restore;
+pub mod slider;
+pub mod spawn_palette;
 pub mod text_box;
+pub mod timeline;
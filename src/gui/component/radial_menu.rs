@@ -0,0 +1,143 @@
+use crate::{
+    gui::{
+        builder::GuiBuilder,
+        color::GuiColor,
+        text::{StyledText, TextBackgroundType, TextLabel},
+        texture_frame::TextureFrame,
+        transform::GuiTransform,
+    },
+    shared::{action::Action, input::InputController},
+};
+use cgmath::{vec2, InnerSpace, Vector2};
+use std::f32::consts::TAU;
+
+pub const RADIAL_MENU_RADIUS: f32 = 96.0;
+/// How far (in pixels) the pointer has to move from the center before an option gets picked.
+pub const RADIAL_MENU_DEADZONE: f32 = 24.0;
+
+/// A menu that opens while a key is held, with options laid out in a circle. Moving the mouse towards an option
+/// and releasing the key picks it. Works with a locked mouse too, in which case it opens in the middle of the
+/// screen and gets steered by mouse movement instead of the cursor.
+#[derive(Debug, Clone)]
+pub struct RadialMenu<T: Copy> {
+    /// Options are laid out clockwise, starting from the top.
+    pub options: Vec<(T, StyledText)>,
+
+    open: bool,
+    /// `None` means the middle of the screen.
+    center: Option<Vector2<f32>>,
+    pointer: Vector2<f32>,
+}
+
+impl<T: Copy> RadialMenu<T> {
+    pub fn new(options: Vec<(T, StyledText)>) -> Self {
+        Self {
+            options,
+
+            open: false,
+            center: None,
+            pointer: vec2(0.0, 0.0),
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// The index of the option the pointer is currently pointing at.
+    pub fn highlighted_index(&self) -> Option<usize> {
+        if self.options.is_empty() || self.pointer.magnitude() < RADIAL_MENU_DEADZONE {
+            return None;
+        }
+
+        // clockwise from the top, with y going down
+        let angle = self.pointer.x.atan2(-self.pointer.y).rem_euclid(TAU);
+        let slice = TAU / self.options.len() as f32;
+        Some((angle / slice).round() as usize % self.options.len())
+    }
+
+    /// Handles opening, steering and closing the menu. Returns the picked option when `hold_action` is released.
+    ///
+    /// Call this before anything else that reads the mouse delta, since an open menu consumes it.
+    pub fn update(&mut self, input: &mut InputController, hold_action: Action) -> Option<T> {
+        if !self.open {
+            if input.focused_component_id().is_none() && input.action_pressed(hold_action) {
+                self.open = true;
+                self.center = (!input.is_mouse_locked()).then(|| input.cursor_position());
+                self.pointer = vec2(0.0, 0.0);
+            }
+            return None;
+        }
+
+        match self.center {
+            Some(center) => self.pointer = input.cursor_position() - center,
+            None => self.pointer += input.consume_mouse_delta(),
+        }
+        if self.pointer.magnitude() > RADIAL_MENU_RADIUS {
+            self.pointer = self.pointer.normalize_to(RADIAL_MENU_RADIUS);
+        }
+
+        if input.action_held(hold_action) {
+            return None;
+        }
+
+        self.open = false;
+        self.highlighted_index().map(|index| self.options[index].0)
+    }
+
+    pub fn render(&self, builder: &mut GuiBuilder) {
+        if !self.open {
+            return;
+        }
+
+        let center = self
+            .center
+            .map(|center| center - builder.context.offset)
+            .unwrap_or(builder.context.frame / 2.0);
+        let white = builder.context.white();
+        let highlighted_index = self.highlighted_index();
+
+        let slice = TAU / self.options.len().max(1) as f32;
+        for (index, (_, text)) in self.options.iter().enumerate() {
+            let angle = slice * index as f32;
+            let option_center = center + vec2(angle.sin(), -angle.cos()) * RADIAL_MENU_RADIUS;
+            let highlighted = highlighted_index == Some(index);
+
+            builder.element(TextLabel {
+                transform: GuiTransform::from_absolute(
+                    option_center - vec2(80.0, 12.0),
+                    vec2(160.0, 24.0),
+                ),
+                text: text.clone(),
+                char_pixel_height: 16.0,
+                text_alignment: TextLabel::ALIGN_MIDDLE_CENTER,
+                background_color: if highlighted {
+                    GuiColor::DARK_GRAY.with_alpha(0.9)
+                } else {
+                    GuiColor::BLACK.with_alpha(0.75)
+                },
+                background_type: TextBackgroundType::BoundingBoxPerLine,
+                ..Default::default()
+            });
+        }
+
+        // the pointer
+        builder.element(TextureFrame {
+            transform: GuiTransform::from_absolute(center - vec2(3.0, 3.0), vec2(6.0, 6.0)),
+            color: GuiColor::GRAY,
+            section: white,
+        });
+        builder.element(TextureFrame {
+            transform: GuiTransform::from_absolute(
+                center + self.pointer - vec2(4.0, 4.0),
+                vec2(8.0, 8.0),
+            ),
+            color: if highlighted_index.is_some() {
+                GuiColor::WHITE
+            } else {
+                GuiColor::GRAY
+            },
+            section: white,
+        });
+    }
+}
@@ -0,0 +1,97 @@
+use super::button::Button;
+use crate::gui::{
+    builder::GuiBuilder,
+    color::GuiColor,
+    text::{StyledText, TextLabel},
+    texture_frame::TextureFrame,
+    transform::GuiTransform,
+};
+use cgmath::vec2;
+use winit::event::MouseButton;
+
+/// A horizontal strip covering the whole length of a replay, with a playhead that can be dragged around.
+#[derive(Debug)]
+pub struct ReplayScrubber {
+    button: Button,
+    scrubbing: bool,
+}
+
+impl Default for ReplayScrubber {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReplayScrubber {
+    pub fn new() -> Self {
+        Self {
+            button: Button::new(),
+            scrubbing: false,
+        }
+    }
+
+    /// Returns the time the user dragged the playhead to, if they did.
+    pub fn render(
+        &mut self,
+        builder: &mut GuiBuilder,
+        transform: GuiTransform,
+        start_time: f64,
+        end_time: f64,
+        time: f64,
+        label: &str,
+    ) -> Option<f64> {
+        self.button.update(&mut builder.context, transform);
+
+        let duration = (end_time - start_time).max(f64::EPSILON);
+        let (position, size) = transform.absolute(builder.context.frame);
+        let time_to_x = |time: f64| position.x + ((time - start_time) / duration) as f32 * size.x;
+
+        let mut seek_time = None;
+        if self.button.left_pressed() {
+            self.scrubbing = true;
+        }
+        if self.scrubbing {
+            if builder.context.input_controller.held(MouseButton::Left) {
+                let cursor_x =
+                    builder.context.input_controller.cursor_position().x - builder.context.offset.x;
+                let cursor_time = start_time + ((cursor_x - position.x) / size.x) as f64 * duration;
+                seek_time = Some(cursor_time.clamp(start_time, end_time));
+            } else {
+                self.scrubbing = false;
+            }
+        }
+
+        let white = builder.context.white();
+        builder.element(TextureFrame {
+            transform: GuiTransform::from_absolute(position, size),
+            color: GuiColor::BLACK.with_alpha(0.75),
+            section: white,
+        });
+        builder.element(TextureFrame {
+            transform: GuiTransform::from_absolute(
+                position,
+                vec2(time_to_x(time) - position.x, size.y),
+            ),
+            color: GuiColor::DARK_AQUA.with_alpha(0.5),
+            section: white,
+        });
+        builder.element(TextureFrame {
+            transform: GuiTransform::from_absolute(
+                vec2(time_to_x(time) - 1.0, position.y),
+                vec2(2.0, size.y),
+            ),
+            color: GuiColor::RED,
+            section: white,
+        });
+
+        builder.element(TextLabel {
+            transform: GuiTransform::from_absolute(position + vec2(4.0, 4.0), size),
+            text: StyledText::from_format_string(label),
+            char_pixel_height: 16.0,
+            text_alignment: TextLabel::ALIGN_TOP_LEFT,
+            ..Default::default()
+        });
+
+        seek_time
+    }
+}
@@ -0,0 +1,92 @@
+use super::menu::{button_list, TextButton};
+use crate::{
+    gui::{
+        builder::GuiBuilder,
+        color::GuiColor,
+        text::{StyledText, TextBackgroundType, TextLabel},
+        transform::GuiTransform,
+    },
+    shared::localization::tr,
+};
+use cgmath::vec2;
+use std::time::SystemTime;
+
+pub const SESSION_PROMPT_TITLE_HEIGHT: f32 = 20.0;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionRestoreChoice {
+    /// Restore this save slot.
+    Restore(String),
+    Dismiss,
+}
+
+/// Offers to pick up where the last session left off, from its newest autosave.
+#[derive(Debug, Default)]
+pub struct SessionRestorePrompt {
+    /// The autosave slot on offer, while the prompt is up.
+    pub slot: Option<String>,
+    title: String,
+    restore_button: TextButton,
+    dismiss_button: TextButton,
+}
+
+impl SessionRestorePrompt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn offer(&mut self, slot: String, saved_at: SystemTime) {
+        let minutes_ago = saved_at
+            .elapsed()
+            .map(|elapsed| elapsed.as_secs() / 60)
+            .unwrap_or_default();
+        self.title = tr!("session.title", minutes = minutes_ago);
+        self.restore_button.text = StyledText::from_format_string(&tr!("session.restore"));
+        self.dismiss_button.text = StyledText::from_format_string(&tr!("session.dismiss"));
+        self.slot = Some(slot);
+    }
+
+    /// Returns what was picked, if anything was. Either way the prompt goes away afterwards.
+    pub fn render(
+        &mut self,
+        builder: &mut GuiBuilder,
+        transform: GuiTransform,
+    ) -> Option<SessionRestoreChoice> {
+        let slot = self.slot.as_ref()?;
+
+        let (position, size) = builder.context.absolute(transform);
+        builder.element(TextLabel {
+            transform: GuiTransform::from_absolute(
+                position,
+                vec2(size.x, SESSION_PROMPT_TITLE_HEIGHT),
+            ),
+            text: StyledText::from_format_string(&self.title),
+            char_pixel_height: 16.0,
+            text_alignment: TextLabel::ALIGN_TOP_CENTER,
+            background_color: GuiColor::BLACK.with_alpha(0.75),
+            background_type: TextBackgroundType::BoundingBoxPerLine,
+            ..Default::default()
+        });
+        button_list(
+            builder,
+            GuiTransform::from_absolute(
+                position + vec2(0.0, SESSION_PROMPT_TITLE_HEIGHT),
+                size - vec2(0.0, SESSION_PROMPT_TITLE_HEIGHT),
+            ),
+            &mut [&mut [&mut self.restore_button, &mut self.dismiss_button]],
+            true,
+        );
+
+        let choice = if self.restore_button.button.left_pressed() {
+            Some(SessionRestoreChoice::Restore(slot.clone()))
+        } else if self.dismiss_button.button.left_pressed() {
+            Some(SessionRestoreChoice::Dismiss)
+        } else {
+            None
+        };
+        if choice.is_some() {
+            self.slot = None;
+        }
+        choice
+    }
+}
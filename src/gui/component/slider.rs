@@ -0,0 +1,105 @@
+use super::button::Button;
+use crate::gui::{
+    builder::GuiBuilder,
+    color::GuiColor,
+    text::{StyledText, TextLabel},
+    texture_frame::TextureFrame,
+    transform::GuiTransform,
+};
+use cgmath::vec2;
+use winit::event::MouseButton;
+
+/// A horizontal bar with a handle that can be dragged anywhere between a minimum and maximum value.
+#[derive(Debug)]
+pub struct Slider {
+    button: Button,
+    sliding: bool,
+}
+
+impl Default for Slider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Slider {
+    pub fn new() -> Self {
+        Self {
+            button: Button::new(),
+            sliding: false,
+        }
+    }
+
+    /// Returns the value the user dragged the handle to, if they did.
+    pub fn render(
+        &mut self,
+        builder: &mut GuiBuilder,
+        transform: GuiTransform,
+        min: f64,
+        max: f64,
+        value: f64,
+        label: &str,
+    ) -> Option<f64> {
+        self.button.update(&mut builder.context, transform);
+
+        let range = (max - min).max(f64::EPSILON);
+        let (position, size) = transform.absolute(builder.context.frame);
+        let value_to_x = |value: f64| position.x + ((value - min) / range) as f32 * size.x;
+
+        let mut new_value = None;
+        if self.button.left_pressed() {
+            self.sliding = true;
+        }
+        if self.sliding {
+            if builder.context.input_controller.held(MouseButton::Left) {
+                let cursor_x =
+                    builder.context.input_controller.cursor_position().x - builder.context.offset.x;
+                let cursor_value = min + ((cursor_x - position.x) / size.x) as f64 * range;
+                new_value = Some(cursor_value.clamp(min, max));
+            } else {
+                self.sliding = false;
+            }
+        }
+
+        let white = builder.context.white();
+        builder.element(TextureFrame {
+            transform: GuiTransform::from_absolute(position, size),
+            color: GuiColor::BLACK.with_alpha(0.75),
+            section: white,
+        });
+        // a notch at zero, if it's in range
+        if (min..=max).contains(&0.0) {
+            builder.element(TextureFrame {
+                transform: GuiTransform::from_absolute(
+                    vec2(value_to_x(0.0) - 0.5, position.y),
+                    vec2(1.0, size.y),
+                ),
+                color: GuiColor::GRAY,
+                section: white,
+            });
+        }
+        let handle_color = if self.sliding || self.button.hovering() {
+            GuiColor::WHITE
+        } else {
+            GuiColor::AQUA
+        };
+        builder.element(TextureFrame {
+            transform: GuiTransform::from_absolute(
+                vec2(value_to_x(new_value.unwrap_or(value)) - 3.0, position.y),
+                vec2(6.0, size.y),
+            ),
+            color: handle_color,
+            section: white,
+        });
+
+        builder.element(TextLabel {
+            transform: GuiTransform::from_absolute(position + vec2(4.0, 4.0), size),
+            text: StyledText::from_format_string(label),
+            char_pixel_height: 16.0,
+            text_alignment: TextLabel::ALIGN_TOP_LEFT,
+            ..Default::default()
+        });
+
+        new_value
+    }
+}
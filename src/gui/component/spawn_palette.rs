@@ -0,0 +1,103 @@
+use super::menu::{button_list, TextButton};
+use crate::{
+    gui::{
+        builder::GuiBuilder,
+        text::{StyledText, TextLabel},
+        transform::GuiTransform,
+    },
+    shared::localization::tr,
+    special::prefab::PREFABS,
+};
+use cgmath::vec2;
+
+pub const PALETTE_TITLE_HEIGHT: f32 = 20.0;
+
+/// A column of buttons, one per prefab, for picking what a click in the world spawns.
+#[derive(Debug, Default)]
+pub struct SpawnPalette {
+    pub open: bool,
+    /// The prefab that clicking in the world spawns, if any.
+    pub selected: Option<String>,
+    buttons: Vec<(String, TextButton)>,
+}
+
+impl SpawnPalette {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Closing it also drops the selected prefab, so clicks go back to selecting entities.
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        if !self.open {
+            self.selected = None;
+        }
+    }
+
+    /// Clicking a prefab selects it, or deselects it if it already was.
+    pub fn render(&mut self, builder: &mut GuiBuilder, transform: GuiTransform) {
+        if !self.open {
+            return;
+        }
+
+        // prefabs can be added at runtime
+        let prefabs = PREFABS.read().unwrap();
+        if !self
+            .buttons
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .eq(prefabs.palette_names())
+        {
+            self.buttons = prefabs
+                .palette_names()
+                .map(|name| (name.to_owned(), TextButton::default()))
+                .collect();
+        }
+        drop(prefabs);
+
+        for (name, button) in self.buttons.iter_mut() {
+            button.text =
+                StyledText::from_format_string(&if self.selected.as_ref() == Some(name) {
+                    format!("§e{name}")
+                } else {
+                    format!("§f{name}")
+                });
+        }
+
+        let (position, size) = builder.context.absolute(transform);
+        builder.element(TextLabel {
+            transform: GuiTransform::from_absolute(position, vec2(size.x, PALETTE_TITLE_HEIGHT)),
+            text: StyledText::from_format_string(&tr!("palette.title")),
+            char_pixel_height: 16.0,
+            text_alignment: TextLabel::ALIGN_TOP_LEFT,
+            ..Default::default()
+        });
+
+        let mut rows: Vec<[&mut TextButton; 1]> = self
+            .buttons
+            .iter_mut()
+            .map(|(_, button)| [button])
+            .collect();
+        let mut rows: Vec<&mut [&mut TextButton]> =
+            rows.iter_mut().map(|row| row.as_mut_slice()).collect();
+        button_list(
+            builder,
+            GuiTransform::from_absolute(
+                position + vec2(0.0, PALETTE_TITLE_HEIGHT),
+                size - vec2(0.0, PALETTE_TITLE_HEIGHT),
+            ),
+            &mut rows,
+            true,
+        );
+
+        for (name, button) in self.buttons.iter() {
+            if button.button.left_pressed() {
+                self.selected = if self.selected.as_ref() == Some(name) {
+                    None
+                } else {
+                    Some(name.clone())
+                };
+            }
+        }
+    }
+}
@@ -33,7 +33,7 @@ impl Default for TextBoxDescriptor {
             selected_text_styling: TextStyling {
                 text_color: GuiColor::BLUE,
                 drop_shadow_color: GuiColor::DARK_BLUE,
-                bold: false,
+                ..Default::default()
             },
             max_chars: 1024,
             default_text: String::new(),
@@ -60,6 +60,8 @@ pub struct TextBox {
     blink_start_time: Instant,
     id: GuiComponentId,
     is_focused: bool,
+    preedit_text: String,
+    preedit_cursor: Option<(usize, usize)>,
 }
 
 impl Default for TextBox {
@@ -81,6 +83,8 @@ impl TextBox {
             blink_start_time: Instant::now(),
             id: Default::default(),
             is_focused: false,
+            preedit_text: String::new(),
+            preedit_cursor: None,
         }
     }
 
@@ -114,6 +118,16 @@ impl TextBox {
 
         let mut new_text = input_controller.just_typed().to_owned();
 
+        if is_focused {
+            input_controller
+                .preedit_text()
+                .clone_into(&mut self.preedit_text);
+            self.preedit_cursor = input_controller.preedit_cursor();
+        } else {
+            self.preedit_text.clear();
+            self.preedit_cursor = None;
+        }
+
         if !is_focused {
             self.cursor_position = u32::MAX;
             self.selection_anchor = self.cursor_position;
@@ -350,49 +364,61 @@ impl TextBox {
                 < 0.5;
         let cursor_alpha = if cursor_is_visible { 0.75 } else { 0.0 };
 
-        let mut sections = Vec::with_capacity(4);
-
-        if selection_byte_range.is_empty() {
-            sections.push(((0, cursor_byte_index), text_styling));
-            sections.push((
+        // IME composition text lives after the cursor char in raw_text, and gets spliced in at the cursor
+        let preedit_start = label.text.raw_text.len();
+        label.text.raw_text.push_str(&self.preedit_text);
+        let preedit_end = label.text.raw_text.len();
+        let preedit_cursor = preedit_start
+            + self
+                .preedit_cursor
+                .map(|(cursor_start, _)| cursor_start.min(self.preedit_text.len()))
+                .unwrap_or(self.preedit_text.len());
+
+        let cursor_sections = |styling: TextStyling| {
+            let preedit_styling = TextStyling {
+                underlined: true,
+                ..styling
+            };
+            let cursor = (
                 cursor_char_range,
                 TextStyling {
-                    text_color: text_styling
+                    text_color: styling
                         .text_color
-                        .with_alpha(text_styling.text_color.a * cursor_alpha),
-                    drop_shadow_color: text_styling
+                        .with_alpha(styling.text_color.a * cursor_alpha),
+                    drop_shadow_color: styling
                         .drop_shadow_color
-                        .with_alpha(text_styling.drop_shadow_color.a * cursor_alpha),
-                    ..text_styling
+                        .with_alpha(styling.drop_shadow_color.a * cursor_alpha),
+                    ..styling
                 },
-            ));
+            );
+
+            [
+                ((preedit_start, preedit_cursor), preedit_styling),
+                cursor,
+                ((preedit_cursor, preedit_end), preedit_styling),
+            ]
+        };
+
+        let mut sections = Vec::with_capacity(6);
+
+        if selection_byte_range.is_empty() {
+            sections.push(((0, cursor_byte_index), text_styling));
+            sections.extend(cursor_sections(text_styling));
             sections.push(((cursor_byte_index, self.current_input.len()), text_styling));
         } else {
             sections.push(((0, selection_byte_range.start), text_styling));
 
-            let cursor = (
-                cursor_char_range,
-                TextStyling {
-                    text_color: selected_text_styling
-                        .text_color
-                        .with_alpha(selected_text_styling.text_color.a * cursor_alpha),
-                    drop_shadow_color: selected_text_styling
-                        .drop_shadow_color
-                        .with_alpha(selected_text_styling.drop_shadow_color.a * cursor_alpha),
-                    ..selected_text_styling
-                },
-            );
             let selected_text = (
                 (selection_byte_range.start, selection_byte_range.end),
                 selected_text_styling,
             );
 
             if self.cursor_position == selection_min {
-                sections.push(cursor);
+                sections.extend(cursor_sections(selected_text_styling));
                 sections.push(selected_text);
             } else {
                 sections.push(selected_text);
-                sections.push(cursor);
+                sections.extend(cursor_sections(selected_text_styling));
             }
 
             sections.push((
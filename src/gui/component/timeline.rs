@@ -0,0 +1,215 @@
+use super::button::Button;
+use crate::{
+    gui::{
+        builder::GuiBuilder,
+        color::GuiColor,
+        text::{StyledText, TextLabel},
+        texture_frame::TextureFrame,
+        transform::GuiTransform,
+    },
+    shared::localization::tr,
+    special::worldline::Worldline,
+};
+use cgmath::{vec2, InnerSpace, Vector2};
+use winit::event::MouseButton;
+
+pub const MARKER_PICK_RADIUS: f32 = 6.0;
+pub const TRACE_SAMPLE_WIDTH: f32 = 4.0;
+
+/// A horizontal strip showing a window of a worldline's history, ending at the present. Keyframe events show
+/// up as markers that can be clicked to select them, and dragging anywhere else scrubs the review time.
+#[derive(Debug)]
+pub struct TimelineScrubber {
+    button: Button,
+    /// How much coordinate time (in seconds) the strip covers.
+    pub time_window: f64,
+
+    review_time: Option<f64>,
+    selected_event_index: Option<usize>,
+    scrubbing: bool,
+}
+
+impl Default for TimelineScrubber {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimelineScrubber {
+    pub fn new() -> Self {
+        Self {
+            button: Button::new(),
+            time_window: 60.0,
+
+            review_time: None,
+            selected_event_index: None,
+            scrubbing: false,
+        }
+    }
+
+    /// The coordinate time being reviewed, if the user has scrubbed somewhere.
+    pub fn review_time(&self) -> Option<f64> {
+        self.review_time
+    }
+
+    /// Reviews `review_time` as if the user had scrubbed there.
+    pub fn set_review_time(&mut self, review_time: f64) {
+        self.review_time = Some(review_time);
+    }
+
+    /// The index (into [`Worldline::events`]) of the event the user clicked on.
+    pub fn selected_event_index(&self) -> Option<usize> {
+        self.selected_event_index
+    }
+
+    /// Keeps reviewing the same moment after the universe is rebased by `time_offset` seconds.
+    pub fn rebase(&mut self, time_offset: f64) {
+        if let Some(review_time) = &mut self.review_time {
+            *review_time -= time_offset;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.review_time = None;
+        self.selected_event_index = None;
+        self.scrubbing = false;
+    }
+
+    pub fn render(
+        &mut self,
+        builder: &mut GuiBuilder,
+        transform: GuiTransform,
+        worldline: &Worldline,
+        now: f64,
+    ) {
+        self.button.update(&mut builder.context, transform);
+
+        // events get dropped when the worldline is rewritten
+        if self
+            .selected_event_index
+            .is_some_and(|index| index >= worldline.events().len())
+        {
+            self.selected_event_index = None;
+        }
+
+        let time_window = self.time_window;
+        let start_time = now - time_window;
+        let (position, size) = transform.absolute(builder.context.frame);
+        let time_to_x =
+            |time: f64| position.x + ((time - start_time) / time_window) as f32 * size.x;
+
+        // input
+        {
+            let cursor_x =
+                builder.context.input_controller.cursor_position().x - builder.context.offset.x;
+            let cursor_time = start_time + ((cursor_x - position.x) / size.x) as f64 * time_window;
+
+            if self.button.left_pressed() {
+                let picked_event = worldline
+                    .events_between(start_time, now)
+                    .map(|(index, event)| {
+                        (index, (time_to_x(event.frame.position.w) - cursor_x).abs())
+                    })
+                    .filter(|&(_, distance)| distance <= MARKER_PICK_RADIUS)
+                    .min_by(|(_, a), (_, b)| a.total_cmp(b));
+
+                match picked_event {
+                    Some((index, _)) => self.selected_event_index = Some(index),
+                    None => self.scrubbing = true,
+                }
+            }
+
+            if self.scrubbing {
+                if builder.context.input_controller.held(MouseButton::Left) {
+                    self.review_time = Some(cursor_time.clamp(start_time, now));
+                } else {
+                    self.scrubbing = false;
+                }
+            }
+
+            if self.button.right_pressed() {
+                self.clear();
+            }
+        }
+
+        let white = builder.context.white();
+        let rect = |builder: &mut GuiBuilder,
+                    rect_position: Vector2<f32>,
+                    rect_size: Vector2<f32>,
+                    color: GuiColor| {
+            builder.element(TextureFrame {
+                transform: GuiTransform::from_absolute(rect_position, rect_size),
+                color,
+                section: white,
+            });
+        };
+
+        rect(builder, position, size, GuiColor::BLACK.with_alpha(0.75));
+
+        // speed trace
+        let sample_count = ((size.x / TRACE_SAMPLE_WIDTH) as usize).max(2);
+        let sample_width = size.x / sample_count as f32;
+        for (i, event) in worldline
+            .sample(start_time, now, sample_count)
+            .into_iter()
+            .enumerate()
+        {
+            let height = event.frame.velocity.magnitude() as f32 * size.y;
+            rect(
+                builder,
+                vec2(
+                    position.x + sample_width * i as f32,
+                    position.y + size.y - height,
+                ),
+                vec2(sample_width, height),
+                GuiColor::DARK_AQUA.with_alpha(0.5),
+            );
+        }
+
+        // event markers
+        for (index, event) in worldline.events_between(start_time, now) {
+            let selected = self.selected_event_index == Some(index);
+            let width = if selected { 4.0 } else { 2.0 };
+            let color = if selected {
+                GuiColor::WHITE
+            } else if event.kind.is_acceleration() {
+                GuiColor::GOLD
+            } else {
+                GuiColor::GRAY
+            };
+
+            rect(
+                builder,
+                vec2(time_to_x(event.frame.position.w) - width / 2.0, position.y),
+                vec2(width, size.y),
+                color,
+            );
+        }
+
+        let label_text = if let Some(review_time) = self.review_time {
+            rect(
+                builder,
+                vec2(time_to_x(review_time) - 1.0, position.y),
+                vec2(2.0, size.y),
+                GuiColor::RED,
+            );
+
+            let review_event = worldline.get_event_at_time(review_time);
+            tr!(
+                "timeline.reviewing",
+                ago = format!("{:.2}", now - review_time),
+                proper_time = format!("{:.2}", review_event.proper_time)
+            )
+        } else {
+            tr!("timeline.drag_to_review")
+        };
+
+        builder.element(TextLabel {
+            transform: GuiTransform::from_absolute(position + vec2(4.0, 4.0), size),
+            text: StyledText::from_format_string(&label_text),
+            char_pixel_height: 16.0,
+            text_alignment: TextLabel::ALIGN_TOP_LEFT,
+            ..Default::default()
+        });
+    }
+}
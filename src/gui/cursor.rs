@@ -0,0 +1,109 @@
+use super::{
+    builder::GuiBuilder, color::GuiColor, texture_frame::TextureFrame, transform::GuiTransform,
+};
+use crate::graphics::texture::TEXTURE_IMAGES;
+use cgmath::{vec2, Vector2};
+
+/// A texture drawn under the (unlocked) mouse cursor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CursorTexture {
+    pub texture: String,
+    /// The pixel in the texture that lines up with the actual cursor position.
+    pub hotspot: Vector2<f32>,
+}
+
+impl CursorTexture {
+    pub fn new(texture: &str, hotspot: Vector2<f32>) -> Self {
+        Self {
+            texture: texture.to_owned(),
+            hotspot,
+        }
+    }
+
+    fn texture_size(&self) -> Vector2<f32> {
+        TEXTURE_IMAGES
+            .get(&self.texture)
+            .map(|image| vec2(image.width() as f32, image.height() as f32))
+            .unwrap_or(vec2(16.0, 16.0))
+    }
+}
+
+/// How the crosshair (while the mouse is locked) and cursor (while it isn't) look.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CursorStyle {
+    pub crosshair_texture: String,
+    /// Size of the crosshair in pixels, before `scale`.
+    pub crosshair_size: f32,
+    pub crosshair_color: GuiColor,
+    /// Whether to draw the cursor in-game instead of using the system cursor.
+    pub draw_cursor: bool,
+    pub cursor: CursorTexture,
+    /// Used instead of `cursor` while something interactable is under the cursor.
+    pub hover_cursor: CursorTexture,
+    /// How many screen pixels each texture pixel takes up.
+    pub scale: f32,
+}
+
+impl Default for CursorStyle {
+    fn default() -> Self {
+        Self {
+            crosshair_texture: "crosshair".to_owned(),
+            crosshair_size: 20.0,
+            crosshair_color: GuiColor::WHITE.with_alpha(0.75),
+            draw_cursor: true,
+            cursor: CursorTexture::new("cursor", vec2(0.0, 0.0)),
+            hover_cursor: CursorTexture::new("cursor_hover", vec2(5.0, 0.0)),
+            scale: 2.0,
+        }
+    }
+}
+
+impl CursorStyle {
+    /// Draws either the crosshair or the cursor depending on whether the mouse is locked. This should be the
+    /// last thing drawn so it ends up on top.
+    pub fn render(&self, builder: &mut GuiBuilder) {
+        let input_controller = &builder.context.input_controller;
+
+        if input_controller.is_mouse_locked() {
+            let size = vec2(self.crosshair_size, self.crosshair_size) * self.scale;
+            let section = builder
+                .context
+                .texture_provider
+                .get_section(&self.crosshair_texture);
+
+            builder.element(TextureFrame {
+                transform: GuiTransform::from_absolute(
+                    ((builder.context.frame - size) / 2.0).map(f32::floor),
+                    size,
+                ),
+                color: self.crosshair_color,
+                section,
+            });
+        } else if self.draw_cursor {
+            let hovering = input_controller.hovered_component_id().is_some()
+                || input_controller.hovered_text_span().is_some();
+            let cursor = if hovering {
+                &self.hover_cursor
+            } else {
+                &self.cursor
+            };
+
+            let position = input_controller.cursor_position()
+                - builder.context.offset
+                - cursor.hotspot * self.scale;
+            let section = builder
+                .context
+                .texture_provider
+                .get_section(&cursor.texture);
+
+            builder.element(TextureFrame {
+                transform: GuiTransform::from_absolute(
+                    position,
+                    cursor.texture_size() * self.scale,
+                ),
+                color: GuiColor::WHITE,
+                section,
+            });
+        }
+    }
+}
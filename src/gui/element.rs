@@ -1,10 +1,23 @@
-use super::{builder::GuiBuilder, color::GuiColor, text::TextLabel, transform::GuiTransform};
+use super::{
+    builder::GuiBuilder,
+    color::GuiColor,
+    sound::GuiSound,
+    text::{TextInteractionId, TextLabel},
+    transform::GuiTransform,
+};
 use crate::{
     app_state::TextureProvider,
     graphics::{texture::OrientedSection, vertex::Vertex2D},
-    shared::{bounding_box::bbox, indexed_container::IndexedContainer, input::InputController},
+    shared::{bounding_box::bbox, input::InputController, interning::TextureId},
 };
 use cgmath::{vec2, ElementWise, Vector2};
+use lazy_static::lazy_static;
+use std::sync::mpsc::Sender;
+use winit::event::MouseButton;
+
+lazy_static! {
+    pub static ref WHITE_TEXTURE: TextureId = TextureId::intern("white");
+}
 
 #[derive(Debug)]
 pub struct GuiContext<'a> {
@@ -14,6 +27,7 @@ pub struct GuiContext<'a> {
 
     pub texture_provider: &'a TextureProvider,
     pub input_controller: &'a mut InputController,
+    pub sound_sender: Option<Sender<GuiSound>>,
 }
 
 impl<'a> GuiContext<'a> {
@@ -29,6 +43,19 @@ impl<'a> GuiContext<'a> {
 
             texture_provider,
             input_controller,
+            sound_sender: None,
+        }
+    }
+
+    pub fn with_sound_sender(mut self, sound_sender: Sender<GuiSound>) -> Self {
+        self.sound_sender = Some(sound_sender);
+        self
+    }
+
+    /// Asks the audio system to play a GUI sound, if there's one listening.
+    pub fn play_sound(&self, sound: GuiSound) {
+        if let Some(sound_sender) = &self.sound_sender {
+            let _ = sound_sender.send(sound);
         }
     }
 
@@ -36,6 +63,10 @@ impl<'a> GuiContext<'a> {
         GuiBuilder::new(self)
     }
 
+    pub fn builder_with_capacity(self, primitive_capacity: usize) -> GuiBuilder<'a> {
+        GuiBuilder::with_capacity(self, primitive_capacity)
+    }
+
     pub fn absolute_position(&self, transform: GuiTransform) -> Vector2<f32> {
         transform.absolute_position(self.frame) + self.offset
     }
@@ -53,7 +84,21 @@ impl<'a> GuiContext<'a> {
     }
 
     pub fn white(&self) -> OrientedSection {
-        self.texture_provider.get_section("white")
+        self.texture_provider.get_section_by_id(*WHITE_TEXTURE)
+    }
+
+    /// The interactable text span that the cursor was over last frame, if any.
+    pub fn hovered_text_span(&self) -> Option<TextInteractionId> {
+        self.input_controller.hovered_text_span()
+    }
+
+    pub fn text_span_hovered(&self, id: TextInteractionId) -> bool {
+        self.input_controller.text_span_is_hovered(id)
+    }
+
+    /// Whether an interactable text span was left-clicked this frame.
+    pub fn text_span_clicked(&self, id: TextInteractionId) -> bool {
+        self.text_span_hovered(id) && self.input_controller.pressed(MouseButton::Left)
     }
 
     pub fn char_pixel_height(&self, transform: GuiTransform, lines: u32) -> f32 {
@@ -72,51 +117,61 @@ pub struct GuiPrimitive {
     pub absolute_size: Vector2<f32>,
     pub section: OrientedSection,
     pub color: GuiColor,
+    /// Horizontal shear, as a portion of the height. The top edge is shifted right by half of this and the bottom
+    /// edge is shifted left by half of this, which keeps the center in place.
+    pub skew: f32,
 }
 
+/// How the corners from [`GuiPrimitive::quad()`] make up two triangles.
+pub const QUAD_INDICES: [u32; 6] = [0, 1, 2, 2, 3, 0];
+
 impl GuiPrimitive {
-    pub fn vertices(&self, frame: Vector2<f32>) -> IndexedContainer<Vertex2D> {
+    /// The four corners, or nothing if it wouldn't be visible anyway.
+    pub fn quad(&self, frame: Vector2<f32>) -> Option<[Vertex2D; 4]> {
         if !self.color.is_visible() {
-            return IndexedContainer::default();
+            return None;
         }
 
         let corner_0 = self.absolute_position.div_element_wise(frame);
         let corner_1 = corner_0 + self.absolute_size.div_element_wise(frame);
         let rect = bbox!(corner_0, corner_1);
 
+        let shear = self.skew * self.absolute_size.y / frame.x / 2.0;
+        let sheared_corner = |is_max: [bool; 2]| {
+            let [x, y] = rect.get_corner(is_max);
+            [if is_max[1] { x - shear } else { x + shear }, y]
+        };
+
         let color = [self.color.r, self.color.g, self.color.b, self.color.a];
 
         let uv = self.section.uv_corners();
         let tex_index = self.section.section.layer_index;
 
-        IndexedContainer {
-            items: vec![
-                Vertex2D {
-                    pos: rect.get_corner([false, false]),
-                    uv: uv.top_left,
-                    tex_index,
-                    color,
-                },
-                Vertex2D {
-                    pos: rect.get_corner([false, true]),
-                    uv: uv.bottom_left,
-                    tex_index,
-                    color,
-                },
-                Vertex2D {
-                    pos: rect.get_corner([true, true]),
-                    uv: uv.bottom_right,
-                    tex_index,
-                    color,
-                },
-                Vertex2D {
-                    pos: rect.get_corner([true, false]),
-                    uv: uv.top_right,
-                    tex_index,
-                    color,
-                },
-            ],
-            indices: vec![0, 1, 2, 2, 3, 0],
-        }
+        Some([
+            Vertex2D {
+                pos: sheared_corner([false, false]),
+                uv: uv.top_left,
+                tex_index,
+                color,
+            },
+            Vertex2D {
+                pos: sheared_corner([false, true]),
+                uv: uv.bottom_left,
+                tex_index,
+                color,
+            },
+            Vertex2D {
+                pos: sheared_corner([true, true]),
+                uv: uv.bottom_right,
+                tex_index,
+                color,
+            },
+            Vertex2D {
+                pos: sheared_corner([true, false]),
+                uv: uv.top_right,
+                tex_index,
+                color,
+            },
+        ])
     }
 }
@@ -0,0 +1,95 @@
+use super::{
+    color::GuiColor,
+    element::{GuiContext, GuiElement, GuiPrimitive},
+    transform::GuiTransform,
+};
+use cgmath::vec2;
+
+/// A line plotted across a strip from left to right, for seeing at a glance how something's changed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Graph {
+    pub transform: GuiTransform,
+    /// Spread out evenly across the graph, from left to right.
+    pub values: Vec<f32>,
+    /// The values at the bottom and top edges. Anything outside of it is clamped to the edge.
+    pub range: (f32, f32),
+    pub line_color: GuiColor,
+    pub background_color: GuiColor,
+    /// In pixels.
+    pub line_thickness: f32,
+    /// Thickness (in pixels) of the gap between the edge of the background and the line.
+    pub padding: f32,
+}
+
+impl Default for Graph {
+    fn default() -> Self {
+        Self {
+            transform: Default::default(),
+            values: Vec::new(),
+            range: (0.0, 1.0),
+            line_color: GuiColor::WHITE,
+            background_color: GuiColor::DARK_GRAY,
+            line_thickness: 1.0,
+            padding: 2.0,
+        }
+    }
+}
+
+impl GuiElement for Graph {
+    fn transform(&self) -> GuiTransform {
+        self.transform
+    }
+
+    fn render(&self, context: &mut GuiContext) -> Vec<GuiPrimitive> {
+        let (absolute_position, absolute_size) = self.transform.absolute(context.frame);
+        let white = context.white();
+
+        let mut primitives = vec![GuiPrimitive {
+            absolute_position,
+            absolute_size,
+            section: white,
+            color: self.background_color,
+            skew: 0.0,
+        }];
+
+        let padding = vec2(self.padding, self.padding);
+        let plot_position = absolute_position + padding;
+        let plot_size = (absolute_size - padding * 2.0).map(|v| v.max(0.0));
+        let thickness = self.line_thickness.min(plot_size.y);
+
+        let (min, max) = self.range;
+        let span = (max - min).max(f32::EPSILON);
+        // the top of the line at each value, so it stays inside at either edge
+        let heights = self
+            .values
+            .iter()
+            .map(|value| {
+                let portion = ((value - min) / span).clamp(0.0, 1.0);
+                plot_position.y + (plot_size.y - thickness) * (1.0 - portion)
+            })
+            .collect::<Vec<_>>();
+        let step = plot_size.x / heights.len().saturating_sub(1).max(1) as f32;
+
+        // flat between each value and the next, with a step up or down to join them
+        for (index, pair) in heights.windows(2).enumerate() {
+            let x = plot_position.x + step * index as f32;
+            let (top, bottom) = (pair[0].min(pair[1]), pair[0].max(pair[1]));
+            primitives.push(GuiPrimitive {
+                absolute_position: vec2(x, pair[0]),
+                absolute_size: vec2(step, thickness),
+                section: white,
+                color: self.line_color,
+                skew: 0.0,
+            });
+            primitives.push(GuiPrimitive {
+                absolute_position: vec2(x + step - thickness / 2.0, top),
+                absolute_size: vec2(thickness, bottom - top + thickness),
+                section: white,
+                color: self.line_color,
+                skew: 0.0,
+            });
+        }
+
+        primitives
+    }
+}
@@ -0,0 +1,269 @@
+use super::{
+    builder::GuiBuilder,
+    color::GuiColor,
+    component::button::Button,
+    text::{StyledText, TextLabel},
+    texture_frame::TextureFrame,
+    transform::{GuiTransform, UDim2},
+};
+use cgmath::{vec2, ElementWise, Vector2};
+use linear_map::LinearMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use winit::event::MouseButton;
+
+/// A point on the screen that HUD widgets can be pinned to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HudAnchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl HudAnchor {
+    /// Where this anchor is on the screen, from `(0.0, 0.0)` (top left) to `(1.0, 1.0)` (bottom right).
+    pub fn point(self) -> Vector2<f32> {
+        match self {
+            Self::TopLeft => vec2(0.0, 0.0),
+            Self::Top => vec2(0.5, 0.0),
+            Self::TopRight => vec2(1.0, 0.0),
+            Self::Left => vec2(0.0, 0.5),
+            Self::Center => vec2(0.5, 0.5),
+            Self::Right => vec2(1.0, 0.5),
+            Self::BottomLeft => vec2(0.0, 1.0),
+            Self::Bottom => vec2(0.5, 1.0),
+            Self::BottomRight => vec2(1.0, 1.0),
+        }
+    }
+
+    /// The anchor of whichever third of the screen `point` (in scale) is in.
+    pub fn nearest(point: Vector2<f32>) -> Self {
+        let third = |v: f32| {
+            if v < 1.0 / 3.0 {
+                0
+            } else if v > 2.0 / 3.0 {
+                2
+            } else {
+                1
+            }
+        };
+
+        match (third(point.x), third(point.y)) {
+            (0, 0) => Self::TopLeft,
+            (1, 0) => Self::Top,
+            (2, 0) => Self::TopRight,
+            (0, 1) => Self::Left,
+            (1, 1) => Self::Center,
+            (2, 1) => Self::Right,
+            (0, _) => Self::BottomLeft,
+            (1, _) => Self::Bottom,
+            _ => Self::BottomRight,
+        }
+    }
+
+    /// Which way margins push away from the anchor, per axis. Edges push inwards, centers push right/down.
+    fn margin_direction(self) -> Vector2<f32> {
+        self.point().map(|v| if v > 0.5 { -1.0 } else { 1.0 })
+    }
+
+    /// Which axes this anchor is touching a screen edge on.
+    fn edge_axes(self) -> Vector2<f32> {
+        self.point().map(|v| if v == 0.5 { 0.0 } else { 1.0 })
+    }
+}
+
+/// Where a HUD widget goes: an anchor, and a margin away from it in unscaled pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HudPlacement {
+    pub anchor: HudAnchor,
+    pub margin: Vector2<f32>,
+}
+
+impl HudPlacement {
+    pub fn new(anchor: HudAnchor, margin: Vector2<f32>) -> Self {
+        Self { anchor, margin }
+    }
+
+    /// The transform of a widget with the given size placed here. `safe_area` is extra margin (in unscaled
+    /// pixels) kept between the widget and any screen edge it's anchored to.
+    pub fn transform(self, size: UDim2, gui_scale: f32, safe_area: f32) -> GuiTransform {
+        let anchor_point = self.anchor.point();
+        let offset = (self.margin + self.anchor.edge_axes() * safe_area)
+            .mul_element_wise(self.anchor.margin_direction())
+            * gui_scale;
+
+        GuiTransform {
+            position: UDim2::new((anchor_point.x, offset.x), (anchor_point.y, offset.y)),
+            size,
+            anchor_point,
+            ..Default::default()
+        }
+    }
+
+    /// The inverse of [`HudPlacement::transform()`]: finds the placement (snapped to the nearest anchor) of a
+    /// widget at an absolute position.
+    pub fn from_absolute(
+        absolute_position: Vector2<f32>,
+        absolute_size: Vector2<f32>,
+        frame: Vector2<f32>,
+        gui_scale: f32,
+        safe_area: f32,
+    ) -> Self {
+        let center = absolute_position + absolute_size / 2.0;
+        let anchor = HudAnchor::nearest(center.div_element_wise(frame));
+        let anchor_point = anchor.point();
+
+        let widget_anchor_position =
+            absolute_position + absolute_size.mul_element_wise(anchor_point);
+        let offset = widget_anchor_position - frame.mul_element_wise(anchor_point);
+        let margin = (offset / gui_scale.max(f32::EPSILON))
+            .mul_element_wise(anchor.margin_direction())
+            - anchor.edge_axes() * safe_area;
+
+        Self {
+            anchor,
+            margin: margin.map(f32::round),
+        }
+    }
+}
+
+/// Where every HUD widget goes, by name. Widgets without a placement here use their own default.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HudLayout {
+    pub placements: BTreeMap<String, HudPlacement>,
+    /// Extra margin (in unscaled pixels) kept clear around the edges of the screen.
+    pub safe_area: f32,
+}
+
+impl Default for HudLayout {
+    fn default() -> Self {
+        Self {
+            placements: BTreeMap::new(),
+            safe_area: 0.0,
+        }
+    }
+}
+
+impl HudLayout {
+    pub fn placement(&self, name: &str, default: HudPlacement) -> HudPlacement {
+        self.placements.get(name).copied().unwrap_or(default)
+    }
+
+    pub fn transform(
+        &self,
+        name: &str,
+        default: HudPlacement,
+        size: UDim2,
+        gui_scale: f32,
+    ) -> GuiTransform {
+        self.placement(name, default)
+            .transform(size, gui_scale, self.safe_area)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct HudDrag {
+    /// Where the widget was grabbed, relative to its top left corner.
+    grab_offset: Vector2<f32>,
+}
+
+/// Lets the user drag HUD widgets around. Each widget gets snapped to the anchor of whichever third of the
+/// screen it's dropped in.
+#[derive(Debug, Default)]
+pub struct HudLayoutEditor {
+    pub active: bool,
+    buttons: LinearMap<String, Button>,
+    dragging: Option<(String, HudDrag)>,
+}
+
+impl HudLayoutEditor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handles dragging for one widget and draws its outline. Call this for every HUD widget while the editor
+    /// is active. Returns `true` when the widget was dropped somewhere new, meaning the layout should be saved.
+    pub fn edit_widget(
+        &mut self,
+        builder: &mut GuiBuilder,
+        layout: &mut HudLayout,
+        name: &str,
+        default: HudPlacement,
+        size: UDim2,
+        gui_scale: f32,
+    ) -> bool {
+        let frame = builder.context.frame;
+        let transform = layout.transform(name, default, size, gui_scale);
+        let (absolute_position, absolute_size) = transform.absolute(frame);
+
+        let button = match self.buttons.get_mut(name) {
+            Some(button) => button,
+            None => {
+                self.buttons.insert(name.to_owned(), Button::new());
+                self.buttons.get_mut(name).unwrap()
+            }
+        };
+        button.update(&mut builder.context, transform);
+
+        let cursor_position =
+            builder.context.input_controller.cursor_position() - builder.context.offset;
+        if button.left_pressed() && self.dragging.is_none() {
+            self.dragging = Some((
+                name.to_owned(),
+                HudDrag {
+                    grab_offset: cursor_position - absolute_position,
+                },
+            ));
+        }
+
+        let mut dropped = false;
+        let is_dragging = matches!(&self.dragging, Some((dragged_name, _)) if dragged_name == name);
+        if let Some((_, drag)) = self.dragging.as_ref().filter(|_| is_dragging) {
+            layout.placements.insert(
+                name.to_owned(),
+                HudPlacement::from_absolute(
+                    cursor_position - drag.grab_offset,
+                    absolute_size,
+                    frame,
+                    gui_scale,
+                    layout.safe_area,
+                ),
+            );
+
+            if !builder.context.input_controller.held(MouseButton::Left) {
+                self.dragging = None;
+                dropped = true;
+            }
+        }
+
+        let white = builder.context.white();
+        let outline_color = if is_dragging {
+            GuiColor::YELLOW
+        } else if button.hovering() {
+            GuiColor::WHITE
+        } else {
+            GuiColor::GRAY
+        };
+        builder.element(TextureFrame {
+            transform,
+            color: outline_color.with_alpha(0.25),
+            section: white,
+        });
+        builder.element(TextLabel {
+            transform,
+            text: StyledText::from_format_string(&format!("§e{name}")),
+            char_pixel_height: 16.0,
+            text_alignment: TextLabel::ALIGN_MIDDLE_CENTER,
+            ..Default::default()
+        });
+
+        dropped
+    }
+}
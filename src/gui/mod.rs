@@ -1,7 +1,12 @@
 pub mod builder;
 pub mod color;
 pub mod component;
+pub mod cursor;
 pub mod element;
+pub mod graph;
+pub mod hud;
+pub mod progress_bar;
+pub mod sound;
 pub mod text;
 pub mod texture_frame;
 pub mod transform;
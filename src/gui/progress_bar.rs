@@ -0,0 +1,61 @@
+use super::{
+    color::GuiColor,
+    element::{GuiContext, GuiElement, GuiPrimitive},
+    transform::GuiTransform,
+};
+use cgmath::vec2;
+
+/// A horizontal bar that fills up from left to right.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProgressBar {
+    pub transform: GuiTransform,
+    /// How full the bar is, from `0.0` to `1.0`.
+    pub progress: f32,
+    pub fill_color: GuiColor,
+    pub background_color: GuiColor,
+    /// Thickness (in pixels) of the gap between the edge of the background and the fill.
+    pub padding: f32,
+}
+
+impl Default for ProgressBar {
+    fn default() -> Self {
+        Self {
+            transform: Default::default(),
+            progress: 0.0,
+            fill_color: GuiColor::WHITE,
+            background_color: GuiColor::DARK_GRAY,
+            padding: 2.0,
+        }
+    }
+}
+
+impl GuiElement for ProgressBar {
+    fn transform(&self) -> GuiTransform {
+        self.transform
+    }
+
+    fn render(&self, context: &mut GuiContext) -> Vec<GuiPrimitive> {
+        let (absolute_position, absolute_size) = self.transform.absolute(context.frame);
+        let white = context.white();
+
+        let padding = vec2(self.padding, self.padding);
+        let fill_size = (absolute_size - padding * 2.0).map(|v| v.max(0.0));
+
+        vec![
+            GuiPrimitive {
+                absolute_position,
+                absolute_size,
+                section: white,
+                color: self.background_color,
+                skew: 0.0,
+            },
+            GuiPrimitive {
+                absolute_position: absolute_position + padding,
+                absolute_size: vec2(fill_size.x * self.progress.clamp(0.0, 1.0), fill_size.y),
+                section: white,
+                color: self.fill_color,
+                skew: 0.0,
+            },
+        ]
+    }
+}
@@ -0,0 +1,57 @@
+use std::collections::BTreeMap;
+
+/// Something a GUI component did that might deserve a sound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GuiSound {
+    Hover,
+    Click,
+    Toggle,
+    Error,
+}
+
+impl GuiSound {
+    pub const ALL: [Self; 4] = [Self::Hover, Self::Click, Self::Toggle, Self::Error];
+
+    pub fn default_sound_name(self) -> &'static str {
+        match self {
+            Self::Hover => "gui/hover",
+            Self::Click => "gui/click",
+            Self::Toggle => "gui/toggle",
+            Self::Error => "gui/error",
+        }
+    }
+}
+
+/// Per-theme replacements for the default GUI sounds.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GuiSoundTheme {
+    /// `None` silences the sound entirely.
+    pub overrides: BTreeMap<GuiSound, Option<String>>,
+}
+
+impl GuiSoundTheme {
+    /// The themes that can be picked by name, see [`GuiSoundTheme::named()`].
+    pub const NAMES: [&'static str; 3] = ["default", "quiet", "silent"];
+
+    /// One of the built-in themes: `"default"` plays everything, `"quiet"` leaves out hovering, and `"silent"`
+    /// doesn't play anything.
+    pub fn named(name: &str) -> Option<Self> {
+        let silenced: &[GuiSound] = match name {
+            "default" => &[],
+            "quiet" => &[GuiSound::Hover],
+            "silent" => &GuiSound::ALL,
+            _ => return None,
+        };
+        Some(Self {
+            overrides: silenced.iter().map(|&sound| (sound, None)).collect(),
+        })
+    }
+
+    /// The name of the sound that should play for a GUI sound event, if any.
+    pub fn resolve(&self, sound: GuiSound) -> Option<&str> {
+        match self.overrides.get(&sound) {
+            Some(sound_name) => sound_name.as_deref(),
+            None => Some(sound.default_sound_name()),
+        }
+    }
+}
@@ -1,6 +1,9 @@
 use crate::{
     graphics::texture::{OrientedSection, TEXTURE_IMAGES},
-    shared::bounding_box::{bbox, BBox2},
+    shared::{
+        bounding_box::{bbox, BBox2},
+        interning::TextureId,
+    },
 };
 
 use super::{
@@ -10,12 +13,17 @@ use super::{
 };
 use cgmath::{vec2, ElementWise, Vector2};
 use codepage_437::CP437_WINGDINGS;
+use derive_more::*;
 use image::{DynamicImage, GenericImageView};
 use lazy_static::lazy_static;
+use rand::{seq::SliceRandom, Rng};
+use std::{collections::BTreeMap, ops::Range};
 
 pub const FONT_CHARS_PER_ROW: u32 = 16;
 pub const FONT_PIXELS_PER_CHAR: u32 = 8;
 pub const FONT_CHAR_PIXEL_PORTION: f32 = 1.0 / (FONT_PIXELS_PER_CHAR as f32);
+/// How far the top of an italic character leans to the right, relative to its bottom.
+pub const ITALIC_SKEW: f32 = FONT_CHAR_PIXEL_PORTION * 2.0;
 
 #[derive(Debug, Clone, Copy)]
 pub struct CharData {
@@ -81,15 +89,55 @@ pub fn generate_char_data(atlas: &DynamicImage) -> [CharData; 256] {
 }
 
 lazy_static! {
+    pub static ref FONT_TEXTURE: TextureId = TextureId::intern("font");
     pub static ref FONT_CHAR_DATA: [CharData; 256] =
         generate_char_data(TEXTURE_IMAGES.get("font").unwrap());
+    /// Printable characters grouped by their pixel width, used for obfuscated text.
+    pub static ref FONT_CHARS_BY_WIDTH: BTreeMap<u32, Vec<u8>> = {
+        let mut chars_by_width = BTreeMap::<u32, Vec<u8>>::new();
+        for ibm_code in 1..=255u8 {
+            let char_data = FONT_CHAR_DATA[ibm_code as usize];
+            if char_data.width > 0.0 {
+                chars_by_width
+                    .entry((char_data.width / FONT_CHAR_PIXEL_PORTION).round() as u32)
+                    .or_default()
+                    .push(ibm_code);
+            }
+        }
+        chars_by_width
+    };
+}
+
+/// Picks a random character with the same width as the given one, so obfuscated text doesn't shift around.
+pub fn obfuscate_char(ibm_code: u8) -> u8 {
+    let width = FONT_CHAR_DATA[ibm_code as usize].width;
+    if width <= 0.0 {
+        return ibm_code;
+    }
+
+    FONT_CHARS_BY_WIDTH
+        .get(&((width / FONT_CHAR_PIXEL_PORTION).round() as u32))
+        .and_then(|candidates| candidates.choose(&mut rand::thread_rng()))
+        .copied()
+        .unwrap_or(ibm_code)
 }
 
+/// Identifies a clickable span of [`StyledText`]. What the id means is up to whoever made the text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, From, Into)]
+pub struct TextInteractionId(pub u64);
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct TextStyling {
     pub text_color: GuiColor,
     pub drop_shadow_color: GuiColor,
     pub bold: bool,
+    pub italic: bool,
+    pub underlined: bool,
+    pub strikethrough: bool,
+    /// Every character is replaced with a random character of the same width each frame.
+    pub obfuscated: bool,
+    /// Text with an interaction id can be hovered and clicked, see [`GuiContext::text_span_clicked()`].
+    pub interaction: Option<TextInteractionId>,
 }
 
 impl Default for TextStyling {
@@ -98,6 +146,11 @@ impl Default for TextStyling {
             text_color: GuiColor::WHITE,
             drop_shadow_color: GuiColor::INVISIBLE,
             bold: false,
+            italic: false,
+            underlined: false,
+            strikethrough: false,
+            obfuscated: false,
+            interaction: None,
         }
     }
 }
@@ -111,28 +164,51 @@ pub struct StyledText {
 impl std::fmt::Display for StyledText {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for &((start, end), styling) in self.sections.iter() {
-            use color_eyre::owo_colors::{DynColors, OwoColorize};
+            use color_eyre::owo_colors::{DynColors, OwoColorize, Style};
             let color = styling.text_color * 255.0;
             let shadow_color = styling.drop_shadow_color * 255.0;
             let raw_text_slice = &self.raw_text[start..end];
-            let text =
-                raw_text_slice.color(DynColors::Rgb(color.r as u8, color.g as u8, color.b as u8));
-            let shadow_dyn_color = DynColors::Rgb(
-                shadow_color.r as u8,
-                shadow_color.g as u8,
-                shadow_color.b as u8,
-            );
-            if styling.bold {
-                if shadow_color.is_visible() {
-                    write!(f, "{}", text.bold().on_color(shadow_dyn_color))?;
-                } else {
-                    write!(f, "{}", text.bold())?;
-                }
-            } else if shadow_color.is_visible() {
-                write!(f, "{}", text.on_color(shadow_dyn_color))?;
+            let obfuscated_text: String;
+            let raw_text_slice = if styling.obfuscated {
+                let mut rng = rand::thread_rng();
+                obfuscated_text = raw_text_slice
+                    .chars()
+                    .map(|character| {
+                        if character.is_whitespace() {
+                            character
+                        } else {
+                            rng.gen_range('!'..='~')
+                        }
+                    })
+                    .collect();
+                &obfuscated_text
             } else {
-                write!(f, "{}", text)?;
+                raw_text_slice
+            };
+
+            let mut style =
+                Style::new().color(DynColors::Rgb(color.r as u8, color.g as u8, color.b as u8));
+            if shadow_color.is_visible() {
+                style = style.on_color(DynColors::Rgb(
+                    shadow_color.r as u8,
+                    shadow_color.g as u8,
+                    shadow_color.b as u8,
+                ));
+            }
+            if styling.bold {
+                style = style.bold();
+            }
+            if styling.italic {
+                style = style.italic();
+            }
+            if styling.underlined {
+                style = style.underline();
             }
+            if styling.strikethrough {
+                style = style.strikethrough();
+            }
+
+            write!(f, "{}", raw_text_slice.style(style))?;
         }
         Ok(())
     }
@@ -149,6 +225,7 @@ impl StyledText {
     pub fn from_format_string(text: &str) -> Self {
         const FORMAT_CHAR: char = '§';
         const NEGATE_CHAR: char = '!';
+        const HEX_CHAR: char = '#';
 
         let mut sections = Vec::<((usize, usize), TextStyling)>::new();
         let mut current_section: Option<(usize, usize)> = None;
@@ -156,7 +233,13 @@ impl StyledText {
 
         let mut format_expected = false;
         let mut negated = false;
+        // used to skip over the digits of hex color codes
+        let mut skip_until_byte = 0;
         'char_loop: for (byte_index, character) in text.char_indices() {
+            if byte_index < skip_until_byte {
+                continue 'char_loop;
+            }
+
             let next_byte_index = byte_index + character.len_utf8();
             let at_end = next_byte_index >= text.len();
             if format_expected {
@@ -192,6 +275,33 @@ impl StyledText {
                             current_styling.drop_shadow_color = current_styling.text_color.shadow();
                         }
                     }
+                    // hex color, e.g. §#FF8800 or §#FF880080
+                    (HEX_CHAR, false) => {
+                        let digit_count = text[next_byte_index..]
+                            .chars()
+                            .take(8)
+                            .take_while(|c| c.is_ascii_hexdigit())
+                            .count();
+                        let digit_count = match digit_count {
+                            8 => 8,
+                            6 | 7 => 6,
+                            _ => 0,
+                        };
+
+                        match GuiColor::from_hex(
+                            &text[next_byte_index..next_byte_index + digit_count],
+                        ) {
+                            Some(color) => {
+                                current_styling.text_color = color;
+                                if current_styling.drop_shadow_color.is_visible() {
+                                    current_styling.drop_shadow_color =
+                                        current_styling.text_color.shadow();
+                                }
+                                skip_until_byte = next_byte_index + digit_count;
+                            }
+                            None => is_valid = false,
+                        }
+                    }
                     // reset
                     ('r', false) => {
                         current_styling = TextStyling::default();
@@ -208,6 +318,22 @@ impl StyledText {
                     ('l', negated) => {
                         current_styling.bold = !negated;
                     }
+                    // strikethrough
+                    ('m', negated) => {
+                        current_styling.strikethrough = !negated;
+                    }
+                    // underline
+                    ('n', negated) => {
+                        current_styling.underlined = !negated;
+                    }
+                    // italic
+                    ('o', negated) => {
+                        current_styling.italic = !negated;
+                    }
+                    // obfuscated (minecraft uses §k for this, but that's already taken by drop shadows)
+                    ('j', negated) => {
+                        current_styling.obfuscated = !negated;
+                    }
                     _ => {
                         is_valid = false;
                     }
@@ -244,6 +370,57 @@ impl StyledText {
         }
     }
 
+    /// Makes every section of this text interactable with the given id.
+    pub fn with_interaction(mut self, id: impl Into<TextInteractionId>) -> Self {
+        let id = id.into();
+        for (_, styling) in self.sections.iter_mut() {
+            styling.interaction = Some(id);
+        }
+        self
+    }
+
+    /// Makes the part of this text in `range` (byte indices into [`StyledText::raw_text`]) interactable with the given
+    /// id, splitting sections where it starts and ends.
+    pub fn with_interaction_in(
+        mut self,
+        range: Range<usize>,
+        id: impl Into<TextInteractionId>,
+    ) -> Self {
+        let id = id.into();
+        let mut sections = Vec::with_capacity(self.sections.len() + 2);
+        for ((start, end), styling) in self.sections {
+            let (inner_start, inner_end) = (start.max(range.start), end.min(range.end));
+            if inner_start >= inner_end {
+                sections.push(((start, end), styling));
+                continue;
+            }
+            if start < inner_start {
+                sections.push(((start, inner_start), styling));
+            }
+            sections.push((
+                (inner_start, inner_end),
+                TextStyling {
+                    interaction: Some(id),
+                    ..styling
+                },
+            ));
+            if inner_end < end {
+                sections.push(((inner_end, end), styling));
+            }
+        }
+        self.sections = sections;
+        self
+    }
+
+    /// Multiplies the alpha of every section's text and drop shadow color, for fading text in and out.
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        for (_, styling) in self.sections.iter_mut() {
+            styling.text_color.a *= opacity;
+            styling.drop_shadow_color.a *= opacity;
+        }
+        self
+    }
+
     pub fn extend(&mut self, other: &StyledText) {
         let index_offset = self.raw_text.len();
         self.raw_text.push_str(&other.raw_text);
@@ -262,12 +439,67 @@ pub struct RenderChar {
     pub styling: TextStyling,
 }
 
+impl RenderChar {
+    /// Where the visible pixels of this character start on its line.
+    pub fn start(&self) -> f32 {
+        self.offset + FONT_CHAR_DATA[self.ibm_code as usize].offset
+    }
+
+    /// The horizontal space taken up by this character, including the spacing after it.
+    pub fn advance(&self) -> f32 {
+        FONT_CHAR_DATA[self.ibm_code as usize].width
+            + FONT_CHAR_PIXEL_PORTION
+            + if self.styling.bold {
+                FONT_CHAR_PIXEL_PORTION
+            } else {
+                0.0
+            }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct RenderLine {
     pub chars: Vec<RenderChar>,
     pub total_width: f32,
 }
 
+impl RenderLine {
+    /// Removes characters from the end of the line until `...` fits within `max_width`, then appends it.
+    pub fn truncate_with_ellipsis(&mut self, max_width: f32) {
+        let dot = RenderChar {
+            ibm_code: b'.',
+            offset: 0.0,
+            styling: self
+                .chars
+                .last()
+                .map(|render_char| render_char.styling)
+                .unwrap_or_default(),
+        };
+        let ellipsis_width = dot.advance() * 3.0;
+
+        while let Some(last_char) = self.chars.last() {
+            if last_char.start() + last_char.advance() + ellipsis_width <= max_width {
+                break;
+            }
+            self.chars.pop();
+        }
+
+        let mut cursor = self
+            .chars
+            .last()
+            .map(|render_char| render_char.start() + render_char.advance())
+            .unwrap_or(0.0);
+        for _ in 0..3 {
+            self.chars.push(RenderChar {
+                offset: cursor - FONT_CHAR_DATA[dot.ibm_code as usize].offset,
+                ..dot
+            });
+            cursor += dot.advance();
+        }
+        self.total_width = cursor;
+    }
+}
+
 impl Default for RenderLine {
     fn default() -> Self {
         Self {
@@ -398,6 +630,18 @@ pub enum TextBackgroundType {
     TexturedBoundingBox(OrientedSection),
 }
 
+/// What a [`TextLabel`] does with lines that don't fit within its bounds.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TextOverflow {
+    #[default]
+    /// Lines past the bottom of the label are left out.
+    Clip,
+    /// Lines past the bottom of the label are left out, and the last visible line ends with `...`.
+    Ellipsis,
+    /// Lines past the bottom of the label are left out, and the last visible line fades out towards its end.
+    Fade,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct TextLabel {
     /// The positioning and sizing of the label.
@@ -414,6 +658,8 @@ pub struct TextLabel {
     pub background_color: GuiColor,
     /// The behavior of the background.
     pub background_type: TextBackgroundType,
+    /// The behavior of text that doesn't fit.
+    pub overflow: TextOverflow,
 }
 
 impl Default for TextLabel {
@@ -425,6 +671,7 @@ impl Default for TextLabel {
             text_alignment: Self::ALIGN_TOP_LEFT,
             background_color: GuiColor::INVISIBLE,
             background_type: Default::default(),
+            overflow: Default::default(),
         }
     }
 }
@@ -472,13 +719,18 @@ impl GuiElement for TextLabel {
         let bounds = (absolute_size / char_pixel_height)
             - vec2(FONT_CHAR_PIXEL_PORTION, FONT_CHAR_PIXEL_PORTION);
         let max_lines = (bounds.y / Self::LINE_HEIGHT + 0.01) as usize;
-        let render_data = TextRenderData::generate(&self.text, bounds.x);
+        let mut render_data = TextRenderData::generate(&self.text, bounds.x);
 
         let line_count = render_data.lines.len().min(max_lines);
+        let overflowed = line_count < render_data.lines.len();
+        if overflowed && line_count > 0 && self.overflow == TextOverflow::Ellipsis {
+            render_data.lines[line_count - 1].truncate_with_ellipsis(bounds.x);
+        }
+
         let total_height = Self::LINE_HEIGHT * line_count as f32;
         let lines_start_y = (bounds.y - total_height) * self.text_alignment.y;
 
-        let font_texture_section = texture_provider.get_section("font");
+        let font_texture_section = texture_provider.get_section_by_id(*FONT_TEXTURE);
         let white_texture_section = context.white();
 
         // background
@@ -497,6 +749,7 @@ impl GuiElement for TextLabel {
                         absolute_size,
                         section,
                         color: self.background_color,
+                        skew: 0.0,
                     });
                 }
                 TextBackgroundType::BoundingBox | TextBackgroundType::TexturedBoundingBox(..) => {
@@ -529,6 +782,7 @@ impl GuiElement for TextLabel {
                             ),
                             section,
                             color: self.background_color,
+                            skew: 0.0,
                         });
                     }
                 }
@@ -555,11 +809,67 @@ impl GuiElement for TextLabel {
                     ) * char_pixel_height,
                     section: white_texture_section,
                     color: self.background_color,
+                    skew: 0.0,
                 })
             }
 
+            let fade_line = overflowed
+                && line_index + 1 == line_count
+                && self.overflow == TextOverflow::Fade
+                && line.total_width > 0.0;
+
+            // contest hover for every interactable run of characters on this line
+            let mut interaction_run: Option<(TextInteractionId, f32, f32)> = None;
+            let line_chars = line.chars.iter().map(Some).chain(std::iter::once(None));
+            for render_char in line_chars {
+                let interaction =
+                    render_char.and_then(|render_char| render_char.styling.interaction);
+                if let Some((id, run_start, run_end)) = interaction_run {
+                    if interaction != Some(id) {
+                        let top_left = context.offset
+                            + absolute_top_left
+                            + vec2(start_x + run_start, start_y) * char_pixel_height;
+                        let size = vec2(run_end - run_start, Self::LINE_HEIGHT) * char_pixel_height;
+                        context
+                            .input_controller
+                            .contest_text_span_hover(id, bbox!(top_left, top_left + size));
+                        interaction_run = None;
+                    }
+                }
+                if let (Some(id), Some(render_char)) = (interaction, render_char) {
+                    let run_start = interaction_run
+                        .map(|(_, run_start, _)| run_start)
+                        .unwrap_or(render_char.start());
+                    interaction_run =
+                        Some((id, run_start, render_char.start() + render_char.advance()));
+                }
+            }
+
             for render_char in line.chars.iter() {
-                let char_data = FONT_CHAR_DATA[render_char.ibm_code as usize];
+                let mut render_char = *render_char;
+                if let Some(id) = render_char.styling.interaction {
+                    if context.input_controller.text_span_is_hovered(id) {
+                        render_char.styling.underlined = true;
+                    }
+                }
+                if fade_line {
+                    // fully opaque for the first half of the line, then linearly fades out
+                    let alpha =
+                        ((1.0 - render_char.start() / line.total_width) * 2.0).clamp(0.0, 1.0);
+                    let styling = &mut render_char.styling;
+                    styling.text_color =
+                        styling.text_color.with_alpha(styling.text_color.a * alpha);
+                    styling.drop_shadow_color = styling
+                        .drop_shadow_color
+                        .with_alpha(styling.drop_shadow_color.a * alpha);
+                }
+
+                let ibm_code = if render_char.styling.obfuscated {
+                    obfuscate_char(render_char.ibm_code)
+                } else {
+                    render_char.ibm_code
+                };
+                let char_data = FONT_CHAR_DATA[ibm_code as usize];
 
                 let has_shadow = render_char.styling.drop_shadow_color.is_visible();
                 let extra_offset = if has_shadow {
@@ -575,6 +885,11 @@ impl GuiElement for TextLabel {
                     absolute_size: vec2(char_pixel_height, char_pixel_height),
                     section: font_texture_section.local_uv(char_data.uv),
                     color: render_char.styling.text_color,
+                    skew: if render_char.styling.italic {
+                        ITALIC_SKEW
+                    } else {
+                        0.0
+                    },
                 };
 
                 if has_shadow {
@@ -597,6 +912,47 @@ impl GuiElement for TextLabel {
                     }
                 }
 
+                let bar_offsets = [
+                    (render_char.styling.underlined, 1.0),
+                    (
+                        render_char.styling.strikethrough,
+                        FONT_CHAR_PIXEL_PORTION * 3.0,
+                    ),
+                ];
+                for (_, bar_y) in bar_offsets.into_iter().filter(|&(enabled, _)| enabled) {
+                    if char_data.width <= 0.0 {
+                        break;
+                    }
+
+                    let bar_width = char_data.width
+                        + FONT_CHAR_PIXEL_PORTION
+                        + if render_char.styling.bold {
+                            FONT_CHAR_PIXEL_PORTION
+                        } else {
+                            0.0
+                        };
+                    let bar = GuiPrimitive {
+                        absolute_position: base_primitive.absolute_position
+                            + vec2(char_data.offset, bar_y) * char_pixel_height,
+                        absolute_size: vec2(bar_width, FONT_CHAR_PIXEL_PORTION) * char_pixel_height,
+                        section: white_texture_section,
+                        color: render_char.styling.text_color,
+                        skew: 0.0,
+                    };
+
+                    if has_shadow {
+                        primitives.push(GuiPrimitive {
+                            absolute_position: bar.absolute_position
+                                + vec2(char_pixel_height, char_pixel_height)
+                                    * FONT_CHAR_PIXEL_PORTION,
+                            color: render_char.styling.drop_shadow_color,
+
+                            ..bar
+                        });
+                    }
+                    primitives.push(bar);
+                }
+
                 if render_char.styling.text_color.is_visible() {
                     primitives.push(base_primitive);
                     if render_char.styling.bold {
@@ -26,6 +26,7 @@ impl GuiElement for TextureFrame {
             absolute_size: self.transform.absolute_size(frame),
             section: self.section,
             color: self.color,
+            skew: 0.0,
         }]
     }
 }
@@ -0,0 +1,393 @@
+use crate::{
+    app_state::{create_pipeline_2d, TextureProvider},
+    cli::Cli,
+    graphics::{
+        graphics_controller::{GraphicsController, Pipeline},
+        vertex::Vertex2D,
+    },
+    gui::{
+        builder::GuiBuilder,
+        color::GuiColor,
+        component::{menu::TextButton, slider::Slider},
+        element::GuiContext,
+        progress_bar::ProgressBar,
+        text::{StyledText, TextBackgroundType, TextLabel, TextOverflow},
+        transform::{GuiTransform, UDim2},
+    },
+    shared::{
+        crash::update_crash_context, indexed_container::IndexedVertices, input::InputController,
+    },
+};
+use anyhow::{bail, Result};
+use image::RgbaImage;
+use log::{error, info, warn};
+use std::path::Path;
+use winit::dpi::PhysicalSize;
+
+/// How far apart a channel of the same pixel can be before the pixel counts as different. Adapters don't all
+/// rasterize and blend exactly the same.
+pub const CHANNEL_TOLERANCE: u8 = 8;
+/// The portion of pixels that can differ before a snapshot fails.
+pub const MAX_DIFFERING_PORTION: f64 = 0.002;
+
+/// Reading textures back needs rows that are a multiple of 256 bytes, so snapshots are a multiple of this wide.
+pub const SNAPSHOT_WIDTH_ALIGNMENT: u32 = 64;
+
+/// Draws bits of GUI into offscreen render targets, with no window around.
+#[derive(Debug)]
+pub struct SnapshotRenderer {
+    graphics_controller: GraphicsController,
+    texture_provider: TextureProvider,
+    pipeline_2d: Pipeline<Vertex2D>,
+    gui_vertices: IndexedVertices<Vertex2D>,
+    input_controller: InputController,
+}
+
+impl SnapshotRenderer {
+    pub fn new(adapter_name: Option<&str>) -> Result<Self> {
+        let graphics_controller =
+            GraphicsController::new_offscreen(PhysicalSize::new(256, 256), adapter_name)?;
+        let texture_provider =
+            TextureProvider::with_texture_images(graphics_controller.handle_arc());
+        let pipeline_2d = create_pipeline_2d(&graphics_controller);
+        let gui_vertices = IndexedVertices::new(&graphics_controller);
+
+        Ok(Self {
+            graphics_controller,
+            texture_provider,
+            pipeline_2d,
+            gui_vertices,
+            input_controller: InputController::new(),
+        })
+    }
+
+    /// Builds a GUI into a `width` by `height` target (cleared to black) and reads it back. The input controller
+    /// never gets any input, so nothing's hovered or pressed.
+    pub fn render(
+        &mut self,
+        name: &'static str,
+        width: u32,
+        height: u32,
+        build: impl FnOnce(&mut GuiBuilder),
+    ) -> RgbaImage {
+        assert!(
+            width.is_multiple_of(SNAPSHOT_WIDTH_ALIGNMENT),
+            "Snapshot width must be a multiple of {}",
+            SNAPSHOT_WIDTH_ALIGNMENT
+        );
+
+        let (_, target) = self.graphics_controller.render_target(name, width, height);
+        target.clear();
+
+        let mut gui_builder = GuiContext::new(
+            target.frame(),
+            &self.texture_provider,
+            &mut self.input_controller,
+        )
+        .builder();
+        build(&mut gui_builder);
+
+        self.gui_vertices.replace_contents(gui_builder.finish());
+        self.graphics_controller.render(
+            &target,
+            &self.pipeline_2d,
+            self.gui_vertices.as_pipeline_buffers(),
+            [self.texture_provider.bind_group()],
+        );
+
+        self.graphics_controller
+            .handle()
+            .read_texture_to_image(&target.texture().inner_texture)
+    }
+}
+
+/// How a snapshot compares to its golden image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SnapshotDifference {
+    /// Pixels with a channel more than [`CHANNEL_TOLERANCE`] off.
+    pub differing_pixels: u32,
+    pub total_pixels: u32,
+    /// The furthest apart any one channel got.
+    pub max_channel_difference: u8,
+}
+
+impl SnapshotDifference {
+    /// `None` if the images aren't the same size, in which case there's no comparing them.
+    pub fn between(expected: &RgbaImage, actual: &RgbaImage) -> Option<Self> {
+        if expected.dimensions() != actual.dimensions() {
+            return None;
+        }
+
+        let mut differing_pixels = 0;
+        let mut max_channel_difference = 0;
+        for (expected, actual) in expected.pixels().zip(actual.pixels()) {
+            let difference = expected
+                .0
+                .iter()
+                .zip(actual.0.iter())
+                .map(|(a, b)| a.abs_diff(*b))
+                .max()
+                .unwrap_or(0);
+            max_channel_difference = max_channel_difference.max(difference);
+            if difference > CHANNEL_TOLERANCE {
+                differing_pixels += 1;
+            }
+        }
+
+        Some(Self {
+            differing_pixels,
+            total_pixels: expected.width() * expected.height(),
+            max_channel_difference,
+        })
+    }
+
+    pub fn passed(&self) -> bool {
+        self.differing_pixels as f64 <= self.total_pixels as f64 * MAX_DIFFERING_PORTION
+    }
+}
+
+/// A piece of GUI drawn the same way every time.
+pub struct SnapshotCase {
+    pub name: &'static str,
+    pub width: u32,
+    pub height: u32,
+    pub build: fn(&mut GuiBuilder),
+}
+
+fn label(position: UDim2, size: UDim2, text: &str) -> TextLabel {
+    TextLabel {
+        transform: GuiTransform {
+            position,
+            size,
+            ..Default::default()
+        },
+        text: StyledText::from_format_string(text),
+        ..Default::default()
+    }
+}
+
+pub const SNAPSHOT_CASES: &[SnapshotCase] = &[
+    SnapshotCase {
+        name: "text_label_alignment",
+        width: 256,
+        height: 128,
+        build: |builder| {
+            for alignment in [
+                TextLabel::ALIGN_TOP_LEFT,
+                TextLabel::ALIGN_MIDDLE_CENTER,
+                TextLabel::ALIGN_BOTTOM_RIGHT,
+            ] {
+                builder.element(TextLabel {
+                    text_alignment: alignment,
+                    background_color: GuiColor::DARK_BLUE.with_alpha(0.5),
+                    ..label(
+                        UDim2::from_scale(0.0, 0.0),
+                        UDim2::from_scale(1.0, 1.0),
+                        "Worldline",
+                    )
+                });
+            }
+        },
+    },
+    SnapshotCase {
+        name: "text_label_styling",
+        width: 256,
+        height: 128,
+        build: |builder| {
+            builder.element(TextLabel {
+                char_pixel_height: 16.0,
+                background_color: GuiColor::BLACK.with_alpha(0.75),
+                background_type: TextBackgroundType::BoundingBoxPerLine,
+                ..label(
+                    UDim2::new((0.0, 8.0), (0.0, 8.0)),
+                    UDim2::new((1.0, -16.0), (1.0, -16.0)),
+                    "§cred §agreen §9blue\n§rplain §eyellow\n§6v = 0.87c",
+                )
+            });
+        },
+    },
+    SnapshotCase {
+        name: "text_label_overflow",
+        width: 256,
+        height: 96,
+        build: |builder| {
+            let text = "A label with more text than it has room for, so everything past the second line gets cut off one way or another";
+            for (row, overflow) in [
+                TextOverflow::Clip,
+                TextOverflow::Ellipsis,
+                TextOverflow::Fade,
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                builder.element(TextLabel {
+                    char_pixel_height: 10.0,
+                    overflow,
+                    background_color: GuiColor::DARK_GRAY,
+                    ..label(
+                        UDim2::new((0.0, 0.0), (0.0, row as f32 * 32.0)),
+                        UDim2::new((1.0, 0.0), (0.0, 28.0)),
+                        text,
+                    )
+                });
+            }
+        },
+    },
+    SnapshotCase {
+        name: "progress_bar",
+        width: 128,
+        height: 64,
+        build: |builder| {
+            for (row, progress) in [0.0, 0.5, 1.0].into_iter().enumerate() {
+                builder.element(ProgressBar {
+                    transform: GuiTransform {
+                        position: UDim2::new((0.0, 8.0), (0.0, 4.0 + row as f32 * 20.0)),
+                        size: UDim2::new((1.0, -16.0), (0.0, 16.0)),
+                        ..Default::default()
+                    },
+                    progress,
+                    fill_color: GuiColor::GREEN,
+                    ..Default::default()
+                });
+            }
+        },
+    },
+    SnapshotCase {
+        name: "text_button",
+        width: 128,
+        height: 64,
+        build: |builder| {
+            let mut button = TextButton {
+                text: StyledText::from_format_string("Resume"),
+                ..Default::default()
+            };
+            button.render(
+                builder,
+                TextLabel {
+                    text_alignment: TextLabel::ALIGN_MIDDLE_CENTER,
+                    ..label(
+                        UDim2::new((0.0, 8.0), (0.0, 8.0)),
+                        UDim2::new((1.0, -16.0), (1.0, -16.0)),
+                        "",
+                    )
+                },
+            );
+        },
+    },
+    SnapshotCase {
+        name: "slider",
+        width: 256,
+        height: 64,
+        build: |builder| {
+            Slider::new().render(
+                builder,
+                GuiTransform {
+                    position: UDim2::new((0.0, 8.0), (0.5, -12.0)),
+                    size: UDim2::new((1.0, -16.0), (0.0, 24.0)),
+                    ..Default::default()
+                },
+                0.0,
+                10.0,
+                2.5,
+                "Time scale: 2.5",
+            );
+        },
+    },
+];
+
+/// Renders every [`SNAPSHOT_CASES`] case and compares it to `<name>.png` in `directory`. Anything that doesn't match
+/// gets written next to it as `<name>.actual.png`, to look at. With `--update-snapshots`, the golden images get
+/// (re)written instead.
+pub fn run_gui_snapshots(cli: &Cli, directory: &Path) -> Result<()> {
+    let update = cli.update_snapshots;
+    update_crash_context(|context| context.scenario = Some("gui snapshots".to_owned()));
+
+    let mut renderer = SnapshotRenderer::new(cli.adapter.as_deref())?;
+    std::fs::create_dir_all(directory)?;
+
+    let mut failed = 0;
+    for case in SNAPSHOT_CASES {
+        let actual = renderer.render(case.name, case.width, case.height, case.build);
+        let golden_path = directory.join(format!("{}.png", case.name));
+        let actual_path = directory.join(format!("{}.actual.png", case.name));
+
+        if update {
+            actual.save(&golden_path)?;
+            info!("{}: wrote {}", case.name, golden_path.display());
+            continue;
+        }
+
+        let expected = match image::open(&golden_path) {
+            Ok(expected) => expected.into_rgba8(),
+            Err(err) => {
+                error!(
+                    "{}: couldn't load {} ({}), FAILED. Run with --update-snapshots to make it",
+                    case.name,
+                    golden_path.display(),
+                    err
+                );
+                actual.save(&actual_path)?;
+                failed += 1;
+                continue;
+            }
+        };
+
+        match SnapshotDifference::between(&expected, &actual) {
+            Some(difference) if difference.passed() => {
+                info!(
+                    "{}: {} of {} pixels differ (up to {} per channel)",
+                    case.name,
+                    difference.differing_pixels,
+                    difference.total_pixels,
+                    difference.max_channel_difference
+                );
+                // a stale one from an earlier failure would just be confusing
+                if actual_path.exists() {
+                    std::fs::remove_file(&actual_path)?;
+                }
+            }
+            Some(difference) => {
+                error!(
+                    "{}: {} of {} pixels differ (up to {} per channel), FAILED. See {}",
+                    case.name,
+                    difference.differing_pixels,
+                    difference.total_pixels,
+                    difference.max_channel_difference,
+                    actual_path.display()
+                );
+                actual.save(&actual_path)?;
+                failed += 1;
+            }
+            None => {
+                error!(
+                    "{}: expected {}x{}, got {}x{}, FAILED",
+                    case.name,
+                    expected.width(),
+                    expected.height(),
+                    actual.width(),
+                    actual.height()
+                );
+                actual.save(&actual_path)?;
+                failed += 1;
+            }
+        }
+    }
+
+    if update {
+        warn!(
+            "Updated {} golden images in {}",
+            SNAPSHOT_CASES.len(),
+            directory.display()
+        );
+        return Ok(());
+    }
+    if failed > 0 {
+        bail!(
+            "{} of {} GUI snapshots failed",
+            failed,
+            SNAPSHOT_CASES.len()
+        );
+    }
+    info!("All {} GUI snapshots matched", SNAPSHOT_CASES.len());
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use crate::{
+    app_state::scenario::load_scenario_or_default, cli::Cli, shared::crash::update_crash_context,
+    special::worldline::PHYS_TIME_STEP,
+};
+use anyhow::Result;
+use log::info;
+use std::time::Instant;
+
+/// Steps the scenario's universe as fast as it'll go for [`Cli::headless_duration`] seconds of simulated time,
+/// with no window or GPU involved.
+pub fn run_headless(cli: &Cli) -> Result<()> {
+    let scenario = load_scenario_or_default(cli.scenario.as_deref());
+    let mut universe = scenario.build().universe;
+    update_crash_context(|context| {
+        context.scenario = Some(scenario.name.clone());
+        context.entity_count = universe.entities.len();
+    });
+    info!(
+        "Simulating '{}' ({} entities) for {}s",
+        scenario.name,
+        universe.entities.len(),
+        cli.headless_duration
+    );
+
+    let start = Instant::now();
+    let tick_count = (cli.headless_duration / PHYS_TIME_STEP).ceil() as u64;
+    for _ in 0..tick_count {
+        universe.step(PHYS_TIME_STEP);
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+
+    let user_event = universe.user_event_now();
+    info!(
+        "Ran {} ticks in {:.3}s ({:.0} ticks/s), ending at coordinate time {:.3}s and user proper time {:.3}s",
+        tick_count,
+        elapsed,
+        tick_count as f64 / elapsed.max(f64::EPSILON),
+        universe.time,
+        user_event.proper_time
+    );
+
+    Ok(())
+}
@@ -0,0 +1,184 @@
+use super::protocol::{
+    read_message, write_message, ClientMessage, PlayerInfo, ServerMessage, DEFAULT_PORT,
+    MAX_SERVER_MESSAGE_BYTES, PROTOCOL_VERSION,
+};
+use crate::{
+    app_state::physics::{PhysicsThread, UniverseEdit},
+    shared::numerical_integration::Integrator,
+    special::{universe::EntityId, worldline::WorldlineEvent},
+};
+use anyhow::{Context, Result};
+use log::warn;
+use std::{
+    io::BufReader,
+    net::{Shutdown, TcpStream, ToSocketAddrs},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::Duration,
+};
+
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug)]
+pub enum NetEvent {
+    Message(ServerMessage),
+    Disconnected(String),
+}
+
+/// A connection to a [`Server`](super::server::Server). Messages are read on a background thread, and picked up
+/// with [`NetClient::poll()`].
+#[derive(Debug)]
+pub struct NetClient {
+    /// As it was given, e.g. "localhost:7905".
+    pub address: String,
+    pub name: String,
+    stream: TcpStream,
+    receiver: Receiver<NetEvent>,
+    connected: bool,
+
+    /// The entity the server gave us to fly, once it's welcomed us.
+    pub entity_id: Option<EntityId>,
+    pub scenario: String,
+    /// Everyone on the server, including us. Kept up to date by the app as messages come in.
+    pub players: Vec<PlayerInfo>,
+}
+
+impl NetClient {
+    /// Connects and says hello. Without a port in `address`, the [`DEFAULT_PORT`] is used.
+    pub fn connect(address: &str, name: &str) -> Result<Self> {
+        let socket_address = if address.contains(':') {
+            address.to_socket_addrs()
+        } else {
+            (address, DEFAULT_PORT).to_socket_addrs()
+        }?
+        .next()
+        .context("the address didn't resolve to anything")?;
+
+        let mut stream = TcpStream::connect_timeout(&socket_address, CONNECT_TIMEOUT)?;
+        stream.set_nodelay(true)?;
+        write_message(
+            &mut stream,
+            &ClientMessage::Hello {
+                version: PROTOCOL_VERSION,
+                name: name.to_owned(),
+            },
+        )?;
+
+        let (sender, receiver) = mpsc::channel();
+        let mut reader = BufReader::new(stream.try_clone()?);
+        thread::Builder::new()
+            .name("net client".into())
+            .spawn(move || loop {
+                let event =
+                    match read_message::<ServerMessage>(&mut reader, MAX_SERVER_MESSAGE_BYTES) {
+                        Ok(Some(message)) => NetEvent::Message(message),
+                        Ok(None) => {
+                            NetEvent::Disconnected("the server closed the connection".into())
+                        }
+                        Err(error) => NetEvent::Disconnected(error.to_string()),
+                    };
+                let disconnected = matches!(event, NetEvent::Disconnected(_));
+                if sender.send(event).is_err() || disconnected {
+                    return;
+                }
+            })?;
+
+        Ok(Self {
+            address: address.to_owned(),
+            name: name.to_owned(),
+            stream,
+            receiver,
+            connected: true,
+
+            entity_id: None,
+            scenario: String::new(),
+            players: Vec::new(),
+        })
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    pub fn player_name(&self, entity_id: EntityId) -> Option<&str> {
+        self.players
+            .iter()
+            .find(|player| player.entity_id == entity_id)
+            .map(|player| player.name.as_str())
+    }
+
+    pub fn send(&mut self, message: &ClientMessage) {
+        if !self.connected {
+            return;
+        }
+        if let Err(error) = write_message(&mut self.stream, message) {
+            warn!("Couldn't send to the server: {}", error);
+            self.disconnect();
+        }
+    }
+
+    pub fn disconnect(&mut self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+
+    /// Everything that's come in since last time.
+    pub fn poll(&mut self) -> Vec<NetEvent> {
+        let mut events = Vec::new();
+        loop {
+            match self.receiver.try_recv() {
+                Ok(event) => {
+                    if let NetEvent::Disconnected(_) = event {
+                        self.connected = false;
+                    }
+                    events.push(event);
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.connected = false;
+                    break;
+                }
+            }
+        }
+        events
+    }
+
+    /// Sends out whatever the user just did to their own worldline, going by the physics thread's edit log.
+    pub fn send_own_events(&mut self, physics: &mut PhysicsThread) {
+        let edits = physics.take_edit_log();
+        let Some(entity_id) = self.entity_id else {
+            return;
+        };
+        let Some(entity) = physics.universe().entities.get(&entity_id) else {
+            return;
+        };
+
+        let events: Vec<WorldlineEvent> = edits
+            .into_iter()
+            .filter_map(|edit| match edit {
+                UniverseEdit::InsertEvent {
+                    entity_id: edited_id,
+                    coord_time,
+                    kind,
+                } if edited_id == entity_id => {
+                    // the same event the edit made, worked out again from the edited worldline
+                    Some(WorldlineEvent {
+                        kind,
+                        integrator: Integrator::default(),
+                        ..entity.worldline.get_event_at_time(coord_time)
+                    })
+                }
+                _ => None,
+            })
+            .collect();
+        for event in events {
+            self.send(&ClientMessage::Event(event));
+        }
+    }
+}
+
+impl Drop for NetClient {
+    fn drop(&mut self) {
+        // the reader thread has its own handle to the socket, which would otherwise keep it open
+        self.disconnect();
+    }
+}
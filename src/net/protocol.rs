@@ -0,0 +1,110 @@
+use crate::special::{
+    universe::{Entity, EntityId, Universe},
+    worldline::WorldlineEvent,
+};
+use anyhow::{bail, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::io::{BufRead, Read, Write};
+
+/// Bumped whenever a change would stop older clients and servers from understanding each other.
+pub const PROTOCOL_VERSION: u32 = 1;
+pub const DEFAULT_PORT: u16 = 7905;
+/// In chars. Longer names get cut off.
+pub const MAX_NAME_LENGTH: usize = 24;
+/// In chars. Longer messages get cut off.
+pub const MAX_CHAT_LENGTH: usize = 256;
+/// In bytes. Anything a client sends is a lot smaller than this, so a longer line means something's wrong with them.
+pub const MAX_CLIENT_MESSAGE_BYTES: usize = 64 * 1024;
+/// In bytes. Has to fit a whole [`ServerMessage::Welcome`], universe and all.
+pub const MAX_SERVER_MESSAGE_BYTES: usize = 256 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerInfo {
+    /// The entity the player flies.
+    pub entity_id: EntityId,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ClientMessage {
+    /// Has to be the first thing sent. The server answers with [`ServerMessage::Welcome`] or
+    /// [`ServerMessage::Rejected`].
+    Hello {
+        version: u32,
+        name: String,
+    },
+    /// A new event on the player's own worldline, exactly as it was worked out on their end. Everything after it
+    /// gets replaced, like with [`Worldline::insert_event()`](crate::special::worldline::Worldline::insert_event).
+    Event(WorldlineEvent),
+    Chat(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ServerMessage {
+    /// The whole universe as the server has it, with [`Universe::user_entity_id`] already set to the player's
+    /// own entity.
+    Welcome {
+        entity_id: EntityId,
+        scenario: String,
+        universe: Box<Universe>,
+        /// Including the player being welcomed.
+        players: Vec<PlayerInfo>,
+    },
+    /// The connection gets closed right after.
+    Rejected(String),
+    PlayerJoined {
+        player: PlayerInfo,
+        entity: Box<Entity>,
+    },
+    /// Their entity goes away along with them.
+    PlayerLeft(EntityId),
+    /// Another player's [`ClientMessage::Event`], passed along.
+    Event {
+        entity_id: EntityId,
+        event: WorldlineEvent,
+    },
+    Chat {
+        from: String,
+        text: String,
+    },
+}
+
+/// Messages are sent as JSON, one per line.
+pub fn write_message(writer: &mut impl Write, message: &impl Serialize) -> Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    writer.write_all(&line)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads the next message, or `None` once the other end has hung up. Fails without reading any further if the line
+/// goes on for more than `max_bytes`, rather than holding onto however much the other end sends.
+pub fn read_message<T: DeserializeOwned>(
+    reader: &mut impl BufRead,
+    max_bytes: usize,
+) -> Result<Option<T>> {
+    let mut line = Vec::new();
+    if reader
+        .by_ref()
+        .take(max_bytes as u64 + 1)
+        .read_until(b'\n', &mut line)?
+        == 0
+    {
+        return Ok(None);
+    }
+    if line.len() > max_bytes {
+        bail!("a message went over {} bytes", max_bytes);
+    }
+    Ok(Some(serde_json::from_slice(&line)?))
+}
+
+/// Trims a name or chat message, cuts it down to `max_chars`, and strips out formatting codes so nobody can pass
+/// their text off as something else.
+pub fn sanitize_text(text: &str, max_chars: usize) -> String {
+    text.trim()
+        .chars()
+        .filter(|&char| char != '§' && !char.is_control())
+        .take(max_chars)
+        .collect()
+}
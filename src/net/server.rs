@@ -0,0 +1,387 @@
+use super::protocol::{
+    read_message, sanitize_text, write_message, ClientMessage, PlayerInfo, ServerMessage,
+    MAX_CHAT_LENGTH, MAX_CLIENT_MESSAGE_BYTES, MAX_NAME_LENGTH, PROTOCOL_VERSION,
+};
+use crate::{
+    app_state::physics::UniverseEdit,
+    shared::interning::ModelId,
+    special::{
+        universe::{Entity, EntityId, Universe},
+        worldline::Worldline,
+    },
+};
+use anyhow::Result;
+use cgmath::{vec3, Matrix4, Vector4};
+use log::{info, warn};
+use std::{
+    collections::BTreeMap,
+    io::BufReader,
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How often the server's universe gets stepped when nothing else is going on.
+pub const SERVER_TICK: Duration = Duration::from_millis(50);
+/// Anyone who can't take a message in this long gets dropped, rather than holding everyone else up.
+pub const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+/// How far apart players are spawned, in light-seconds.
+pub const SPAWN_SPACING: f64 = 10.0;
+pub const PLAYER_COLORS: [[f32; 4]; 6] = [
+    [0.3, 0.8, 1.0, 1.0],
+    [1.0, 0.5, 0.3, 1.0],
+    [0.5, 1.0, 0.4, 1.0],
+    [1.0, 0.9, 0.3, 1.0],
+    [0.9, 0.4, 1.0, 1.0],
+    [1.0, 1.0, 1.0, 1.0],
+];
+
+type ConnectionId = u64;
+
+#[derive(Debug)]
+enum ServerEvent {
+    Connected(ConnectionId, TcpStream),
+    Message(ConnectionId, ClientMessage),
+    Disconnected(ConnectionId),
+}
+
+#[derive(Debug)]
+struct Connection {
+    stream: TcpStream,
+    address: SocketAddr,
+    /// Set once they've said hello.
+    player: Option<PlayerInfo>,
+}
+
+/// Keeps the one true copy of the universe, and passes worldline events between players.
+///
+/// Positions never go over the wire, only the events that players put on their own worldlines. Every client
+/// steps its own copy of the universe from those, and draws everyone else with the light delay from its own
+/// frame. Each client's coordinate time runs at its own pace (see [`Universe::step()`]), so an event can show up
+/// in someone's "past", but that just rewrites a stretch of worldline they haven't seen the light from yet.
+#[derive(Debug)]
+pub struct Server {
+    pub scenario: String,
+    pub universe: Universe,
+    /// Anyone past this many players gets turned away.
+    pub max_players: Option<usize>,
+    connections: BTreeMap<ConnectionId, Connection>,
+    /// How many players have joined so far, for spacing them out.
+    join_count: u32,
+}
+
+impl Server {
+    pub fn new(scenario: impl Into<String>, universe: Universe) -> Self {
+        Self {
+            scenario: scenario.into(),
+            universe,
+            max_players: None,
+            connections: BTreeMap::new(),
+            join_count: 0,
+        }
+    }
+
+    pub fn players(&self) -> impl Iterator<Item = &PlayerInfo> {
+        self.connections
+            .values()
+            .filter_map(|connection| connection.player.as_ref())
+    }
+
+    /// Sends a message to one connection, dropping it if that doesn't work.
+    fn send(&mut self, connection_id: ConnectionId, message: &ServerMessage) {
+        let Some(connection) = self.connections.get_mut(&connection_id) else {
+            return;
+        };
+        if let Err(error) = write_message(&mut connection.stream, message) {
+            warn!("Couldn't send to {}: {}", connection.address, error);
+            // the reader thread notices and reports the disconnect
+            let _ = connection.stream.shutdown(Shutdown::Both);
+        }
+    }
+
+    /// Tells a connection why it can't join, then hangs up on it.
+    fn reject(&mut self, connection_id: ConnectionId, reason: String) {
+        if let Some(connection) = self.connections.get(&connection_id) {
+            info!("Turned away {}: {}", connection.address, reason);
+        }
+        self.send(connection_id, &ServerMessage::Rejected(reason));
+        if let Some(connection) = self.connections.get(&connection_id) {
+            let _ = connection.stream.shutdown(Shutdown::Both);
+        }
+    }
+
+    /// Sends a message to everyone who's joined, except `skip`.
+    fn broadcast(&mut self, message: &ServerMessage, skip: Option<ConnectionId>) {
+        let connection_ids: Vec<ConnectionId> = self
+            .connections
+            .iter()
+            .filter(|(&connection_id, connection)| {
+                connection.player.is_some() && Some(connection_id) != skip
+            })
+            .map(|(&connection_id, _)| connection_id)
+            .collect();
+        for connection_id in connection_ids {
+            self.send(connection_id, message);
+        }
+    }
+
+    /// A new entity for a player, at rest next to the scenario's own user and spread out from everyone before.
+    fn spawn_player_entity(&mut self) -> (EntityId, Entity) {
+        let slot = self.join_count;
+        self.join_count += 1;
+
+        let offset = vec3((slot + 1) as f64 * SPAWN_SPACING, 0.0, 0.0);
+        let event = self.universe.user_event_now().comoving(offset);
+        let entity = Entity {
+            worldline: Worldline::new(event.frame),
+            model: Some(ModelId::intern("cube")),
+            model_matrix: Matrix4::from_scale(1.5),
+            model_color: Vector4::from(PLAYER_COLORS[slot as usize % PLAYER_COLORS.len()]),
+            ..Default::default()
+        };
+
+        let entity_id = EntityId::generate();
+        self.universe
+            .insert_entity_with_id(entity_id, entity.clone());
+        (entity_id, entity)
+    }
+
+    fn handle_message(&mut self, connection_id: ConnectionId, message: ClientMessage) {
+        let Some(connection) = self.connections.get(&connection_id) else {
+            return;
+        };
+        let player = connection.player.clone();
+
+        match (message, player) {
+            (ClientMessage::Hello { version, name }, None) => {
+                if version != PROTOCOL_VERSION {
+                    self.reject(
+                        connection_id,
+                        format!(
+                            "the server is on protocol version {PROTOCOL_VERSION}, you're on {version}"
+                        ),
+                    );
+                    return;
+                }
+                if self
+                    .max_players
+                    .is_some_and(|max_players| self.players().count() >= max_players)
+                {
+                    self.reject(connection_id, "the server is full".to_owned());
+                    return;
+                }
+
+                let name = match sanitize_text(&name, MAX_NAME_LENGTH) {
+                    name if name.is_empty() => format!("Player {}", self.join_count + 1),
+                    name => name,
+                };
+                let (entity_id, entity) = self.spawn_player_entity();
+                let player = PlayerInfo { entity_id, name };
+                info!(
+                    "{} joined from {}",
+                    player.name, self.connections[&connection_id].address
+                );
+
+                self.broadcast(
+                    &ServerMessage::PlayerJoined {
+                        player: player.clone(),
+                        entity: Box::new(entity),
+                    },
+                    None,
+                );
+                if let Some(connection) = self.connections.get_mut(&connection_id) {
+                    connection.player = Some(player);
+                }
+
+                let mut universe = self.universe.clone();
+                universe.user_entity_id = entity_id;
+                let welcome = ServerMessage::Welcome {
+                    entity_id,
+                    scenario: self.scenario.clone(),
+                    universe: Box::new(universe),
+                    players: self.players().cloned().collect(),
+                };
+                self.send(connection_id, &welcome);
+            }
+            (ClientMessage::Event(event), Some(player)) => {
+                // clients are trusted with their own worldlines, but not with going faster than light
+                if !event.is_valid() {
+                    warn!("Ignoring an impossible event from {}", player.name);
+                    return;
+                }
+
+                UniverseEdit::InsertExactEvent {
+                    entity_id: player.entity_id,
+                    event,
+                }
+                .apply(&mut self.universe);
+                self.broadcast(
+                    &ServerMessage::Event {
+                        entity_id: player.entity_id,
+                        event,
+                    },
+                    Some(connection_id),
+                );
+            }
+            (ClientMessage::Chat(text), Some(player)) => {
+                let text = sanitize_text(&text, MAX_CHAT_LENGTH);
+                if text.is_empty() {
+                    return;
+                }
+
+                info!("<{}> {}", player.name, text);
+                self.broadcast(
+                    &ServerMessage::Chat {
+                        from: player.name,
+                        text,
+                    },
+                    None,
+                );
+            }
+            (ClientMessage::Hello { .. }, Some(player)) => {
+                warn!("{} said hello twice", player.name);
+            }
+            (_, None) => {
+                warn!(
+                    "{} sent something before saying hello",
+                    self.connections[&connection_id].address
+                );
+            }
+        }
+    }
+
+    fn handle_event(&mut self, event: ServerEvent) {
+        match event {
+            ServerEvent::Connected(connection_id, stream) => {
+                let Ok(address) = stream.peer_addr() else {
+                    return;
+                };
+                self.connections.insert(
+                    connection_id,
+                    Connection {
+                        stream,
+                        address,
+                        player: None,
+                    },
+                );
+            }
+            ServerEvent::Message(connection_id, message) => {
+                self.handle_message(connection_id, message);
+            }
+            ServerEvent::Disconnected(connection_id) => {
+                let Some(connection) = self.connections.remove(&connection_id) else {
+                    return;
+                };
+                let Some(player) = connection.player else {
+                    return;
+                };
+
+                info!("{} left", player.name);
+                self.universe.remove_entity(player.entity_id);
+                self.broadcast(&ServerMessage::PlayerLeft(player.entity_id), None);
+            }
+        }
+    }
+
+    /// Runs the server on the current thread, forever.
+    pub fn run(mut self, listener: TcpListener) -> Result<()> {
+        let (sender, receiver) = mpsc::channel();
+        thread::Builder::new()
+            .name("server listener".into())
+            .spawn(move || accept_connections(listener, sender))?;
+
+        let mut last_step = Instant::now();
+        loop {
+            self.handle_events(&receiver)?;
+
+            // nobody relies on the server's own frame, so it can step in big chunks
+            let now = Instant::now();
+            self.universe.step(
+                (now - last_step)
+                    .as_secs_f64()
+                    .min(SERVER_TICK.as_secs_f64() * 4.0),
+            );
+            last_step = now;
+        }
+    }
+
+    /// Handles whatever's come in, waiting up to a [`SERVER_TICK`] for the first of it.
+    fn handle_events(&mut self, receiver: &Receiver<ServerEvent>) -> Result<()> {
+        let first_event = match receiver.recv_timeout(SERVER_TICK) {
+            Ok(event) => event,
+            Err(RecvTimeoutError::Timeout) => return Ok(()),
+            Err(RecvTimeoutError::Disconnected) => anyhow::bail!("stopped listening"),
+        };
+        for event in std::iter::once(first_event).chain(receiver.try_iter()) {
+            self.handle_event(event);
+        }
+        Ok(())
+    }
+}
+
+fn accept_connections(listener: TcpListener, sender: Sender<ServerEvent>) {
+    for (connection_id, stream) in (0..).zip(listener.incoming()) {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                warn!("Couldn't accept a connection: {}", error);
+                continue;
+            }
+        };
+        let _ = stream.set_nodelay(true);
+        let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+        let Ok(read_stream) = stream.try_clone() else {
+            continue;
+        };
+
+        if sender
+            .send(ServerEvent::Connected(connection_id, stream))
+            .is_err()
+        {
+            return;
+        }
+        let sender = sender.clone();
+        let _ = thread::Builder::new()
+            .name(format!("connection {connection_id}"))
+            .spawn(move || read_connection(connection_id, read_stream, sender));
+    }
+}
+
+fn read_connection(connection_id: ConnectionId, stream: TcpStream, sender: Sender<ServerEvent>) {
+    let mut reader = BufReader::new(stream);
+    loop {
+        match read_message::<ClientMessage>(&mut reader, MAX_CLIENT_MESSAGE_BYTES) {
+            Ok(Some(message)) => {
+                if sender
+                    .send(ServerEvent::Message(connection_id, message))
+                    .is_err()
+                {
+                    return;
+                }
+            }
+            Ok(None) => break,
+            Err(error) => {
+                warn!("Dropping connection {}: {}", connection_id, error);
+                break;
+            }
+        }
+    }
+    let _ = sender.send(ServerEvent::Disconnected(connection_id));
+}
+
+/// Starts a [`Server`] on a background thread, for hosting from inside the app. Returns the address it's
+/// listening on.
+pub fn spawn_server(address: SocketAddr, server: Server) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(address)?;
+    let address = listener.local_addr()?;
+    thread::Builder::new()
+        .name("server".into())
+        .spawn(move || {
+            if let Err(error) = server.run(listener) {
+                warn!("The server stopped: {}", error);
+            }
+        })?;
+    info!("Hosting on {}", address);
+    Ok(address)
+}
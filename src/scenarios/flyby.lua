@@ -0,0 +1,30 @@
+-- launches a cube past the one at rest, then slows it down once it's far enough away
+
+local runner
+local stopped = false
+
+function on_start()
+    runner = world.spawn({
+        model = "subdivided_cube",
+        position = { -200, 0, 0 },
+        velocity = { 0.8, 0, 0 },
+        scale = 5,
+        color = { 1, 0.5, 0.5, 1 },
+    })
+    world.annotate(runner, "0.8c")
+    gui.toast(gui.tr("flyby.watch"))
+end
+
+function on_tick(delta)
+    if stopped then
+        return
+    end
+
+    local frame = world.frame(runner)
+    if frame and frame.x > 200 then
+        stopped = true
+        world.insert_event(runner, world.time(), { -2, 0, 0 })
+        world.insert_event(runner, world.time() + 0.5, nil)
+        gui.toast(gui.tr("flyby.aged", { age = string.format("%.1f", frame.proper_time) }))
+    end
+end
@@ -0,0 +1,181 @@
+-- the satellites broadcast what their clocks read, and the receiver works out where it is from how late each reading
+-- arrives. without relativistic corrections, it takes the readings at face value, and since moving clocks run slow,
+-- the fix drifts further off the longer it goes
+
+local RECEIVER_POSITION = { 0, 21, 0 }
+-- the start has to be at a right angle to the axis
+local ORBITS = {
+    { radius = 60, speed = 0.3, axis = { 0, 1, 0 }, start = { 1, 0, 0 } },
+    { radius = 70, speed = 0.4, axis = { 0, 0, 1 }, start = { 0, 1, 0 } },
+    { radius = 80, speed = 0.2, axis = { 1, 0, 0 }, start = { 0, 0, 1 } },
+    { radius = 65, speed = 0.35, axis = { 0, math.sqrt(0.5), math.sqrt(0.5) }, start = { 1, 0, 0 } },
+}
+local SOLVER_ITERATIONS = 12
+-- the fix moves in steps this far apart, in seconds
+local UPDATE_INTERVAL = 0.2
+
+local receiver
+local fix_marker
+local satellites = {}
+local corrected = true
+local fix
+local since_update = 0
+
+local function add(a, b)
+    return { a[1] + b[1], a[2] + b[2], a[3] + b[3] }
+end
+
+local function sub(a, b)
+    return { a[1] - b[1], a[2] - b[2], a[3] - b[3] }
+end
+
+local function scale(v, factor)
+    return { v[1] * factor, v[2] * factor, v[3] * factor }
+end
+
+local function cross(a, b)
+    return { a[2] * b[3] - a[3] * b[2], a[3] * b[1] - a[1] * b[3], a[1] * b[2] - a[2] * b[1] }
+end
+
+local function length(v)
+    return math.sqrt(v[1] ^ 2 + v[2] ^ 2 + v[3] ^ 2)
+end
+
+local function position(frame)
+    return { frame.x, frame.y, frame.z }
+end
+
+-- Cramer's rule, or nil if there's no single answer
+local function solve3(m, rhs)
+    local function det(a)
+        return a[1][1] * (a[2][2] * a[3][3] - a[2][3] * a[3][2])
+            - a[1][2] * (a[2][1] * a[3][3] - a[2][3] * a[3][1])
+            + a[1][3] * (a[2][1] * a[3][2] - a[2][2] * a[3][1])
+    end
+    local determinant = det(m)
+    if math.abs(determinant) < 1e-12 then
+        return nil
+    end
+
+    local result = {}
+    for column = 1, 3 do
+        local replaced = {}
+        for row = 1, 3 do
+            replaced[row] = { m[row][1], m[row][2], m[row][3] }
+            replaced[row][column] = rhs[row]
+        end
+        result[column] = det(replaced) / determinant
+    end
+    return result
+end
+
+-- when the signal reaching the receiver right now left the satellite
+local function emission_time(satellite, now)
+    local time = now
+    for _ = 1, SOLVER_ITERATIONS do
+        time = now - length(sub(position(world.frame(satellite.id, time)), RECEIVER_POSITION))
+    end
+    return time
+end
+
+-- when the receiver figures a reading was sent, counting from the one it synced up with at the start
+local function sent_time(satellite, reading)
+    local rate = corrected and satellite.gamma or 1
+    return satellite.sync_time + (reading - satellite.sync_reading) * rate
+end
+
+-- least squares over every satellite's distance, starting from the last fix
+local function solve_fix(ranges)
+    local guess = fix or { 0, 0, 0 }
+    for _ = 1, SOLVER_ITERATIONS do
+        local normal = { { 0, 0, 0 }, { 0, 0, 0 }, { 0, 0, 0 } }
+        local rhs = { 0, 0, 0 }
+        for _, range in ipairs(ranges) do
+            local offset = sub(guess, range.position)
+            local distance = length(offset)
+            local residual = distance - range.distance
+            for i = 1, 3 do
+                rhs[i] = rhs[i] - offset[i] / distance * residual
+                for j = 1, 3 do
+                    normal[i][j] = normal[i][j] + offset[i] * offset[j] / (distance * distance)
+                end
+            end
+        end
+
+        local step = solve3(normal, rhs)
+        if not step then
+            break
+        end
+        guess = add(guess, step)
+    end
+    return guess
+end
+
+-- the satellites only show up in queries once on_start returns, so this waits for the first tick
+local function sync()
+    local now = world.time()
+    for _, satellite in ipairs(satellites) do
+        local frame = world.frame(satellite.id)
+        satellite.gamma = 1 / math.sqrt(1 - frame.vx ^ 2 - frame.vy ^ 2 - frame.vz ^ 2)
+        satellite.sync_time = emission_time(satellite, now)
+        satellite.sync_reading = world.frame(satellite.id, satellite.sync_time).proper_time
+        world.annotate(satellite.id, gui.tr("gps.satellite", { rate = string.format("%.3f", 1 / satellite.gamma) }))
+    end
+end
+
+function on_start()
+    receiver = world.spawn_prefab("beacon", { position = RECEIVER_POSITION })
+    fix_marker = world.spawn({
+        model = "uv_sphere",
+        position = RECEIVER_POSITION,
+        scale = 1.5,
+        color = { 1, 0.3, 0.3, 1 },
+    })
+    world.attach(fix_marker, receiver, { 0, 0, 0 })
+    world.annotate(fix_marker, gui.tr("gps.fix"))
+
+    for _, orbit in ipairs(ORBITS) do
+        local start = scale(orbit.start, orbit.radius)
+        local angular_velocity = scale(orbit.axis, orbit.speed / orbit.radius)
+        local id = world.spawn_prefab("clock", { position = start, velocity = cross(angular_velocity, start) })
+        world.orbit(id, world.time(), { 0, 0, 0 }, angular_velocity)
+        table.insert(satellites, { id = id })
+    end
+
+    gui.toast(gui.tr("gps.intro"))
+end
+
+function on_action(name)
+    if name ~= "ScenarioToggle" then
+        return
+    end
+    corrected = not corrected
+    gui.toast(gui.tr(corrected and "gps.corrections_on" or "gps.corrections_off"))
+end
+
+function on_tick(delta)
+    if not satellites[1].gamma then
+        sync()
+    end
+    since_update = since_update + delta
+    if since_update < UPDATE_INTERVAL then
+        return
+    end
+    since_update = 0
+
+    local now = world.time()
+    local ranges = {}
+    for _, satellite in ipairs(satellites) do
+        local reading = world.frame(satellite.id, emission_time(satellite, now)).proper_time
+        local sent = sent_time(satellite, reading)
+        table.insert(ranges, { position = position(world.frame(satellite.id, sent)), distance = now - sent })
+    end
+
+    fix = solve_fix(ranges)
+    local offset = sub(fix, RECEIVER_POSITION)
+    world.attach(fix_marker, receiver, offset)
+    world.annotate(receiver, gui.tr("gps.receiver", {
+        error = string.format("%.2f", length(offset)),
+        corrections = gui.tr(corrected and "gps.corrected" or "gps.uncorrected"),
+    }))
+end
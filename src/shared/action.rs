@@ -0,0 +1,204 @@
+use super::{
+    gamepad::GamepadButton,
+    input::{Input, KeyChord, ModifierKey},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use winit::keyboard::NamedKey;
+
+/// Something the user can do, independent of which inputs are bound to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    ThrustForward,
+    ThrustBackward,
+    ThrustLeft,
+    ThrustRight,
+    ThrustUp,
+    ThrustDown,
+    RollLeft,
+    RollRight,
+    ToggleMouseLock,
+    QuickActionMenu,
+    ToggleHudEditor,
+    ToggleProfiler,
+    /// Goes to the next page of the debug overlay, or hides it after the last one.
+    CycleDebugPage,
+    QuickSave,
+    QuickLoad,
+    ToggleReplayRecording,
+    /// Watches the newest replay, or goes back to the live universe.
+    ToggleReplayPlayback,
+    ReplayPlayPause,
+    ReplayCycleObserver,
+    ReplayFreeCamera,
+    ToggleSpawnPalette,
+    DeleteSelected,
+    /// Pins the selected entity for comparing clocks with, or unpins it.
+    PinObserver,
+    /// Switches between dragging out rulers and the usual picking and spawning.
+    ToggleRulerTool,
+    /// Marks an event on the selected entity's worldline (or the user's) for the event ordering panel.
+    MarkEvent,
+    Undo,
+    Redo,
+    /// Copies the selected entities (and the rest of the selection group), to the OS clipboard too.
+    CopySelected,
+    /// Pastes copied entities where the cursor's pointing.
+    Paste,
+    OpenChat,
+    ToggleLobby,
+    /// Opens or closes a second window with its own view, e.g. for a second monitor.
+    ToggleAuxiliaryWindow,
+    CycleAuxiliaryView,
+    /// Freezes time and lets the camera fly around on its own, for taking pictures.
+    TogglePhotoMode,
+    /// Circles the camera around the selected entity, watching from its rest frame.
+    ToggleOrbitCamera,
+    CapturePhoto,
+    CyclePhotoFilter,
+    /// Switches the red and blue shift of things moving towards or away from the camera on or off, e.g. to show
+    /// what it does.
+    ToggleDoppler,
+    /// Moves on to the next step of a scenario's guided tour.
+    AdvanceTour,
+    /// Does nothing on its own, it's there for scenario scripts to pick up in `on_action`.
+    ScenarioToggle,
+}
+
+/// Which inputs trigger which actions. An action can have any number of chords bound to it, and it counts as
+/// held/pressed/released if any of them are. When chords on the same input overlap, the one with the most
+/// modifiers held wins.
+///
+/// Actions missing from saved bindings (e.g. ones added since they were saved) get their default chords.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    from = "BTreeMap<Action, Vec<KeyChord>>",
+    into = "BTreeMap<Action, Vec<KeyChord>>"
+)]
+pub struct ActionBindings {
+    pub bindings: BTreeMap<Action, Vec<KeyChord>>,
+}
+
+impl From<BTreeMap<Action, Vec<KeyChord>>> for ActionBindings {
+    fn from(bindings: BTreeMap<Action, Vec<KeyChord>>) -> Self {
+        let mut action_bindings = Self::default();
+        action_bindings.bindings.extend(bindings);
+        action_bindings
+    }
+}
+
+impl From<ActionBindings> for BTreeMap<Action, Vec<KeyChord>> {
+    fn from(value: ActionBindings) -> Self {
+        value.bindings
+    }
+}
+
+impl Default for ActionBindings {
+    fn default() -> Self {
+        let mut bindings = BTreeMap::new();
+        let mut bind = |action, chords: Vec<KeyChord>| {
+            bindings.insert(action, chords);
+        };
+
+        bind(Action::ThrustForward, vec!["w".into()]);
+        bind(Action::ThrustBackward, vec!["s".into()]);
+        bind(Action::ThrustLeft, vec!["a".into()]);
+        bind(Action::ThrustRight, vec!["d".into()]);
+        bind(Action::ThrustUp, vec![NamedKey::Shift.into()]);
+        bind(Action::ThrustDown, vec![NamedKey::Control.into()]);
+        bind(
+            Action::RollLeft,
+            vec!["q".into(), GamepadButton::LeftBumper.into()],
+        );
+        bind(
+            Action::RollRight,
+            vec!["e".into(), GamepadButton::RightBumper.into()],
+        );
+        bind(
+            Action::ToggleMouseLock,
+            vec![NamedKey::Tab.into(), GamepadButton::Start.into()],
+        );
+        bind(Action::QuickActionMenu, vec!["f".into()]);
+        bind(Action::ToggleHudEditor, vec![NamedKey::F7.into()]);
+        bind(Action::ToggleProfiler, vec![NamedKey::F8.into()]);
+        bind(Action::CycleDebugPage, vec![NamedKey::F3.into()]);
+        bind(Action::QuickSave, vec![NamedKey::F5.into()]);
+        bind(Action::QuickLoad, vec![NamedKey::F9.into()]);
+        bind(Action::ToggleReplayRecording, vec![NamedKey::F6.into()]);
+        bind(Action::ToggleReplayPlayback, vec![NamedKey::F10.into()]);
+        bind(Action::ReplayPlayPause, vec!["p".into()]);
+        bind(Action::ReplayCycleObserver, vec!["o".into()]);
+        bind(Action::ReplayFreeCamera, vec!["c".into()]);
+        bind(Action::ToggleSpawnPalette, vec!["b".into()]);
+        bind(Action::DeleteSelected, vec![NamedKey::Delete.into()]);
+        bind(Action::PinObserver, vec!["k".into()]);
+        bind(Action::ToggleRulerTool, vec!["r".into()]);
+        bind(Action::MarkEvent, vec!["m".into()]);
+        bind(
+            Action::Undo,
+            vec![KeyChord::new([ModifierKey::Control], "z")],
+        );
+        bind(
+            Action::Redo,
+            vec![
+                KeyChord::new([ModifierKey::Control], "y"),
+                KeyChord::new([ModifierKey::Control, ModifierKey::Shift], "z"),
+            ],
+        );
+        bind(
+            Action::CopySelected,
+            vec![KeyChord::new([ModifierKey::Control], "c")],
+        );
+        bind(
+            Action::Paste,
+            vec![KeyChord::new([ModifierKey::Control], "v")],
+        );
+        bind(Action::OpenChat, vec!["t".into(), NamedKey::Enter.into()]);
+        bind(Action::ToggleLobby, vec!["l".into()]);
+        bind(Action::ToggleAuxiliaryWindow, vec![NamedKey::F4.into()]);
+        bind(Action::CycleAuxiliaryView, vec!["v".into()]);
+        bind(Action::TogglePhotoMode, vec![NamedKey::F2.into()]);
+        bind(Action::ToggleOrbitCamera, vec!["i".into()]);
+        bind(Action::CapturePhoto, vec![NamedKey::F12.into()]);
+        bind(Action::CyclePhotoFilter, vec!["g".into()]);
+        bind(Action::ToggleDoppler, vec!["u".into()]);
+        bind(Action::AdvanceTour, vec![NamedKey::Space.into()]);
+        bind(Action::ScenarioToggle, vec!["j".into()]);
+
+        Self { bindings }
+    }
+}
+
+impl ActionBindings {
+    pub fn chords(&self, action: Action) -> &[KeyChord] {
+        self.bindings
+            .get(&action)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Adds another chord for an action, keeping the existing ones.
+    pub fn bind(&mut self, action: Action, chord: impl Into<KeyChord>) {
+        let chord = chord.into();
+        let chords = self.bindings.entry(action).or_default();
+        if !chords.contains(&chord) {
+            chords.push(chord);
+        }
+    }
+
+    pub fn unbind(&mut self, action: Action, chord: impl Into<KeyChord>) {
+        let chord = chord.into();
+        if let Some(chords) = self.bindings.get_mut(&action) {
+            chords.retain(|bound_chord| *bound_chord != chord);
+        }
+    }
+
+    /// Every action that has a chord on the input, regardless of modifiers.
+    pub fn actions_for(&self, input: &Input) -> impl Iterator<Item = Action> + '_ {
+        let input = input.clone();
+        self.bindings
+            .iter()
+            .filter(move |(_, chords)| chords.iter().any(|chord| chord.input == input))
+            .map(|(&action, _)| action)
+    }
+}
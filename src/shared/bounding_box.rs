@@ -1,18 +1,25 @@
 use super::f32_util::AddWithEpsilon;
+use cgmath::{num_traits::NumCast, BaseFloat, Matrix4, Point3, Transform};
 
-pub type Point<const D: usize> = [f32; D];
+/// What a [`BoundingBox`]'s coordinates can be made of. In practice, `f32` for graphics and GUI stuff, and `f64`
+/// for anything in spacetime.
+pub trait BoundingBoxScalar: BaseFloat + AddWithEpsilon {}
+
+impl<S: BaseFloat + AddWithEpsilon> BoundingBoxScalar for S {}
+
+pub type Point<const D: usize, S = f32> = [S; D];
 
 /// Arbitrary-dimensional bounding box.
 ///
 /// But good luck finding a practical use for anything other than two or three dimensions.
 #[derive(Debug, Clone, Copy, PartialEq)]
-pub struct BoundingBox<const D: usize> {
-    min: Point<D>,
-    max: Point<D>,
+pub struct BoundingBox<const D: usize, S: BoundingBoxScalar = f32> {
+    min: Point<D, S>,
+    max: Point<D, S>,
 }
 
 /// Alias for [BoundingBox].
-pub type BBox<const D: usize> = BoundingBox<D>;
+pub type BBox<const D: usize, S = f32> = BoundingBox<D, S>;
 /// Alias for a [one-dimensional BoundingBox](BoundingBox<1>). (aka basically a bounding line segment)
 pub type BBox1 = BoundingBox<1>;
 /// Alias for a [two-dimensional BoundingBox](BoundingBox<2>).
@@ -24,58 +31,95 @@ pub type BBox3 = BoundingBox<3>;
 /// Seriously though, why would you need this?
 pub type BBox4 = BoundingBox<4>;
 
-impl<const D: usize> std::fmt::Display for BoundingBox<D> {
+/// Alias for a [BoundingBox] with `f64` coordinates.
+pub type BBoxF64<const D: usize> = BoundingBox<D, f64>;
+/// Alias for a [two-dimensional BoundingBox](BoundingBox<2>) with `f64` coordinates.
+pub type BBox2F64 = BoundingBox<2, f64>;
+/// Alias for a [three-dimensional BoundingBox](BoundingBox<3>) with `f64` coordinates.
+pub type BBox3F64 = BoundingBox<3, f64>;
+/// Alias for a [four-dimensional BoundingBox](BoundingBox<4>) with `f64` coordinates, e.g. a region of
+/// spacetime.
+pub type BBox4F64 = BoundingBox<4, f64>;
+
+impl<const D: usize, S: BoundingBoxScalar> std::fmt::Display for BoundingBox<D, S> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "BoundingBox(\n\t{:?}\n\t{:?}\n)", self.min, self.max)
     }
 }
 
-impl<const D: usize> Default for BoundingBox<D> {
+impl<const D: usize, S: BoundingBoxScalar> Default for BoundingBox<D, S> {
     /// Create a bounding box with both corners initialized to zero.
     fn default() -> Self {
         Self {
-            min: [0.0; D],
-            max: [0.0; D],
+            min: [S::zero(); D],
+            max: [S::zero(); D],
+        }
+    }
+}
+
+impl<const D: usize> From<BoundingBox<D, f32>> for BoundingBox<D, f64> {
+    fn from(value: BoundingBox<D, f32>) -> Self {
+        Self {
+            min: value.min.map(|value| value as f64),
+            max: value.max.map(|value| value as f64),
+        }
+    }
+}
+
+impl<const D: usize> BoundingBox<D, f64> {
+    /// Converts down to `f32` coordinates, losing precision.
+    pub fn to_f32(&self) -> BoundingBox<D, f32> {
+        BoundingBox {
+            min: self.min.map(|value| value as f32),
+            max: self.max.map(|value| value as f32),
         }
     }
 }
 
-impl BoundingBox<1> {
+impl<S: BoundingBoxScalar> BoundingBox<1, S> {
     /// Create a one-dimensional bounding ~box~ line segment with both ~corners~ ends initialized to zero.
     pub fn d1(&self) -> Self {
         Self::default()
     }
 
-    pub fn length(&self) -> f32 {
+    pub fn length(&self) -> S {
         self.max[0] - self.min[0]
     }
 }
 
-impl BoundingBox<2> {
+impl<S: BoundingBoxScalar> BoundingBox<2, S> {
     /// Create a two-dimensional bounding box with both corners initialized to zero.
     pub fn d2() -> Self {
         Self::default()
     }
 
     /// Width times height.
-    pub fn area(&self) -> f32 {
+    pub fn area(&self) -> S {
         self.measure()
     }
 }
 
-impl BoundingBox<3> {
+impl<S: BoundingBoxScalar> BoundingBox<3, S> {
     /// Create a three-dimensional bounding box with both corners initialized to zero.
     pub fn d3() -> Self {
         Self::default()
     }
 
     /// Width times height times length.
-    pub fn volume(&self) -> f32 {
+    pub fn volume(&self) -> S {
         self.measure()
     }
+
+    /// The smallest axis-aligned box containing this box after being transformed by `matrix`.
+    pub fn transformed(&self, matrix: &Matrix4<S>) -> Self {
+        Self::new(self.corners().map(|corner| {
+            let transformed = matrix.transform_point(Point3::from(corner));
+            [transformed.x, transformed.y, transformed.z]
+        }))
+    }
 }
 
-impl BoundingBox<4> {
+impl<S: BoundingBoxScalar> BoundingBox<4, S> {
     /// Create a four-dimensional bounding box with both corners initialized to zero.
     /// Why would you need this?
     pub fn d4() -> Self {
@@ -83,14 +127,14 @@ impl BoundingBox<4> {
     }
 
     /// Width times height times length times a secret, scarier fourth thing
-    pub fn hypervolume(&self) -> f32 {
+    pub fn hypervolume(&self) -> S {
         self.measure()
     }
 }
 
-impl<const D: usize> BoundingBox<D> {
+impl<const D: usize, S: BoundingBoxScalar> BoundingBox<D, S> {
     /// Create the smallest bounding box that contains all provided points.
-    pub fn new(positions: impl IntoIterator<Item = impl Into<Point<D>>>) -> Self {
+    pub fn new(positions: impl IntoIterator<Item = impl Into<Point<D, S>>>) -> Self {
         let mut bounding_box = Self::default();
         bounding_box.only_fit(positions);
         bounding_box
@@ -98,11 +142,11 @@ impl<const D: usize> BoundingBox<D> {
 
     /// Changes the bounding box to the smallest size that contains all provided points,
     /// ignoring any previous bounds.
-    pub fn only_fit(&mut self, positions: impl IntoIterator<Item = impl Into<Point<D>>>) {
+    pub fn only_fit(&mut self, positions: impl IntoIterator<Item = impl Into<Point<D, S>>>) {
         let mut positions = positions.into_iter();
-        let first_pos: Point<D> = match positions.next() {
+        let first_pos: Point<D, S> = match positions.next() {
             Some(first_pos) => first_pos.into(),
-            None => [0.0; D],
+            None => [S::zero(); D],
         };
         self.min = first_pos;
         self.max = first_pos;
@@ -113,8 +157,8 @@ impl<const D: usize> BoundingBox<D> {
     }
 
     /// Checks whether a point is within the bounding box.
-    pub fn point_is_within(&self, position: impl Into<Point<D>>) -> bool {
-        let position: Point<D> = position.into();
+    pub fn point_is_within(&self, position: impl Into<Point<D, S>>) -> bool {
+        let position: Point<D, S> = position.into();
 
         for (index, value) in position.into_iter().enumerate() {
             if value < self.min[index] || value > self.max[index] {
@@ -134,8 +178,8 @@ impl<const D: usize> BoundingBox<D> {
     /// and a newly provided point.
     ///
     /// Returns whether the box changed size.
-    pub fn expand_to_fit(&mut self, position: impl Into<Point<D>>) -> bool {
-        let position: Point<D> = position.into();
+    pub fn expand_to_fit(&mut self, position: impl Into<Point<D, S>>) -> bool {
+        let position: Point<D, S> = position.into();
         let is_outside = !self.point_is_within(position);
         for (index, value) in position.into_iter().enumerate() {
             self.min[index] = value.min(self.min[index]);
@@ -156,7 +200,7 @@ impl<const D: usize> BoundingBox<D> {
     /// Applies [`BoundingBox::expand_to_fit()`] on all points in an iterator.
     pub fn expand_to_fit_iter(
         &mut self,
-        positions: impl Iterator<Item = impl Into<Point<D>>>,
+        positions: impl Iterator<Item = impl Into<Point<D, S>>>,
     ) -> bool {
         let mut expanded = false;
         for position in positions {
@@ -179,18 +223,18 @@ impl<const D: usize> BoundingBox<D> {
     }
 
     /// The minimum corner of this bounding box's margins.
-    pub const fn min(&self) -> Point<D> {
+    pub const fn min(&self) -> Point<D, S> {
         self.min
     }
 
     /// The maximum corner of this bounding box's margins.
-    pub const fn max(&self) -> Point<D> {
+    pub const fn max(&self) -> Point<D, S> {
         self.max
     }
 
     /// The center point of this bounding box.
-    pub fn center(&self) -> Point<D> {
-        std::array::from_fn(|index| (self.min[index] + self.max[index]) / 2.0)
+    pub fn center(&self) -> Point<D, S> {
+        std::array::from_fn(|index| (self.min[index] + self.max[index]) / (S::one() + S::one()))
     }
 
     /// Retrieves the position of a specific corner of the box.
@@ -204,7 +248,7 @@ impl<const D: usize> BoundingBox<D> {
     /// // retrieves the (+X, -Y, +Z) corner
     /// assert_eq!(cube.get_corner([true, false, true]), [1.0, -3.0, 1.0])
     /// ```
-    pub fn get_corner(&self, is_max: [bool; D]) -> Point<D> {
+    pub fn get_corner(&self, is_max: [bool; D]) -> Point<D, S> {
         let mut i = 0;
         is_max.map(|is_max| {
             let value = if is_max { self.max[i] } else { self.min[i] };
@@ -213,8 +257,76 @@ impl<const D: usize> BoundingBox<D> {
         })
     }
 
+    /// Iterates over all `2^D` corners of the box, starting with [`BoundingBox::min()`] and ending with
+    /// [`BoundingBox::max()`].
+    pub fn corners(&self) -> impl Iterator<Item = Point<D, S>> + '_ {
+        (0..1usize << D)
+            .map(|mask| self.get_corner(std::array::from_fn(|axis| mask & (1 << axis) != 0)))
+    }
+
+    /// The point within the box that's closest to `position`, which is just `position` if it's inside.
+    pub fn closest_point(&self, position: impl Into<Point<D, S>>) -> Point<D, S> {
+        let position: Point<D, S> = position.into();
+        std::array::from_fn(|index| position[index].clamp(self.min[index], self.max[index]))
+    }
+
+    /// Squared distance from `position` to the closest point in the box, or zero if it's inside.
+    pub fn distance_squared_to(&self, position: impl Into<Point<D, S>>) -> S {
+        let position: Point<D, S> = position.into();
+        let closest_point = self.closest_point(position);
+        (0..D)
+            .map(|index| (position[index] - closest_point[index]).powi(2))
+            .fold(S::zero(), |sum, value| sum + value)
+    }
+
+    /// Intersects a ray with the box using the slab method, returning the range of `t` (in multiples of
+    /// `direction`) where `origin + direction * t` is inside the box. The range only covers `t >= 0`, so if the
+    /// origin is inside the box, it starts at zero. `direction` doesn't need to be normalized.
+    pub fn ray_intersection(
+        &self,
+        origin: impl Into<Point<D, S>>,
+        direction: impl Into<Point<D, S>>,
+    ) -> Option<(S, S)> {
+        let origin: Point<D, S> = origin.into();
+        let direction: Point<D, S> = direction.into();
+
+        let mut t_enter = S::zero();
+        let mut t_exit = S::infinity();
+        for index in 0..D {
+            if direction[index].is_zero() {
+                // parallel to this slab, so it either never enters it or never leaves it
+                if origin[index] < self.min[index] || origin[index] > self.max[index] {
+                    return None;
+                }
+                continue;
+            }
+
+            let inverse_direction = direction[index].recip();
+            let t_min = (self.min[index] - origin[index]) * inverse_direction;
+            let t_max = (self.max[index] - origin[index]) * inverse_direction;
+            t_enter = t_enter.max(t_min.min(t_max));
+            t_exit = t_exit.min(t_min.max(t_max));
+
+            if t_exit < t_enter {
+                return None;
+            }
+        }
+
+        Some((t_enter, t_exit))
+    }
+
+    /// Where a ray first hits the box, as a multiple of `direction`. See [`BoundingBox::ray_intersection()`].
+    pub fn ray_hit(
+        &self,
+        origin: impl Into<Point<D, S>>,
+        direction: impl Into<Point<D, S>>,
+    ) -> Option<S> {
+        self.ray_intersection(origin, direction)
+            .map(|(t_enter, _)| t_enter)
+    }
+
     /// The size of this bounding box.
-    pub fn size(&self) -> Point<D> {
+    pub fn size(&self) -> Point<D, S> {
         let mut i = 0;
         self.max.map(|max| {
             let value = max - self.min[i];
@@ -226,13 +338,13 @@ impl<const D: usize> BoundingBox<D> {
     /// The product of all components in this bounding box's size.
     ///
     /// This is the dimension-independant method for what is usually called *"area"* or *"volume"*.
-    pub fn measure(&self) -> f32 {
+    pub fn measure(&self) -> S {
         self.size()
             .into_iter()
-            .fold(1.0, |product, value| product * value)
+            .fold(S::one(), |product, value| product * value)
     }
 
-    pub fn offset(&self, offset: impl Into<Point<D>>) -> Self {
+    pub fn offset(&self, offset: impl Into<Point<D, S>>) -> Self {
         let offset = offset.into();
 
         let mut new_min = self.min;
@@ -247,7 +359,7 @@ impl<const D: usize> BoundingBox<D> {
         }
     }
 
-    pub fn offset_with_epsilon(&self, offset: impl Into<Point<D>>) -> Self {
+    pub fn offset_with_epsilon(&self, offset: impl Into<Point<D, S>>) -> Self {
         let offset = offset.into();
 
         let mut new_min = self.min;
@@ -262,11 +374,11 @@ impl<const D: usize> BoundingBox<D> {
         }
     }
 
-    pub fn project(&self, axis: usize) -> BoundingBox<{ D - 1 }> {
+    pub fn project(&self, axis: usize) -> BoundingBox<{ D - 1 }, S> {
         let axis = axis.min(D - 1);
 
-        let mut new_min = [0.0; D - 1];
-        let mut new_max = [0.0; D - 1];
+        let mut new_min = [S::zero(); D - 1];
+        let mut new_max = [S::zero(); D - 1];
 
         let mut new_index = 0;
         for index in 0..D {
@@ -284,8 +396,8 @@ impl<const D: usize> BoundingBox<D> {
     }
 
     pub fn intersection(&self, other: Self) -> Option<Self> {
-        let mut new_min = [0.0; D];
-        let mut new_max = [0.0; D];
+        let mut new_min = [S::zero(); D];
+        let mut new_max = [S::zero(); D];
 
         for index in 0..D {
             let min = self.min[index].max(other.min[index]);
@@ -305,39 +417,57 @@ impl<const D: usize> BoundingBox<D> {
         })
     }
 
-    pub fn extend(&self, amount: impl Into<Point<D>>) -> Self {
+    /// Whether the two boxes share any space, including just touching.
+    pub fn overlaps(&self, other: Self) -> bool {
+        (0..D)
+            .all(|index| self.min[index] <= other.max[index] && other.min[index] <= self.max[index])
+    }
+
+    pub fn extend(&self, amount: impl Into<Point<D, S>>) -> Self {
         let amount = amount.into();
 
         let offset = self.offset(amount);
         Self::new([self.min, self.max, offset.min, offset.max])
     }
 
-    pub fn extend_with_epsilon(&self, amount: impl Into<Point<D>>) -> Self {
+    pub fn extend_with_epsilon(&self, amount: impl Into<Point<D, S>>) -> Self {
         let amount = amount.into();
 
         let offset = self.offset_with_epsilon(amount);
         Self::new([self.min, self.max, offset.min, offset.max])
     }
 
-    pub fn retract(&self, amount: impl Into<Point<D>>) -> Option<Self> {
+    pub fn retract(&self, amount: impl Into<Point<D, S>>) -> Option<Self> {
         let amount = amount.into();
 
         let offset = self.offset(amount);
         self.intersection(offset)
     }
 
-    pub fn retract_with_epsilon(&self, amount: impl Into<Point<D>>) -> Option<Self> {
+    pub fn retract_with_epsilon(&self, amount: impl Into<Point<D, S>>) -> Option<Self> {
         let amount = amount.into();
 
         let offset = self.offset_with_epsilon(amount);
         self.intersection(offset)
     }
 
-    pub fn point_from_normalized(&self, normalized_point: impl Into<Point<D>>) -> Point<D> {
+    /// Converts to a different scalar type, or returns `None` if a coordinate can't be represented in it.
+    pub fn cast<T: BoundingBoxScalar>(&self) -> Option<BoundingBox<D, T>> {
+        let mut min = [T::zero(); D];
+        let mut max = [T::zero(); D];
+        for index in 0..D {
+            min[index] = <T as NumCast>::from(self.min[index])?;
+            max[index] = <T as NumCast>::from(self.max[index])?;
+        }
+
+        Some(BoundingBox { min, max })
+    }
+
+    pub fn point_from_normalized(&self, normalized_point: impl Into<Point<D, S>>) -> Point<D, S> {
         let normalized_point = normalized_point.into();
 
         let size = self.size();
-        let mut point = [0.0; D];
+        let mut point = [S::zero(); D];
         for i in 0..D {
             point[i] = self.min[i] + normalized_point[i] * size[i];
         }
@@ -345,11 +475,11 @@ impl<const D: usize> BoundingBox<D> {
         point
     }
 
-    pub fn point_to_normalized(&self, point: impl Into<Point<D>>) -> Point<D> {
+    pub fn point_to_normalized(&self, point: impl Into<Point<D, S>>) -> Point<D, S> {
         let point = point.into();
 
         let size = self.size();
-        let mut normalized_point = [0.0; D];
+        let mut normalized_point = [S::zero(); D];
         for i in 0..D {
             normalized_point[i] = (point[i] - self.min[i]) / size[i];
         }
@@ -358,10 +488,12 @@ impl<const D: usize> BoundingBox<D> {
     }
 }
 
+#[cfg(feature = "client")]
 macro_rules! bbox {
     ($($position:expr),*) => {
         crate::shared::bounding_box::BoundingBox::new([$($position,)*].into_iter())
     };
 }
 
+#[cfg(feature = "client")]
 pub(crate) use bbox;
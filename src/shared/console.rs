@@ -0,0 +1,82 @@
+use lazy_static::lazy_static;
+use log::{Level, Log, Metadata, Record};
+use std::{collections::VecDeque, sync::Mutex};
+
+/// The most console messages kept around before anyone drains them.
+pub const MAX_CAPTURED_MESSAGES: usize = 256;
+/// How many log lines (at any level that gets logged at all) are kept for crash reports.
+pub const MAX_RECENT_LOG_LINES: usize = 200;
+
+lazy_static! {
+    static ref CAPTURED_MESSAGES: Mutex<VecDeque<(Level, String)>> = Default::default();
+    static ref RECENT_LOG_LINES: Mutex<VecDeque<String>> = Default::default();
+}
+
+/// Wraps another logger, keeping a copy of everything at or above `capture_level` so it can be shown in-game.
+#[derive(Debug)]
+pub struct CapturingLogger<L: Log> {
+    inner: L,
+    capture_level: Level,
+}
+
+impl<L: Log> CapturingLogger<L> {
+    pub fn new(inner: L, capture_level: Level) -> Self {
+        Self {
+            inner,
+            capture_level,
+        }
+    }
+}
+
+impl<L: Log> Log for CapturingLogger<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.inner.enabled(record.metadata()) {
+            if let Ok(mut lines) = RECENT_LOG_LINES.lock() {
+                if lines.len() >= MAX_RECENT_LOG_LINES {
+                    lines.pop_front();
+                }
+                lines.push_back(format!(
+                    "[{} {}] {}",
+                    record.level(),
+                    record.target(),
+                    record.args()
+                ));
+            }
+        }
+        if record.level() <= self.capture_level && self.inner.enabled(record.metadata()) {
+            if let Ok(mut messages) = CAPTURED_MESSAGES.lock() {
+                if messages.len() >= MAX_CAPTURED_MESSAGES {
+                    messages.pop_front();
+                }
+                messages.push_back((record.level(), record.args().to_string()));
+            }
+        }
+
+        self.inner.log(record);
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Takes every console message captured since the last call.
+pub fn drain_captured_messages() -> Vec<(Level, String)> {
+    CAPTURED_MESSAGES
+        .lock()
+        .map(|mut messages| messages.drain(..).collect())
+        .unwrap_or_default()
+}
+
+/// The last [`MAX_RECENT_LOG_LINES`] lines logged, oldest first. Unlike [`drain_captured_messages()`], this leaves
+/// them there.
+pub fn recent_log_lines() -> Vec<String> {
+    match RECENT_LOG_LINES.lock() {
+        Ok(lines) => lines.iter().cloned().collect(),
+        Err(poisoned) => poisoned.into_inner().iter().cloned().collect(),
+    }
+}
@@ -0,0 +1,200 @@
+use super::{console::recent_log_lines, version::APP_VERSION};
+use lazy_static::lazy_static;
+use log::error;
+use std::{
+    backtrace::Backtrace,
+    fmt::Write as _,
+    fs,
+    panic::{self, PanicHookInfo},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::Mutex,
+    thread,
+    time::SystemTime,
+};
+
+pub const CRASH_REPORTS_PATH: &str = "crash_reports";
+
+/// What the app was up to, kept up to date as it goes so it can be put in a crash report.
+#[derive(Debug, Clone, Default)]
+pub struct CrashContext {
+    /// E.g. "client", "headless" or "server".
+    pub mode: &'static str,
+    pub adapter: Option<String>,
+    pub scenario: Option<String>,
+    pub entity_count: usize,
+    pub coord_time: f64,
+    pub paused: bool,
+    pub multiplayer: Option<String>,
+}
+
+lazy_static! {
+    static ref CRASH_CONTEXT: Mutex<CrashContext> = Default::default();
+}
+
+/// Changes what goes in a crash report.
+pub fn update_crash_context(update: impl FnOnce(&mut CrashContext)) {
+    if let Ok(mut context) = CRASH_CONTEXT.lock() {
+        update(&mut context);
+    }
+}
+
+/// Makes panics write a report to [`CRASH_REPORTS_PATH`] on top of the usual message on stderr. With
+/// `show_message_box`, a panic on the main thread also tells the user where the report went.
+pub fn install_crash_handler(show_message_box: bool) {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+
+        // background tasks catch their panics and carry on, so only the main thread going down is a real crash
+        let show_message_box = show_message_box && thread::current().name() == Some("main");
+        let report = crash_report(info);
+        match write_crash_report(&report) {
+            Ok(path) => {
+                error!("Wrote a crash report to {}", path.display());
+                if show_message_box {
+                    show_crash_message(&format!(
+                        "Worldline crashed: {}\n\nA crash report was written to {}",
+                        panic_message(info),
+                        path.display()
+                    ));
+                }
+            }
+            Err(write_error) => {
+                error!("Couldn't write a crash report: {}", write_error);
+                if show_message_box {
+                    show_crash_message(&format!("Worldline crashed: {}", panic_message(info)));
+                }
+            }
+        }
+    }));
+}
+
+fn panic_message(info: &PanicHookInfo) -> String {
+    let payload = info.payload();
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "(no message)".to_owned());
+    match info.location() {
+        Some(location) => format!("{message} ({location})"),
+        None => message,
+    }
+}
+
+fn crash_report(info: &PanicHookInfo) -> String {
+    // a panic while the lock was held shouldn't stop the report from going out
+    let context = match CRASH_CONTEXT.lock() {
+        Ok(context) => context.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    };
+
+    let mut report = String::new();
+    let _ = writeln!(report, "Worldline v{APP_VERSION} crashed");
+    let _ = writeln!(
+        report,
+        "thread '{}' panicked: {}",
+        thread::current().name().unwrap_or("<unnamed>"),
+        panic_message(info)
+    );
+    let _ = writeln!(
+        report,
+        "os: {} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+
+    let _ = writeln!(report, "\n== context ==");
+    let _ = writeln!(report, "mode: {}", context.mode);
+    let _ = writeln!(
+        report,
+        "adapter: {}",
+        context.adapter.as_deref().unwrap_or("none")
+    );
+    let _ = writeln!(
+        report,
+        "scenario: {}",
+        context.scenario.as_deref().unwrap_or("none")
+    );
+    let _ = writeln!(
+        report,
+        "universe: {} entities, coordinate time {:.3}s{}",
+        context.entity_count,
+        context.coord_time,
+        if context.paused { ", paused" } else { "" }
+    );
+    let _ = writeln!(
+        report,
+        "multiplayer: {}",
+        context.multiplayer.as_deref().unwrap_or("no")
+    );
+
+    let _ = writeln!(report, "\n== backtrace ==");
+    let _ = writeln!(report, "{}", Backtrace::force_capture());
+
+    let _ = writeln!(report, "\n== log ==");
+    for line in recent_log_lines() {
+        let _ = writeln!(report, "{line}");
+    }
+
+    report
+}
+
+fn write_crash_report(report: &str) -> std::io::Result<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    fs::create_dir_all(CRASH_REPORTS_PATH)?;
+    let path = Path::new(CRASH_REPORTS_PATH).join(format!("crash-{timestamp}.txt"));
+    fs::write(&path, report)?;
+    Ok(path.canonicalize().unwrap_or(path))
+}
+
+/// There's no window left to draw in, so this leans on whatever the OS has for popping up a dialog. If none of it
+/// is there, the message on stderr will have to do.
+fn show_crash_message(message: &str) {
+    let result = if cfg!(target_os = "windows") {
+        let message = message.replace('\'', "''");
+        Command::new("powershell")
+            .args([
+                "-NoProfile",
+                "-Command",
+                &format!(
+                    "Add-Type -AssemblyName PresentationFramework; \
+                     [System.Windows.MessageBox]::Show('{message}', 'Worldline', 'OK', 'Error')"
+                ),
+            ])
+            .status()
+    } else if cfg!(target_os = "macos") {
+        let message = message.replace('\\', "\\\\").replace('"', "\\\"");
+        Command::new("osascript")
+            .args([
+                "-e",
+                &format!(
+                    "display dialog \"{message}\" with title \"Worldline\" buttons {{\"OK\"}} with icon stop"
+                ),
+            ])
+            .status()
+    } else {
+        Command::new("zenity")
+            .args([
+                "--error",
+                "--title=Worldline",
+                "--no-markup",
+                "--text",
+                message,
+            ])
+            .status()
+            .or_else(|_| {
+                Command::new("kdialog")
+                    .args(["--title", "Worldline", "--error", message])
+                    .status()
+            })
+    };
+
+    if let Err(error) = result {
+        error!("Couldn't show the crash message: {}", error);
+    }
+}
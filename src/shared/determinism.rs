@@ -0,0 +1,57 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::sync::Mutex;
+
+/// How long every frame is treated as taking in deterministic mode, no matter how long it really took.
+pub const FIXED_FRAME_TIME: f64 = 1.0 / 60.0;
+
+/// Hands out random IDs. Unseeded, they come from the thread RNG. Once seeded, the same IDs come out in the same
+/// order every run, which is what keeps replays, input playback and (one day) multiplayer peers in agreement about
+/// which entity is which.
+///
+/// Each kind of ID draws from its own stream, so e.g. opening a menu doesn't shift every entity ID after it.
+#[derive(Debug)]
+pub struct IdGenerator {
+    stream: u64,
+    rng: Mutex<Option<ChaCha8Rng>>,
+}
+
+impl IdGenerator {
+    pub const fn new(stream: u64) -> Self {
+        Self {
+            stream,
+            rng: Mutex::new(None),
+        }
+    }
+
+    /// Restarts the sequence from `seed`, or goes back to being unseeded with `None`.
+    pub fn seed(&self, seed: Option<u64>) {
+        *self.rng.lock().unwrap() = seed.map(|seed| {
+            // ChaCha is portable, so seeds give the same IDs on every platform and rand version
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            rng.set_stream(self.stream);
+            rng
+        });
+    }
+
+    pub fn is_seeded(&self) -> bool {
+        self.rng.lock().unwrap().is_some()
+    }
+
+    pub fn next_id(&self) -> u128 {
+        match self.rng.lock().unwrap().as_mut() {
+            Some(rng) => rng.gen(),
+            None => rand::random(),
+        }
+    }
+}
+
+pub static ENTITY_IDS: IdGenerator = IdGenerator::new(0);
+pub static GUI_COMPONENT_IDS: IdGenerator = IdGenerator::new(1);
+
+/// Seeds (or unseeds) every [`IdGenerator`]. Has to happen before anything generates an ID to be of any use.
+pub fn seed_ids(seed: Option<u64>) {
+    for generator in [&ENTITY_IDS, &GUI_COMPONENT_IDS] {
+        generator.seed(seed);
+    }
+}
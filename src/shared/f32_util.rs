@@ -47,3 +47,17 @@ impl AddWithEpsilon for f32 {
         }
     }
 }
+
+impl AddWithEpsilon for f64 {
+    fn add_with_epsilon(self, rhs: Self) -> Self {
+        if rhs.is_zero() {
+            return self;
+        }
+
+        rhs + if rhs.is_sign_positive() {
+            self.next_up()
+        } else {
+            self.next_down()
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use serde::{Deserialize, Serialize};
+
+/// A gamepad button, named by position (Xbox-style layouts have A at [`GamepadButton::South`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+    /// The left stick pushed past [`STICK_PRESS_THRESHOLD`] in a direction, so it can be used like the D-pad.
+    LeftStickUp,
+    LeftStickDown,
+    LeftStickLeft,
+    LeftStickRight,
+}
+
+/// How far the left stick has to be pushed, after the dead zone, to count as pressing one of the
+/// [`GamepadButton::LeftStickUp`] and similar buttons.
+pub const STICK_PRESS_THRESHOLD: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum GamepadAxis {
+    /// Right is positive.
+    LeftStickX,
+    /// Up is positive.
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    /// From `0.0` (released) to `1.0` (fully pressed).
+    LeftTrigger,
+    RightTrigger,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GamepadSettings {
+    /// Axis values with a smaller magnitude than this are treated as zero, to hide stick drift.
+    pub dead_zone: f32,
+    /// Degrees per second of rotation at full right stick.
+    pub look_sensitivity: f32,
+    pub invert_look_y: bool,
+}
+
+impl Default for GamepadSettings {
+    fn default() -> Self {
+        Self {
+            dead_zone: 0.15,
+            look_sensitivity: 120.0,
+            invert_look_y: false,
+        }
+    }
+}
+
+impl GamepadSettings {
+    /// Zeroes out the dead zone, and rescales the rest so the output still smoothly goes from `0.0` to `1.0`.
+    pub fn apply_dead_zone(&self, value: f32) -> f32 {
+        let magnitude = value.abs();
+        if magnitude <= self.dead_zone {
+            return 0.0;
+        }
+
+        value.signum() * ((magnitude - self.dead_zone) / (1.0 - self.dead_zone)).min(1.0)
+    }
+}
+
+#[cfg(feature = "gamepad")]
+pub use poller::GamepadPoller;
+
+#[cfg(feature = "gamepad")]
+mod poller {
+    use super::{GamepadAxis, GamepadButton};
+    use crate::shared::input::InputController;
+    use gilrs::{Axis, Button, EventType, Gilrs};
+    use log::{info, warn};
+
+    /// Reads gamepad events with gilrs and feeds them into an [`InputController`].
+    #[derive(Debug)]
+    pub struct GamepadPoller {
+        gilrs: Gilrs,
+    }
+
+    impl GamepadPoller {
+        pub fn new() -> Option<Self> {
+            match Gilrs::new() {
+                Ok(gilrs) => Some(Self { gilrs }),
+                Err(error) => {
+                    warn!("Gamepad support is unavailable: {}", error);
+                    None
+                }
+            }
+        }
+
+        fn convert_button(button: Button) -> Option<GamepadButton> {
+            Some(match button {
+                Button::South => GamepadButton::South,
+                Button::East => GamepadButton::East,
+                Button::North => GamepadButton::North,
+                Button::West => GamepadButton::West,
+                Button::LeftTrigger => GamepadButton::LeftBumper,
+                Button::RightTrigger => GamepadButton::RightBumper,
+                Button::LeftTrigger2 => GamepadButton::LeftTrigger,
+                Button::RightTrigger2 => GamepadButton::RightTrigger,
+                Button::Select => GamepadButton::Select,
+                Button::Start => GamepadButton::Start,
+                Button::LeftThumb => GamepadButton::LeftStick,
+                Button::RightThumb => GamepadButton::RightStick,
+                Button::DPadUp => GamepadButton::DPadUp,
+                Button::DPadDown => GamepadButton::DPadDown,
+                Button::DPadLeft => GamepadButton::DPadLeft,
+                Button::DPadRight => GamepadButton::DPadRight,
+                _ => return None,
+            })
+        }
+
+        fn convert_axis(axis: Axis) -> Option<GamepadAxis> {
+            Some(match axis {
+                Axis::LeftStickX => GamepadAxis::LeftStickX,
+                Axis::LeftStickY => GamepadAxis::LeftStickY,
+                Axis::RightStickX => GamepadAxis::RightStickX,
+                Axis::RightStickY => GamepadAxis::RightStickY,
+                _ => return None,
+            })
+        }
+
+        /// Call once per frame, before anything reads the input controller.
+        pub fn poll(&mut self, input_controller: &mut InputController) {
+            while let Some(event) = self.gilrs.next_event() {
+                match event.event {
+                    EventType::ButtonPressed(button, _) => {
+                        if let Some(button) = Self::convert_button(button) {
+                            input_controller.gamepad_button_event(button, true);
+                        }
+                    }
+                    EventType::ButtonReleased(button, _) => {
+                        if let Some(button) = Self::convert_button(button) {
+                            input_controller.gamepad_button_event(button, false);
+                        }
+                    }
+                    // analog triggers show up as buttons with a value
+                    EventType::ButtonChanged(Button::LeftTrigger2, value, _) => {
+                        input_controller.gamepad_axis_event(GamepadAxis::LeftTrigger, value);
+                    }
+                    EventType::ButtonChanged(Button::RightTrigger2, value, _) => {
+                        input_controller.gamepad_axis_event(GamepadAxis::RightTrigger, value);
+                    }
+                    EventType::AxisChanged(axis, value, _) => {
+                        if let Some(axis) = Self::convert_axis(axis) {
+                            input_controller.gamepad_axis_event(axis, value);
+                        }
+                    }
+                    EventType::Connected => {
+                        info!("Gamepad connected: {}", self.gilrs.gamepad(event.id).name());
+                    }
+                    EventType::Disconnected => {
+                        info!("Gamepad disconnected");
+                        input_controller.reset_gamepad();
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
@@ -122,6 +122,7 @@ where
             vertices: &self.vertices,
             instances: None,
             indices: Some(&self.indices),
+            push_constants: &[],
         }
     }
 }
@@ -1,20 +1,34 @@
-use crate::{app_state::WinitEvent, gui::component::GuiComponentId, shared::bounding_box::BBox2};
+use crate::{
+    app_state::WinitEvent,
+    gui::{component::GuiComponentId, text::TextInteractionId},
+    shared::{
+        action::{Action, ActionBindings},
+        bounding_box::BBox2,
+        gamepad::{GamepadAxis, GamepadButton, GamepadSettings, STICK_PRESS_THRESHOLD},
+        input_recording::{InputRecorder, RawInputEvent},
+        mouse_gesture::MouseGestureTracker,
+        navigation::{pick_navigation_target, NavigationInput},
+    },
+};
 use cgmath::{vec2, Vector2};
 use derive_more::*;
-use linear_map::set::LinearSet;
+use linear_map::{set::LinearSet, LinearMap};
+use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
+use std::collections::BTreeMap;
 use winit::{
     dpi::PhysicalPosition,
     event::{DeviceEvent, Ime, MouseButton, MouseScrollDelta, WindowEvent},
-    keyboard::{Key, NamedKey},
+    keyboard::{Key, KeyCode, NamedKey, PhysicalKey},
     platform::modifier_supplement::KeyEventExtModifierSupplement,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq, From)]
+#[derive(Debug, Clone, PartialEq, Eq, From, Serialize, Deserialize)]
 pub enum Input {
     CharacterKey(SmolStr),
     NamedKey(NamedKey),
     MouseButton(MouseButton),
+    GamepadButton(GamepadButton),
 }
 
 impl From<&str> for Input {
@@ -35,49 +49,212 @@ impl From<&String> for Input {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ModifierKey {
+    Shift,
+    Control,
+    Alt,
+    Super,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum KeySide {
+    Left,
+    Right,
+}
+
+/// A modifier that has to be held for a [`KeyChord`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Modifier {
+    pub key: ModifierKey,
+    /// `None` means either side works.
+    pub side: Option<KeySide>,
+}
+
+impl From<ModifierKey> for Modifier {
+    fn from(key: ModifierKey) -> Self {
+        Self { key, side: None }
+    }
+}
+
+impl Modifier {
+    pub fn new(key: ModifierKey, side: KeySide) -> Self {
+        Self {
+            key,
+            side: Some(side),
+        }
+    }
+
+    fn from_key_code(key_code: KeyCode) -> Option<(ModifierKey, KeySide)> {
+        Some(match key_code {
+            KeyCode::ShiftLeft => (ModifierKey::Shift, KeySide::Left),
+            KeyCode::ShiftRight => (ModifierKey::Shift, KeySide::Right),
+            KeyCode::ControlLeft => (ModifierKey::Control, KeySide::Left),
+            KeyCode::ControlRight => (ModifierKey::Control, KeySide::Right),
+            KeyCode::AltLeft => (ModifierKey::Alt, KeySide::Left),
+            KeyCode::AltRight => (ModifierKey::Alt, KeySide::Right),
+            KeyCode::SuperLeft => (ModifierKey::Super, KeySide::Left),
+            KeyCode::SuperRight => (ModifierKey::Super, KeySide::Right),
+            _ => return None,
+        })
+    }
+}
+
+/// An input that only counts while some modifiers are held, like Ctrl+Shift+S. A chord without modifiers is just
+/// a plain input, and doesn't care about which modifiers are held.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyChord {
+    pub modifiers: Vec<Modifier>,
+    pub input: Input,
+}
+
+impl KeyChord {
+    pub fn new(
+        modifiers: impl IntoIterator<Item = impl Into<Modifier>>,
+        input: impl Into<Input>,
+    ) -> Self {
+        Self {
+            modifiers: modifiers.into_iter().map(Into::into).collect(),
+            input: input.into(),
+        }
+    }
+}
+
+macro_rules! chord_from {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for KeyChord {
+                fn from(value: $t) -> Self {
+                    Self {
+                        modifiers: Vec::new(),
+                        input: value.into(),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+chord_from!(Input, &str, NamedKey, MouseButton, GamepadButton);
+
+/// Timing for held keys repeating, which the [`InputController`] handles itself instead of trusting the OS.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyRepeatSettings {
+    /// Seconds a key has to be held before it starts repeating.
+    pub initial_delay: f64,
+    /// Repeats per second after that. Zero turns repeating off.
+    pub rate: f64,
+}
+
+impl Default for KeyRepeatSettings {
+    fn default() -> Self {
+        Self {
+            initial_delay: 0.4,
+            rate: 25.0,
+        }
+    }
+}
+
+impl KeyRepeatSettings {
+    /// How many times a key held for `held_time` seconds has repeated.
+    pub fn repeats_after(&self, held_time: f64) -> u64 {
+        if self.rate <= 0.0 || held_time < self.initial_delay {
+            return 0;
+        }
+
+        ((held_time - self.initial_delay) * self.rate) as u64 + 1
+    }
+}
+
 #[derive(Debug)]
 pub struct InputController {
     held_inputs: LinearSet<Input>,
+    held_modifiers: LinearSet<(ModifierKey, KeySide)>,
     pressed_inputs: LinearSet<Input>,
     pressed_or_repeated_inputs: LinearSet<Input>,
     released_inputs: LinearSet<Input>,
+    /// How long each repeatable input has been held for.
+    repeat_timers: LinearMap<Input, f64>,
 
     mouse_delta: Vector2<f32>,
+    mouse_gestures: MouseGestureTracker,
     scroll_delta: f32,
     cursor_position: Vector2<f32>,
     cursor_in_window: bool,
 
     just_typed: String,
+    preedit_text: String,
+    preedit_cursor: Option<(usize, usize)>,
     focused_component_id: Option<GuiComponentId>,
     contested_hover: Option<(GuiComponentId, BBox2)>,
     hovered_component_id: Option<GuiComponentId>,
+    contested_scroll: Option<GuiComponentId>,
+    scroll_capture_id: Option<GuiComponentId>,
+    contested_text_span: Option<TextInteractionId>,
+    hovered_text_span: Option<TextInteractionId>,
     in_a_menu_next: bool,
     in_a_menu: bool,
 
+    navigation_inputs: LinearSet<NavigationInput>,
+    navigable_components_next: Vec<(GuiComponentId, BBox2)>,
+    navigable_components: Vec<(GuiComponentId, BBox2)>,
+    nav_selected_component_id: Option<GuiComponentId>,
+
+    gamepad_axes: BTreeMap<GamepadAxis, f32>,
+
+    recorder: Option<InputRecorder>,
+    playing_back: bool,
+
     pub force_mouse_unlock: bool,
+    pub bindings: ActionBindings,
+    pub gamepad_settings: GamepadSettings,
+    pub key_repeat: KeyRepeatSettings,
 }
 
 impl Default for InputController {
     fn default() -> Self {
         Self {
             held_inputs: Default::default(),
+            held_modifiers: Default::default(),
             pressed_inputs: Default::default(),
             released_inputs: Default::default(),
             pressed_or_repeated_inputs: Default::default(),
+            repeat_timers: Default::default(),
 
             mouse_delta: vec2(0.0, 0.0),
+            mouse_gestures: Default::default(),
             scroll_delta: 0.0,
             cursor_position: vec2(0.0, 0.0),
             cursor_in_window: false,
 
             just_typed: Default::default(),
+            preedit_text: Default::default(),
+            preedit_cursor: None,
             focused_component_id: None,
             contested_hover: None,
             hovered_component_id: None,
+            contested_scroll: None,
+            scroll_capture_id: None,
+            contested_text_span: None,
+            hovered_text_span: None,
             in_a_menu_next: false,
             in_a_menu: false,
 
+            navigation_inputs: Default::default(),
+            navigable_components_next: Vec::new(),
+            navigable_components: Vec::new(),
+            nav_selected_component_id: None,
+
+            gamepad_axes: BTreeMap::new(),
+
+            recorder: None,
+            playing_back: false,
+
             force_mouse_unlock: true,
+            bindings: Default::default(),
+            gamepad_settings: Default::default(),
+            key_repeat: Default::default(),
         }
     }
 }
@@ -117,6 +294,87 @@ impl InputController {
     consume!(consume_pressed_or_released, pressed_or_repeated_inputs);
     consume!(consume_released, released_inputs);
 
+    /// Whether the modifier is held, on whichever side(s) it asks for.
+    pub fn modifier_held(&self, modifier: Modifier) -> bool {
+        match modifier.side {
+            Some(side) => self.held_modifiers.contains(&(modifier.key, side)),
+            None => {
+                self.held_modifiers.contains(&(modifier.key, KeySide::Left))
+                    || self
+                        .held_modifiers
+                        .contains(&(modifier.key, KeySide::Right))
+            }
+        }
+    }
+
+    fn chord_modifiers_held(&self, chord: &KeyChord) -> bool {
+        chord
+            .modifiers
+            .iter()
+            .all(|&modifier| self.modifier_held(modifier))
+    }
+
+    /// Whether a chord for some other action on the same input, with more modifiers, is currently being held.
+    /// This is what stops S from firing along with Ctrl+Shift+S.
+    fn chord_is_shadowed(&self, action: Action, chord: &KeyChord) -> bool {
+        self.bindings
+            .bindings
+            .iter()
+            .filter(|(&other_action, _)| other_action != action)
+            .flat_map(|(_, chords)| chords.iter())
+            .any(|other_chord| {
+                other_chord.input == chord.input
+                    && other_chord.modifiers.len() > chord.modifiers.len()
+                    && self.chord_modifiers_held(other_chord)
+            })
+    }
+
+    fn action_in(&self, action: Action, inputs: &LinearSet<Input>) -> bool {
+        self.bindings.chords(action).iter().any(|chord| {
+            // typing into a text box shouldn't set off whatever the keys are bound to, shortcuts like Ctrl+V
+            // included, since the text box has its own uses for those
+            let typing = self.focused_component_id.is_some()
+                && matches!(chord.input, Input::CharacterKey(_) | Input::NamedKey(_));
+            !typing
+                && inputs.contains(&chord.input)
+                && self.chord_modifiers_held(chord)
+                && !self.chord_is_shadowed(action, chord)
+        })
+    }
+
+    /// Whether any chord bound to the action is held.
+    pub fn action_held(&self, action: Action) -> bool {
+        self.action_in(action, &self.held_inputs)
+    }
+
+    /// Whether any chord bound to the action was just pressed.
+    pub fn action_pressed(&self, action: Action) -> bool {
+        self.action_in(action, &self.pressed_inputs)
+    }
+
+    /// Every action with a chord that's held, in no particular order.
+    pub fn held_actions(&self) -> Vec<Action> {
+        self.bindings
+            .bindings
+            .keys()
+            .copied()
+            .filter(|&action| self.action_held(action))
+            .collect()
+    }
+
+    pub fn held_modifiers(&self) -> impl Iterator<Item = (ModifierKey, KeySide)> + '_ {
+        self.held_modifiers.iter().copied()
+    }
+
+    /// Whether an input bound to the action was just released, and no other chord is still holding it down.
+    pub fn action_released(&self, action: Action) -> bool {
+        self.bindings
+            .chords(action)
+            .iter()
+            .any(|chord| self.released_inputs.contains(&chord.input))
+            && !self.action_held(action)
+    }
+
     pub fn consume_input(&mut self, input: impl Into<Input>) -> bool {
         let input = input.into();
 
@@ -150,24 +408,100 @@ impl InputController {
         self.mouse_delta
     }
 
+    /// Takes this frame's mouse movement so nothing else (e.g. the camera) sees it.
+    pub fn consume_mouse_delta(&mut self) -> Vector2<f32> {
+        std::mem::replace(&mut self.mouse_delta, vec2(0.0, 0.0))
+    }
+
+    /// Whether the button was just pressed as the second click of a double click (or more).
+    pub fn double_clicked(&self, button: MouseButton) -> bool {
+        self.click_count(button) >= 2
+    }
+
+    /// If the button was just pressed, how many quick clicks in a row it's been (1 for a single click, 2 for a
+    /// double click, 3 for a triple click, ...). Otherwise 0.
+    pub fn click_count(&self, button: MouseButton) -> u32 {
+        self.mouse_gestures.press_click_count(button).unwrap_or(0)
+    }
+
+    /// Whether the button was just released without having been dragged.
+    pub fn clicked(&self, button: MouseButton) -> bool {
+        self.mouse_gestures.clicked(button)
+    }
+
+    /// Whether the button is held and the mouse has moved far enough to count as a drag.
+    pub fn dragging(&self, button: MouseButton) -> bool {
+        self.mouse_gestures.dragging(button)
+    }
+
+    pub fn drag_started(&self, button: MouseButton) -> bool {
+        self.mouse_gestures.drag_started(button)
+    }
+
+    pub fn drag_ended(&self, button: MouseButton) -> bool {
+        self.mouse_gestures.drag_ended(button)
+    }
+
+    /// Where the cursor was when the button was pressed, if it's held.
+    pub fn press_origin(&self, button: MouseButton) -> Option<Vector2<f32>> {
+        self.mouse_gestures.press_origin(button)
+    }
+
+    /// How far the mouse has moved since the button was pressed, if it's held. Works while the mouse is locked.
+    pub fn drag_travel(&self, button: MouseButton) -> Option<Vector2<f32>> {
+        self.mouse_gestures.drag_travel(button)
+    }
+
     pub fn cursor_position(&self) -> Vector2<f32> {
         self.cursor_position
     }
 
+    /// The raw scroll wheel movement this frame, in lines, regardless of which component (if any) captured it.
     pub fn scroll_delta(&self) -> f32 {
         self.scroll_delta
     }
 
+    /// The scroll wheel movement this frame if the component captured it with
+    /// [`InputController::contest_scroll()`], otherwise zero.
+    pub fn component_scroll_delta(&self, id: GuiComponentId) -> f32 {
+        if self.scroll_capture_id == Some(id) {
+            self.scroll_delta
+        } else {
+            0.0
+        }
+    }
+
+    /// The scroll wheel movement this frame if no component captured it, for things like the throttle.
+    pub fn uncaptured_scroll_delta(&self) -> f32 {
+        if self.scroll_capture_id.is_none() {
+            self.scroll_delta
+        } else {
+            0.0
+        }
+    }
+
     pub fn just_typed(&self) -> &str {
         &self.just_typed
     }
 
+    /// The in-progress IME composition text, which hasn't been committed yet.
+    pub fn preedit_text(&self) -> &str {
+        &self.preedit_text
+    }
+
+    /// Byte range of the IME's cursor/selection within [`InputController::preedit_text()`], if the IME
+    /// wants one to be shown.
+    pub fn preedit_cursor(&self) -> Option<(usize, usize)> {
+        self.preedit_cursor
+    }
+
     pub fn emulate_just_typed(&mut self, text: &str) {
         self.just_typed.push_str(text);
     }
 
     pub fn clear_inputs(&mut self) {
         self.mouse_delta = vec2(0.0, 0.0);
+        self.mouse_gestures.clear();
         self.scroll_delta = 0.0;
 
         self.pressed_inputs.clear();
@@ -177,8 +511,22 @@ impl InputController {
         self.just_typed.clear();
 
         self.hovered_component_id = self.contested_hover.take().map(|(id, _)| id);
+        self.scroll_capture_id = self.contested_scroll.take();
+        self.hovered_text_span = self.contested_text_span.take();
         self.in_a_menu = self.in_a_menu_next;
         self.in_a_menu_next = false;
+
+        self.navigation_inputs.clear();
+        self.navigable_components = std::mem::take(&mut self.navigable_components_next);
+        if let Some(selected_id) = self.nav_selected_component_id {
+            if !self
+                .navigable_components
+                .iter()
+                .any(|(id, _)| *id == selected_id)
+            {
+                self.nav_selected_component_id = None;
+            }
+        }
     }
 
     pub fn focused_component_id(&self) -> Option<GuiComponentId> {
@@ -229,97 +577,447 @@ impl InputController {
         self.contested_hover = Some((id, bounding_box));
     }
 
+    pub fn hovered_component_id(&self) -> Option<GuiComponentId> {
+        self.hovered_component_id
+    }
+
     pub fn component_is_hovered(&self, id: GuiComponentId) -> bool {
         self.hovered_component_id == Some(id)
     }
 
+    /// Claims the scroll wheel while the cursor is within `bounding_box`. Like hover contesting, the last
+    /// component to contest wins, and it takes effect on the next frame.
+    pub fn contest_scroll(&mut self, id: GuiComponentId, bounding_box: BBox2) {
+        if !self.cursor_in_window || self.is_mouse_locked() {
+            return;
+        }
+        if !bounding_box.point_is_within(self.cursor_position) {
+            return;
+        }
+
+        self.contested_scroll = Some(id);
+    }
+
+    pub fn scroll_capture_id(&self) -> Option<GuiComponentId> {
+        self.scroll_capture_id
+    }
+
+    pub fn contest_text_span_hover(&mut self, id: TextInteractionId, bounding_box: BBox2) {
+        if !self.cursor_in_window || self.is_mouse_locked() {
+            return;
+        }
+        if !bounding_box.point_is_within(self.cursor_position) {
+            return;
+        }
+
+        self.contested_text_span = Some(id);
+    }
+
+    pub fn hovered_text_span(&self) -> Option<TextInteractionId> {
+        self.hovered_text_span
+    }
+
+    pub fn text_span_is_hovered(&self, id: TextInteractionId) -> bool {
+        self.hovered_text_span == Some(id)
+    }
+
     pub fn report_in_a_menu(&mut self) {
         self.in_a_menu_next = true;
     }
 
+    /// Registers a component as something that can be selected with [`NavigationInput`]s. Like hover contesting,
+    /// this has to be done every frame, and takes effect on the next frame.
+    pub fn register_navigable(&mut self, id: GuiComponentId, bounding_box: BBox2) {
+        self.navigable_components_next.push((id, bounding_box));
+    }
+
+    /// Feeds in a navigation input, moving the selection immediately if it's directional.
+    pub fn push_navigation(&mut self, input: NavigationInput) {
+        if let Some(direction) = input.direction() {
+            if let Some(target) = pick_navigation_target(
+                &self.navigable_components,
+                self.nav_selected_component_id,
+                direction,
+            ) {
+                self.nav_selected_component_id = Some(target);
+            }
+        }
+        self.navigation_inputs.insert(input);
+    }
+
+    /// Counts held keys and gamepad buttons towards repeating, and marks the ones that repeat this frame as
+    /// [`InputController::pressed_or_repeated()`]. Should be called once per frame with that frame's delta time,
+    /// after its events have come in.
+    pub fn advance_key_repeat(&mut self, delta: f64) {
+        let mut repeated = Vec::new();
+        for (input, held_time) in self.repeat_timers.iter_mut() {
+            // the timer starts on the frame after the press
+            if self.pressed_inputs.contains(input) {
+                continue;
+            }
+
+            let old_held_time = *held_time;
+            *held_time += delta;
+            if self.key_repeat.repeats_after(*held_time)
+                > self.key_repeat.repeats_after(old_held_time)
+            {
+                repeated.push(input.clone());
+            }
+        }
+
+        for input in repeated {
+            // only directions repeat in menus, activating something over and over would be bad
+            if let Some(navigation) = self
+                .menu_navigation_for(&input)
+                .filter(|navigation| navigation.direction().is_some())
+            {
+                self.push_navigation(navigation);
+            }
+            self.pressed_or_repeated_inputs.insert(input);
+        }
+    }
+
+    /// The menu navigation that pressing `input` does right now, if any.
+    fn menu_navigation_for(&self, input: &Input) -> Option<NavigationInput> {
+        // a text box or something wants the keys
+        if !self.in_a_menu || self.focused_component_id.is_some() {
+            return None;
+        }
+
+        match input {
+            Input::NamedKey(NamedKey::ArrowUp)
+            | Input::GamepadButton(GamepadButton::DPadUp | GamepadButton::LeftStickUp) => {
+                Some(NavigationInput::Up)
+            }
+            Input::NamedKey(NamedKey::ArrowDown)
+            | Input::GamepadButton(GamepadButton::DPadDown | GamepadButton::LeftStickDown) => {
+                Some(NavigationInput::Down)
+            }
+            Input::NamedKey(NamedKey::ArrowLeft)
+            | Input::GamepadButton(GamepadButton::DPadLeft | GamepadButton::LeftStickLeft) => {
+                Some(NavigationInput::Left)
+            }
+            Input::NamedKey(NamedKey::ArrowRight)
+            | Input::GamepadButton(GamepadButton::DPadRight | GamepadButton::LeftStickRight) => {
+                Some(NavigationInput::Right)
+            }
+            Input::NamedKey(NamedKey::Enter) | Input::GamepadButton(GamepadButton::South) => {
+                Some(NavigationInput::Activate)
+            }
+            Input::NamedKey(NamedKey::Escape) | Input::GamepadButton(GamepadButton::East) => {
+                Some(NavigationInput::Back)
+            }
+            _ => None,
+        }
+    }
+
+    /// Registers a key or gamepad button being pressed.
+    fn press_repeatable(&mut self, input: Input) {
+        if let Some(navigation) = self.menu_navigation_for(&input) {
+            self.push_navigation(navigation);
+        }
+
+        self.repeat_timers.insert(input.clone(), 0.0);
+        self.held_inputs.insert(input.clone());
+        self.pressed_inputs.insert(input.clone());
+        self.pressed_or_repeated_inputs.insert(input);
+    }
+
+    fn release_input(&mut self, input: Input) {
+        self.repeat_timers.remove(&input);
+        self.held_inputs.remove(&input);
+        self.released_inputs.insert(input);
+    }
+
+    /// Feeds in a gamepad button press or release. The D-pad and face buttons also navigate menus, and so does
+    /// pushing the left stick (see [`GamepadButton::LeftStickUp`]).
+    pub fn gamepad_button_event(&mut self, button: GamepadButton, pressed: bool) {
+        self.raw_event(RawInputEvent::GamepadButton { button, pressed });
+    }
+
+    pub fn gamepad_axis_event(&mut self, axis: GamepadAxis, value: f32) {
+        self.raw_event(RawInputEvent::GamepadAxis { axis, value });
+    }
+
+    fn apply_gamepad_button(&mut self, button: GamepadButton, pressed: bool) {
+        let input = Input::GamepadButton(button);
+
+        if pressed {
+            self.press_repeatable(input);
+        } else {
+            self.release_input(input);
+        }
+    }
+
+    /// Presses or releases the [`GamepadButton::LeftStickUp`] and similar buttons for `axis`, once it's crossed
+    /// [`STICK_PRESS_THRESHOLD`] either way.
+    fn update_stick_directions(&mut self, axis: GamepadAxis) {
+        let (negative, positive) = match axis {
+            GamepadAxis::LeftStickX => {
+                (GamepadButton::LeftStickLeft, GamepadButton::LeftStickRight)
+            }
+            GamepadAxis::LeftStickY => (GamepadButton::LeftStickDown, GamepadButton::LeftStickUp),
+            _ => return,
+        };
+        let value = self.gamepad_axis(axis);
+        for (button, pressed) in [
+            (negative, value <= -STICK_PRESS_THRESHOLD),
+            (positive, value >= STICK_PRESS_THRESHOLD),
+        ] {
+            if pressed != self.held_inputs.contains(&Input::GamepadButton(button)) {
+                self.apply_gamepad_button(button, pressed);
+            }
+        }
+    }
+
+    /// The value of a gamepad axis, with the dead zone applied.
+    pub fn gamepad_axis(&self, axis: GamepadAxis) -> f32 {
+        self.gamepad_settings
+            .apply_dead_zone(self.gamepad_axes.get(&axis).copied().unwrap_or(0.0))
+    }
+
+    /// Releases everything that's being held, e.g. when the window loses focus and won't hear about the
+    /// releases.
+    pub fn release_all(&mut self) {
+        self.raw_event(RawInputEvent::ReleaseAll);
+    }
+
+    fn apply_release_all(&mut self) {
+        for input in std::mem::take(&mut self.held_inputs) {
+            self.released_inputs.insert(input);
+        }
+        self.repeat_timers.clear();
+        self.held_modifiers.clear();
+        self.mouse_gestures.reset();
+    }
+
+    /// Releases every gamepad button and centers every axis, e.g. when the gamepad gets disconnected.
+    pub fn reset_gamepad(&mut self) {
+        self.raw_event(RawInputEvent::GamepadReset);
+    }
+
+    fn apply_reset_gamepad(&mut self) {
+        self.gamepad_axes.clear();
+
+        let gamepad_inputs = self
+            .held_inputs
+            .iter()
+            .filter(|input| matches!(input, Input::GamepadButton(_)))
+            .cloned()
+            .collect::<Vec<_>>();
+        for input in gamepad_inputs {
+            self.release_input(input);
+        }
+    }
+
+    pub fn navigation_pressed(&self, input: NavigationInput) -> bool {
+        self.navigation_inputs.contains(&input)
+    }
+
+    pub fn nav_selected_component_id(&self) -> Option<GuiComponentId> {
+        self.nav_selected_component_id
+    }
+
+    pub fn component_is_nav_selected(&self, id: GuiComponentId) -> bool {
+        self.nav_selected_component_id == Some(id)
+    }
+
+    /// Whether the component is selected and the activate input was just pressed.
+    pub fn component_nav_activated(&self, id: GuiComponentId) -> bool {
+        self.component_is_nav_selected(id) && self.navigation_pressed(NavigationInput::Activate)
+    }
+
     pub fn is_movement_suppressed(&self) -> bool {
         self.focused_component_id.is_some() || !self.is_mouse_locked()
     }
 
+    /// Starts recording every raw input event, see [`InputController::end_recorded_frame()`].
+    pub fn start_recording(&mut self, recorder: InputRecorder) {
+        self.recorder = Some(recorder);
+    }
+
+    pub fn stop_recording(&mut self) -> Option<InputRecorder> {
+        self.recorder.take()
+    }
+
+    pub fn recorder_mut(&mut self) -> Option<&mut InputRecorder> {
+        self.recorder.as_mut()
+    }
+
+    /// While playing back, real input from the window is ignored. Recorded events have to be fed in with
+    /// [`InputController::raw_event()`].
+    pub fn set_playing_back(&mut self, playing_back: bool) {
+        self.playing_back = playing_back;
+    }
+
+    pub fn is_playing_back(&self) -> bool {
+        self.playing_back
+    }
+
     pub fn winit_event(&mut self, winit_event: WinitEvent) {
-        match winit_event {
+        if self.playing_back {
+            return;
+        }
+
+        let raw_event = match winit_event {
             WinitEvent::Window(event) => match event {
                 WindowEvent::KeyboardInput { event, .. } => {
-                    if self.cursor_in_window {
-                        if let Some(ref text) = event.text {
-                            for character in text.chars() {
-                                self.just_typed.push(character);
-                            }
-                        }
-                    }
-
-                    let key = event.key_without_modifiers();
-                    let pressed = event.state.is_pressed();
-
-                    let input = match key {
-                        Key::Character(character) => Input::CharacterKey(character),
-                        Key::Named(named_key) => Input::NamedKey(named_key),
-                        _ => return,
+                    let input = match event.key_without_modifiers() {
+                        Key::Character(character) => Some(Input::CharacterKey(character)),
+                        Key::Named(named_key) => Some(Input::NamedKey(named_key)),
+                        _ => None,
+                    };
+                    // the logical key doesn't say which side a modifier is on
+                    let modifier = match event.physical_key {
+                        PhysicalKey::Code(key_code) => Modifier::from_key_code(key_code),
+                        _ => None,
                     };
 
-                    if pressed {
-                        if !self.cursor_in_window {
-                            return;
-                        }
-
-                        if !event.repeat {
-                            self.held_inputs.insert(input.clone());
-                            self.pressed_inputs.insert(input.clone());
-                        }
-                        self.pressed_or_repeated_inputs.insert(input);
-                    } else {
-                        self.held_inputs.remove(&input);
-                        self.released_inputs.insert(input);
+                    RawInputEvent::Key {
+                        input,
+                        modifier,
+                        pressed: event.state.is_pressed(),
+                        repeat: event.repeat,
+                        text: event.text.as_ref().map(|text| text.to_string()),
                     }
                 }
-                WindowEvent::MouseInput { state, button, .. } => {
-                    if state.is_pressed() {
-                        if !self.cursor_in_window {
-                            return;
-                        }
-                        self.held_inputs.insert((*button).into());
-                        self.pressed_inputs.insert((*button).into());
-                        self.pressed_or_repeated_inputs.insert((*button).into());
-                    } else {
-                        self.held_inputs.remove(&(*button).into());
-                        self.released_inputs.insert((*button).into());
-                    };
+                WindowEvent::MouseInput { state, button, .. } => RawInputEvent::MouseButton {
+                    button: *button,
+                    pressed: state.is_pressed(),
+                },
+                WindowEvent::CursorEntered { .. } => RawInputEvent::CursorEntered,
+                WindowEvent::CursorLeft { .. } => RawInputEvent::CursorLeft,
+                WindowEvent::CursorMoved { position, .. } => {
+                    RawInputEvent::CursorMoved(vec2(position.x as f32, position.y as f32))
                 }
-                WindowEvent::CursorEntered { .. } => {
-                    self.cursor_in_window = true;
+                WindowEvent::Ime(Ime::Preedit(text, cursor)) => {
+                    RawInputEvent::ImePreedit(text.clone(), *cursor)
                 }
-                WindowEvent::CursorLeft { .. } => {
-                    self.cursor_in_window = false;
+                WindowEvent::Ime(Ime::Commit(text)) => RawInputEvent::ImeCommit(text.clone()),
+                WindowEvent::Ime(Ime::Disabled) => RawInputEvent::ImeDisabled,
+                _ => return,
+            },
+            WinitEvent::Device(event) => match event {
+                DeviceEvent::MouseWheel { delta } => RawInputEvent::MouseWheel(match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(PhysicalPosition { y, .. }) => *y as f32 / 16.0,
+                }),
+                DeviceEvent::MouseMotion { delta } => {
+                    RawInputEvent::MouseMotion(vec2(delta.0 as f32, delta.1 as f32))
                 }
-                WindowEvent::CursorMoved { position, .. } => {
-                    self.cursor_position = vec2(position.x as f32, position.y as f32);
+                _ => return,
+            },
+        };
+
+        self.raw_event(raw_event);
+    }
+
+    /// Applies an input event, recording it if there's a recording going.
+    pub fn raw_event(&mut self, event: RawInputEvent) {
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record(event.clone());
+        }
+
+        match event {
+            RawInputEvent::Key {
+                input,
+                modifier,
+                pressed,
+                repeat,
+                text,
+            } => {
+                if self.cursor_in_window {
+                    if let Some(text) = text {
+                        self.just_typed.push_str(&text);
+                    }
                 }
-                WindowEvent::Ime(Ime::Commit(text)) => {
-                    if self.cursor_in_window {
-                        self.just_typed.push_str(text);
+
+                if let Some(modifier) = modifier {
+                    if pressed {
+                        self.held_modifiers.insert(modifier);
+                    } else {
+                        self.held_modifiers.remove(&modifier);
                     }
                 }
-                _ => {}
-            },
-            WinitEvent::Device(event) => match event {
-                DeviceEvent::MouseWheel { delta } if self.cursor_in_window => {
-                    self.scroll_delta += match delta {
-                        MouseScrollDelta::LineDelta(_, y) => *y,
-                        MouseScrollDelta::PixelDelta(PhysicalPosition { y, .. }) => {
-                            *y as f32 / 16.0
-                        }
+
+                let Some(input) = input else {
+                    return;
+                };
+
+                if pressed {
+                    // the OS's key repeat is inconsistent between platforms, so we do our own
+                    if repeat || !self.cursor_in_window {
+                        return;
                     }
+                    self.press_repeatable(input);
+                } else {
+                    self.release_input(input);
                 }
-                DeviceEvent::MouseMotion { delta } if self.is_mouse_locked() => {
-                    self.mouse_delta += vec2(delta.0 as f32, delta.1 as f32)
+            }
+            RawInputEvent::MouseButton { button, pressed } => {
+                if pressed {
+                    if !self.cursor_in_window {
+                        return;
+                    }
+                    self.held_inputs.insert(button.into());
+                    self.pressed_inputs.insert(button.into());
+                    self.pressed_or_repeated_inputs.insert(button.into());
+                    self.mouse_gestures.press(button, self.cursor_position);
+                } else {
+                    self.held_inputs.remove(&button.into());
+                    self.released_inputs.insert(button.into());
+                    self.mouse_gestures.release(button);
+                };
+            }
+            RawInputEvent::CursorEntered => {
+                self.cursor_in_window = true;
+            }
+            RawInputEvent::CursorLeft => {
+                self.cursor_in_window = false;
+            }
+            RawInputEvent::CursorMoved(position) => {
+                self.cursor_position = position;
+                if !self.is_mouse_locked() {
+                    self.mouse_gestures.cursor_moved(self.cursor_position);
                 }
-                _ => {}
-            },
+                // the mouse takes over from keyboard/gamepad navigation
+                self.nav_selected_component_id = None;
+            }
+            RawInputEvent::MouseMotion(delta) => {
+                if self.is_mouse_locked() {
+                    self.mouse_delta += delta;
+                    self.mouse_gestures.mouse_motion(delta);
+                }
+            }
+            RawInputEvent::MouseWheel(delta) => {
+                if self.cursor_in_window {
+                    self.scroll_delta += delta;
+                }
+            }
+            RawInputEvent::ImePreedit(text, cursor) => {
+                self.preedit_text = text;
+                self.preedit_cursor = cursor;
+            }
+            RawInputEvent::ImeCommit(text) => {
+                self.preedit_text.clear();
+                self.preedit_cursor = None;
+                if self.cursor_in_window {
+                    self.just_typed.push_str(&text);
+                }
+            }
+            RawInputEvent::ImeDisabled => {
+                self.preedit_text.clear();
+                self.preedit_cursor = None;
+            }
+            RawInputEvent::GamepadButton { button, pressed } => {
+                self.apply_gamepad_button(button, pressed)
+            }
+            RawInputEvent::GamepadAxis { axis, value } => {
+                self.gamepad_axes.insert(axis, value);
+                self.update_stick_directions(axis);
+            }
+            RawInputEvent::GamepadReset => self.apply_reset_gamepad(),
+            RawInputEvent::ReleaseAll => self.apply_release_all(),
         }
     }
 }
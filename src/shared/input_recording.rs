@@ -0,0 +1,165 @@
+use super::{
+    gamepad::{GamepadAxis, GamepadButton},
+    input::{Input, KeySide, ModifierKey},
+};
+use anyhow::Result;
+use cgmath::Vector2;
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+use winit::event::MouseButton;
+
+pub const INPUT_RECORDING_VERSION: u32 = 1;
+
+/// Everything the [`InputController`](super::input::InputController) reacts to, boiled down into something
+/// that can be saved and fed back in later.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RawInputEvent {
+    Key {
+        /// `None` for keys that don't map to an [`Input`], which can still type text or be modifiers.
+        input: Option<Input>,
+        modifier: Option<(ModifierKey, KeySide)>,
+        pressed: bool,
+        repeat: bool,
+        text: Option<String>,
+    },
+    MouseButton {
+        button: MouseButton,
+        pressed: bool,
+    },
+    CursorEntered,
+    CursorLeft,
+    CursorMoved(Vector2<f32>),
+    /// Mouse movement while locked.
+    MouseMotion(Vector2<f32>),
+    /// In lines.
+    MouseWheel(f32),
+    ImePreedit(String, Option<(usize, usize)>),
+    ImeCommit(String),
+    ImeDisabled,
+    GamepadButton {
+        button: GamepadButton,
+        pressed: bool,
+    },
+    GamepadAxis {
+        axis: GamepadAxis,
+        value: f32,
+    },
+    GamepadReset,
+    ReleaseAll,
+}
+
+/// One rendered frame's worth of input, along with the timing that frame ran with.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    pub frame_time: f64,
+    pub ticks: u32,
+    pub events: Vec<RawInputEvent>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputRecording {
+    pub version: u32,
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl Default for InputRecording {
+    fn default() -> Self {
+        Self {
+            version: INPUT_RECORDING_VERSION,
+            frames: Vec::new(),
+        }
+    }
+}
+
+impl InputRecording {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let recording: Self = ron::from_str(&fs::read_to_string(path.into())?)?;
+        if recording.version != INPUT_RECORDING_VERSION {
+            anyhow::bail!(
+                "input recording is version {}, expected {}",
+                recording.version,
+                INPUT_RECORDING_VERSION
+            );
+        }
+        Ok(recording)
+    }
+
+    pub fn save(&self, path: impl Into<PathBuf>) -> Result<()> {
+        fs::write(path.into(), ron::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+/// Collects raw input events and frame timings into an [`InputRecording`].
+#[derive(Debug)]
+pub struct InputRecorder {
+    pub path: PathBuf,
+    recording: InputRecording,
+    current_frame: Vec<RawInputEvent>,
+}
+
+impl InputRecorder {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            recording: InputRecording::default(),
+            current_frame: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, event: RawInputEvent) {
+        self.current_frame.push(event);
+    }
+
+    /// Everything recorded since the last frame ended belongs to this frame.
+    pub fn end_frame(&mut self, frame_time: f64, ticks: u32) {
+        self.recording.frames.push(RecordedFrame {
+            frame_time,
+            ticks,
+            events: std::mem::take(&mut self.current_frame),
+        });
+    }
+
+    /// Throws away events that came in before recording properly started (e.g. during loading).
+    pub fn discard_current_frame(&mut self) {
+        self.current_frame.clear();
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.recording.frames.len()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.recording.save(self.path.clone())
+    }
+}
+
+/// Hands out the frames of an [`InputRecording`] in order.
+#[derive(Debug)]
+pub struct InputPlayer {
+    recording: InputRecording,
+    next_frame: usize,
+}
+
+impl InputPlayer {
+    pub fn new(recording: InputRecording) -> Self {
+        Self {
+            recording,
+            next_frame: 0,
+        }
+    }
+
+    pub fn next_frame(&mut self) -> Option<&RecordedFrame> {
+        let frame = self.recording.frames.get(self.next_frame)?;
+        self.next_frame += 1;
+        Some(frame)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_frame >= self.recording.frames.len()
+    }
+
+    pub fn progress(&self) -> (usize, usize) {
+        (self.next_frame, self.recording.frames.len())
+    }
+}
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    sync::RwLock,
+};
+
+/// Hands out a number for every distinct name. Names are never forgotten, which is fine as long as they come from
+/// a fixed set of assets rather than user input.
+#[derive(Debug, Default)]
+pub struct Interner {
+    ids: HashMap<&'static str, u32>,
+    names: Vec<&'static str>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, name: &str) -> Option<u32> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn intern(&mut self, name: &str) -> u32 {
+        if let Some(id) = self.get(name) {
+            return id;
+        }
+
+        // leaked so handles can give out their names without holding onto the lock
+        let name: &'static str = Box::leak(name.to_owned().into_boxed_str());
+        let id = self.names.len() as u32;
+        self.names.push(name);
+        self.ids.insert(name, id);
+        id
+    }
+
+    pub fn name(&self, id: u32) -> &'static str {
+        self.names[id as usize]
+    }
+}
+
+macro_rules! interned_id {
+    ($(#[$meta:meta])* $name:ident, $registry:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name(u32);
+
+        lazy_static::lazy_static! {
+            static ref $registry: RwLock<Interner> = RwLock::new(Interner::new());
+        }
+
+        impl $name {
+            /// Looks up (or registers) a name. This takes a lock, so do it once up front rather than every frame.
+            pub fn intern(name: &str) -> Self {
+                if let Some(id) = $registry.read().unwrap().get(name) {
+                    return Self(id);
+                }
+                Self($registry.write().unwrap().intern(name))
+            }
+
+            pub fn name(self) -> &'static str {
+                $registry.read().unwrap().name(self.0)
+            }
+
+            /// For using IDs to index into a `Vec`.
+            pub fn index(self) -> usize {
+                self.0 as usize
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.name())
+            }
+        }
+
+        // IDs depend on the order names got interned in, so only the names themselves are saved
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.name())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                Ok(Self::intern(&String::deserialize(deserializer)?))
+            }
+        }
+    };
+}
+
+interned_id!(
+    /// A cheap handle to the name of a model.
+    ModelId,
+    MODEL_NAMES
+);
+
+interned_id!(
+    /// A cheap handle to the name of a texture in the [`TextureProvider`](crate::app_state::TextureProvider).
+    TextureId,
+    TEXTURE_NAMES
+);
+
+interned_id!(
+    /// A cheap handle to the name of a [`Prefab`](crate::special::prefab::Prefab).
+    PrefabId,
+    PREFAB_NAMES
+);
@@ -0,0 +1,208 @@
+use anyhow::{Context, Result};
+use lazy_static::lazy_static;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Write},
+    fs,
+    path::Path,
+    sync::RwLock,
+};
+
+/// Anything missing from the current language comes from this one.
+pub const FALLBACK_LANGUAGE: &str = "en";
+/// String tables that are always available, by language code.
+pub const BUILTIN_LANGUAGES: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ron")),
+    ("de", include_str!("../locales/de.ron")),
+];
+/// More string tables, as `<language code>.ron`. These add to (or replace strings in) the built-in ones.
+pub const LOCALES_PATH: &str = "locales";
+/// Text starting with this is a key to look up, e.g. `"@train.front_clock"`. Anything else is shown as it is.
+pub const KEY_PREFIX: char = '@';
+
+/// Translated strings by key. Strings can have `{name}` placeholders, which get filled in by [`tr!`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct StringTable {
+    pub strings: BTreeMap<String, String>,
+}
+
+impl StringTable {
+    pub fn parse(source: &str) -> Result<Self> {
+        Ok(ron::from_str(source)?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("couldn't read {}", path.display()))?;
+        Self::parse(&source).with_context(|| format!("couldn't parse {}", path.display()))
+    }
+
+    pub fn extend(&mut self, other: Self) {
+        self.strings.extend(other.strings);
+    }
+}
+
+/// Every string table there is, and which one's in use.
+#[derive(Debug, Clone)]
+pub struct Localization {
+    language: String,
+    tables: BTreeMap<String, StringTable>,
+}
+
+impl Default for Localization {
+    fn default() -> Self {
+        Self {
+            language: FALLBACK_LANGUAGE.to_owned(),
+            tables: BTreeMap::new(),
+        }
+    }
+}
+
+impl Localization {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The built-in string tables, plus whatever's in [`LOCALES_PATH`].
+    pub fn load_all() -> Self {
+        let mut localization = Self::new();
+        for &(language, source) in BUILTIN_LANGUAGES {
+            let table =
+                StringTable::parse(source).expect("the built-in string tables should be valid");
+            localization.add_strings(language, table);
+        }
+
+        if let Ok(entries) = fs::read_dir(LOCALES_PATH) {
+            for path in entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+            {
+                if path.extension().is_none_or(|extension| extension != "ron") {
+                    continue;
+                }
+                let Some(language) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                    continue;
+                };
+                match StringTable::load(&path) {
+                    Ok(table) => localization.add_strings(language, table),
+                    Err(error) => warn!("{:#}", error),
+                }
+            }
+        }
+
+        localization
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Every language with a string table, by code.
+    pub fn languages(&self) -> impl Iterator<Item = &str> {
+        self.tables.keys().map(String::as_str)
+    }
+
+    pub fn has_language(&self, language: &str) -> bool {
+        self.tables.contains_key(language)
+    }
+
+    /// Switches languages. Returns false (and doesn't switch) if there's no string table for `language`.
+    pub fn set_language(&mut self, language: &str) -> bool {
+        if !self.has_language(language) {
+            return false;
+        }
+        self.language = language.to_owned();
+        true
+    }
+
+    /// Adds strings for `language`, replacing any with the same keys.
+    pub fn add_strings(&mut self, language: &str, table: StringTable) {
+        self.tables
+            .entry(language.to_owned())
+            .or_default()
+            .extend(table);
+    }
+
+    /// The string for `key` in the current language, falling back on [`FALLBACK_LANGUAGE`] and then the key itself.
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        [self.language.as_str(), FALLBACK_LANGUAGE]
+            .into_iter()
+            .find_map(|language| self.tables.get(language)?.strings.get(key))
+            .map(String::as_str)
+            .unwrap_or(key)
+    }
+
+    /// Like [`Localization::get()`], with every `{name}` filled in from `args`.
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let mut text = self.get(key).to_owned();
+        for (name, value) in args {
+            let placeholder = format!("{{{name}}}");
+            if text.contains(&placeholder) {
+                let mut formatted = String::new();
+                let _ = write!(formatted, "{value}");
+                text = text.replace(&placeholder, &formatted);
+            }
+        }
+        text
+    }
+
+    /// Text that might be a key (see [`KEY_PREFIX`]), as it should be shown.
+    pub fn localize<'a>(&'a self, text: &'a str) -> &'a str {
+        match text.strip_prefix(KEY_PREFIX) {
+            Some(key) => self.get(key),
+            None => text,
+        }
+    }
+}
+
+/// The language the system is set to (from `LC_ALL`, `LC_MESSAGES` or `LANG`), e.g. `"de"` for `de_DE.UTF-8`.
+pub fn system_language() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .map(|value| {
+            value
+                .split(['_', '.', '@'])
+                .next()
+                .unwrap_or_default()
+                .to_lowercase()
+        })
+        .filter(|language| !language.is_empty() && language != "c" && language != "posix")
+}
+
+lazy_static! {
+    /// Used by [`tr!`]. Loaded the first time it's used.
+    pub static ref LOCALIZATION: RwLock<Localization> = RwLock::new(Localization::load_all());
+}
+
+/// What [`tr!`] expands to.
+pub fn translate(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    LOCALIZATION.read().unwrap().format(key, args)
+}
+
+/// See [`Localization::localize()`].
+pub fn localize(text: &str) -> String {
+    LOCALIZATION.read().unwrap().localize(text).to_owned()
+}
+
+/// Looks up a string in the current language, e.g. `tr!("save.saved", slot = slot)` fills in `{slot}`.
+#[cfg(feature = "client")]
+macro_rules! tr {
+    ($key:expr) => {
+        $crate::shared::localization::translate($key, &[])
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::shared::localization::translate(
+            $key,
+            &[$((stringify!($name), &$value as &dyn std::fmt::Display)),+],
+        )
+    };
+}
+
+#[cfg(feature = "client")]
+pub(crate) use tr;
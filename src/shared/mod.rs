@@ -1,8 +1,27 @@
+#[cfg(feature = "client")]
+pub mod action;
 pub mod bounding_box;
 pub mod char_indexing;
+pub mod console;
+pub mod crash;
+pub mod determinism;
 pub mod f32_util;
+#[cfg(feature = "client")]
+pub mod gamepad;
+#[cfg(feature = "client")]
 pub mod indexed_container;
+#[cfg(feature = "client")]
 pub mod input;
+#[cfg(feature = "client")]
+pub mod input_recording;
+pub mod interning;
+pub mod localization;
+#[cfg(feature = "client")]
+pub mod mouse_gesture;
+#[cfg(feature = "client")]
+pub mod navigation;
 pub mod numerical_integration;
 pub mod performance_counter;
+pub mod profiler;
+pub mod tasks;
 pub mod version;
@@ -0,0 +1,157 @@
+use cgmath::{InnerSpace, Vector2};
+use linear_map::{set::LinearSet, LinearMap};
+use std::time::{Duration, Instant};
+use winit::event::MouseButton;
+
+/// The most time between two clicks for them to count as a double click.
+pub const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(400);
+/// The furthest (in pixels) two clicks can be from each other and still count as a double click.
+pub const DOUBLE_CLICK_RADIUS: f32 = 6.0;
+/// How far (in pixels) the mouse has to move while held down before it counts as a drag instead of a click.
+pub const DRAG_THRESHOLD: f32 = 4.0;
+
+#[derive(Debug, Clone, Copy)]
+struct MousePress {
+    origin: Vector2<f32>,
+    /// How far the mouse has moved since the press. Tracked separately from the cursor position so that it
+    /// also works while the mouse is locked.
+    travel: Vector2<f32>,
+    click_count: u32,
+    dragging: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LastClick {
+    position: Vector2<f32>,
+    time: Instant,
+    click_count: u32,
+}
+
+/// Sorts mouse presses into clicks, multi-clicks, and drags. Owned by the
+/// [`InputController`](super::input::InputController), which exposes the queries.
+#[derive(Debug, Default)]
+pub struct MouseGestureTracker {
+    presses: LinearMap<MouseButton, MousePress>,
+    last_clicks: LinearMap<MouseButton, LastClick>,
+
+    pressed_click_counts: LinearMap<MouseButton, u32>,
+    clicked: LinearSet<MouseButton>,
+    drag_started: LinearSet<MouseButton>,
+    drag_ended: LinearSet<MouseButton>,
+}
+
+impl MouseGestureTracker {
+    pub fn press(&mut self, button: MouseButton, position: Vector2<f32>) {
+        let now = Instant::now();
+        let click_count = match self.last_clicks.get(&button) {
+            Some(last_click)
+                if now.duration_since(last_click.time) <= DOUBLE_CLICK_TIME
+                    && (position - last_click.position).magnitude() <= DOUBLE_CLICK_RADIUS =>
+            {
+                last_click.click_count + 1
+            }
+            _ => 1,
+        };
+
+        self.presses.insert(
+            button,
+            MousePress {
+                origin: position,
+                travel: Vector2::new(0.0, 0.0),
+                click_count,
+                dragging: false,
+            },
+        );
+        self.pressed_click_counts.insert(button, click_count);
+    }
+
+    pub fn release(&mut self, button: MouseButton) {
+        let Some(press) = self.presses.remove(&button) else {
+            return;
+        };
+
+        if press.dragging {
+            self.drag_ended.insert(button);
+            self.last_clicks.remove(&button);
+        } else {
+            self.clicked.insert(button);
+            self.last_clicks.insert(
+                button,
+                LastClick {
+                    position: press.origin,
+                    time: Instant::now(),
+                    click_count: press.click_count,
+                },
+            );
+        }
+    }
+
+    /// The cursor moved to a new position.
+    pub fn cursor_moved(&mut self, position: Vector2<f32>) {
+        for (_, press) in self.presses.iter_mut() {
+            press.travel = position - press.origin;
+        }
+        self.update_drags();
+    }
+
+    /// The mouse moved while locked, so there's no cursor position to go off of.
+    pub fn mouse_motion(&mut self, delta: Vector2<f32>) {
+        for (_, press) in self.presses.iter_mut() {
+            press.travel += delta;
+        }
+        self.update_drags();
+    }
+
+    fn update_drags(&mut self) {
+        for (&button, press) in self.presses.iter_mut() {
+            if !press.dragging && press.travel.magnitude() > DRAG_THRESHOLD {
+                press.dragging = true;
+                self.drag_started.insert(button);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.pressed_click_counts.clear();
+        self.clicked.clear();
+        self.drag_started.clear();
+        self.drag_ended.clear();
+    }
+
+    pub fn reset(&mut self) {
+        self.clear();
+        self.presses.clear();
+        self.last_clicks.clear();
+    }
+
+    /// If the button was pressed this frame, how many clicks in a row this press makes (2 for a double click).
+    pub fn press_click_count(&self, button: MouseButton) -> Option<u32> {
+        self.pressed_click_counts.get(&button).copied()
+    }
+
+    pub fn clicked(&self, button: MouseButton) -> bool {
+        self.clicked.contains(&button)
+    }
+
+    pub fn dragging(&self, button: MouseButton) -> bool {
+        self.presses
+            .get(&button)
+            .is_some_and(|press| press.dragging)
+    }
+
+    pub fn drag_started(&self, button: MouseButton) -> bool {
+        self.drag_started.contains(&button)
+    }
+
+    pub fn drag_ended(&self, button: MouseButton) -> bool {
+        self.drag_ended.contains(&button)
+    }
+
+    pub fn press_origin(&self, button: MouseButton) -> Option<Vector2<f32>> {
+        self.presses.get(&button).map(|press| press.origin)
+    }
+
+    pub fn drag_travel(&self, button: MouseButton) -> Option<Vector2<f32>> {
+        self.presses.get(&button).map(|press| press.travel)
+    }
+}
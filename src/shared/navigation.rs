@@ -0,0 +1,76 @@
+use super::bounding_box::BBox2;
+use crate::gui::component::GuiComponentId;
+use cgmath::{vec2, InnerSpace, Vector2};
+
+/// A directional or confirm/cancel input used to move between GUI components without a mouse.
+///
+/// These come from the arrow keys (while in a menu) and from gamepads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavigationInput {
+    Up,
+    Down,
+    Left,
+    Right,
+    /// Acts like a left click on the selected component.
+    Activate,
+    /// Backs out of the current menu.
+    Back,
+}
+
+impl NavigationInput {
+    /// The screen-space direction of this input, if it has one. +Y is down.
+    pub fn direction(self) -> Option<Vector2<f32>> {
+        match self {
+            Self::Up => Some(vec2(0.0, -1.0)),
+            Self::Down => Some(vec2(0.0, 1.0)),
+            Self::Left => Some(vec2(-1.0, 0.0)),
+            Self::Right => Some(vec2(1.0, 0.0)),
+            Self::Activate | Self::Back => None,
+        }
+    }
+}
+
+/// Picks the component that directional navigation should move to from `current`.
+///
+/// Candidates have to be at least partially in the direction of travel, and ones that are closer along the
+/// direction are preferred over ones that are off to the side.
+///
+/// If there's no current selection, the top-left-most component is picked instead.
+pub fn pick_navigation_target(
+    components: &[(GuiComponentId, BBox2)],
+    current: Option<GuiComponentId>,
+    direction: Vector2<f32>,
+) -> Option<GuiComponentId> {
+    let current_center = current.and_then(|current| {
+        components
+            .iter()
+            .find(|(id, _)| *id == current)
+            .map(|(_, bounding_box)| Vector2::from(bounding_box.center()))
+    });
+
+    let Some(current_center) = current_center else {
+        return components
+            .iter()
+            .min_by(|(_, a), (_, b)| {
+                let [a, b] = [a.min(), b.min()].map(|[x, y]| y * 4.0 + x);
+                a.total_cmp(&b)
+            })
+            .map(|&(id, _)| id);
+    };
+
+    components
+        .iter()
+        .filter(|(id, _)| Some(*id) != current)
+        .filter_map(|&(id, bounding_box)| {
+            let offset = Vector2::from(bounding_box.center()) - current_center;
+            let along = offset.dot(direction);
+            if along <= 0.0 {
+                return None;
+            }
+            let across = (offset - direction * along).magnitude();
+
+            Some((id, along + across * 2.0))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(id, _)| id)
+}
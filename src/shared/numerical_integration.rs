@@ -1,5 +1,17 @@
+use serde::{Deserialize, Serialize};
 use std::ops::{Add, AddAssign, Div, Mul};
 
+/// Which numerical method to step something with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Integrator {
+    /// Fourth-order Runge-Kutta. Very accurate per step, but energy slowly drifts over long spans.
+    #[default]
+    RungeKutta4,
+    /// Velocity Verlet. Less accurate per step, but it's symplectic, so energy stays bounded instead of drifting
+    /// off. Better for long orbital or circular motion.
+    VelocityVerlet,
+}
+
 pub fn runge_kutta_step<T>(
     initial_value: T,
     initial_time: f64,
@@ -51,3 +63,28 @@ where
 
     current_value
 }
+
+/// One step of [velocity Verlet](https://en.wikipedia.org/wiki/Verlet_integration#Velocity_Verlet) integration,
+/// returning the new `(position, velocity)`.
+///
+/// `acceleration` takes the position and velocity. Velocity-dependent accelerations get evaluated at a predicted
+/// velocity for the end of the step, which makes this not strictly symplectic for them, but it's close enough.
+pub fn velocity_verlet_step<T>(
+    position: T,
+    velocity: T,
+    time_step: f64,
+    mut acceleration: impl FnMut(T, T) -> T,
+) -> (T, T)
+where
+    T: Copy + Add<Output = T> + Mul<f64, Output = T>,
+{
+    let initial_acceleration = acceleration(position, velocity);
+    let new_position =
+        position + velocity * time_step + initial_acceleration * (time_step * time_step / 2.0);
+
+    let predicted_velocity = velocity + initial_acceleration * time_step;
+    let new_acceleration = acceleration(new_position, predicted_velocity);
+    let new_velocity = velocity + (initial_acceleration + new_acceleration) * (time_step / 2.0);
+
+    (new_position, new_velocity)
+}
@@ -1,10 +1,48 @@
-use std::time::{Duration, Instant};
+use linear_map::LinearMap;
+use serde::Serialize;
+use std::{
+    ops::AddAssign,
+    time::{Duration, Instant},
+};
+
+/// What the GPU got asked to do over some stretch of time, usually a frame. Counted by the graphics controller.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RenderStats {
+    pub render_passes: u32,
+    pub draw_calls: u32,
+    pub instances: u64,
+    /// Vertices (or indices, for indexed draws) for every instance.
+    pub vertices: u64,
+    /// Uploaded to buffers, including filling in new ones. Texture uploads aren't counted.
+    pub buffer_bytes_written: u64,
+    /// Because they were new, or had changed size.
+    pub render_targets_recreated: u32,
+}
+
+impl AddAssign for RenderStats {
+    fn add_assign(&mut self, other: Self) {
+        self.render_passes += other.render_passes;
+        self.draw_calls += other.draw_calls;
+        self.instances += other.instances;
+        self.vertices += other.vertices;
+        self.buffer_bytes_written += other.buffer_bytes_written;
+        self.render_targets_recreated += other.render_targets_recreated;
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct PerformanceCounter {
     times: Vec<Duration>,
     recording_start: Instant,
     last_tick: Instant,
+
+    open_sections: LinearMap<&'static str, Instant>,
+    /// Time spent in each section since the last tick.
+    frame_sections: LinearMap<&'static str, Duration>,
+    /// Time spent in each section since the last flush.
+    section_totals: LinearMap<&'static str, Duration>,
+    /// The most recent frame's time, and the time spent in each section during it.
+    last_frame: (Duration, Vec<(&'static str, Duration)>),
 }
 
 impl Default for PerformanceCounter {
@@ -13,15 +51,25 @@ impl Default for PerformanceCounter {
             times: Default::default(),
             recording_start: Instant::now(),
             last_tick: Instant::now(),
+
+            open_sections: Default::default(),
+            frame_sections: Default::default(),
+            section_totals: Default::default(),
+            last_frame: Default::default(),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct PerformanceReport {
     pub mean: Duration,
     pub slowest: Duration,
     pub fastest: Duration,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    /// Mean time per frame spent in each named section, in the order they were first seen.
+    pub sections: Vec<(&'static str, Duration)>,
     pub start: Instant,
     pub end: Instant,
 }
@@ -32,8 +80,30 @@ impl PerformanceCounter {
     }
 
     pub fn tick(&mut self) {
-        self.push_time(self.last_tick.elapsed());
+        let frame_time = self.last_tick.elapsed();
+        self.push_time(frame_time);
         self.last_tick = Instant::now();
+
+        self.last_frame.0 = frame_time;
+        self.last_frame.1.clear();
+        for (name, time) in self.frame_sections.drain() {
+            *self.section_totals.entry(name).or_insert(Duration::ZERO) += time;
+            self.last_frame.1.push((name, time));
+        }
+    }
+
+    /// Adds a whole frame that was timed somewhere else, e.g. by copying [`PerformanceCounter::last_frame()`] from
+    /// another counter.
+    pub fn push_frame(&mut self, time: Duration, sections: &[(&'static str, Duration)]) {
+        self.push_time(time);
+        for &(name, section_time) in sections {
+            *self.section_totals.entry(name).or_insert(Duration::ZERO) += section_time;
+        }
+    }
+
+    /// The frame time and section times from the last [`PerformanceCounter::tick()`].
+    pub fn last_frame(&self) -> (Duration, &[(&'static str, Duration)]) {
+        (self.last_frame.0, &self.last_frame.1)
     }
 
     pub fn push_time(&mut self, time: Duration) {
@@ -43,21 +113,55 @@ impl PerformanceCounter {
         self.times.push(time);
     }
 
+    /// Starts timing a named part of the frame. Sections can be entered more than once per frame (e.g. physics
+    /// ticks), in which case the times add up.
+    pub fn begin_section(&mut self, name: &'static str) {
+        self.open_sections.insert(name, Instant::now());
+    }
+
+    pub fn end_section(&mut self, name: &'static str) {
+        let Some(start) = self.open_sections.remove(&name) else {
+            return;
+        };
+
+        *self.frame_sections.entry(name).or_insert(Duration::ZERO) += start.elapsed();
+    }
+
+    /// Adds time to a section that was measured somewhere else, like on another thread.
+    pub fn add_section_time(&mut self, name: &'static str, time: Duration) {
+        *self.frame_sections.entry(name).or_insert(Duration::ZERO) += time;
+    }
+
     pub fn report(&self) -> Option<PerformanceReport> {
         if self.times.is_empty() {
             return None;
         }
 
-        let mean = self.times.iter().sum::<Duration>() / self.times.len() as u32;
+        let frame_count = self.times.len() as u32;
+        let mean = self.times.iter().sum::<Duration>() / frame_count;
         let (slowest, fastest) = self.times.iter().fold(
             (Duration::ZERO, Duration::MAX),
             |(slowest, fastest), &time| (time.max(slowest), time.min(fastest)),
         );
 
+        let mut sorted_times = self.times.clone();
+        sorted_times.sort_unstable();
+        let percentile = |fraction: f64| {
+            sorted_times[((sorted_times.len() - 1) as f64 * fraction).round() as usize]
+        };
+
         Some(PerformanceReport {
             mean,
             slowest,
             fastest,
+            p50: percentile(0.5),
+            p95: percentile(0.95),
+            p99: percentile(0.99),
+            sections: self
+                .section_totals
+                .iter()
+                .map(|(&name, &total)| (name, total / frame_count))
+                .collect(),
             start: self.recording_start,
             end: Instant::now(),
         })
@@ -66,6 +170,7 @@ impl PerformanceCounter {
     pub fn flush(&mut self) -> Option<PerformanceReport> {
         let report = self.report();
         self.times.clear();
+        self.section_totals.clear();
         report
     }
 }
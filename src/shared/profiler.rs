@@ -0,0 +1,87 @@
+use anyhow::Result;
+use std::path::PathBuf;
+
+pub const PROFILE_PATH: &str = "profile.puffin";
+
+/// Marks the rest of the enclosing block as a named span in the profiler. Without the `profiler` feature, it's
+/// nothing at all.
+macro_rules! profile_scope {
+    ($name:expr) => {
+        #[cfg(feature = "profiler")]
+        puffin::profile_scope!($name);
+    };
+}
+
+pub(crate) use profile_scope;
+
+/// Records [`profile_scope!`]s with [puffin](https://github.com/EmbarkStudios/puffin) while it's turned on, and saves
+/// what it caught to a `.puffin` file for `puffin_viewer`.
+#[derive(Default)]
+pub struct Profiler {
+    #[cfg(feature = "profiler")]
+    frame_view: Option<puffin::GlobalFrameView>,
+}
+
+impl std::fmt::Debug for Profiler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Profiler")
+            .field("enabled", &self.is_enabled())
+            .finish()
+    }
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the game was built with the `profiler` feature.
+    pub const fn is_available() -> bool {
+        cfg!(feature = "profiler")
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        #[cfg(feature = "profiler")]
+        return puffin::are_scopes_on();
+        #[cfg(not(feature = "profiler"))]
+        return false;
+    }
+
+    /// Starts or stops recording. Turning it on throws away anything captured before.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        #[cfg(feature = "profiler")]
+        {
+            if enabled && !self.is_enabled() {
+                self.frame_view = Some(puffin::GlobalFrameView::default());
+            }
+            puffin::set_scopes_on(enabled);
+        }
+        #[cfg(not(feature = "profiler"))]
+        let _ = enabled;
+    }
+
+    /// Marks the end of a frame. Should be called exactly once per rendered frame.
+    pub fn new_frame(&mut self) {
+        #[cfg(feature = "profiler")]
+        puffin::GlobalProfiler::lock().new_frame();
+    }
+
+    /// Writes every captured frame to a `.puffin` file.
+    pub fn save(&self, path: impl Into<PathBuf>) -> Result<()> {
+        #[cfg(feature = "profiler")]
+        {
+            let Some(frame_view) = self.frame_view.as_ref() else {
+                anyhow::bail!("nothing has been profiled");
+            };
+
+            let mut file = std::io::BufWriter::new(std::fs::File::create(path.into())?);
+            frame_view.lock().write(&mut file)?;
+            Ok(())
+        }
+        #[cfg(not(feature = "profiler"))]
+        {
+            let _ = path;
+            anyhow::bail!("built without the profiler feature")
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use log::error;
+use std::{
+    fmt::{self, Debug},
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+/// Leaves room for the main and physics threads.
+pub const MAX_WORKER_THREADS: usize = 4;
+
+type Job = Box<dyn FnOnce() + Send>;
+type Callback<C> = Box<dyn FnOnce(&mut C) + Send>;
+
+/// A small thread pool for work that shouldn't hold up the frame loop (file IO, decoding, building meshes, etc.).
+/// Whatever a task produces is handed to a callback that runs back on the main thread, with access to some
+/// context `C` (usually the [`AppState`](crate::app_state::AppState)), the next time
+/// [`TaskPool::take_callbacks()`] is called.
+pub struct TaskPool<C> {
+    job_sender: Sender<Job>,
+    callback_sender: Sender<Callback<C>>,
+    callback_receiver: Receiver<Callback<C>>,
+    in_flight: Arc<AtomicUsize>,
+    worker_count: usize,
+}
+
+impl<C: 'static> Debug for TaskPool<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskPool")
+            .field("in_flight", &self.in_flight())
+            .field("worker_count", &self.worker_count)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<C: 'static> Default for TaskPool<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: 'static> TaskPool<C> {
+    pub fn new() -> Self {
+        let worker_count = thread::available_parallelism()
+            .map(|count| count.get().saturating_sub(2))
+            .unwrap_or(1)
+            .clamp(1, MAX_WORKER_THREADS);
+
+        let (job_sender, job_receiver) = mpsc::channel::<Job>();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+        for index in 0..worker_count {
+            let job_receiver = Arc::clone(&job_receiver);
+            thread::Builder::new()
+                .name(format!("task worker {index}"))
+                .spawn(move || loop {
+                    // the lock is only held while waiting, not while working
+                    let job = job_receiver.lock().unwrap().recv();
+                    match job {
+                        Ok(job) => job(),
+                        // the pool was dropped
+                        Err(_) => break,
+                    }
+                })
+                .expect("failed to spawn a task worker thread");
+        }
+
+        let (callback_sender, callback_receiver) = mpsc::channel();
+
+        Self {
+            job_sender,
+            callback_sender,
+            callback_receiver,
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            worker_count,
+        }
+    }
+
+    /// Runs `work` on a worker thread, then `then` on the main thread with the result.
+    pub fn spawn<T: Send + 'static>(
+        &self,
+        work: impl FnOnce() -> T + Send + 'static,
+        then: impl FnOnce(&mut C, T) + Send + 'static,
+    ) {
+        let callback_sender = self.callback_sender.clone();
+        self.spawn_detached(move || {
+            let output = work();
+            let _ = callback_sender.send(Box::new(move |context: &mut C| then(context, output)));
+        });
+    }
+
+    /// Runs `work` on a worker thread without getting anything back. A panic is logged rather than taking the
+    /// worker down with it.
+    pub fn spawn_detached(&self, work: impl FnOnce() + Send + 'static) {
+        let in_flight = Arc::clone(&self.in_flight);
+        in_flight.fetch_add(1, Ordering::SeqCst);
+
+        let job: Job = Box::new(move || {
+            if panic::catch_unwind(AssertUnwindSafe(work)).is_err() {
+                error!("A background task panicked");
+            }
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+        });
+        // the workers only stop once the pool is dropped
+        let _ = self.job_sender.send(job);
+    }
+
+    /// How many tasks are waiting or running.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Every callback that's ready, in the order they were queued. They're handed out rather than run here since
+    /// the pool usually lives inside the context they need.
+    pub fn take_callbacks(&self) -> Vec<Callback<C>> {
+        self.callback_receiver.try_iter().collect()
+    }
+}
@@ -1,8 +1,9 @@
 use super::{transform::*, worldline::MAX_SPEED};
-use crate::shared::numerical_integration::runge_kutta_step;
+use crate::shared::numerical_integration::{runge_kutta_step, velocity_verlet_step, Integrator};
 use cgmath::{vec3, vec4, InnerSpace, Vector3, Vector4};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct InertialFrame {
     pub position: Vector4<f64>,
     pub velocity: Vector3<f64>,
@@ -27,6 +28,18 @@ impl InertialFrame {
         }
     }
 
+    /// Maps this frame into coordinates where light goes at `speed_of_light` (a portion of its real speed), so that
+    /// light delay, length contraction and Doppler shift can be worked out as usual for a slower light.
+    pub fn with_speed_of_light(self, speed_of_light: f64) -> Self {
+        Self {
+            position: self
+                .position
+                .truncate()
+                .extend(self.position.w * speed_of_light),
+            velocity: rescale_velocity(self.velocity, speed_of_light),
+        }
+    }
+
     pub fn predict(self, delta_time: f64) -> Self {
         Self {
             position: self.position + self.velocity.extend(1.0) * delta_time,
@@ -36,11 +49,17 @@ impl InertialFrame {
 
     /// Simulates the movement of this frame with a given proper acceleration and a time step.
     ///
-    /// Uses the fourth-degree [Runge-Kutta method](https://en.wikipedia.org/wiki/Runge%E2%80%93Kutta_methods),
-    /// so smaller `delta_time` values are more precise.
+    /// With [`Integrator::RungeKutta4`], uses the fourth-degree
+    /// [Runge-Kutta method](https://en.wikipedia.org/wiki/Runge%E2%80%93Kutta_methods). Either way, smaller
+    /// `delta_time` values are more precise.
     ///
     /// Returns the elapsed proper time during this time-step.
-    pub fn step(&mut self, delta_time: f64, proper_accel: Vector3<f64>) -> f64 {
+    pub fn step(
+        &mut self,
+        delta_time: f64,
+        proper_accel: Vector3<f64>,
+        integrator: Integrator,
+    ) -> f64 {
         let transform = lorentz_boost(-self.velocity);
 
         let old_velocity = self.velocity;
@@ -50,23 +69,49 @@ impl InertialFrame {
             (1.0 - velocity.magnitude2()) * (accel_4.truncate() - velocity * accel_4.w)
         };
 
-        self.velocity = runge_kutta_step(self.velocity, 0.0, delta_time, &velocity_derivative);
+        match integrator {
+            Integrator::RungeKutta4 => {
+                self.velocity =
+                    runge_kutta_step(self.velocity, 0.0, delta_time, &velocity_derivative);
+                self.clamp_speed();
+
+                self.position =
+                    runge_kutta_step(self.position.truncate(), 0.0, delta_time, |time, _| {
+                        runge_kutta_step(old_velocity, 0.0, time, &velocity_derivative)
+                    })
+                    .extend(self.position.w + delta_time);
+
+                runge_kutta_step(0.0, 0.0, delta_time, |time, _| {
+                    1.0 / lorentz_factor(runge_kutta_step(
+                        old_velocity,
+                        0.0,
+                        time,
+                        &velocity_derivative,
+                    ))
+                })
+            }
+            Integrator::VelocityVerlet => {
+                let (position, velocity) = velocity_verlet_step(
+                    self.position.truncate(),
+                    self.velocity,
+                    delta_time,
+                    |_, velocity| velocity_derivative(0.0, velocity),
+                );
+                self.velocity = velocity;
+                self.clamp_speed();
+                self.position = position.extend(self.position.w + delta_time);
+
+                // trapezoidal rule, which matches Verlet's order of accuracy
+                (1.0 / lorentz_factor(old_velocity) + 1.0 / lorentz_factor(self.velocity))
+                    * delta_time
+                    / 2.0
+            }
+        }
+    }
+
+    fn clamp_speed(&mut self) {
         if self.velocity.magnitude2() > MAX_SPEED * MAX_SPEED {
             self.velocity = self.velocity.normalize_to(MAX_SPEED);
         }
-
-        self.position = runge_kutta_step(self.position.truncate(), 0.0, delta_time, |time, _| {
-            runge_kutta_step(old_velocity, 0.0, time, &velocity_derivative)
-        })
-        .extend(self.position.w + delta_time);
-
-        runge_kutta_step(0.0, 0.0, delta_time, |time, _| {
-            1.0 / lorentz_factor(runge_kutta_step(
-                old_velocity,
-                0.0,
-                time,
-                &velocity_derivative,
-            ))
-        })
     }
 }
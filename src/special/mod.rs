@@ -1,5 +1,9 @@
 pub mod inertial_frame;
 pub mod metric;
+pub mod prefab;
+pub mod spatial_index;
+pub mod star_system;
 pub mod transform;
 pub mod universe;
 pub mod worldline;
+pub mod worldline_encoding;
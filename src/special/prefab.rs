@@ -0,0 +1,213 @@
+use super::{
+    inertial_frame::InertialFrame,
+    universe::{Attachment, Entity, EntityId},
+    worldline::{Worldline, WorldlineEvent, WorldlineEventKind},
+};
+use crate::shared::{
+    interning::{ModelId, PrefabId},
+    numerical_integration::Integrator,
+};
+use anyhow::{Context, Result};
+use cgmath::{vec4, Matrix4, Vector3};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path, sync::RwLock};
+
+/// Always available, and always loaded first.
+pub const BUILTIN_PREFABS: &str = include_str!("../prefabs/builtin.ron");
+/// More prefabs, loaded over the built-in ones if the file exists.
+pub const PREFABS_PATH: &str = "prefabs.ron";
+/// Prefabs with this tag don't show up in the spawn palette.
+pub const HIDDEN_TAG: &str = "hidden";
+/// Entities from prefabs with this tag get deleted along with whatever they're attached to.
+pub const PART_TAG: &str = "part";
+
+/// A kind of entity that can be spawned by name, so every "beacon" looks and moves the same.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Prefab {
+    pub model: Option<ModelId>,
+    pub scale: f32,
+    pub color: [f32; 4],
+    /// How it moves right after spawning.
+    pub kind: WorldlineEventKind,
+    /// For finding entities by what they are rather than what they're called.
+    pub tags: Vec<String>,
+    /// Other prefabs spawned along with this one, attached to it.
+    pub parts: Vec<PrefabPart>,
+}
+
+/// Another prefab that gets spawned attached to its parent, like the mirrors of a light clock.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrefabPart {
+    pub prefab: String,
+    /// In the parent's rest frame.
+    #[serde(default)]
+    pub offset: [f64; 3],
+    /// Stretches the part's model, on top of its own prefab's scale.
+    #[serde(default = "default_part_scale")]
+    pub scale: [f32; 3],
+    /// See [`Attachment::bounce`].
+    #[serde(default)]
+    pub bounce: Option<[f64; 3]>,
+}
+
+fn default_part_scale() -> [f32; 3] {
+    [1.0; 3]
+}
+
+impl Default for Prefab {
+    fn default() -> Self {
+        Self {
+            model: None,
+            scale: 1.0,
+            color: [1.0; 4],
+            kind: WorldlineEventKind::Inertial,
+            tags: Vec::new(),
+            parts: Vec::new(),
+        }
+    }
+}
+
+impl Prefab {
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|prefab_tag| prefab_tag == tag)
+    }
+
+    /// A new entity made from this prefab, starting out at `frame`.
+    pub fn instantiate(&self, id: PrefabId, frame: InertialFrame) -> Entity {
+        // inserting an event right on top of the first one would throw the first one out, so this starts the
+        // worldline off with the right kind instead
+        let worldline = Worldline::from_event(WorldlineEvent {
+            frame,
+            proper_time: 0.0,
+            kind: self.kind,
+            integrator: Integrator::default(),
+        });
+
+        let [r, g, b, a] = self.color;
+        Entity {
+            worldline,
+            model: self.model,
+            model_matrix: Matrix4::from_scale(self.scale),
+            model_color: vec4(r, g, b, a),
+            prefab: Some(id),
+            ..Default::default()
+        }
+    }
+}
+
+/// Every prefab there is, by name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PrefabRegistry {
+    prefabs: BTreeMap<String, Prefab>,
+}
+
+impl PrefabRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(source: &str) -> Result<Self> {
+        Ok(ron::from_str(source)?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("couldn't read {}", path.display()))?;
+        Self::parse(&source).with_context(|| format!("couldn't parse {}", path.display()))
+    }
+
+    /// The built-in prefabs, plus (or replaced by) whatever's in [`PREFABS_PATH`].
+    pub fn load_all() -> Self {
+        let mut registry =
+            Self::parse(BUILTIN_PREFABS).expect("the built-in prefabs should be valid");
+        if Path::new(PREFABS_PATH).exists() {
+            match Self::load(PREFABS_PATH) {
+                Ok(user_prefabs) => registry.extend(user_prefabs),
+                Err(error) => warn!("{:#}, only the built-in prefabs are available", error),
+            }
+        }
+        registry
+    }
+
+    /// Adds every prefab from `other`, replacing any with the same name.
+    pub fn extend(&mut self, other: Self) {
+        self.prefabs.extend(other.prefabs);
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, prefab: Prefab) {
+        self.prefabs.insert(name.into(), prefab);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Prefab> {
+        self.prefabs.get(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.prefabs.keys().map(String::as_str)
+    }
+
+    /// Everything but the [`HIDDEN_TAG`]ged ones.
+    pub fn palette_names(&self) -> impl Iterator<Item = &str> {
+        self.prefabs
+            .iter()
+            .filter(|(_, prefab)| !prefab.has_tag(HIDDEN_TAG))
+            .map(|(name, _)| name.as_str())
+    }
+
+    pub fn with_tag<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = (&'a str, &'a Prefab)> {
+        self.prefabs
+            .iter()
+            .filter(move |(_, prefab)| prefab.has_tag(tag))
+            .map(|(name, prefab)| (name.as_str(), prefab))
+    }
+
+    /// Makes an entity from the prefab called `name`, if there is one.
+    pub fn instantiate(&self, name: &str, frame: InertialFrame) -> Option<Entity> {
+        self.get(name)
+            .map(|prefab| prefab.instantiate(PrefabId::intern(name), frame))
+    }
+
+    /// Makes the parts that go with the prefab called `name`, already attached to `parent_id`. Parts don't get parts
+    /// of their own.
+    pub fn instantiate_parts(
+        &self,
+        name: &str,
+        parent_id: EntityId,
+        frame: InertialFrame,
+    ) -> Vec<(EntityId, Entity)> {
+        let Some(prefab) = self.get(name) else {
+            return Vec::new();
+        };
+        prefab
+            .parts
+            .iter()
+            .filter_map(|part| {
+                let Some(mut entity) = self.instantiate(&part.prefab, frame) else {
+                    warn!(
+                        "Prefab '{}' has a part called '{}', which doesn't exist",
+                        name, part.prefab
+                    );
+                    return None;
+                };
+                let [x, y, z] = part.scale;
+                entity.model_matrix = entity.model_matrix * Matrix4::from_nonuniform_scale(x, y, z);
+                entity.attachment = Some(Attachment {
+                    parent: parent_id,
+                    offset: Vector3::from(part.offset),
+                    bounce: part.bounce.map(Vector3::from),
+                });
+                Some((EntityId::generate(), entity))
+            })
+            .collect()
+    }
+}
+
+lazy_static::lazy_static! {
+    /// The prefabs used by [`Universe::spawn_prefab()`](super::universe::Universe::spawn_prefab). Loaded the first
+    /// time it's used.
+    pub static ref PREFABS: RwLock<PrefabRegistry> = RwLock::new(PrefabRegistry::load_all());
+}
@@ -0,0 +1,278 @@
+use super::universe::EntityId;
+use crate::shared::bounding_box::{BBox3F64, Point};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Which grid cell a point falls into.
+pub type CellCoord = [i64; 3];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SpatialEntry {
+    bounds: BBox3F64,
+    min_cell: CellCoord,
+    max_cell: CellCoord,
+}
+
+/// A uniform grid over entity bounding boxes, for broad-phase queries (culling, raycasts, radar, collision) that
+/// shouldn't have to look at every entity. Entities are updated one at a time, and only move between cells when
+/// their bounds cross a cell boundary, so keeping it up to date every frame is cheap.
+///
+/// The index doesn't care what frame the bounds are in; the renderer keeps one with light-delayed positions
+/// relative to the user.
+#[derive(Debug, Clone)]
+pub struct SpatialIndex {
+    cell_size: f64,
+    cells: HashMap<CellCoord, Vec<EntityId>>,
+    entries: BTreeMap<EntityId, SpatialEntry>,
+}
+
+impl Default for SpatialIndex {
+    fn default() -> Self {
+        Self::new(SpatialIndex::DEFAULT_CELL_SIZE)
+    }
+}
+
+impl SpatialIndex {
+    /// In light-seconds.
+    pub const DEFAULT_CELL_SIZE: f64 = 16.0;
+    /// Bounds spanning more cells than this on any axis get clamped, so one huge entity can't make every update
+    /// touch millions of cells. They're still returned by queries near their center.
+    pub const MAX_CELLS_PER_AXIS: i64 = 64;
+
+    pub fn new(cell_size: f64) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            entries: BTreeMap::new(),
+        }
+    }
+
+    pub fn cell_size(&self) -> f64 {
+        self.cell_size
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.entries.clear();
+    }
+
+    pub fn cell_of(&self, point: Point<3, f64>) -> CellCoord {
+        point.map(|value| (value / self.cell_size).floor() as i64)
+    }
+
+    fn cell_range(&self, bounds: BBox3F64) -> (CellCoord, CellCoord) {
+        let min_cell = self.cell_of(bounds.min());
+        let mut max_cell = self.cell_of(bounds.max());
+        for axis in 0..3 {
+            max_cell[axis] = max_cell[axis].min(min_cell[axis] + Self::MAX_CELLS_PER_AXIS - 1);
+        }
+        (min_cell, max_cell)
+    }
+
+    fn cells_in_range(min_cell: CellCoord, max_cell: CellCoord) -> impl Iterator<Item = CellCoord> {
+        (min_cell[0]..=max_cell[0]).flat_map(move |x| {
+            (min_cell[1]..=max_cell[1])
+                .flat_map(move |y| (min_cell[2]..=max_cell[2]).map(move |z| [x, y, z]))
+        })
+    }
+
+    fn remove_from_cells(&mut self, entity_id: EntityId, entry: SpatialEntry) {
+        for cell in Self::cells_in_range(entry.min_cell, entry.max_cell) {
+            if let Some(entity_ids) = self.cells.get_mut(&cell) {
+                entity_ids.retain(|&id| id != entity_id);
+                if entity_ids.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+    }
+
+    /// Inserts an entity, or moves it if it's already in the index.
+    pub fn update(&mut self, entity_id: EntityId, bounds: BBox3F64) {
+        let (min_cell, max_cell) = self.cell_range(bounds);
+        let new_entry = SpatialEntry {
+            bounds,
+            min_cell,
+            max_cell,
+        };
+
+        if let Some(entry) = self.entries.get_mut(&entity_id) {
+            if entry.min_cell == min_cell && entry.max_cell == max_cell {
+                // still in the same cells
+                entry.bounds = bounds;
+                return;
+            }
+
+            let old_entry = *entry;
+            self.remove_from_cells(entity_id, old_entry);
+        }
+
+        for cell in Self::cells_in_range(min_cell, max_cell) {
+            self.cells.entry(cell).or_default().push(entity_id);
+        }
+        self.entries.insert(entity_id, new_entry);
+    }
+
+    pub fn remove(&mut self, entity_id: EntityId) -> bool {
+        let Some(entry) = self.entries.remove(&entity_id) else {
+            return false;
+        };
+
+        self.remove_from_cells(entity_id, entry);
+        true
+    }
+
+    /// Removes every entity that `keep` returns false for.
+    pub fn retain(&mut self, mut keep: impl FnMut(EntityId) -> bool) {
+        let removed = self
+            .entries
+            .keys()
+            .copied()
+            .filter(|&entity_id| !keep(entity_id))
+            .collect::<Vec<_>>();
+        for entity_id in removed {
+            self.remove(entity_id);
+        }
+    }
+
+    pub fn bounds(&self, entity_id: EntityId) -> Option<BBox3F64> {
+        self.entries.get(&entity_id).map(|entry| entry.bounds)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (EntityId, BBox3F64)> + '_ {
+        self.entries
+            .iter()
+            .map(|(&entity_id, entry)| (entity_id, entry.bounds))
+    }
+
+    /// Every entity whose bounds intersect `region`.
+    pub fn query_box(&self, region: BBox3F64) -> Vec<EntityId> {
+        let (min_cell, max_cell) = self.cell_range(region);
+
+        let mut found = BTreeSet::new();
+        for cell in Self::cells_in_range(min_cell, max_cell) {
+            let Some(entity_ids) = self.cells.get(&cell) else {
+                continue;
+            };
+            for &entity_id in entity_ids {
+                if self.entries[&entity_id].bounds.overlaps(region) {
+                    found.insert(entity_id);
+                }
+            }
+        }
+
+        found.into_iter().collect()
+    }
+
+    /// Every entity whose bounds come within `radius` of `center`, sorted by distance.
+    pub fn query_radius(&self, center: Point<3, f64>, radius: f64) -> Vec<(EntityId, f64)> {
+        let region = BBox3F64::new([
+            center.map(|value| value - radius),
+            center.map(|value| value + radius),
+        ]);
+
+        let mut found = self
+            .query_box(region)
+            .into_iter()
+            .filter_map(|entity_id| {
+                let distance = self.entries[&entity_id]
+                    .bounds
+                    .distance_squared_to(center)
+                    .sqrt();
+                (distance <= radius).then_some((entity_id, distance))
+            })
+            .collect::<Vec<_>>();
+        found.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+        found
+    }
+
+    /// Every entity a ray hits within `max_distance` (in multiples of `direction`), sorted by where the ray enters
+    /// them. Only the cells along the ray are looked at.
+    pub fn raycast(
+        &self,
+        origin: Point<3, f64>,
+        direction: Point<3, f64>,
+        max_distance: f64,
+    ) -> Vec<(EntityId, f64)> {
+        let mut found = BTreeMap::new();
+
+        // walk the grid cell by cell (Amanatides & Woo)
+        let mut cell = self.cell_of(origin);
+        let mut step = [0i64; 3];
+        let mut t_max = [f64::INFINITY; 3];
+        let mut t_delta = [f64::INFINITY; 3];
+        for axis in 0..3 {
+            if direction[axis] > 0.0 {
+                step[axis] = 1;
+                let boundary = (cell[axis] + 1) as f64 * self.cell_size;
+                t_max[axis] = (boundary - origin[axis]) / direction[axis];
+                t_delta[axis] = self.cell_size / direction[axis];
+            } else if direction[axis] < 0.0 {
+                step[axis] = -1;
+                let boundary = cell[axis] as f64 * self.cell_size;
+                t_max[axis] = (boundary - origin[axis]) / direction[axis];
+                t_delta[axis] = -self.cell_size / direction[axis];
+            }
+        }
+
+        let mut t = 0.0;
+        while t <= max_distance {
+            if let Some(entity_ids) = self.cells.get(&cell) {
+                for &entity_id in entity_ids {
+                    if found.contains_key(&entity_id) {
+                        continue;
+                    }
+                    if let Some(hit) = self.entries[&entity_id]
+                        .bounds
+                        .ray_hit(origin, direction)
+                        .filter(|&hit| hit <= max_distance)
+                    {
+                        found.insert(entity_id, hit);
+                    }
+                }
+            }
+
+            let axis = (0..3)
+                .min_by(|&a, &b| t_max[a].total_cmp(&t_max[b]))
+                .unwrap();
+            if t_max[axis].is_infinite() {
+                // zero direction
+                break;
+            }
+            t = t_max[axis];
+            t_max[axis] += t_delta[axis];
+            cell[axis] += step[axis];
+        }
+
+        let mut found = found.into_iter().collect::<Vec<_>>();
+        found.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+        found
+    }
+
+    /// Pairs of entities whose bounds overlap, for collision broad-phase. Each pair is only listed once, with the
+    /// smaller ID first.
+    pub fn overlapping_pairs(&self) -> Vec<(EntityId, EntityId)> {
+        let mut pairs = BTreeSet::new();
+        for entity_ids in self.cells.values() {
+            for (index, &a) in entity_ids.iter().enumerate() {
+                for &b in &entity_ids[index + 1..] {
+                    let pair = (a.min(b), a.max(b));
+                    if !pairs.contains(&pair)
+                        && self.entries[&a].bounds.overlaps(self.entries[&b].bounds)
+                    {
+                        pairs.insert(pair);
+                    }
+                }
+            }
+        }
+
+        pairs.into_iter().collect()
+    }
+}
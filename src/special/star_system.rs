@@ -0,0 +1,206 @@
+use super::{
+    inertial_frame::InertialFrame,
+    prefab::{PrefabRegistry, PREFABS},
+    universe::{Entity, Universe},
+    worldline::{Orbit, Worldline, WorldlineEvent, WorldlineEventKind},
+};
+use crate::shared::{interning::ModelId, numerical_integration::Integrator};
+use cgmath::{vec3, InnerSpace, Matrix4, Vector3, Vector4, Zero};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::TAU;
+
+/// Star colors, from cool to hot.
+pub const STAR_COLORS: [[f32; 4]; 5] = [
+    [1.0, 0.55, 0.35, 1.0],
+    [1.0, 0.75, 0.5, 1.0],
+    [1.0, 0.95, 0.8, 1.0],
+    [0.95, 0.95, 1.0, 1.0],
+    [0.7, 0.8, 1.0, 1.0],
+];
+pub const PLANET_COLORS: [[f32; 4]; 6] = [
+    [0.5, 0.7, 0.9, 1.0],
+    [0.8, 0.6, 0.4, 1.0],
+    [0.6, 0.8, 0.5, 1.0],
+    [0.9, 0.85, 0.7, 1.0],
+    [0.7, 0.4, 0.3, 1.0],
+    [0.6, 0.6, 0.65, 1.0],
+];
+
+/// How [`generate_star_systems()`] fills space. Everything's in light-seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StarSystemSettings {
+    /// The same seed and settings always give the same systems.
+    pub seed: u64,
+    /// Systems are scattered through a cube this wide, centered on the origin.
+    pub extent: f64,
+    /// Systems per billion cubic light-seconds.
+    pub density: f64,
+    /// Nothing gets put this close to the origin, so the user doesn't start out inside a star.
+    pub clear_radius: f64,
+    pub min_planets: u32,
+    pub max_planets: u32,
+    /// How fast the innermost planets go, as a fraction of c. Ones further out go slower.
+    pub max_orbital_speed: f64,
+    /// The chance of a system getting a beacon over it, from 0 to 1.
+    pub beacon_chance: f64,
+}
+
+impl Default for StarSystemSettings {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            extent: 10000.0,
+            density: 1.0,
+            clear_radius: 500.0,
+            min_planets: 1,
+            max_planets: 6,
+            max_orbital_speed: 0.3,
+            beacon_chance: 0.25,
+        }
+    }
+}
+
+impl StarSystemSettings {
+    pub fn system_count(&self) -> usize {
+        (self.extent.powi(3) / 1e9 * self.density).round().max(0.0) as usize
+    }
+}
+
+/// Makes an entity from a prefab, or a plain sphere if the prefab's missing (e.g. it got replaced by one with no
+/// model). `scale` is on top of the prefab's own, and `color` replaces the prefab's if it's given.
+fn build_body(
+    prefabs: &PrefabRegistry,
+    prefab: &str,
+    event: WorldlineEvent,
+    scale: f32,
+    color: Option<[f32; 4]>,
+) -> Entity {
+    let mut entity = prefabs
+        .instantiate(prefab, event.frame)
+        .unwrap_or_else(|| Entity {
+            model: Some(ModelId::intern("uv_sphere")),
+            ..Default::default()
+        });
+    entity.worldline = Worldline::from_event(event);
+    entity.model_matrix = entity.model_matrix * Matrix4::from_scale(scale);
+    if let Some(color) = color {
+        entity.model_color = Vector4::from(color);
+    }
+    entity
+}
+
+/// Two directions perpendicular to `normal` and each other.
+fn plane_basis(normal: Vector3<f64>) -> (Vector3<f64>, Vector3<f64>) {
+    let helper = if normal.x.abs() < 0.9 {
+        Vector3::unit_x()
+    } else {
+        Vector3::unit_y()
+    };
+    let u = normal.cross(helper).normalize();
+    (u, normal.cross(u))
+}
+
+fn random_direction(rng: &mut ChaCha8Rng) -> Vector3<f64> {
+    // uniform on the sphere
+    let z: f64 = rng.gen_range(-1.0..=1.0);
+    let angle = rng.gen_range(0.0..TAU);
+    let ring = (1.0 - z * z).sqrt();
+    vec3(ring * angle.cos(), ring * angle.sin(), z)
+}
+
+/// Scatters star systems through the universe: a star at rest in the middle of each one, planets going around it on
+/// circular worldlines, and now and then a beacon hanging over it. They all start out at the universe's current
+/// coordinate time. Returns how many systems were made.
+pub fn generate_star_systems(universe: &mut Universe, settings: &StarSystemSettings) -> usize {
+    let mut rng = ChaCha8Rng::seed_from_u64(settings.seed);
+    let prefabs = PREFABS.read().unwrap();
+    let time = universe.time;
+    let half_extent = settings.extent / 2.0;
+    let event_at = |position: Vector3<f64>, velocity: Vector3<f64>, kind| WorldlineEvent {
+        frame: InertialFrame {
+            position: position.extend(time),
+            velocity,
+        },
+        proper_time: 0.0,
+        kind,
+        integrator: Integrator::default(),
+    };
+
+    let system_count = settings.system_count();
+    let mut made = 0;
+    for _ in 0..system_count {
+        let center = vec3(
+            rng.gen_range(-half_extent..=half_extent),
+            rng.gen_range(-half_extent..=half_extent),
+            rng.gen_range(-half_extent..=half_extent),
+        );
+        if center.magnitude() < settings.clear_radius {
+            continue;
+        }
+        made += 1;
+
+        let star_scale = rng.gen_range(0.5..1.5);
+        let star_color = STAR_COLORS[rng.gen_range(0..STAR_COLORS.len())];
+        universe.insert_entity(build_body(
+            &prefabs,
+            "star",
+            event_at(center, Vector3::zero(), WorldlineEventKind::Inertial),
+            star_scale,
+            Some(star_color),
+        ));
+
+        // planets mostly share a plane, like they would if they formed from a disk
+        let system_normal = random_direction(&mut rng);
+        let planet_count =
+            rng.gen_range(settings.min_planets..=settings.max_planets.max(settings.min_planets));
+        let innermost_radius = 100.0 * star_scale as f64;
+        let mut radius = innermost_radius;
+        for _ in 0..planet_count {
+            radius += rng.gen_range(60.0..200.0);
+            let tilt = random_direction(&mut rng) * 0.1;
+            let normal = (system_normal + tilt).normalize();
+            let (u, w) = plane_basis(normal);
+
+            // slower further out, like Kepler's third law would have it
+            let speed = settings.max_orbital_speed * (innermost_radius / radius).sqrt();
+            let angular_speed = speed / radius;
+            let phase = rng.gen_range(0.0..TAU);
+            let offset = (u * phase.cos() + w * phase.sin()) * radius;
+            let angular_velocity = normal * angular_speed;
+
+            universe.insert_entity(build_body(
+                &prefabs,
+                "planet",
+                event_at(
+                    center + offset,
+                    angular_velocity.cross(offset),
+                    WorldlineEventKind::Circular(Orbit {
+                        center,
+                        angular_velocity,
+                    }),
+                ),
+                rng.gen_range(0.4..1.6),
+                Some(PLANET_COLORS[rng.gen_range(0..PLANET_COLORS.len())]),
+            ));
+        }
+
+        if rng.gen_bool(settings.beacon_chance.clamp(0.0, 1.0)) {
+            universe.insert_entity(build_body(
+                &prefabs,
+                "beacon",
+                event_at(
+                    center + system_normal * (radius + 50.0),
+                    Vector3::zero(),
+                    WorldlineEventKind::Inertial,
+                ),
+                1.0,
+                None,
+            ));
+        }
+    }
+
+    made
+}
@@ -1,3 +1,4 @@
+use super::worldline::MAX_SPEED;
 use cgmath::{InnerSpace, Matrix, Matrix3, Matrix4, SquareMatrix, Vector3, Vector4, Zero};
 
 /// Calculates the Lorentz/gamma (time dilation/length contraction) factor for a given 3-velocity.
@@ -66,6 +67,17 @@ pub fn const_accel_displacement(proper_accel: f64, rest_time: f64) -> f64 {
     ((1.0 + (proper_accel * rest_time).powi(2)).sqrt() - 1.0) / proper_accel
 }
 
+/// Expresses a 3-velocity in units of a rescaled speed of light (given as a portion of the real one), so that the
+/// other helpers here can be used as if light were that slow. Clamped to stay just under it.
+pub fn rescale_velocity(velocity: Vector3<f64>, speed_of_light: f64) -> Vector3<f64> {
+    let rescaled = velocity / speed_of_light;
+    if rescaled.magnitude2() >= MAX_SPEED * MAX_SPEED {
+        rescaled.normalize_to(MAX_SPEED)
+    } else {
+        rescaled
+    }
+}
+
 /// Converts a 3-velocity to its corresponding proper velocity (displacement per moving-clock-second).
 pub fn velocity_3_to_proper(velocity: Vector3<f64>) -> Vector3<f64> {
     velocity * lorentz_factor(velocity)
@@ -1,26 +1,123 @@
 use super::{
+    inertial_frame::InertialFrame,
+    prefab::PREFABS,
     transform::lorentz_factor,
-    worldline::{Worldline, WorldlineEvent, PHYS_TIME_STEP},
+    worldline::{Worldline, WorldlineEvent, WorldlineEventKind, PHYS_TIME_STEP},
 };
-use cgmath::{vec4, Matrix4, SquareMatrix, Vector4};
+use crate::shared::{
+    determinism::ENTITY_IDS,
+    interning::{ModelId, PrefabId},
+    profiler::profile_scope,
+};
+use cgmath::{vec4, InnerSpace, Matrix4, SquareMatrix, Vector3, Vector4, Zero};
 use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
-use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, num::ParseIntError};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// Saved as a hex string, since RON can't do 128-bit integers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
 pub struct EntityId(pub u128);
 
 impl EntityId {
+    /// Reproducible once [`ENTITY_IDS`] is seeded.
     pub fn generate() -> Self {
-        Self(rand::random())
+        Self(ENTITY_IDS.next_id())
+    }
+}
+
+impl From<EntityId> for String {
+    fn from(entity_id: EntityId) -> Self {
+        format!("{:032x}", entity_id.0)
+    }
+}
+
+impl TryFrom<String> for EntityId {
+    type Error = ParseIntError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        u128::from_str_radix(&value, 16).map(Self)
+    }
+}
+
+/// How far apart a chain of attachments can get before the rest of it is ignored. [`Universe::attach()`] won't
+/// make cycles, but a hand-edited save could.
+pub const MAX_ATTACHMENT_DEPTH: usize = 32;
+/// The slowest [`Universe::speed_of_light`] can go, as a portion of the real speed of light.
+pub const MIN_SPEED_OF_LIGHT: f64 = 0.001;
+/// How many times [`Attachment::apply()`] halves its guess at where a bouncing child is.
+pub const BOUNCE_SOLVER_ITERATIONS: usize = 48;
+
+/// Pins an entity to a parent, so it rides along without a worldline of its own.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Attachment {
+    pub parent: EntityId,
+    /// In the parent's instantaneous rest frame.
+    pub offset: Vector3<f64>,
+    /// If set, the child bounces from `offset` to `offset + bounce` and back at the speed of light (in the parent's
+    /// rest frame), like the photon in a light clock.
+    #[serde(default)]
+    pub bounce: Option<Vector3<f64>>,
+}
+
+impl Attachment {
+    /// Where the child is at the same coordinate time as `parent_event`. See [`WorldlineEvent::comoving()`].
+    pub fn apply(&self, parent_event: WorldlineEvent) -> WorldlineEvent {
+        let offset = match self.bounce {
+            Some(bounce) => self.offset + bounce * self.bounce_progress(bounce, parent_event),
+            None => self.offset,
+        };
+        parent_event.comoving(offset)
+    }
+
+    /// How far along its bounce the child is, from 0 at `offset` to 1 at the far end. The child's time in the parent's
+    /// rest frame depends on where it is (see [`WorldlineEvent::comoving()`]), and where it is depends on that time,
+    /// so this solves for where the two agree.
+    fn bounce_progress(&self, bounce: Vector3<f64>, parent_event: WorldlineEvent) -> f64 {
+        let length = bounce.magnitude();
+        if length <= 0.0 {
+            return 0.0;
+        }
+        let progress = |rest_time: f64| {
+            let phase = (rest_time / length).rem_euclid(2.0);
+            1.0 - (phase - 1.0).abs()
+        };
+
+        // rest_time + slope * progress(rest_time) = target, and the left side only ever goes up since the parent's
+        // slower than light
+        let velocity = parent_event.frame.velocity;
+        let target = parent_event.proper_time - self.offset.dot(velocity);
+        let slope = bounce.dot(velocity);
+        let (mut low, mut high) = (target - slope.abs(), target + slope.abs());
+        for _ in 0..BOUNCE_SOLVER_ITERATIONS {
+            let middle = (low + high) / 2.0;
+            if middle + slope * progress(middle) < target {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        progress((low + high) / 2.0)
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Entity {
+    #[serde(with = "super::worldline_encoding::compact")]
     pub worldline: Worldline,
-    pub model: Option<String>,
+    pub model: Option<ModelId>,
     pub model_matrix: Matrix4<f32>,
     pub model_color: Vector4<f32>,
+    /// What it was spawned as, if it came from a prefab.
+    #[serde(default)]
+    pub prefab: Option<PrefabId>,
+    /// While this is set, where the entity is comes from its parent instead of its worldline. Use
+    /// [`Universe::entity_event_at_time()`] rather than the worldline directly for anything that might be attached.
+    #[serde(default)]
+    pub attachment: Option<Attachment>,
+    /// Labels given to it by hand, e.g. to pick out everything that belongs together in a big scene.
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 impl Default for Entity {
@@ -30,15 +127,43 @@ impl Default for Entity {
             model: None,
             model_matrix: Matrix4::identity(),
             model_color: vec4(1.0, 1.0, 1.0, 1.0),
+            prefab: None,
+            attachment: None,
+            tags: Vec::new(),
         }
     }
 }
 
-#[derive(Debug, Clone)]
+impl Entity {
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|entity_tag| entity_tag == tag)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Universe {
     pub entities: BTreeMap<EntityId, Entity>,
     pub user_entity_id: EntityId,
     pub time: f64,
+    /// How fast light looks, as a portion of its real speed. Turning it down exaggerates relativistic effects at low
+    /// speeds (e.g. for demos), but it only changes how things are shown. The physics always use the real one.
+    #[serde(default = "default_speed_of_light")]
+    pub speed_of_light: f64,
+    /// Where the coordinates' origin has been moved to by [`Universe::rebase()`], in the original coordinates.
+    #[serde(default = "default_origin")]
+    pub origin: Vector3<f64>,
+    /// How much coordinate time [`Universe::rebase()`] has taken off, so the scenario's own time is this plus
+    /// [`Universe::time`].
+    #[serde(default)]
+    pub time_origin: f64,
+}
+
+fn default_speed_of_light() -> f64 {
+    1.0
+}
+
+fn default_origin() -> Vector3<f64> {
+    Vector3::zero()
 }
 
 impl Default for Universe {
@@ -52,6 +177,9 @@ impl Default for Universe {
             entities,
             user_entity_id,
             time: 1000.0,
+            speed_of_light: 1.0,
+            origin: Vector3::zero(),
+            time_origin: 0.0,
         }
     }
 }
@@ -71,21 +199,219 @@ impl Universe {
         entity_id
     }
 
+    /// Like [`Universe::insert_entity()`], for when the ID has to be known ahead of time. Replaces whatever
+    /// already had that ID.
+    pub fn insert_entity_with_id(&mut self, entity_id: EntityId, entity: Entity) {
+        self.entities.insert(entity_id, entity);
+    }
+
+    /// Spawns one of the [`PREFABS`] at `frame`, along with its parts. Returns `None` if there's no prefab called
+    /// `name`.
+    pub fn spawn_prefab(&mut self, name: &str, frame: InertialFrame) -> Option<EntityId> {
+        let prefabs = PREFABS.read().unwrap();
+        let entity_id = self.insert_entity(prefabs.instantiate(name, frame)?);
+        for (part_id, part) in prefabs.instantiate_parts(name, entity_id, frame) {
+            self.insert_entity_with_id(part_id, part);
+        }
+        Some(entity_id)
+    }
+
+    /// Every entity spawned from a prefab with the given tag.
+    pub fn entities_tagged<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = EntityId> + 'a {
+        let prefabs = PREFABS.read().unwrap();
+        let tagged: Vec<PrefabId> = prefabs
+            .with_tag(tag)
+            .map(|(name, _)| PrefabId::intern(name))
+            .collect();
+
+        self.entities
+            .iter()
+            .filter(move |(_, entity)| entity.prefab.is_some_and(|prefab| tagged.contains(&prefab)))
+            .map(|(&entity_id, _)| entity_id)
+    }
+
+    /// Anything attached to the entity gets let go first, so it carries on from where it was.
     pub fn remove_entity(&mut self, entity_id: EntityId) -> Option<Entity> {
         if entity_id == self.user_entity_id {
             return None;
         }
 
+        for child_id in self.children(entity_id).collect::<Vec<_>>() {
+            self.detach(child_id);
+        }
         self.entities.remove(&entity_id)
     }
 
+    /// Where an entity is at some coordinate time, following its attachments up to whatever has a worldline.
+    pub fn entity_event_at_time(&self, entity: &Entity, coord_time: f64) -> WorldlineEvent {
+        let mut attachments = Vec::new();
+        let mut root = entity;
+        while let Some(attachment) = root.attachment {
+            // a missing parent just leaves the entity on its own worldline
+            let Some(parent) = self.entities.get(&attachment.parent) else {
+                break;
+            };
+            if attachments.len() >= MAX_ATTACHMENT_DEPTH {
+                break;
+            }
+            attachments.push(attachment);
+            root = parent;
+        }
+
+        attachments.iter().rev().fold(
+            root.worldline.get_event_at_time(coord_time),
+            |event, attachment| attachment.apply(event),
+        )
+    }
+
+    pub fn get_event_at_time(
+        &self,
+        entity_id: EntityId,
+        coord_time: f64,
+    ) -> Option<WorldlineEvent> {
+        let entity = self.entities.get(&entity_id)?;
+        Some(self.entity_event_at_time(entity, coord_time))
+    }
+
+    /// Whether `entity_id` is `ancestor_id`, or is attached to it somewhere up the chain.
+    pub fn is_descendant_of(&self, entity_id: EntityId, ancestor_id: EntityId) -> bool {
+        let mut current = entity_id;
+        for _ in 0..=MAX_ATTACHMENT_DEPTH {
+            if current == ancestor_id {
+                return true;
+            }
+            match self
+                .entities
+                .get(&current)
+                .and_then(|entity| entity.attachment)
+            {
+                Some(attachment) => current = attachment.parent,
+                None => return false,
+            }
+        }
+        false
+    }
+
+    /// Everything attached directly to an entity.
+    pub fn children(&self, parent_id: EntityId) -> impl Iterator<Item = EntityId> + '_ {
+        self.entities
+            .iter()
+            .filter(move |(_, entity)| {
+                entity
+                    .attachment
+                    .is_some_and(|attachment| attachment.parent == parent_id)
+            })
+            .map(|(&entity_id, _)| entity_id)
+    }
+
+    /// Attaches an entity to a parent, `offset` away in the parent's rest frame. Returns `false` (and does
+    /// nothing) if either entity doesn't exist, or if it would make a cycle.
+    pub fn attach(
+        &mut self,
+        entity_id: EntityId,
+        parent_id: EntityId,
+        offset: Vector3<f64>,
+    ) -> bool {
+        if !self.entities.contains_key(&parent_id) || self.is_descendant_of(parent_id, entity_id) {
+            return false;
+        }
+        let Some(entity) = self.entities.get_mut(&entity_id) else {
+            return false;
+        };
+
+        entity.attachment = Some(Attachment {
+            parent: parent_id,
+            offset,
+            bounce: None,
+        });
+        true
+    }
+
+    /// Lets go of an attached entity. It gets a worldline of its own starting from where it is now, and coasts
+    /// from there.
+    pub fn detach(&mut self, entity_id: EntityId) {
+        let Some(entity) = self.entities.get(&entity_id) else {
+            return;
+        };
+        if entity.attachment.is_none() {
+            return;
+        }
+
+        let event = WorldlineEvent {
+            kind: WorldlineEventKind::Inertial,
+            ..self.entity_event_at_time(entity, self.time)
+        };
+        let entity = self.entities.get_mut(&entity_id).unwrap();
+        entity.attachment = None;
+        entity.worldline = Worldline::from_event(event);
+    }
+
+    /// The closest entity to the user (other than the user itself) at the current coordinate time.
+    pub fn nearest_entity_to_user(&self) -> Option<EntityId> {
+        let user_position = self.user_event_now().frame.position.truncate();
+
+        self.entities
+            .iter()
+            .filter(|(&entity_id, _)| entity_id != self.user_entity_id)
+            .map(|(&entity_id, entity)| {
+                let position = self
+                    .entity_event_at_time(entity, self.time)
+                    .frame
+                    .position
+                    .truncate();
+                (entity_id, (position - user_position).magnitude2())
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(entity_id, _)| entity_id)
+    }
+
+    /// Sets [`Universe::speed_of_light`], keeping it between [`MIN_SPEED_OF_LIGHT`] and the real thing.
+    pub fn set_speed_of_light(&mut self, speed_of_light: f64) {
+        self.speed_of_light = speed_of_light.clamp(MIN_SPEED_OF_LIGHT, 1.0);
+    }
+
+    /// Moves the coordinates' origin to `offset` (in the current coordinates, with coordinate time in `w`), shifting
+    /// every worldline and the current time back by it. Nothing moves relative to anything else, so it can't be seen,
+    /// but positions and times stay small enough to keep their precision after a long journey.
+    pub fn rebase(&mut self, offset: Vector4<f64>) {
+        for entity in self.entities.values_mut() {
+            entity.worldline.translate(-offset);
+        }
+        self.time -= offset.w;
+        self.origin += offset.truncate();
+        self.time_origin += offset.w;
+    }
+
+    /// Where the scenario's origin is in the current coordinates, as moved by [`Universe::rebase()`].
+    pub fn scenario_origin(&self) -> Vector4<f64> {
+        self.origin.extend(self.time_origin)
+    }
+
+    /// A coordinate time in the scenario's own coordinates, unaffected by [`Universe::rebase()`], for anything
+    /// shown to the user or handed to scenarios and scripts.
+    pub fn scenario_time(&self, coord_time: f64) -> f64 {
+        coord_time + self.time_origin
+    }
+
+    /// The opposite of [`Universe::scenario_time()`].
+    pub fn from_scenario_time(&self, scenario_time: f64) -> f64 {
+        scenario_time - self.time_origin
+    }
+
+    /// `frame` in coordinates where light goes at [`Universe::speed_of_light`], for showing relativistic effects with.
+    pub fn shown_frame(&self, frame: InertialFrame) -> InertialFrame {
+        frame.with_speed_of_light(self.speed_of_light)
+    }
+
     pub fn user_event_now(&self) -> WorldlineEvent {
-        self.get_user_entity()
-            .worldline
-            .get_event_at_time(self.time)
+        self.entity_event_at_time(self.get_user_entity(), self.time)
     }
 
+    /// Entities are stepped in parallel, but each one only depends on its own worldline and the user's frame from
+    /// before the step, so the order they get to doesn't change the outcome.
     pub fn step(&mut self, delta: f64) {
+        profile_scope!("Universe::step");
+
         let user_event = self.user_event_now();
         let user_frame = user_event.frame;
         let user_gamma = lorentz_factor(user_frame.velocity);
@@ -93,6 +419,10 @@ impl Universe {
         self.time += delta * user_gamma;
 
         self.entities.par_iter_mut().for_each(|(_, entity)| {
+            // attached entities don't use their worldlines
+            if entity.attachment.is_some() {
+                return;
+            }
             entity.worldline.time_resolution = PHYS_TIME_STEP * user_gamma;
             entity.worldline.bake_events(self.time);
         });
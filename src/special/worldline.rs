@@ -1,30 +1,134 @@
 use super::{inertial_frame::InertialFrame, transform::lorentz_factor};
-use cgmath::Vector3;
+use crate::shared::numerical_integration::Integrator;
+use cgmath::{InnerSpace, One, Quaternion, Rad, Rotation, Rotation3, Vector3, Vector4};
 use derive_more::*;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
 pub const PHYS_TIME_STEP: f64 = 1.0 / 240.0;
 pub const EVENT_BAKE_INTERVAL: f64 = 1.0;
 pub const MAX_SPEED: f64 = 0.99999999999;
+/// New accelerations that differ from the current one by less than this fraction of its size get merged into it,
+/// instead of starting a new segment. Turning while thrusting would otherwise add an event every frame.
+pub const COALESCE_TOLERANCE: f64 = 1e-3;
+/// Segments shorter than this (in seconds of coordinate time) are thrown out when something gets inserted after them.
+pub const MIN_SEGMENT_DURATION: f64 = 1e-9;
 
 /// A specific kind of worldline event, paired with information specific to that kind.
-#[derive(Debug, Clone, Copy, Unwrap, IsVariant)]
+#[derive(Debug, Clone, Copy, PartialEq, Unwrap, IsVariant, Serialize, Deserialize)]
 pub enum WorldlineEventKind {
     /// Constant velocity.
     Inertial,
     /// Constant proper acceleration.
     Acceleration(Vector3<f64>),
+    /// Going around in a circle at a constant speed, like a planet.
+    Circular(Orbit),
+}
+
+impl WorldlineEventKind {
+    /// Whether a segment of `other` would carry on the same as one of `self`, within [`COALESCE_TOLERANCE`]. Coasting
+    /// counts as accelerating by nothing.
+    pub fn nearly_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Circular(a), Self::Circular(b)) => a == b,
+            (Self::Circular(_), _) | (_, Self::Circular(_)) => false,
+            _ => {
+                let a = self.acceleration();
+                let b = other.acceleration();
+                (a - b).magnitude() <= COALESCE_TOLERANCE * a.magnitude().max(b.magnitude())
+            }
+        }
+    }
+
+    /// The proper acceleration, or zero if it's not accelerating.
+    pub fn acceleration(&self) -> Vector3<f64> {
+        match self {
+            Self::Acceleration(proper_accel) => *proper_accel,
+            _ => Vector3::new(0.0, 0.0, 0.0),
+        }
+    }
+}
+
+/// Uniform circular motion around a fixed point.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Orbit {
+    pub center: Vector3<f64>,
+    /// Points along the axis (by the right-hand rule), with a length in radians per second of coordinate time.
+    pub angular_velocity: Vector3<f64>,
 }
 
 /// A keyframe event on a worldline.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct WorldlineEvent {
     pub frame: InertialFrame,
     pub proper_time: f64,
     pub kind: WorldlineEventKind,
+    /// How the segment starting at this event gets simulated, if it's not inertial.
+    #[serde(default)]
+    pub integrator: Integrator,
 }
 
 impl WorldlineEvent {
+    /// Whether it's somewhere real and slower than light, for anything that comes from outside, like another player
+    /// or the clipboard.
+    pub fn is_valid(&self) -> bool {
+        let frame = self.frame;
+        let numbers = [
+            frame.position.x,
+            frame.position.y,
+            frame.position.z,
+            frame.position.w,
+        ];
+        numbers.iter().all(|number| number.is_finite())
+            && self.proper_time.is_finite()
+            && frame.velocity.magnitude() < 1.0
+    }
+
+    /// The event `offset` away in this event's instantaneous rest frame, at the same coordinate time and moving
+    /// along with it.
+    ///
+    /// Moving along with it, the offset gets length contracted along the velocity, and the proper time there lags
+    /// behind by `offset · velocity`, so that the two are synchronized in the rest frame. That's exact for an
+    /// inertial path, and close enough for an accelerating one as long as the offset is small.
+    pub fn comoving(self, offset: Vector3<f64>) -> Self {
+        let velocity = self.frame.velocity;
+        let speed2 = velocity.magnitude2();
+        let contracted_offset = if speed2 > 0.0 {
+            let parallel = velocity * (offset.dot(velocity) / speed2);
+            offset - parallel + parallel / lorentz_factor(velocity)
+        } else {
+            offset
+        };
+
+        Self {
+            frame: InertialFrame {
+                position: self.frame.position + contracted_offset.extend(0.0),
+                velocity,
+            },
+            proper_time: self.proper_time - offset.dot(velocity),
+            ..self
+        }
+    }
+
+    /// The same event, moved `offset` over in space and time (`w`). Orbits get their centers moved along with it.
+    pub fn translated(self, offset: Vector4<f64>) -> Self {
+        let kind = match self.kind {
+            WorldlineEventKind::Circular(orbit) => WorldlineEventKind::Circular(Orbit {
+                center: orbit.center + offset.truncate(),
+                ..orbit
+            }),
+            kind => kind,
+        };
+        Self {
+            frame: InertialFrame {
+                position: self.frame.position + offset,
+                ..self.frame
+            },
+            kind,
+            ..self
+        }
+    }
+
     pub fn get_event_at_time_offset(&self, coord_time_offset: f64, time_resolution: f64) -> Self {
         let (frame, proper_time) = match self.kind {
             WorldlineEventKind::Inertial => (
@@ -44,27 +148,57 @@ impl WorldlineEvent {
                     if i == step_count - 1 {
                         step_size = coord_time_offset.rem_euclid(step_size);
                     }
-                    proper_time += frame.step(step_size, proper_accel);
+                    proper_time += frame.step(step_size, proper_accel, self.integrator);
                 }
 
                 (frame, proper_time)
             }
+            WorldlineEventKind::Circular(Orbit {
+                center,
+                angular_velocity,
+            }) => {
+                // unlike acceleration, this one has an exact solution: spin the position and velocity around the
+                // axis, and the speed never changes so neither does time dilation
+                let angular_speed = angular_velocity.magnitude();
+                let rotation = if angular_speed > 0.0 {
+                    Quaternion::from_axis_angle(
+                        angular_velocity / angular_speed,
+                        Rad(angular_speed * coord_time_offset),
+                    )
+                } else {
+                    Quaternion::one()
+                };
+
+                let position = self.frame.position.truncate();
+                (
+                    InertialFrame {
+                        position: (center + rotation.rotate_vector(position - center))
+                            .extend(self.frame.position.w + coord_time_offset),
+                        velocity: rotation.rotate_vector(self.frame.velocity),
+                    },
+                    self.proper_time + coord_time_offset / lorentz_factor(self.frame.velocity),
+                )
+            }
         };
 
         Self {
             frame,
             proper_time,
             kind: self.kind,
+            integrator: self.integrator,
         }
     }
 }
 
 /// The path that an entity traces through spacetime. There is no notion of "now" on a worldline alone, it
 /// simply represents a static path that can be modified.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Worldline {
     events: VecDeque<WorldlineEvent>,
     pub time_resolution: f64,
+    /// How many inserted events got merged into the one before or replaced a zero-length one, for debugging.
+    #[serde(skip)]
+    coalesced_events: usize,
 }
 
 impl Default for Worldline {
@@ -80,12 +214,86 @@ impl Worldline {
                 frame: start_frame,
                 proper_time: 0.0,
                 kind: WorldlineEventKind::Inertial,
+                integrator: Integrator::default(),
             }]
             .into(),
             time_resolution: PHYS_TIME_STEP,
+            coalesced_events: 0,
         }
     }
 
+    /// Starts from an event partway through some other path, keeping its proper time.
+    pub fn from_event(start_event: WorldlineEvent) -> Self {
+        Self {
+            events: [start_event].into(),
+            time_resolution: PHYS_TIME_STEP,
+            coalesced_events: 0,
+        }
+    }
+
+    /// Picks up from events that were saved or sent elsewhere, which have to already be in order of coordinate time.
+    pub fn from_events(events: VecDeque<WorldlineEvent>, time_resolution: f64) -> Self {
+        Self {
+            events,
+            time_resolution,
+            coalesced_events: 0,
+        }
+    }
+
+    /// A copy of this worldline to try events out on. See [`WorldlineBranch`].
+    pub fn branch(&self) -> WorldlineBranch {
+        WorldlineBranch {
+            worldline: self.clone(),
+            candidates: Vec::new(),
+        }
+    }
+
+    /// All of the keyframe events, in order of coordinate time.
+    pub fn events(&self) -> &VecDeque<WorldlineEvent> {
+        &self.events
+    }
+
+    /// How many inserted events have been coalesced away so far. See [`Worldline::insert_event_with_integrator()`].
+    pub fn coalesced_events(&self) -> usize {
+        self.coalesced_events
+    }
+
+    /// The keyframe events (and their indices) that happen within the given coordinate time range.
+    pub fn events_between(
+        &self,
+        start_coord_time: f64,
+        end_coord_time: f64,
+    ) -> impl Iterator<Item = (usize, &WorldlineEvent)> {
+        let start_index = self
+            .events
+            .partition_point(|event| event.frame.position.w < start_coord_time);
+
+        self.events
+            .iter()
+            .enumerate()
+            .skip(start_index)
+            .take_while(move |(_, event)| event.frame.position.w <= end_coord_time)
+    }
+
+    /// Samples the worldline at `count` evenly spaced coordinate times, from `start_coord_time` to
+    /// `end_coord_time` (inclusive).
+    pub fn sample(
+        &self,
+        start_coord_time: f64,
+        end_coord_time: f64,
+        count: usize,
+    ) -> Vec<WorldlineEvent> {
+        let step = if count > 1 {
+            (end_coord_time - start_coord_time) / (count - 1) as f64
+        } else {
+            0.0
+        };
+
+        (0..count)
+            .map(|i| self.get_event_at_time(start_coord_time + step * i as f64))
+            .collect()
+    }
+
     fn get_neighbor_event_indices(&self, coord_time: f64) -> (Option<usize>, Option<usize>) {
         if self.events.is_empty() {
             return (None, None);
@@ -117,6 +325,7 @@ impl Worldline {
                 frame: InertialFrame::default(),
                 proper_time: 0.0,
                 kind: WorldlineEventKind::Inertial,
+                integrator: Integrator::default(),
             },
             (None, Some(index_after)) => {
                 let fake_inertial = WorldlineEvent {
@@ -139,18 +348,75 @@ impl Worldline {
     }
 
     pub fn insert_event(&mut self, coord_time: f64, kind: WorldlineEventKind) {
+        self.insert_event_with_integrator(coord_time, kind, Integrator::default());
+    }
+
+    /// Like [`Worldline::insert_event()`], but the new segment is simulated with a specific integrator. Baked
+    /// events carry it along.
+    ///
+    /// To keep rapid inputs from bloating the worldline, an event that would carry on the same as the one before it
+    /// (see [`WorldlineEventKind::nearly_eq()`]) gets merged into it instead, and one that would leave a zero-length
+    /// segment behind replaces it.
+    pub fn insert_event_with_integrator(
+        &mut self,
+        coord_time: f64,
+        kind: WorldlineEventKind,
+        integrator: Integrator,
+    ) {
         self.bake_events(coord_time);
+
+        while let (Some(index_before), _) = self.get_neighbor_event_indices(coord_time) {
+            let before = self.events[index_before];
+            if before.integrator == integrator && before.kind.nearly_eq(&kind) {
+                // whatever came after is still replaced, same as if the event had gone in
+                self.events.drain(index_before + 1..);
+                self.coalesced_events += 1;
+                return;
+            }
+            if coord_time - before.frame.position.w >= MIN_SEGMENT_DURATION || index_before == 0 {
+                break;
+            }
+            self.events.drain(index_before..);
+            self.coalesced_events += 1;
+        }
+
+        self.split_at(coord_time, kind, integrator);
+    }
+
+    /// Starts a new segment at `coord_time`, throwing out everything after it.
+    fn split_at(&mut self, coord_time: f64, kind: WorldlineEventKind, integrator: Integrator) {
+        // worked out before anything gets thrown out, so an event right on top of an existing one (or before the
+        // first one) still starts from where the worldline was
+        let mut event = self.get_event_at_time(coord_time);
         let (_, index_after) = self.get_neighbor_event_indices(coord_time);
 
         if let Some(index_after) = index_after {
             self.events.drain(index_after..);
         }
 
-        let mut event = self.get_event_at_time(coord_time);
         event.kind = kind;
+        event.integrator = integrator;
+        self.events.push_back(event);
+    }
+
+    /// Like [`Worldline::insert_event()`], but with an event that's already been worked out somewhere else (e.g.
+    /// on another client), so it goes in exactly as it is.
+    pub fn insert_exact_event(&mut self, event: WorldlineEvent) {
+        let index_after = self
+            .events
+            .partition_point(|existing| existing.frame.position.w < event.frame.position.w);
+        self.events.drain(index_after..);
         self.events.push_back(event);
     }
 
+    /// Moves the whole worldline `offset` over in space and time (`w`). See
+    /// [`Universe::rebase()`](super::universe::Universe::rebase).
+    pub fn translate(&mut self, offset: Vector4<f64>) {
+        for event in self.events.iter_mut() {
+            *event = event.translated(offset);
+        }
+    }
+
     pub fn bake_events(&mut self, coord_time: f64) {
         let (index_before, index_after) = self.get_neighbor_event_indices(coord_time);
         if index_after.is_some() {
@@ -160,17 +426,56 @@ impl Worldline {
 
         if let Some(index_before) = index_before {
             let event = self.events[index_before];
-            if event.kind.is_inertial() {
-                // no need to bake for linear motion
+            if event.kind.is_inertial() || event.kind.is_circular() {
+                // no need to bake for motion with an exact solution
                 return;
             }
 
             let multiplier = self.time_resolution / PHYS_TIME_STEP;
             let mut bake_coord_time = event.frame.position.w + EVENT_BAKE_INTERVAL * multiplier;
             while bake_coord_time < coord_time {
-                self.insert_event(bake_coord_time, event.kind);
+                self.split_at(bake_coord_time, event.kind, event.integrator);
                 bake_coord_time += EVENT_BAKE_INTERVAL * multiplier;
             }
         }
     }
 }
+
+/// A hypothetical copy of a worldline, for trying events out on without touching the real one, e.g. to preview a
+/// planned burn. It samples like any other worldline, and once it's been decided on, it either gets committed back or
+/// just dropped.
+#[derive(Debug, Clone)]
+pub struct WorldlineBranch {
+    worldline: Worldline,
+    /// The coordinate time and kind of every event put in so far, oldest first, to do over on the real worldline.
+    candidates: Vec<(f64, WorldlineEventKind)>,
+}
+
+impl WorldlineBranch {
+    /// What the worldline would look like with the candidate events in.
+    pub fn worldline(&self) -> &Worldline {
+        &self.worldline
+    }
+
+    /// The events put in so far, oldest first. Inserting each of these into the real worldline commits the branch.
+    pub fn candidates(&self) -> &[(f64, WorldlineEventKind)] {
+        &self.candidates
+    }
+
+    /// See [`Worldline::insert_event()`].
+    pub fn insert_event(&mut self, coord_time: f64, kind: WorldlineEventKind) {
+        self.worldline.insert_event(coord_time, kind);
+        self.candidates.push((coord_time, kind));
+    }
+
+    /// See [`Worldline::sample()`].
+    pub fn sample(
+        &self,
+        start_coord_time: f64,
+        end_coord_time: f64,
+        count: usize,
+    ) -> Vec<WorldlineEvent> {
+        self.worldline
+            .sample(start_coord_time, end_coord_time, count)
+    }
+}
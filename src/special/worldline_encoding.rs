@@ -0,0 +1,406 @@
+use super::{
+    inertial_frame::InertialFrame,
+    worldline::{Orbit, Worldline, WorldlineEvent, WorldlineEventKind, MAX_SPEED},
+};
+use crate::shared::numerical_integration::Integrator;
+use anyhow::{bail, Context, Result};
+use cgmath::{vec3, vec4, InnerSpace, Vector3};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// What every encoded worldline starts with.
+pub const WORLDLINE_ENCODING_MAGIC: [u8; 2] = *b"WL";
+/// Bumped whenever the layout changes. Anything newer than this can't be read.
+pub const WORLDLINE_ENCODING_VERSION: u8 = 1;
+
+/// How precisely numbers get stored.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Quantization {
+    /// Exactly as they are, which replays and saves need to come back out the same.
+    Lossless,
+    /// Rounded to single precision.
+    Single,
+    /// Rounded to the nearest multiple of a step. The smaller the changes between events are compared to the step,
+    /// the smaller it gets.
+    Fixed(f64),
+}
+
+impl Quantization {
+    fn tag(self) -> u8 {
+        match self {
+            Self::Lossless => 0,
+            Self::Single => 1,
+            Self::Fixed(_) => 2,
+        }
+    }
+
+    fn quantize(self, value: f64) -> u64 {
+        match self {
+            Self::Lossless => value.to_bits(),
+            Self::Single => (value as f32).to_bits() as u64,
+            Self::Fixed(step) => (value / step).round() as i64 as u64,
+        }
+    }
+
+    fn dequantize(self, word: u64) -> f64 {
+        match self {
+            Self::Lossless => f64::from_bits(word),
+            Self::Single => f32::from_bits(word as u32) as f64,
+            Self::Fixed(step) => word as i64 as f64 * step,
+        }
+    }
+
+    /// How `word` differs from `previous`, as something that's small when they're close. Floats that are close share
+    /// their top bits, so XORing them leaves those at zero. Fixed-point ones just get subtracted.
+    fn delta(self, word: u64, previous: u64) -> u64 {
+        match self {
+            Self::Lossless | Self::Single => word ^ previous,
+            Self::Fixed(_) => {
+                let difference = word.wrapping_sub(previous) as i64;
+                ((difference << 1) ^ (difference >> 63)) as u64
+            }
+        }
+    }
+
+    fn undelta(self, delta: u64, previous: u64) -> u64 {
+        match self {
+            Self::Lossless | Self::Single => delta ^ previous,
+            Self::Fixed(_) => {
+                let difference = (delta >> 1) as i64 ^ -((delta & 1) as i64);
+                previous.wrapping_add(difference as u64)
+            }
+        }
+    }
+}
+
+/// Where each number in an event goes, so each one is only ever compared with the same number from earlier events.
+const POSITION: usize = 0;
+const VELOCITY: usize = 4;
+const PROPER_TIME: usize = 7;
+const ACCELERATION: usize = 8;
+const ORBIT_CENTER: usize = 11;
+const ORBIT_ANGULAR_VELOCITY: usize = 14;
+const CHANNELS: usize = 17;
+
+/// Writes numbers as their change from the last one in the same channel.
+struct EventWriter {
+    bytes: Vec<u8>,
+    quantization: Quantization,
+    previous: [u64; CHANNELS],
+}
+
+impl EventWriter {
+    fn write_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.bytes.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.bytes.push(value as u8);
+    }
+
+    fn write(&mut self, channel: usize, value: f64) {
+        let word = self.quantization.quantize(value);
+        let delta = self.quantization.delta(word, self.previous[channel]);
+        self.previous[channel] = word;
+        self.write_varint(delta);
+    }
+
+    fn write_vector(&mut self, channel: usize, vector: Vector3<f64>) {
+        for (index, value) in [vector.x, vector.y, vector.z].into_iter().enumerate() {
+            self.write(channel + index, value);
+        }
+    }
+}
+
+/// The other end of an [`EventWriter`].
+struct EventReader<'a> {
+    bytes: &'a [u8],
+    quantization: Quantization,
+    previous: [u64; CHANNELS],
+}
+
+impl EventReader<'_> {
+    fn read_byte(&mut self) -> Result<u8> {
+        let (&byte, rest) = self
+            .bytes
+            .split_first()
+            .context("encoded worldline ends early")?;
+        self.bytes = rest;
+        Ok(byte)
+    }
+
+    fn read_f64(&mut self) -> Result<f64> {
+        let Some((bytes, rest)) = self.bytes.split_first_chunk::<8>() else {
+            bail!("encoded worldline ends early");
+        };
+        self.bytes = rest;
+        Ok(f64::from_le_bytes(*bytes))
+    }
+
+    fn read_varint(&mut self) -> Result<u64> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        bail!("encoded worldline has a number that's too long");
+    }
+
+    fn read(&mut self, channel: usize) -> Result<f64> {
+        let delta = self.read_varint()?;
+        let word = self.quantization.undelta(delta, self.previous[channel]);
+        self.previous[channel] = word;
+        Ok(self.quantization.dequantize(word))
+    }
+
+    fn read_vector(&mut self, channel: usize) -> Result<Vector3<f64>> {
+        Ok(vec3(
+            self.read(channel)?,
+            self.read(channel + 1)?,
+            self.read(channel + 2)?,
+        ))
+    }
+}
+
+/// Encodes all of `worldline`'s events, with their numbers stored as precisely as `quantization` says. After a short
+/// header, each event is a byte of flags followed by how much each of its numbers changed since the event before, so
+/// anything that stays put (like most of a straight-line trip) takes a single byte.
+pub fn encode(worldline: &Worldline, quantization: Quantization) -> Vec<u8> {
+    let mut writer = EventWriter {
+        bytes: Vec::new(),
+        quantization,
+        previous: [0; CHANNELS],
+    };
+    writer.bytes.extend(WORLDLINE_ENCODING_MAGIC);
+    writer.bytes.push(WORLDLINE_ENCODING_VERSION);
+    writer.bytes.push(quantization.tag());
+    if let Quantization::Fixed(step) = quantization {
+        writer.bytes.extend(step.to_le_bytes());
+    }
+    writer.bytes.extend(worldline.time_resolution.to_le_bytes());
+    writer.write_varint(worldline.events().len() as u64);
+
+    for event in worldline.events() {
+        let kind = match event.kind {
+            WorldlineEventKind::Inertial => 0,
+            WorldlineEventKind::Acceleration(_) => 1,
+            WorldlineEventKind::Circular(_) => 2,
+        };
+        let integrator = match event.integrator {
+            Integrator::RungeKutta4 => 0,
+            Integrator::VelocityVerlet => 1,
+        };
+        writer.bytes.push(kind | integrator << 2);
+
+        let position = event.frame.position;
+        for (index, value) in [position.x, position.y, position.z, position.w]
+            .into_iter()
+            .enumerate()
+        {
+            writer.write(POSITION + index, value);
+        }
+        writer.write_vector(VELOCITY, event.frame.velocity);
+        writer.write(PROPER_TIME, event.proper_time);
+        match event.kind {
+            WorldlineEventKind::Inertial => {}
+            WorldlineEventKind::Acceleration(acceleration) => {
+                writer.write_vector(ACCELERATION, acceleration)
+            }
+            WorldlineEventKind::Circular(orbit) => {
+                writer.write_vector(ORBIT_CENTER, orbit.center);
+                writer.write_vector(ORBIT_ANGULAR_VELOCITY, orbit.angular_velocity);
+            }
+        }
+    }
+
+    writer.bytes
+}
+
+/// Reads a worldline back out of what [`encode()`] made.
+pub fn decode(bytes: &[u8]) -> Result<Worldline> {
+    let Some((magic, rest)) = bytes.split_first_chunk::<2>() else {
+        bail!("encoded worldline is too short");
+    };
+    if *magic != WORLDLINE_ENCODING_MAGIC {
+        bail!("not an encoded worldline");
+    }
+    let mut reader = EventReader {
+        bytes: rest,
+        quantization: Quantization::Lossless,
+        previous: [0; CHANNELS],
+    };
+
+    let version = reader.read_byte()?;
+    if version > WORLDLINE_ENCODING_VERSION {
+        bail!(
+            "encoded worldline is version {}, expected {} or older",
+            version,
+            WORLDLINE_ENCODING_VERSION
+        );
+    }
+    reader.quantization = match reader.read_byte()? {
+        0 => Quantization::Lossless,
+        1 => Quantization::Single,
+        2 => Quantization::Fixed(reader.read_f64()?),
+        tag => bail!("encoded worldline has an unknown quantization {}", tag),
+    };
+    let time_resolution = reader.read_f64()?;
+    let count = reader.read_varint()? as usize;
+    if count == 0 {
+        bail!("encoded worldline has no events");
+    }
+
+    // every event takes up at least a byte, so a bad count can't ask for more than there is
+    let mut events = VecDeque::with_capacity(count.min(reader.bytes.len()));
+    for _ in 0..count {
+        let flags = reader.read_byte()?;
+        let integrator = match flags >> 2 {
+            0 => Integrator::RungeKutta4,
+            1 => Integrator::VelocityVerlet,
+            integrator => bail!("encoded worldline has an unknown integrator {}", integrator),
+        };
+
+        let position = vec4(
+            reader.read(POSITION)?,
+            reader.read(POSITION + 1)?,
+            reader.read(POSITION + 2)?,
+            reader.read(POSITION + 3)?,
+        );
+        let mut velocity = reader.read_vector(VELOCITY)?;
+        // rounding could have pushed it up to the speed of light
+        if velocity.magnitude() > MAX_SPEED {
+            velocity = velocity.normalize_to(MAX_SPEED);
+        }
+        let proper_time = reader.read(PROPER_TIME)?;
+        let kind = match flags & 0b11 {
+            0 => WorldlineEventKind::Inertial,
+            1 => WorldlineEventKind::Acceleration(reader.read_vector(ACCELERATION)?),
+            2 => WorldlineEventKind::Circular(Orbit {
+                center: reader.read_vector(ORBIT_CENTER)?,
+                angular_velocity: reader.read_vector(ORBIT_ANGULAR_VELOCITY)?,
+            }),
+            kind => bail!("encoded worldline has an unknown event kind {}", kind),
+        };
+
+        if events
+            .back()
+            .is_some_and(|previous: &WorldlineEvent| previous.frame.position.w > position.w)
+        {
+            bail!("encoded worldline's events are out of order");
+        }
+        events.push_back(WorldlineEvent {
+            frame: InertialFrame { position, velocity },
+            proper_time,
+            kind,
+            integrator,
+        });
+    }
+    if !reader.bytes.is_empty() {
+        bail!(
+            "encoded worldline has {} bytes left over",
+            reader.bytes.len()
+        );
+    }
+
+    Ok(Worldline::from_events(events, time_resolution))
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// For text formats, which can't hold raw bytes.
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |group, (index, &byte)| {
+                group | (byte as u32) << (16 - index * 8)
+            });
+        for index in 0..4 {
+            if index <= chunk.len() {
+                text.push(BASE64_ALPHABET[(group >> (18 - index * 6)) as usize & 0x3f] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+fn base64_decode(text: &str) -> Result<Vec<u8>> {
+    let text = text.trim_end_matches('=');
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    let mut group = 0u32;
+    let mut bits = 0;
+    for character in text.bytes() {
+        let Some(value) = BASE64_ALPHABET
+            .iter()
+            .position(|&letter| letter == character)
+        else {
+            bail!("'{}' isn't base64", character as char);
+        };
+        group = group << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((group >> bits) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+/// For `#[serde(with)]`: writes a worldline as its [lossless](Quantization::Lossless) encoding, and reads either that
+/// or the field-by-field layout it used to be written with.
+pub mod compact {
+    use super::{base64_decode, base64_encode, decode, encode, Quantization, Worldline};
+    use serde::{
+        de::{self, value::MapAccessDeserializer, MapAccess, Visitor},
+        Deserialize, Deserializer, Serializer,
+    };
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(
+        worldline: &Worldline,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let bytes = encode(worldline, Quantization::Lossless);
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&base64_encode(&bytes))
+        } else {
+            serializer.serialize_bytes(&bytes)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Worldline, D::Error> {
+        deserializer.deserialize_any(CompactVisitor)
+    }
+
+    struct CompactVisitor;
+
+    impl<'de> Visitor<'de> for CompactVisitor {
+        type Value = Worldline;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("an encoded worldline")
+        }
+
+        fn visit_str<E: de::Error>(self, text: &str) -> Result<Worldline, E> {
+            let bytes = base64_decode(text).map_err(E::custom)?;
+            self.visit_bytes(&bytes)
+        }
+
+        fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Worldline, E> {
+            decode(bytes).map_err(E::custom)
+        }
+
+        // from before worldlines were encoded
+        fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Worldline, A::Error> {
+            Worldline::deserialize(MapAccessDeserializer::new(map))
+        }
+    }
+}
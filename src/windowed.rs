@@ -0,0 +1,298 @@
+use crate::{
+    app_state::{frame_pacing::FrameLimiter, AppState, WinitEvent},
+    cli::Cli,
+    shared::{determinism::seed_ids, version::APP_VERSION},
+};
+use anyhow::Result;
+use log::error;
+use std::{sync::Arc, time::Instant};
+use winit::{
+    application::ApplicationHandler,
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{DeviceEvent, DeviceId, MouseScrollDelta, WindowEvent},
+    event_loop::{ActiveEventLoop, EventLoop},
+    window::{CursorGrabMode, Fullscreen, Window, WindowId},
+};
+
+struct App {
+    cli: Cli,
+    window: Option<Arc<Window>>,
+    app_state: Option<AppState>,
+    mouse_locked: bool,
+    /// Set when locking the cursor isn't supported, so it gets put back in the middle of the window every frame
+    /// instead.
+    recenter_cursor: bool,
+    cursor_visible: bool,
+    last_frame: Instant,
+    frame_limiter: FrameLimiter,
+    /// In hertz, if the monitor the window's on says.
+    refresh_rate: Option<f64>,
+    /// Fully covered by other windows, so there's no point drawing anything.
+    occluded: bool,
+    suspended: bool,
+}
+
+impl App {
+    /// Starts drawing frames again after [`window_visible()`] was false. The time spent hidden doesn't count as a
+    /// frame.
+    fn wake(&mut self) {
+        if let Some(window) = &self.window {
+            if window_visible(window, self.occluded, self.suspended) {
+                self.last_frame = Instant::now();
+                window.request_redraw();
+            }
+        }
+    }
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if let (Some(window), Some(app_state)) = (&self.window, &mut self.app_state) {
+            // back from being suspended, the window's still around but its surface isn't
+            if let Err(err) = app_state
+                .graphics_controller
+                .recreate_window_surface(Arc::clone(window))
+            {
+                error!("Couldn't draw to the window after resuming: {}", err);
+            }
+            self.suspended = false;
+            self.wake();
+            return;
+        }
+
+        let mut window_attributes =
+            Window::default_attributes().with_title(format!("Worldline v{}", APP_VERSION));
+        if let (Some(width), Some(height)) = (self.cli.width, self.cli.height) {
+            window_attributes = window_attributes.with_inner_size(PhysicalSize::new(width, height));
+        }
+        if self.cli.fullscreen {
+            window_attributes =
+                window_attributes.with_fullscreen(Some(Fullscreen::Borderless(None)));
+        }
+        let window = Arc::new(event_loop.create_window(window_attributes).unwrap());
+        window.set_ime_allowed(true);
+
+        // has to come before anything gets an ID
+        seed_ids(self.cli.seed);
+
+        let mut app_state = AppState::new(Arc::clone(&window), &self.cli).unwrap();
+        if self.cli.seed.is_some() {
+            app_state.enable_deterministic_mode();
+        }
+        if let Some(path) = &self.cli.record_input {
+            app_state.start_input_recording(path);
+        }
+        if let Some(path) = &self.cli.playback_input {
+            if let Err(err) = app_state.start_input_playback(path) {
+                error!("Couldn't play back input from {}: {}", path.display(), err);
+            }
+        }
+        self.mouse_locked = app_state.input_controller.is_mouse_locked();
+        self.app_state = Some(app_state);
+
+        self.refresh_rate = monitor_refresh_rate(&window);
+        self.window = Some(window);
+    }
+
+    fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
+        self.suspended = true;
+        if let Some(app_state) = &mut self.app_state {
+            app_state.window_focus_changed(false);
+            app_state.graphics_controller.drop_window_surface();
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        let (window, app_state) = match (&self.window, &mut self.app_state) {
+            (Some(window), Some(app_state)) => (window, app_state),
+            _ => return,
+        };
+
+        if app_state.auxiliary_window_id() == Some(window_id) {
+            auxiliary_window_event(app_state, &event);
+            return;
+        }
+        if window_id != window.id() {
+            return;
+        }
+
+        app_state.winit_event(WinitEvent::Window(&event));
+
+        match event {
+            WindowEvent::CloseRequested => {
+                if let Err(err) = app_state.finish_input_recording() {
+                    error!("Couldn't save input recording: {}", err);
+                }
+                app_state.save_session();
+                event_loop.exit();
+            }
+            WindowEvent::KeyboardInput {
+                // device_id,
+                // event: input_event,
+                // is_synthetic,
+                ..
+            } => {}
+            WindowEvent::RedrawRequested => {
+                if !window_visible(window, self.occluded, self.suspended) {
+                    // no more redraws get asked for, so nothing happens until it's back
+                    return;
+                }
+
+                self.frame_limiter
+                    .wait(app_state.frame_rate_limit(self.refresh_rate));
+                let real_frame_time = self.last_frame.elapsed();
+                self.last_frame = Instant::now();
+
+                // physics runs on its own thread, this just tells it how much time passed
+                let frame_time = app_state.begin_frame(real_frame_time.as_secs_f64());
+
+                // where the magic happens
+                app_state.render(frame_time);
+
+                if app_state.benchmark_finished() {
+                    app_state.finish_benchmark();
+                    event_loop.exit();
+                    return;
+                }
+
+                // mouse logic
+                let new_mouse_locked = app_state.input_controller.is_mouse_locked();
+                if new_mouse_locked != self.mouse_locked {
+                    if new_mouse_locked {
+                        self.recenter_cursor =
+                            window.set_cursor_grab(CursorGrabMode::Locked).is_err();
+                        if self.recenter_cursor {
+                            let _ = window.set_cursor_grab(CursorGrabMode::Confined);
+                        }
+                    } else {
+                        window.set_cursor_grab(CursorGrabMode::None).unwrap();
+                    }
+                }
+                self.mouse_locked = new_mouse_locked;
+                if self.mouse_locked && self.recenter_cursor {
+                    // without a real lock the cursor stops at the window's edges, and so does the mouse motion
+                    let size = window.inner_size();
+                    let _ = window
+                        .set_cursor_position(PhysicalPosition::new(size.width / 2, size.height / 2));
+                }
+
+                // the system cursor is hidden while locked, or while the game draws its own
+                let new_cursor_visible = !new_mouse_locked && !app_state.draws_cursor();
+                if new_cursor_visible != self.cursor_visible {
+                    window.set_cursor_visible(new_cursor_visible);
+                }
+                self.cursor_visible = new_cursor_visible;
+
+                app_state.input_controller.clear_inputs();
+
+                if app_state.wants_auxiliary_window() && app_state.auxiliary_window_id().is_none() {
+                    let window_attributes = Window::default_attributes()
+                        .with_title(format!("Worldline v{}", APP_VERSION));
+                    match event_loop.create_window(window_attributes) {
+                        Ok(auxiliary_window) => {
+                            app_state.open_auxiliary_window(Arc::new(auxiliary_window))
+                        }
+                        Err(err) => {
+                            error!("Couldn't open a second window: {}", err);
+                            app_state.close_auxiliary_window();
+                        }
+                    }
+                }
+
+                window.request_redraw();
+            }
+            WindowEvent::Resized(new_size) => {
+                app_state.graphics_controller.resize(new_size);
+                self.wake();
+            }
+            WindowEvent::Moved(_) | WindowEvent::ScaleFactorChanged { .. } => {
+                // it might be on another monitor now
+                self.refresh_rate = monitor_refresh_rate(window);
+            }
+            WindowEvent::Occluded(occluded) => {
+                self.occluded = occluded;
+                self.wake();
+            }
+            WindowEvent::Focused(is_focused) => {
+                app_state.window_focus_changed(is_focused);
+            }
+            _ => {
+
+            }
+        }
+    }
+
+    fn device_event(
+        &mut self,
+        _event_loop: &ActiveEventLoop,
+        _device_id: DeviceId,
+        event: DeviceEvent,
+    ) {
+        let (_, game_state) = match (&self.window, &mut self.app_state) {
+            (Some(window), Some(app_state)) => (window, app_state),
+            _ => return,
+        };
+
+        game_state.winit_event(WinitEvent::Device(&event))
+    }
+}
+
+/// Whether the main window can be seen at all. When it can't, frames stop until it can again, instead of spinning on a
+/// surface that never shows up.
+fn window_visible(window: &Window, occluded: bool, suspended: bool) -> bool {
+    let size = window.inner_size();
+    !occluded && !suspended && size.width * size.height > 0
+}
+
+fn monitor_refresh_rate(window: &Window) -> Option<f64> {
+    let millihertz = window.current_monitor()?.refresh_rate_millihertz()?;
+    Some(millihertz as f64 / 1000.0)
+}
+
+/// The second window only gets keyboard input (so keys work whichever window has focus) and the scroll wheel. The
+/// mouse otherwise stays with the main window.
+fn auxiliary_window_event(app_state: &mut AppState, event: &WindowEvent) {
+    match event {
+        WindowEvent::CloseRequested => app_state.close_auxiliary_window(),
+        WindowEvent::Resized(new_size) => app_state.resize_auxiliary_window(*new_size),
+        WindowEvent::MouseWheel { delta, .. } => {
+            let notches = match delta {
+                MouseScrollDelta::LineDelta(_, y) => *y,
+                MouseScrollDelta::PixelDelta(position) => position.y as f32 / 16.0,
+            };
+            if let Some(view) = &mut app_state.auxiliary_view {
+                view.scroll(notches);
+            }
+        }
+        WindowEvent::KeyboardInput { .. } | WindowEvent::ModifiersChanged(_) => {
+            app_state.winit_event(WinitEvent::Window(event));
+        }
+        _ => {}
+    }
+}
+
+/// Opens the window and runs the app until it's closed.
+pub fn run_windowed(cli: Cli) -> Result<()> {
+    let mut app = App {
+        cli,
+        window: None,
+        app_state: None,
+        mouse_locked: false,
+        recenter_cursor: false,
+        cursor_visible: true,
+        last_frame: Instant::now(),
+        frame_limiter: FrameLimiter::new(),
+        refresh_rate: None,
+        occluded: false,
+        suspended: false,
+    };
+
+    EventLoop::new().unwrap().run_app(&mut app)?;
+
+    Ok(())
+}